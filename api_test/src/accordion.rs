@@ -0,0 +1,181 @@
+//! Stacked sections that expand and collapse below their headers.
+//!
+//! [`Ui::accordion`] shows a header per [`AccordionSection`] and the content of the open ones
+//! below it. The height of a section follows the `active` factor of its header, which moves
+//! towards 1 while the section is open, so sections slide open and shut instead of snapping.
+use crate::ui::Ui;
+use clay_layout::id::Id;
+use clay_layout::layout::LayoutDirection;
+use clay_layout::math::Vector2;
+use clay_layout::{Declaration, fit, fixed, grow};
+
+/// Factors closer to fully open than this show the content at its own height.
+const OPEN_THRESHOLD: f32 = 0.99;
+/// Closed sections with a factor below this aren't built anymore.
+const CLOSED_THRESHOLD: f32 = 0.01;
+
+/// A section of a [`Ui::accordion`].
+pub struct AccordionSection<'a> {
+    title: &'a str,
+    default_open: bool,
+    content: Box<dyn FnOnce(&Ui) + 'a>,
+}
+
+impl<'a> AccordionSection<'a> {
+    /// A section titled `title` showing the content added by `content` while open. Sections
+    /// start out closed.
+    pub fn new(title: &'a str, content: impl FnOnce(&Ui) + 'a) -> Self {
+        Self {
+            title,
+            default_open: false,
+            content: Box::new(content),
+        }
+    }
+
+    /// Starts the section out open the first time the accordion is shown.
+    pub fn open(mut self) -> Self {
+        self.default_open = true;
+        self
+    }
+}
+
+/// Toggles the section at `clicked`. Opening it closes the others when `exclusive`.
+fn toggle(open: &mut [bool], clicked: usize, exclusive: bool) {
+    open[clicked] = !open[clicked];
+    if exclusive && open[clicked] {
+        for (index, open) in open.iter_mut().enumerate() {
+            *open &= index == clicked;
+        }
+    }
+}
+
+impl Ui {
+    /// Shows `sections` below each other, each one a header that expands and collapses its
+    /// content when clicked. With `exclusive` opening a section collapses the others. Returns
+    /// the index of the section toggled this frame.
+    ///
+    /// Headers are styled by the `accordion_header` rules. The animation steps while a header
+    /// is drawn, so the rule should give them a background.
+    pub fn accordion(
+        &self,
+        id: &str,
+        exclusive: bool,
+        sections: Vec<AccordionSection<'_>>,
+    ) -> Option<usize> {
+        let header_ids: Vec<_> = (0..sections.len())
+            .map(|index| self.id_index(id, index as u32 * 2 + 1))
+            .collect();
+
+        let mut open = Vec::with_capacity(sections.len());
+        let mut clicked = None;
+        for (section, header_id) in sections.iter().zip(&header_ids) {
+            let is_new = !self.has_item_state(*header_id);
+            let item = self.item_state(*header_id);
+            if is_new && section.default_open {
+                // Open from the start instead of sliding open on the first frame
+                item.open = true;
+                item.active = 1.0;
+            }
+            if item.was_clicked {
+                clicked = Some(open.len());
+            }
+            open.push(item.open);
+        }
+        if let Some(clicked) = clicked {
+            toggle(&mut open, clicked, exclusive);
+            for (header_id, open) in header_ids.iter().zip(&open) {
+                self.item_state(*header_id).open = *open;
+            }
+        }
+
+        let mut decl = Declaration::new();
+        decl.id(self.id(id))
+            .layout()
+            .width(grow!())
+            .height(fit!())
+            .child_gap(2)
+            .direction(LayoutDirection::TopToBottom)
+            .end();
+        self.with_layout(&decl, |ui| {
+            for (index, section) in sections.into_iter().enumerate() {
+                let header_id = header_ids[index];
+                let content_id = ui.id_index(id, index as u32 * 2 + 2);
+                ui.expander_header(
+                    "accordion_header",
+                    header_id,
+                    section.title,
+                    open[index],
+                    false,
+                );
+                ui.accordion_body(
+                    content_id,
+                    open[index],
+                    ui.item_factors(header_id).active,
+                    section.content,
+                );
+            }
+        });
+        clicked
+    }
+
+    /// The content of a section, clipped to its height scaled by `factor` while animating.
+    fn accordion_body(
+        &self,
+        content_id: Id,
+        open: bool,
+        factor: f32,
+        f: Box<dyn FnOnce(&Ui) + '_>,
+    ) {
+        if !open && factor < CLOSED_THRESHOLD {
+            return;
+        }
+
+        let mut outer = Declaration::new();
+        {
+            let mut layout = outer.layout();
+            layout.width(grow!());
+            if open && factor >= OPEN_THRESHOLD {
+                layout.height(fit!());
+            } else {
+                // Unknown sizes start collapsed, the content is measured this frame
+                let height = self
+                    .last_frame_rect(content_id)
+                    .map_or(0.0, |rect| rect.w - rect.y);
+                layout.height(fixed!((height * factor).round()));
+            }
+            layout.end();
+        }
+        outer.clip(false, true, Vector2::new(0.0, 0.0));
+
+        let mut content = Declaration::new();
+        content
+            .id(content_id)
+            .layout()
+            .width(grow!())
+            .height(fit!())
+            .direction(LayoutDirection::TopToBottom)
+            .end();
+
+        self.with_layout(&outer, |ui| ui.with_layout(&content, f));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toggle() {
+        let mut open = [true, false, true];
+        toggle(&mut open, 1, false);
+        assert_eq!(open, [true, true, true]);
+        toggle(&mut open, 0, false);
+        assert_eq!(open, [false, true, true]);
+
+        // Opening one closes the others, closing one leaves them
+        toggle(&mut open, 0, true);
+        assert_eq!(open, [true, false, false]);
+        toggle(&mut open, 0, true);
+        assert_eq!(open, [false, false, false]);
+    }
+}
@@ -0,0 +1,306 @@
+//! Easing curves and tweens for animating widget state.
+//!
+//! [`Easing`] maps linear progress in 0..1 to eased progress, [`Tween`] animates a value over
+//! time with a curve, and [`Ui::animate_bool`] keeps the progress per id for the common case of
+//! fading something in and out when a flag changes.
+use crate::ui::Ui;
+use clay_layout::id::Id;
+use glam::Vec4;
+
+/// Maps linear progress `t` in 0..1 to eased progress. All curves start at 0.0 and end at 1.0,
+/// springs overshoot in between.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Easing {
+    Linear,
+    /// CSS style cubic bezier with control points (x1, y1) and (x2, y2). The x coordinates
+    /// should be in 0..1.
+    CubicBezier(f32, f32, f32, f32),
+    /// Damped oscillation settling at 1.0. Higher `damping` settles faster and `frequency` is
+    /// the number of oscillations over the animation.
+    Spring {
+        damping: f32,
+        frequency: f32,
+    },
+    /// Jumps in `n` equal steps, the first one at the start.
+    Steps(u32),
+}
+
+impl Default for Easing {
+    fn default() -> Self {
+        Easing::EASE_IN_OUT
+    }
+}
+
+impl Easing {
+    pub const EASE: Easing = Easing::CubicBezier(0.25, 0.1, 0.25, 1.0);
+    pub const EASE_IN: Easing = Easing::CubicBezier(0.42, 0.0, 1.0, 1.0);
+    pub const EASE_OUT: Easing = Easing::CubicBezier(0.0, 0.0, 0.58, 1.0);
+    pub const EASE_IN_OUT: Easing = Easing::CubicBezier(0.42, 0.0, 0.58, 1.0);
+    pub const SPRING: Easing = Easing::Spring {
+        damping: 6.0,
+        frequency: 1.5,
+    };
+
+    pub fn apply(&self, t: f32) -> f32 {
+        if t <= 0.0 {
+            return 0.0;
+        }
+        if t >= 1.0 {
+            return 1.0;
+        }
+
+        match *self {
+            Easing::Linear => t,
+            Easing::CubicBezier(x1, y1, x2, y2) => {
+                let s = solve_bezier_x(x1, x2, t);
+                bezier(y1, y2, s)
+            }
+            Easing::Spring { damping, frequency } => {
+                let angle = t * frequency * std::f32::consts::TAU;
+                1.0 - (-damping * t).exp() * angle.cos()
+            }
+            Easing::Steps(n) => {
+                let n = n.max(1) as f32;
+                ((t * n).floor() + 1.0).min(n) / n
+            }
+        }
+    }
+}
+
+/// One coordinate of a cubic bezier from 0 to 1 with control values `p1` and `p2`.
+fn bezier(p1: f32, p2: f32, s: f32) -> f32 {
+    let inv = 1.0 - s;
+    3.0 * inv * inv * s * p1 + 3.0 * inv * s * s * p2 + s * s * s
+}
+
+fn bezier_slope(p1: f32, p2: f32, s: f32) -> f32 {
+    let inv = 1.0 - s;
+    3.0 * inv * inv * p1 + 6.0 * inv * s * (p2 - p1) + 3.0 * s * s * (1.0 - p2)
+}
+
+/// Finds the curve parameter where the x coordinate equals `x`.
+fn solve_bezier_x(x1: f32, x2: f32, x: f32) -> f32 {
+    // Newton converges in a few steps for well behaved curves
+    let mut s = x;
+    for _ in 0..8 {
+        let error = bezier(x1, x2, s) - x;
+        if error.abs() < 1e-5 {
+            return s;
+        }
+        let slope = bezier_slope(x1, x2, s);
+        if slope.abs() < 1e-6 {
+            break;
+        }
+        s = (s - error / slope).clamp(0.0, 1.0);
+    }
+
+    // Fall back to bisection on flat parts
+    let (mut low, mut high) = (0.0, 1.0);
+    s = x;
+    for _ in 0..32 {
+        if bezier(x1, x2, s) < x {
+            low = s;
+        } else {
+            high = s;
+        }
+        s = (low + high) * 0.5;
+    }
+    s
+}
+
+/// Fraction to move towards a target each frame for exponential smoothing, independent of
+/// the frame rate. `speed` is how many times per second the remaining distance halves.
+pub fn smoothing_factor(speed: f32, delta_time: f32) -> f32 {
+    1.0 - 2f32.powf(-speed * delta_time)
+}
+
+/// Moves the rectangle `current` by `factor` of the way towards `target`, snapping once all
+/// edges are within half a pixel so it doesn't creep forever.
+pub fn approach_rect(current: Vec4, target: Vec4, factor: f32) -> Vec4 {
+    let next = current.lerp(target, factor);
+    if (next - target).abs().max_element() < 0.5 {
+        target
+    } else {
+        next
+    }
+}
+
+/// Animates a value from `from` to `to` over `duration` seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tween {
+    from: f32,
+    to: f32,
+    duration: f32,
+    elapsed: f32,
+    easing: Easing,
+}
+
+impl Tween {
+    pub fn new(from: f32, to: f32, duration: f32, easing: Easing) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            elapsed: 0.0,
+            easing,
+        }
+    }
+
+    /// Advances the tween and returns the new value.
+    pub fn update(&mut self, delta_time: f32) -> f32 {
+        self.elapsed = (self.elapsed + delta_time).min(self.duration);
+        self.value()
+    }
+
+    /// Linear progress in 0..1.
+    pub fn progress(&self) -> f32 {
+        if self.duration > 0.0 {
+            self.elapsed / self.duration
+        } else {
+            1.0
+        }
+    }
+
+    pub fn value(&self) -> f32 {
+        self.from + (self.to - self.from) * self.easing.apply(self.progress())
+    }
+
+    pub fn target(&self) -> f32 {
+        self.to
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.progress() >= 1.0
+    }
+
+    /// Starts over from the current value towards `to`, keeping the duration and curve.
+    pub fn retarget(&mut self, to: f32) {
+        self.from = self.value();
+        self.to = to;
+        self.elapsed = 0.0;
+    }
+}
+
+/// State behind [`Ui::animate_bool`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BoolAnimation {
+    /// Linear progress towards `true`, eased when read.
+    progress: f32,
+    pub(crate) frame: u64,
+}
+
+impl BoolAnimation {
+    pub(crate) fn new(value: bool, frame: u64) -> Self {
+        Self {
+            progress: if value { 1.0 } else { 0.0 },
+            frame,
+        }
+    }
+
+    /// Steps towards `value` so a full transition takes `time` seconds, changing direction
+    /// half way through continues from where it was.
+    pub(crate) fn update(&mut self, value: bool, time: f32, delta_time: f32) -> f32 {
+        let step = if time > 0.0 { delta_time / time } else { 1.0 };
+        self.progress = if value {
+            (self.progress + step).min(1.0)
+        } else {
+            (self.progress - step).max(0.0)
+        };
+        self.progress
+    }
+}
+
+impl Ui {
+    /// Returns a factor that eases from 0.0 to 1.0 over `time` seconds after `value` turns
+    /// true and back when it turns false. The first call for an id starts at rest.
+    pub fn animate_bool(&self, id: Id, value: bool, time: f32) -> f32 {
+        self.animate_bool_with(id, value, time, Easing::default())
+    }
+
+    /// [`Ui::animate_bool`] with a custom curve.
+    pub fn animate_bool_with(&self, id: Id, value: bool, time: f32, easing: Easing) -> f32 {
+        easing.apply(self.step_bool_animation(id, value, time))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-3, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_curves() {
+        for easing in [
+            Easing::Linear,
+            Easing::EASE,
+            Easing::EASE_IN,
+            Easing::EASE_OUT,
+            Easing::EASE_IN_OUT,
+            Easing::SPRING,
+            Easing::Steps(4),
+        ] {
+            assert_eq!(easing.apply(-0.5), 0.0);
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+        }
+
+        // A bezier with control points on the diagonal is linear
+        let linear = Easing::CubicBezier(0.25, 0.25, 0.75, 0.75);
+        for t in [0.1, 0.3, 0.5, 0.9] {
+            assert_near(linear.apply(t), t);
+        }
+        assert_near(Easing::EASE_IN_OUT.apply(0.5), 0.5);
+        assert!(Easing::EASE_IN.apply(0.25) < 0.25);
+        assert!(Easing::EASE_OUT.apply(0.25) > 0.25);
+
+        assert_eq!(Easing::Steps(4).apply(0.1), 0.25);
+        assert_eq!(Easing::Steps(4).apply(0.6), 0.75);
+
+        // Springs overshoot
+        assert!((0..100).any(|i| Easing::SPRING.apply(i as f32 / 100.0) > 1.0));
+    }
+
+    #[test]
+    fn test_tween() {
+        let mut tween = Tween::new(10.0, 20.0, 0.5, Easing::Linear);
+        assert_eq!(tween.value(), 10.0);
+        assert_near(tween.update(0.25), 15.0);
+        assert!(!tween.is_finished());
+
+        tween.retarget(0.0);
+        assert_near(tween.value(), 15.0);
+        assert_near(tween.update(0.25), 7.5);
+        assert_eq!(tween.update(10.0), 0.0);
+        assert!(tween.is_finished());
+
+        assert_eq!(Tween::new(1.0, 2.0, 0.0, Easing::EASE).value(), 2.0);
+    }
+
+    #[test]
+    fn test_approach_rect() {
+        let from = Vec4::new(0.0, 0.0, 100.0, 20.0);
+        let to = Vec4::new(0.0, 40.0, 100.0, 60.0);
+
+        let halfway = approach_rect(from, to, 0.5);
+        assert_eq!(halfway, Vec4::new(0.0, 20.0, 100.0, 40.0));
+
+        let mut rect = from;
+        for _ in 0..20 {
+            rect = approach_rect(rect, to, 0.5);
+        }
+        assert_eq!(rect, to);
+    }
+
+    #[test]
+    fn test_bool_animation() {
+        let mut animation = BoolAnimation::new(false, 0);
+        assert_near(animation.update(true, 0.2, 0.1), 0.5);
+        // Reversing half way goes back from the current position
+        assert_near(animation.update(false, 0.2, 0.05), 0.25);
+        assert_eq!(animation.update(false, 0.2, 1.0), 0.0);
+        assert_eq!(animation.update(true, 0.0, 0.0), 1.0);
+    }
+}
@@ -0,0 +1,166 @@
+//! Typed area properties shared by the data driven ui formats (scripts and ui descriptions).
+use crate::rgba;
+use clay_layout::Declaration;
+use clay_layout::color::Color as ClayColor;
+use clay_layout::layout::{
+    Alignment, LayoutAlignmentX, LayoutAlignmentY, LayoutDirection, Padding, Sizing,
+};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum AreaProp {
+    Width(Sizing),
+    Height(Sizing),
+    Direction(LayoutDirection),
+    Padding(u16),
+    Gap(u16),
+    AlignX(LayoutAlignmentX),
+    AlignY(LayoutAlignmentY),
+    Background(ClayColor),
+    Radius(f32),
+    Border(u16),
+    BorderColor(ClayColor),
+}
+
+/// Property value as written in the source format, before it's checked against the property.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PropValue<'v> {
+    Ident(&'v str),
+    Number(f32),
+    /// Fraction in `0..=1`, written as `50%`
+    Percent(f32),
+    Color(ClayColor),
+}
+
+impl fmt::Display for PropValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PropValue::Ident(v) => write!(f, "'{}'", v),
+            PropValue::Number(v) => write!(f, "{}", v),
+            PropValue::Percent(v) => write!(f, "{}%", v * 100.0),
+            PropValue::Color(_) => f.write_str("color"),
+        }
+    }
+}
+
+/// Parses `rrggbb` or `rrggbbaa` (without the leading `#`).
+pub(crate) fn parse_hex_color(hex: &str) -> Option<ClayColor> {
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    let alpha = if hex.len() == 8 { channel(6)? } else { 255 };
+
+    Some(rgba(channel(0)?, channel(2)?, channel(4)?, alpha))
+}
+
+fn sizing_value(value: PropValue) -> Option<Sizing> {
+    match value {
+        PropValue::Ident("grow") => Some(Sizing::Grow(0.0, f32::MAX)),
+        PropValue::Ident("fit") => Some(Sizing::Fit(0.0, f32::MAX)),
+        PropValue::Number(v) if v >= 0.0 => Some(Sizing::Fixed(v)),
+        PropValue::Percent(v) if (0.0..=1.0).contains(&v) => Some(Sizing::Percent(v)),
+        _ => None,
+    }
+}
+
+fn u16_value(value: PropValue) -> Option<u16> {
+    match value {
+        PropValue::Number(v) if v >= 0.0 && v <= u16::MAX as f32 && v.fract() == 0.0 => {
+            Some(v as u16)
+        }
+        _ => None,
+    }
+}
+
+pub(crate) fn color_value(value: PropValue) -> Option<ClayColor> {
+    match value {
+        PropValue::Color(c) => Some(c),
+        _ => None,
+    }
+}
+
+/// Converts a `name: value` pair into a typed area property.
+pub(crate) fn area_prop(name: &str, value: PropValue) -> Result<AreaProp, String> {
+    let ident = match value {
+        PropValue::Ident(v) => Some(v),
+        _ => None,
+    };
+
+    let prop = match name {
+        "width" => sizing_value(value).map(AreaProp::Width),
+        "height" => sizing_value(value).map(AreaProp::Height),
+        "direction" => match ident {
+            Some("left_to_right") => Some(AreaProp::Direction(LayoutDirection::LeftToRight)),
+            Some("top_to_bottom") => Some(AreaProp::Direction(LayoutDirection::TopToBottom)),
+            _ => None,
+        },
+        "padding" => u16_value(value).map(AreaProp::Padding),
+        "gap" => u16_value(value).map(AreaProp::Gap),
+        "align_x" => match ident {
+            Some("left") => Some(AreaProp::AlignX(LayoutAlignmentX::Left)),
+            Some("center") => Some(AreaProp::AlignX(LayoutAlignmentX::Center)),
+            Some("right") => Some(AreaProp::AlignX(LayoutAlignmentX::Right)),
+            _ => None,
+        },
+        "align_y" => match ident {
+            Some("top") => Some(AreaProp::AlignY(LayoutAlignmentY::Top)),
+            Some("center") => Some(AreaProp::AlignY(LayoutAlignmentY::Center)),
+            Some("bottom") => Some(AreaProp::AlignY(LayoutAlignmentY::Bottom)),
+            _ => None,
+        },
+        "background" => color_value(value).map(AreaProp::Background),
+        "radius" => match value {
+            PropValue::Number(v) if v >= 0.0 => Some(AreaProp::Radius(v)),
+            _ => None,
+        },
+        "border" => u16_value(value).map(AreaProp::Border),
+        "border_color" => color_value(value).map(AreaProp::BorderColor),
+        _ => return Err(format!("unknown area property '{}'", name)),
+    };
+
+    prop.ok_or_else(|| format!("invalid value {} for '{}'", value, name))
+}
+
+pub(crate) fn apply_area_props<I, C>(decl: &mut Declaration<'_, I, C>, props: &[AreaProp]) {
+    let mut align_x = LayoutAlignmentX::Left;
+    let mut align_y = LayoutAlignmentY::Top;
+
+    for prop in props {
+        match *prop {
+            AreaProp::Width(size) => {
+                decl.layout().width(size);
+            }
+            AreaProp::Height(size) => {
+                decl.layout().height(size);
+            }
+            AreaProp::Direction(direction) => {
+                decl.layout().direction(direction);
+            }
+            AreaProp::Padding(padding) => {
+                decl.layout().padding(Padding::all(padding));
+            }
+            AreaProp::Gap(gap) => {
+                decl.layout().child_gap(gap);
+            }
+            AreaProp::AlignX(x) => align_x = x,
+            AreaProp::AlignY(y) => align_y = y,
+            AreaProp::Background(color) => {
+                decl.background_color(color);
+            }
+            AreaProp::Radius(radius) => {
+                decl.corner_radius().all(radius);
+            }
+            AreaProp::Border(width) => {
+                decl.border().all_directions(width);
+            }
+            AreaProp::BorderColor(color) => {
+                decl.border().color(color);
+            }
+        }
+    }
+
+    decl.layout()
+        .child_alignment(Alignment::new(align_x, align_y));
+}
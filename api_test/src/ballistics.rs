@@ -0,0 +1,283 @@
+//! Turning raw audio levels into what meters and level plots show.
+//!
+//! Audio comes in blocks at its own rate while the ui draws at whatever rate it gets.
+//! [`MeterBallistics`] smooths levels the way a meter needle moves and holds peaks long enough
+//! to read them. [`HistoryBuffer`] keeps levels at a fixed rate for plots. Both are stepped with
+//! the seconds since the last frame, so they behave the same at any frame rate.
+use crate::animation::smoothing_factor;
+use std::collections::VecDeque;
+
+/// Root mean square of `samples`, the level a block of audio is heard at.
+pub fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let sum: f32 = samples.iter().map(|sample| sample * sample).sum();
+    (sum / samples.len() as f32).sqrt()
+}
+
+/// Largest absolute value of `samples`.
+pub fn peak(samples: &[f32]) -> f32 {
+    samples
+        .iter()
+        .fold(0.0, |peak, sample| f32::max(peak, sample.abs()))
+}
+
+/// A meter level that rises fast and falls slowly, with a peak held above it for a while.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeterBallistics {
+    /// How many times per second the distance to a higher level halves.
+    pub attack: f32,
+    /// How many times per second the distance to a lower level halves.
+    pub release: f32,
+    /// Seconds a peak is held before it starts falling.
+    pub hold: f32,
+    /// How fast a held peak falls afterwards, in level per second.
+    pub fall: f32,
+    level: f32,
+    peak: f32,
+    peak_age: f32,
+}
+
+impl Default for MeterBallistics {
+    fn default() -> Self {
+        Self {
+            attack: 60.0,
+            release: 6.0,
+            hold: 1.5,
+            fall: 0.5,
+            level: 0.0,
+            peak: 0.0,
+            peak_age: 0.0,
+        }
+    }
+}
+
+impl MeterBallistics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn attack(mut self, attack: f32) -> Self {
+        self.attack = attack;
+        self
+    }
+
+    pub fn release(mut self, release: f32) -> Self {
+        self.release = release;
+        self
+    }
+
+    pub fn hold(mut self, hold: f32) -> Self {
+        self.hold = hold;
+        self
+    }
+
+    pub fn fall(mut self, fall: f32) -> Self {
+        self.fall = fall;
+        self
+    }
+
+    /// Moves towards `level` over `delta_time` seconds.
+    pub fn update(&mut self, level: f32, delta_time: f32) {
+        self.update_levels(level, level, delta_time);
+    }
+
+    /// Moves towards the level of a block of `samples` that arrived over `delta_time`
+    /// seconds: the meter follows their RMS and the held peak their largest sample.
+    pub fn update_samples(&mut self, samples: &[f32], delta_time: f32) {
+        self.update_levels(rms(samples), peak(samples), delta_time);
+    }
+
+    /// Moves the meter towards `level` and the held peak towards `peak` over `delta_time`
+    /// seconds, for hosts that measure both themselves.
+    pub fn update_levels(&mut self, level: f32, peak: f32, delta_time: f32) {
+        let speed = if level > self.level {
+            self.attack
+        } else {
+            self.release
+        };
+        self.level += (level - self.level) * smoothing_factor(speed, delta_time);
+
+        if peak >= self.peak {
+            self.peak = peak;
+            self.peak_age = 0.0;
+        } else {
+            self.peak_age += delta_time;
+            if self.peak_age > self.hold {
+                self.peak -= self.fall * delta_time;
+            }
+        }
+        self.peak = self.peak.max(self.level);
+    }
+
+    /// The smoothed level.
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+
+    /// The held peak, never below [`MeterBallistics::level`].
+    pub fn peak(&self) -> f32 {
+        self.peak
+    }
+
+    /// Drops the level and the peak to silence, for example when playback stops.
+    pub fn reset(&mut self) {
+        self.level = 0.0;
+        self.peak = 0.0;
+        self.peak_age = 0.0;
+    }
+}
+
+/// The last `capacity` levels taken at a fixed rate, oldest first, for plotting a level over
+/// time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryBuffer {
+    values: VecDeque<f32>,
+    capacity: usize,
+    /// Seconds between two values.
+    interval: f32,
+    /// Seconds since the last value was taken.
+    elapsed: f32,
+    /// Loudest level seen since the last value was taken.
+    pending: f32,
+}
+
+impl HistoryBuffer {
+    /// A buffer keeping `capacity` values, one every `interval` seconds.
+    pub fn new(capacity: usize, interval: f32) -> Self {
+        Self {
+            values: VecDeque::with_capacity(capacity),
+            capacity,
+            interval,
+            elapsed: 0.0,
+            pending: 0.0,
+        }
+    }
+
+    /// Adds `value`, dropping the oldest one if the buffer is full.
+    pub fn push(&mut self, value: f32) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.values.len() == self.capacity {
+            self.values.pop_front();
+        }
+        self.values.push_back(value);
+    }
+
+    /// Takes `level` as the level over the last `delta_time` seconds and adds a value for every
+    /// interval that passed. Each value is the loudest level of its interval, so short peaks
+    /// aren't lost between frames.
+    pub fn update(&mut self, level: f32, delta_time: f32) {
+        self.pending = self.pending.max(level);
+        self.elapsed += delta_time;
+        if self.interval <= 0.0 {
+            self.push(self.pending);
+            self.pending = 0.0;
+            self.elapsed = 0.0;
+            return;
+        }
+        while self.elapsed >= self.interval {
+            self.elapsed -= self.interval;
+            self.push(self.pending);
+        }
+        if self.elapsed < delta_time {
+            // A value was taken, the rest of this update starts the next interval
+            self.pending = level;
+        }
+    }
+
+    /// The values from oldest to newest.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = f32> + '_ {
+        self.values.iter().copied()
+    }
+
+    /// The newest value.
+    pub fn latest(&self) -> Option<f32> {
+        self.values.back().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.elapsed = 0.0;
+        self.pending = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-3, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_levels() {
+        assert_near(rms(&[0.5, -0.5, 0.5, -0.5]), 0.5);
+        assert_eq!(rms(&[]), 0.0);
+        assert_eq!(peak(&[0.2, -0.9, 0.5]), 0.9);
+    }
+
+    #[test]
+    fn test_meter_ballistics() {
+        // The same second at 60 and at 20 frames per second ends up in the same place
+        let mut fast = MeterBallistics::new();
+        let mut slow = MeterBallistics::new();
+        for _ in 0..60 {
+            fast.update(0.8, 1.0 / 60.0);
+        }
+        for _ in 0..20 {
+            slow.update(0.8, 1.0 / 20.0);
+        }
+        assert_near(fast.level(), slow.level());
+        assert_near(fast.level(), 0.8);
+        assert_eq!(fast.peak(), 0.8);
+
+        // Falls slower than it rose, the peak stays until the hold time is over
+        fast.update(0.0, 0.1);
+        assert!(fast.level() > 0.4);
+        assert_eq!(fast.peak(), 0.8);
+        for _ in 0..15 {
+            fast.update(0.0, 0.1);
+        }
+        assert!(fast.peak() < 0.8);
+        assert!(fast.peak() >= fast.level());
+
+        fast.reset();
+        assert_eq!((fast.level(), fast.peak()), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_history_buffer() {
+        let mut history = HistoryBuffer::new(3, 0.1);
+        // A short peak within an interval is kept
+        history.update(0.2, 0.04);
+        history.update(0.9, 0.04);
+        history.update(0.1, 0.04);
+        assert_eq!(history.iter().collect::<Vec<_>>(), [0.9]);
+
+        // A long frame takes several values
+        history.update(0.5, 0.25);
+        assert_eq!(history.iter().collect::<Vec<_>>(), [0.9, 0.5, 0.5]);
+        history.push(0.3);
+        assert_eq!(history.iter().collect::<Vec<_>>(), [0.5, 0.5, 0.3]);
+        assert_eq!(history.latest(), Some(0.3));
+
+        history.clear();
+        assert!(history.is_empty());
+    }
+}
@@ -0,0 +1,105 @@
+//! Spreading expensive work over several frames.
+//!
+//! With a budget set by [`Ui::set_frame_budget`], work that can wait stops once the frame has
+//! used its time and continues in the next one. That covers collecting generated glyphs and app
+//! work run through [`Ui::run_budgeted`], such as downsampling waveforms. [`Ui::pending_work`]
+//! tells the host if anything is left so it keeps requesting frames while the ui catches up.
+use crate::ui::Ui;
+use std::time::{Duration, Instant};
+
+/// Work left over after a frame, see [`Ui::pending_work`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PendingWork {
+    /// Strings waiting to be rasterized or being rasterized.
+    pub text_generations: usize,
+    /// Images waiting to be decoded or being decoded.
+    pub image_loads: usize,
+    /// Directories of file trees waiting to be listed or being listed.
+    pub directory_listings: usize,
+    /// Calls to [`Ui::run_budgeted`] this frame that ran out of time.
+    pub deferred_tasks: usize,
+}
+
+impl PendingWork {
+    pub fn is_empty(&self) -> bool {
+        *self == PendingWork::default()
+    }
+}
+
+/// Point in time where deferrable work of the current frame should stop.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FrameDeadline(Option<Instant>);
+
+impl FrameDeadline {
+    /// Deadline `budget` after `start`, or none at all without a budget.
+    pub(crate) fn new(start: Instant, budget: Option<Duration>) -> Self {
+        Self(budget.map(|budget| start + budget))
+    }
+
+    pub(crate) fn unlimited() -> Self {
+        Self(None)
+    }
+
+    pub(crate) fn is_reached(&self) -> bool {
+        self.0.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Time left before the deadline, `None` without a budget.
+    pub(crate) fn remaining(&self) -> Option<Duration> {
+        self.0
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+}
+
+impl Ui {
+    /// Time left of the frame budget, `None` when no budget is set.
+    pub fn budget_remaining(&self) -> Option<Duration> {
+        self.frame_deadline().remaining()
+    }
+
+    /// Calls `step` until it returns true to signal that the work is done, or the frame budget
+    /// is used up. At least one step runs every frame so the work always progresses. Returns
+    /// true if the work finished, otherwise it should be resumed next frame.
+    pub fn run_budgeted(&self, mut step: impl FnMut() -> bool) -> bool {
+        let deadline = self.frame_deadline();
+        loop {
+            if step() {
+                return true;
+            }
+            if deadline.is_reached() {
+                self.count_deferred_task();
+                return false;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deadline() {
+        let start = Instant::now();
+        assert!(!FrameDeadline::unlimited().is_reached());
+        assert_eq!(FrameDeadline::unlimited().remaining(), None);
+
+        let spent = FrameDeadline::new(start, Some(Duration::ZERO));
+        assert!(spent.is_reached());
+        assert_eq!(spent.remaining(), Some(Duration::ZERO));
+
+        let later = FrameDeadline::new(start, Some(Duration::from_secs(60)));
+        assert!(!later.is_reached());
+        assert!(later.remaining().unwrap() > Duration::from_secs(59));
+    }
+
+    #[test]
+    fn test_pending_work() {
+        assert!(PendingWork::default().is_empty());
+        let pending = PendingWork {
+            deferred_tasks: 1,
+            ..Default::default()
+        };
+        assert!(!pending.is_empty());
+    }
+}
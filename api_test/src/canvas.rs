@@ -0,0 +1,264 @@
+//! Drawing surfaces that can be panned and zoomed, for timelines and node graphs.
+//!
+//! [`Ui::canvas`] fills the space it's given and shows a world of its own through a
+//! [`Viewport`]. Dragging with the middle button, or with the left button on empty space,
+//! pans the view and the mouse wheel zooms around the mouse. What's drawn with the
+//! [`Painter`] is given in world coordinates and skipped when it's out of view.
+use crate::input::MouseButton;
+use crate::style::WidgetState;
+use crate::ui::{Ui, apply_style};
+use clay_layout::color::Color as ClayColor;
+use clay_layout::elements::{FloatingAttachToElement, FloatingClipToElement};
+use clay_layout::id::Id;
+use clay_layout::math::Vector2;
+use clay_layout::{Declaration, fixed, grow};
+use glam::{Vec2, Vec4, Vec4Swizzles};
+
+/// Zoom can't go further out than this.
+const MIN_ZOOM: f32 = 0.1;
+/// Zoom can't go further in than this.
+const MAX_ZOOM: f32 = 10.0;
+/// Zoom factor of one step of the mouse wheel.
+const WHEEL_ZOOM: f32 = 1.1;
+
+/// Pan and zoom of a canvas, kept by the ui between frames.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CanvasView {
+    offset: Vec2,
+    zoom: f32,
+    /// Set while a drag that pans the view goes on.
+    panning: bool,
+}
+
+impl Default for CanvasView {
+    fn default() -> Self {
+        Self {
+            offset: Vec2::ZERO,
+            zoom: 1.0,
+            panning: false,
+        }
+    }
+}
+
+/// The part of its world a canvas shows this frame.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    /// World position shown at the top left corner of the canvas.
+    pub offset: Vec2,
+    /// Screen pixels per world unit.
+    pub zoom: f32,
+    /// Where the canvas is on screen as (x0, y0, x1, y1), as laid out in the previous frame.
+    /// Empty until the canvas has been laid out once.
+    pub rect: Vec4,
+    /// World position of the mouse while it's over the canvas.
+    pub mouse: Option<Vec2>,
+}
+
+impl Viewport {
+    /// Screen position of the world position `world`.
+    pub fn to_screen(self, world: Vec2) -> Vec2 {
+        self.rect.xy() + (world - self.offset) * self.zoom
+    }
+
+    /// World position of the screen position `screen`, like the mouse position.
+    pub fn to_world(self, screen: Vec2) -> Vec2 {
+        self.offset + (screen - self.rect.xy()) / self.zoom
+    }
+
+    /// The world rect shown, as (x0, y0, x1, y1).
+    pub fn visible(&self) -> Vec4 {
+        let size = (self.rect.zw() - self.rect.xy()) / self.zoom;
+        Vec4::new(
+            self.offset.x,
+            self.offset.y,
+            self.offset.x + size.x,
+            self.offset.y + size.y,
+        )
+    }
+
+    /// Whether any of the world rect `rect`, as (x0, y0, x1, y1), is shown.
+    pub fn is_visible(&self, rect: Vec4) -> bool {
+        let visible = self.visible();
+        rect.x < visible.z && rect.z > visible.x && rect.y < visible.w && rect.w > visible.y
+    }
+}
+
+/// Offset that keeps the world position under `anchor`, relative to the top left corner of
+/// the canvas, in place when the zoom changes from `zoom` to `new_zoom`.
+fn zoom_offset(offset: Vec2, zoom: f32, new_zoom: f32, anchor: Vec2) -> Vec2 {
+    offset + anchor / zoom - anchor / new_zoom
+}
+
+/// Draws into a canvas in world coordinates, see [`Ui::canvas`].
+pub struct Painter<'a> {
+    ui: &'a Ui,
+    viewport: Viewport,
+}
+
+impl Painter<'_> {
+    /// The ui the canvas is declared with.
+    pub fn ui(&self) -> &Ui {
+        self.ui
+    }
+
+    /// Fills the world rect `rect`, as (x0, y0, x1, y1), with `color`.
+    pub fn rect(&self, rect: Vec4, color: ClayColor) {
+        self.place(None, rect, Some(color), |_| {});
+    }
+
+    /// Draws `text` with its top left corner at the world position `pos`. Text keeps the font
+    /// size of the ui at any zoom.
+    pub fn text(&self, pos: Vec2, text: &str, color: ClayColor) {
+        let size = self.ui.text_size(text, self.ui.font_size());
+        let extent = Vec2::new(size.width, size.height) / self.viewport.zoom;
+        let rect = Vec4::new(pos.x, pos.y, pos.x + extent.x, pos.y + extent.y);
+        self.place(None, rect, None, |ui| ui.text_element(text, color));
+    }
+
+    /// Declares an element with `id` covering the world rect `rect`, with the children added
+    /// by `f`, so it can be hovered and clicked like any other. Returns false when it's out of
+    /// view and wasn't declared.
+    pub fn element(
+        &self,
+        id: Id,
+        rect: Vec4,
+        color: Option<ClayColor>,
+        f: impl FnOnce(&Ui),
+    ) -> bool {
+        self.place(Some(id), rect, color, f)
+    }
+
+    fn place(
+        &self,
+        id: Option<Id>,
+        rect: Vec4,
+        color: Option<ClayColor>,
+        f: impl FnOnce(&Ui),
+    ) -> bool {
+        let viewport = &self.viewport;
+        if !viewport.is_visible(rect) {
+            return false;
+        }
+        let pos = viewport.to_screen(rect.xy()) - viewport.rect.xy();
+        let size = (rect.zw() - rect.xy()) * viewport.zoom;
+
+        let mut decl = Declaration::new();
+        if let Some(id) = id {
+            decl.id(id);
+        }
+        decl.layout()
+            .width(fixed!(size.x))
+            .height(fixed!(size.y))
+            .end()
+            .floating()
+            .attach_to(FloatingAttachToElement::Parent)
+            .clip_to(FloatingClipToElement::AttachedParent)
+            .offset(Vector2::new(pos.x, pos.y))
+            .end();
+        if let Some(color) = color {
+            decl.background_color(color);
+        }
+        self.ui.with_layout(&decl, f);
+        true
+    }
+}
+
+impl Ui {
+    /// Declares a canvas filling the space it's given and draws into it with `f`. Returns the
+    /// viewport it was drawn with.
+    ///
+    /// The view is panned by dragging with the middle button, or with the left button on
+    /// space not taken by an element declared with [`Painter::element`], and zoomed around
+    /// the mouse with the wheel. The canvas is styled by the `canvas` rule.
+    pub fn canvas(&self, id: &str, f: impl FnOnce(&Painter, &Viewport)) -> Viewport {
+        let canvas_id = self.id(id);
+        let input = self.input();
+        let rect = self.last_frame_rect(canvas_id).unwrap_or(Vec4::ZERO);
+        let hovered = input.is_hovered(rect);
+
+        let views = self.canvas_views();
+        let view = views.entry(canvas_id.id.id).or_default();
+        let pan_pressed = (input.button_pressed(MouseButton::Left)
+            && input.hit_test(input.mouse_pos) == Some(canvas_id.id.id))
+            || (input.button_pressed(MouseButton::Middle) && hovered);
+        if pan_pressed {
+            view.panning = true;
+        }
+        if !input.button_down(MouseButton::Left) && !input.button_down(MouseButton::Middle) {
+            view.panning = false;
+        }
+        if view.panning {
+            view.offset -= input.mouse_delta / view.zoom;
+        }
+
+        let wheel = self.take_scroll(rect).y;
+        if wheel != 0.0 {
+            let zoom = (view.zoom * WHEEL_ZOOM.powf(wheel)).clamp(MIN_ZOOM, MAX_ZOOM);
+            let anchor = input.mouse_pos - rect.xy();
+            view.offset = zoom_offset(view.offset, view.zoom, zoom, anchor);
+            view.zoom = zoom;
+        }
+
+        let mut viewport = Viewport {
+            offset: view.offset,
+            zoom: view.zoom,
+            rect,
+            mouse: None,
+        };
+        viewport.mouse = hovered.then(|| viewport.to_world(input.mouse_pos));
+
+        let style = self.widget_style(
+            "canvas",
+            WidgetState {
+                active: view.panning,
+                ..Default::default()
+            },
+        );
+        let mut decl = Declaration::new();
+        decl.id(canvas_id)
+            .layout()
+            .width(grow!())
+            .height(grow!())
+            .end()
+            .clip(true, true, Vector2::new(0.0, 0.0));
+        apply_style(&mut decl, &style);
+
+        let painter = Painter { ui: self, viewport };
+        self.with_layout(&decl, |_| f(&painter, &viewport));
+        viewport
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_viewport() {
+        let viewport = Viewport {
+            offset: Vec2::new(100.0, 50.0),
+            zoom: 2.0,
+            rect: Vec4::new(10.0, 20.0, 210.0, 120.0),
+            mouse: None,
+        };
+        let world = Vec2::new(130.0, 60.0);
+        assert_eq!(viewport.to_screen(world), Vec2::new(70.0, 40.0));
+        assert_eq!(viewport.to_world(viewport.to_screen(world)), world);
+        assert_eq!(viewport.visible(), Vec4::new(100.0, 50.0, 200.0, 100.0));
+        assert!(viewport.is_visible(Vec4::new(190.0, 90.0, 250.0, 150.0)));
+        // Touching the edge isn't in view
+        assert!(!viewport.is_visible(Vec4::new(200.0, 60.0, 250.0, 70.0)));
+        assert!(!viewport.is_visible(Vec4::new(0.0, 0.0, 50.0, 40.0)));
+    }
+
+    #[test]
+    fn test_zoom_offset() {
+        let anchor = Vec2::new(40.0, 30.0);
+        let offset = zoom_offset(Vec2::new(10.0, 10.0), 1.0, 2.0, anchor);
+        // The world position under the anchor stays the same
+        assert_eq!(Vec2::new(10.0, 10.0) + anchor / 1.0, offset + anchor / 2.0);
+        assert_eq!(offset, Vec2::new(30.0, 25.0));
+        // Zooming around the top left corner keeps the offset
+        assert_eq!(zoom_offset(offset, 2.0, 0.5, Vec2::ZERO), offset);
+    }
+}
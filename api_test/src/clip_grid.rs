@@ -0,0 +1,461 @@
+//! Clip launch grids, the session view of a live set.
+//!
+//! [`Ui::clip_grid`] shows a column of clip slots for every track and a row for every scene,
+//! with a button at the end of each row that launches the whole scene. Clips are launched and
+//! stopped by clicking them and moved or copied by dragging them to another slot. The grid
+//! doesn't change any clip itself, it returns what was asked for as [`ClipEvent`]s and shows
+//! the states the caller passes in the next frame.
+//!
+//! A playing clip draws a ring filling up over its loop and fades its slot in as it starts. A
+//! queued clip pulses until the caller starts it, usually on the next bar.
+use crate::image::ImageInfo;
+use crate::input::MouseButton;
+use crate::style::WidgetState;
+use crate::tiny_skia_renderer::clay_to_tiny_skia_color;
+use crate::ui::{Ui, apply_style, rgb};
+use clay_layout::color::Color as ClayColor;
+use clay_layout::elements::FloatingAttachToElement;
+use clay_layout::id::Id;
+use clay_layout::layout::{Alignment, LayoutAlignmentX, LayoutAlignmentY, LayoutDirection};
+use clay_layout::math::Vector2;
+use clay_layout::{Declaration, fit, fixed};
+use glam::Vec4Swizzles;
+use std::f32::consts::{FRAC_PI_2, TAU};
+use tiny_skia::{LineCap, Paint, PathBuilder, Pixmap, Stroke, Transform};
+
+/// Height of a clip slot in font sizes.
+const SLOT_HEIGHT: f32 = 1.3;
+/// Width of a clip slot in slot heights.
+const SLOT_WIDTH: f32 = 4.0;
+/// Width of the scene buttons at the end of the rows in slot heights.
+const SCENE_WIDTH: f32 = 2.5;
+/// Space between two slots.
+const GAP: u16 = 2;
+/// Size of the progress rings in slot heights.
+const RING_SIZE: f32 = 0.5;
+/// Seconds a slot takes to fade in when its clip starts playing.
+const FADE_TIME: f32 = 0.15;
+/// Pulses per second of queued clips.
+const PULSE_RATE: f64 = 2.0;
+
+/// What a clip slot holds and does.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ClipState {
+    #[default]
+    Empty,
+    /// Holds a clip that's stopped.
+    Loaded,
+    Playing,
+    /// Holds a clip that was launched and starts at the next launch point.
+    Queued,
+}
+
+/// A clip slot of a [`ClipTrack`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClipSlot<'a> {
+    pub name: &'a str,
+    pub state: ClipState,
+    /// How far a playing clip is through its loop, from 0.0 to 1.0.
+    pub progress: f32,
+}
+
+impl<'a> ClipSlot<'a> {
+    pub const EMPTY: ClipSlot<'static> = ClipSlot {
+        name: "",
+        state: ClipState::Empty,
+        progress: 0.0,
+    };
+
+    pub fn new(name: &'a str, state: ClipState) -> Self {
+        Self {
+            name,
+            state,
+            progress: 0.0,
+        }
+    }
+
+    pub fn progress(mut self, progress: f32) -> Self {
+        self.progress = progress;
+        self
+    }
+}
+
+/// A column of a clip grid. Scenes past the end of `slots` are empty.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClipTrack<'a> {
+    pub name: &'a str,
+    pub slots: &'a [ClipSlot<'a>],
+}
+
+/// Something asked of a [`Ui::clip_grid`]. Slots are given as (track, scene).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipEvent {
+    Launch {
+        track: usize,
+        scene: usize,
+    },
+    /// Stops whatever plays on `track`.
+    Stop {
+        track: usize,
+    },
+    /// Launches the clips of the row of `scene` and stops the tracks without one.
+    LaunchScene {
+        scene: usize,
+    },
+    Move {
+        from: (usize, usize),
+        to: (usize, usize),
+    },
+    Copy {
+        from: (usize, usize),
+        to: (usize, usize),
+    },
+}
+
+/// The slot a clip is being dragged from, kept between frames.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct ClipGridState {
+    dragging: Option<(usize, usize)>,
+}
+
+/// What clicking the slot of `track` and `scene` holding a clip in `state` does. Clicking a
+/// playing clip or an empty slot stops the track, clicking any other clip launches it.
+fn click_event(state: ClipState, track: usize, scene: usize) -> ClipEvent {
+    match state {
+        ClipState::Empty | ClipState::Playing => ClipEvent::Stop { track },
+        ClipState::Loaded | ClipState::Queued => ClipEvent::Launch { track, scene },
+    }
+}
+
+/// How far a queued clip is into its pulse at `time` seconds, going from 0.0 to 1.0 and back.
+fn pulse(time: f64) -> f32 {
+    (0.5 - 0.5 * (time * PULSE_RATE * TAU as f64).cos()) as f32
+}
+
+/// A ring `size` pixels across, filled clockwise from the top up to `progress`.
+fn ring_pixmap(size: u32, progress: f32, track: ClayColor, fill: ClayColor) -> Option<Pixmap> {
+    let mut pixmap = Pixmap::new(size, size)?;
+    let center = size as f32 * 0.5;
+    let width = (size as f32 * 0.18).max(2.0);
+    let radius = center - width * 0.5;
+    let stroke = Stroke {
+        width,
+        line_cap: LineCap::Butt,
+        ..Default::default()
+    };
+    let mut paint = Paint {
+        anti_alias: true,
+        ..Default::default()
+    };
+    let mut arc = |to: f32, color: ClayColor| {
+        let steps = (to * 48.0).ceil().max(1.0) as usize;
+        let mut path = PathBuilder::new();
+        for step in 0..=steps {
+            let angle = -FRAC_PI_2 + to * TAU * step as f32 / steps as f32;
+            let (x, y) = (center + radius * angle.cos(), center + radius * angle.sin());
+            if step == 0 {
+                path.move_to(x, y);
+            } else {
+                path.line_to(x, y);
+            }
+        }
+        paint.set_color(clay_to_tiny_skia_color(color));
+        if let Some(path) = path.finish() {
+            pixmap.stroke_path(&path, &paint, &stroke, Transform::identity(), None);
+        }
+    };
+    arc(1.0, track);
+    let progress = progress.clamp(0.0, 1.0);
+    if progress > 0.0 {
+        arc(progress, fill);
+    }
+    Some(pixmap)
+}
+
+impl Ui {
+    /// A grid of clip slots with a column for each of `tracks` and a row for each of `scenes`,
+    /// named by the buttons that launch them. Returns what was clicked and dragged this frame.
+    ///
+    /// Clicking a clip launches it, or stops its track when it's playing, and clicking an
+    /// empty slot stops its track. Dragging a clip to another slot moves it there, or copies
+    /// it with Ctrl or Cmd held.
+    ///
+    /// Slots are styled by the `clip_slot` rule with the `loaded`, `playing` and `queued`
+    /// classes for the states of their clips, and `:active` while a clip is dragged over them.
+    /// The rings are styled by `clip_progress`, the scene buttons by `clip_scene` and the
+    /// track names by `clip_grid`.
+    pub fn clip_grid(&self, id: &str, tracks: &[ClipTrack], scenes: &[&str]) -> Vec<ClipEvent> {
+        let grid_id = self.id(id);
+        let input = self.input();
+        let slot = |track: usize, scene: usize| {
+            tracks[track]
+                .slots
+                .get(scene)
+                .copied()
+                .unwrap_or(ClipSlot::EMPTY)
+        };
+        let slot_id = |track: usize, scene: usize| {
+            self.id_index(id, (scene * tracks.len() + track) as u32 + 1)
+        };
+        let scene_id =
+            |scene: usize| self.id_index(id, (scenes.len() * tracks.len() + scene) as u32 + 1);
+        let slot_at = |element: Option<u32>| {
+            (0..scenes.len())
+                .flat_map(|scene| (0..tracks.len()).map(move |track| (track, scene)))
+                .find(|(track, scene)| Some(slot_id(*track, *scene).id.id) == element)
+        };
+
+        let mut events = Vec::new();
+        for scene in 0..scenes.len() {
+            if input.was_clicked(scene_id(scene).id.id, MouseButton::Left) {
+                events.push(ClipEvent::LaunchScene { scene });
+            }
+            for track in 0..tracks.len() {
+                if input.was_clicked(slot_id(track, scene).id.id, MouseButton::Left) {
+                    events.push(click_event(slot(track, scene).state, track, scene));
+                }
+            }
+        }
+
+        let hit = input.hit_test(input.mouse_pos);
+        let state = self.clip_grids().entry(grid_id.id.id).or_default();
+        if input.button_pressed(MouseButton::Left)
+            && let Some((track, scene)) = slot_at(input.pressed_id)
+            && slot(track, scene).state != ClipState::Empty
+        {
+            state.dragging = Some((track, scene));
+        }
+        // Only dropping on another slot counts, releasing where it started is a click
+        let target = state
+            .dragging
+            .and(slot_at(hit))
+            .filter(|to| state.dragging != Some(*to));
+        if !input.button_down(MouseButton::Left)
+            && let Some(from) = state.dragging.take()
+            && let Some(to) = target
+        {
+            events.push(if input.modifiers().command() {
+                ClipEvent::Copy { from, to }
+            } else {
+                ClipEvent::Move { from, to }
+            });
+        }
+        let dragging = state.dragging.filter(|_| target.is_some());
+
+        let style = self.widget_style("clip_grid", WidgetState::default());
+        let progress_style = self.widget_style("clip_progress", WidgetState::default());
+        let text_color = style.text_color.unwrap_or(rgb(180, 180, 180));
+        let fill = progress_style.background.unwrap_or(rgb(220, 220, 220));
+        let ring_track = progress_style.border_color.unwrap_or(rgb(70, 70, 70));
+        let pulse = pulse(self.time());
+        let slot_height = (self.font_size() as f32 * SLOT_HEIGHT).round();
+        let slot_width = (slot_height * SLOT_WIDTH).round();
+        let ring_size = (slot_height * RING_SIZE).round() as u32;
+
+        let mut decl = Declaration::new();
+        decl.id(grid_id)
+            .layout()
+            .width(fit!())
+            .height(fit!())
+            .child_gap(GAP)
+            .direction(LayoutDirection::TopToBottom)
+            .end();
+        apply_style(&mut decl, &style);
+        let mut row = Declaration::new();
+        row.layout()
+            .width(fit!())
+            .height(fit!())
+            .child_gap(GAP)
+            .direction(LayoutDirection::LeftToRight)
+            .end();
+        let mut name = Declaration::new();
+        name.layout()
+            .width(fixed!(slot_width))
+            .height(fit!())
+            .end()
+            .clip(true, false, Vector2::new(0.0, 0.0));
+
+        // Style of a slot holding a clip in `clip_state`, fading in the playing style
+        let slot_style = |slot_id: Id, clip_state: ClipState, widget_state: WidgetState| {
+            let resolve = |class: Option<&str>| {
+                if let Some(class) = class {
+                    self.push_pending_class(class);
+                }
+                self.widget_style("clip_slot", widget_state)
+            };
+            let base = match clip_state {
+                ClipState::Empty => resolve(None),
+                ClipState::Loaded | ClipState::Playing => resolve(Some("loaded")),
+                ClipState::Queued => resolve(Some("loaded")).lerp(&resolve(Some("queued")), pulse),
+            };
+            let playing = self.animate_bool(slot_id, clip_state == ClipState::Playing, FADE_TIME);
+            if playing > 0.0 {
+                base.lerp(&resolve(Some("playing")), playing)
+            } else {
+                base
+            }
+        };
+        // Contents of a slot holding `slot`, also used for the clip following the mouse
+        let slot_contents = |ui: &Ui, slot: ClipSlot, color: ClayColor| {
+            let ring = match slot.state {
+                ClipState::Empty => return,
+                ClipState::Loaded => ring_pixmap(ring_size, 0.0, ring_track, fill),
+                ClipState::Playing => ring_pixmap(ring_size, slot.progress, ring_track, fill),
+                ClipState::Queued => {
+                    let fill = ClayColor {
+                        a: fill.a * pulse,
+                        ..fill
+                    };
+                    ring_pixmap(ring_size, 1.0, ring_track, fill)
+                }
+            };
+            let mut icon = Declaration::new();
+            icon.layout()
+                .width(fixed!(ring_size as f32))
+                .height(fixed!(ring_size as f32))
+                .end();
+            if let Some(pixmap) = ring {
+                icon.image()
+                    .data(ui.alloc_image(ImageInfo::new(pixmap)))
+                    .end();
+            }
+            ui.with_layout(&icon, |_| {});
+            ui.text_element(slot.name, color);
+        };
+        let slot_decl = |slot_id: Option<Id>, style| {
+            let mut decl = Declaration::new();
+            if let Some(slot_id) = slot_id {
+                decl.id(slot_id);
+            }
+            decl.layout()
+                .width(fixed!(slot_width))
+                .height(fixed!(slot_height))
+                .child_gap(4)
+                .child_alignment(Alignment::new(
+                    LayoutAlignmentX::Left,
+                    LayoutAlignmentY::Center,
+                ))
+                .end()
+                .clip(true, false, Vector2::new(0.0, 0.0));
+            apply_style(&mut decl, &style);
+            decl
+        };
+
+        self.with_layout(&decl, |ui| {
+            ui.with_layout(&row, |ui| {
+                for track in tracks {
+                    ui.with_layout(&name, |ui| ui.text_element(track.name, text_color));
+                }
+            });
+            for (scene, scene_name) in scenes.iter().enumerate() {
+                ui.with_layout(&row, |ui| {
+                    for track in 0..tracks.len() {
+                        let slot = slot(track, scene);
+                        let id = slot_id(track, scene);
+                        let style = slot_style(
+                            id,
+                            slot.state,
+                            WidgetState {
+                                hovered: hit == Some(id.id.id),
+                                active: target == Some((track, scene)),
+                                ..Default::default()
+                            },
+                        );
+                        let color = style.text_color.unwrap_or(rgb(220, 220, 220));
+                        ui.with_layout(&slot_decl(Some(id), style), |ui| {
+                            slot_contents(ui, slot, color)
+                        });
+                    }
+
+                    let id = scene_id(scene);
+                    let style = ui.widget_style(
+                        "clip_scene",
+                        WidgetState {
+                            hovered: hit == Some(id.id.id),
+                            active: input.pressed_id == Some(id.id.id),
+                            ..Default::default()
+                        },
+                    );
+                    let mut button = Declaration::new();
+                    button
+                        .id(id)
+                        .layout()
+                        .width(fixed!(slot_height * SCENE_WIDTH))
+                        .height(fixed!(slot_height))
+                        .child_alignment(Alignment::new(
+                            LayoutAlignmentX::Left,
+                            LayoutAlignmentY::Center,
+                        ))
+                        .end()
+                        .clip(true, false, Vector2::new(0.0, 0.0));
+                    apply_style(&mut button, &style);
+                    let color = style.text_color.unwrap_or(rgb(220, 220, 220));
+                    ui.with_layout(&button, |ui| ui.text_element(scene_name, color));
+                });
+            }
+
+            // The clip dragged follows the mouse until it's dropped
+            if let Some((track, scene)) = dragging
+                && let Some(rect) = ui.last_frame_rect(grid_id)
+            {
+                let slot = slot(track, scene);
+                let style = slot_style(slot_id(track, scene), slot.state, WidgetState::default());
+                let color = style.text_color.unwrap_or(rgb(220, 220, 220));
+                let offset = input.mouse_pos - rect.xy() + 8.0;
+                let mut ghost = slot_decl(None, style);
+                ghost
+                    .floating()
+                    .attach_to(FloatingAttachToElement::Parent)
+                    .offset(Vector2::new(offset.x, offset.y))
+                    .end();
+                ui.with_layout(&ghost, |ui| slot_contents(ui, slot, color));
+            }
+        });
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_click_event() {
+        assert_eq!(
+            click_event(ClipState::Loaded, 1, 2),
+            ClipEvent::Launch { track: 1, scene: 2 }
+        );
+        assert_eq!(
+            click_event(ClipState::Queued, 1, 2),
+            ClipEvent::Launch { track: 1, scene: 2 }
+        );
+        assert_eq!(
+            click_event(ClipState::Playing, 1, 2),
+            ClipEvent::Stop { track: 1 }
+        );
+        assert_eq!(
+            click_event(ClipState::Empty, 3, 0),
+            ClipEvent::Stop { track: 3 }
+        );
+
+        assert_eq!(pulse(0.0), 0.0);
+        assert!((pulse(0.25) - 1.0).abs() < 1e-5);
+        assert!((pulse(0.5)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_ring_pixmap() {
+        let track = rgb(70, 70, 70);
+        let fill = rgb(100, 150, 255);
+        let ring = ring_pixmap(40, 0.25, track, fill).unwrap();
+        let pixel = |x: u32, y: u32| ring.pixel(x, y).unwrap();
+        // Filled from the top to the right, the track from there on
+        assert_eq!(pixel(20, 2).blue(), 255);
+        assert_eq!(pixel(31, 8).blue(), 255);
+        assert_eq!(pixel(20, 38).blue(), 70);
+        assert_eq!(pixel(2, 20).blue(), 70);
+        // The middle is empty
+        assert_eq!(pixel(20, 20).alpha(), 0);
+        assert!(ring_pixmap(0, 0.5, track, fill).is_none());
+    }
+}
@@ -0,0 +1,387 @@
+//! Date and time pickers.
+//!
+//! [`Ui::date_picker`] shows the date in a field that opens a calendar of one month below it,
+//! with buttons to page through the months. [`Ui::time_picker`] opens grids of the hours and of
+//! the minutes in five minute steps instead. Both close once a value has been picked.
+use crate::input::MouseButton;
+use crate::style::WidgetState;
+use crate::ui::{Ui, apply_style, rgb};
+use clay_layout::id::Id;
+use clay_layout::layout::{Alignment, LayoutAlignmentX, LayoutAlignmentY, LayoutDirection};
+use clay_layout::{Declaration, fit, fixed, grow};
+use glam::Vec2;
+use std::fmt;
+
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const WEEKDAY_NAMES: [&str; 7] = ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"];
+
+/// Minutes between the entries of the time picker.
+const MINUTE_STEP: u32 = 5;
+
+/// A day in the proleptic Gregorian calendar. Always a valid date, see [`Date::new`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    year: i32,
+    /// 1 to 12.
+    month: u32,
+    /// 1 to the number of days in the month.
+    day: u32,
+}
+
+impl Date {
+    /// The date with the month and day clamped to valid ones.
+    pub fn new(year: i32, month: u32, day: u32) -> Self {
+        let month = month.clamp(1, 12);
+        Self {
+            year,
+            month,
+            day: day.clamp(1, days_in_month(year, month)),
+        }
+    }
+
+    pub fn year(self) -> i32 {
+        self.year
+    }
+
+    /// 1 to 12.
+    pub fn month(self) -> u32 {
+        self.month
+    }
+
+    /// 1 to the number of days in the month.
+    pub fn day(self) -> u32 {
+        self.day
+    }
+
+    /// Day of the week, 0 for Monday to 6 for Sunday.
+    pub fn weekday(self) -> u32 {
+        // Sakamoto's method, counting from Sunday
+        const OFFSETS: [i32; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+        let year = if self.month < 3 {
+            self.year - 1
+        } else {
+            self.year
+        };
+        let from_sunday = (year + year.div_euclid(4) - year.div_euclid(100)
+            + year.div_euclid(400)
+            + OFFSETS[self.month as usize - 1]
+            + self.day as i32)
+            .rem_euclid(7);
+        (from_sunday as u32 + 6) % 7
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// A time of the day to the minute.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Time {
+    /// 0 to 23.
+    pub hour: u32,
+    /// 0 to 59.
+    pub minute: u32,
+}
+
+impl Time {
+    pub fn new(hour: u32, minute: u32) -> Self {
+        Self {
+            hour: hour.min(23),
+            minute: minute.min(59),
+        }
+    }
+}
+
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour, self.minute)
+    }
+}
+
+fn is_leap_year(year: i32) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+pub fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// The month `delta` months after `month` of `year`, as (year, month).
+fn add_months(year: i32, month: u32, delta: i32) -> (i32, u32) {
+    let index = year * 12 + month as i32 - 1 + delta;
+    (index.div_euclid(12), index.rem_euclid(12) as u32 + 1)
+}
+
+/// Days shown by the calendar of a month, a week per row starting on Monday. `None` for the
+/// cells before the first and after the last day.
+fn calendar_days(year: i32, month: u32) -> Vec<Option<u32>> {
+    let lead = Date::new(year, month, 1).weekday() as usize;
+    let days = days_in_month(year, month);
+    let mut cells = vec![None; lead];
+    cells.extend((1..=days).map(Some));
+    cells.resize(cells.len().div_ceil(7) * 7, None);
+    cells
+}
+
+impl Ui {
+    /// A field showing `date` that opens a calendar to pick another one. Returns true when a
+    /// day was picked. Styled by the `picker_field` and `calendar_cell` rules, the picked day
+    /// has the `selected` class.
+    pub fn date_picker(&self, id: &str, date: &mut Date) -> bool {
+        let field_id = self.id(id);
+        let popup_id = self.id_index(id, 1);
+        if self.picker_field(field_id, popup_id, &date.to_string()) {
+            self.item_state(field_id).calendar_month = Some((date.year, date.month));
+        }
+
+        let (year, month) = self
+            .item_state(field_id)
+            .calendar_month
+            .unwrap_or((date.year, date.month));
+        let cell = self.picker_cell_size();
+        let mut changed = false;
+        self.popup_panel(popup_id, "popup", |ui| {
+            let mut page = 0;
+            ui.picker_row(7.0 * cell, |ui| {
+                if ui.picker_cell(ui.id_index(id, 2), "<", false, cell) {
+                    page = -1;
+                }
+                let title = format!("{} {}", MONTH_NAMES[month as usize - 1], year);
+                let mut decl = Declaration::new();
+                decl.layout()
+                    .width(grow!())
+                    .height(fixed!(cell))
+                    .child_alignment(Alignment::new(
+                        LayoutAlignmentX::Center,
+                        LayoutAlignmentY::Center,
+                    ))
+                    .end();
+                ui.with_layout(&decl, |ui| {
+                    ui.text_element(ui.alloc_str(title), rgb(220, 220, 220))
+                });
+                if ui.picker_cell(ui.id_index(id, 3), ">", false, cell) {
+                    page = 1;
+                }
+            });
+
+            let header = ui.widget_style("calendar_header", Default::default());
+            let color = header.text_color.unwrap_or(rgb(150, 150, 150));
+            ui.picker_row(7.0 * cell, |ui| {
+                for name in WEEKDAY_NAMES {
+                    let mut decl = Declaration::new();
+                    decl.layout()
+                        .width(fixed!(cell))
+                        .height(fixed!(cell))
+                        .child_alignment(Alignment::new(
+                            LayoutAlignmentX::Center,
+                            LayoutAlignmentY::Center,
+                        ))
+                        .end();
+                    ui.with_layout(&decl, |ui| ui.text_element(name, color));
+                }
+            });
+
+            for week in calendar_days(year, month).chunks(7) {
+                ui.picker_row(7.0 * cell, |ui| {
+                    for day in week {
+                        let Some(day) = *day else {
+                            let mut blank = Declaration::new();
+                            blank
+                                .layout()
+                                .width(fixed!(cell))
+                                .height(fixed!(cell))
+                                .end();
+                            ui.with_layout(&blank, |_| {});
+                            continue;
+                        };
+                        let picked = Date::new(year, month, day);
+                        let text = ui.alloc_str(day.to_string());
+                        let cell_id = ui.id_index(id, 3 + day);
+                        if ui.picker_cell(cell_id, text, picked == *date, cell) {
+                            *date = picked;
+                            changed = true;
+                            ui.close_popup();
+                        }
+                    }
+                });
+            }
+
+            if page != 0 {
+                ui.item_state(field_id).calendar_month = Some(add_months(year, month, page));
+            }
+        });
+        changed
+    }
+
+    /// A field showing `time` that opens grids of hours and minutes to pick another one.
+    /// Returns true when the hour or minute changed. Styled like [`Ui::date_picker`].
+    pub fn time_picker(&self, id: &str, time: &mut Time) -> bool {
+        let field_id = self.id(id);
+        let popup_id = self.id_index(id, 1);
+        self.picker_field(field_id, popup_id, &time.to_string());
+
+        let cell = self.picker_cell_size();
+        let mut changed = false;
+        self.popup_panel(popup_id, "popup", |ui| {
+            let hours: Vec<u32> = (0..24).collect();
+            for row in hours.chunks(6) {
+                ui.picker_row(6.0 * cell, |ui| {
+                    for hour in row {
+                        let text = ui.alloc_str(format!("{:02}", hour));
+                        let cell_id = ui.id_index(id, 2 + hour);
+                        if ui.picker_cell(cell_id, text, *hour == time.hour, cell) {
+                            changed |= time.hour != *hour;
+                            time.hour = *hour;
+                        }
+                    }
+                });
+            }
+
+            // Picking the minute is the last step
+            let minutes: Vec<u32> = (0..60).step_by(MINUTE_STEP as usize).collect();
+            let current = time.minute / MINUTE_STEP * MINUTE_STEP;
+            for row in minutes.chunks(6) {
+                ui.picker_row(6.0 * cell, |ui| {
+                    for minute in row {
+                        let text = ui.alloc_str(format!(":{:02}", minute));
+                        let cell_id = ui.id_index(id, 26 + minute);
+                        if ui.picker_cell(cell_id, text, *minute == current, cell) {
+                            changed |= time.minute != *minute;
+                            time.minute = *minute;
+                            ui.close_popup();
+                        }
+                    }
+                });
+            }
+        });
+        changed
+    }
+
+    /// The field of a picker showing `text`, opening the popup below it when pressed. Returns
+    /// true when it opened the popup. Pressing it while open closes the popup like any press
+    /// outside of it.
+    fn picker_field(&self, field_id: Id, popup_id: Id, text: &str) -> bool {
+        let input = self.input();
+        let pressed =
+            input.button_pressed(MouseButton::Left) && input.pressed_id == Some(field_id.id.id);
+        let open = pressed && self.popups().index_of(popup_id.id.id).is_none();
+        if open {
+            let pos = self
+                .last_frame_rect(field_id)
+                .map_or(input.mouse_pos, |rect| Vec2::new(rect.x, rect.w));
+            self.open_popup_at(popup_id, pos);
+        }
+
+        let style = self.animated_widget_style("picker_field", field_id);
+        let color = style.text_color.unwrap_or(rgb(220, 220, 220));
+        let mut decl = Declaration::new();
+        decl.id(field_id)
+            .layout()
+            .width(fit!())
+            .height(fit!())
+            .end();
+        apply_style(&mut decl, &style);
+        let text = self.alloc_str(text.to_string());
+        self.with_layout(&decl, |ui| ui.text_element(text, color));
+        open
+    }
+
+    /// A row of a picker's grid, `width` wide.
+    fn picker_row(&self, width: f32, f: impl FnOnce(&Ui)) {
+        let mut decl = Declaration::new();
+        decl.layout()
+            .width(fixed!(width))
+            .height(fit!())
+            .direction(LayoutDirection::LeftToRight)
+            .end();
+        self.with_layout(&decl, f);
+    }
+
+    /// A square cell of a picker's grid showing `text`. Returns true when clicked.
+    fn picker_cell(&self, id: Id, text: &str, selected: bool, size: f32) -> bool {
+        let input = self.input();
+        let hovered = self
+            .last_frame_rect(id)
+            .is_some_and(|rect| input.is_hovered(rect));
+        if selected {
+            self.push_pending_class("selected");
+        }
+        let style = self.widget_style(
+            "calendar_cell",
+            WidgetState {
+                hovered,
+                ..Default::default()
+            },
+        );
+        let color = style.text_color.unwrap_or(rgb(220, 220, 220));
+
+        let mut decl = Declaration::new();
+        decl.id(id)
+            .layout()
+            .width(fixed!(size))
+            .height(fixed!(size))
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Center,
+                LayoutAlignmentY::Center,
+            ))
+            .end();
+        apply_style(&mut decl, &style);
+        self.with_layout(&decl, |ui| ui.text_element(text, color));
+        input.was_clicked(id.id.id, MouseButton::Left)
+    }
+
+    /// Side of the cells of picker grids, fitting two digits of the current font.
+    fn picker_cell_size(&self) -> f32 {
+        (self.font_size() as f32 * 1.6).round()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calendar() {
+        assert_eq!(Date::new(2024, 1, 1).weekday(), 0);
+        assert_eq!(Date::new(2000, 2, 29).weekday(), 1);
+        assert_eq!(Date::new(1970, 1, 1).weekday(), 3);
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(1900, 2), 28);
+        assert_eq!(Date::new(2023, 2, 31), Date::new(2023, 2, 28));
+        let clamped = Date::new(2024, 13, 0);
+        assert_eq!((clamped.month(), clamped.day()), (12, 1));
+        assert_eq!(clamped.weekday(), 6);
+
+        assert_eq!(add_months(2024, 12, 1), (2025, 1));
+        assert_eq!(add_months(2024, 1, -1), (2023, 12));
+
+        // September 2024 starts on a Sunday and spans six weeks
+        let days = calendar_days(2024, 9);
+        assert_eq!(days.len(), 42);
+        assert_eq!(days[5], None);
+        assert_eq!(days[6], Some(1));
+        assert_eq!(days[35], Some(30));
+    }
+}
@@ -1,7 +1,57 @@
+use crate::accordion::AccordionSection;
+use crate::clip_grid::{ClipEvent, ClipSlot, ClipState, ClipTrack};
+use crate::date_picker::{Date, Time};
+use crate::fader::{Fader, FaderTaper};
+use crate::file_tree::FsModel;
+use crate::image::{ImageFit, ImageInfo};
+use crate::input::CursorIcon;
+use crate::keyboard::Keyboard;
+use crate::meter_bridge::MeterChannel;
+use crate::minimap::MinimapItem;
+use crate::pan::SpeakerLayout;
+use crate::piano_roll::{MidiNote, PianoRoll, PianoRollView};
+use crate::region::Region;
+use crate::rotated_text::TextRotation;
+use crate::ruler::TimeScale;
+use crate::scale::{Scale, ScaleKind};
+use crate::scroll_area::Align;
+use crate::scrub::TimeSpan;
+use crate::splitter::DIVIDER_SIZE;
+use crate::table::Column;
+use crate::toast::NotifyLevel;
+use crate::transition::Transition;
+use crate::transport::TransportState;
+use crate::ui::{DragValue, Label};
 use crate::{Ui, area, rgb, rgba};
 use clay_layout::{
-    color::Color as ClayColor, fixed, grow, layout::LayoutDirection, layout::Padding,
+    color::Color as ClayColor,
+    elements::{FloatingAttachToElement, FloatingClipToElement},
+    fit, fixed, grow,
+    layout::LayoutDirection,
+    layout::Padding,
+    math::Vector2,
 };
+use glam::{Vec2, Vec4};
+use std::cell::RefCell;
+
+/// Horizontal zoom of the timeline at `zoom_level` 1.0.
+const PIXELS_PER_SECOND: f32 = 60.0;
+/// Length of the project shown in the timeline, in seconds.
+const PROJECT_LENGTH: f32 = 300.0;
+/// Sample rate of the project audio.
+const SAMPLE_RATE: u32 = 48_000;
+
+/// Scenes of the session grid.
+const SCENES: [&str; 4] = ["Intro", "Verse", "Chorus", "Outro"];
+
+/// Installed plugins as (name, vendor, CPU load in percent, load as text).
+const PLUGINS: [(&str, &str, f32, &str); 5] = [
+    ("Compressor", "Yaui Audio", 1.2, "1.2%"),
+    ("Equalizer", "Yaui Audio", 0.8, "0.8%"),
+    ("Reverb", "Hall Labs", 4.5, "4.5%"),
+    ("Delay", "Hall Labs", 2.1, "2.1%"),
+    ("Saturator", "Tube Works", 3.0, "3.0%"),
+];
 
 // DAW-specific data structures
 #[derive(Debug, Clone)]
@@ -14,6 +64,8 @@ pub struct Track {
     pub pan: f32,
     pub clips: Vec<Clip>,
     pub track_type: TrackType,
+    /// Labels the user gave the track.
+    pub tags: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -39,35 +91,62 @@ pub enum ClipType {
     Midi { notes: Vec<MidiNote> },
 }
 
-#[derive(Debug, Clone)]
-pub struct MidiNote {
-    pub pitch: u8,
-    pub velocity: u8,
-    pub start: f32,
-    pub duration: f32,
-}
-
 #[derive(Debug)]
 pub struct DawState {
     pub tracks: Vec<Track>,
     pub timeline_position: f32,
     pub zoom_level: f32,
-    pub is_playing: bool,
-    pub is_recording: bool,
+    pub transport: TransportState,
     pub tempo: f32,
     pub time_signature: (u8, u8),
     pub selected_tool: Tool,
+    pub playhead_follow: PlayheadFollow,
     pub mixer_visible: bool,
+    /// Level sent from the mix to the reverb bus.
+    pub reverb_send: f32,
+    /// Where the reverb bus is placed among the surround speakers.
+    pub reverb_position: Vec2,
+    /// Notes held on the keyboard of the piano roll.
+    pub held_notes: Vec<u8>,
+    /// Scale the piano roll highlights.
+    pub scale: Scale,
+    /// Note of the scale whose triad the piano roll highlights, 0 for the root.
+    pub chord_degree: usize,
+    /// Notes edited in the piano roll.
+    pub piano_roll_notes: Vec<MidiNote>,
+    pub piano_roll_view: PianoRollView,
+    /// Names of the clips in the session grid by track and scene, `None` for empty slots.
+    pub session_clips: Vec<Vec<Option<String>>>,
+    /// Scene whose clip each track plays.
+    pub playing_clips: Vec<Option<usize>>,
+    /// Scene whose clip each track starts at the next bar.
+    pub queued_clips: Vec<Option<usize>>,
+    pub settings_open: bool,
+    pub track_info_open: bool,
+    /// When the project is due for export, edited in the settings dialog.
+    pub export_date: Date,
+    pub export_time: Time,
+    pub selected_loops: Vec<usize>,
+    /// Filters the plugin list of the browser.
+    pub plugin_query: String,
+    pub selected_plugin: Option<usize>,
+    /// Star ratings of the drum samples in the browser.
+    pub sample_ratings: [u8; 3],
+    /// Files shown under "Disk" in the browser.
+    pub disk_files: FsModel,
+    /// Fraction of the width taken by the track area, the panels get the rest.
+    pub track_split: f32,
+    /// Loop and selection ranges drawn over the timeline, in seconds.
+    pub regions: Vec<Region>,
+    pub artwork: ImageInfo,
     // String storage to keep formatted strings alive
     pub time_display_text: String,
     pub track_volume_texts: Vec<String>,
-    pub timeline_marker_texts: Vec<String>,
-    pub piano_key_ids: Vec<String>,
     pub clip_ids: Vec<String>,
     pub track_row_ids: Vec<String>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Tool {
     Select,
     Draw,
@@ -77,6 +156,45 @@ pub enum Tool {
     Zoom,
 }
 
+/// How the timeline scrolls to keep the playhead in view during playback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayheadFollow {
+    Off,
+    /// Scrolls with the playhead, keeping it in the middle of the view.
+    Continuous,
+    /// Jumps a page ahead when the playhead reaches the right edge of the view.
+    Page,
+}
+
+impl PlayheadFollow {
+    fn next(self) -> Self {
+        match self {
+            PlayheadFollow::Off => PlayheadFollow::Continuous,
+            PlayheadFollow::Continuous => PlayheadFollow::Page,
+            PlayheadFollow::Page => PlayheadFollow::Off,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            PlayheadFollow::Off => "Follow: Off",
+            PlayheadFollow::Continuous => "Follow: Scroll",
+            PlayheadFollow::Page => "Follow: Page",
+        }
+    }
+
+    /// Where the view starting at `scroll` and `view_width` wide should scroll to for the
+    /// playhead at `x`, or `None` to stay.
+    fn scroll_for(self, x: f32, scroll: f32, view_width: f32) -> Option<f32> {
+        match self {
+            PlayheadFollow::Off => None,
+            PlayheadFollow::Continuous => Some(x - view_width * 0.5),
+            PlayheadFollow::Page if x < scroll || x >= scroll + view_width => Some(x),
+            PlayheadFollow::Page => None,
+        }
+    }
+}
+
 impl DawState {
     pub fn update_time_display(&mut self) {
         let minutes = (self.timeline_position / 60.0) as u32;
@@ -93,6 +211,17 @@ impl DawState {
     }
 }
 
+/// Stand-in for project artwork loaded from disk: a diagonal gradient.
+fn project_artwork() -> ImageInfo {
+    let mut pixmap = tiny_skia::Pixmap::new(64, 64).unwrap();
+    let width = pixmap.width() as usize;
+    for (index, pixel) in pixmap.pixels_mut().iter_mut().enumerate() {
+        let (x, y) = ((index % width) as u8, (index / width) as u8);
+        *pixel = tiny_skia::PremultipliedColorU8::from_rgba(x * 3, 60, y * 3, 255).unwrap();
+    }
+    ImageInfo::new(pixmap)
+}
+
 impl Default for DawState {
     fn default() -> Self {
         let tracks = vec![
@@ -105,6 +234,7 @@ impl Default for DawState {
                 pan: 0.0,
                 clips: vec![],
                 track_type: TrackType::Audio,
+                tags: vec!["percussion".to_string()],
             },
             Track {
                 name: "Congas".to_string(),
@@ -115,6 +245,7 @@ impl Default for DawState {
                 pan: 0.2,
                 clips: vec![],
                 track_type: TrackType::Audio,
+                tags: vec!["percussion".to_string()],
             },
             Track {
                 name: "Cowbells".to_string(),
@@ -125,6 +256,7 @@ impl Default for DawState {
                 pan: -0.1,
                 clips: vec![],
                 track_type: TrackType::Audio,
+                tags: vec!["percussion".to_string()],
             },
         ];
 
@@ -133,32 +265,64 @@ impl Default for DawState {
             .iter()
             .map(|track| format!("Vol: {:.1}", track.volume))
             .collect();
-        let timeline_marker_texts = (0..20).map(|i| format!("{}", i)).collect();
-        let mut piano_key_ids = Vec::new();
-        for octave in 0..4 {
-            for note in 0..12 {
-                piano_key_ids.push(format!("key_{}_{}", octave, note));
-            }
-        }
 
         // Pre-allocate clip and track row IDs (for a reasonable number)
         let clip_ids: Vec<String> = (0..100).map(|i| format!("clip_{}", i)).collect();
         let track_row_ids: Vec<String> = (0..20).map(|i| format!("track_row_{}", i)).collect();
 
+        // The first three scenes of every track hold a clip
+        let session_clips = tracks
+            .iter()
+            .map(|track| {
+                (0..SCENES.len())
+                    .map(|scene| (scene < 3).then(|| format!("{} {}", track.name, scene + 1)))
+                    .collect()
+            })
+            .collect();
+        let track_count = tracks.len();
+
         let mut state = Self {
             tracks,
             timeline_position: 0.0,
             zoom_level: 1.0,
-            is_playing: false,
-            is_recording: false,
+            transport: TransportState::default(),
             tempo: 120.0,
             time_signature: (4, 4),
             selected_tool: Tool::Select,
+            playhead_follow: PlayheadFollow::Page,
             mixer_visible: true,
+            reverb_send: 0.3,
+            reverb_position: Vec2::new(0.0, -0.5),
+            held_notes: Vec::new(),
+            scale: Scale::new(0, ScaleKind::Major),
+            chord_degree: 0,
+            piano_roll_notes: [(60, 0.0), (64, 1.0), (67, 2.0), (72, 3.0)]
+                .into_iter()
+                .map(|(pitch, start)| MidiNote {
+                    pitch,
+                    velocity: 100,
+                    start,
+                    duration: 1.0,
+                })
+                .collect(),
+            piano_roll_view: PianoRollView::default(),
+            session_clips,
+            playing_clips: vec![None; track_count],
+            queued_clips: vec![None; track_count],
+            settings_open: false,
+            track_info_open: false,
+            export_date: Date::new(2025, 6, 1),
+            export_time: Time::new(18, 0),
+            selected_loops: Vec::new(),
+            plugin_query: String::new(),
+            selected_plugin: None,
+            sample_ratings: [4, 0, 2],
+            disk_files: FsModel::new(concat!(env!("CARGO_MANIFEST_DIR"), "/../data")),
+            track_split: 0.79,
+            regions: vec![Region::new("Loop", 8.0, 16.0, rgba(100, 150, 255, 60))],
+            artwork: project_artwork(),
             time_display_text: String::new(),
             track_volume_texts,
-            timeline_marker_texts,
-            piano_key_ids,
             clip_ids,
             track_row_ids,
         };
@@ -171,11 +335,8 @@ impl Default for DawState {
 
 // Reusable UI components that should be added to the base UI library
 trait UiExtensions {
-    fn knob(&self, label: &str, value: &mut f32, min: f32, max: f32) -> bool;
-    fn fader(&self, label: &str, value: &mut f32, vertical: bool) -> bool;
     fn waveform_display(&self, data: &[f32], width: f32, height: f32, color: ClayColor);
     fn piano_roll(&self, notes: &[MidiNote], width: f32, height: f32);
-    fn meter(&self, level: f32, peak: f32, vertical: bool);
     fn transport_button(&self, icon: &str, active: bool) -> bool;
     fn track_header(&self, track: &Track) -> TrackHeaderResponse;
 }
@@ -192,69 +353,129 @@ pub struct TrackHeaderResponse {
 }
 
 // Top toolbar components
-fn toolbar_parameter_controls(ui: &Ui) {
+fn toolbar_parameter_controls(state: &mut DawState, ui: &Ui) {
     area!(ui, {
         id: "toolbar_parameter_controls",
         layout: {
-            width: fixed!(200.0),
+            width: fit!(),
             height: fixed!(40.0),
             padding: Padding::all(5),
             direction: LayoutDirection::LeftToRight,
         },
         background_color: rgb(32, 32, 32),
     }, |ui: &Ui| {
-        ui.label("Parameter", rgb(200, 200, 200));
-        ui.label("Control", rgb(200, 200, 200));
+        ui.drag_value_with("Tempo", &mut state.tempo, DragValue::new(0.5).range(20.0, 300.0).precision(1));
+        ui.checkbox("Mixer", &mut state.mixer_visible);
+        ui.drag_value_with("Zoom", &mut state.zoom_level, DragValue::new(0.01).range(0.1, 10.0));
+        if ui.button("Settings").clicked {
+            state.settings_open = true;
+        }
+    });
+}
+
+fn settings_dialog(state: &mut DawState, ui: &Ui) {
+    let mut open = state.settings_open;
+    ui.modal("settings_dialog", &mut open, |ui| {
+        ui.heading("Project Settings");
+        ui.drag_value_with(
+            "Project Tempo",
+            &mut state.tempo,
+            DragValue::new(0.5).range(20.0, 300.0).precision(1),
+        );
+        if ui.link("Reset Tempo") {
+            state.tempo = 120.0;
+            ui.notify(NotifyLevel::Info, "Tempo reset to 120 BPM");
+        }
+        let mut beats = state.time_signature.0 as i32;
+        if ui.stepper("Beats per Bar", &mut beats, 1, 1..=16) {
+            state.time_signature.0 = beats as u8;
+        }
+        ui.checkbox("Show Mixer", &mut state.mixer_visible);
+        ui.date_picker("export_date", &mut state.export_date);
+        ui.time_picker("export_time", &mut state.export_time);
+        ui.focus_default("Close");
+        if ui.button("Close").clicked {
+            state.settings_open = false;
+        }
+    });
+    state.settings_open &= open;
+}
+
+fn track_info_window(state: &mut DawState, ui: &Ui) {
+    ui.window("Track Info", &mut state.track_info_open, |ui| {
+        ui.heading("Tracks");
+        for (index, (track, volume)) in state
+            .tracks
+            .iter_mut()
+            .zip(&state.track_volume_texts)
+            .enumerate()
+        {
+            let text = ui.alloc_str(format!("{}  {}", track.name, volume));
+            let pan = ui.alloc_str(format!("{} Pan", track.name));
+            let info = ui.group(|ui| {
+                ui.label(text, rgb(220, 220, 220));
+                let panned = ui.scrub_label(pan, &mut track.pan, 0.005);
+                track.pan = track.pan.clamp(-1.0, 1.0);
+                ui.tag_input(&format!("track_tags_{}", index), &mut track.tags) | panned
+            });
+            if info.any_changed {
+                ui.notify(NotifyLevel::Info, format!("{} changed", track.name));
+            }
+        }
     });
 }
 
 fn toolbar_tools(state: &mut DawState, ui: &Ui) {
+    let tools = [
+        (Tool::Select, "Select"),
+        (Tool::Draw, "Draw"),
+        (Tool::Erase, "Erase"),
+        (Tool::Move, "Move"),
+        (Tool::Cut, "Cut"),
+        (Tool::Zoom, "Zoom"),
+    ];
+
     area!(ui, {
         id: "toolbar_tools",
         layout: {
-            width: fixed!(300.0),
+            width: fit!(),
             height: fixed!(40.0),
             padding: Padding::all(2),
             direction: LayoutDirection::LeftToRight,
         },
     }, |ui: &Ui| {
-        let tools = [
-            (Tool::Select, "🔍oesthu"),
-            (Tool::Draw, "✏️oesth"),
-            (Tool::Erase, "🗑️osteh"),
-            (Tool::Move, "↔️osethu"),
-            (Tool::Cut, "✂️oust"),
-            (Tool::Zoom, "🔍oesuth"),
-        ];
-
-        for (_tool, icon) in tools {
-            let is_selected = matches!(state.selected_tool, _tool);
-            ui.label(icon, if is_selected {
-                rgb(100, 150, 255)
-            } else {
-                rgba(150, 150, 150, 128) // Semi-transparent when not selected
-            });
+        for (tool, label) in tools {
+            ui.radio(label, &mut state.selected_tool, tool);
         }
     });
+
+    // Right-clicking the tools opens them around the mouse
+    let labels = tools.map(|(_, label)| label);
+    if let Some(index) = ui.radial_menu("tool_menu", &labels) {
+        state.selected_tool = tools[index].0;
+    }
 }
 
 fn transport_controls(state: &mut DawState, ui: &Ui) {
     area!(ui, {
         id: "transport_controls",
         layout: {
-            width: fixed!(250.0),
+            width: fit!(),
             height: fixed!(40.0),
             padding: Padding::all(5),
             direction: LayoutDirection::LeftToRight,
         },
     }, |ui: &Ui| {
-        ui.label("⏮️", rgb(200, 200, 200)); // Previous
-        ui.label("⏹️", rgb(200, 200, 200)); // Stop
-        ui.label(if state.is_playing { "⏸️" } else { "▶️" },
-                if state.is_playing { rgb(100, 255, 100) } else { rgb(200, 200, 200) });
-        ui.label("⏭️", rgb(200, 200, 200)); // Next
-        ui.label(if state.is_recording { "⏺️" } else { "⏺️" },
-                if state.is_recording { rgb(255, 100, 100) } else { rgb(200, 200, 200) });
+        let was_playing = state.transport.playing;
+        // Stopping when already stopped goes back to the start
+        if ui.transport("transport", &mut state.transport).stopped && !was_playing {
+            state.timeline_position = 0.0;
+            state.update_time_display();
+        }
+        if ui.button(state.playhead_follow.label()).clicked {
+            state.playhead_follow = state.playhead_follow.next();
+        }
+        ui.tooltip_text("How the timeline follows the playhead during playback");
     });
 }
 
@@ -272,6 +493,30 @@ fn time_display(state: &DawState, ui: &Ui) {
     });
 }
 
+/// Levels of the master bus, made up from the playback position while playing.
+fn master_meters(state: &DawState, ui: &Ui) {
+    area!(ui, {
+        id: "master_meters",
+        layout: {
+            width: fit!(),
+            height: fixed!(40.0),
+            padding: Padding::all(10),
+            direction: LayoutDirection::TopToBottom,
+            child_gap: 4,
+        },
+    }, |ui: &Ui| {
+        for (channel, id) in ["master_left", "master_right"].into_iter().enumerate() {
+            let level = if state.transport.playing {
+                let t = state.timeline_position * 0.7 + channel as f32;
+                0.55 + 0.25 * t.sin() + 0.15 * (t * 3.1).sin()
+            } else {
+                0.0
+            };
+            ui.meter(id, level, level, false);
+        }
+    });
+}
+
 fn toolbar(state: &mut DawState, ui: &Ui) {
     area!(ui, {
         id: "toolbar",
@@ -283,15 +528,60 @@ fn toolbar(state: &mut DawState, ui: &Ui) {
         },
         background_color: rgb(40, 40, 40),
     }, |ui| {
-        toolbar_parameter_controls(ui);
-        toolbar_tools(state, ui);
-        transport_controls(state, ui);
-        time_display(state, ui);
+        ui.toolbar("toolbar_items", |ui| {
+            toolbar_parameter_controls(state, ui);
+            toolbar_tools(state, ui);
+            transport_controls(state, ui);
+            time_display(state, ui);
+            master_meters(state, ui);
+        });
+
+        // Glyphs and images still loading in the background
+        if !ui.pending_work().is_empty() {
+            ui.spinner(16.0);
+        }
+    });
+}
+
+fn menu_bar(state: &mut DawState, ui: &Ui) {
+    ui.menu_bar(|ui| {
+        ui.menu("File", |ui| {
+            ui.menu_item("New Project");
+            ui.menu_item("Open Project...");
+            ui.menu_item("Save Project");
+        });
+        ui.menu("Edit", |ui| {
+            ui.menu_item("Undo");
+            ui.menu_item("Redo");
+        });
+        ui.menu("View", |ui| {
+            if ui.menu_item("Toggle Mixer") {
+                state.mixer_visible = !state.mixer_visible;
+            }
+            ui.menu("Playhead Follow", |ui| {
+                let modes = [
+                    (PlayheadFollow::Off, "Follow Off"),
+                    (PlayheadFollow::Continuous, "Follow Continuous"),
+                    (PlayheadFollow::Page, "Follow Page"),
+                ];
+                for (mode, label) in modes {
+                    if ui.menu_item(label) {
+                        state.playhead_follow = mode;
+                    }
+                }
+            });
+            if ui.menu_item("Project Settings...") {
+                state.settings_open = true;
+            }
+            if ui.menu_item("Track Info") {
+                state.track_info_open = true;
+            }
+        });
     });
 }
 
 // Track area components
-fn track_header(track: &Track, ui: &Ui) {
+fn track_header(track: &mut Track, ui: &Ui) {
     area!(ui, {
         id: "track_header",
         layout: {
@@ -312,8 +602,8 @@ fn track_header(track: &Track, ui: &Ui) {
                 direction: LayoutDirection::LeftToRight,
             },
         }, |ui: &Ui| {
-            ui.label("M", if track.muted { rgb(255, 100, 100) } else { rgb(100, 100, 100) });
-            ui.label("S", if track.soloed { rgb(255, 255, 100) } else { rgb(100, 100, 100) });
+            ui.checkbox("M", &mut track.muted);
+            ui.checkbox("S", &mut track.soloed);
             // Note: For now using a static string, would need track index to use stored volume text
             ui.label("Vol: N/A", rgb(200, 200, 200));
         });
@@ -362,32 +652,121 @@ fn track_timeline(track: &Track, timeline_width: f32, state: &DawState, ui: &Ui)
     });
 }
 
-fn track_area(state: &DawState, ui: &Ui) {
-    let _timeline_width = 1200.0; // Should be based on zoom and project length
+fn track_area(state: &mut DawState, ui: &Ui, width: f32) {
+    let timeline_width = PROJECT_LENGTH * PIXELS_PER_SECOND * state.zoom_level;
+    let playhead_x = state.timeline_position * PIXELS_PER_SECOND * state.zoom_level;
 
     area!(ui, {
         id: "track_area",
         layout: {
-            width: grow!(),
+            width: fixed!(width),
             height: fixed!(600.0),
             direction: LayoutDirection::TopToBottom,
         },
         background_color: rgb(0, 0, 255),
     }, |ui| {
-        // Time ruler
+        let pixels_per_second = PIXELS_PER_SECOND * state.zoom_level;
+        let view = Vec2::new(width, 546.0);
+
+        // The ruler stays above the scrolled lanes and shows the part of the project they do
+        let scroll = ui.scroll_area_state("timeline", view);
+        ui.ruler(
+            "time_ruler",
+            &TimeScale,
+            (scroll.offset.x / pixels_per_second) as f64,
+            pixels_per_second as f64,
+            Vec2::new(width, 30.0),
+        );
+        ui.set_cursor(ui.id("time_ruler"), CursorIcon::ResizeHorizontal);
+
+        let span = TimeSpan {
+            start: 0.0,
+            duration: PROJECT_LENGTH as f64,
+            sample_rate: SAMPLE_RATE,
+        };
+        // Hosts audition the audio under the mouse from this
+        let scrub = ui.scrub(ui.id("timeline_lanes"), span);
+
+        let timeline = ui.scroll_area("timeline", view, |ui| {
+            area!(ui, {
+                id: "timeline_lanes",
+                layout: {
+                    width: fixed!(timeline_width),
+                    height: fixed!(view.y),
+                },
+                background_color: rgb(28, 28, 36),
+            }, |ui: &Ui| {
+                if let Some(scrub) = scrub {
+                    let color = if scrub.dragging { rgb(255, 255, 255) } else { rgb(120, 120, 140) };
+                    area!(ui, {
+                        id: "scrub_line",
+                        layout: {
+                            width: fixed!(1.0),
+                            height: fixed!(view.y),
+                        },
+                        background_color: color,
+                        floating: {
+                            offset: Vector2::new((scrub.fraction * timeline_width).round(), 0.0),
+                            attach_to: FloatingAttachToElement::Parent,
+                            clip_to: FloatingClipToElement::AttachedParent,
+                        },
+                    }, |_ui| {});
+                }
+
+                area!(ui, {
+                    id: "playhead",
+                    layout: {
+                        width: fixed!(2.0),
+                        height: fixed!(view.y),
+                    },
+                    background_color: rgb(255, 220, 80),
+                    floating: {
+                        offset: Vector2::new(playhead_x, 0.0),
+                        attach_to: FloatingAttachToElement::Parent,
+                        clip_to: FloatingClipToElement::AttachedParent,
+                    },
+                }, |_ui| {});
+
+                ui.region_overlay("timeline_regions", &mut state.regions, span);
+            });
+        });
+
+        if state.transport.playing {
+            let scroll = timeline.offset;
+            if let Some(x) = state.playhead_follow.scroll_for(playhead_x, scroll.x, width) {
+                ui.scroll_to("timeline", Vec2::new(x, scroll.y), Align::Start);
+            }
+        }
+
+        // The whole project below the lanes, dragging the view scrolls them
+        let mut items: Vec<MinimapItem> = state
+            .regions
+            .iter()
+            .map(|region| MinimapItem {
+                rect: Vec4::new(
+                    region.start as f32 * pixels_per_second,
+                    0.0,
+                    region.end as f32 * pixels_per_second,
+                    view.y,
+                ),
+                color: region.color,
+            })
+            .collect();
+        items.push(MinimapItem {
+            rect: Vec4::new(playhead_x, 0.0, playhead_x, view.y),
+            color: rgb(255, 220, 80),
+        });
+        let viewport = Vec4::from((timeline.offset, timeline.offset + view));
         area!(ui, {
-            id: "time_ruler",
+            id: "timeline_overview",
             layout: {
-                width: grow!(),
-                height: fixed!(30.0),
+                width: fixed!(width),
+                height: fixed!(24.0),
             },
-            background_color: rgb(60, 60, 60),
-        }, |ui: &Ui| {
-            for i in 0..20 {
-                let _x = i as f32 * 60.0; // Every second
-                if i < state.timeline_marker_texts.len() {
-                    ui.label(&state.timeline_marker_texts[i], rgb(200, 200, 200));
-                }
+        }, |ui| {
+            let dragged = ui.minimap("timeline_minimap", timeline.content_size, viewport, &items);
+            if let Some(offset) = dragged {
+                ui.scroll_to("timeline", offset, Align::Start);
             }
         });
 
@@ -416,7 +795,7 @@ fn track_area(state: &DawState, ui: &Ui) {
 }
 
 // Mixer panel components
-fn channel_strip(track: &Track, ui: &Ui) {
+fn channel_strip(track: &mut Track, ui: &Ui) {
     area!(ui, {
         id: "channel_strip",
         layout: {
@@ -427,7 +806,11 @@ fn channel_strip(track: &Track, ui: &Ui) {
         },
         background_color: rgb(45, 45, 45),
     }, |ui: &Ui| {
-        ui.label(&track.name, rgb(255, 255, 255));
+        ui.label_with(
+            &track.name,
+            rgb(255, 255, 255),
+            Label::new().rotation(TextRotation::CounterClockwise),
+        );
 
         // EQ section
         area!(ui, {
@@ -465,18 +848,11 @@ fn channel_strip(track: &Track, ui: &Ui) {
             // For now using static text - would need track index and state to use stored volume text
             ui.label("0.8", rgb(200, 200, 200));
 
-            // Volume fader (vertical)
-            area!(ui, {
-                id: "volume_fader",
-                layout: {
-                    width: fixed!(20.0),
-                    height: grow!(),
-                },
-                background_color: rgb(60, 60, 60),
-            }, |_ui| {
-                // Fader handle
-                let _handle_y = (1.0 - track.volume) * 100.0;
-            });
+            ui.fader_with(
+                "Volume",
+                &mut track.volume,
+                Fader::new(true).taper(FaderTaper::Decibels),
+            );
 
             // Mute/Solo buttons
             area!(ui, {
@@ -487,8 +863,8 @@ fn channel_strip(track: &Track, ui: &Ui) {
                     direction: LayoutDirection::LeftToRight,
                 },
             }, |ui: &Ui| {
-                ui.label("M", if track.muted { rgb(255, 100, 100) } else { rgb(100, 100, 100) });
-                ui.label("S", if track.soloed { rgb(255, 255, 100) } else { rgb(100, 100, 100) });
+                ui.checkbox("M", &mut track.muted);
+                ui.checkbox("S", &mut track.soloed);
             });
         });
     });
@@ -531,7 +907,7 @@ fn mixer_panel(state: &DawState, ui: &Ui) {
 }
 
 // Piano roll / step sequencer at bottom
-fn piano_roll_panel(state: &DawState, ui: &Ui) {
+fn piano_roll_panel(state: &mut DawState, ui: &Ui) {
     area!(ui, {
         id: "piano_roll_panel",
         layout: {
@@ -541,58 +917,29 @@ fn piano_roll_panel(state: &DawState, ui: &Ui) {
         },
         background_color: rgb(30, 30, 40),
     }, |ui: &Ui| {
-        // Piano keys
-        area!(ui, {
-            id: "piano_keys",
-            layout: {
-                width: fixed!(80.0),
-                height: grow!(),
-            },
-            background_color: rgb(25, 25, 35),
-        }, |ui: &Ui| {
-            for octave in 0..4 {
-                for note in 0..12 {
-                    let is_black_key = matches!(note, 1 | 3 | 6 | 8 | 10);
-                    let key_color = if is_black_key {
-                        rgb(20, 20, 20)
-                    } else {
-                        rgb(240, 240, 240)
-                    };
-
-                    let key_index = octave * 12 + note;
-                    let id = if key_index < state.piano_key_ids.len() {
-                        &state.piano_key_ids[key_index]
-                    } else {
-                        "default_key"
-                    };
-
-                    area!(ui, {
-                        id: id,
-                        layout: {
-                            width: grow!(),
-                            height: fixed!(12.0),
-                        },
-                        background_color: key_color,
-                    }, |_ui| {});
-                }
-            }
+        // Scrolls the keyboard as it's taller than the panel
+        ui.scroll_area("piano_keys", Vec2::new(72.0, 200.0), |ui| {
+            ui.keyboard_with(
+                "piano_keyboard",
+                &mut state.held_notes,
+                Keyboard::new(4).vertical(true),
+            );
         });
 
-        // Note grid
-        area!(ui, {
-            id: "note_grid",
-            layout: {
-                width: grow!(),
-                height: grow!(),
-            },
-            background_color: rgb(35, 35, 45),
-        }, |_ui| {
-            // Grid lines and notes would be drawn here
-        });
+        // Note editor, shading the notes outside of the scale and highlighting its chord
+        let options = PianoRoll::new()
+            .scale(state.scale)
+            .chord(state.scale.chord(state.chord_degree, 3));
+        ui.piano_roll_with(
+            "note_grid",
+            &mut state.piano_roll_notes,
+            &mut state.piano_roll_view,
+            &options,
+        );
     });
 }
 
-fn impact_panel(_state: &DawState, ui: &Ui) {
+fn impact_panel(state: &mut DawState, ui: &Ui) {
     area!(ui, {
         id: "impact_panel",
         layout: {
@@ -601,37 +948,266 @@ fn impact_panel(_state: &DawState, ui: &Ui) {
             direction: LayoutDirection::TopToBottom,
         },
         background_color: rgb(50, 150, 60),
-    }, |_ui| {});
+    }, |ui| {
+        session_grid(state, ui);
+    });
 }
 
-fn mixing_panel(state: &DawState, ui: &Ui) {
+/// Clip launcher for the tracks, queued clips start on the next bar.
+fn session_grid(state: &mut DawState, ui: &Ui) {
+    let bar = 4.0 * 60.0 / state.tempo;
+    let progress = (state.timeline_position / bar).fract();
+    // The simulated playback moves 0.1 seconds a frame
+    if progress < 0.1 / bar {
+        for (playing, queued) in state.playing_clips.iter_mut().zip(&mut state.queued_clips) {
+            if let Some(scene) = queued.take() {
+                *playing = Some(scene);
+            }
+        }
+    }
+
+    let slots: Vec<Vec<ClipSlot>> = state
+        .session_clips
+        .iter()
+        .enumerate()
+        .map(|(track, clips)| {
+            clips
+                .iter()
+                .enumerate()
+                .map(|(scene, clip)| match clip {
+                    None => ClipSlot::EMPTY,
+                    Some(name) if state.playing_clips[track] == Some(scene) => {
+                        ClipSlot::new(name, ClipState::Playing).progress(progress)
+                    }
+                    Some(name) if state.queued_clips[track] == Some(scene) => {
+                        ClipSlot::new(name, ClipState::Queued)
+                    }
+                    Some(name) => ClipSlot::new(name, ClipState::Loaded),
+                })
+                .collect()
+        })
+        .collect();
+    let tracks: Vec<ClipTrack> = state
+        .tracks
+        .iter()
+        .zip(&slots)
+        .map(|(track, slots)| ClipTrack {
+            name: &track.name,
+            slots,
+        })
+        .collect();
+    let events = ui.clip_grid("session", &tracks, &SCENES);
+
+    for event in events {
+        match event {
+            ClipEvent::Launch { track, scene } => state.queued_clips[track] = Some(scene),
+            ClipEvent::Stop { track } => {
+                state.playing_clips[track] = None;
+                state.queued_clips[track] = None;
+            }
+            ClipEvent::LaunchScene { scene } => {
+                for (track, clips) in state.session_clips.iter().enumerate() {
+                    if clips[scene].is_some() {
+                        state.queued_clips[track] = Some(scene);
+                    } else {
+                        state.playing_clips[track] = None;
+                        state.queued_clips[track] = None;
+                    }
+                }
+            }
+            ClipEvent::Move { from, to } | ClipEvent::Copy { from, to } => {
+                let clip = state.session_clips[from.0][from.1].clone();
+                if matches!(event, ClipEvent::Move { .. }) {
+                    state.session_clips[from.0][from.1] = None;
+                    if state.playing_clips[from.0] == Some(from.1) {
+                        state.playing_clips[from.0] = None;
+                    }
+                }
+                state.session_clips[to.0][to.1] = clip;
+            }
+        }
+    }
+}
+
+fn mixing_panel(state: &mut DawState, ui: &Ui) {
     area!(ui, {
         id: "mixing_panel",
         layout: {
             width: grow!(),
-            height: grow!(),
+            height: fixed!(300.0),
             direction: LayoutDirection::TopToBottom,
         },
         background_color: rgb(160, 60, 70),
-    }, |_ui| {});
+    }, |ui| {
+        // Levels of the tracks, made up from the playback position while playing
+        let channels: Vec<MeterChannel> = state
+            .tracks
+            .iter()
+            .enumerate()
+            .map(|(index, track)| {
+                let level = if state.transport.playing && !track.muted {
+                    let t = state.timeline_position * (0.9 + 0.2 * index as f32) + index as f32;
+                    track.volume * (0.75 + 0.2 * t.sin() + 0.1 * (t * 2.3).sin())
+                } else {
+                    0.0
+                };
+                MeterChannel {
+                    label: ui.alloc_str(track.name.clone()),
+                    level,
+                    peak: level * 1.2,
+                }
+            })
+            .collect();
+        let sections = vec![
+            AccordionSection::new("Levels", |ui| {
+                ui.meter_bridge("track_levels", &channels);
+            })
+            .open(),
+            AccordionSection::new("Inserts", |ui| {
+                for name in ["Compressor", "Equalizer"] {
+                    ui.label(name, rgb(220, 220, 220));
+                }
+            }),
+            AccordionSection::new("Panning", |ui| {
+                for track in &mut state.tracks {
+                    let label = ui.alloc_str(format!("{} Balance", track.name));
+                    ui.pan(label, &mut track.pan);
+                }
+            }),
+            AccordionSection::new("Sends", |ui| {
+                ui.knob("Reverb Bus", &mut state.reverb_send, 0.0, 1.0);
+                ui.surround_pan(
+                    "Reverb Position",
+                    &mut state.reverb_position,
+                    SpeakerLayout::Surround,
+                );
+            }),
+        ];
+        ui.accordion("mixing_sections", true, sections);
+    });
+}
+
+fn plugin_table(ui: &Ui) {
+    let columns = [
+        Column::new("Plugin").sortable(),
+        Column::new("Vendor").width(fixed!(110.0)).sortable(),
+        Column::new("CPU").width(fixed!(60.0)).sortable(),
+    ];
+    ui.table("plugin_table", &columns, |table| {
+        let mut order: Vec<usize> = (0..PLUGINS.len()).collect();
+        if let Some(sort) = table.sort() {
+            order.sort_by(|a, b| {
+                let (a, b) = (&PLUGINS[*a], &PLUGINS[*b]);
+                let ordering = match sort.column {
+                    0 => a.0.cmp(b.0),
+                    1 => a.1.cmp(b.1),
+                    _ => a.2.total_cmp(&b.2),
+                };
+                sort.order.apply(ordering)
+            });
+        }
+        for index in order {
+            let (name, vendor, _, load) = PLUGINS[index];
+            table.row(|row| {
+                row.cell(name);
+                row.cell(vendor);
+                row.cell(load);
+            });
+        }
+    });
+}
+
+fn browser_panel(state: &mut DawState, ui: &Ui, width: f32) {
+    area!(ui, {
+        id: "browser_panel",
+        layout: {
+            width: grow!(),
+            height: fit!(),
+            padding: Padding::all(4),
+            direction: LayoutDirection::TopToBottom,
+            child_gap: 2,
+        },
+    }, |ui| {
+        ui.image(&state.artwork, Vec2::new(width - 8.0, 48.0), ImageFit::Cover);
+        ui.tree_node("Samples", |ui| {
+            ui.tree_node("Drums", |ui| {
+                let names = ["Kick.wav", "Snare.wav", "Hihat.wav"];
+                for (name, rating) in names.iter().zip(&mut state.sample_ratings) {
+                    ui.label(name, rgb(200, 200, 200));
+                    ui.rating(&format!("{}_rating", name), rating, 5);
+                }
+            });
+            ui.tree_node("Percussion", |ui| {
+                for name in ["Bongo.wav", "Conga.wav", "Cowbell.wav"] {
+                    ui.label(name, rgb(200, 200, 200));
+                }
+            });
+        });
+        ui.tree_node("Disk", |ui| {
+            let response = ui.file_tree("disk_files", &state.disk_files);
+            if let Some(name) = response.selected.as_ref().and_then(|path| path.file_name()) {
+                ui.label(&name.to_string_lossy(), rgb(140, 140, 140));
+            }
+        });
+        ui.tree_node("Plugins", |ui| {
+            for name in ["Compressor", "Equalizer", "Reverb"] {
+                ui.label(name, rgb(200, 200, 200));
+            }
+        });
+        plugin_table(ui);
+        ui.search_field("plugin_search", &mut state.plugin_query);
+        let names: Vec<&str> = PLUGINS.iter().map(|plugin| plugin.0).collect();
+        ui.filtered_list(
+            "plugin_results",
+            &names,
+            &state.plugin_query,
+            &mut state.selected_plugin,
+        );
+        ui.popup("plugins_menu", |ui| {
+            for action in ["Rescan Plugins", "Show in Folder"] {
+                if ui.button(action).clicked {
+                    ui.close_popup();
+                }
+            }
+        });
+
+        // Scrolls once the loops don't fit in the fixed height
+        area!(ui, {
+            id: "loop_list",
+            layout: {
+                width: grow!(),
+                height: fixed!(120.0),
+            },
+        }, |ui| {
+            let loops = [
+                "Amen Break", "Funky Drummer", "Think Break", "Apache", "Impeach the President",
+                "Synth Arp 120", "Bass Groove 98", "Pad Swell", "Vocal Chop A", "Vocal Chop B",
+                "Riser 8 Bars", "Downlifter",
+            ];
+            ui.list_box("loops", &loops, &mut state.selected_loops);
+        });
+    });
 }
 
-fn panels(state: &DawState, ui: &Ui) {
+fn panels(state: &mut DawState, ui: &Ui, width: f32) {
     area!(ui, {
         id: "panels",
         layout: {
-            width: fixed!(400.0),
+            width: grow!(),
             height: grow!(),
             direction: LayoutDirection::TopToBottom,
         },
         background_color: rgb(40, 40, 50),
     }, |ui| {
-        impact_panel(&state, &ui);
-        mixing_panel(&state, &ui);
+        browser_panel(state, ui, width);
+        impact_panel(state, ui);
+        ui.show_animated("mixing_panel_transition", state.mixer_visible, Transition::SlideDown, |ui| {
+            mixing_panel(state, ui);
+        });
     });
 }
 
-fn playback_toolbar(state: &DawState, ui: &Ui) {
+fn playback_toolbar(_state: &DawState, ui: &Ui) {
     area!(ui, {
         id: "playback_toolbar",
         layout: {
@@ -639,13 +1215,37 @@ fn playback_toolbar(state: &DawState, ui: &Ui) {
             height: fixed!(80.0),
         },
         background_color: rgb(40, 40, 150),
-    }, |ui| {
+    }, |_ui| {
+    });
+}
+
+fn status_bar(state: &DawState, ui: &Ui) {
+    let cpu: f32 = PLUGINS.iter().map(|plugin| plugin.2).sum();
+    let tracks = state.tracks.len();
+    ui.status_bar(|left, center, right| {
+        left.text(state.time_display_text.as_str())
+            .text(format!("{:.0} BPM", state.tempo));
+        if state.transport.recording {
+            center.text("Recording");
+        } else if state.transport.playing {
+            center.text("Playing");
+        }
+        right
+            .text(format!("{} tracks", tracks))
+            .text(format!("CPU {:.1}%", cpu));
     });
 }
 
 pub fn daw_ui(state: &mut DawState, ui: &Ui, width: f32, height: f32) {
     // Update time display (simulate time progression)
     state.timeline_position += 0.1; // Simulate time passing
+    if state.transport.looping
+        && let Some(range) = state.regions.iter().find(|region| region.name == "Loop")
+        && range.end > range.start
+        && state.timeline_position as f64 >= range.end
+    {
+        state.timeline_position = range.start as f32;
+    }
     state.update_time_display();
 
     area!(ui, {
@@ -656,6 +1256,8 @@ pub fn daw_ui(state: &mut DawState, ui: &Ui, width: f32, height: f32) {
             direction: LayoutDirection::TopToBottom,
         },
     }, |ui| {
+        menu_bar(state, ui);
+
         // Top toolbar
         toolbar(state, ui);
 
@@ -668,11 +1270,25 @@ pub fn daw_ui(state: &mut DawState, ui: &Ui, width: f32, height: f32) {
                 direction: LayoutDirection::LeftToRight,
             },
         }, |ui| {
-            // Track area (left/center)
-            panels(&state, ui);
+            // Both panes need the state but are declared one after the other
+            let mut split = state.track_split;
+            let track_width = (width * split).round();
+            let panels_width = width - track_width - DIVIDER_SIZE;
+            let state = RefCell::new(&mut *state);
+            ui.split_horizontal(
+                "main_split",
+                &mut split,
+                |ui| track_area(&mut state.borrow_mut(), ui, track_width),
+                |ui| panels(&mut state.borrow_mut(), ui, panels_width),
+            );
+            state.borrow_mut().track_split = split;
         });
 
        playback_toolbar(state, ui);
+        status_bar(state, ui);
+
+        settings_dialog(state, ui);
+        track_info_window(state, ui);
 
         // Bottom piano roll/step sequencer
         //piano_roll_panel(&state, ui);
@@ -0,0 +1,231 @@
+//! Local debug server that lets an external inspector attach to a running app.
+//!
+//! The protocol is line based: a client connects over TCP and sends a command per line, and the
+//! server answers each with a single line of JSON. `snapshot` replies with a description of the
+//! most recent frame, anything else with an `error` object. A client can keep the connection
+//! open and send `snapshot` again whenever it wants to refresh.
+//!
+//! At most [`MAX_CLIENTS`] inspectors are served at once, further connections get an error and
+//! are closed. The same goes for clients sending lines longer than [`MAX_LINE_LEN`].
+use crate::json::JsonValue;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+/// Address used when the host doesn't pick one. Only binds to loopback.
+pub const DEFAULT_ADDR: &str = "127.0.0.1:7878";
+
+/// How long a client waits for the ui thread to produce a snapshot before giving up.
+const SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Number of clients served at the same time, each on its own thread.
+pub const MAX_CLIENTS: usize = 4;
+
+/// Longest line, in bytes, accepted from a client. Commands are short so this only guards against
+/// clients streaming data without ever ending the line.
+pub const MAX_LINE_LEN: usize = 256;
+
+type SnapshotRequest = Sender<String>;
+
+/// A line sent by a client.
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    Snapshot,
+}
+
+impl Command {
+    fn parse(line: &str) -> Result<Self, String> {
+        match line.trim() {
+            "snapshot" => Ok(Command::Snapshot),
+            "" => Err("empty command".to_owned()),
+            other => Err(format!("unknown command `{}`", other)),
+        }
+    }
+}
+
+/// A single line JSON object with `message` as its `error`.
+fn error_reply(message: &str) -> String {
+    JsonValue::object([("error", JsonValue::from(message))]).to_string()
+}
+
+pub(crate) struct DebugServer {
+    requests: Receiver<SnapshotRequest>,
+    local_addr: SocketAddr,
+}
+
+impl DebugServer {
+    /// Binds `addr` and starts accepting inspector connections on a background thread.
+    pub(crate) fn start(addr: &str) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        let (request_sender, requests) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("yaui_debug_server".to_owned())
+            .spawn(move || {
+                let clients = Arc::new(AtomicUsize::new(0));
+                for mut stream in listener.incoming().flatten() {
+                    if clients.fetch_add(1, Ordering::AcqRel) >= MAX_CLIENTS {
+                        clients.fetch_sub(1, Ordering::AcqRel);
+                        let _ = writeln!(stream, "{}", error_reply("too many clients"));
+                        continue;
+                    }
+                    let request_sender = request_sender.clone();
+                    let client_count = Arc::clone(&clients);
+                    let spawned = thread::Builder::new()
+                        .name("yaui_debug_client".to_owned())
+                        .spawn(move || {
+                            handle_client(stream, request_sender);
+                            client_count.fetch_sub(1, Ordering::AcqRel);
+                        });
+                    if spawned.is_err() {
+                        clients.fetch_sub(1, Ordering::AcqRel);
+                    }
+                }
+            })?;
+
+        Ok(Self {
+            requests,
+            local_addr,
+        })
+    }
+
+    /// The address the server is listening on.
+    pub(crate) fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Returns the clients currently waiting for a snapshot. Building the snapshot isn't free so
+    /// the ui only does it when someone asked for it.
+    pub(crate) fn take_requests(&self) -> Vec<SnapshotRequest> {
+        self.requests.try_iter().collect()
+    }
+}
+
+/// Reads the next line from `reader` without buffering more than [`MAX_LINE_LEN`] bytes of it.
+/// Returns `None` once the client has closed the connection, and an `InvalidData` error for lines
+/// that are too long or not UTF-8.
+fn read_line(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = Vec::new();
+    reader
+        .take(MAX_LINE_LEN as u64 + 1)
+        .read_until(b'\n', &mut line)?;
+    if line.is_empty() {
+        return Ok(None);
+    }
+    if line.last() == Some(&b'\n') {
+        line.pop();
+    }
+    if line.len() > MAX_LINE_LEN {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "line too long"));
+    }
+    String::from_utf8(line)
+        .map(Some)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "line is not valid UTF-8"))
+}
+
+fn handle_client(stream: TcpStream, request_sender: Sender<SnapshotRequest>) {
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+
+    let mut reader = BufReader::new(stream);
+    loop {
+        let line = match read_line(&mut reader) {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(error) => {
+                // The rest of the line is still in flight, so there's no telling where the next
+                // command starts
+                if error.kind() == io::ErrorKind::InvalidData {
+                    let _ = writeln!(writer, "{}", error_reply(&error.to_string()));
+                }
+                break;
+            }
+        };
+
+        let reply = match Command::parse(&line) {
+            Ok(Command::Snapshot) => {
+                let (reply_sender, reply) = mpsc::channel();
+                if request_sender.send(reply_sender).is_err() {
+                    break;
+                }
+                reply
+                    .recv_timeout(SNAPSHOT_TIMEOUT)
+                    .unwrap_or_else(|_| error_reply("timed out waiting for a frame"))
+            }
+            Err(message) => error_reply(&message),
+        };
+
+        if writeln!(writer, "{}", reply).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let server = DebugServer::start("127.0.0.1:0").unwrap();
+        let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+        writeln!(stream, "snapshot").unwrap();
+
+        // Act as the ui thread and answer the request once it shows up
+        let mut answered = false;
+        for _ in 0..200 {
+            for request in server.take_requests() {
+                request.send(r#"{"frame":1}"#.to_owned()).unwrap();
+                answered = true;
+            }
+            if answered {
+                break;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert!(answered);
+
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line).unwrap();
+        assert_eq!(line.trim_end(), r#"{"frame":1}"#);
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(Command::parse("snapshot\r"), Ok(Command::Snapshot));
+        assert_eq!(
+            Command::parse("frame"),
+            Err("unknown command `frame`".to_owned())
+        );
+        assert!(Command::parse("  ").is_err());
+        assert_eq!(error_reply("empty command"), r#"{"error":"empty command"}"#);
+    }
+
+    #[test]
+    fn test_read_line_is_bounded() {
+        let mut input = io::Cursor::new(b"snapshot\r\nlast".to_vec());
+        assert_eq!(
+            read_line(&mut input).unwrap().as_deref(),
+            Some("snapshot\r")
+        );
+        assert_eq!(read_line(&mut input).unwrap().as_deref(), Some("last"));
+        assert_eq!(read_line(&mut input).unwrap(), None);
+
+        let mut input = io::Cursor::new(vec![b'a'; MAX_LINE_LEN * 4]);
+        let error = read_line(&mut input).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(input.position(), MAX_LINE_LEN as u64 + 1);
+
+        let mut input = io::Cursor::new(format!("{}\n", "a".repeat(MAX_LINE_LEN)));
+        assert_eq!(
+            read_line(&mut input).unwrap().map(|line| line.len()),
+            Some(MAX_LINE_LEN)
+        );
+    }
+}
@@ -0,0 +1,276 @@
+//! Faders, the sliding level controls of mixer channel strips.
+//!
+//! [`Ui::fader`] moves its cap with the mouse by as much as the mouse moves, so grabbing it
+//! never makes the value jump. Pressing the track away from the cap moves the cap there first.
+//! With [`FaderTaper::Decibels`] the cap moves evenly in decibels instead of gain, which gives
+//! the quiet end of the range the room it needs.
+use crate::input::MouseButton;
+use crate::style::WidgetState;
+use crate::ui::{Ui, apply_style, rgb};
+use crate::value_drag::{DragAxis, round_to};
+use clay_layout::elements::{FloatingAttachToElement, FloatingClipToElement};
+use clay_layout::layout::{Alignment, LayoutAlignmentX, LayoutAlignmentY, LayoutDirection};
+use clay_layout::math::Vector2;
+use clay_layout::{Declaration, fit, fixed};
+
+/// Length of the track the cap slides along.
+const TRACK_LENGTH: f32 = 140.0;
+/// Thickness of the fader across the track.
+const THICKNESS: f32 = 24.0;
+/// Length of the cap along the track.
+const CAP_LENGTH: f32 = 12.0;
+/// Level at the bottom of a decibel fader short of silence, which is at its very end.
+const MIN_DB: f32 = -60.0;
+/// Level at the top of a decibel fader.
+const MAX_DB: f32 = 6.0;
+
+/// How the position of the cap maps to the value of a [`Fader`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FaderTaper {
+    /// The value goes from 0.0 to 1.0 along the track.
+    #[default]
+    Linear,
+    /// The value is a gain going from silence up to +6 dB, evenly spaced in decibels.
+    Decibels,
+}
+
+/// Options for [`Ui::fader_with`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fader {
+    pub vertical: bool,
+    pub taper: FaderTaper,
+}
+
+impl Fader {
+    pub fn new(vertical: bool) -> Self {
+        Self {
+            vertical,
+            taper: FaderTaper::default(),
+        }
+    }
+
+    pub fn taper(mut self, taper: FaderTaper) -> Self {
+        self.taper = taper;
+        self
+    }
+}
+
+/// Value of a fader with its cap at `position`, from 0.0 at the start of the track to 1.0.
+fn position_to_value(position: f32, taper: FaderTaper) -> f32 {
+    let position = position.clamp(0.0, 1.0);
+    match taper {
+        FaderTaper::Linear => position,
+        FaderTaper::Decibels if position == 0.0 => 0.0,
+        FaderTaper::Decibels => db_to_gain(MIN_DB + position * (MAX_DB - MIN_DB)),
+    }
+}
+
+/// Position of the cap for `value`, the inverse of [`position_to_value`].
+fn value_to_position(value: f32, taper: FaderTaper) -> f32 {
+    match taper {
+        FaderTaper::Linear => value.clamp(0.0, 1.0),
+        FaderTaper::Decibels if value <= 0.0 => 0.0,
+        FaderTaper::Decibels => ((gain_to_db(value) - MIN_DB) / (MAX_DB - MIN_DB)).clamp(0.0, 1.0),
+    }
+}
+
+fn db_to_gain(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+fn gain_to_db(gain: f32) -> f32 {
+    20.0 * gain.log10()
+}
+
+/// `value` moved to the closest step while stepping is held: a tenth of the range, or a whole
+/// decibel with [`FaderTaper::Decibels`].
+fn step_value(value: f32, taper: FaderTaper) -> f32 {
+    match taper {
+        FaderTaper::Linear => round_to(value, 0.1),
+        FaderTaper::Decibels if value <= 0.0 => 0.0,
+        FaderTaper::Decibels => db_to_gain(gain_to_db(value).round()),
+    }
+}
+
+/// `value` as shown while the fader is dragged.
+fn format_value(value: f32, taper: FaderTaper) -> String {
+    match taper {
+        FaderTaper::Linear => format!("{:.2}", value),
+        FaderTaper::Decibels if value <= 0.0 => "-inf dB".to_string(),
+        FaderTaper::Decibels => format!("{:.1} dB", gain_to_db(value)),
+    }
+}
+
+impl Ui {
+    /// A fader for `value` from 0.0 to 1.0, labeled with `label`, along a vertical or
+    /// horizontal track. Holding shift moves the cap slower than the mouse and holding Ctrl
+    /// moves it in steps of 0.1, or whole decibels with [`FaderTaper::Decibels`], see
+    /// [`crate::value_drag::DragConfig`]. Returns true when the value changed this frame.
+    pub fn fader(&self, label: &str, value: &mut f32, vertical: bool) -> bool {
+        self.fader_with(label, value, Fader::new(vertical))
+    }
+
+    /// [`Ui::fader`] with options, for example a volume fader going by decibels with
+    /// `ui.fader_with("Volume", &mut gain, Fader::new(true).taper(FaderTaper::Decibels))`.
+    ///
+    /// The track is styled by the `fader` rule and the cap by `fader_cap`, with `:hover` and
+    /// `:active` while dragged.
+    pub fn fader_with(&self, label: &str, value: &mut f32, options: Fader) -> bool {
+        let old_value = *value;
+        let id = self.id(label);
+        let cap_id = self.id_index(label, 1);
+        let input = self.input();
+        let travel = TRACK_LENGTH - CAP_LENGTH;
+
+        let held = input.button_down(MouseButton::Left) && input.pressed_id == Some(id.id.id);
+        let held_cap =
+            input.button_down(MouseButton::Left) && input.pressed_id == Some(cap_id.id.id);
+        let axis = if options.vertical {
+            DragAxis::Vertical
+        } else {
+            DragAxis::Horizontal
+        };
+        let drag = self.value_drag(id, held || held_cap, *value, axis);
+        if let Some(drag) = drag {
+            let position = match self.last_frame_rect(id) {
+                Some(rect) if held && input.button_pressed(MouseButton::Left) => {
+                    // Centers the cap on where the track was pressed
+                    let offset = if options.vertical {
+                        rect.w - input.mouse_pos.y
+                    } else {
+                        input.mouse_pos.x - rect.x
+                    };
+                    (offset - CAP_LENGTH * 0.5) / travel
+                }
+                _ => value_to_position(drag.value, options.taper) + drag.delta / travel,
+            };
+            let moved = position_to_value(position, options.taper);
+            *value = self.drag_to(id, moved, |value| step_value(value, options.taper));
+        }
+        let position = value_to_position(*value, options.taper);
+
+        let hovered = self
+            .last_frame_rect(cap_id)
+            .is_some_and(|rect| input.is_hovered(rect));
+        let active = held || held_cap;
+        let track_style = self.widget_style("fader", WidgetState::default());
+        let cap_style = self.widget_style(
+            "fader_cap",
+            WidgetState {
+                hovered,
+                active,
+                ..Default::default()
+            },
+        );
+        let color = track_style.text_color.unwrap_or(rgb(220, 220, 220));
+
+        let (width, height) = if options.vertical {
+            (THICKNESS, TRACK_LENGTH)
+        } else {
+            (TRACK_LENGTH, THICKNESS)
+        };
+        let offset = (position * travel).round();
+        let cap_pos = if options.vertical {
+            Vector2::new(0.0, travel - offset)
+        } else {
+            Vector2::new(offset, 0.0)
+        };
+
+        let mut decl = Declaration::new();
+        decl.layout()
+            .width(fit!())
+            .height(fit!())
+            .child_gap(4)
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Center,
+                LayoutAlignmentY::Center,
+            ))
+            .direction(if options.vertical {
+                LayoutDirection::TopToBottom
+            } else {
+                LayoutDirection::LeftToRight
+            })
+            .end();
+
+        let mut track = Declaration::new();
+        track
+            .id(id)
+            .layout()
+            .width(fixed!(width))
+            .height(fixed!(height))
+            .end();
+        apply_style(&mut track, &track_style);
+
+        let mut cap = Declaration::new();
+        cap.id(cap_id)
+            .layout()
+            .width(fixed!(if options.vertical { width } else { CAP_LENGTH }))
+            .height(fixed!(if options.vertical { CAP_LENGTH } else { height }))
+            .end()
+            .floating()
+            .attach_to(FloatingAttachToElement::Parent)
+            .clip_to(FloatingClipToElement::AttachedParent)
+            .offset(cap_pos)
+            .end();
+        apply_style(&mut cap, &cap_style);
+
+        self.with_layout(&decl, |ui| {
+            ui.with_layout(&track, |ui| ui.with_layout(&cap, |_| {}));
+            ui.text_element(label, color);
+        });
+        if let Some(drag) = drag {
+            let (delta, precision) = match options.taper {
+                FaderTaper::Linear => (*value - drag.start, 2),
+                FaderTaper::Decibels => (gain_to_db(*value) - gain_to_db(drag.start), 1),
+            };
+            self.drag_readout(id, &format_value(*value, options.taper), delta, precision);
+        }
+        *value != old_value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_taper() {
+        assert_eq!(position_to_value(0.25, FaderTaper::Linear), 0.25);
+        assert_eq!(position_to_value(1.5, FaderTaper::Linear), 1.0);
+
+        // The ends are silence and +6 dB, unity gain sits well up the track
+        assert_eq!(position_to_value(0.0, FaderTaper::Decibels), 0.0);
+        assert_near(
+            position_to_value(1.0, FaderTaper::Decibels),
+            db_to_gain(6.0),
+        );
+        let unity = value_to_position(1.0, FaderTaper::Decibels);
+        assert_near(unity, 60.0 / 66.0);
+        assert_eq!(value_to_position(0.0, FaderTaper::Decibels), 0.0);
+
+        // Stepping goes by whole decibels and keeps silence
+        assert_near(
+            step_value(db_to_gain(-6.3), FaderTaper::Decibels),
+            db_to_gain(-6.0),
+        );
+        assert_eq!(step_value(0.0, FaderTaper::Decibels), 0.0);
+
+        for position in [0.1, 0.5, 0.9] {
+            for taper in [FaderTaper::Linear, FaderTaper::Decibels] {
+                let value = position_to_value(position, taper);
+                assert_near(value_to_position(value, taper), position);
+            }
+        }
+    }
+
+    #[test]
+    fn test_format_value() {
+        assert_eq!(format_value(0.5, FaderTaper::Linear), "0.50");
+        assert_eq!(format_value(1.0, FaderTaper::Decibels), "0.0 dB");
+        assert_eq!(format_value(0.0, FaderTaper::Decibels), "-inf dB");
+    }
+}
@@ -0,0 +1,504 @@
+//! A file tree browser, for picking samples and projects from disk.
+//!
+//! [`Ui::file_tree`] shows the directory of an [`FsModel`] as an expandable tree. Directories
+//! are listed on the background workers the first time they're opened, so a slow or large one
+//! doesn't stall the ui, and show a loading row until the listing is there. Listings are kept
+//! by the ui, the expanded directories and the selection by the tree.
+use crate::style::WidgetState;
+use crate::ui::{Ui, apply_style, rgb};
+use background_worker::{
+    AnySend, BoxAnySend, CallbackError, TryRecvError, WorkHandle, WorkSystem, WorkerResult,
+};
+use clay_layout::color::Color as ClayColor;
+use clay_layout::id::Id;
+use clay_layout::layout::{Alignment, LayoutAlignmentX, LayoutAlignmentY, LayoutDirection};
+use clay_layout::{Declaration, fit, fixed, grow};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Number of directory listings handed to the workers per frame.
+const LISTINGS_PER_FRAME: usize = 4;
+
+/// The directory a [`Ui::file_tree`] shows and which of its entries are listed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FsModel {
+    root: PathBuf,
+    show_hidden: bool,
+    extensions: Vec<String>,
+}
+
+impl FsModel {
+    /// Shows the contents of `root`, without hidden entries.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            show_hidden: false,
+            extensions: Vec::new(),
+        }
+    }
+
+    /// Also lists entries starting with a dot.
+    pub fn show_hidden(mut self, show: bool) -> Self {
+        self.show_hidden = show;
+        self
+    }
+
+    /// Only lists the files with one of `extensions`, compared without case. Directories are
+    /// always listed.
+    pub fn extensions(mut self, extensions: &[&str]) -> Self {
+        self.extensions = extensions.iter().map(|ext| ext.to_lowercase()).collect();
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn shows(&self, entry: &FsEntry) -> bool {
+        if !self.show_hidden && entry.name.starts_with('.') {
+            return false;
+        }
+        if entry.is_dir || self.extensions.is_empty() {
+            return true;
+        }
+        entry
+            .path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| self.extensions.contains(&ext.to_lowercase()))
+    }
+}
+
+/// An entry of a listed directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FsEntry {
+    pub(crate) name: String,
+    pub(crate) path: PathBuf,
+    pub(crate) is_dir: bool,
+}
+
+/// What [`Ui::file_tree`] reports after a frame.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FileTreeResponse {
+    /// Entry selected by clicking it, kept across frames.
+    pub selected: Option<PathBuf>,
+    /// A different entry was selected this frame.
+    pub selection_changed: bool,
+    /// An opened directory is still being listed.
+    pub loading: bool,
+}
+
+/// Expanded directories and selection of a file tree, kept by the ui across frames.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct FileTreeState {
+    expanded: HashSet<PathBuf>,
+    selected: Option<PathBuf>,
+}
+
+/// The entries of `path`, directories first and each sorted by name without case.
+fn list_directory(path: &Path) -> std::io::Result<Vec<FsEntry>> {
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let path = entry.path();
+        entries.push(FsEntry {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            // Follows links, so a link to a directory can be opened
+            is_dir: path.is_dir(),
+            path,
+        });
+    }
+    entries.sort_by_cached_key(|entry| (!entry.is_dir, entry.name.to_lowercase()));
+    Ok(entries)
+}
+
+fn job_list_directory(data: BoxAnySend, _state: Arc<Mutex<AnySend>>) -> WorkerResult {
+    let path = data.downcast::<PathBuf>().unwrap();
+    match list_directory(&path) {
+        Ok(entries) => Ok(Box::new(entries)),
+        Err(e) => Err(CallbackError::Other(e.to_string())),
+    }
+}
+
+enum Listing {
+    Queued,
+    Loading(WorkHandle),
+    Listed(Vec<FsEntry>),
+    Failed(String),
+}
+
+/// Lists directories on the background workers and keeps the listings by path.
+pub(crate) struct DirectoryCache {
+    listings: HashMap<PathBuf, Listing>,
+    queue: VecDeque<PathBuf>,
+    list_directory_async_id: usize,
+}
+
+impl DirectoryCache {
+    pub(crate) fn new(bg_worker: &WorkSystem) -> Self {
+        let state: Arc<Mutex<AnySend>> = Arc::new(Mutex::new(()));
+        let list_directory_async_id =
+            bg_worker.register_callback_with_state(job_list_directory, state);
+
+        Self {
+            listings: HashMap::new(),
+            queue: VecDeque::new(),
+            list_directory_async_id,
+        }
+    }
+
+    /// The entries of `path`, `Ok(None)` while it's being listed. Requests the listing the
+    /// first time it's asked for.
+    pub(crate) fn get(&mut self, path: &Path) -> Result<Option<&[FsEntry]>, &str> {
+        if !self.listings.contains_key(path) {
+            self.listings.insert(path.to_path_buf(), Listing::Queued);
+            self.queue.push_back(path.to_path_buf());
+        }
+        match &self.listings[path] {
+            Listing::Queued | Listing::Loading(_) => Ok(None),
+            Listing::Listed(entries) => Ok(Some(entries)),
+            Listing::Failed(error) => Err(error),
+        }
+    }
+
+    /// Collects finished listings and submits queued ones.
+    pub(crate) fn update(&mut self, bg_worker: &WorkSystem) {
+        for listing in self.listings.values_mut() {
+            let Listing::Loading(receiver) = listing else {
+                continue;
+            };
+
+            *listing = match receiver.try_recv() {
+                Ok(Ok(data)) => Listing::Listed(*data.downcast::<Vec<FsEntry>>().unwrap()),
+                Ok(Err(e)) => Listing::Failed(e.to_string()),
+                Err(TryRecvError::Empty) => continue,
+                Err(TryRecvError::Disconnected) => {
+                    Listing::Failed("directory lister stopped".to_string())
+                }
+            };
+        }
+
+        for _ in 0..LISTINGS_PER_FRAME {
+            let Some(path) = self.queue.pop_front() else {
+                break;
+            };
+
+            let receiver = bg_worker.add_work(self.list_directory_async_id, path.clone());
+            self.listings.insert(path, Listing::Loading(receiver));
+        }
+    }
+
+    /// Forgets all listings, so directories are listed again the next time they're shown.
+    pub(crate) fn clear(&mut self) {
+        self.listings.clear();
+        self.queue.clear();
+    }
+
+    /// Queued or being listed.
+    pub(crate) fn loading(&self) -> usize {
+        self.listings
+            .values()
+            .filter(|listing| matches!(listing, Listing::Queued | Listing::Loading(_)))
+            .count()
+    }
+}
+
+impl Ui {
+    /// Shows the directory of `model` as a tree. Clicking a directory opens or closes it and
+    /// clicking any entry selects it, see [`FileTreeResponse`]. Directories are listed in the
+    /// background the first time they're opened.
+    ///
+    /// Rows are styled by the `file_tree_row` rules, with the `selected` class for the selected
+    /// one. Icons take the text color of `file_tree_icon`, or `file_tree_icon.folder` for
+    /// directories.
+    pub fn file_tree(&self, id: &str, model: &FsModel) -> FileTreeResponse {
+        let tree_id = self.id(id);
+        let mut state = self.file_trees().remove(&tree_id.id.id).unwrap_or_default();
+        let mut response = FileTreeResponse::default();
+
+        let mut decl = Declaration::new();
+        decl.id(tree_id)
+            .layout()
+            .width(grow!())
+            .height(fit!())
+            .direction(LayoutDirection::TopToBottom)
+            .end();
+        self.with_layout(&decl, |ui| {
+            ui.file_tree_dir(id, model, &model.root, 0, &mut state, &mut response);
+        });
+
+        response.selection_changed = response.selected.is_some();
+        if let Some(selected) = response.selected.take() {
+            state.selected = Some(selected);
+        }
+        response.selected = state.selected.clone();
+        self.file_trees().insert(tree_id.id.id, state);
+        response
+    }
+
+    /// Forgets the directory listings of all file trees, so changes on disk show.
+    pub fn refresh_file_trees(&self) {
+        self.directories().clear();
+    }
+
+    /// The rows of the entries of `dir` and of its expanded subdirectories, `depth` levels in.
+    /// Entries clicked this frame are selected into `response`.
+    fn file_tree_dir(
+        &self,
+        id: &str,
+        model: &FsModel,
+        dir: &Path,
+        depth: u16,
+        state: &mut FileTreeState,
+        response: &mut FileTreeResponse,
+    ) {
+        let entries = match self.directories().get(dir) {
+            Ok(Some(entries)) => entries.to_vec(),
+            Ok(None) => {
+                response.loading = true;
+                self.file_tree_message(depth, "Loading...");
+                return;
+            }
+            Err(error) => {
+                let error = self.alloc_str(error.to_string());
+                self.file_tree_message(depth, error);
+                return;
+            }
+        };
+
+        for entry in entries.iter().filter(|entry| model.shows(entry)) {
+            let row_id = self.id(&format!("{}/{}", id, entry.path.display()));
+            if self.item_state(row_id).was_clicked {
+                if entry.is_dir && !state.expanded.remove(&entry.path) {
+                    state.expanded.insert(entry.path.clone());
+                }
+                if state.selected.as_ref() != Some(&entry.path) {
+                    response.selected = Some(entry.path.clone());
+                }
+            }
+            let open = state.expanded.contains(&entry.path);
+            let selected = response.selected.as_ref().or(state.selected.as_ref());
+            self.file_tree_row(row_id, entry, depth, open, selected == Some(&entry.path));
+            if open {
+                self.file_tree_dir(id, model, &entry.path, depth + 1, state, response);
+            }
+        }
+    }
+
+    /// The row of `entry`, indented by `depth`.
+    fn file_tree_row(&self, row_id: Id, entry: &FsEntry, depth: u16, open: bool, selected: bool) {
+        let hovered = self
+            .last_frame_rect(row_id)
+            .is_some_and(|rect| self.input().is_hovered(rect));
+        if selected {
+            self.push_pending_class("selected");
+        }
+        let style = self.widget_style(
+            "file_tree_row",
+            WidgetState {
+                hovered,
+                ..Default::default()
+            },
+        );
+        let text_color = style.text_color.unwrap_or(rgb(220, 220, 220));
+        let arrow_color = self.file_icon_color(false);
+        let icon_size = (self.font_size() as f32 * 0.5).round();
+
+        let mut decl = Declaration::new();
+        decl.id(row_id)
+            .layout()
+            .width(grow!())
+            .height(fit!())
+            .child_gap(6)
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Left,
+                LayoutAlignmentY::Center,
+            ))
+            .direction(LayoutDirection::LeftToRight)
+            .end();
+        apply_style(&mut decl, &style);
+
+        let mut spacer = Declaration::new();
+        spacer
+            .layout()
+            .width(fixed!(icon_size))
+            .height(fixed!(icon_size))
+            .end();
+
+        self.with_layout(&decl, |ui| {
+            ui.file_tree_indent(depth, 0.0);
+            if entry.is_dir {
+                ui.expand_icon(open, false, arrow_color);
+            } else {
+                ui.with_layout(&spacer, |_| {});
+            }
+            ui.file_icon(entry.is_dir);
+            ui.text_element(ui.alloc_str(entry.name.clone()), text_color);
+        });
+    }
+
+    /// A row without an entry, for directories being listed or that can't be read.
+    fn file_tree_message(&self, depth: u16, message: &str) {
+        let style = self.widget_style("file_tree_row", WidgetState::default());
+        let color = style.text_color.unwrap_or(rgb(220, 220, 220));
+        // Lined up with the names of the entries, past the arrow, the icon and the gaps
+        let font_size = self.font_size() as f32;
+        let skip = (font_size * 0.5).round() + (font_size * 0.9).round() + 12.0;
+
+        let mut decl = Declaration::new();
+        decl.layout()
+            .width(grow!())
+            .height(fit!())
+            .direction(LayoutDirection::LeftToRight)
+            .end();
+        apply_style(&mut decl, &style);
+        self.with_layout(&decl, |ui| {
+            ui.file_tree_indent(depth, skip);
+            ui.text_element(message, color);
+        });
+    }
+
+    /// Space in front of the rows `depth` levels in, plus `extra`.
+    fn file_tree_indent(&self, depth: u16, extra: f32) {
+        let width = (self.font_size() as f32 * 0.8).round() * depth as f32 + extra;
+        if width > 0.0 {
+            let mut decl = Declaration::new();
+            decl.layout().width(fixed!(width)).height(fixed!(1.0)).end();
+            self.with_layout(&decl, |_| {});
+        }
+    }
+
+    /// Color of the folder icons if `folder`, of the file icons and arrows otherwise.
+    fn file_icon_color(&self, folder: bool) -> ClayColor {
+        if folder {
+            self.push_pending_class("folder");
+        }
+        let style = self.widget_style("file_tree_icon", WidgetState::default());
+        style.text_color.unwrap_or(if folder {
+            rgb(220, 180, 90)
+        } else {
+            rgb(170, 170, 170)
+        })
+    }
+
+    /// A folder or a page, drawn from rectangles.
+    fn file_icon(&self, folder: bool) {
+        let color = self.file_icon_color(folder);
+        let size = (self.font_size() as f32 * 0.9).round();
+
+        let rect = |width: f32, height: f32, color: ClayColor| {
+            let mut decl = Declaration::new();
+            decl.layout()
+                .width(fixed!(width.round()))
+                .height(fixed!(height.round()))
+                .end()
+                .background_color(color);
+            decl
+        };
+
+        let mut decl = Declaration::new();
+        decl.layout()
+            .width(fixed!(size))
+            .height(fixed!(size))
+            .direction(LayoutDirection::TopToBottom)
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Left,
+                LayoutAlignmentY::Center,
+            ))
+            .end();
+        self.with_layout(&decl, |ui| {
+            if folder {
+                // A tab on top of the body
+                ui.with_layout(&rect(size * 0.45, size * 0.15, color), |_| {});
+                ui.with_layout(&rect(size, size * 0.6, color), |_| {});
+            } else {
+                let mut page = Declaration::new();
+                page.layout()
+                    .width(fixed!(size))
+                    .height(fixed!(size))
+                    .child_alignment(Alignment::new(
+                        LayoutAlignmentX::Center,
+                        LayoutAlignmentY::Center,
+                    ))
+                    .end();
+                ui.with_layout(&page, |ui| {
+                    ui.with_layout(&rect(size * 0.7, size, color), |_| {});
+                });
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_directory() {
+        let dir = std::env::temp_dir().join(format!("yaui_file_tree_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("Snares")).unwrap();
+        std::fs::create_dir_all(dir.join("drums")).unwrap();
+        for file in ["kick.WAV", "b.txt", ".hidden.wav"] {
+            std::fs::write(dir.join(file), []).unwrap();
+        }
+
+        let entries = list_directory(&dir).unwrap();
+        let names: Vec<_> = entries.iter().map(|entry| entry.name.as_str()).collect();
+        // Directories first, then by name without case
+        assert_eq!(
+            names,
+            ["drums", "Snares", ".hidden.wav", "b.txt", "kick.WAV"]
+        );
+
+        let shown = |model: &FsModel| -> Vec<_> {
+            entries
+                .iter()
+                .filter(|entry| model.shows(entry))
+                .map(|entry| entry.name.as_str())
+                .collect()
+        };
+        let model = FsModel::new(&dir);
+        assert_eq!(shown(&model), ["drums", "Snares", "b.txt", "kick.WAV"]);
+        let model = model.extensions(&["wav"]);
+        assert_eq!(shown(&model), ["drums", "Snares", "kick.WAV"]);
+        let model = model.show_hidden(true);
+        assert_eq!(
+            shown(&model),
+            ["drums", "Snares", ".hidden.wav", "kick.WAV"]
+        );
+
+        assert!(list_directory(&dir.join("missing")).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_directory_cache() {
+        let dir = std::env::temp_dir().join(format!("yaui_dir_cache_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("loops")).unwrap();
+
+        let worker = WorkSystem::new(1);
+        let mut cache = DirectoryCache::new(&worker);
+        let missing = dir.join("missing");
+        assert_eq!(cache.get(&dir), Ok(None));
+        assert_eq!(cache.get(&missing), Ok(None));
+        assert_eq!(cache.loading(), 2);
+
+        for _ in 0..500 {
+            cache.update(&worker);
+            if cache.loading() == 0 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+
+        let listed = cache.get(&dir).unwrap().unwrap();
+        assert_eq!(listed.len(), 1);
+        assert!(listed[0].is_dir);
+        assert!(cache.get(&missing).is_err());
+
+        cache.clear();
+        assert_eq!(cache.get(&dir), Ok(None));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,45 @@
+//! Tracks a file on disk so hot-reloadable resources can pick up edits while the app runs.
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+pub(crate) struct WatchedFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl WatchedFile {
+    pub(crate) fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            modified: None,
+        }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads the file and remembers its modification time.
+    pub(crate) fn read(&mut self) -> io::Result<String> {
+        self.modified = std::fs::metadata(&self.path)?.modified().ok();
+        std::fs::read_to_string(&self.path)
+    }
+
+    /// Returns the new contents if the file changed since it was last read.
+    pub(crate) fn read_if_changed(&mut self) -> Option<io::Result<String>> {
+        let modified = std::fs::metadata(&self.path)
+            .and_then(|m| m.modified())
+            .ok();
+        if modified.is_none() || modified == self.modified {
+            return None;
+        }
+
+        let result = self.read();
+        if result.is_err() {
+            // The file may be mid-save, try again next time
+            self.modified = None;
+        }
+        Some(result)
+    }
+}
@@ -0,0 +1,349 @@
+//! Keyboard focus traversal, focus scopes and focus traps.
+//!
+//! Widgets that can take keyboard focus register themselves while they're declared, and Tab
+//! and Shift+Tab move the focus through them in declaration order once the frame is done.
+//!
+//! A focus scope groups the widgets of a panel or window and remembers which of them was
+//! focused last, so focus moved into the scope with [`Ui::focus_into`] lands back there. The
+//! first time, or when that widget is gone, it goes to the widget marked with
+//! [`Ui::focus_default`] instead.
+//!
+//! Popups and modals are scopes that also trap the focus: the focus moves into them when they
+//! open, while one is open Tab only cycles through the widgets inside the top-most one, and
+//! when it closes the focus goes back to the widget that had it when it opened.
+use crate::input::Key;
+use crate::ui::Ui;
+use clay_layout::id::Id;
+use std::collections::HashMap;
+
+struct Trap {
+    id: u32,
+    /// Focused when the trap opened, gets the focus back when it closes.
+    restore: Option<Id>,
+}
+
+/// What a scope keeps between frames.
+#[derive(Default, Clone, Copy)]
+struct ScopeMemory {
+    /// Widget inside the scope that had the focus last.
+    last: Option<Id>,
+    /// Widget set with [`Ui::focus_default`].
+    default: Option<Id>,
+}
+
+/// A scope declared this frame.
+struct FrameScope {
+    id: u32,
+    /// Index of the scope around this one in [`FocusState::frame_scopes`].
+    parent: Option<usize>,
+    trap: bool,
+}
+
+#[derive(Default)]
+pub(crate) struct FocusState {
+    /// Open traps, innermost last.
+    traps: Vec<Trap>,
+    scopes: HashMap<u32, ScopeMemory>,
+    /// Scopes declared this frame in declaration order.
+    frame_scopes: Vec<FrameScope>,
+    /// Scopes being declared around the current element, as indices into `frame_scopes`.
+    declaring: Vec<usize>,
+    /// Focusable widgets of this frame in declaration order, with the innermost scope each
+    /// one is in.
+    focusables: Vec<(Id, Option<usize>)>,
+    /// Scopes the focus moves into once the frame is done.
+    entering: Vec<u32>,
+}
+
+impl FocusState {
+    /// Forgets traps that weren't declared this frame and returns where the focus goes back
+    /// to if any closed.
+    fn close_traps(&mut self) -> Option<Option<Id>> {
+        let closed = self.traps.iter().position(|trap| {
+            !self
+                .frame_scopes
+                .iter()
+                .any(|scope| scope.trap && scope.id == trap.id)
+        })?;
+        let restore = self.traps[closed].restore;
+        self.traps.truncate(closed);
+        Some(restore)
+    }
+
+    /// Ids of the scopes around the frame scope at `index`, innermost first.
+    fn chain(&self, mut index: Option<usize>) -> impl Iterator<Item = &FrameScope> {
+        std::iter::from_fn(move || {
+            let scope = &self.frame_scopes[index?];
+            index = scope.parent;
+            Some(scope)
+        })
+    }
+
+    /// Innermost trap around the frame scope at `index`.
+    fn trap_of(&self, index: Option<usize>) -> Option<u32> {
+        self.chain(index)
+            .find(|scope| scope.trap)
+            .map(|scope| scope.id)
+    }
+
+    /// Whether `id` was registered this frame inside the scope `scope`.
+    fn contains(&self, scope: u32, id: Id) -> bool {
+        self.focusables.iter().any(|(focusable, index)| {
+            focusable.id.id == id.id.id && self.chain(*index).any(|outer| outer.id == scope)
+        })
+    }
+
+    /// Where the focus goes when it moves into `scope`: the widget focused last if it's still
+    /// there, otherwise the default one.
+    fn target(&self, scope: u32) -> Option<Id> {
+        let memory = self.scopes.get(&scope)?;
+        [memory.last, memory.default]
+            .into_iter()
+            .flatten()
+            .find(|id| self.contains(scope, *id))
+    }
+
+    /// Lets every scope around the focused widget remember it.
+    fn remember(&mut self, focus: Id) {
+        let Some(index) = self
+            .focusables
+            .iter()
+            .find(|(id, _)| id.id.id == focus.id.id)
+            .map(|(_, index)| *index)
+        else {
+            return;
+        };
+        let ids: Vec<u32> = self.chain(index).map(|scope| scope.id).collect();
+        for id in ids {
+            self.scopes.entry(id).or_default().last = Some(focus);
+        }
+    }
+
+    /// The widget after `focus` among those in the top-most trap, or outside of all traps when
+    /// none is open. Wraps around at the ends.
+    fn next(&self, focus: Option<Id>, backwards: bool) -> Option<Id> {
+        let layer = self.traps.last().map(|trap| trap.id);
+        let candidates: Vec<Id> = self
+            .focusables
+            .iter()
+            .filter(|(_, index)| self.trap_of(*index) == layer)
+            .map(|(id, _)| *id)
+            .collect();
+        let current =
+            focus.and_then(|focus| candidates.iter().position(|id| id.id.id == focus.id.id));
+        step(candidates.len(), current, backwards).map(|index| candidates[index])
+    }
+
+    /// Forgets what was declared in a frame that was given up, see [`Ui::end`].
+    pub(crate) fn discard_frame(&mut self) {
+        self.frame_scopes.clear();
+        self.declaring.clear();
+        self.focusables.clear();
+    }
+}
+
+/// Index Tab moves to from `current` among `len` widgets.
+fn step(len: usize, current: Option<usize>, backwards: bool) -> Option<usize> {
+    let last = len.checked_sub(1)?;
+    Some(match (current, backwards) {
+        (None, false) => 0,
+        (None, true) => last,
+        (Some(index), false) if index >= last => 0,
+        (Some(index), false) => index + 1,
+        (Some(0), true) => last,
+        (Some(index), true) => index - 1,
+    })
+}
+
+impl Ui {
+    /// Declares `f` inside the focus scope `id`, which remembers the widget in it that had the
+    /// focus last. See [`Ui::focus_into`].
+    pub fn focus_scope(&self, id: &str, f: impl FnOnce(&Ui)) {
+        self.declare_scope(self.id(id).id.id, false, f);
+    }
+
+    /// Makes the widget `id` the one that gets the focus when it moves into the scope or
+    /// dialog being declared, until another widget in it has been focused.
+    pub fn focus_default(&self, id: &str) {
+        let id = self.id(id);
+        let focus = self.focus_state();
+        if let Some(index) = focus.declaring.last() {
+            let scope = focus.frame_scopes[*index].id;
+            focus.scopes.entry(scope).or_default().default = Some(id);
+        }
+    }
+
+    /// Moves the focus into the scope `id` once the frame is done: to the widget in it that
+    /// had the focus last, the default one, or otherwise the first one in it.
+    pub fn focus_into(&self, id: &str) {
+        let id = self.id(id);
+        self.focus_state().entering.push(id.id.id);
+    }
+
+    /// Declares `f` inside the focus trap `id`. The focus is taken from the widget outside
+    /// the first time the trap is declared, moves into the trap once the frame is done and is
+    /// given back once the trap isn't declared anymore.
+    pub(crate) fn focus_trap(&self, id: Id, f: impl FnOnce(&Ui)) {
+        let focus = self.focus_state();
+        if !focus.traps.iter().any(|trap| trap.id == id.id.id) {
+            let restore = self.focus_id();
+            focus.traps.push(Trap {
+                id: id.id.id,
+                restore,
+            });
+            focus.entering.push(id.id.id);
+            self.clear_focus();
+        }
+        self.declare_scope(id.id.id, true, f);
+    }
+
+    fn declare_scope(&self, id: u32, trap: bool, f: impl FnOnce(&Ui)) {
+        let focus = self.focus_state();
+        let parent = focus.declaring.last().copied();
+        focus.frame_scopes.push(FrameScope { id, parent, trap });
+        focus.declaring.push(focus.frame_scopes.len() - 1);
+        f(self);
+        self.focus_state().declaring.pop();
+    }
+
+    /// Lets Tab move the focus to `id`. Called by widgets that handle keyboard input.
+    pub(crate) fn register_focusable(&self, id: Id) {
+        let focus = self.focus_state();
+        let scope = focus.declaring.last().copied();
+        focus.focusables.push((id, scope));
+    }
+
+    /// Closes the traps that weren't declared, moves the focus into scopes and applies Tab,
+    /// run when the frame is done.
+    pub(crate) fn update_focus(&self) {
+        let focus = self.focus_state();
+        if let Some(restore) = focus.close_traps() {
+            // Unless the focus was moved to a widget that is still there, like a text field
+            // clicked outside of a popup
+            let moved = self.focus_id().is_some_and(|focused| {
+                focus
+                    .focusables
+                    .iter()
+                    .any(|(id, _)| id.id.id == focused.id.id)
+            });
+            match restore {
+                Some(id) if !moved => self.set_focus_id(id),
+                None if !moved => self.clear_focus(),
+                _ => {}
+            }
+        }
+
+        for scope in std::mem::take(&mut focus.entering) {
+            let first = focus
+                .focusables
+                .iter()
+                .map(|(id, _)| *id)
+                .find(|id| focus.contains(scope, *id));
+            if let Some(id) = focus.target(scope).or(first) {
+                self.set_focus_id(id);
+            }
+        }
+
+        let input = self.input();
+        if input.keys_pressed.contains(&Key::Tab) {
+            let backwards = input.modifiers().shift();
+            if let Some(id) = focus.next(self.focus_id(), backwards) {
+                self.set_focus_id(id);
+            }
+        }
+
+        if let Some(id) = self.focus_id() {
+            focus.remember(id);
+        }
+        focus.frame_scopes.clear();
+        focus.focusables.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step() {
+        assert_eq!(step(3, None, false), Some(0));
+        assert_eq!(step(3, None, true), Some(2));
+        assert_eq!(step(3, Some(1), false), Some(2));
+        assert_eq!(step(3, Some(2), false), Some(0));
+        assert_eq!(step(3, Some(0), true), Some(2));
+        assert_eq!(step(0, None, false), None);
+    }
+
+    fn declare(focus: &mut FocusState, id: u32, parent: Option<usize>, trap: bool) -> usize {
+        focus.frame_scopes.push(FrameScope { id, parent, trap });
+        focus.frame_scopes.len() - 1
+    }
+
+    #[test]
+    fn test_close_traps() {
+        let mut focus = FocusState::default();
+        for id in [1, 2, 3] {
+            focus.traps.push(Trap { id, restore: None });
+        }
+        // A popup closing takes the ones opened from it along
+        declare(&mut focus, 1, None, true);
+        declare(&mut focus, 3, None, true);
+        assert!(focus.close_traps().is_some());
+        assert_eq!(focus.traps.len(), 1);
+
+        focus.frame_scopes.clear();
+        declare(&mut focus, 1, None, true);
+        assert!(focus.close_traps().is_none());
+    }
+
+    #[test]
+    fn test_discard_frame() {
+        let mut focus = FocusState::default();
+        focus.traps.push(Trap {
+            id: 1,
+            restore: None,
+        });
+        // Declaring the trap panicked halfway through
+        let trap = declare(&mut focus, 1, None, true);
+        focus.declaring.push(trap);
+
+        focus.discard_frame();
+        assert!(focus.frame_scopes.is_empty());
+        assert!(focus.declaring.is_empty());
+        // The trap stays open for the next frame to declare it again
+        assert_eq!(focus.traps.len(), 1);
+    }
+
+    #[test]
+    fn test_scope_target() {
+        let widget = |id| Id {
+            id: clay_layout::Clay_ElementId {
+                id,
+                offset: 0,
+                baseId: id,
+                stringId: "".into(),
+            },
+        };
+        let mut focus = FocusState::default();
+        let panel = declare(&mut focus, 10, None, false);
+        let inner = declare(&mut focus, 11, Some(panel), false);
+        focus.focusables = vec![
+            (widget(1), Some(panel)),
+            (widget(2), Some(inner)),
+            (widget(3), None),
+        ];
+        assert!(focus.target(10).is_none());
+
+        focus.scopes.entry(10).or_default().default = Some(widget(1));
+        assert_eq!(focus.target(10).map(|id| id.id.id), Some(1));
+
+        // Focusing a widget in the inner scope is remembered by the panel around it as well
+        focus.remember(widget(2));
+        assert_eq!(focus.target(10).map(|id| id.id.id), Some(2));
+        assert_eq!(focus.target(11).map(|id| id.id.id), Some(2));
+
+        // A widget that isn't in the scope anymore falls back to the default
+        focus.focusables.retain(|(id, _)| id.id.id != 2);
+        assert_eq!(focus.target(10).map(|id| id.id.id), Some(1));
+    }
+}
@@ -1,10 +1,19 @@
+use crate::budget::FrameDeadline;
+use crate::image::ImageInfo;
 use crate::internal_error::{InternalError, InternalResult};
-use background_worker::{AnySend, BoxAnySend, Receiver, WorkSystem, WorkerResult};
+use crate::long_text::{self, TextPiece};
+use crate::rotated_text::{TextRotation, rotate_pixmap};
+use crate::text_limits::TextLimits;
+use crate::tiny_skia_renderer::{blend_source_over, premultiply_coverage};
+use background_worker::{
+    AnySend, BoxAnySend, CallbackError, TryRecvError, WorkHandle, WorkSystem, WorkerResult,
+};
 use cosmic_text::{
-    Attrs, AttrsOwned, Buffer, Color, FontSystem, Metrics, Shaping, SwashCache, Weight,
+    Attrs, AttrsOwned, Buffer, Color, FontSystem, LayoutGlyph, Metrics, Shaping, SwashCache, Weight,
 };
-use std::borrow::Cow;
-use std::collections::HashMap;
+use glam::Vec4;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ops::Range;
 use std::sync::{Arc, Mutex};
 use tiny_skia::Pixmap;
 
@@ -52,8 +61,9 @@ struct FontFaceInfo {
 #[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct CachedString {
-    pub data: tiny_skia::Pixmap,
-    //pub data: RawVoidPtr,
+    /// `None` when there is nothing to draw, such as for empty or whitespace only strings. The
+    /// size is still the measured one so layouts line up.
+    pub data: Option<tiny_skia::Pixmap>,
     pub id: u64,
     pub stride: u32,
     pub width: u32,
@@ -64,6 +74,10 @@ pub struct CachedString {
 
 type LoadedFonts = HashMap<FontHandle, FontInfo>;
 type CachedStrings = HashMap<GeneratorConfig, CachedString>;
+/// Pieces of the long strings, see [`crate::long_text`].
+type PiecedStrings = HashMap<GeneratorConfig, Vec<TextPiece>>;
+/// Rasterized strings turned by 90 degrees, see [`crate::rotated_text`].
+type RotatedStrings = HashMap<(GeneratorConfig, TextRotation), ImageInfo>;
 
 #[allow(dead_code)]
 #[derive(Debug)]
@@ -89,46 +103,194 @@ impl AsyncState {
     }
 }
 
+/// Vertical font metrics in pixels for a given font size.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct FontMetrics {
+    /// Distance from the top of a line to the baseline.
+    pub ascent: f32,
+    /// Distance from the baseline to the bottom of the descenders.
+    pub descent: f32,
+    /// Extra spacing the font recommends between lines.
+    pub line_gap: f32,
+    /// Height of a line of text, this is also the height used for layout and rasterization.
+    pub line_height: f32,
+}
+
+impl FontMetrics {
+    fn scaled(&self, font_size: u32) -> Self {
+        let size = font_size as f32;
+        Self {
+            ascent: self.ascent * size,
+            descent: self.descent * size,
+            line_gap: self.line_gap * size,
+            line_height: self.line_height * size,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 struct FontInfo {
     attrs: AttrsOwned,
+    /// Metrics for a font size of 1.
+    metrics: FontMetrics,
+}
+
+/// Byte offset of a character boundary in a text, as found by hit-testing its [`TextLayout`].
+pub type CharIndex = usize;
+
+/// One visual line of a [`TextLayout`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextLine {
+    /// Bytes of the full text on this line, without the line break.
+    pub range: Range<usize>,
+    /// Caret positions as byte offset and x in pixels, in order along the line. Wrapped lines
+    /// don't have a caret at their end as that is the start of the next line.
+    pub carets: Vec<(usize, f32)>,
+    pub width: f32,
+}
+
+/// Text wrapped to a width and split into visual lines, for placing carets and hit-testing in
+/// editors.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextLayout {
+    /// At least one line once text was laid out, empty text has an empty line.
+    pub lines: Vec<TextLine>,
+    pub line_height: f32,
+}
+
+impl TextLayout {
+    pub fn height(&self) -> f32 {
+        self.lines.len() as f32 * self.line_height
+    }
+
+    /// Visual line the caret at byte `offset` is on.
+    pub fn line_at(&self, offset: usize) -> usize {
+        self.lines
+            .iter()
+            .rposition(|line| line.range.start <= offset)
+            .unwrap_or(0)
+    }
+
+    /// Horizontal position of the caret at byte `offset` on `line`.
+    pub fn caret_x(&self, line: usize, offset: usize) -> f32 {
+        self.lines
+            .get(line)
+            .and_then(|line| line.carets.iter().rev().find(|(o, _)| *o <= offset))
+            .map_or(0.0, |(_, x)| *x)
+    }
+
+    /// Byte offset of the caret on `line` closest to `x`.
+    pub fn offset_at_x(&self, line: usize, x: f32) -> usize {
+        self.lines
+            .get(line)
+            .and_then(|line| {
+                line.carets
+                    .iter()
+                    .min_by(|a, b| (a.1 - x).abs().total_cmp(&(b.1 - x).abs()))
+            })
+            .map_or(0, |(offset, _)| *offset)
+    }
+
+    /// Byte offset of the caret closest to (`x`, `y`) relative to the top left of the text.
+    pub fn offset_at(&self, x: f32, y: f32) -> usize {
+        let line = if self.line_height > 0.0 {
+            (y / self.line_height).max(0.0) as usize
+        } else {
+            0
+        };
+        self.offset_at_x(line.min(self.lines.len().saturating_sub(1)), x)
+    }
+
+    /// Rect of the character starting at `index` as (x0, y0, x1, y1) relative to the top left
+    /// of the text. Characters shaped together, like ligatures, share the rect of the whole
+    /// cluster. `None` at the end of a line, where there is no character.
+    pub fn char_rect(&self, index: CharIndex) -> Option<Vec4> {
+        let line_index = self.line_at(index);
+        let line = self.lines.get(line_index)?;
+        if index >= line.range.end {
+            return None;
+        }
+        let start = line.carets.iter().rev().find(|(o, _)| *o <= index)?;
+        let end = line
+            .carets
+            .iter()
+            .find(|(o, _)| *o > index)
+            .map_or(line.width, |(_, x)| *x);
+        let y = line_index as f32 * self.line_height;
+        Some(Vec4::new(start.1, y, end, y + self.line_height))
+    }
 }
 
+/// Default number of text generation jobs submitted per frame.
+pub(crate) const DEFAULT_TEXT_JOBS_PER_FRAME: usize = 4;
+
 struct InflightGeneration {
     config: GeneratorConfig,
-    receiver: Receiver<WorkerResult>,
+    receiver: WorkHandle,
+}
+
+/// Cache statistics reported by the debug tooling.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct TextCacheStats {
+    pub(crate) loaded_fonts: usize,
+    pub(crate) cached_strings: usize,
+    /// Size of all cached pixmaps in bytes.
+    pub(crate) cached_bytes: usize,
+    pub(crate) inflight_generations: usize,
+    /// Requested strings waiting for a free slot in the per-frame budget.
+    pub(crate) pending_generations: usize,
 }
 
 #[allow(dead_code)]
 pub(crate) struct TextGenerator {
     async_state: Arc<Mutex<AnySend>>,
     cached_strings: CachedStrings,
+    pieced_strings: PiecedStrings,
+    rotated_strings: RotatedStrings,
     /// These are for messure texts on the main thread.
     sync_font_system: FontSystem,
     sync_loaded_fonts: LoadedFonts,
     inflight_text_generations: Vec<InflightGeneration>,
+    /// Fonts the workers are still loading. Their strings aren't generated until they're done.
+    loading_fonts: Vec<(FontHandle, WorkHandle)>,
+    /// Fonts the workers failed to load, drawn with [`TextGenerator::fallback_font`] instead.
+    failed_fonts: HashSet<FontHandle>,
+    /// Strings requested but not yet submitted to the workers, in request order.
+    pending_text_generations: Vec<GeneratorConfig>,
+    /// Everything that is pending or inflight, so a string is only generated once.
+    requested_text_generations: HashSet<GeneratorConfig>,
     font_id_counter: u64,
     text_buffers_id: u64,
+    /// Applied to every string before it's measured or rasterized.
+    limits: TextLimits,
+    /// Characters measured text had that none of the loaded fonts have a glyph for.
+    missing_glyphs: BTreeSet<char>,
+    /// Missing glyphs not yet reported, see [`TextGenerator::take_new_missing_glyphs`].
+    new_missing_glyphs: Vec<char>,
     load_font_async_id: usize,
     gen_text_async_id: usize,
 }
 
+/// Contents of a font file. The same data is shared by the font systems used for measuring and
+/// rasterizing so each font is only kept in memory once.
+type FontData = Arc<dyn AsRef<[u8]> + Send + Sync>;
+
 pub(crate) struct LoadConfig {
     pub(crate) font_id: FontHandle,
-    pub(crate) font_path: Cow<'static, str>,
+    pub(crate) font_data: FontData,
+    /// Resolved when the font was loaded for measuring, reused so both font systems agree.
+    font_info: FontInfo,
 }
 
-/// Loads a font into the font system and stores its information.
+/// Loads a font into the font system and returns its information.
 fn load_font(
-    id: FontHandle,
     font_path: &str,
-    loaded_fonts: &mut LoadedFonts,
+    font_data: FontData,
     font_system: &mut FontSystem,
-) -> InternalResult<()> {
+) -> InternalResult<FontInfo> {
     let font_db = font_system.db_mut();
 
-    // Load the font from the given path. This assumes that the path points to a valid font file.
-    let ids = font_db.load_font_source(cosmic_text::fontdb::Source::File(font_path.into()));
+    let ids = font_db.load_font_source(cosmic_text::fontdb::Source::Binary(font_data));
 
     // Check if a font ID was obtained from loading the font.
     // If not, an error is returned since we can't proceed without an ID.
@@ -159,24 +321,46 @@ fn load_font(
             .family(cosmic_text::Family::Name(family_name)),
     );
 
-    loaded_fonts.insert(id, FontInfo { attrs });
-    Ok(())
+    let font = font_system
+        .get_font(face_id)
+        .ok_or(InternalError::GenericError {
+            text: format!("Unable to read font {}", font_path),
+        })?;
+    let font_metrics = font.as_swash().metrics(&[]);
+    let units_per_em = font_metrics.units_per_em.max(1) as f32;
+    let ascent = font_metrics.ascent / units_per_em;
+    let descent = font_metrics.descent / units_per_em;
+    let line_gap = font_metrics.leading / units_per_em;
+
+    let metrics = FontMetrics {
+        ascent,
+        descent,
+        line_gap,
+        line_height: ascent + descent + line_gap,
+    };
+
+    Ok(FontInfo { attrs, metrics })
 }
 
-fn measure_string_size(
+/// Shapes `text` and returns the buffer with its size. Measuring and rasterizing both go through
+/// here so the layout reserves exactly the space the rendered text covers. Lines are wrapped at
+/// `wrap_width` if given. Returns `None` for a font size of zero, which cosmic-text can't lay out.
+fn shape_text(
     text: &str,
     font_info: &FontInfo,
     font_size: u32,
-    line_height: f32,
+    wrap_width: Option<f32>,
     font_system: &mut FontSystem,
-) -> Option<(f32, f32)> {
-    // Define metrics for the text
+) -> Option<(Buffer, f32, f32)> {
+    if font_size == 0 {
+        return None;
+    }
+
+    let line_height = font_info.metrics.scaled(font_size).line_height;
     let metrics = Metrics::new(font_size as _, line_height);
 
-    // Create a buffer for the text
     let mut buffer = Buffer::new(font_system, metrics);
-
-    // Set the text in the buffer with default attributes
+    buffer.set_size(font_system, wrap_width, None);
     buffer.set_text(
         font_system,
         text,
@@ -187,133 +371,271 @@ fn measure_string_size(
     // Shape the text to compute layout without rendering
     buffer.shape_until_scroll(font_system, true);
 
-    // Get the layout runs which contain size information
-    let layout_runs = buffer.layout_runs();
-
-    // Calculate width and height; this assumes single line text for simplicity
     let mut width = 0.0f32;
     let mut height = 0.0f32;
-    for run in layout_runs {
+    for run in buffer.layout_runs() {
         width = width.max(run.line_w);
         height += run.line_height;
     }
 
-    Some((width, height))
+    Some((buffer, width, height))
 }
 
-#[allow(dead_code)]
-fn generate_text(
+/// The characters of `glyph` in `run_text` if none of the fonts have a glyph for them, which
+/// cosmic-text shapes to glyph 0 after trying all of them. Whitespace and control characters
+/// aren't drawn and don't count.
+fn missing_chars<'a>(run_text: &'a str, glyph: &LayoutGlyph) -> impl Iterator<Item = char> + 'a {
+    let chars = if glyph.glyph_id == 0 {
+        &run_text[glyph.start..glyph.end]
+    } else {
+        ""
+    };
+    chars
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+}
+
+/// Characters of the shaped `buffer` that none of the fonts have a glyph for.
+fn missing_glyphs(buffer: &Buffer) -> impl Iterator<Item = char> + '_ {
+    buffer.layout_runs().flat_map(|run| {
+        run.glyphs
+            .iter()
+            .flat_map(move |glyph| missing_chars(run.text, glyph))
+    })
+}
+
+/// Size of `text` as rasterized, adding the characters missing from the fonts to `missing`.
+fn measure_string_size(
     text: &str,
     font_info: &FontInfo,
     font_size: u32,
-    line_height: f32,
-    state: &mut AsyncState,
-) -> WorkerResult {
-    // Define metrics for the text
-    let metrics = Metrics::new(font_size as _, line_height);
-
-    // Create a buffer for the text
-    let mut buffer = Buffer::new(&mut state.font_system, metrics);
+    font_system: &mut FontSystem,
+    missing: &mut BTreeSet<char>,
+) -> Option<(f32, f32)> {
+    let shaped = shape_text(text, font_info, font_size, None, font_system);
+    if let Some((buffer, _, _)) = &shaped {
+        missing.extend(missing_glyphs(buffer));
+    }
+    let (width, height) = shaped.map_or((0.0, 0.0), |(_, width, height)| (width, height));
+    // Rasterized text covers whole pixels
+    Some((width.ceil(), height.ceil()))
+}
 
-    // Set the text in the buffer with default attributes
-    buffer.set_text(
-        &mut state.font_system,
-        text,
-        &font_info.attrs.as_attrs(),
-        Shaping::Basic,
-    );
+/// Wraps `text` at `width`, if given, and splits it into visual lines with their caret
+/// positions.
+fn layout_text(
+    text: &str,
+    font_info: &FontInfo,
+    font_size: u32,
+    width: Option<f32>,
+    font_system: &mut FontSystem,
+) -> TextLayout {
+    let mut layout = TextLayout {
+        lines: Vec::new(),
+        line_height: font_info.metrics.scaled(font_size).line_height,
+    };
 
-    // Shape the text to compute layout without rendering
-    buffer.shape_until_scroll(&mut state.font_system, true);
+    if let Some((buffer, _, _)) = shape_text(text, font_info, font_size, width, font_system) {
+        // Byte offset of each paragraph in `text`
+        let mut paragraph_starts = Vec::with_capacity(buffer.lines.len());
+        let mut offset = 0;
+        for line in &buffer.lines {
+            paragraph_starts.push(offset);
+            offset += line.text().len() + line.ending().as_str().len();
+        }
 
-    // Get the layout runs which contain size information
-    let layout_runs = buffer.layout_runs();
+        let runs: Vec<_> = buffer.layout_runs().collect();
+        for (i, run) in runs.iter().enumerate() {
+            let base = paragraph_starts[run.line_i];
+            let start = match i.checked_sub(1).map(|prev| &runs[prev]) {
+                Some(prev) if prev.line_i == run.line_i => layout.lines[i - 1].range.end,
+                _ => base,
+            };
+            // Wrapped lines end where the next one starts, trailing whitespace included
+            let next_start = runs
+                .get(i + 1)
+                .filter(|next| next.line_i == run.line_i)
+                .map(|next| {
+                    next.glyphs
+                        .first()
+                        .map_or(base + run.text.len(), |g| base + g.start)
+                });
+            let end = next_start.unwrap_or(base + run.text.len()).max(start);
+
+            let mut carets = vec![(start, 0.0)];
+            for glyph in run.glyphs {
+                let offset = base + glyph.start;
+                if offset > carets.last().map_or(start, |(last, _)| *last) && offset < end {
+                    carets.push((offset, glyph.x));
+                }
+            }
+            let width = run.glyphs.last().map_or(0.0, |g| g.x + g.w);
+            // The end of a wrapped line is the start of the next one
+            if next_start.is_none() && end > start {
+                carets.push((end, width));
+            }
 
-    // Calculate width and height; this assumes single line text for simplicity
-    let mut width = 0.0f32;
-    let mut height = 0.0f32;
-    for run in layout_runs {
-        width = width.max(run.line_w);
-        height += run.line_height;
+            layout.lines.push(TextLine {
+                range: start..end,
+                carets,
+                width,
+            });
+        }
     }
 
-    // + 8 as we always do 8 pixels wide in the rendering
-    let width = width as usize;
-    let height = height as usize;
-
-    let mut pixmap = Pixmap::new(width as _, height as _).unwrap();
-
-    let _output = vec![0; width * height];
-
-    // Create a default text color
-    let text_color = Color::rgb(0xFF, 0xFF, 0xFF);
-    let _max_y_with_pixels = 0;
-    let pixels = pixmap.pixels_mut();
+    if layout.lines.is_empty() {
+        layout.lines.push(TextLine {
+            range: 0..0,
+            carets: vec![(0, 0.0)],
+            width: 0.0,
+        });
+    }
+    layout
+}
 
-    // Draw the buffer (for performance, instead use SwashCache directly)
-    buffer.draw(
-        &mut state.font_system,
-        &mut state.swash_cache,
-        text_color,
-        |x, y, _w, _h, color| {
-            let c = (color.0 >> 24) as u8;
-            if x < 0 || y < 0 || x >= width as i32 || y >= height as i32 {
-                return;
+/// Draws the outline of a box in place of a glyph none of the fonts have, so the missing
+/// character shows instead of whatever the font has as its placeholder, which can be blank.
+/// The box is `advance` wide minus a margin and stands on the `baseline`.
+fn draw_tofu(
+    x: f32,
+    advance: f32,
+    baseline: f32,
+    font_size: u32,
+    color: Color,
+    blend: &mut impl FnMut(i32, i32, Color),
+) {
+    let margin = (font_size as f32 * 0.08).max(1.0);
+    let stroke = (font_size as f32 / 16.0).round().max(1.0) as i32;
+    let x0 = (x + margin).round() as i32;
+    let x1 = ((x + advance - margin).round() as i32).max(x0 + stroke * 2);
+    let y1 = baseline.round() as i32;
+    let y0 = y1 - (font_size as f32 * 0.7).round() as i32;
+    for y in y0..y1 {
+        for x in x0..x1 {
+            let edge = x < x0 + stroke || x >= x1 - stroke || y < y0 + stroke || y >= y1 - stroke;
+            if edge {
+                blend(x, y, color);
             }
+        }
+    }
+}
 
-            let color = tiny_skia::PremultipliedColorU8::from_rgba(c, c, c, c).unwrap();
+#[allow(dead_code)]
+fn generate_text(
+    text: &str,
+    font_info: &FontInfo,
+    font_size: u32,
+    state: &mut AsyncState,
+) -> WorkerResult {
+    let shaped = shape_text(text, font_info, font_size, None, &mut state.font_system);
+    let (width, height) = shaped
+        .as_ref()
+        .map_or((0.0, 0.0), |(_, width, height)| (*width, *height));
 
-            pixels[(y as usize * width + x as usize) as usize] = color;
-        },
-    );
+    let width = width.ceil() as usize;
+    let height = height.ceil() as usize;
 
-    Ok(Box::new(CachedString {
-        data: pixmap,
-        //data: RawVoidPtr(Box::into_raw(output.into_boxed_slice()) as _),
+    let mut cached = CachedString {
+        data: None,
         stride: width as u32,
         width: width as u32,
-        //height: max_y_with_pixels as u32,
         height: height as u32,
         sub_pixel_step_x: 1,
         sub_pixel_step_y: 1,
         id: 0,
-    }))
+    };
+
+    // Pixmaps can't be zero sized and there is no point rasterizing blanks
+    let Some(((buffer, _, _), mut pixmap)) = shaped
+        .zip(Pixmap::new(width as _, height as _))
+        .filter(|_| !text.chars().all(char::is_whitespace))
+    else {
+        return Ok(Box::new(cached));
+    };
+
+    // Create a default text color
+    let text_color = Color::rgb(0xFF, 0xFF, 0xFF);
+    let pixels = pixmap.pixels_mut();
+    let mut blend = |x: i32, y: i32, color: Color| {
+        if x < 0 || y < 0 || x >= width as i32 || y >= height as i32 {
+            return;
+        }
+
+        // Glyphs can overlap so blend instead of overwriting the earlier coverage
+        let (r, g, b, a) = color.as_rgba_tuple();
+        let src = premultiply_coverage(tiny_skia::ColorU8::from_rgba(r, g, b, 255), a);
+        let pixel = &mut pixels[y as usize * width + x as usize];
+        *pixel = blend_source_over(*pixel, src);
+    };
+
+    for run in buffer.layout_runs() {
+        for glyph in run.glyphs {
+            if missing_chars(run.text, glyph).next().is_some() {
+                draw_tofu(
+                    glyph.x, glyph.w, run.line_y, font_size, text_color, &mut blend,
+                );
+                continue;
+            }
+            let physical = glyph.physical((0.0, 0.0), 1.0);
+            state.swash_cache.with_pixels(
+                &mut state.font_system,
+                physical.cache_key,
+                glyph.color_opt.unwrap_or(text_color),
+                |x, y, color| blend(physical.x + x, run.line_y as i32 + physical.y + y, color),
+            );
+        }
+    }
+
+    cached.data = Some(pixmap);
+    Ok(Box::new(cached))
 }
 
 fn job_generate_text(data: BoxAnySend, state: Arc<Mutex<AnySend>>) -> WorkerResult {
-    let data = data.downcast::<Box<GeneratorConfig>>().unwrap();
+    let data = data
+        .downcast::<Box<GeneratorConfig>>()
+        .map_err(|_| CallbackError::InvalidDataType)?;
     let mut locked_state = state.lock().unwrap();
-    let mut state = locked_state.downcast_mut::<AsyncState>().unwrap();
-
-    if let Some(font) = state.loaded_fonts.get(&data.font_handle) {
-        let font_clone = font.clone();
-        generate_text(
-            &data.text,
-            &font_clone,
-            data.size,
-            data.size as f32 * 1.1,
-            &mut state,
-        )
-    } else {
-        panic!("Font not found");
-    }
+    let state = locked_state
+        .downcast_mut::<AsyncState>()
+        .ok_or(CallbackError::InvalidStateType)?;
+
+    // Strings are only requested for fonts that were loaded, this is a bug but not one worth
+    // taking down the worker for
+    let font = state
+        .loaded_fonts
+        .get(&data.font_handle)
+        .cloned()
+        .ok_or_else(|| CallbackError::Other(format!("Font {} not loaded", data.font_handle)))?;
+    generate_text(&data.text, &font, data.size, state)
 }
 
 fn job_load_font(data: BoxAnySend, state: Arc<Mutex<AnySend>>) -> WorkerResult {
-    let config = data.downcast::<Box<LoadConfig>>().unwrap();
-    let locked_state = state.lock();
-    let mut t = locked_state.unwrap();
-    let state = t.downcast_mut::<AsyncState>().unwrap();
-
-    load_font(
-        config.font_id,
-        &config.font_path,
-        &mut state.loaded_fonts,
-        &mut state.font_system,
-    )
-    .unwrap();
-
-    // TODO: Error handling
+    let config = data
+        .downcast::<Box<LoadConfig>>()
+        .map_err(|_| CallbackError::InvalidDataType)?;
+    let mut locked_state = state.lock().unwrap();
+    let state = locked_state
+        .downcast_mut::<AsyncState>()
+        .ok_or(CallbackError::InvalidStateType)?;
+
+    // The font data was validated when it was loaded for measuring, but that font system may
+    // not be configured the same
+    let ids = state
+        .font_system
+        .db_mut()
+        .load_font_source(cosmic_text::fontdb::Source::Binary(
+            config.font_data.clone(),
+        ));
+    if ids.is_empty() {
+        return Err(CallbackError::Other(format!(
+            "No font faces found for font {}",
+            config.font_id
+        )));
+    }
+    state
+        .loaded_fonts
+        .insert(config.font_id, config.font_info.clone());
+
     Ok(Box::new(()))
 }
 
@@ -332,137 +654,837 @@ impl TextGenerator {
             sync_loaded_fonts: HashMap::new(),
             font_id_counter: 1,
             cached_strings: HashMap::new(),
+            pieced_strings: HashMap::new(),
+            rotated_strings: HashMap::new(),
             load_font_async_id,
             gen_text_async_id,
             inflight_text_generations: Vec::new(),
+            loading_fonts: Vec::new(),
+            failed_fonts: HashSet::new(),
+            pending_text_generations: Vec::new(),
+            requested_text_generations: HashSet::new(),
             text_buffers_id: 1,
+            limits: TextLimits::default(),
+            missing_glyphs: BTreeSet::new(),
+            new_missing_glyphs: Vec::new(),
         }
     }
 
     pub fn load_font(&mut self, path: &str, bg_worker: &WorkSystem) -> InternalResult<FontHandle> {
         let font_id = self.font_id_counter;
+        let font_data: FontData = Arc::new(std::fs::read(path)?);
+
         // First we load the font sync so we know it loaded fine, if it's ok we
         // will also schedle it to be loaded async to be used for rendering later.
         // We load it on the main thread also for text measurement.
-        load_font(
-            font_id,
-            path,
-            &mut self.sync_loaded_fonts,
-            &mut self.sync_font_system,
-        )?;
+        let font_info = load_font(path, font_data.clone(), &mut self.sync_font_system)?;
+        self.sync_loaded_fonts.insert(font_id, font_info.clone());
 
-        // Start loading the font async.
-        bg_worker.add_work(
+        // Start loading the font async, sharing the file data with the sync font system.
+        let receiver = bg_worker.add_work(
             self.load_font_async_id,
             Box::new(LoadConfig {
                 font_id,
-                font_path: Cow::Owned(path.to_string()),
+                font_data,
+                font_info,
             }),
         );
+        self.loading_fonts.push((font_id, receiver));
 
         self.font_id_counter += 1;
 
         Ok(font_id)
     }
 
+    /// The font loaded first that didn't fail, used in place of the ones that did.
+    pub(crate) fn fallback_font(&self) -> Option<FontHandle> {
+        self.sync_loaded_fonts
+            .keys()
+            .filter(|font| !self.failed_fonts.contains(font))
+            .min()
+            .copied()
+    }
+
+    /// Moves the pending strings of fonts that failed to load to the fallback font, or drops them
+    /// if there is none. They were requested before the font was known to have failed.
+    fn retarget_failed_pending(&mut self) {
+        let fallback = self.fallback_font();
+        for mut config in std::mem::take(&mut self.pending_text_generations) {
+            if self.failed_fonts.contains(&config.font_handle) {
+                self.requested_text_generations.remove(&config);
+                let Some(fallback) = fallback else {
+                    continue;
+                };
+                config.font_handle = fallback;
+                if self.cached_strings.contains_key(&config)
+                    || !self.requested_text_generations.insert(config.clone())
+                {
+                    continue;
+                }
+            }
+            self.pending_text_generations.push(config);
+        }
+    }
+
+    /// `font_id`, or the fallback font if it failed to load. Text is measured with the
+    /// resolved font as well so the layout fits what gets drawn.
+    fn resolve_font(&self, font_id: FontHandle) -> FontHandle {
+        if self.failed_fonts.contains(&font_id) {
+            self.fallback_font().unwrap_or(font_id)
+        } else {
+            font_id
+        }
+    }
+
     pub(crate) fn measure_text_size(
         &mut self,
         text: &str,
         font_id: FontHandle,
         font_size: u32,
     ) -> Option<(f32, f32)> {
-        if let Some(font_info) = self.sync_loaded_fonts.get(&font_id) {
-            let line_height = font_size as f32 * 1.1; // TODO: Proper size calculation here
-            measure_string_size(
-                text,
-                font_info,
-                font_size,
-                line_height,
-                &mut self.sync_font_system,
-            )
-        } else {
-            None
+        let font_id = self.resolve_font(font_id);
+        let font_info = self.sync_loaded_fonts.get(&font_id)?;
+        let text = self.limits.display(text);
+        let mut missing = BTreeSet::new();
+        let size = measure_string_size(
+            &text,
+            font_info,
+            font_size,
+            &mut self.sync_font_system,
+            &mut missing,
+        );
+        self.add_missing_glyphs(missing);
+        size
+    }
+
+    fn add_missing_glyphs(&mut self, missing: BTreeSet<char>) {
+        for c in missing {
+            if self.missing_glyphs.insert(c) {
+                self.new_missing_glyphs.push(c);
+            }
         }
     }
 
+    /// Characters of the measured text so far that none of the loaded fonts have a glyph for.
+    /// They're drawn as boxes.
+    pub(crate) fn missing_glyphs(&self) -> &BTreeSet<char> {
+        &self.missing_glyphs
+    }
+
+    /// The missing glyphs found since the last call, so each is only reported once.
+    pub(crate) fn take_new_missing_glyphs(&mut self) -> Vec<char> {
+        std::mem::take(&mut self.new_missing_glyphs)
+    }
+
+    /// Lays out `text` wrapped at `width`, if given, for editing, see [`TextLayout`].
+    pub(crate) fn layout_text(
+        &mut self,
+        text: &str,
+        font_id: FontHandle,
+        font_size: u32,
+        width: Option<f32>,
+    ) -> Option<TextLayout> {
+        let font_id = self.resolve_font(font_id);
+        let font_info = self.sync_loaded_fonts.get(&font_id)?;
+        Some(layout_text(
+            text,
+            font_info,
+            font_size,
+            width,
+            &mut self.sync_font_system,
+        ))
+    }
+
+    pub(crate) fn font_metrics(&self, font_id: FontHandle, font_size: u32) -> Option<FontMetrics> {
+        self.sync_loaded_fonts
+            .get(&self.resolve_font(font_id))
+            .map(|font_info| font_info.metrics.scaled(font_size))
+    }
+
+    /// Returns the cached string, or requests it to be generated. Requests are only sent to the
+    /// workers by [`TextGenerator::submit_pending`]. Long strings aren't generated as a whole,
+    /// their visible pieces are requested once laid out, see [`TextGenerator::text_pieces`].
     pub fn queue_generate_text(
         &mut self,
         text: &str,
         size: u32,
         font_id: FontHandle,
-        bg_worker: &WorkSystem,
     ) -> Option<CachedString> {
+        let font_id = self.resolve_font(font_id);
+        // Without a font to fall back to there is nothing to draw it with
+        if self.failed_fonts.contains(&font_id) {
+            return None;
+        }
         let gen_config = GeneratorConfig {
             font_handle: font_id,
-            text: text.to_string(),
+            text: self.limits.display(text).into_owned(),
             sub_pixel_steps_x: 1,
             sub_pixel_steps_y: 1,
             size,
         };
+        if long_text::is_long(&gen_config.text) {
+            return None;
+        }
 
         // First check if we have the text cached.
         // TODO: Fix this. We should not clone because it will clone the whole text buffer.
         if let Some(cached_string) = self.cached_strings.get(&gen_config) {
-            return Some(cached_string.clone());
+            Some(cached_string.clone())
         } else {
-            // Queue the text generation if it's not cached.
+            if !self.requested_text_generations.contains(&gen_config) {
+                self.requested_text_generations.insert(gen_config.clone());
+                self.pending_text_generations.push(gen_config);
+            }
+
+            None
+        }
+    }
+
+    /// Sends at most `max_jobs` pending requests to the workers, strings for which `is_visible`
+    /// returns true first. The rest stay pending for the next frame. No new jobs are submitted
+    /// while `2 * max_jobs` are still in flight, so newly visible text never waits behind a long
+    /// queue of work that was submitted earlier.
+    pub(crate) fn submit_pending(
+        &mut self,
+        bg_worker: &WorkSystem,
+        max_jobs: usize,
+        is_visible: impl Fn(&str, u32, FontHandle) -> bool,
+    ) {
+        let max_jobs =
+            max_jobs.min((max_jobs * 2).saturating_sub(self.inflight_text_generations.len()));
+
+        // Strings of fonts that failed can't be generated, they're requested again with the
+        // fallback font
+        let requested = &mut self.requested_text_generations;
+        self.pending_text_generations.retain(|config| {
+            let failed = self.failed_fonts.contains(&config.font_handle);
+            if failed {
+                requested.remove(config);
+            }
+            !failed
+        });
+
+        // Strings of fonts the workers don't have yet wait for them
+        let (mut waiting, mut ready): (Vec<_>, Vec<_>) =
+            self.pending_text_generations.drain(..).partition(|config| {
+                self.loading_fonts
+                    .iter()
+                    .any(|(font, _)| *font == config.font_handle)
+            });
+        let jobs = take_prioritized(&mut ready, max_jobs, |config| {
+            is_visible(&config.text, config.size, config.font_handle)
+        });
+        ready.append(&mut waiting);
+        self.pending_text_generations = ready;
+
+        for config in jobs {
             let inflight = InflightGeneration {
-                config: gen_config.clone(),
-                receiver: bg_worker.add_work(self.gen_text_async_id, Box::new(gen_config)),
+                config: config.clone(),
+                receiver: bg_worker.add_work(self.gen_text_async_id, Box::new(config)),
             };
 
             self.inflight_text_generations.push(inflight);
-
-            None
         }
     }
 
-    pub fn update(&mut self) {
-        let mut i = 0;
-        while i < self.inflight_text_generations.len() {
-            let inflight = &self.inflight_text_generations[i];
-            if let Ok(data) = inflight.receiver.try_recv() {
-                match data {
-                    Ok(mut data) => {
-                        let data = data.downcast_mut::<CachedString>().unwrap();
-                        data.id = self.text_buffers_id;
-                        self.cached_strings
-                            .insert(inflight.config.clone(), data.clone());
-                        self.inflight_text_generations.remove(i);
-                        self.text_buffers_id += 1;
-                    }
+    /// Collects generated strings from the workers. Strings still waiting when `deadline` is
+    /// reached are collected by a later update. Returns the errors of the failed ones.
+    pub(crate) fn update(&mut self, deadline: FrameDeadline) -> Vec<String> {
+        let mut errors = Vec::new();
+        let failed_fonts = &mut self.failed_fonts;
+        let failed_count = failed_fonts.len();
+        self.loading_fonts
+            .retain(|(font, receiver)| match receiver.try_recv() {
+                Ok(Ok(_)) => false,
+                Ok(Err(e)) => {
+                    errors.push(format!("Unable to load font {}: {}", font, e));
+                    failed_fonts.insert(*font);
+                    false
+                }
+                Err(TryRecvError::Empty) => true,
+                Err(TryRecvError::Disconnected) => {
+                    errors.push(format!("Unable to load font {}: font loader stopped", font));
+                    failed_fonts.insert(*font);
+                    false
+                }
+            });
+        if self.failed_fonts.len() != failed_count {
+            self.retarget_failed_pending();
+        }
 
-                    Err(e) => {
-                        println!("Error generating text: {:?}", e);
-                        i += 1;
-                    }
+        let cached_strings = &mut self.cached_strings;
+        let requested = &mut self.requested_text_generations;
+        let text_buffers_id = &mut self.text_buffers_id;
+
+        self.inflight_text_generations.retain(|inflight| {
+            if deadline.is_reached() {
+                return true;
+            }
+            match inflight.receiver.try_recv() {
+                Ok(Ok(mut data)) => {
+                    let data = data.downcast_mut::<CachedString>().unwrap();
+                    data.id = *text_buffers_id;
+                    cached_strings.insert(inflight.config.clone(), data.clone());
+                    requested.remove(&inflight.config);
+                    *text_buffers_id += 1;
+                    false
+                }
+                Ok(Err(e)) => {
+                    errors.push(format!("Error generating text: {:?}", e));
+                    requested.remove(&inflight.config);
+                    false
+                }
+                Err(TryRecvError::Empty) => true,
+                Err(TryRecvError::Disconnected) => {
+                    requested.remove(&inflight.config);
+                    false
                 }
             }
+        });
+        errors
+    }
+
+    pub(crate) fn stats(&self) -> TextCacheStats {
+        TextCacheStats {
+            loaded_fonts: self.sync_loaded_fonts.len(),
+            cached_strings: self.cached_strings.len(),
+            cached_bytes: self
+                .cached_strings
+                .values()
+                .filter_map(|s| s.data.as_ref())
+                .map(|data| data.data().len())
+                .sum(),
+            inflight_generations: self.inflight_text_generations.len(),
+            pending_generations: self.pending_text_generations.len(),
         }
     }
 
+    /// Rasterizes `text` on the calling thread, waiting for a worker that is generating text to
+    /// finish first. The result isn't cached. `None` while the workers haven't loaded the font.
+    pub(crate) fn generate_text_now(
+        &mut self,
+        text: &str,
+        size: u32,
+        font_id: FontHandle,
+    ) -> Option<CachedString> {
+        let font_id = self.resolve_font(font_id);
+        let mut locked_state = self.async_state.lock().unwrap();
+        let state = locked_state.downcast_mut::<AsyncState>()?;
+        let font = state.loaded_fonts.get(&font_id)?.clone();
+        let text = self.limits.display(text);
+        let mut data = generate_text(&text, &font, size, state).ok()?;
+        data.downcast_mut::<CachedString>().cloned()
+    }
+
+    pub(crate) fn limits(&self) -> TextLimits {
+        self.limits
+    }
+
+    /// Sets the limits for strings requested from now on, the ones already cached stay.
+    pub(crate) fn set_limits(&mut self, limits: TextLimits) {
+        self.limits = limits;
+    }
+
     pub fn get_text(&self, text: &str, size: u32, font_id: FontHandle) -> Option<&CachedString> {
+        let gen_config = GeneratorConfig {
+            font_handle: self.resolve_font(font_id),
+            text: self.limits.display(text).into_owned(),
+            sub_pixel_steps_x: 1,
+            sub_pixel_steps_y: 1,
+            size,
+        };
+
+        self.cached_strings.get(&gen_config)
+    }
+
+    /// `text` turned by `rotation` as an image, once the string is rasterized. Requests the
+    /// string until then. `None` for blank text, which has nothing to draw.
+    pub(crate) fn rotated_text(
+        &mut self,
+        text: &str,
+        size: u32,
+        font_id: FontHandle,
+        rotation: TextRotation,
+    ) -> Option<ImageInfo> {
+        let gen_config = GeneratorConfig {
+            font_handle: self.resolve_font(font_id),
+            text: self.limits.display(text).into_owned(),
+            sub_pixel_steps_x: 1,
+            sub_pixel_steps_y: 1,
+            size,
+        };
+        let key = (gen_config, rotation);
+        if let Some(image) = self.rotated_strings.get(&key) {
+            return Some(image.clone());
+        }
+        let Some(cached) = self.cached_strings.get(&key.0) else {
+            let _ = self.queue_generate_text(text, size, font_id);
+            return None;
+        };
+        let image = ImageInfo::new(rotate_pixmap(cached.data.as_ref()?, rotation)?);
+        self.rotated_strings.insert(key, image.clone());
+        Some(image)
+    }
+
+    /// The pieces `text` is drawn in if it's long, measured the first time they're asked for.
+    /// `None` for text that is drawn at once or while the font isn't loaded.
+    pub(crate) fn text_pieces(
+        &mut self,
+        text: &str,
+        size: u32,
+        font_id: FontHandle,
+    ) -> Option<&[TextPiece]> {
+        let font_id = self.resolve_font(font_id);
+        let text = self.limits.display(text);
+        if !long_text::is_long(&text) {
+            return None;
+        }
         let gen_config = GeneratorConfig {
             font_handle: font_id,
-            text: text.to_string(),
+            text: text.into_owned(),
             sub_pixel_steps_x: 1,
             sub_pixel_steps_y: 1,
             size,
         };
+        if !self.pieced_strings.contains_key(&gen_config) {
+            let font_info = self.sync_loaded_fonts.get(&font_id)?;
+            let mut missing = BTreeSet::new();
+            let mut x = 0.0;
+            let pieces = long_text::split_pieces(&gen_config.text)
+                .into_iter()
+                .map(|range| {
+                    let text = gen_config.text[range].to_string();
+                    let (width, _) = measure_string_size(
+                        &text,
+                        font_info,
+                        size,
+                        &mut self.sync_font_system,
+                        &mut missing,
+                    )
+                    .unwrap_or_default();
+                    x += width;
+                    TextPiece {
+                        text,
+                        x: x - width,
+                        width,
+                    }
+                })
+                .collect();
+            self.pieced_strings.insert(gen_config.clone(), pieces);
+            self.add_missing_glyphs(missing);
+        }
+        self.pieced_strings.get(&gen_config).map(Vec::as_slice)
+    }
 
-        self.cached_strings.get(&gen_config).map(|s| &*s)
+    /// The pieces of `text` if it's long and was laid out by [`TextGenerator::text_pieces`].
+    pub(crate) fn cached_text_pieces(
+        &self,
+        text: &str,
+        size: u32,
+        font_id: FontHandle,
+    ) -> Option<&[TextPiece]> {
+        let text = self.limits.display(text);
+        if !long_text::is_long(&text) {
+            return None;
+        }
+        let gen_config = GeneratorConfig {
+            font_handle: self.resolve_font(font_id),
+            text: text.into_owned(),
+            sub_pixel_steps_x: 1,
+            sub_pixel_steps_y: 1,
+            size,
+        };
+        self.pieced_strings.get(&gen_config).map(Vec::as_slice)
     }
 }
 
+/// Removes up to `max` entries from `pending`, the ones matching `is_priority` first. Order is
+/// otherwise kept so requests are served first come, first served. The deferred prioritized
+/// entries are moved to the front.
+fn take_prioritized<T>(
+    pending: &mut Vec<T>,
+    max: usize,
+    is_priority: impl Fn(&T) -> bool,
+) -> Vec<T> {
+    let (first, rest): (Vec<T>, Vec<T>) = pending.drain(..).partition(|t| is_priority(t));
+    pending.extend(first.into_iter().chain(rest));
+
+    let count = max.min(pending.len());
+    pending.drain(..count).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    pub(crate) const TEST_FONT: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../data/Source_Sans_3/static/SourceSans3-Regular.ttf"
+    );
+
+    #[test]
+    fn test_failed_font_falls_back() {
+        let worker = WorkSystem::new(1);
+        let mut generator = TextGenerator::new(&worker);
+        let first = generator.load_font(TEST_FONT, &worker).unwrap();
+        let second = generator.load_font(TEST_FONT, &worker).unwrap();
+
+        // Text queued while the font is still loading waits for it
+        generator.queue_generate_text("Kick", 16, second);
+        assert_eq!(generator.pending_text_generations.len(), 1);
+
+        // Font data the workers can't read fails the job instead of the worker
+        let config = LoadConfig {
+            font_id: second,
+            font_data: Arc::new(vec![0u8; 64]),
+            font_info: generator.sync_loaded_fonts[&second].clone(),
+        };
+        let receiver = worker.add_work(generator.load_font_async_id, Box::new(config));
+        generator.loading_fonts.retain(|(font, _)| *font != second);
+        generator.loading_fonts.push((second, receiver));
+        let mut errors = Vec::new();
+        while !generator.loading_fonts.is_empty() {
+            errors.extend(generator.update(FrameDeadline::unlimited()));
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        assert_eq!(errors.len(), 1);
+        assert!(generator.failed_fonts.contains(&second));
+        assert_eq!(generator.fallback_font(), Some(first));
+
+        // The queued text is generated with the fallback font instead
+        generator.submit_pending(&worker, 8, |_, _, _| true);
+        generator.queue_generate_text("Kick", 16, second);
+        assert!(generator.pending_text_generations.is_empty());
+        assert_eq!(generator.inflight_text_generations.len(), 1);
+        assert!(
+            generator
+                .inflight_text_generations
+                .iter()
+                .all(|inflight| inflight.config.font_handle == first)
+        );
+
+        // Nothing is requested when there is nothing to fall back to
+        generator.failed_fonts.insert(first);
+        generator.pending_text_generations.clear();
+        assert!(generator.queue_generate_text("Snare", 16, second).is_none());
+        assert!(generator.pending_text_generations.is_empty());
+    }
+
+    #[test]
+    fn test_long_text_pieces() {
+        let worker = WorkSystem::new(1);
+        let mut generator = TextGenerator::new(&worker);
+        let font = generator.load_font(TEST_FONT, &worker).unwrap();
+
+        let text = "Lyrics of a long song ".repeat(40);
+        assert!(generator.queue_generate_text(&text, 16, font).is_none());
+        assert!(generator.pending_text_generations.is_empty());
+        assert!(generator.text_pieces("Short", 16, font).is_none());
+
+        let (width, _) = generator.measure_text_size(&text, font, 16).unwrap();
+        let pieces = generator.text_pieces(&text, 16, font).unwrap().to_vec();
+        assert!(pieces.len() > 1);
+        assert_eq!(
+            pieces.iter().map(|p| p.text.as_str()).collect::<String>(),
+            text
+        );
+        for pair in pieces.windows(2) {
+            assert_eq!(pair[1].x, pair[0].x + pair[0].width);
+        }
+        // Each piece is rounded up to whole pixels
+        let end = pieces.last().map(|p| p.x + p.width).unwrap();
+        assert!(end >= width && end <= width + pieces.len() as f32);
+        assert_eq!(
+            generator.cached_text_pieces(&text, 16, font),
+            Some(pieces.as_slice())
+        );
+    }
+
+    #[test]
+    fn test_missing_glyphs() {
+        let worker = WorkSystem::new(1);
+        let mut generator = TextGenerator::new(&worker);
+        let font = generator.load_font(TEST_FONT, &worker).unwrap();
+
+        // Private use characters no font has
+        generator.measure_text_size("Kick \u{E000}\u{E001}", font, 16);
+        generator.measure_text_size("Snare\t\u{E000}", font, 16);
+        assert_eq!(
+            generator.take_new_missing_glyphs(),
+            ['\u{E000}', '\u{E001}']
+        );
+        // Reported once
+        generator.measure_text_size("\u{E001}", font, 16);
+        assert!(generator.take_new_missing_glyphs().is_empty());
+        assert_eq!(generator.missing_glyphs().len(), 2);
+
+        // Drawn as a box, ink in the middle of its edge but not inside of it
+        let mut state = AsyncState::new();
+        let font_info = load_test_font(&mut state.font_system);
+        let generated = generate_text("\u{E000}", &font_info, 32, &mut state).unwrap();
+        let generated = generated.downcast_ref::<CachedString>().unwrap();
+        let pixmap = generated.data.as_ref().unwrap();
+        let alpha = |x: u32, y: u32| pixmap.pixel(x, y).unwrap().alpha();
+        let (mid_x, mid_y) = (pixmap.width() / 2, pixmap.height() / 2);
+        assert_eq!(alpha(mid_x, mid_y), 0);
+        assert!((0..mid_y).any(|y| alpha(mid_x, y) == 255));
+        assert!((0..mid_x).any(|x| alpha(x, mid_y) == 255));
+    }
+
+    #[test]
+    fn test_font_data_is_shared() {
+        let worker = WorkSystem::new(1);
+        let mut generator = TextGenerator::new(&worker);
+        let font = generator.load_font(TEST_FONT, &worker).unwrap();
+
+        // Wait for the async side to pick up the font
+        for _ in 0..500 {
+            if generator
+                .async_state
+                .lock()
+                .unwrap()
+                .downcast_ref::<AsyncState>()
+                .unwrap()
+                .loaded_fonts
+                .contains_key(&font)
+            {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+
+        let locked = generator.async_state.lock().unwrap();
+        let async_state = locked.downcast_ref::<AsyncState>().unwrap();
+        let async_attrs = &async_state.loaded_fonts[&font].attrs;
+        assert_eq!(async_attrs, &generator.sync_loaded_fonts[&font].attrs);
+
+        // Both databases reference the same bytes
+        let data_ptr = |font_system: &FontSystem| {
+            font_system
+                .db()
+                .faces()
+                .find_map(|face| match &face.source {
+                    cosmic_text::fontdb::Source::Binary(data) => {
+                        Some(data.as_ref().as_ref().as_ptr())
+                    }
+                    _ => None,
+                })
+                .unwrap()
+        };
+        assert_eq!(
+            data_ptr(&async_state.font_system),
+            data_ptr(&generator.sync_font_system)
+        );
+    }
+
+    fn load_test_font(font_system: &mut FontSystem) -> FontInfo {
+        let font_data: FontData = Arc::new(std::fs::read(TEST_FONT).unwrap());
+        load_font(TEST_FONT, font_data, font_system).unwrap()
+    }
+
+    #[test]
+    fn test_measured_size_matches_rasterized() {
+        let mut state = AsyncState::new();
+        let font_info = load_test_font(&mut state.font_system);
+
+        for size in [11, 16, 32, 57] {
+            for text in ["Hello", "gjpqy", "ÅÄÖ fill", "Track 12 (Bus)"] {
+                let measured = measure_string_size(
+                    text,
+                    &font_info,
+                    size,
+                    &mut state.font_system,
+                    &mut BTreeSet::new(),
+                )
+                .unwrap();
+                let generated = generate_text(text, &font_info, size, &mut state).unwrap();
+                let generated = generated.downcast_ref::<CachedString>().unwrap();
+
+                assert_eq!(
+                    (generated.width as f32, generated.height as f32),
+                    measured,
+                    "{:?} at size {}",
+                    text,
+                    size
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_layout_text() {
+        let mut font_system = FontSystem::new();
+        let font_info = load_test_font(&mut font_system);
+        let text = "Kick drum and snare\n\nHats";
+        let width = measure_string_size(
+            "Kick drum",
+            &font_info,
+            16,
+            &mut font_system,
+            &mut BTreeSet::new(),
+        )
+        .unwrap()
+        .0;
+        let layout = layout_text(text, &font_info, 16, Some(width + 4.0), &mut font_system);
+
+        let lines: Vec<_> = layout
+            .lines
+            .iter()
+            .map(|line| &text[line.range.clone()])
+            .collect();
+        assert_eq!(lines[0].trim_end(), "Kick drum");
+        assert_eq!(lines[1..], ["and snare", "", "Hats"]);
+        // Lines are contiguous apart from the line breaks
+        for pair in layout.lines.windows(2) {
+            assert!(pair[1].range.start - pair[0].range.end <= 1);
+        }
+
+        assert_eq!(layout.line_at(text.len()), 3);
+        assert_eq!(layout.line_at(layout.lines[1].range.start), 1);
+        assert_eq!(
+            layout.offset_at(-5.0, layout.line_height * 3.5),
+            text.find("Hats").unwrap()
+        );
+        assert_eq!(layout.offset_at(1000.0, 1000.0), text.len());
+
+        let x = layout.caret_x(0, 4);
+        assert!(x > 0.0 && x < width);
+        assert_eq!(layout.offset_at_x(0, x), 4);
+
+        // Characters span from their caret to the next one, lines end without one
+        let kick = layout.char_rect(0).unwrap();
+        assert_eq!((kick.x, kick.y), (0.0, 0.0));
+        assert_eq!(kick.z, layout.caret_x(0, 1));
+        assert_eq!(kick.w, layout.line_height);
+        let hats = layout.char_rect(text.find("Hats").unwrap()).unwrap();
+        assert_eq!(hats.y, layout.line_height * 3.0);
+        assert!(layout.char_rect(text.len()).is_none());
+        assert!(layout.char_rect(layout.lines[2].range.start).is_none());
+        let s = layout.char_rect(text.len() - 1).unwrap();
+        assert_eq!(s.z, layout.lines[3].width);
+
+        let empty = layout_text("", &font_info, 16, Some(100.0), &mut font_system);
+        assert_eq!(empty.lines.len(), 1);
+        assert_eq!(empty.offset_at(50.0, 50.0), 0);
+    }
+
+    #[test]
+    fn test_default_layout() {
+        // Without any lines, as before the first layout
+        let layout = TextLayout::default();
+        assert!(layout.char_rect(0).is_none());
+        assert_eq!(layout.caret_x(0, 0), 0.0);
+        assert_eq!(layout.offset_at_x(0, 10.0), 0);
+    }
+
+    #[test]
+    fn test_generated_text_is_premultiplied() {
+        let mut state = AsyncState::new();
+        let font_info = load_test_font(&mut state.font_system);
+
+        let generated = generate_text("AVo/Wg", &font_info, 24, &mut state).unwrap();
+        let generated = generated.downcast_ref::<CachedString>().unwrap();
+        let pixels = generated.data.as_ref().unwrap().pixels();
+
+        // White text, so every channel equals the coverage
+        assert!(
+            pixels
+                .iter()
+                .all(|p| p.red() == p.alpha() && p.green() == p.alpha() && p.blue() == p.alpha())
+        );
+        // Anti-aliased edges are there
+        assert!(pixels.iter().any(|p| p.alpha() > 0 && p.alpha() < 255));
+    }
+
+    #[test]
+    fn test_descenders_are_not_clipped() {
+        let mut state = AsyncState::new();
+        let font_info = load_test_font(&mut state.font_system);
+        let size = 40;
+        let metrics = font_info.metrics.scaled(size);
+
+        let generated = generate_text("gjpqy", &font_info, size, &mut state).unwrap();
+        let generated = generated.downcast_ref::<CachedString>().unwrap();
+        let pixmap = generated.data.as_ref().unwrap();
+
+        let last_inked_row = (0..pixmap.height())
+            .rev()
+            .find(|y| (0..pixmap.width()).any(|x| pixmap.pixel(x, *y).unwrap().alpha() > 0))
+            .unwrap();
+
+        // The descenders reach well below the baseline and the bottom row is still blank
+        assert!(last_inked_row as f32 > metrics.ascent + size as f32 * 0.1);
+        assert!(last_inked_row < pixmap.height() - 1);
+        assert!(metrics.line_height > size as f32);
+    }
+
+    #[test]
+    fn test_degenerate_text() {
+        let mut state = AsyncState::new();
+        let font_info = load_test_font(&mut state.font_system);
+
+        for (text, size) in [
+            ("", 16),
+            (" ", 16),
+            ("\t \t", 16),
+            ("   ", 1),
+            ("x", 0),
+            ("", 0),
+        ] {
+            let measured = measure_string_size(
+                text,
+                &font_info,
+                size,
+                &mut state.font_system,
+                &mut BTreeSet::new(),
+            )
+            .unwrap();
+            let generated = generate_text(text, &font_info, size, &mut state).unwrap();
+            let generated = generated.downcast_ref::<CachedString>().unwrap();
+
+            assert!(generated.data.is_none(), "{:?} at size {}", text, size);
+            assert_eq!(
+                (generated.width as f32, generated.height as f32),
+                measured,
+                "{:?} at size {}",
+                text,
+                size
+            );
+        }
+
+        // A space still takes up room in the layout
+        let (width, height) = measure_string_size(
+            " ",
+            &font_info,
+            16,
+            &mut state.font_system,
+            &mut BTreeSet::new(),
+        )
+        .unwrap();
+        assert!(width > 0.0 && height > 0.0);
+    }
+
+    #[test]
+    fn test_take_prioritized() {
+        let mut pending = vec![1, 2, 3, 4, 5, 6];
+        let even = |v: &i32| v % 2 == 0;
+
+        assert_eq!(take_prioritized(&mut pending, 2, even), [2, 4]);
+        assert_eq!(pending, [6, 1, 3, 5]);
+
+        assert_eq!(take_prioritized(&mut pending, 3, even), [6, 1, 3]);
+        assert_eq!(pending, [5]);
+
+        assert!(take_prioritized(&mut pending, 0, even).is_empty());
+        assert_eq!(take_prioritized(&mut pending, 4, even), [5]);
+        assert!(pending.is_empty());
+    }
+
     #[test]
     fn test_srgb_to_linear() {
         assert_eq!(srgb_to_linear(0.0), 0.0);
         assert_eq!(srgb_to_linear(1.0), 1.0);
-        assert_eq!(srgb_to_linear(0.5), 0.21404114048207108);
+        assert_eq!(srgb_to_linear(0.5), 0.214_041_14);
     }
 
     #[test]
@@ -0,0 +1,72 @@
+//! Widgets that are interacted with as one, like a channel strip or a clip.
+//!
+//! [`Ui::group`] doesn't add an element of its own, so it can wrap widgets in any layout. It
+//! sums up how the elements with an id declared inside of it were interacted with in a
+//! [`GroupResponse`]. Anonymous elements, like plain text, aren't hit-tested and don't count.
+use crate::input::MouseButton;
+use crate::ui::Ui;
+use clay_layout::id::Id;
+use glam::Vec4;
+
+/// What [`Ui::group`] reports after a frame.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct GroupResponse {
+    /// An element of the group was clicked with the left button this frame.
+    pub any_clicked: bool,
+    /// What the closure of the group returned, true when one of its widgets changed.
+    pub any_changed: bool,
+    /// The mouse is over `rect`, gaps between the elements included.
+    pub hovered: bool,
+    /// Smallest rect around the elements of the group as (x0, y0, x1, y1), where they were
+    /// laid out in the last frame. `None` until they've been laid out once.
+    pub rect: Option<Vec4>,
+}
+
+/// Smallest rect around `a` and `b`, both as (x0, y0, x1, y1).
+fn union_rect(a: Vec4, b: Vec4) -> Vec4 {
+    Vec4::new(a.x.min(b.x), a.y.min(b.y), a.z.max(b.z), a.w.max(b.w))
+}
+
+impl Ui {
+    /// Declares the widgets added by `f` and reports how they were interacted with as a
+    /// whole. `f` returns whether any of its widgets changed, which most widgets return, like
+    /// `ui.checkbox("M", &mut muted) | ui.checkbox("S", &mut soloed)`.
+    pub fn group(&self, f: impl FnOnce(&Ui) -> bool) -> GroupResponse {
+        let start = self.frame_tree().len();
+        let any_changed = f(self);
+        let ids: Vec<Id> = self.frame_tree()[start..]
+            .iter()
+            .map(|node| node.id)
+            .collect();
+
+        let input = self.input();
+        let mut response = GroupResponse {
+            any_changed,
+            ..Default::default()
+        };
+        for id in ids {
+            response.any_clicked |= input.was_clicked(id.id.id, MouseButton::Left);
+            if let Some(rect) = self.last_frame_rect(id) {
+                response.rect = Some(response.rect.map_or(rect, |union| union_rect(union, rect)));
+            }
+        }
+        response.hovered = response.rect.is_some_and(|rect| input.is_hovered(rect));
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_union_rect() {
+        let a = Vec4::new(10.0, 20.0, 50.0, 40.0);
+        let b = Vec4::new(30.0, 0.0, 80.0, 30.0);
+        assert_eq!(union_rect(a, b), Vec4::new(10.0, 0.0, 80.0, 40.0));
+        assert_eq!(union_rect(a, a), a);
+        // Apart, the gap between them is included
+        let c = Vec4::new(100.0, 100.0, 110.0, 110.0);
+        assert_eq!(union_rect(a, c), Vec4::new(10.0, 20.0, 110.0, 110.0));
+    }
+}
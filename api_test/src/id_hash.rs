@@ -0,0 +1,63 @@
+//! How element ids are made from names.
+//!
+//! Ids key state that outlives a frame, such as scroll offsets and window positions, so they
+//! are computed here rather than by Clay, whose hash may change between versions. The hash is
+//! part of the crate's behavior and must not change:
+//!
+//! 1. The base is the 32-bit FNV-1a hash of the UTF-8 bytes of the name, with the offset basis
+//!    `0x811c9dc5` xor'ed with the seed.
+//! 2. The id continues the FNV-1a hash from the base over the index as 4 little-endian bytes.
+//!    Ids without an index use index 0.
+//! 3. Zero means "no id" to Clay, so a hash of zero becomes 1.
+//!
+//! The seed is 0 unless set with [`crate::ui::Ui::set_id_seed`].
+use clay_layout::Clay_ElementId;
+use clay_layout::id::Id;
+
+const FNV_OFFSET_BASIS: u32 = 0x811c_9dc5;
+const FNV_PRIME: u32 = 0x0100_0193;
+
+fn fnv1a(hash: u32, bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .fold(hash, |hash, byte| (hash ^ *byte as u32).wrapping_mul(FNV_PRIME))
+}
+
+/// The base hash of `name` and its id for `index`, neither of them zero.
+pub fn hash_id(name: &str, index: u32, seed: u32) -> (u32, u32) {
+    let base = fnv1a(FNV_OFFSET_BASIS ^ seed, name.as_bytes());
+    let id = fnv1a(base, &index.to_le_bytes());
+    (base.max(1), id.max(1))
+}
+
+/// The Clay id for the `index`th element named `name`.
+pub(crate) fn make_id(name: &str, index: u32, seed: u32) -> Id {
+    let (base, id) = hash_id(name, index, seed);
+    Id {
+        id: Clay_ElementId {
+            id,
+            offset: index,
+            baseId: base,
+            // Only Clay's own debug view reads this, see [`crate::id_path`] for names
+            stringId: "".into(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stable_hashes() {
+        // Plain FNV-1a for the base, these must never change
+        assert_eq!(hash_id("", 0, 0).0, 0x811c_9dc5);
+        assert_eq!(hash_id("a", 0, 0).0, 0xe40c_292c);
+        assert_eq!(hash_id("foobar", 0, 0).0, 0xbf9c_f968);
+
+        assert_eq!(hash_id("play", 0, 0), (0xc2cb_d863, 0x3acd_bb93));
+        assert_eq!(hash_id("play", 1, 0), (0xc2cb_d863, 0xeaa1_7c82));
+        assert_ne!(hash_id("play", 0, 0).1, hash_id("play", 0, 7).1);
+        assert_eq!(make_id("play", 3, 0).id.offset, 3);
+    }
+}
@@ -0,0 +1,77 @@
+//! Readable paths for element ids, for diagnostics.
+//!
+//! Ids are hashes, which say nothing about the widget they belong to when they show up in the
+//! inspector or an error message. Debug builds remember the name each id was made from and
+//! join the names of an element and its parents into a path such as
+//! `daw_ui_root/toolbar/transport/play`. Release builds keep nothing and show the hash.
+#[cfg(debug_assertions)]
+use std::collections::HashMap;
+
+#[derive(Default)]
+pub(crate) struct IdPaths {
+    /// Name each id was made from, the index appended as `name[index]` for indexed ids.
+    #[cfg(debug_assertions)]
+    names: HashMap<u32, String>,
+    /// Path of each element as of the last frame it was declared in.
+    #[cfg(debug_assertions)]
+    paths: HashMap<u32, String>,
+}
+
+impl IdPaths {
+    /// Remembers that `id` was made from `name`, which is only built the first time.
+    #[cfg_attr(not(debug_assertions), allow(unused_variables))]
+    pub(crate) fn name(&mut self, id: u32, name: impl FnOnce() -> String) {
+        #[cfg(debug_assertions)]
+        self.names.entry(id).or_insert_with(name);
+    }
+
+    /// Sets the path of the element `id` declared inside of `parent`.
+    #[cfg_attr(not(debug_assertions), allow(unused_variables))]
+    pub(crate) fn declare(&mut self, id: u32, parent: Option<u32>) {
+        #[cfg(debug_assertions)]
+        {
+            let name = self
+                .names
+                .get(&id)
+                .cloned()
+                .unwrap_or_else(|| format!("#{}", id));
+            let path = match parent.and_then(|parent| self.paths.get(&parent)) {
+                Some(parent) => format!("{}/{}", parent, name),
+                None => name,
+            };
+            self.paths.insert(id, path);
+        }
+    }
+
+    /// The path of `id`, or its hash as `#1234` when it isn't known.
+    pub(crate) fn describe(&self, id: u32) -> String {
+        #[cfg(debug_assertions)]
+        if let Some(path) = self.paths.get(&id).or_else(|| self.names.get(&id)) {
+            return path.clone();
+        }
+        format!("#{}", id)
+    }
+}
+
+#[cfg(all(test, debug_assertions))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paths() {
+        let mut paths = IdPaths::default();
+        paths.name(1, || "root".to_string());
+        paths.name(2, || "toolbar".to_string());
+        paths.name(3, || "play".to_string());
+        paths.name(3, || unreachable!());
+        paths.declare(1, None);
+        paths.declare(2, Some(1));
+        paths.declare(3, Some(2));
+        // Declared without a name, like ids that weren't made by the ui
+        paths.declare(4, Some(2));
+
+        assert_eq!(paths.describe(3), "root/toolbar/play");
+        assert_eq!(paths.describe(4), "root/toolbar/#4");
+        assert_eq!(paths.describe(5), "#5");
+    }
+}
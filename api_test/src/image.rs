@@ -0,0 +1,348 @@
+//! Image widgets.
+//!
+//! [`Ui::image`] declares an element of a given size showing an [`ImageInfo`], which is
+//! scaled into it by the renderer according to an [`ImageFit`]. Images decoded by the image
+//! cache are fetched with [`Ui::cached_image`].
+//!
+//! [`ImageEffects`] tint, gray out or fade an image while it's drawn. They're set per
+//! [`ImageInfo`], so a hovered or disabled variant of an icon shares the pixels of the icon.
+use crate::tiny_skia_renderer::RenderImage;
+use crate::ui::{Ui, rgb};
+use clay_layout::color::Color as ClayColor;
+use clay_layout::{Declaration, fixed};
+use glam::Vec2;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use tiny_skia::{Pixmap, PremultipliedColorU8};
+
+/// How an image is scaled into the element showing it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageFit {
+    /// Stretched to the size of the element, ignoring its aspect ratio.
+    #[default]
+    Fill,
+    /// As large as fits within the element with its aspect ratio kept, centered.
+    Contain,
+    /// As small as covers the whole element with its aspect ratio kept, centered and cut off
+    /// at the edges of the element.
+    Cover,
+}
+
+/// Color changes applied to the pixels of an image as it's drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageEffects {
+    /// Multiplied with the pixels, including their alpha. White leaves them as they are.
+    pub tint: ClayColor,
+    /// Replaces the colors with their luminance before tinting.
+    pub grayscale: bool,
+    /// 0.0 for invisible to 1.0 for opaque.
+    pub opacity: f32,
+}
+
+impl Default for ImageEffects {
+    fn default() -> Self {
+        Self {
+            tint: rgb(255, 255, 255),
+            grayscale: false,
+            opacity: 1.0,
+        }
+    }
+}
+
+impl ImageEffects {
+    /// True when the colors of the pixels change, not only their opacity.
+    pub(crate) fn changes_colors(&self) -> bool {
+        self.grayscale || self.tint != rgb(255, 255, 255)
+    }
+
+    /// `pixel` with the grayscale and tint applied. The opacity is left to the renderer.
+    pub(crate) fn apply(&self, pixel: PremultipliedColorU8) -> PremultipliedColorU8 {
+        let (mut r, mut g, mut b) = (
+            pixel.red() as f32,
+            pixel.green() as f32,
+            pixel.blue() as f32,
+        );
+        if self.grayscale {
+            // Rec. 709 luma, which stays premultiplied as the weights add up to one
+            let luma = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+            (r, g, b) = (luma, luma, luma);
+        }
+        let alpha = self.tint.a.clamp(0.0, 255.0) / 255.0;
+        let channel =
+            |value: f32, tint: f32| (value * tint.clamp(0.0, 255.0) / 255.0 * alpha).round() as u8;
+        let a = (pixel.alpha() as f32 * alpha).round() as u8;
+        PremultipliedColorU8::from_rgba(
+            channel(r, self.tint.r).min(a),
+            channel(g, self.tint.g).min(a),
+            channel(b, self.tint.b).min(a),
+            a,
+        )
+        .unwrap_or(PremultipliedColorU8::TRANSPARENT)
+    }
+}
+
+/// Pixels of an image, how they are fit into the element showing them and the effects they're
+/// drawn with. Cloning shares the pixels.
+#[derive(Debug, Clone)]
+pub struct ImageInfo {
+    pixmap: Arc<Pixmap>,
+    /// Hash of the pixels, so frames showing the same image hash the same in every run.
+    pixels_hash: u64,
+    fit: ImageFit,
+    effects: ImageEffects,
+}
+
+impl ImageInfo {
+    pub fn new(pixmap: Pixmap) -> Self {
+        Self::from(Arc::new(pixmap))
+    }
+
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.pixmap.width() as f32, self.pixmap.height() as f32)
+    }
+
+    /// The image with its pixels multiplied by `color`.
+    pub fn tint(mut self, color: ClayColor) -> Self {
+        self.effects.tint = color;
+        self
+    }
+
+    /// The image in shades of gray, for disabled icons for example.
+    pub fn grayscale(mut self) -> Self {
+        self.effects.grayscale = true;
+        self
+    }
+
+    /// The image drawn with `opacity` from 0.0 to 1.0.
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.effects.opacity = opacity.clamp(0.0, 1.0);
+        self
+    }
+}
+
+/// Hashes every pixel once, when the image is created.
+impl From<Arc<Pixmap>> for ImageInfo {
+    fn from(pixmap: Arc<Pixmap>) -> Self {
+        let mut hasher = DefaultHasher::new();
+        (pixmap.width(), pixmap.height(), pixmap.data()).hash(&mut hasher);
+        Self {
+            pixels_hash: hasher.finish(),
+            pixmap,
+            fit: ImageFit::default(),
+            effects: ImageEffects::default(),
+        }
+    }
+}
+
+impl RenderImage for ImageInfo {
+    fn image(&self) -> Option<(&Pixmap, ImageFit)> {
+        Some((&self.pixmap, self.fit))
+    }
+
+    fn effects(&self) -> ImageEffects {
+        self.effects
+    }
+
+    fn pixels_id(&self) -> u64 {
+        self.pixels_hash
+    }
+}
+
+/// Where an image of `image` size goes for an element at `bounds` (x, y, width, height), as
+/// (x, y, width, height). With [`ImageFit::Cover`] this extends past `bounds`.
+pub(crate) fn fit_rect(
+    image: (f32, f32),
+    bounds: (f32, f32, f32, f32),
+    fit: ImageFit,
+) -> (f32, f32, f32, f32) {
+    let (x, y, width, height) = bounds;
+    let scale_x = width / image.0;
+    let scale_y = height / image.1;
+    let scale = match fit {
+        ImageFit::Fill => return bounds,
+        ImageFit::Contain => scale_x.min(scale_y),
+        ImageFit::Cover => scale_x.max(scale_y),
+    };
+    let (fit_width, fit_height) = (image.0 * scale, image.1 * scale);
+    (
+        x + (width - fit_width) * 0.5,
+        y + (height - fit_height) * 0.5,
+        fit_width,
+        fit_height,
+    )
+}
+
+impl Ui {
+    /// An element of `size` showing `image` scaled into it with `fit`, drawn with the effects
+    /// set on `image`.
+    pub fn image(&self, image: &ImageInfo, size: Vec2, fit: ImageFit) {
+        let info = self.alloc_image(ImageInfo {
+            fit,
+            ..image.clone()
+        });
+
+        let mut decl = Declaration::new();
+        decl.layout()
+            .width(fixed!(size.x))
+            .height(fixed!(size.y))
+            .end()
+            .image()
+            .data(info)
+            .end();
+        self.with_layout(&decl, |_| {});
+    }
+
+    /// The PNG file at `path` once the image cache has decoded it. The first call starts
+    /// decoding it in the background, see [`Ui::prewarm_images`].
+    pub fn cached_image(&self, path: &str) -> Option<ImageInfo> {
+        let cache = self.image_cache();
+        cache.request(path);
+        cache.get(path).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::TextGenerator;
+    use crate::tiny_skia_renderer::clay_tiny_skia_render;
+    use background_worker::WorkSystem;
+    use clay_layout::color::Color as ClayColor;
+    use clay_layout::math::BoundingBox;
+    use clay_layout::render_commands::{CornerRadii, Image, RenderCommand, RenderCommandConfig};
+
+    #[test]
+    fn test_fit_rect() {
+        let bounds = (10.0, 20.0, 100.0, 50.0);
+        assert_eq!(fit_rect((40.0, 40.0), bounds, ImageFit::Fill), bounds);
+        // A square image in a wide element is limited by the height, or the width to cover it
+        assert_eq!(
+            fit_rect((40.0, 40.0), bounds, ImageFit::Contain),
+            (35.0, 20.0, 50.0, 50.0)
+        );
+        assert_eq!(
+            fit_rect((40.0, 40.0), bounds, ImageFit::Cover),
+            (10.0, -5.0, 100.0, 100.0)
+        );
+    }
+
+    #[test]
+    fn test_effects_apply() {
+        let pixel = PremultipliedColorU8::from_rgba(100, 50, 0, 200).unwrap();
+        assert_eq!(ImageEffects::default().apply(pixel), pixel);
+
+        let gray = ImageEffects {
+            grayscale: true,
+            ..Default::default()
+        };
+        let luma = gray.apply(pixel);
+        assert_eq!((luma.red(), luma.green(), luma.blue()), (57, 57, 57));
+        assert_eq!(luma.alpha(), 200);
+
+        // Half transparent red keeps the red channel and halves the alpha
+        let tint = ImageEffects {
+            tint: ClayColor::rgba(255.0, 0.0, 0.0, 127.5),
+            ..Default::default()
+        };
+        let tinted = tint.apply(pixel);
+        assert_eq!(
+            (tinted.red(), tinted.green(), tinted.blue(), tinted.alpha()),
+            (50, 0, 0, 100)
+        );
+    }
+
+    /// Draws a 2x2 pixmap filled with `color` as `image` over the whole of a 4x4 target.
+    fn render_image(color: tiny_skia::Color, image: impl FnOnce(ImageInfo) -> ImageInfo) -> Pixmap {
+        let worker = WorkSystem::new(1);
+        let text_generator = TextGenerator::new(&worker);
+        let mut pixmap = Pixmap::new(2, 2).unwrap();
+        pixmap.fill(color);
+        let image = image(ImageInfo::new(pixmap));
+
+        let commands = [RenderCommand::<ImageInfo, ()> {
+            bounding_box: BoundingBox::new(0.0, 0.0, 4.0, 4.0),
+            config: RenderCommandConfig::Image(Image {
+                background_color: ClayColor::rgba(0.0, 0.0, 0.0, 0.0),
+                corner_radii: CornerRadii {
+                    top_left: 0.0,
+                    top_right: 0.0,
+                    bottom_left: 0.0,
+                    bottom_right: 0.0,
+                },
+                data: &image,
+            }),
+            id: 1,
+            z_index: 0,
+        }];
+        let mut target = Pixmap::new(4, 4).unwrap();
+        clay_tiny_skia_render(&mut target, &commands, &text_generator);
+        target
+    }
+
+    #[test]
+    fn test_pixels_id() {
+        let pixmap = |color: tiny_skia::Color| {
+            let mut pixmap = Pixmap::new(4, 4).unwrap();
+            pixmap.fill(color);
+            pixmap
+        };
+        let red = ImageInfo::new(pixmap(tiny_skia::Color::from_rgba8(255, 0, 0, 255)));
+        let also_red = ImageInfo::new(pixmap(tiny_skia::Color::from_rgba8(255, 0, 0, 255)));
+        let blue = ImageInfo::new(pixmap(tiny_skia::Color::from_rgba8(0, 0, 255, 255)));
+        // Separate buffers with the same pixels are the same image
+        assert_eq!(red.pixels_id(), also_red.pixels_id());
+        assert_ne!(red.pixels_id(), blue.pixels_id());
+        assert_eq!(red.clone().grayscale().pixels_id(), red.pixels_id());
+    }
+
+    #[test]
+    fn test_image_effects_are_drawn() {
+        let red = tiny_skia::Color::from_rgba8(255, 0, 0, 255);
+        let pixel = render_image(red, |image| image.opacity(0.5))
+            .pixel(1, 1)
+            .unwrap();
+        assert_eq!((pixel.red(), pixel.alpha()), (128, 128));
+
+        let pixel = render_image(red, |image| image.grayscale().tint(rgb(0, 255, 0)))
+            .pixel(1, 1)
+            .unwrap();
+        assert_eq!((pixel.red(), pixel.green(), pixel.blue()), (0, 54, 0));
+        assert_eq!(pixel.alpha(), 255);
+    }
+
+    #[test]
+    fn test_covering_image_is_clipped() {
+        let worker = WorkSystem::new(1);
+        let text_generator = TextGenerator::new(&worker);
+        let mut pixmap = Pixmap::new(2, 2).unwrap();
+        pixmap.fill(tiny_skia::Color::from_rgba8(0, 255, 0, 255));
+        let image = ImageInfo {
+            fit: ImageFit::Cover,
+            ..ImageInfo::new(pixmap)
+        };
+
+        let commands = [RenderCommand::<ImageInfo, ()> {
+            bounding_box: BoundingBox::new(2.0, 4.0, 10.0, 4.0),
+            config: RenderCommandConfig::Image(Image {
+                background_color: ClayColor::rgba(0.0, 0.0, 0.0, 0.0),
+                corner_radii: CornerRadii {
+                    top_left: 0.0,
+                    top_right: 0.0,
+                    bottom_left: 0.0,
+                    bottom_right: 0.0,
+                },
+                data: &image,
+            }),
+            id: 1,
+            z_index: 0,
+        }];
+        let mut target = Pixmap::new(16, 16).unwrap();
+        clay_tiny_skia_render(&mut target, &commands, &text_generator);
+
+        // Scaled to 10x10 but only drawn within the 10x4 element
+        assert_eq!(target.pixel(6, 5).unwrap().green(), 255);
+        assert_eq!(target.pixel(6, 2).unwrap().alpha(), 0);
+        assert_eq!(target.pixel(6, 9).unwrap().alpha(), 0);
+    }
+}
@@ -0,0 +1,155 @@
+use crate::image::ImageInfo;
+use background_worker::{
+    AnySend, BoxAnySend, CallbackError, TryRecvError, WorkHandle, WorkSystem, WorkerResult,
+};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tiny_skia::Pixmap;
+
+/// Number of image loads handed to the workers per frame. Loads are queued beyond this so a
+/// large prewarm doesn't block the ui thread on the bounded work queue.
+const IMAGE_LOADS_PER_FRAME: usize = 2;
+
+enum ImageEntry {
+    Queued,
+    Loading(WorkHandle),
+    Loaded(ImageInfo),
+    Failed(String),
+}
+
+/// Cache statistics reported by the debug tooling.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct ImageCacheStats {
+    pub(crate) loaded: usize,
+    /// Queued or currently being decoded.
+    pub(crate) loading: usize,
+    pub(crate) failed: usize,
+}
+
+fn job_load_image(data: BoxAnySend, _state: Arc<Mutex<AnySend>>) -> WorkerResult {
+    let path = data.downcast::<String>().unwrap();
+    // The pixels are hashed here to keep that off the ui thread
+    match Pixmap::load_png(&*path) {
+        Ok(pixmap) => Ok(Box::new(ImageInfo::new(pixmap))),
+        Err(e) => Err(CallbackError::Other(format!("{}: {}", path, e))),
+    }
+}
+
+/// Decodes images on the background workers and keeps them by path.
+pub(crate) struct ImageCache {
+    entries: HashMap<String, ImageEntry>,
+    queue: VecDeque<String>,
+    load_image_async_id: usize,
+}
+
+impl ImageCache {
+    pub(crate) fn new(bg_worker: &WorkSystem) -> Self {
+        let state: Arc<Mutex<AnySend>> = Arc::new(Mutex::new(()));
+        let load_image_async_id = bg_worker.register_callback_with_state(job_load_image, state);
+
+        Self {
+            entries: HashMap::new(),
+            queue: VecDeque::new(),
+            load_image_async_id,
+        }
+    }
+
+    /// Requests `path` to be loaded unless it's already cached or in progress.
+    pub(crate) fn request(&mut self, path: &str) {
+        if !self.entries.contains_key(path) {
+            self.entries.insert(path.to_string(), ImageEntry::Queued);
+            self.queue.push_back(path.to_string());
+        }
+    }
+
+    pub(crate) fn get(&self, path: &str) -> Option<&ImageInfo> {
+        match self.entries.get(path) {
+            Some(ImageEntry::Loaded(image)) => Some(image),
+            _ => None,
+        }
+    }
+
+    /// Collects finished loads and submits queued ones. Returns the errors of the loads that
+    /// failed.
+    pub(crate) fn update(&mut self, bg_worker: &WorkSystem) -> Vec<String> {
+        let mut errors = Vec::new();
+        for entry in self.entries.values_mut() {
+            let ImageEntry::Loading(receiver) = entry else {
+                continue;
+            };
+
+            *entry = match receiver.try_recv() {
+                Ok(Ok(data)) => ImageEntry::Loaded(*data.downcast::<ImageInfo>().unwrap()),
+                Ok(Err(e)) => {
+                    errors.push(format!("Unable to load image {}", e));
+                    ImageEntry::Failed(e.to_string())
+                }
+                Err(TryRecvError::Empty) => continue,
+                Err(TryRecvError::Disconnected) => {
+                    ImageEntry::Failed("image loader stopped".to_string())
+                }
+            };
+        }
+
+        for _ in 0..IMAGE_LOADS_PER_FRAME {
+            let Some(path) = self.queue.pop_front() else {
+                break;
+            };
+
+            let receiver = bg_worker.add_work(self.load_image_async_id, path.clone());
+            self.entries.insert(path, ImageEntry::Loading(receiver));
+        }
+        errors
+    }
+
+    pub(crate) fn stats(&self) -> ImageCacheStats {
+        let mut stats = ImageCacheStats::default();
+        for entry in self.entries.values() {
+            match entry {
+                ImageEntry::Queued | ImageEntry::Loading(_) => stats.loading += 1,
+                ImageEntry::Loaded(_) => stats.loaded += 1,
+                ImageEntry::Failed(_) => stats.failed += 1,
+            }
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_and_fail() {
+        let dir = std::env::temp_dir().join(format!("yaui_images_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("red.png");
+        let mut pixmap = Pixmap::new(4, 2).unwrap();
+        pixmap.fill(tiny_skia::Color::from_rgba8(255, 0, 0, 255));
+        pixmap.save_png(&path).unwrap();
+        let path = path.to_str().unwrap();
+
+        let worker = WorkSystem::new(1);
+        let mut cache = ImageCache::new(&worker);
+        cache.request(path);
+        cache.request("does/not/exist.png");
+        cache.request(path);
+        assert_eq!(cache.stats().loading, 2);
+
+        for _ in 0..500 {
+            cache.update(&worker);
+            if cache.stats().loading == 0 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+
+        let stats = cache.stats();
+        assert_eq!((stats.loaded, stats.failed), (1, 1));
+        assert_eq!(
+            cache.get(path).map(|image| image.size()),
+            Some(glam::Vec2::new(4.0, 2.0))
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
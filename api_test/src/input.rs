@@ -0,0 +1,660 @@
+//! Input events fed by the host and the hooks that see them before widgets do.
+//!
+//! The host pushes raw events with [`Ui::push_event`] between frames. They are processed in
+//! [`Ui::begin`]: every event is first passed through the event hooks in registration order, and
+//! only events that no hook consumed update the input state that widgets read. Events the ui
+//! derives from the raw input, such as [`Event::Clicked`], go through the same hooks, so a hook
+//! can also observe or swallow widget interactions.
+use crate::ui::Ui;
+use glam::{Vec2, Vec4};
+use std::collections::{HashMap, HashSet};
+use std::ops::{BitOr, BitOrAssign};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+}
+
+impl MouseButton {
+    fn index(self) -> usize {
+        match self {
+            MouseButton::Left => 0,
+            MouseButton::Right => 1,
+            MouseButton::Middle => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Enter,
+    Escape,
+    Tab,
+    Backspace,
+    Delete,
+    Space,
+    Shift,
+    Control,
+    Alt,
+    Super,
+    /// Letter and digit keys, letters are lower case.
+    Character(char),
+}
+
+/// Set of modifier keys held down.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Modifiers(u8);
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers(0);
+    pub const SHIFT: Modifiers = Modifiers(1);
+    pub const CTRL: Modifiers = Modifiers(2);
+    pub const ALT: Modifiers = Modifiers(4);
+    /// The Windows key, or Cmd on macOS.
+    pub const SUPER: Modifiers = Modifiers(8);
+    /// The key used for shortcuts and multi-select on the current platform: Cmd on macOS and
+    /// Ctrl everywhere else.
+    pub const COMMAND: Modifiers = if cfg!(target_os = "macos") {
+        Modifiers::SUPER
+    } else {
+        Modifiers::CTRL
+    };
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// True if all modifiers in `other` are held, others may be held as well.
+    pub fn contains(self, other: Modifiers) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn shift(self) -> bool {
+        self.contains(Modifiers::SHIFT)
+    }
+
+    /// See [`Modifiers::COMMAND`].
+    pub fn command(self) -> bool {
+        self.contains(Modifiers::COMMAND)
+    }
+
+    fn from_key(key: Key) -> Modifiers {
+        match key {
+            Key::Shift => Modifiers::SHIFT,
+            Key::Control => Modifiers::CTRL,
+            Key::Alt => Modifiers::ALT,
+            Key::Super => Modifiers::SUPER,
+            _ => Modifiers::NONE,
+        }
+    }
+}
+
+impl BitOr for Modifiers {
+    type Output = Modifiers;
+
+    fn bitor(self, rhs: Modifiers) -> Modifiers {
+        Modifiers(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Modifiers {
+    fn bitor_assign(&mut self, rhs: Modifiers) {
+        self.0 |= rhs.0;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    /// Mouse position in window coordinates.
+    MouseMove {
+        x: f32,
+        y: f32,
+    },
+    MouseDown {
+        button: MouseButton,
+    },
+    MouseUp {
+        button: MouseButton,
+    },
+    Scroll {
+        dx: f32,
+        dy: f32,
+    },
+    KeyDown {
+        key: Key,
+    },
+    KeyUp {
+        key: Key,
+    },
+    /// Text entered by the user, after keyboard layout and IME processing.
+    Text {
+        ch: char,
+    },
+    /// Generated by the ui when a mouse button is pressed and released over the same element.
+    /// `id` is the element id as found in [`clay_layout::id::Id`], `modifiers` are the ones
+    /// held on release.
+    Clicked {
+        id: u32,
+        button: MouseButton,
+        modifiers: Modifiers,
+    },
+}
+
+/// Mouse cursor shape, declared per area with `cursor:` in [`crate::area!`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CursorIcon {
+    #[default]
+    Default,
+    Text,
+    Crosshair,
+    /// Over something that can be clicked, like a link.
+    Pointer,
+    Grab,
+    Grabbing,
+    ResizeHorizontal,
+    ResizeVertical,
+    /// Resizing both ways from a corner.
+    ResizeDiagonal,
+    Move,
+    /// No cursor, for example while a value is scrubbed.
+    Hidden,
+}
+
+/// What a hook wants to happen with an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventFlow {
+    /// Pass the event on to the next hook and then the widgets.
+    Continue,
+    /// Stop the event here, later hooks and widgets never see it.
+    Consume,
+}
+
+/// Read-only view of the input state handed to event hooks.
+#[derive(Debug, Clone, Copy)]
+pub struct EventContext {
+    pub frame: u64,
+    pub mouse_pos: Vec2,
+    pub mouse_down: [bool; 3],
+    pub modifiers: Modifiers,
+    /// Top-most element with an id under the mouse, as laid out in the previous frame.
+    pub hovered: Option<u32>,
+}
+
+/// Handle returned by [`Ui::add_event_hook`] for removing the hook again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EventHookId(u32);
+
+pub(crate) type EventHook = Box<dyn FnMut(&Event, &EventContext) -> EventFlow>;
+
+#[derive(Default)]
+pub(crate) struct EventHooks {
+    hooks: Vec<(EventHookId, EventHook)>,
+    next_id: u32,
+}
+
+impl EventHooks {
+    pub(crate) fn add(&mut self, hook: EventHook) -> EventHookId {
+        let id = EventHookId(self.next_id);
+        self.next_id += 1;
+        self.hooks.push((id, hook));
+        id
+    }
+
+    pub(crate) fn remove(&mut self, id: EventHookId) -> bool {
+        let len = self.hooks.len();
+        self.hooks.retain(|(hook_id, _)| *hook_id != id);
+        self.hooks.len() != len
+    }
+
+    /// Runs the hooks in registration order. Returns false if one of them consumed the event.
+    fn dispatch(&mut self, event: &Event, context: &EventContext) -> bool {
+        self.hooks
+            .iter_mut()
+            .all(|(_, hook)| hook(event, context) == EventFlow::Continue)
+    }
+}
+
+/// Input state after the events of the current frame have been processed.
+#[derive(Debug, Default)]
+pub(crate) struct InputState {
+    pub(crate) mouse_pos: Vec2,
+    /// How far the mouse moved this frame.
+    pub(crate) mouse_delta: Vec2,
+    pub(crate) mouse_down: [bool; 3],
+    /// Buttons that went down this frame.
+    pub(crate) mouse_pressed: [bool; 3],
+    pub(crate) scroll: Vec2,
+    pub(crate) keys_pressed: Vec<Key>,
+    pub(crate) keys_down: Vec<Key>,
+    pub(crate) text: String,
+    /// Element the left button was pressed on, kept until it's released.
+    pub(crate) pressed_id: Option<u32>,
+    /// Elements clicked this frame (only the ones whose `Clicked` event wasn't consumed).
+    pub(crate) clicked: Vec<(u32, MouseButton, Modifiers)>,
+    press_targets: [Option<u32>; 3],
+    /// Element rectangles (x0, y0, x1, y1) of the previous frame in declaration order, so later
+    /// entries are drawn on top of earlier ones.
+    pub(crate) hit_boxes: Vec<(u32, Vec4)>,
+    /// Cursors declared by elements of the previous frame.
+    pub(crate) cursor_zones: HashMap<u32, CursorIcon>,
+    /// Elements of a layer that captures the mouse, such as a modal dialog, as of the previous
+    /// frame. Everything else is ignored by hit testing and hover while set.
+    pub(crate) capture: Option<HashSet<u32>>,
+}
+
+fn contains(aabb: Vec4, pos: Vec2) -> bool {
+    pos.x >= aabb.x && pos.y >= aabb.y && pos.x < aabb.z && pos.y < aabb.w
+}
+
+impl InputState {
+    /// False for elements outside the layer capturing the mouse.
+    pub(crate) fn accepts(&self, id: u32) -> bool {
+        self.capture.as_ref().is_none_or(|ids| ids.contains(&id))
+    }
+
+    /// Rectangle of `id` in the previous frame.
+    pub(crate) fn rect_of(&self, id: u32) -> Option<Vec4> {
+        self.hit_boxes
+            .iter()
+            .find(|(hit_id, _)| *hit_id == id)
+            .map(|(_, aabb)| *aabb)
+    }
+
+    /// Top-most element under `pos`.
+    pub(crate) fn hit_test(&self, pos: Vec2) -> Option<u32> {
+        self.hit_boxes
+            .iter()
+            .rev()
+            .find(|(id, aabb)| self.accepts(*id) && contains(*aabb, pos))
+            .map(|(id, _)| *id)
+    }
+
+    /// Cursor of the top-most element under `pos` that declares one. While the left button is
+    /// held, the element it was pressed on keeps its cursor wherever the mouse goes.
+    pub(crate) fn cursor_at(&self, pos: Vec2) -> CursorIcon {
+        if self.button_down(MouseButton::Left)
+            && let Some(cursor) = self.pressed_id.and_then(|id| self.cursor_zones.get(&id))
+        {
+            return *cursor;
+        }
+        self.hit_boxes
+            .iter()
+            .rev()
+            .filter(|(id, aabb)| self.accepts(*id) && contains(*aabb, pos))
+            .find_map(|(id, _)| self.cursor_zones.get(id).copied())
+            .unwrap_or_default()
+    }
+
+    /// Whether `button` is held down.
+    pub(crate) fn button_down(&self, button: MouseButton) -> bool {
+        self.mouse_down[button.index()]
+    }
+
+    /// Whether `button` went down this frame.
+    pub(crate) fn button_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_pressed[button.index()]
+    }
+
+    /// True if `id` was clicked with `button` this frame.
+    pub(crate) fn was_clicked(&self, id: u32, button: MouseButton) -> bool {
+        self.clicked
+            .iter()
+            .any(|(clicked, clicked_button, _)| *clicked == id && *clicked_button == button)
+    }
+
+    /// Modifiers held when `id` was clicked with `button` this frame, `None` if it wasn't.
+    pub(crate) fn click_modifiers(&self, id: u32, button: MouseButton) -> Option<Modifiers> {
+        self.clicked
+            .iter()
+            .find(|(clicked, clicked_button, _)| *clicked == id && *clicked_button == button)
+            .map(|(_, _, modifiers)| *modifiers)
+    }
+
+    /// Modifier keys currently held down.
+    pub(crate) fn modifiers(&self) -> Modifiers {
+        self.keys_down.iter().fold(Modifiers::NONE, |mods, key| {
+            mods | Modifiers::from_key(*key)
+        })
+    }
+
+    pub(crate) fn is_hovered(&self, aabb: Vec4) -> bool {
+        contains(aabb, self.mouse_pos)
+    }
+
+    /// Wheel movement of this frame if the mouse is over `aabb`. Nothing is left for the
+    /// elements asking after it, so of nested scrolled elements only the innermost one scrolls
+    /// as long as it's asked first.
+    pub(crate) fn take_scroll(&mut self, aabb: Vec4) -> Vec2 {
+        if !self.is_hovered(aabb) {
+            return Vec2::ZERO;
+        }
+        std::mem::take(&mut self.scroll)
+    }
+
+    fn context(&self, frame: u64) -> EventContext {
+        EventContext {
+            frame,
+            mouse_pos: self.mouse_pos,
+            mouse_down: self.mouse_down,
+            modifiers: self.modifiers(),
+            hovered: self.hit_test(self.mouse_pos),
+        }
+    }
+
+    /// Clears the per-frame state before the events of a new frame are applied.
+    pub(crate) fn begin_frame(&mut self) {
+        self.scroll = Vec2::ZERO;
+        self.mouse_delta = Vec2::ZERO;
+        self.mouse_pressed = [false; 3];
+        self.keys_pressed.clear();
+        self.text.clear();
+        self.clicked.clear();
+    }
+
+    /// Runs `event` through the hooks and applies it if nothing consumed it. Events generated
+    /// from it are dispatched right away.
+    pub(crate) fn process(&mut self, event: Event, hooks: &mut EventHooks, frame: u64) {
+        if !hooks.dispatch(&event, &self.context(frame)) {
+            return;
+        }
+
+        match event {
+            Event::MouseMove { x, y } => {
+                let pos = Vec2::new(x, y);
+                self.mouse_delta += pos - self.mouse_pos;
+                self.mouse_pos = pos;
+            }
+            Event::MouseDown { button } => {
+                self.mouse_down[button.index()] = true;
+                self.mouse_pressed[button.index()] = true;
+                let target = self.hit_test(self.mouse_pos);
+                self.press_targets[button.index()] = target;
+                if button == MouseButton::Left {
+                    self.pressed_id = target;
+                }
+            }
+            Event::MouseUp { button } => {
+                self.mouse_down[button.index()] = false;
+                let target = self.press_targets[button.index()].take();
+                if button == MouseButton::Left {
+                    self.pressed_id = None;
+                }
+
+                if let Some(id) = target.filter(|id| Some(*id) == self.hit_test(self.mouse_pos)) {
+                    let modifiers = self.modifiers();
+                    self.process(
+                        Event::Clicked {
+                            id,
+                            button,
+                            modifiers,
+                        },
+                        hooks,
+                        frame,
+                    );
+                }
+            }
+            Event::Scroll { dx, dy } => self.scroll += Vec2::new(dx, dy),
+            Event::KeyDown { key } => {
+                self.keys_pressed.push(key);
+                if !self.keys_down.contains(&key) {
+                    self.keys_down.push(key);
+                }
+            }
+            Event::KeyUp { key } => self.keys_down.retain(|k| *k != key),
+            Event::Text { ch } => self.text.push(ch),
+            Event::Clicked {
+                id,
+                button,
+                modifiers,
+            } => self.clicked.push((id, button, modifiers)),
+        }
+    }
+}
+
+impl Ui {
+    /// Registers a hook that sees every input event before the widgets do. Hooks run in the
+    /// order they were added and returning [`EventFlow::Consume`] stops the event from reaching
+    /// later hooks and the widgets. Useful for analytics, macro recording or global gestures.
+    pub fn add_event_hook(
+        &self,
+        hook: impl FnMut(&Event, &EventContext) -> EventFlow + 'static,
+    ) -> EventHookId {
+        self.event_hooks().add(Box::new(hook))
+    }
+
+    /// Modifier keys currently held down.
+    pub fn modifiers(&self) -> Modifiers {
+        self.input().modifiers()
+    }
+
+    /// True if `key` was pressed this frame while exactly `modifiers` were held, for
+    /// shortcuts such as `ui.key_pressed_with(Modifiers::COMMAND, Key::Character('z'))`.
+    pub fn key_pressed_with(&self, modifiers: Modifiers, key: Key) -> bool {
+        let input = self.input();
+        input.modifiers() == modifiers && input.keys_pressed.contains(&key)
+    }
+
+    /// Removes a hook added with [`Ui::add_event_hook`]. Returns false if it was already removed.
+    pub fn remove_event_hook(&self, id: EventHookId) -> bool {
+        self.event_hooks().remove(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn input_with_boxes() -> InputState {
+        InputState {
+            // A panel with a button inside of it
+            hit_boxes: vec![
+                (1, Vec4::new(0.0, 0.0, 100.0, 100.0)),
+                (2, Vec4::new(10.0, 10.0, 50.0, 30.0)),
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn click(input: &mut InputState, hooks: &mut EventHooks, x: f32, y: f32) {
+        input.process(Event::MouseMove { x, y }, hooks, 0);
+        input.process(
+            Event::MouseDown {
+                button: MouseButton::Left,
+            },
+            hooks,
+            0,
+        );
+        input.process(
+            Event::MouseUp {
+                button: MouseButton::Left,
+            },
+            hooks,
+            0,
+        );
+    }
+
+    #[test]
+    fn test_click_hits_topmost() {
+        let mut input = input_with_boxes();
+        let mut hooks = EventHooks::default();
+
+        click(&mut input, &mut hooks, 20.0, 20.0);
+        assert_eq!(input.clicked, [(2, MouseButton::Left, Modifiers::NONE)]);
+
+        input.begin_frame();
+        click(&mut input, &mut hooks, 80.0, 80.0);
+        assert_eq!(input.clicked, [(1, MouseButton::Left, Modifiers::NONE)]);
+
+        // Releasing outside of the pressed element isn't a click
+        input.begin_frame();
+        input.process(Event::MouseMove { x: 20.0, y: 20.0 }, &mut hooks, 0);
+        input.process(
+            Event::MouseDown {
+                button: MouseButton::Left,
+            },
+            &mut hooks,
+            0,
+        );
+        assert_eq!(input.pressed_id, Some(2));
+        input.process(Event::MouseMove { x: 80.0, y: 80.0 }, &mut hooks, 0);
+        input.process(
+            Event::MouseUp {
+                button: MouseButton::Left,
+            },
+            &mut hooks,
+            0,
+        );
+        assert!(input.clicked.is_empty());
+        assert_eq!(input.pressed_id, None);
+    }
+
+    #[test]
+    fn test_capture() {
+        let mut input = input_with_boxes();
+        let mut hooks = EventHooks::default();
+        // A dialog declared before the panel but capturing the mouse
+        input
+            .hit_boxes
+            .insert(0, (3, Vec4::new(0.0, 0.0, 40.0, 40.0)));
+        input.capture = Some(HashSet::from([3]));
+
+        click(&mut input, &mut hooks, 20.0, 20.0);
+        assert_eq!(input.clicked, [(3, MouseButton::Left, Modifiers::NONE)]);
+
+        input.begin_frame();
+        click(&mut input, &mut hooks, 80.0, 80.0);
+        assert!(input.clicked.is_empty());
+        assert!(!input.accepts(1));
+    }
+
+    #[test]
+    fn test_hooks_observe_and_consume() {
+        let mut input = input_with_boxes();
+        let mut hooks = EventHooks::default();
+
+        let recorded = Rc::new(RefCell::new(Vec::new()));
+        let recorder = recorded.clone();
+        hooks.add(Box::new(move |event, _| {
+            recorder.borrow_mut().push(*event);
+            EventFlow::Continue
+        }));
+
+        // Swallow clicks on the button and all scrolling
+        let blocker = hooks.add(Box::new(|event, context| match event {
+            Event::Clicked { id: 2, .. } => EventFlow::Consume,
+            Event::Scroll { .. } if context.hovered == Some(2) => EventFlow::Consume,
+            _ => EventFlow::Continue,
+        }));
+
+        click(&mut input, &mut hooks, 20.0, 20.0);
+        input.process(Event::Scroll { dx: 0.0, dy: 1.0 }, &mut hooks, 0);
+        assert!(input.clicked.is_empty());
+        assert_eq!(input.scroll, Vec2::ZERO);
+
+        // The recorder runs first so it still saw everything
+        assert_eq!(recorded.borrow().len(), 5);
+        assert!(matches!(recorded.borrow()[3], Event::Clicked { id: 2, .. }));
+
+        assert!(hooks.remove(blocker));
+        assert!(!hooks.remove(blocker));
+        click(&mut input, &mut hooks, 20.0, 20.0);
+        assert_eq!(input.clicked, [(2, MouseButton::Left, Modifiers::NONE)]);
+    }
+
+    #[test]
+    fn test_cursor_zones() {
+        let mut input = input_with_boxes();
+        // A resize edge on the right of the panel, drawn after the button
+        input
+            .hit_boxes
+            .push((3, Vec4::new(90.0, 0.0, 100.0, 100.0)));
+        input.cursor_zones.insert(1, CursorIcon::Crosshair);
+        input.cursor_zones.insert(3, CursorIcon::ResizeHorizontal);
+
+        // The button declares nothing so the panel below it decides
+        assert_eq!(
+            input.cursor_at(Vec2::new(20.0, 20.0)),
+            CursorIcon::Crosshair
+        );
+        assert_eq!(
+            input.cursor_at(Vec2::new(95.0, 50.0)),
+            CursorIcon::ResizeHorizontal
+        );
+        assert_eq!(input.cursor_at(Vec2::new(200.0, 50.0)), CursorIcon::Default);
+
+        // Dragged out of the edge it was pressed on
+        input.mouse_down[MouseButton::Left.index()] = true;
+        input.pressed_id = Some(3);
+        assert_eq!(
+            input.cursor_at(Vec2::new(200.0, 50.0)),
+            CursorIcon::ResizeHorizontal
+        );
+    }
+
+    #[test]
+    fn test_modifiers() {
+        let mut input = input_with_boxes();
+        let mut hooks = EventHooks::default();
+
+        for key in [Key::Shift, Key::Control, Key::Character('a')] {
+            input.process(Event::KeyDown { key }, &mut hooks, 0);
+        }
+        assert_eq!(input.modifiers(), Modifiers::SHIFT | Modifiers::CTRL);
+        assert!(input.modifiers().contains(Modifiers::CTRL));
+        assert!(!input.modifiers().contains(Modifiers::ALT));
+
+        click(&mut input, &mut hooks, 20.0, 20.0);
+        assert_eq!(
+            input.clicked,
+            [(2, MouseButton::Left, Modifiers::SHIFT | Modifiers::CTRL)]
+        );
+
+        input.process(Event::KeyUp { key: Key::Shift }, &mut hooks, 0);
+        input.process(Event::KeyUp { key: Key::Control }, &mut hooks, 0);
+        assert!(input.modifiers().is_empty());
+    }
+
+    #[test]
+    fn test_mouse_delta() {
+        let mut input = InputState::default();
+        let mut hooks = EventHooks::default();
+
+        input.process(Event::MouseMove { x: 10.0, y: 5.0 }, &mut hooks, 0);
+        input.begin_frame();
+        input.process(Event::MouseMove { x: 14.0, y: 5.0 }, &mut hooks, 0);
+        input.process(Event::MouseMove { x: 20.0, y: 2.0 }, &mut hooks, 0);
+        assert_eq!(input.mouse_delta, Vec2::new(10.0, -3.0));
+
+        input.begin_frame();
+        assert_eq!(input.mouse_delta, Vec2::ZERO);
+    }
+
+    #[test]
+    fn test_take_scroll() {
+        let mut input = InputState::default();
+        let mut hooks = EventHooks::default();
+        input.process(Event::MouseMove { x: 20.0, y: 20.0 }, &mut hooks, 0);
+        input.process(Event::Scroll { dx: 0.0, dy: 2.0 }, &mut hooks, 0);
+
+        let outer = Vec4::new(0.0, 0.0, 100.0, 100.0);
+        assert_eq!(
+            input.take_scroll(Vec4::new(50.0, 50.0, 60.0, 60.0)),
+            Vec2::ZERO
+        );
+        assert_eq!(input.take_scroll(outer), Vec2::new(0.0, 2.0));
+        // Taken by the inner area, nothing left for the one around it
+        assert_eq!(input.take_scroll(outer), Vec2::ZERO);
+    }
+}
@@ -0,0 +1,328 @@
+//! On-screen piano keyboards for playing and auditioning notes.
+//!
+//! [`Ui::keyboard`] plays the key under the mouse while the button is held, gliding from key to
+//! key as the mouse moves. While it has the keyboard focus, the letter keys of the computer
+//! keyboard play too, laid out like a piano: the row from A to L for the white keys and the row
+//! above for the black ones. The notes held are kept in a list owned by the caller and every
+//! change comes back as a [`NoteEvent`], ready to be sent on to a synth.
+use crate::input::{Key, MouseButton};
+use crate::style::WidgetState;
+use crate::ui::{Ui, apply_style};
+use clay_layout::elements::FloatingAttachToElement;
+use clay_layout::id::Id;
+use clay_layout::layout::LayoutDirection;
+use clay_layout::math::Vector2;
+use clay_layout::{Declaration, fit, fixed};
+
+/// Size of the white keys across the keyboard.
+const WHITE_WIDTH: f32 = 14.0;
+/// Length of the white keys.
+const WHITE_LENGTH: f32 = 56.0;
+/// Size of the black keys across the keyboard.
+const BLACK_WIDTH: f32 = 9.0;
+/// Length of the black keys.
+const BLACK_LENGTH: f32 = 34.0;
+/// Velocity of the notes played from the computer keyboard.
+const KEY_VELOCITY: u8 = 100;
+/// Computer keys from the lowest note of the keyboard up, a semitone apart.
+const KEY_MAP: &str = "awsedftgyhujkolp";
+
+/// Options for [`Ui::keyboard_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Keyboard {
+    pub octaves: u32,
+    /// MIDI note of the lowest key, C3 unless set.
+    pub first_note: u8,
+    /// Lays the keys out from the bottom up with the black keys on the left, to go next to a
+    /// piano roll.
+    pub vertical: bool,
+}
+
+impl Keyboard {
+    pub fn new(octaves: u32) -> Self {
+        Self {
+            octaves,
+            first_note: 48,
+            vertical: false,
+        }
+    }
+
+    pub fn first_note(mut self, first_note: u8) -> Self {
+        self.first_note = first_note;
+        self
+    }
+
+    pub fn vertical(mut self, vertical: bool) -> Self {
+        self.vertical = vertical;
+        self
+    }
+}
+
+/// A change to the notes held on a [`Ui::keyboard`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteEvent {
+    /// `note` started, at `velocity` from 1 to 127.
+    On {
+        note: u8,
+        velocity: u8,
+    },
+    Off {
+        note: u8,
+    },
+}
+
+/// True for the notes played on black keys.
+fn is_black(note: u8) -> bool {
+    matches!(note % 12, 1 | 3 | 6 | 8 | 10)
+}
+
+/// Number of white keys below `note` on a keyboard starting at a C.
+fn white_keys_below(note: u8) -> u32 {
+    let note = note as u32;
+    let in_octave = [0, 1, 1, 2, 2, 3, 4, 4, 5, 5, 6, 6][note as usize % 12];
+    note / 12 * 7 + in_octave
+}
+
+/// How many semitones above the lowest note of the keyboard `key` plays.
+fn key_offset(key: Key) -> Option<u8> {
+    match key {
+        Key::Character(c) => KEY_MAP.find(c).map(|index| index as u8),
+        _ => None,
+    }
+}
+
+/// Moves `held` to the notes of `wanted`, returning the notes released and then the ones
+/// started.
+fn note_events(held: &mut Vec<u8>, wanted: &[(u8, u8)]) -> Vec<NoteEvent> {
+    let mut events = Vec::new();
+    held.retain(|note| {
+        let keep = wanted.iter().any(|(wanted, _)| wanted == note);
+        if !keep {
+            events.push(NoteEvent::Off { note: *note });
+        }
+        keep
+    });
+    for &(note, velocity) in wanted {
+        if !held.contains(&note) {
+            held.push(note);
+            events.push(NoteEvent::On { note, velocity });
+        }
+    }
+    events
+}
+
+impl Ui {
+    /// A piano keyboard `octaves` octaves wide starting at C3. `held_notes` are the MIDI notes
+    /// held down, which the keyboard keeps up to date: notes added to it anywhere else are
+    /// released on the next frame. Returns the notes started and released this frame.
+    ///
+    /// Clicking the keyboard gives it the keyboard focus. Pressing a key further from its base
+    /// plays it louder.
+    pub fn keyboard(&self, id: &str, octaves: u32, held_notes: &mut Vec<u8>) -> Vec<NoteEvent> {
+        self.keyboard_with(id, held_notes, Keyboard::new(octaves))
+    }
+
+    /// [`Ui::keyboard`] with options, for example a keyboard beside a piano roll with
+    /// `ui.keyboard_with("keys", &mut held, Keyboard::new(4).first_note(36).vertical(true))`.
+    ///
+    /// The keys are styled by the `keyboard_key` and `keyboard_black_key` rules, with `:active`
+    /// while held, and the keyboard by the `keyboard` rule, `:active` while focused.
+    pub fn keyboard_with(
+        &self,
+        id: &str,
+        held_notes: &mut Vec<u8>,
+        options: Keyboard,
+    ) -> Vec<NoteEvent> {
+        let keyboard_id = self.id(id);
+        let input = self.input();
+        let first_note = options.first_note.min(127);
+        let last_note = (first_note as u32 + options.octaves * 12).min(128) as u8;
+        let keys: Vec<(u8, Id)> = (first_note..last_note)
+            .map(|note| (note, self.id_index(id, note as u32 + 1)))
+            .collect();
+        let key_at = |element: Option<u32>| {
+            keys.iter()
+                .find(|(_, key_id)| Some(key_id.id.id) == element)
+                .map(|(note, key_id)| (*note, *key_id))
+        };
+
+        self.register_focusable(keyboard_id);
+        let hovered = self
+            .last_frame_rect(keyboard_id)
+            .is_some_and(|rect| input.is_hovered(rect));
+        let mut focused = self.is_focused(keyboard_id);
+        if input.button_pressed(MouseButton::Left) {
+            focused = hovered;
+        }
+        self.set_focused(keyboard_id, focused);
+
+        let mut wanted = Vec::new();
+        // Glides over the keys while the button is held after pressing one of them
+        if input.button_down(MouseButton::Left)
+            && key_at(input.pressed_id).is_some()
+            && let Some((note, key_id)) = key_at(input.hit_test(input.mouse_pos))
+            && let Some(rect) = self.last_frame_rect(key_id)
+        {
+            let depth = if options.vertical {
+                (input.mouse_pos.x - rect.x) / (rect.z - rect.x).max(1.0)
+            } else {
+                (input.mouse_pos.y - rect.y) / (rect.w - rect.y).max(1.0)
+            };
+            let depth = depth.clamp(0.0, 1.0);
+            wanted.push((note, (40.0 + depth * 87.0) as u8));
+        }
+        if focused && input.modifiers().is_empty() {
+            for key in &input.keys_down {
+                if let Some(offset) = key_offset(*key)
+                    && let Some(note) = first_note.checked_add(offset)
+                    && note < last_note
+                    && !wanted.iter().any(|(wanted, _)| *wanted == note)
+                {
+                    wanted.push((note, KEY_VELOCITY));
+                }
+            }
+        }
+        let events = note_events(held_notes, &wanted);
+
+        let style = self.widget_style(
+            "keyboard",
+            WidgetState {
+                active: focused,
+                ..Default::default()
+            },
+        );
+        let mut decl = Declaration::new();
+        decl.id(keyboard_id)
+            .layout()
+            .width(fit!())
+            .height(fit!())
+            .direction(if options.vertical {
+                LayoutDirection::TopToBottom
+            } else {
+                LayoutDirection::LeftToRight
+            })
+            .end();
+        apply_style(&mut decl, &style);
+
+        let first_white = white_keys_below(first_note);
+        let white_count = white_keys_below(last_note) - first_white;
+        // Width and height of a key `width` across the keyboard and `length` long
+        let size = |width: f32, length: f32| {
+            if options.vertical {
+                (length, width)
+            } else {
+                (width, length)
+            }
+        };
+        self.with_layout(&decl, |ui| {
+            // The black keys go last so they are drawn over the white ones and hit first
+            let (black, mut white): (Vec<_>, Vec<_>) =
+                keys.iter().partition(|(note, _)| is_black(*note));
+            if options.vertical {
+                white.reverse();
+            }
+            for &(note, key_id) in white.into_iter().chain(black) {
+                let black = is_black(note);
+                let key_style = ui.widget_style(
+                    if black {
+                        "keyboard_black_key"
+                    } else {
+                        "keyboard_key"
+                    },
+                    WidgetState {
+                        active: held_notes.contains(&note),
+                        ..Default::default()
+                    },
+                );
+                let mut key = Declaration::new();
+                key.id(key_id);
+                if black {
+                    // Centered on the edge between the white keys around it
+                    let below = white_keys_below(note) - first_white;
+                    let edge = if options.vertical {
+                        (white_count - below) as f32 * WHITE_WIDTH
+                    } else {
+                        below as f32 * WHITE_WIDTH
+                    };
+                    let edge = (edge - BLACK_WIDTH * 0.5).round();
+                    let (width, height) = size(BLACK_WIDTH, BLACK_LENGTH);
+                    key.layout()
+                        .width(fixed!(width))
+                        .height(fixed!(height))
+                        .end()
+                        .floating()
+                        .attach_to(FloatingAttachToElement::Parent)
+                        .offset(if options.vertical {
+                            Vector2::new(0.0, edge)
+                        } else {
+                            Vector2::new(edge, 0.0)
+                        })
+                        .end();
+                } else {
+                    let (width, height) = size(WHITE_WIDTH, WHITE_LENGTH);
+                    key.layout()
+                        .width(fixed!(width))
+                        .height(fixed!(height))
+                        .end();
+                }
+                apply_style(&mut key, &key_style);
+                ui.with_layout(&key, |_| {});
+            }
+        });
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_layout() {
+        assert!(is_black(49) && !is_black(48) && !is_black(52) && is_black(70));
+        assert_eq!(white_keys_below(48), 28);
+        assert_eq!(white_keys_below(50), 29);
+        // There is no black key between E and F
+        assert_eq!(white_keys_below(53), 31);
+        assert_eq!(white_keys_below(60), 35);
+
+        assert_eq!(key_offset(Key::Character('a')), Some(0));
+        assert_eq!(key_offset(Key::Character('w')), Some(1));
+        assert_eq!(key_offset(Key::Character('k')), Some(12));
+        assert_eq!(key_offset(Key::Character('z')), None);
+        assert_eq!(key_offset(Key::Space), None);
+    }
+
+    #[test]
+    fn test_note_events() {
+        let mut held = Vec::new();
+        assert_eq!(
+            note_events(&mut held, &[(60, 100), (64, 90)]),
+            [
+                NoteEvent::On {
+                    note: 60,
+                    velocity: 100
+                },
+                NoteEvent::On {
+                    note: 64,
+                    velocity: 90
+                }
+            ]
+        );
+        // Gliding to another key releases the one before
+        assert_eq!(
+            note_events(&mut held, &[(62, 80), (64, 90)]),
+            [
+                NoteEvent::Off { note: 60 },
+                NoteEvent::On {
+                    note: 62,
+                    velocity: 80
+                }
+            ]
+        );
+        assert_eq!(held, [64, 62]);
+        assert!(note_events(&mut held, &[(62, 80), (64, 90)]).is_empty());
+        assert_eq!(note_events(&mut held, &[]).len(), 2);
+        assert!(held.is_empty());
+    }
+}
@@ -0,0 +1,276 @@
+//! Rotary knobs, the usual control for synth and effect parameters.
+//!
+//! [`Ui::knob`] is turned by dragging up and down, which is easier to do precisely than
+//! following the circle. The knob is drawn with tiny-skia each frame as an arc showing the
+//! value over a track for the whole range, and a line pointing at the value. For ranges around
+//! zero, like pan, the arc starts from the middle.
+use crate::image::ImageInfo;
+use crate::input::MouseButton;
+use crate::style::WidgetState;
+use crate::ui::{Ui, rgb};
+use crate::value_drag::{DragAxis, precision_step, round_to};
+use clay_layout::color::Color as ClayColor;
+use clay_layout::layout::{Alignment, LayoutAlignmentX, LayoutAlignmentY, LayoutDirection};
+use clay_layout::{Declaration, fit, fixed};
+use std::f32::consts::PI;
+use tiny_skia::{Color, LineCap, Paint, PathBuilder, Pixmap, Stroke, Transform};
+
+/// Angle of the lowest value, clockwise from pointing right. The range goes around to the same
+/// angle on the right side.
+const START_ANGLE: f32 = PI * 0.75;
+/// Angle the knob turns through over the whole range.
+const SWEEP: f32 = PI * 1.5;
+/// Pixels dragged to go through the whole range, more with fine adjustment held.
+const DRAG_RANGE: f32 = 200.0;
+/// Seconds between two clicks for them to be a double-click.
+const DOUBLE_CLICK_TIME: f32 = 0.4;
+
+/// Options for [`Ui::knob_with`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Knob {
+    pub min: f32,
+    pub max: f32,
+    /// Value a double-click resets to, the middle of the range unless set.
+    pub default: Option<f32>,
+    /// Number of decimals the value is shown with while dragged.
+    pub precision: usize,
+    /// Steps the value moves in while stepping is held, one decimal fewer than shown unless set.
+    pub step: Option<f32>,
+}
+
+impl Knob {
+    pub fn new(min: f32, max: f32) -> Self {
+        Self {
+            min,
+            max,
+            default: None,
+            precision: 2,
+            step: None,
+        }
+    }
+
+    pub fn default(mut self, default: f32) -> Self {
+        self.default = Some(default);
+        self
+    }
+
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    pub fn step(mut self, step: f32) -> Self {
+        self.step = Some(step);
+        self
+    }
+}
+
+/// Where `value` is in `min..=max`, from 0.0 to 1.0.
+fn fraction(value: f32, min: f32, max: f32) -> f32 {
+    if max <= min {
+        return 0.0;
+    }
+    ((value - min) / (max - min)).clamp(0.0, 1.0)
+}
+
+/// Points along the arc of a knob from `from` to `to`, both fractions of the range.
+fn arc(path: &mut PathBuilder, center: f32, radius: f32, from: f32, to: f32) {
+    let (from, to) = (from.min(to), from.max(to));
+    let steps = ((to - from) * 48.0).ceil().max(1.0) as usize;
+    for step in 0..=steps {
+        let angle = START_ANGLE + (from + (to - from) * step as f32 / steps as f32) * SWEEP;
+        let (x, y) = (center + radius * angle.cos(), center + radius * angle.sin());
+        if step == 0 {
+            path.move_to(x, y);
+        } else {
+            path.line_to(x, y);
+        }
+    }
+}
+
+fn skia_color(color: ClayColor) -> Color {
+    Color::from_rgba8(color.r as u8, color.g as u8, color.b as u8, color.a as u8)
+}
+
+/// A knob `size` pixels across at `value`, with the arc drawn from `origin`, both as fractions
+/// of the range.
+pub(crate) fn knob_pixmap(
+    size: u32,
+    value: f32,
+    origin: f32,
+    track: ClayColor,
+    fill: ClayColor,
+) -> Option<Pixmap> {
+    let mut pixmap = Pixmap::new(size, size)?;
+    let center = size as f32 * 0.5;
+    let width = (size as f32 * 0.1).max(2.0);
+    let radius = center - width * 0.5;
+    let stroke = Stroke {
+        width,
+        line_cap: LineCap::Round,
+        ..Default::default()
+    };
+    let mut paint = Paint {
+        anti_alias: true,
+        ..Default::default()
+    };
+
+    let mut draw = |path: PathBuilder, color: ClayColor| {
+        paint.set_color(skia_color(color));
+        if let Some(path) = path.finish() {
+            pixmap.stroke_path(&path, &paint, &stroke, Transform::identity(), None);
+        }
+    };
+
+    let mut path = PathBuilder::new();
+    arc(&mut path, center, radius, 0.0, 1.0);
+    draw(path, track);
+
+    let mut path = PathBuilder::new();
+    if value != origin {
+        arc(&mut path, center, radius, origin, value);
+    }
+    let angle = START_ANGLE + value * SWEEP;
+    path.move_to(center, center);
+    path.line_to(
+        center + (radius - width) * angle.cos(),
+        center + (radius - width) * angle.sin(),
+    );
+    draw(path, fill);
+    Some(pixmap)
+}
+
+impl Ui {
+    /// A knob for `value` between `min` and `max`, labeled with `label`. Dragging up raises the
+    /// value and dragging down lowers it, slower with shift held and in steps with Ctrl held,
+    /// see [`crate::value_drag::DragConfig`]. Double-clicking resets it to the middle of the
+    /// range. Returns true when the value changed this frame.
+    pub fn knob(&self, label: &str, value: &mut f32, min: f32, max: f32) -> bool {
+        self.knob_with(label, value, Knob::new(min, max))
+    }
+
+    /// [`Ui::knob`] with a value to reset to and formatting options, for example
+    /// `ui.knob_with("Gain", &mut gain, Knob::new(-24.0, 24.0).default(0.0).precision(1))`.
+    ///
+    /// Styled by the `knob` rules: the background color for the value and the pointer, the
+    /// border color for the track and the text color for the label.
+    pub fn knob_with(&self, label: &str, value: &mut f32, options: Knob) -> bool {
+        let old_value = *value;
+        let id = self.id(label);
+        let input = self.input();
+        let held = input.button_down(MouseButton::Left) && input.pressed_id == Some(id.id.id);
+        let drag = self.value_drag(id, held, *value, DragAxis::Vertical);
+        if let Some(drag) = drag {
+            let moved = drag.value + drag.delta * (options.max - options.min) / DRAG_RANGE;
+            let moved = moved.clamp(options.min, options.max.max(options.min));
+            let step = options.step.unwrap_or(precision_step(options.precision));
+            *value = self.drag_to(id, moved, |value| round_to(value, step));
+        }
+
+        // A click that turned the knob doesn't count towards a double-click
+        let dt = self.delta_time();
+        let item = self.item_state(id);
+        if held && input.mouse_delta.y != 0.0 {
+            item.dragging = true;
+        }
+        let last_click = item.last_click.map(|seconds| seconds + dt);
+        item.last_click = last_click;
+        if input.was_clicked(id.id.id, MouseButton::Left) && !item.dragging {
+            if last_click.is_some_and(|seconds| seconds < DOUBLE_CLICK_TIME) {
+                *value = options.default.unwrap_or((options.min + options.max) * 0.5);
+                item.last_click = None;
+            } else {
+                item.last_click = Some(0.0);
+            }
+        }
+        if !held {
+            item.dragging = false;
+        }
+        *value = value.clamp(options.min, options.max.max(options.min));
+
+        let hovered = self
+            .last_frame_rect(id)
+            .is_some_and(|rect| input.is_hovered(rect));
+        let style = self.widget_style(
+            "knob",
+            WidgetState {
+                hovered,
+                active: held,
+                ..Default::default()
+            },
+        );
+        let fill = style.background.unwrap_or(rgb(100, 150, 255));
+        let track = style.border_color.unwrap_or(rgb(70, 70, 70));
+        let color = style.text_color.unwrap_or(rgb(220, 220, 220));
+
+        let size = (self.font_size() as f32 * 2.4).round();
+        let at = fraction(*value, options.min, options.max);
+        let origin = if options.min < 0.0 && options.max > 0.0 {
+            fraction(0.0, options.min, options.max)
+        } else {
+            0.0
+        };
+
+        let mut decl = Declaration::new();
+        decl.layout()
+            .width(fit!())
+            .height(fit!())
+            .child_gap(2)
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Center,
+                LayoutAlignmentY::Top,
+            ))
+            .direction(LayoutDirection::TopToBottom)
+            .end();
+        let mut dial = Declaration::new();
+        dial.id(id)
+            .layout()
+            .width(fixed!(size))
+            .height(fixed!(size))
+            .end();
+        if let Some(pixmap) = knob_pixmap(size as u32, at, origin, track, fill) {
+            dial.image()
+                .data(self.alloc_image(ImageInfo::new(pixmap)))
+                .end();
+        }
+        self.with_layout(&decl, |ui| {
+            ui.with_layout(&dial, |_| {});
+            ui.text_element(label, color);
+        });
+        if let Some(drag) = drag {
+            let text = format!("{:.*}", options.precision, *value);
+            self.drag_readout(id, &text, *value - drag.start, options.precision);
+        }
+        *value != old_value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fraction() {
+        assert_eq!(fraction(5.0, 0.0, 10.0), 0.5);
+        assert_eq!(fraction(0.0, -1.0, 1.0), 0.5);
+        assert_eq!(fraction(20.0, 0.0, 10.0), 1.0);
+        assert_eq!(fraction(-5.0, 0.0, 10.0), 0.0);
+        assert_eq!(fraction(1.0, 1.0, 1.0), 0.0);
+    }
+
+    #[test]
+    fn test_knob_pixmap() {
+        let track = rgb(70, 70, 70);
+        let fill = rgb(100, 150, 255);
+        let knob = knob_pixmap(40, 0.5, 0.0, track, fill).unwrap();
+        let pixel = |x: u32, y: u32| knob.pixel(x, y).unwrap();
+        // The bottom between the ends of the range is empty
+        assert_eq!(pixel(20, 38).alpha(), 0);
+        // Filled up to the top on the left, the track on the right
+        assert_eq!(pixel(2, 20).blue(), 255);
+        assert_eq!(pixel(38, 20).blue(), 70);
+        // The pointer goes up from the center
+        assert_eq!(pixel(20, 10).blue(), 255);
+        assert!(knob_pixmap(0, 0.5, 0.0, track, fill).is_none());
+    }
+}
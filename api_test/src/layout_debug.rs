@@ -0,0 +1,155 @@
+//! Wireframes of the layout for documentation and bug reports.
+//!
+//! [`Ui::render_layout_debug`] draws the elements with an id of the last frame as outlines, one
+//! color per depth, with their padding shaded and their id name in the top left corner.
+//! Nothing of the styling shows, so the sizes and positions Clay computed are easy to see.
+//! [`Ui::save_layout_debug`] writes the same to a PNG file.
+use crate::internal_error::{InternalError, InternalResult};
+use crate::style::TextRole;
+use crate::ui::Ui;
+use clay_layout::layout::Padding;
+use glam::Vec4;
+use std::path::Path;
+use tiny_skia::{Color, Paint, Pixmap, PixmapPaint, Rect, Transform};
+
+/// Outline colors, picked by depth so nested elements stand apart from their parents.
+const DEPTH_COLORS: [(u8, u8, u8); 6] = [
+    (255, 96, 96),
+    (96, 200, 96),
+    (96, 160, 255),
+    (240, 200, 64),
+    (200, 120, 255),
+    (64, 220, 220),
+];
+
+/// Opacity of the padding shading.
+const PADDING_ALPHA: u8 = 64;
+
+fn depth_color(depth: u32) -> (u8, u8, u8) {
+    DEPTH_COLORS[depth as usize % DEPTH_COLORS.len()]
+}
+
+fn fill(pixmap: &mut Pixmap, x0: f32, y0: f32, x1: f32, y1: f32, color: Color) {
+    let Some(rect) = Rect::from_ltrb(x0, y0, x1, y1) else {
+        return;
+    };
+    let mut paint = Paint::default();
+    paint.set_color(color);
+    pixmap.fill_rect(rect, &paint, Transform::identity(), None);
+}
+
+/// Draws the 1 pixel outline of `rect` (x0, y0, x1, y1) and shades its `padding`.
+fn draw_wireframe(pixmap: &mut Pixmap, rect: Vec4, padding: Padding, (r, g, b): (u8, u8, u8)) {
+    let [x0, y0, x1, y1] = rect.round().to_array();
+
+    // Padding larger than the element is clamped so the strips don't overlap
+    let left = (padding.left as f32).min(x1 - x0);
+    let right = (padding.right as f32).min(x1 - x0 - left);
+    let top = (padding.top as f32).min(y1 - y0);
+    let bottom = (padding.bottom as f32).min(y1 - y0 - top);
+    let shade = Color::from_rgba8(r, g, b, PADDING_ALPHA);
+    fill(pixmap, x0, y0, x1, y0 + top, shade);
+    fill(pixmap, x0, y1 - bottom, x1, y1, shade);
+    fill(pixmap, x0, y0 + top, x0 + left, y1 - bottom, shade);
+    fill(pixmap, x1 - right, y0 + top, x1, y1 - bottom, shade);
+
+    let line = Color::from_rgba8(r, g, b, 255);
+    fill(pixmap, x0, y0, x1, y0 + 1.0, line);
+    fill(pixmap, x0, y1 - 1.0, x1, y1, line);
+    fill(pixmap, x0, y0 + 1.0, x0 + 1.0, y1 - 1.0, line);
+    fill(pixmap, x1 - 1.0, y0 + 1.0, x1, y1 - 1.0, line);
+}
+
+/// Draws `label` on a dark backing in the top left corner of `rect`, if it fits inside.
+fn draw_label(pixmap: &mut Pixmap, rect: Vec4, label: &Pixmap) {
+    let (width, height) = (label.width() as f32 + 4.0, label.height() as f32 + 2.0);
+    if width > rect.z - rect.x || height > rect.w - rect.y {
+        return;
+    }
+    let (x, y) = (rect.x.round() + 1.0, rect.y.round() + 1.0);
+    fill(
+        pixmap,
+        x,
+        y,
+        x + width - 2.0,
+        y + height - 2.0,
+        Color::from_rgba8(0, 0, 0, 160),
+    );
+    pixmap.draw_pixmap(
+        x as i32 + 1,
+        y as i32,
+        label.as_ref(),
+        &PixmapPaint::default(),
+        Transform::identity(),
+        None,
+    );
+}
+
+impl Ui {
+    /// Draws the wireframe of the last frame onto `pixmap`: the bounds of every element with an
+    /// id, its padding and its id name. Call it after [`Ui::end`], before the next frame begins.
+    /// Elements are drawn in declaration order so children end up on top of their parents.
+    pub fn render_layout_debug(&self, pixmap: &mut Pixmap) {
+        let label_size = self.font_size_for(TextRole::Caption);
+        for node in self.frame_tree() {
+            let Some(rect) = self.last_frame_rect(node.id) else {
+                continue;
+            };
+            draw_wireframe(pixmap, rect, node.padding, depth_color(node.depth));
+
+            let path = self.id_path(node.id);
+            let name = path.rsplit('/').next().unwrap_or(&path);
+            if let Some(label) = self
+                .generate_text_now(name, label_size)
+                .and_then(|text| text.data)
+            {
+                draw_label(pixmap, rect, &label);
+            }
+        }
+    }
+
+    /// Writes the wireframe of the last frame, see [`Ui::render_layout_debug`], on a dark
+    /// background to the PNG file at `path`.
+    pub fn save_layout_debug(&self, path: impl AsRef<Path>) -> InternalResult<()> {
+        let (width, height) = self.window_size();
+        let mut pixmap =
+            Pixmap::new(width as u32, height as u32).ok_or(InternalError::GenericError {
+                text: format!("Invalid window size {}x{}", width, height),
+            })?;
+        pixmap.fill(Color::from_rgba8(24, 24, 24, 255));
+        self.render_layout_debug(&mut pixmap);
+        pixmap
+            .save_png(path)
+            .map_err(|e| InternalError::GenericError {
+                text: format!("Unable to save layout wireframe: {}", e),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draw_wireframe() {
+        let mut pixmap = Pixmap::new(20, 20).unwrap();
+        let rect = Vec4::new(2.0, 2.0, 18.0, 18.0);
+        let padding = Padding::new(4, 2, 4, 0);
+        draw_wireframe(&mut pixmap, rect, padding, (255, 0, 0));
+
+        let alpha = |x: u32, y: u32| pixmap.pixel(x, y).unwrap().alpha();
+        // Outline
+        assert_eq!(alpha(2, 10), 255);
+        assert_eq!(alpha(17, 10), 255);
+        assert_eq!(alpha(10, 2), 255);
+        assert_eq!(alpha(10, 17), 255);
+        // Padding inside of the outline, none at the bottom
+        assert_eq!(alpha(4, 10), PADDING_ALPHA);
+        assert_eq!(alpha(10, 4), PADDING_ALPHA);
+        assert_eq!(alpha(16, 10), PADDING_ALPHA);
+        assert_eq!(alpha(10, 16), 0);
+        // Content and outside
+        assert_eq!(alpha(10, 10), 0);
+        assert_eq!(alpha(0, 0), 0);
+    }
+}
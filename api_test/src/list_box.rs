@@ -0,0 +1,315 @@
+//! List boxes: a scrolled column of selectable text rows.
+//!
+//! Clicking a row selects it, and when the selection can hold several rows Shift-click extends
+//! it and Ctrl-click (Cmd on macOS) toggles single rows. While the list has keyboard focus the
+//! arrow, Home, End and page keys move a cursor through the rows and the list scrolls to keep
+//! it in view.
+use crate::input::{Key, Modifiers, MouseButton};
+use crate::style::WidgetState;
+use crate::ui::{Ui, apply_separators, apply_style, rgb};
+use clay_layout::id::Id;
+use clay_layout::layout::{Alignment, LayoutAlignmentX, LayoutAlignmentY, LayoutDirection};
+use clay_layout::math::Vector2;
+use clay_layout::{Declaration, fit, fixed, grow};
+use glam::Vec2;
+
+/// Keyboard cursor of a list box, kept in its [`crate::ui::ItemState`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ListCursor {
+    index: Option<usize>,
+    /// Row Shift selections extend from.
+    anchor: usize,
+}
+
+/// How a click or key press changes the selection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectMode {
+    /// Only the picked row is selected.
+    Replace,
+    /// The picked row flips between selected and not, the rest stays.
+    Toggle,
+    /// The rows from the anchor to the picked one are selected.
+    Extend,
+}
+
+impl SelectMode {
+    fn from_modifiers(modifiers: Modifiers) -> Self {
+        if modifiers.shift() {
+            SelectMode::Extend
+        } else if modifiers.command() {
+            SelectMode::Toggle
+        } else {
+            SelectMode::Replace
+        }
+    }
+}
+
+/// What a list box selects into: `Option<usize>` holds a single row and `Vec<usize>` any
+/// number of them.
+pub trait ListSelection {
+    fn is_selected(&self, index: usize) -> bool;
+    /// Picks `index` with `mode`. `anchor` is where an [`SelectMode::Extend`] starts.
+    fn select(&mut self, index: usize, anchor: usize, mode: SelectMode);
+    /// Lowest selected row.
+    fn first(&self) -> Option<usize>;
+}
+
+impl ListSelection for Option<usize> {
+    fn is_selected(&self, index: usize) -> bool {
+        *self == Some(index)
+    }
+
+    fn select(&mut self, index: usize, _anchor: usize, _mode: SelectMode) {
+        *self = Some(index);
+    }
+
+    fn first(&self) -> Option<usize> {
+        *self
+    }
+}
+
+impl ListSelection for Vec<usize> {
+    fn is_selected(&self, index: usize) -> bool {
+        self.contains(&index)
+    }
+
+    fn select(&mut self, index: usize, anchor: usize, mode: SelectMode) {
+        match mode {
+            SelectMode::Replace => {
+                self.clear();
+                self.push(index);
+            }
+            SelectMode::Toggle => {
+                if let Some(pos) = self.iter().position(|selected| *selected == index) {
+                    self.remove(pos);
+                } else {
+                    self.push(index);
+                    self.sort_unstable();
+                }
+            }
+            SelectMode::Extend => {
+                self.clear();
+                self.extend(anchor.min(index)..=anchor.max(index));
+            }
+        }
+    }
+
+    fn first(&self) -> Option<usize> {
+        self.iter().min().copied()
+    }
+}
+
+/// Row the cursor moves to from `current` for `key` in a list of `len` rows showing `page`
+/// rows at a time. `None` for keys that don't move it.
+fn cursor_target(current: Option<usize>, len: usize, page: usize, key: Key) -> Option<usize> {
+    let last = len.checked_sub(1)?;
+    let next = match (key, current) {
+        (Key::Up | Key::Down | Key::PageUp | Key::PageDown, None) => 0,
+        (Key::Up, Some(index)) => index.saturating_sub(1),
+        (Key::Down, Some(index)) => index + 1,
+        (Key::PageUp, Some(index)) => index.saturating_sub(page),
+        (Key::PageDown, Some(index)) => index + page,
+        (Key::Home, _) => 0,
+        (Key::End, _) => last,
+        _ => return None,
+    };
+    Some(next.min(last))
+}
+
+impl Ui {
+    /// A list of `items` where clicked rows, or rows picked with the keyboard while it has
+    /// focus, are stored in `selected`. It is as tall as its rows up to the space left below
+    /// it in its parent and scrolls beyond that. Returns true when the selection changed.
+    ///
+    /// Styled by the `list_box` rule, which gets `:active` while focused and draws its
+    /// `separator` between the rows, and `list_item` rules for the rows, with the `selected`
+    /// class on selected ones.
+    pub fn list_box(&self, id: &str, items: &[&str], selected: &mut impl ListSelection) -> bool {
+        let list_id = self.id(id);
+        let content_id = self.id_index(id, 1);
+        let row_ids: Vec<Id> = (0..items.len())
+            .map(|index| self.id_index(id, index as u32 + 2))
+            .collect();
+        let input = self.input();
+
+        // Sizes come from the previous frame, rows are assumed to be equally tall
+        let list_rect = self.last_frame_rect(list_id);
+        let row_height = row_ids
+            .first()
+            .and_then(|row| self.last_frame_rect(*row))
+            .map_or(0.0, |rect| rect.w - rect.y);
+        let content_height = self
+            .last_frame_rect(content_id)
+            .map_or(0.0, |rect| rect.w - rect.y);
+        let (window_size, parent) = self.available_space();
+        let top = list_rect.map_or(0.0, |rect| rect.y);
+        let bottom = parent.map_or(window_size.1 as f32, |rect| rect.w);
+        let available = (bottom - top).max(row_height);
+        let height = if content_height > 0.0 {
+            content_height.min(available)
+        } else {
+            available
+        };
+
+        self.register_focusable(list_id);
+        let hovered = list_rect.is_some_and(|rect| input.is_hovered(rect));
+        let mut focused = self.is_focused(list_id);
+        if input.button_pressed(MouseButton::Left) {
+            focused = hovered;
+        }
+
+        let item = self.item_state(list_id);
+        let mut cursor = item.list_cursor;
+        let mut offset = item.scroll.y;
+        let mut changed = false;
+
+        for (index, row) in row_ids.iter().enumerate() {
+            if let Some(modifiers) = input.click_modifiers(row.id.id, MouseButton::Left) {
+                let mode = SelectMode::from_modifiers(modifiers);
+                selected.select(index, cursor.anchor, mode);
+                cursor.index = Some(index);
+                if mode != SelectMode::Extend {
+                    cursor.anchor = index;
+                }
+                changed = true;
+            }
+        }
+
+        if focused && row_height > 0.0 {
+            let page = ((height / row_height) as usize).max(1);
+            let extend = input.modifiers().shift();
+            for key in &input.keys_pressed {
+                let current = cursor.index.or(selected.first());
+                let Some(index) = cursor_target(current, items.len(), page, *key) else {
+                    continue;
+                };
+                let mode = if extend {
+                    SelectMode::Extend
+                } else {
+                    SelectMode::Replace
+                };
+                selected.select(index, cursor.anchor, mode);
+                cursor.index = Some(index);
+                if !extend {
+                    cursor.anchor = index;
+                }
+                changed = true;
+
+                // Scroll just enough to show the cursor row
+                let row_top = index as f32 * row_height;
+                offset = offset.max(row_top + row_height - height).min(row_top);
+            }
+        }
+
+        if hovered {
+            offset -= input.scroll.y * row_height;
+        }
+        let offset = offset.clamp(0.0, (content_height - height).max(0.0));
+
+        let item = self.item_state(list_id);
+        item.list_cursor = cursor;
+        item.scroll = Vec2::new(0.0, offset);
+        self.set_focused(list_id, focused);
+
+        let style = self.widget_style(
+            "list_box",
+            WidgetState {
+                active: focused,
+                ..Default::default()
+            },
+        );
+        let mut decl = Declaration::new();
+        decl.id(list_id)
+            .layout()
+            .width(grow!())
+            .height(fixed!(height))
+            .end()
+            .clip(false, true, Vector2::new(0.0, -offset));
+        apply_style(&mut decl, &style);
+
+        let mut content_decl = Declaration::new();
+        content_decl
+            .id(content_id)
+            .layout()
+            .width(grow!())
+            .height(fit!())
+            .direction(LayoutDirection::TopToBottom)
+            .end();
+        apply_separators(&mut content_decl, &style);
+
+        self.with_layout(&decl, |ui| {
+            ui.with_layout(&content_decl, |ui| {
+                for (index, (label, row)) in items.iter().zip(&row_ids).enumerate() {
+                    ui.list_row(*row, label, selected.is_selected(index));
+                }
+            });
+        });
+        changed
+    }
+
+    fn list_row(&self, id: Id, label: &str, selected: bool) {
+        let hovered = self
+            .last_frame_rect(id)
+            .is_some_and(|rect| self.input().is_hovered(rect));
+        if selected {
+            self.push_pending_class("selected");
+        }
+        let style = self.widget_style(
+            "list_item",
+            WidgetState {
+                hovered,
+                ..Default::default()
+            },
+        );
+        let color = style.text_color.unwrap_or(rgb(220, 220, 220));
+
+        let mut decl = Declaration::new();
+        decl.id(id)
+            .layout()
+            .width(grow!())
+            .height(fit!())
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Left,
+                LayoutAlignmentY::Center,
+            ))
+            .end();
+        apply_style(&mut decl, &style);
+        self.with_layout(&decl, |ui| ui.text_element(label, color));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multi_select() {
+        let mut selected: Vec<usize> = Vec::new();
+        selected.select(2, 2, SelectMode::Replace);
+        selected.select(5, 2, SelectMode::Toggle);
+        assert_eq!(selected, [2, 5]);
+        selected.select(2, 2, SelectMode::Toggle);
+        assert_eq!(selected, [5]);
+        // Extending works both ways from the anchor
+        selected.select(1, 3, SelectMode::Extend);
+        assert_eq!(selected, [1, 2, 3]);
+        selected.select(5, 3, SelectMode::Extend);
+        assert_eq!(selected, [3, 4, 5]);
+
+        let mut single = None;
+        single.select(4, 0, SelectMode::Extend);
+        assert_eq!(single, Some(4));
+    }
+
+    #[test]
+    fn test_cursor_target() {
+        assert_eq!(cursor_target(None, 10, 4, Key::Down), Some(0));
+        assert_eq!(cursor_target(Some(0), 10, 4, Key::Up), Some(0));
+        assert_eq!(cursor_target(Some(9), 10, 4, Key::Down), Some(9));
+        assert_eq!(cursor_target(Some(3), 10, 4, Key::PageDown), Some(7));
+        assert_eq!(cursor_target(Some(7), 10, 4, Key::PageDown), Some(9));
+        assert_eq!(cursor_target(Some(3), 10, 4, Key::End), Some(9));
+        assert_eq!(cursor_target(Some(3), 10, 4, Key::Enter), None);
+        assert_eq!(cursor_target(None, 0, 4, Key::Down), None);
+    }
+}
@@ -0,0 +1,157 @@
+//! Long single-line strings, like log lines or lyrics, drawn in pieces.
+//!
+//! Rasterizing such a string at once makes a pixmap much wider than the screen of which only a
+//! small part is ever seen. Strings longer than [`LONG_TEXT_LEN`] are split into pieces instead,
+//! measured once to know where they go, and only the pieces inside of the clip rect of the text
+//! are rasterized. Scrolling brings other pieces into view, which are rasterized when they
+//! first show and cached like any other string.
+use clay_layout::math::BoundingBox;
+use glam::Vec4;
+use std::ops::Range;
+
+/// Strings longer than this in bytes are drawn in pieces.
+pub(crate) const LONG_TEXT_LEN: usize = 512;
+/// Length in bytes pieces are cut at, shorter when they end at a space.
+const PIECE_LEN: usize = 64;
+
+/// Part of a long string, placed `x` from its start.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TextPiece {
+    pub(crate) text: String,
+    pub(crate) x: f32,
+    pub(crate) width: f32,
+}
+
+/// Whether `text` is drawn in pieces. Text with line breaks is laid out in lines and isn't.
+pub(crate) fn is_long(text: &str) -> bool {
+    text.len() > LONG_TEXT_LEN && !text.contains('\n')
+}
+
+/// Splits `text` into pieces of at most about [`PIECE_LEN`] bytes. Pieces end after a space
+/// when there is one in their second half, so kerning and ligatures are rarely split.
+pub(crate) fn split_pieces(text: &str) -> Vec<Range<usize>> {
+    let mut pieces = Vec::with_capacity(text.len() / PIECE_LEN + 1);
+    let mut start = 0;
+    while start < text.len() {
+        let mut end = (start + PIECE_LEN).min(text.len());
+        while !text.is_char_boundary(end) {
+            end += 1;
+        }
+        if end < text.len()
+            && let Some(space) = text[start..end].rfind(' ')
+            && space >= PIECE_LEN / 2
+        {
+            end = start + space + 1;
+        }
+        pieces.push(start..end);
+        start = end;
+    }
+    pieces
+}
+
+/// Clip rects of the scissor commands being drawn, as (x0, y0, x1, y1).
+pub(crate) struct ClipStack {
+    rects: Vec<Vec4>,
+}
+
+impl ClipStack {
+    /// Clips to a screen of `width` x `height`.
+    pub(crate) fn new(width: f32, height: f32) -> Self {
+        Self {
+            rects: vec![Vec4::new(0.0, 0.0, width, height)],
+        }
+    }
+
+    /// Clips to `bb` inside of the current clip rect.
+    pub(crate) fn push(&mut self, bb: BoundingBox) {
+        let top = self.current();
+        let rect = Vec4::new(bb.x, bb.y, bb.x + bb.width, bb.y + bb.height);
+        self.rects.push(Vec4::new(
+            rect.x.max(top.x),
+            rect.y.max(top.y),
+            rect.z.min(top.z),
+            rect.w.min(top.w),
+        ));
+    }
+
+    /// Goes back to the clip rect before the last [`ClipStack::push`]. The screen is kept.
+    pub(crate) fn pop(&mut self) {
+        if self.rects.len() > 1 {
+            self.rects.pop();
+        }
+    }
+
+    fn current(&self) -> Vec4 {
+        *self.rects.last().unwrap()
+    }
+
+    /// The `pieces` of the text laid out at `bb` that show inside of the clip rect.
+    pub(crate) fn visible<'a>(
+        &self,
+        pieces: &'a [TextPiece],
+        bb: BoundingBox,
+    ) -> impl Iterator<Item = &'a TextPiece> {
+        let clip = self.current();
+        let shown = bb.y < clip.w && bb.y + bb.height > clip.y;
+        pieces.iter().filter(move |piece| {
+            let x = bb.x + piece.x;
+            shown && x < clip.z && x + piece.width > clip.x
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_pieces() {
+        let text = "x".repeat(150);
+        assert_eq!(split_pieces(&text), [0..64, 64..128, 128..150]);
+        assert!(split_pieces("").is_empty());
+
+        // Ends after the last space in the second half of a piece
+        let words = format!("{} {}", "a".repeat(40), "b".repeat(60));
+        assert_eq!(split_pieces(&words), [0..41, 41..101]);
+        let early = format!("{} {}", "a".repeat(10), "b".repeat(60));
+        assert_eq!(split_pieces(&early), [0..64, 64..71]);
+
+        // Not cut inside of a character, "ö" takes two bytes
+        let text = format!("a{}", "ö".repeat(40));
+        assert_eq!(split_pieces(&text), [0..65, 65..81]);
+        assert!(!is_long(&text));
+        assert!(is_long(&"x".repeat(600)));
+        assert!(!is_long(&"x\n".repeat(300)));
+    }
+
+    #[test]
+    fn test_visible_pieces() {
+        let pieces: Vec<_> = (0..10)
+            .map(|index| TextPiece {
+                text: index.to_string(),
+                x: index as f32 * 100.0,
+                width: 100.0,
+            })
+            .collect();
+        let bb = |x, y| BoundingBox::new(x, y, 1000.0, 20.0);
+        let visible = |clips: &ClipStack, bb| -> Vec<_> {
+            clips
+                .visible(&pieces, bb)
+                .map(|piece| piece.text.as_str())
+                .collect()
+        };
+
+        let mut clips = ClipStack::new(250.0, 100.0);
+        assert_eq!(visible(&clips, bb(0.0, 0.0)), ["0", "1", "2"]);
+        // Scrolled to the left
+        assert_eq!(visible(&clips, bb(-420.0, 0.0)), ["4", "5", "6"]);
+        assert!(visible(&clips, bb(0.0, 100.0)).is_empty());
+
+        clips.push(BoundingBox::new(150.0, 0.0, 500.0, 50.0));
+        assert_eq!(visible(&clips, bb(0.0, 0.0)), ["1", "2"]);
+        assert!(visible(&clips, bb(0.0, 60.0)).is_empty());
+        clips.pop();
+        clips.pop();
+        assert_eq!(visible(&clips, bb(0.0, 60.0)), ["0", "1", "2"]);
+    }
+}
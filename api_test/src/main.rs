@@ -1,15 +1,82 @@
 #![allow(dead_code)]
 
 use crate::ui::FontStyle;
-use minifb::{Key, Window, WindowOptions};
+use minifb::{Key, KeyRepeat, Window, WindowOptions};
+mod accordion;
+mod animation;
+mod area_props;
+mod ballistics;
+mod budget;
+mod canvas;
+mod clip_grid;
+mod date_picker;
 mod daw_ui;
+#[cfg(feature = "debug-server")]
+mod debug_server;
+mod fader;
+mod file_tree;
+mod file_watch;
+mod focus;
 mod font;
+mod group;
+mod id_hash;
+mod id_path;
+mod image;
+mod images;
+mod input;
 mod internal_error;
-mod render_api;
+mod json;
+mod keyboard;
+mod knob;
+mod layout_debug;
+mod list_box;
+mod long_text;
+mod memo;
+mod menu;
+mod meter;
+mod meter_bridge;
+mod metrics;
+mod minifb_input;
+mod minimap;
+mod modal;
+mod pan;
+mod piano_roll;
+mod popup;
+mod radial_menu;
+mod rating;
+mod region;
+mod rotated_text;
+mod ruler;
+mod scale;
+#[cfg(feature = "script")]
+mod script;
+mod scroll_area;
+mod scrub;
+mod scrub_label;
+mod search;
+mod splitter;
+mod status_bar;
+mod stepper;
+mod style;
+mod table;
+mod tag_input;
+mod text_edit;
+mod text_limits;
+mod thread_safety;
 mod tiny_skia_renderer;
+mod toast;
+mod toolbar;
+mod tooltip;
+mod transition;
+mod transport;
+mod typography;
 mod ui;
+mod ui_description;
+mod value_drag;
+mod window;
 
 use crate::daw_ui::{DawState, daw_ui};
+use crate::minifb_input::MinifbInput;
 use ui::Ui;
 
 // Re-export for use in other modules
@@ -20,7 +87,7 @@ const HEIGHT: usize = 1080;
 
 fn main() {
     let mut buffer: Vec<u32> = vec![0; WIDTH * HEIGHT];
-    let ui = Ui::new();
+    let mut ui = Ui::new();
 
     let font = ui
         .load_font("data/Source_Sans_3/static/SourceSans3-Regular.ttf")
@@ -28,6 +95,16 @@ fn main() {
 
     ui.register_font(font, FontStyle::Default);
     ui.set_font(font);
+    ui.set_animated_layout(true);
+    // Leave time for the rest of a 60 fps frame, glyphs that miss it show up a frame later
+    ui.set_frame_budget(Some(std::time::Duration::from_millis(8)));
+    // Prints which widgets were used on exit
+    ui.set_widget_metrics(std::env::args().any(|arg| arg == "--metrics"));
+
+    #[cfg(feature = "debug-server")]
+    if let Err(e) = ui.start_debug_server(debug_server::DEFAULT_ADDR) {
+        eprintln!("Unable to start debug server: {}", e);
+    }
 
     let mut window = Window::new(
         "Test - ESC to exit",
@@ -41,6 +118,7 @@ fn main() {
 
     // Limit to max ~60 fps update rate
     window.set_target_fps(60);
+    let mut input = MinifbInput::new(&mut window);
     let mut daw_state = DawState::default(); // In real app, this would be persistent
     let mut last_time = std::time::Instant::now();
 
@@ -53,13 +131,26 @@ fn main() {
         let delta_time = current_time.duration_since(last_time);
         last_time = current_time;
 
+        input.poll(&window, &ui);
         ui.begin(delta_time.as_secs_f32(), (WIDTH, HEIGHT));
 
         daw_ui(&mut daw_state, &ui, WIDTH as f32, HEIGHT as f32);
 
         ui.end(&mut buffer);
+        input.update_cursor(&mut window, &ui);
+
+        if window.is_key_pressed(Key::F12, KeyRepeat::No) {
+            match ui.save_layout_debug("layout.png") {
+                Ok(()) => println!("Saved layout wireframe to layout.png"),
+                Err(e) => eprintln!("{}", e),
+            }
+        }
 
         // We unwrap here as we want this code to exit if it fails. Real applications may want to handle this in a different way
         window.update_with_buffer(&buffer, WIDTH, HEIGHT).unwrap();
     }
+
+    if let Some(report) = ui.widget_metrics() {
+        print!("{}", report);
+    }
 }
@@ -0,0 +1,398 @@
+//! Caching the render commands of static parts of the ui.
+//!
+//! [`Ui::memo`] wraps its content in a clip element so the content's commands can be found as
+//! the range between the element's scissor start and end. The range is copied after a frame has
+//! been laid out and spliced back in, moved to where the element ended up, in frames where the
+//! content is skipped. Floating elements in the content are laid out as roots of their own, so
+//! their commands end up outside the range and memos holding any aren't cached.
+use crate::image::ImageInfo;
+use crate::ui::Ui;
+use clay_layout::math::{BoundingBox, Vector2};
+use clay_layout::render_commands::{Border, Rectangle, RenderCommand, RenderCommandConfig, Text};
+use clay_layout::{Declaration, fixed, grow};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+
+type UiRenderCommand<'a> = RenderCommand<'a, ImageInfo, ()>;
+
+#[derive(Debug, Clone)]
+enum CachedConfig {
+    Rectangle(Rectangle),
+    Border(Border),
+    /// The text is kept separately as the command only borrows it for a frame.
+    Text(Text<'static>, String),
+    ScissorStart,
+    ScissorEnd,
+    None,
+}
+
+/// A render command that owns its data.
+#[derive(Debug, Clone)]
+struct CachedCommand {
+    bounding_box: BoundingBox,
+    config: CachedConfig,
+    id: u32,
+    z_index: i16,
+}
+
+impl CachedCommand {
+    /// Returns `None` for images and custom elements, which reference data owned by the frame.
+    fn new(command: &UiRenderCommand) -> Option<Self> {
+        let config = match &command.config {
+            RenderCommandConfig::Rectangle(rect) => CachedConfig::Rectangle(rect.clone()),
+            RenderCommandConfig::Border(border) => CachedConfig::Border(border.clone()),
+            RenderCommandConfig::Text(text) => CachedConfig::Text(
+                Text {
+                    text: "",
+                    ..text.clone()
+                },
+                text.text.to_string(),
+            ),
+            RenderCommandConfig::ScissorStart() => CachedConfig::ScissorStart,
+            RenderCommandConfig::ScissorEnd() => CachedConfig::ScissorEnd,
+            RenderCommandConfig::None() => CachedConfig::None,
+            RenderCommandConfig::Image(_) | RenderCommandConfig::Custom(_) => return None,
+        };
+
+        Some(Self {
+            bounding_box: command.bounding_box,
+            config,
+            id: command.id,
+            z_index: command.z_index,
+        })
+    }
+
+    fn to_command<'a>(
+        &self,
+        offset: Vector2,
+        alloc_str: &mut impl FnMut(String) -> &'a str,
+    ) -> UiRenderCommand<'a> {
+        let config = match &self.config {
+            CachedConfig::Rectangle(rect) => RenderCommandConfig::Rectangle(rect.clone()),
+            CachedConfig::Border(border) => RenderCommandConfig::Border(border.clone()),
+            CachedConfig::Text(config, text) => RenderCommandConfig::Text(Text {
+                text: alloc_str(text.clone()),
+                ..config.clone()
+            }),
+            CachedConfig::ScissorStart => RenderCommandConfig::ScissorStart(),
+            CachedConfig::ScissorEnd => RenderCommandConfig::ScissorEnd(),
+            CachedConfig::None => RenderCommandConfig::None(),
+        };
+
+        let bb = self.bounding_box;
+        UiRenderCommand {
+            bounding_box: BoundingBox::new(bb.x + offset.x, bb.y + offset.y, bb.width, bb.height),
+            config,
+            id: self.id,
+            z_index: self.z_index,
+        }
+    }
+}
+
+struct MemoEntry {
+    /// Hash of the data and the available space the commands were recorded with.
+    key: u64,
+    /// Where the memo element was when recorded.
+    bounding_box: BoundingBox,
+    commands: Vec<CachedCommand>,
+    /// Set when the cached commands can't be trusted anymore and should be recorded again.
+    stale: bool,
+    frame: u64,
+}
+
+enum FrameMemo {
+    /// `declared` are the ids of the elements declared in the content.
+    Record {
+        id: u32,
+        key: u64,
+        declared: HashSet<u32>,
+    },
+    Replay {
+        id: u32,
+    },
+}
+
+/// Cached commands of all memos and what to do with them at the end of the frame.
+#[derive(Default)]
+pub(crate) struct MemoCache {
+    entries: HashMap<u32, MemoEntry>,
+    frame: Vec<FrameMemo>,
+}
+
+/// Commands of the element `id` and its children: its scissor start up to the matching end.
+fn subtree_range(commands: &[UiRenderCommand], id: u32) -> Option<Range<usize>> {
+    let start = commands.iter().position(|command| {
+        command.id == id && matches!(command.config, RenderCommandConfig::ScissorStart())
+    })?;
+
+    let mut depth = 0;
+    for (index, command) in commands.iter().enumerate().skip(start + 1) {
+        match command.config {
+            RenderCommandConfig::ScissorStart() => depth += 1,
+            RenderCommandConfig::ScissorEnd() if depth == 0 => return Some(start..index + 1),
+            RenderCommandConfig::ScissorEnd() => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+impl MemoCache {
+    /// Records the commands of memos whose content was built this frame and splices in the
+    /// cached commands of the ones that were skipped. Memos not used this frame are dropped.
+    pub(crate) fn process<'a>(
+        &mut self,
+        commands: &mut Vec<UiRenderCommand<'a>>,
+        mut alloc_str: impl FnMut(String) -> &'a str,
+        frame: u64,
+    ) {
+        for memo in self.frame.drain(..) {
+            match memo {
+                FrameMemo::Record { id, key, declared } => {
+                    // Content placed outside the memo element, such as floating elements, can't
+                    // be spliced back in with it
+                    let range = subtree_range(commands, id).filter(|range| {
+                        !commands[..range.start]
+                            .iter()
+                            .chain(&commands[range.end..])
+                            .any(|command| declared.contains(&command.id))
+                    });
+                    let cached = range
+                        .clone()
+                        .and_then(|range| commands[range].iter().map(CachedCommand::new).collect());
+                    let (Some(range), Some(cached)) = (range, cached) else {
+                        // Offscreen, floating or not cacheable, the content is built every frame
+                        self.entries.remove(&id);
+                        continue;
+                    };
+
+                    let entry = MemoEntry {
+                        key,
+                        bounding_box: commands[range.start].bounding_box,
+                        commands: cached,
+                        stale: false,
+                        frame,
+                    };
+                    self.entries.insert(id, entry);
+                }
+                FrameMemo::Replay { id } => {
+                    let (Some(range), Some(entry)) =
+                        (subtree_range(commands, id), self.entries.get_mut(&id))
+                    else {
+                        continue;
+                    };
+
+                    let bb = commands[range.start].bounding_box;
+                    let offset =
+                        Vector2::new(bb.x - entry.bounding_box.x, bb.y - entry.bounding_box.y);
+                    // Clay skips offscreen commands, so parts missing from the recording may be
+                    // visible at the new position
+                    entry.stale |= offset.x != 0.0 || offset.y != 0.0;
+
+                    let replayed: Vec<_> = entry
+                        .commands
+                        .iter()
+                        .map(|command| command.to_command(offset, &mut alloc_str))
+                        .collect();
+                    commands.splice(range, replayed);
+                }
+            }
+        }
+
+        self.entries.retain(|_, entry| entry.frame == frame);
+    }
+
+    /// Forgets the memos of a frame that was given up, its commands were never generated.
+    pub(crate) fn discard_frame(&mut self) {
+        self.frame.clear();
+    }
+}
+
+impl Ui {
+    /// Builds the content added by `f` once and replays its render commands in later frames
+    /// while `data` hashes the same and the space around it is unchanged, skipping both the
+    /// widget code and the layout of the content. Meant for static panels such as the piano keys
+    /// column: while replayed, widgets inside don't react to input or animate. Content with
+    /// images or floating elements is built every frame.
+    ///
+    /// The memo element grows to fill its parent and lays out its children left to right.
+    pub fn memo(&self, id: &str, data: &impl Hash, f: impl FnOnce(&Ui)) {
+        let id = self.id(id);
+        let frame = self.current_frame();
+
+        let mut hasher = DefaultHasher::new();
+        data.hash(&mut hasher);
+        let (window_size, parent_rect) = self.available_space();
+        window_size.hash(&mut hasher);
+        if let Some(rect) = parent_rect {
+            rect.to_array().map(f32::to_bits).hash(&mut hasher);
+        }
+        let key = hasher.finish();
+
+        let cache = self.memo_cache();
+        let cached = cache
+            .entries
+            .get_mut(&id.id.id)
+            .filter(|entry| entry.key == key && !entry.stale);
+
+        let mut decl = Declaration::new();
+        decl.id(id).clip(true, true, Vector2::new(0.0, 0.0));
+
+        if let Some(entry) = cached {
+            entry.frame = frame;
+            let size = (entry.bounding_box.width, entry.bounding_box.height);
+            decl.layout()
+                .width(fixed!(size.0))
+                .height(fixed!(size.1))
+                .end();
+            self.with_layout(&decl, |_ui| {});
+            cache.frame.push(FrameMemo::Replay { id: id.id.id });
+        } else {
+            decl.layout().width(grow!()).height(grow!()).end();
+            let start = self.frame_tree().len();
+            self.with_layout(&decl, f);
+            let declared = self.frame_tree()[start..]
+                .iter()
+                .map(|node| node.id.id.id)
+                .filter(|&declared| declared != id.id.id)
+                .collect();
+            cache.frame.push(FrameMemo::Record {
+                id: id.id.id,
+                key,
+                declared,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clay_layout::color::Color;
+    use clay_layout::render_commands::CornerRadii;
+
+    fn command<'a>(
+        id: u32,
+        x: f32,
+        config: RenderCommandConfig<'a, ImageInfo, ()>,
+    ) -> UiRenderCommand<'a> {
+        RenderCommand {
+            bounding_box: BoundingBox::new(x, 0.0, 10.0, 10.0),
+            config,
+            id,
+            z_index: 0,
+        }
+    }
+
+    fn rect<'a>(id: u32, x: f32) -> UiRenderCommand<'a> {
+        let rect = Rectangle {
+            color: Color::rgb(255.0, 0.0, 0.0),
+            corner_radii: CornerRadii {
+                top_left: 0.0,
+                top_right: 0.0,
+                bottom_left: 0.0,
+                bottom_right: 0.0,
+            },
+        };
+        command(id, x, RenderCommandConfig::Rectangle(rect))
+    }
+
+    fn text<'a>(id: u32, x: f32, text: &'a str) -> UiRenderCommand<'a> {
+        let text = Text {
+            text,
+            color: Color::rgb(255.0, 255.0, 255.0),
+            font_id: 0,
+            font_size: 16,
+            letter_spacing: 0,
+            line_height: 0,
+        };
+        command(id, x, RenderCommandConfig::Text(text))
+    }
+
+    fn ids(commands: &[UiRenderCommand]) -> Vec<u32> {
+        commands.iter().map(|command| command.id).collect()
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let mut cache = MemoCache::default();
+        let mut strings = Vec::new();
+
+        // Memo 2 with a nested clip element 4
+        let mut commands = vec![
+            rect(1, 0.0),
+            command(2, 0.0, RenderCommandConfig::ScissorStart()),
+            rect(3, 0.0),
+            command(4, 0.0, RenderCommandConfig::ScissorStart()),
+            text(5, 0.0, "C4"),
+            command(6, 0.0, RenderCommandConfig::ScissorEnd()),
+            command(7, 0.0, RenderCommandConfig::ScissorEnd()),
+            rect(8, 0.0),
+        ];
+        assert_eq!(subtree_range(&commands, 2), Some(1..7));
+        assert_eq!(subtree_range(&commands, 4), Some(3..6));
+
+        cache.frame.push(FrameMemo::Record {
+            id: 2,
+            key: 1,
+            declared: HashSet::from([4]),
+        });
+        cache.process(&mut commands, |s| s.leak(), 0);
+        assert_eq!(cache.entries[&2].commands.len(), 6);
+
+        // The next frame only has the empty memo element, moved to the right
+        let mut commands = vec![
+            rect(1, 0.0),
+            command(2, 20.0, RenderCommandConfig::ScissorStart()),
+            command(9, 0.0, RenderCommandConfig::ScissorEnd()),
+            rect(8, 0.0),
+        ];
+        cache.entries.get_mut(&2).unwrap().frame = 1;
+        cache.frame.push(FrameMemo::Replay { id: 2 });
+        cache.process(
+            &mut commands,
+            |s| {
+                strings.push(s.clone());
+                s.leak()
+            },
+            1,
+        );
+
+        assert_eq!(ids(&commands), [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(commands[4].bounding_box.x, 20.0);
+        assert!(matches!(
+            commands[4].config,
+            RenderCommandConfig::Text(Text { text: "C4", .. })
+        ));
+        assert_eq!(strings, ["C4"]);
+        // Moved content is recorded again next time
+        assert!(cache.entries[&2].stale);
+
+        // Unused memos are dropped
+        cache.process(&mut Vec::new(), |s| s.leak(), 2);
+        assert!(cache.entries.is_empty());
+    }
+
+    #[test]
+    fn test_floating_content_is_not_cached() {
+        let mut cache = MemoCache::default();
+
+        // Memo 2 declared element 3, which floats and is drawn after the memo element
+        let mut commands = vec![
+            command(2, 0.0, RenderCommandConfig::ScissorStart()),
+            command(9, 0.0, RenderCommandConfig::ScissorEnd()),
+            rect(3, 0.0),
+        ];
+        cache.frame.push(FrameMemo::Record {
+            id: 2,
+            key: 1,
+            declared: HashSet::from([3]),
+        });
+        cache.process(&mut commands, |s| s.leak(), 0);
+
+        assert!(cache.entries.is_empty());
+        assert_eq!(ids(&commands), [2, 9, 3]);
+    }
+}
@@ -0,0 +1,321 @@
+//! Menu bars and nested menus built on popups.
+//!
+//! Every menu is a popup, so a submenu is a popup opened from inside another one and the usual
+//! popup rules close them. Items are numbered in declaration order, which is what the arrow keys
+//! move through. The highlighted item of each open menu is kept between frames.
+use crate::input::{Key, MouseButton};
+use crate::style::WidgetState;
+use crate::ui::{Ui, apply_style, rgb};
+use clay_layout::id::Id;
+use clay_layout::layout::{Alignment, LayoutAlignmentX, LayoutAlignmentY, LayoutDirection};
+use clay_layout::{Declaration, fit, grow};
+use glam::Vec2;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Default)]
+pub(crate) struct Menus {
+    /// Menus of the bar being declared, as (header, popup) pairs.
+    bar: Option<Vec<(Id, Id)>>,
+    /// Popups of all menu bar menus, hovering a header only switches between these.
+    bar_popups: HashSet<u32>,
+    /// Item highlighted by the mouse or keyboard in each menu.
+    highlight: HashMap<u32, usize>,
+    /// Number of items in each menu as of the last time it was declared.
+    item_counts: HashMap<u32, usize>,
+    /// Items declared so far in the menu being declared.
+    item_index: usize,
+    /// Set when an item acted on a key press, so the menu around it doesn't.
+    key_used: bool,
+    /// Left or Right pressed in a top level menu, moving to the neighbouring one.
+    switch: Option<isize>,
+}
+
+/// Moves the highlight through `count` items for Up and Down, wrapping around at the ends.
+fn step_highlight(highlight: Option<usize>, count: usize, key: Key) -> Option<usize> {
+    if count == 0 {
+        return highlight;
+    }
+    match key {
+        Key::Down => Some(highlight.map_or(0, |index| (index + 1) % count)),
+        Key::Up => Some(highlight.map_or(count - 1, |index| (index + count - 1) % count)),
+        _ => highlight,
+    }
+}
+
+impl Ui {
+    /// A horizontal bar of menus added with [`Ui::menu`]. Pressing a header opens its menu,
+    /// and while one is open, hovering another header or pressing Left and Right switches to
+    /// it.
+    ///
+    /// Styled by the `menu_bar`, `menu_header`, `menu` and `menu_item` stylesheet rules.
+    pub fn menu_bar(&self, f: impl FnOnce(&Ui)) {
+        let style = self.widget_style("menu_bar", Default::default());
+        let mut decl = Declaration::new();
+        decl.id(self.id("menu_bar"))
+            .layout()
+            .width(grow!())
+            .height(fit!())
+            .child_gap(2)
+            .direction(LayoutDirection::LeftToRight)
+            .end();
+        apply_style(&mut decl, &style);
+
+        let outer = self.menus().bar.replace(Vec::new());
+        self.with_layout(&decl, f);
+        let menus = self.menus();
+        let headers = std::mem::replace(&mut menus.bar, outer).unwrap_or_default();
+
+        let Some(step) = menus.switch.take() else {
+            return;
+        };
+        let open = self.popups().first();
+        if let Some(pos) = headers
+            .iter()
+            .position(|(_, popup)| Some(popup.id.id) == open)
+        {
+            let next = (pos as isize + step).rem_euclid(headers.len() as isize) as usize;
+            let (header, popup) = headers[next];
+            self.open_menu(popup, self.below(header));
+        }
+    }
+
+    /// A menu showing the items added by `f`. In a [`Ui::menu_bar`] it's a header opening the
+    /// menu below it, inside another menu it's an item opening a submenu to the side when
+    /// hovered or picked with Right or Enter. Returns true while the menu is shown.
+    pub fn menu(&self, label: &str, f: impl FnOnce(&Ui)) -> bool {
+        let id = self.id(label);
+        let popup_id = self.id_index(label, 1);
+        if self.menus().bar.is_some() {
+            self.bar_menu(id, popup_id, label, f)
+        } else {
+            self.submenu(id, popup_id, label, f)
+        }
+    }
+
+    /// An entry of a menu. Returns true when it's clicked or picked with Enter, which also
+    /// closes all open menus.
+    pub fn menu_item(&self, label: &str) -> bool {
+        let id = self.id(label);
+        let (pointed, highlighted) = self.menu_row(id, label, false, false);
+
+        // Moving to another entry closes the submenu open next to it
+        let popups = self.popups();
+        if pointed && popups.len() > popups.depth() {
+            popups.truncate(popups.depth());
+        }
+
+        let picked = self.input().was_clicked(id.id.id, MouseButton::Left)
+            || (highlighted && self.menu_key(Key::Enter));
+        if picked {
+            self.popups().truncate(0);
+        }
+        picked
+    }
+
+    fn bar_menu(&self, id: Id, popup_id: Id, label: &str, f: impl FnOnce(&Ui)) -> bool {
+        let input = self.input();
+        let popups = self.popups();
+        let menus = self.menus();
+        menus.bar_popups.insert(popup_id.id.id);
+        if let Some(bar) = menus.bar.as_mut() {
+            bar.push((id, popup_id));
+        }
+
+        // Menus open on press rather than click so the same press can drag onto an item
+        let open = popups.index_of(popup_id.id.id).is_some();
+        let pressed = input.button_pressed(MouseButton::Left)
+            && input.hit_test(input.mouse_pos) == Some(id.id.id);
+        let pointed = self.widget_state(id).hovered && input.mouse_delta != Vec2::ZERO;
+        let bar_open = popups
+            .first()
+            .is_some_and(|first| menus.bar_popups.contains(&first));
+        if pressed && open {
+            popups.truncate(0);
+        } else if pressed || (pointed && bar_open && !open) {
+            self.open_menu(popup_id, self.below(id));
+        }
+        let open = popups.index_of(popup_id.id.id).is_some();
+
+        let style = self.widget_style(
+            "menu_header",
+            WidgetState {
+                hovered: self.widget_state(id).hovered,
+                active: open,
+                disabled: false,
+            },
+        );
+        let color = style.text_color.unwrap_or(rgb(220, 220, 220));
+        let mut decl = Declaration::new();
+        decl.id(id)
+            .layout()
+            .width(fit!())
+            .height(fit!())
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Center,
+                LayoutAlignmentY::Center,
+            ))
+            .end();
+        apply_style(&mut decl, &style);
+        self.with_layout(&decl, |ui| ui.text_element(label, color));
+
+        self.menu_popup(popup_id, f)
+    }
+
+    fn submenu(&self, id: Id, popup_id: Id, label: &str, f: impl FnOnce(&Ui)) -> bool {
+        let popups = self.popups();
+        let depth = popups.depth();
+        let open = popups.index_of(popup_id.id.id) == Some(depth);
+        let (pointed, highlighted) = self.menu_row(id, label, true, open);
+
+        let clicked = self.input().was_clicked(id.id.id, MouseButton::Left);
+        let picked = highlighted && (self.menu_key(Key::Right) || self.menu_key(Key::Enter));
+        if !open && (pointed || clicked || picked) {
+            let pos = self
+                .last_frame_rect(id)
+                .map_or(self.input().mouse_pos, |rect| Vec2::new(rect.z, rect.y));
+            self.open_menu(popup_id, pos);
+            if picked {
+                self.menus().highlight.insert(popup_id.id.id, 0);
+            }
+        }
+
+        self.menu_popup(popup_id, f)
+    }
+
+    /// Declares the row of a menu entry. Returns whether the mouse moved over it this frame
+    /// and whether it's the highlighted entry of its menu.
+    fn menu_row(&self, id: Id, label: &str, submenu: bool, open: bool) -> (bool, bool) {
+        let input = self.input();
+        let menus = self.menus();
+        let index = menus.item_index;
+        menus.item_index += 1;
+
+        // A resting mouse leaves the highlight to the keyboard
+        let pointed = self.widget_state(id).hovered && input.mouse_delta != Vec2::ZERO;
+        let menu = self.popups().current();
+        if let Some(menu) = menu
+            && pointed
+        {
+            menus.highlight.insert(menu, index);
+        }
+        let highlighted = menu.is_some_and(|menu| menus.highlight.get(&menu) == Some(&index));
+
+        let style = self.widget_style(
+            "menu_item",
+            WidgetState {
+                hovered: highlighted || open,
+                ..Default::default()
+            },
+        );
+        let color = style.text_color.unwrap_or(rgb(220, 220, 220));
+        let mut decl = Declaration::new();
+        decl.id(id)
+            .layout()
+            .width(grow!())
+            .height(fit!())
+            .child_gap(24)
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Left,
+                LayoutAlignmentY::Center,
+            ))
+            .direction(LayoutDirection::LeftToRight)
+            .end();
+        apply_style(&mut decl, &style);
+
+        self.with_layout(&decl, |ui| {
+            ui.text_element(label, color);
+            if submenu {
+                let mut spacer = Declaration::new();
+                spacer.layout().width(grow!()).end();
+                ui.with_layout(&spacer, |_| {});
+                ui.text_element(">", color);
+            }
+        });
+        (pointed, highlighted)
+    }
+
+    /// Declares the popup of a menu, handling the keys that move within it.
+    fn menu_popup(&self, popup_id: Id, f: impl FnOnce(&Ui)) -> bool {
+        let Some(index) = self.popups().index_of(popup_id.id.id) else {
+            return false;
+        };
+        let input = self.input();
+        let top_most = index + 1 == self.popups().len();
+        let menus = self.menus();
+
+        if top_most {
+            let count = menus.item_counts.get(&popup_id.id.id).copied().unwrap_or(0);
+            let mut highlight = menus.highlight.get(&popup_id.id.id).copied();
+            for key in &input.keys_pressed {
+                if *key == Key::Left && index > 0 {
+                    self.popups().truncate(index);
+                    return false;
+                }
+                highlight = step_highlight(highlight, count, *key);
+            }
+            if let Some(highlight) = highlight {
+                menus.highlight.insert(popup_id.id.id, highlight);
+            }
+        }
+
+        // Items inside the menu aren't part of the bar around it
+        let bar = menus.bar.take();
+        let item_index = std::mem::replace(&mut menus.item_index, 0);
+        let key_used = std::mem::replace(&mut menus.key_used, false);
+        let shown = self.popup_panel(popup_id, "menu", f);
+
+        let menus = self.menus();
+        let count = std::mem::replace(&mut menus.item_index, item_index);
+        menus.item_counts.insert(popup_id.id.id, count);
+        let used = std::mem::replace(&mut menus.key_used, key_used);
+        menus.bar = bar;
+
+        if shown && top_most && index == 0 && !used {
+            if input.keys_pressed.contains(&Key::Left) {
+                menus.switch = Some(-1);
+            } else if input.keys_pressed.contains(&Key::Right) {
+                menus.switch = Some(1);
+            }
+        }
+        shown
+    }
+
+    fn open_menu(&self, popup_id: Id, pos: Vec2) {
+        self.menus().highlight.remove(&popup_id.id.id);
+        self.open_popup_at(popup_id, pos);
+    }
+
+    /// Below the left edge of `id` as laid out in the previous frame.
+    fn below(&self, id: Id) -> Vec2 {
+        self.last_frame_rect(id)
+            .map_or(self.input().mouse_pos, |rect| Vec2::new(rect.x, rect.w))
+    }
+
+    /// True once per frame if `key` was pressed while the menu being declared is the top-most
+    /// one.
+    fn menu_key(&self, key: Key) -> bool {
+        let popups = self.popups();
+        let menus = self.menus();
+        let top_most = popups.depth() > 0 && popups.depth() == popups.len();
+        if !top_most || menus.key_used || !self.input().keys_pressed.contains(&key) {
+            return false;
+        }
+        menus.key_used = true;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_highlight() {
+        assert_eq!(step_highlight(None, 3, Key::Down), Some(0));
+        assert_eq!(step_highlight(None, 3, Key::Up), Some(2));
+        assert_eq!(step_highlight(Some(2), 3, Key::Down), Some(0));
+        assert_eq!(step_highlight(Some(0), 3, Key::Up), Some(2));
+        assert_eq!(step_highlight(Some(1), 3, Key::Enter), Some(1));
+        assert_eq!(step_highlight(None, 0, Key::Down), None);
+    }
+}
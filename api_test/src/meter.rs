@@ -0,0 +1,184 @@
+//! Level meters for tracks and busses.
+//!
+//! [`Ui::meter`] takes the raw level and peak of a channel each frame and runs them through
+//! [`MeterBallistics`] kept by the ui, so the bar falls back smoothly and the peak is held
+//! for a moment whatever the frame rate. The bar is lit in segments colored by the level they
+//! start at, like the green, yellow and red of a hardware meter.
+use crate::ballistics::MeterBallistics;
+use crate::ui::{Ui, apply_style, rgb};
+use clay_layout::color::Color as ClayColor;
+use clay_layout::elements::{FloatingAttachToElement, FloatingClipToElement};
+use clay_layout::math::Vector2;
+use clay_layout::{Declaration, fixed};
+
+/// Length of a meter along its bar.
+const LENGTH: f32 = 120.0;
+/// Thickness of a meter across its bar.
+const THICKNESS: f32 = 8.0;
+/// Thickness of the peak-hold line.
+const PEAK_LINE: f32 = 2.0;
+
+/// Part of a meter's range drawn in one color, from `from` up to where the next one starts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeterSegment {
+    pub from: f32,
+    pub color: ClayColor,
+}
+
+/// Options for [`Ui::meter_with`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Meter {
+    pub vertical: bool,
+    /// Colors of the range, ordered by where they start. Levels go from 0.0 to 1.0.
+    pub segments: Vec<MeterSegment>,
+    pub ballistics: MeterBallistics,
+}
+
+impl Meter {
+    /// Green up to 0.7, yellow up to 0.9 and red above.
+    pub fn new(vertical: bool) -> Self {
+        Self {
+            vertical,
+            segments: vec![
+                MeterSegment {
+                    from: 0.0,
+                    color: rgb(70, 200, 90),
+                },
+                MeterSegment {
+                    from: 0.7,
+                    color: rgb(230, 200, 60),
+                },
+                MeterSegment {
+                    from: 0.9,
+                    color: rgb(230, 60, 50),
+                },
+            ],
+            ballistics: MeterBallistics::new(),
+        }
+    }
+
+    pub fn segments(mut self, segments: Vec<MeterSegment>) -> Self {
+        self.segments = segments;
+        self
+    }
+
+    pub fn ballistics(mut self, ballistics: MeterBallistics) -> Self {
+        self.ballistics = ballistics;
+        self
+    }
+}
+
+/// The lit parts of a meter at `level` as (from, to, color), one per segment it reaches.
+fn lit_spans(segments: &[MeterSegment], level: f32) -> Vec<(f32, f32, ClayColor)> {
+    let level = level.clamp(0.0, 1.0);
+    segments
+        .iter()
+        .enumerate()
+        .filter(|(_, segment)| segment.from < level)
+        .map(|(index, segment)| {
+            let end = segments.get(index + 1).map_or(1.0, |next| next.from);
+            (segment.from, end.min(level), segment.color)
+        })
+        .collect()
+}
+
+/// Color of the segment `level` is in.
+fn color_at(segments: &[MeterSegment], level: f32) -> Option<ClayColor> {
+    segments
+        .iter()
+        .rev()
+        .find(|segment| segment.from <= level)
+        .or(segments.first())
+        .map(|segment| segment.color)
+}
+
+impl Ui {
+    /// A meter showing `level` with a line at the held `peak`, both from 0.0 to 1.0 as measured
+    /// by the host this frame. Falling levels and peaks are smoothed over frames.
+    pub fn meter(&self, id: &str, level: f32, peak: f32, vertical: bool) {
+        self.meter_with(id, level, peak, &Meter::new(vertical));
+    }
+
+    /// [`Ui::meter`] with segment colors and ballistics. The ballistics are taken the first
+    /// time the meter is shown. The background of the bar is styled by the `meter` rule.
+    pub fn meter_with(&self, id: &str, level: f32, peak: f32, options: &Meter) {
+        let id = self.id(id);
+        let dt = self.delta_time();
+        let ballistics = self.meters().entry(id.id.id).or_insert(options.ballistics);
+        ballistics.update_levels(level, peak, dt);
+        let (level, peak) = (ballistics.level(), ballistics.peak());
+
+        let style = self.widget_style("meter", Default::default());
+        let (width, height) = if options.vertical {
+            (THICKNESS, LENGTH)
+        } else {
+            (LENGTH, THICKNESS)
+        };
+        // Rect of the part of the bar from `from` to `to`, bottom up or left to right
+        let bar = |from: f32, to: f32, color: ClayColor| {
+            let (start, length) = (from * LENGTH, (to - from) * LENGTH);
+            let (offset, width, height) = if options.vertical {
+                (Vector2::new(0.0, LENGTH - start - length), width, length)
+            } else {
+                (Vector2::new(start, 0.0), length, height)
+            };
+            let mut decl = Declaration::new();
+            decl.layout()
+                .width(fixed!(width))
+                .height(fixed!(height))
+                .end()
+                .floating()
+                .attach_to(FloatingAttachToElement::Parent)
+                .clip_to(FloatingClipToElement::AttachedParent)
+                .offset(offset)
+                .end()
+                .background_color(color);
+            decl
+        };
+
+        let mut decl = Declaration::new();
+        decl.id(id)
+            .layout()
+            .width(fixed!(width))
+            .height(fixed!(height))
+            .end();
+        apply_style(&mut decl, &style);
+        self.with_layout(&decl, |ui| {
+            for (from, to, color) in lit_spans(&options.segments, level) {
+                ui.with_layout(&bar(from, to, color), |_| {});
+            }
+            let peak = peak.clamp(0.0, 1.0);
+            if peak > 0.0
+                && let Some(color) = color_at(&options.segments, peak)
+            {
+                let from = (peak - PEAK_LINE / LENGTH).max(0.0);
+                ui.with_layout(&bar(from, peak, color), |_| {});
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lit_spans() {
+        let segments = Meter::new(true).segments;
+        let colors: Vec<ClayColor> = segments.iter().map(|segment| segment.color).collect();
+        assert_eq!(lit_spans(&segments, 0.5), [(0.0, 0.5, colors[0])]);
+        assert_eq!(
+            lit_spans(&segments, 0.8),
+            [(0.0, 0.7, colors[0]), (0.7, 0.8, colors[1])]
+        );
+        assert_eq!(
+            lit_spans(&segments, 2.0).last(),
+            Some(&(0.9, 1.0, colors[2]))
+        );
+        assert!(lit_spans(&segments, 0.0).is_empty());
+
+        assert_eq!(color_at(&segments, 0.95), Some(colors[2]));
+        assert_eq!(color_at(&segments, 0.7), Some(colors[1]));
+        assert_eq!(color_at(&[], 0.5), None);
+    }
+}
@@ -0,0 +1,293 @@
+//! Meter bridges, a row of level meters for every channel of a mix.
+//!
+//! [`Ui::meter_bridge`] draws all of its meters into a single image, filling the bars of every
+//! channel as one path with one gradient for the segment colors, so a bridge with dozens of
+//! channels costs about as much as one meter. Above each bar a clip light comes on when the
+//! channel reaches full scale and stays on until the channel is clicked.
+use crate::ballistics::MeterBallistics;
+use crate::image::ImageInfo;
+use crate::input::MouseButton;
+use crate::meter::{Meter, MeterSegment};
+use crate::tiny_skia_renderer::clay_to_tiny_skia_color;
+use crate::ui::{Ui, apply_style, rgb};
+use clay_layout::color::Color as ClayColor;
+use clay_layout::layout::{Alignment, LayoutAlignmentX, LayoutAlignmentY, LayoutDirection};
+use clay_layout::math::Vector2;
+use clay_layout::{Declaration, fit, fixed};
+use tiny_skia::{
+    FillRule, GradientStop, LinearGradient, Paint, PathBuilder, Pixmap, Point, Rect, Shader,
+    SpreadMode, Transform,
+};
+
+/// Width taken by each channel, its label included.
+const CHANNEL_WIDTH: f32 = 40.0;
+/// Width of the bar of each channel.
+const BAR_WIDTH: f32 = 10.0;
+/// Height of the bars.
+const LENGTH: f32 = 120.0;
+/// Height of the clip lights, which sit above the bars.
+const CLIP_HEIGHT: f32 = 6.0;
+/// Space between a clip light and its bar.
+const CLIP_GAP: f32 = 3.0;
+/// Thickness of the peak-hold lines.
+const PEAK_LINE: f32 = 2.0;
+
+/// One channel of a [`Ui::meter_bridge`] with its level and peak this frame, from 0.0 to 1.0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeterChannel<'a> {
+    pub label: &'a str,
+    pub level: f32,
+    pub peak: f32,
+}
+
+/// What a meter bridge keeps between frames, by bridge id.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct MeterBridge {
+    ballistics: Vec<MeterBallistics>,
+    clipped: Vec<bool>,
+}
+
+/// Smoothed level, held peak and clip light of a channel as drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ChannelLevels {
+    level: f32,
+    peak: f32,
+    clipped: bool,
+}
+
+/// Stops of a gradient going through `segments` from the bottom of a bar to the top, changing
+/// color sharply where a segment starts like separate bars would.
+fn gradient_stops(segments: &[MeterSegment]) -> Vec<GradientStop> {
+    let mut stops = Vec::with_capacity(segments.len() * 2);
+    for (index, segment) in segments.iter().enumerate() {
+        let color = clay_to_tiny_skia_color(segment.color);
+        let end = segments.get(index + 1).map_or(1.0, |next| next.from);
+        stops.push(GradientStop::new(segment.from.clamp(0.0, 1.0), color));
+        stops.push(GradientStop::new(end.clamp(0.0, 1.0), color));
+    }
+    stops
+}
+
+/// Image of the bars and clip lights of `channels`, `None` when there is nothing to draw.
+fn bridge_pixmap(
+    channels: &[ChannelLevels],
+    segments: &[MeterSegment],
+    background: ClayColor,
+    clip: ClayColor,
+) -> Option<Pixmap> {
+    let top = CLIP_HEIGHT + CLIP_GAP;
+    let mut pixmap = Pixmap::new(
+        (channels.len() as f32 * CHANNEL_WIDTH) as u32,
+        (top + LENGTH) as u32,
+    )?;
+    let bar_x = |index: usize| index as f32 * CHANNEL_WIDTH + (CHANNEL_WIDTH - BAR_WIDTH) * 0.5;
+    // Bar of the channel at `index` from `from` up to `to`, fractions of its height
+    let bar = |path: &mut PathBuilder, index: usize, from: f32, to: f32| {
+        let (from, to) = (from.clamp(0.0, 1.0), to.clamp(0.0, 1.0));
+        if let Some(rect) = Rect::from_xywh(
+            bar_x(index),
+            top + (1.0 - to) * LENGTH,
+            BAR_WIDTH,
+            (to - from) * LENGTH,
+        ) {
+            path.push_rect(rect);
+        }
+    };
+
+    // Everything of one color goes in one path, so it's filled in one go
+    let mut tracks = PathBuilder::new();
+    let mut lit = PathBuilder::new();
+    let mut lights = PathBuilder::new();
+    let mut lit_lights = PathBuilder::new();
+    for (index, channel) in channels.iter().enumerate() {
+        bar(&mut tracks, index, 0.0, 1.0);
+        bar(&mut lit, index, 0.0, channel.level);
+        if channel.peak > 0.0 {
+            bar(
+                &mut lit,
+                index,
+                channel.peak - PEAK_LINE / LENGTH,
+                channel.peak,
+            );
+        }
+        let light = Rect::from_xywh(bar_x(index), 0.0, BAR_WIDTH, CLIP_HEIGHT)?;
+        if channel.clipped {
+            lit_lights.push_rect(light);
+        } else {
+            lights.push_rect(light);
+        }
+    }
+
+    let mut paint = Paint::default();
+    let mut fill = |path: PathBuilder, paint: &Paint| {
+        if let Some(path) = path.finish() {
+            pixmap.fill_path(&path, paint, FillRule::Winding, Transform::identity(), None);
+        }
+    };
+    paint.set_color(clay_to_tiny_skia_color(background));
+    fill(tracks, &paint);
+    fill(lights, &paint);
+    paint.set_color(clay_to_tiny_skia_color(clip));
+    fill(lit_lights, &paint);
+    paint.shader = match segments {
+        [] => Shader::SolidColor(clay_to_tiny_skia_color(clip)),
+        [segment] => Shader::SolidColor(clay_to_tiny_skia_color(segment.color)),
+        _ => LinearGradient::new(
+            Point::from_xy(0.0, top + LENGTH),
+            Point::from_xy(0.0, top),
+            gradient_stops(segments),
+            SpreadMode::Pad,
+            Transform::identity(),
+        )?,
+    };
+    fill(lit, &paint);
+    Some(pixmap)
+}
+
+impl Ui {
+    /// A row of vertical meters, one per channel, with the channel labels below.
+    ///
+    /// Levels are smoothed and peaks held like in [`Ui::meter`]. A channel whose peak reaches
+    /// 1.0 lights its clip light until the channel is clicked. The bars are colored like
+    /// [`Meter::new`] and the lit clip lights like the top of the bars. The background of the
+    /// bars and of unlit clip lights is styled by the `meter` rule, the labels by `meter_bridge`.
+    pub fn meter_bridge(&self, id: &str, channels: &[MeterChannel]) {
+        let id = self.id(id);
+        let dt = self.delta_time();
+        let input = self.input();
+        let options = Meter::new(true);
+
+        // The channel clicked clears its clip light
+        let clicked = if input.was_clicked(id.id.id, MouseButton::Left)
+            && let Some(rect) = self.last_frame_rect(id)
+        {
+            Some(((input.mouse_pos.x - rect.x) / CHANNEL_WIDTH).floor() as usize)
+        } else {
+            None
+        };
+
+        let bridge = self.meter_bridges().entry(id.id.id).or_default();
+        bridge.ballistics.resize(channels.len(), options.ballistics);
+        bridge.clipped.resize(channels.len(), false);
+        let levels: Vec<ChannelLevels> = channels
+            .iter()
+            .enumerate()
+            .map(|(index, channel)| {
+                let ballistics = &mut bridge.ballistics[index];
+                ballistics.update_levels(channel.level, channel.peak, dt);
+                let clipped = &mut bridge.clipped[index];
+                if clicked == Some(index) {
+                    *clipped = false;
+                }
+                *clipped |= channel.peak >= 1.0;
+                ChannelLevels {
+                    level: ballistics.level(),
+                    peak: ballistics.peak(),
+                    clipped: *clipped,
+                }
+            })
+            .collect();
+
+        let meter_style = self.widget_style("meter", Default::default());
+        let style = self.widget_style("meter_bridge", Default::default());
+        let background = meter_style.background.unwrap_or(rgb(25, 25, 25));
+        let clip = options
+            .segments
+            .last()
+            .map_or(rgb(230, 60, 50), |segment| segment.color);
+        let color = style.text_color.unwrap_or(rgb(200, 200, 200));
+
+        let mut decl = Declaration::new();
+        decl.layout()
+            .width(fit!())
+            .height(fit!())
+            .child_gap(4)
+            .direction(LayoutDirection::TopToBottom)
+            .end();
+        apply_style(&mut decl, &style);
+        let mut bars = Declaration::new();
+        bars.id(id)
+            .layout()
+            .width(fixed!(channels.len() as f32 * CHANNEL_WIDTH))
+            .height(fixed!(CLIP_HEIGHT + CLIP_GAP + LENGTH))
+            .end();
+        if let Some(pixmap) = bridge_pixmap(&levels, &options.segments, background, clip) {
+            bars.image()
+                .data(self.alloc_image(ImageInfo::new(pixmap)))
+                .end();
+        }
+        let mut labels = Declaration::new();
+        labels
+            .layout()
+            .width(fit!())
+            .height(fit!())
+            .direction(LayoutDirection::LeftToRight)
+            .end();
+        let mut label = Declaration::new();
+        label
+            .layout()
+            .width(fixed!(CHANNEL_WIDTH))
+            .height(fit!())
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Center,
+                LayoutAlignmentY::Top,
+            ))
+            .end()
+            .clip(true, false, Vector2::new(0.0, 0.0));
+
+        self.with_layout(&decl, |ui| {
+            ui.with_layout(&bars, |_| {});
+            ui.with_layout(&labels, |ui| {
+                for channel in channels {
+                    ui.with_layout(&label, |ui| ui.text_element(channel.label, color));
+                }
+            });
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bridge_pixmap() {
+        let segments = Meter::new(true).segments;
+        let background = rgb(25, 25, 25);
+        let clip = rgb(230, 60, 50);
+        let channels = [
+            ChannelLevels {
+                level: 0.5,
+                peak: 0.8,
+                clipped: false,
+            },
+            ChannelLevels {
+                level: 0.95,
+                peak: 1.0,
+                clipped: true,
+            },
+        ];
+        let pixmap = bridge_pixmap(&channels, &segments, background, clip).unwrap();
+        assert_eq!(pixmap.width(), 80);
+        let pixel = |x: f32, y: f32| {
+            let pixel = pixmap
+                .pixel(x as u32, (CLIP_HEIGHT + CLIP_GAP + y) as u32)
+                .unwrap();
+            (pixel.red(), pixel.green(), pixel.blue())
+        };
+        let bottom = LENGTH - 2.0;
+        // Lit in the segment colors up to the level, the track above it
+        assert_eq!(pixel(20.0, bottom), (70, 200, 90));
+        assert_eq!(pixel(20.0, LENGTH * 0.4), (25, 25, 25));
+        assert_eq!(pixel(60.0, LENGTH * 0.2), (230, 200, 60));
+        assert_eq!(pixel(60.0, LENGTH * 0.08), (230, 60, 50));
+        // The peak line in the color of where it is
+        assert_eq!(pixel(20.0, LENGTH * 0.2 + 1.0), (230, 200, 60));
+        // Only the second clip light is on, the space between bars is empty
+        assert_eq!(pixmap.pixel(20, 2).unwrap().red(), 25);
+        assert_eq!(pixmap.pixel(60, 2).unwrap().red(), 230);
+        assert_eq!(pixmap.pixel(2, 60).unwrap().alpha(), 0);
+
+        assert!(bridge_pixmap(&[], &segments, background, clip).is_none());
+    }
+}
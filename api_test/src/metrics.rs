@@ -0,0 +1,181 @@
+//! Interaction metrics of widgets, for studying which controls users touch.
+//!
+//! Once [`Ui::set_widget_metrics`] turns them on, every element with an id counts how often the
+//! mouse entered it and clicked it, and when that first happened after it was shown.
+//! [`Ui::widget_metrics`] returns a [`MetricsReport`] which prints as a table. Nothing is
+//! recorded while the metrics are off, which is the default.
+use crate::ui::Ui;
+use std::collections::HashMap;
+use std::fmt;
+
+/// What was recorded for one element, with times in seconds since the ui was created.
+#[derive(Debug, Clone, Copy, Default)]
+struct Record {
+    first_seen: f64,
+    hovers: u32,
+    clicks: u32,
+    first_interaction: Option<f64>,
+}
+
+/// Collects the interactions while metrics are on.
+#[derive(Debug, Default)]
+pub(crate) struct MetricsRecorder {
+    records: HashMap<u32, Record>,
+}
+
+impl MetricsRecorder {
+    /// Notes that `id` was shown at `time`, only the first time counts.
+    pub(crate) fn seen(&mut self, id: u32, time: f64) {
+        self.records.entry(id).or_insert(Record {
+            first_seen: time,
+            ..Default::default()
+        });
+    }
+
+    /// Counts the mouse entering and clicking `id` at `time`. Elements that were never shown
+    /// are ignored, which leaves out the anonymous ones.
+    pub(crate) fn interact(&mut self, id: u32, entered: bool, clicked: bool, time: f64) {
+        let Some(record) = self.records.get_mut(&id) else {
+            return;
+        };
+        record.hovers += entered as u32;
+        record.clicks += clicked as u32;
+        if entered || clicked {
+            record.first_interaction.get_or_insert(time);
+        }
+    }
+
+    /// Metrics of all elements shown, the ones clicked most first, then hovered most.
+    fn report(&self, describe: impl Fn(u32) -> String) -> MetricsReport {
+        let mut widgets: Vec<_> = self
+            .records
+            .iter()
+            .map(|(id, record)| WidgetMetrics {
+                path: describe(*id),
+                hovers: record.hovers,
+                clicks: record.clicks,
+                time_to_first_interaction: record
+                    .first_interaction
+                    .map(|time| time - record.first_seen),
+            })
+            .collect();
+        widgets.sort_by(|a, b| {
+            (b.clicks, b.hovers)
+                .cmp(&(a.clicks, a.hovers))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        MetricsReport { widgets }
+    }
+}
+
+/// Interactions with one element, see [`Ui::widget_metrics`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct WidgetMetrics {
+    /// Path of the element, see [`Ui::id_path`].
+    pub path: String,
+    /// Times the mouse entered the element.
+    pub hovers: u32,
+    /// Times the element was clicked with the left button.
+    pub clicks: u32,
+    /// Seconds from the element first being shown to it first being hovered or clicked,
+    /// `None` if it never was.
+    pub time_to_first_interaction: Option<f64>,
+}
+
+/// Metrics of every element shown while recording.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetricsReport {
+    /// Clicked most first, then hovered most.
+    pub widgets: Vec<WidgetMetrics>,
+}
+
+impl MetricsReport {
+    /// Elements that were shown but never hovered or clicked.
+    pub fn untouched(&self) -> impl Iterator<Item = &WidgetMetrics> {
+        self.widgets
+            .iter()
+            .filter(|widget| widget.time_to_first_interaction.is_none())
+    }
+}
+
+impl fmt::Display for MetricsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:>7} {:>7} {:>9}  widget", "clicks", "hovers", "first")?;
+        for widget in &self.widgets {
+            let first = match widget.time_to_first_interaction {
+                Some(seconds) => format!("{:.1}s", seconds),
+                None => "-".to_string(),
+            };
+            writeln!(
+                f,
+                "{:>7} {:>7} {:>9}  {}",
+                widget.clicks, widget.hovers, first, widget.path
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Ui {
+    /// Starts recording widget metrics, or stops and discards them. Turning them on again
+    /// starts over.
+    pub fn set_widget_metrics(&self, enabled: bool) {
+        *self.metrics_recorder() = enabled.then(MetricsRecorder::default);
+    }
+
+    /// Metrics recorded since [`Ui::set_widget_metrics`] turned them on, `None` while off.
+    pub fn widget_metrics(&self) -> Option<MetricsReport> {
+        self.metrics_recorder()
+            .as_ref()
+            .map(|recorder| recorder.report(|id| self.describe_id(id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let mut recorder = MetricsRecorder::default();
+        recorder.seen(1, 0.0);
+        recorder.seen(2, 1.0);
+        recorder.seen(3, 1.0);
+        recorder.seen(2, 5.0);
+
+        recorder.interact(2, true, false, 3.0);
+        recorder.interact(2, false, true, 3.5);
+        recorder.interact(1, true, false, 4.0);
+        recorder.interact(1, false, false, 5.0);
+        recorder.interact(1, true, false, 6.0);
+        // Never shown
+        recorder.interact(4, true, true, 6.0);
+
+        let report = recorder.report(|id| format!("w{}", id));
+        let summary: Vec<_> = report
+            .widgets
+            .iter()
+            .map(|w| {
+                (
+                    w.path.as_str(),
+                    w.clicks,
+                    w.hovers,
+                    w.time_to_first_interaction,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("w2", 1, 1, Some(2.0)),
+                ("w1", 0, 2, Some(4.0)),
+                ("w3", 0, 0, None),
+            ]
+        );
+        assert_eq!(report.untouched().count(), 1);
+        assert_eq!(
+            report.to_string().lines().nth(1),
+            Some("      1       1      2.0s  w2")
+        );
+    }
+}
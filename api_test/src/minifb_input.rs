@@ -0,0 +1,148 @@
+//! Translates minifb window input into ui events.
+use crate::input::{CursorIcon, Event, Key, MouseButton};
+use crate::ui::Ui;
+use minifb::{CursorStyle, InputCallback, KeyRepeat, MouseMode, Window};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+const BUTTONS: [(minifb::MouseButton, MouseButton); 3] = [
+    (minifb::MouseButton::Left, MouseButton::Left),
+    (minifb::MouseButton::Right, MouseButton::Right),
+    (minifb::MouseButton::Middle, MouseButton::Middle),
+];
+
+fn map_key(key: minifb::Key) -> Option<Key> {
+    use minifb::Key as K;
+
+    Some(match key {
+        K::Left => Key::Left,
+        K::Right => Key::Right,
+        K::Up => Key::Up,
+        K::Down => Key::Down,
+        K::Home => Key::Home,
+        K::End => Key::End,
+        K::PageUp => Key::PageUp,
+        K::PageDown => Key::PageDown,
+        K::Enter | K::NumPadEnter => Key::Enter,
+        K::Escape => Key::Escape,
+        K::Tab => Key::Tab,
+        K::Backspace => Key::Backspace,
+        K::Delete => Key::Delete,
+        K::Space => Key::Space,
+        K::LeftShift | K::RightShift => Key::Shift,
+        K::LeftCtrl | K::RightCtrl => Key::Control,
+        K::LeftAlt | K::RightAlt => Key::Alt,
+        K::LeftSuper | K::RightSuper => Key::Super,
+        // Key0..Key9 and A..Z are laid out in order at the start of the enum
+        key if (key as u32) < 10 => Key::Character(char::from(b'0' + key as u8)),
+        key if (key as u32) < 36 => Key::Character(char::from(b'a' + key as u8 - 10)),
+        _ => return None,
+    })
+}
+
+fn map_cursor(cursor: CursorIcon) -> CursorStyle {
+    match cursor {
+        CursorIcon::Default => CursorStyle::Arrow,
+        CursorIcon::Text => CursorStyle::Ibeam,
+        CursorIcon::Crosshair => CursorStyle::Crosshair,
+        // minifb has no pointing hand
+        CursorIcon::Pointer => CursorStyle::OpenHand,
+        CursorIcon::Grab => CursorStyle::OpenHand,
+        CursorIcon::Grabbing => CursorStyle::ClosedHand,
+        CursorIcon::ResizeHorizontal => CursorStyle::ResizeLeftRight,
+        CursorIcon::ResizeVertical => CursorStyle::ResizeUpDown,
+        // Nor diagonal arrows
+        CursorIcon::ResizeDiagonal => CursorStyle::ResizeAll,
+        CursorIcon::Move => CursorStyle::ResizeAll,
+        // Hidden separately, see `MinifbInput::update_cursor`
+        CursorIcon::Hidden => CursorStyle::Arrow,
+    }
+}
+
+struct TextInput(Rc<RefCell<Vec<char>>>);
+
+impl InputCallback for TextInput {
+    fn add_char(&mut self, uni_char: u32) {
+        if let Some(ch) = char::from_u32(uni_char).filter(|c| !c.is_control()) {
+            self.0.borrow_mut().push(ch);
+        }
+    }
+}
+
+/// Polls a minifb window once per frame and pushes the changes as events.
+pub struct MinifbInput {
+    mouse_pos: Option<(f32, f32)>,
+    mouse_down: [bool; 3],
+    text: Rc<RefCell<Vec<char>>>,
+    cursor: CursorIcon,
+}
+
+impl MinifbInput {
+    pub fn new(window: &mut Window) -> Self {
+        let text = Rc::new(RefCell::new(Vec::new()));
+        window.set_input_callback(Box::new(TextInput(text.clone())));
+
+        Self {
+            mouse_pos: None,
+            mouse_down: [false; 3],
+            text,
+            cursor: CursorIcon::Default,
+        }
+    }
+
+    /// Call before `Ui::begin`.
+    pub fn poll(&mut self, window: &Window, ui: &Ui) {
+        let mouse_pos = window.get_mouse_pos(MouseMode::Discard);
+        if let Some((x, y)) = mouse_pos.filter(|pos| Some(*pos) != self.mouse_pos) {
+            ui.push_event(Event::MouseMove { x, y });
+        }
+        self.mouse_pos = mouse_pos;
+
+        for (down, (window_button, button)) in self.mouse_down.iter_mut().zip(BUTTONS) {
+            let is_down = window.get_mouse_down(window_button);
+            if is_down != *down {
+                *down = is_down;
+                ui.push_event(if is_down {
+                    Event::MouseDown { button }
+                } else {
+                    Event::MouseUp { button }
+                });
+            }
+        }
+
+        if let Some((dx, dy)) = window.get_scroll_wheel() {
+            ui.push_event(Event::Scroll { dx, dy });
+        }
+
+        for key in window.get_keys_pressed(KeyRepeat::Yes) {
+            if let Some(key) = map_key(key) {
+                ui.push_event(Event::KeyDown { key });
+            }
+        }
+
+        for key in window.get_keys_released() {
+            if let Some(key) = map_key(key) {
+                ui.push_event(Event::KeyUp { key });
+            }
+        }
+
+        for ch in self.text.borrow_mut().drain(..) {
+            ui.push_event(Event::Text { ch });
+        }
+    }
+
+    /// Call after `Ui::end` to show the cursor requested by the hovered element.
+    ///
+    /// minifb can't move the pointer, so [`Ui::take_cursor_warp`] is left alone and scrubbing
+    /// a value stops at the window edge instead of wrapping around.
+    pub fn update_cursor(&mut self, window: &mut Window, ui: &Ui) {
+        let cursor = ui.cursor_icon();
+        if cursor != self.cursor {
+            if (cursor == CursorIcon::Hidden) != (self.cursor == CursorIcon::Hidden) {
+                window.set_cursor_visibility(cursor != CursorIcon::Hidden);
+            }
+            self.cursor = cursor;
+            window.set_cursor_style(map_cursor(cursor));
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! Minimaps, scaled down overviews of content too large to see at once.
+//!
+//! [`Ui::minimap`] squeezes the whole content into the space it's given, each axis on its own,
+//! so a long timeline still fills a short strip. The caller passes the parts worth showing as
+//! [`MinimapItem`]s and the part currently in view, which is drawn as a rectangle on top. The
+//! minimap doesn't scroll anything itself, it returns where the view was dragged to so the
+//! caller can scroll there, usually with [`Ui::scroll_to`].
+use crate::input::{CursorIcon, MouseButton};
+use crate::style::WidgetState;
+use crate::ui::{Ui, UiDeclaration, apply_style};
+use clay_layout::color::Color as ClayColor;
+use clay_layout::elements::{FloatingAttachToElement, FloatingClipToElement};
+use clay_layout::math::Vector2;
+use clay_layout::{Declaration, fixed, grow};
+use glam::{Vec2, Vec2Swizzles, Vec4, Vec4Swizzles};
+
+/// Something shown in a minimap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MinimapItem {
+    /// Rectangle (x0, y0, x1, y1) in the coordinates of the content.
+    pub rect: Vec4,
+    pub color: ClayColor,
+}
+
+/// Scale from content `content` big to a minimap `size` big, zero along axes without content.
+fn map_scale(size: Vec2, content: Vec2) -> Vec2 {
+    let scale = |size: f32, content: f32| if content > 0.0 { size / content } else { 0.0 };
+    Vec2::new(scale(size.x, content.x), scale(size.y, content.y))
+}
+
+/// `offset` of a view `view` big kept inside of content `content` big.
+fn clamp_offset(offset: Vec2, view: Vec2, content: Vec2) -> Vec2 {
+    offset.clamp(Vec2::ZERO, (content - view).max(Vec2::ZERO))
+}
+
+/// A rectangle floating over the minimap at `rect` in minimap pixels, at least a pixel big so
+/// thin items don't disappear.
+fn map_rect(rect: Vec4) -> UiDeclaration<'static> {
+    let size = (rect.zw() - rect.xy()).max(Vec2::ONE);
+    let mut decl = Declaration::new();
+    decl.layout()
+        .width(fixed!(size.x))
+        .height(fixed!(size.y))
+        .end()
+        .floating()
+        .attach_to(FloatingAttachToElement::Parent)
+        .clip_to(FloatingClipToElement::AttachedParent)
+        .offset(Vector2::new(rect.x.round(), rect.y.round()))
+        .end();
+    decl
+}
+
+impl Ui {
+    /// An overview of content `content_extent` big filling the space it's given, showing
+    /// `items` and the part of the content in `viewport`, both as rectangles (x0, y0, x1, y1)
+    /// in content coordinates. Returns the offset of the top left corner of the view it was
+    /// dragged to this frame.
+    ///
+    /// Dragging the view rectangle moves it, pressing anywhere else centers it there first.
+    /// The drag modifiers slow it down and keep it on one axis, see
+    /// [`crate::value_drag::DragConfig`].
+    ///
+    /// Styled by the `minimap` rule and the view rectangle by `minimap_viewport`, with `:hover`
+    /// and `:active` while dragged.
+    pub fn minimap(
+        &self,
+        id: &str,
+        content_extent: Vec2,
+        viewport: Vec4,
+        items: &[MinimapItem],
+    ) -> Option<Vec2> {
+        let map_id = self.id(id);
+        let view_id = self.id_index(id, 1);
+        let input = self.input();
+        let rect = self.last_frame_rect(map_id).unwrap_or_default();
+        let scale = map_scale(rect.zw() - rect.xy(), content_extent);
+        let view_size = viewport.zw() - viewport.xy();
+
+        let held_map =
+            input.button_down(MouseButton::Left) && input.pressed_id == Some(map_id.id.id);
+        let held_view =
+            input.button_down(MouseButton::Left) && input.pressed_id == Some(view_id.id.id);
+        let mut offset = viewport.xy();
+        if let Some((from, moved)) = self.drag_offset(map_id, held_map || held_view)
+            && scale.cmpgt(Vec2::ZERO).all()
+        {
+            // The offset the drag started from is kept as the scroll of the minimap
+            let item = self.item_state(map_id);
+            if input.button_pressed(MouseButton::Left) {
+                item.scroll = if held_map {
+                    (from - rect.xy()) / scale - view_size * 0.5
+                } else {
+                    viewport.xy()
+                };
+            }
+            offset = clamp_offset(item.scroll + moved / scale, view_size, content_extent);
+        }
+
+        let style = self.widget_style("minimap", WidgetState::default());
+        let hovered = self
+            .last_frame_rect(view_id)
+            .is_some_and(|rect| input.is_hovered(rect));
+        let view_style = self.widget_style(
+            "minimap_viewport",
+            WidgetState {
+                hovered,
+                active: held_map || held_view,
+                disabled: false,
+            },
+        );
+        self.set_cursor(view_id, CursorIcon::Grab);
+
+        let mut decl = Declaration::new();
+        decl.id(map_id)
+            .layout()
+            .width(grow!())
+            .height(grow!())
+            .end()
+            .clip(true, true, Vector2::new(0.0, 0.0));
+        apply_style(&mut decl, &style);
+        self.with_layout(&decl, |ui| {
+            for item in items {
+                let mut decl = map_rect(item.rect * scale.xyxy());
+                decl.background_color(item.color);
+                ui.with_layout(&decl, |_| {});
+            }
+            let view = Vec4::from((offset, offset + view_size));
+            let mut decl = map_rect(view * scale.xyxy());
+            decl.id(view_id);
+            apply_style(&mut decl, &view_style);
+            ui.with_layout(&decl, |_| {});
+        });
+
+        (offset != viewport.xy()).then_some(offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_scale() {
+        // A long timeline squeezed into a short strip
+        let scale = map_scale(Vec2::new(200.0, 20.0), Vec2::new(8000.0, 400.0));
+        assert_eq!(scale, Vec2::new(0.025, 0.05));
+        assert_eq!(
+            map_scale(Vec2::new(200.0, 20.0), Vec2::new(0.0, 10.0)).x,
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_clamp_offset() {
+        let content = Vec2::new(1000.0, 500.0);
+        let view = Vec2::new(200.0, 500.0);
+        assert_eq!(
+            clamp_offset(Vec2::new(900.0, 30.0), view, content),
+            Vec2::new(800.0, 0.0)
+        );
+        assert_eq!(
+            clamp_offset(Vec2::new(-5.0, 0.0), view, content),
+            Vec2::ZERO
+        );
+        // Content smaller than the view stays at the start
+        assert_eq!(
+            clamp_offset(Vec2::new(40.0, 0.0), Vec2::splat(2000.0), content),
+            Vec2::ZERO
+        );
+    }
+}
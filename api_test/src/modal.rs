@@ -0,0 +1,73 @@
+//! Modal dialogs.
+//!
+//! [`Ui::modal`] covers the window with a dimmed backdrop floating above the rest of the frame
+//! and centers the dialog on it. The backdrop captures the mouse: hit testing, hover and
+//! cursors only consider it and the dialog while it is shown, and Tab only moves the keyboard
+//! focus between the widgets of the dialog.
+use crate::input::Key;
+use crate::ui::{Ui, apply_style, rgba};
+use clay_layout::elements::FloatingAttachToElement;
+use clay_layout::layout::{Alignment, LayoutAlignmentX, LayoutAlignmentY, LayoutDirection};
+use clay_layout::math::Vector2;
+use clay_layout::{Declaration, fit, fixed};
+
+/// Above the rest of the frame but below tooltips.
+const MODAL_Z_INDEX: i16 = 900;
+
+impl Ui {
+    /// Shows the content added by `f` in a dialog centered over the window while `open` is
+    /// true, dimming and blocking mouse input to the rest of the ui. Escape sets `open` to
+    /// false. Returns true while shown.
+    ///
+    /// Styled by the `modal` stylesheet rule for the dialog and `modal_backdrop` for the
+    /// backdrop's `background`.
+    pub fn modal(&self, id: &str, open: &mut bool, f: impl FnOnce(&Ui)) -> bool {
+        if *open && self.input().keys_pressed.contains(&Key::Escape) {
+            *open = false;
+        }
+        if !*open {
+            return false;
+        }
+
+        let dialog_id = self.id(id);
+        let backdrop_id = self.id_index(id, 1);
+        let (width, height) = self.window_size();
+
+        let backdrop_style = self.widget_style("modal_backdrop", Default::default());
+        let mut backdrop = Declaration::new();
+        backdrop
+            .id(backdrop_id)
+            .layout()
+            .width(fixed!(width as f32))
+            .height(fixed!(height as f32))
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Center,
+                LayoutAlignmentY::Center,
+            ))
+            .end()
+            .floating()
+            .attach_to(FloatingAttachToElement::Root)
+            .offset(Vector2::new(0.0, 0.0))
+            .z_index(MODAL_Z_INDEX)
+            .end()
+            .background_color(backdrop_style.background.unwrap_or(rgba(0, 0, 0, 140)));
+
+        let style = self.widget_style("modal", Default::default());
+        let mut dialog = Declaration::new();
+        dialog
+            .id(dialog_id)
+            .layout()
+            .width(fit!())
+            .height(fit!())
+            .direction(LayoutDirection::TopToBottom)
+            .child_gap(8)
+            .end();
+        apply_style(&mut dialog, &style);
+
+        self.set_modal_layer(backdrop_id);
+        self.with_layout(&backdrop, |ui| {
+            ui.with_layout(&dialog, |ui| ui.focus_trap(dialog_id, f))
+        });
+        true
+    }
+}
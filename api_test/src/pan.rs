@@ -0,0 +1,333 @@
+//! Pan controls, placing a sound between the speakers of a mix.
+//!
+//! [`Ui::pan`] is the stereo balance of a channel strip, a point dragged along a strip from
+//! left to right. [`Ui::surround_pan`] places a source on a square pad with the speakers of a
+//! [`SpeakerLayout`] drawn around it, each lit by how much of the source it plays. Positions
+//! go from -1.0 to 1.0 on both axes, left to right and back to front.
+use crate::input::MouseButton;
+use crate::style::{Style, WidgetState};
+use crate::ui::{Ui, UiDeclaration, apply_style, rgb};
+use crate::value_drag::round_to;
+use clay_layout::color::Color as ClayColor;
+use clay_layout::elements::{FloatingAttachToElement, FloatingClipToElement};
+use clay_layout::id::Id;
+use clay_layout::layout::{Alignment, LayoutAlignmentX, LayoutAlignmentY, LayoutDirection};
+use clay_layout::math::Vector2;
+use clay_layout::{Declaration, fit, fixed};
+use glam::{Vec2, Vec4, Vec4Swizzles};
+
+/// Width and height of the surround pad.
+const PAD_SIZE: f32 = 96.0;
+/// Length of the stereo strip.
+const STRIP_LENGTH: f32 = 120.0;
+/// Steps positions move in on both axes while stepping is held.
+const STEP: f32 = 0.1;
+/// Thickness of the stereo strip.
+const STRIP_THICKNESS: f32 = 14.0;
+/// Size of the point showing the position of the source.
+const SOURCE_SIZE: f32 = 10.0;
+/// Size of the speaker dots.
+const SPEAKER_SIZE: f32 = 6.0;
+
+const STEREO: [Vec2; 2] = [Vec2::new(-1.0, 1.0), Vec2::new(1.0, 1.0)];
+const QUAD: [Vec2; 4] = [
+    Vec2::new(-1.0, 1.0),
+    Vec2::new(1.0, 1.0),
+    Vec2::new(-1.0, -1.0),
+    Vec2::new(1.0, -1.0),
+];
+const SURROUND: [Vec2; 5] = [
+    Vec2::new(-0.6, 1.0),
+    Vec2::new(0.0, 1.0),
+    Vec2::new(0.6, 1.0),
+    Vec2::new(-1.0, -0.7),
+    Vec2::new(1.0, -0.7),
+];
+
+/// Speakers around a [`Ui::surround_pan`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SpeakerLayout {
+    /// Left and right at the front corners.
+    #[default]
+    Stereo,
+    /// One speaker in each corner.
+    Quad,
+    /// Left, center and right at the front and two surrounds behind, 5.1 without the LFE which
+    /// has no position.
+    Surround,
+}
+
+impl SpeakerLayout {
+    /// Positions of the speakers, from -1.0 to 1.0 on both axes.
+    pub fn speakers(self) -> &'static [Vec2] {
+        match self {
+            SpeakerLayout::Stereo => &STEREO,
+            SpeakerLayout::Quad => &QUAD,
+            SpeakerLayout::Surround => &SURROUND,
+        }
+    }
+}
+
+/// How loud each of `speakers` plays a source at `position`, louder the closer it is. The
+/// gains keep the same total power wherever the source is.
+pub fn speaker_gains(position: Vec2, speakers: &[Vec2]) -> Vec<f32> {
+    let weights: Vec<f32> = speakers
+        .iter()
+        .map(|speaker| 1.0 / (position.distance_squared(*speaker) + 0.01))
+        .collect();
+    let power = weights
+        .iter()
+        .map(|weight| weight * weight)
+        .sum::<f32>()
+        .sqrt();
+    weights.iter().map(|weight| weight / power).collect()
+}
+
+/// Position of the screen point `point` over `rect` with its edges moved in by `inset`, -1.0
+/// to 1.0 with up being positive.
+fn to_position(point: Vec2, rect: Vec4, inset: f32) -> Vec2 {
+    let size = (rect.zw() - rect.xy() - inset * 2.0).max(Vec2::ONE);
+    let unit = (point - rect.xy() - inset) / size;
+    Vec2::new(unit.x * 2.0 - 1.0, 1.0 - unit.y * 2.0).clamp(Vec2::NEG_ONE, Vec2::ONE)
+}
+
+/// Offset of the top left corner of a dot `size` wide at `position` in an element `area` big,
+/// kept inside of it at the edges.
+fn dot_offset(position: Vec2, area: Vec2, size: f32) -> Vector2 {
+    let offset = (Vec2::new(position.x + 1.0, 1.0 - position.y) * 0.5 * (area - size)).round();
+    Vector2::new(offset.x, offset.y)
+}
+
+/// `balance` the way mixers show it, `C` in the middle and a percentage to either side.
+fn format_balance(balance: f32) -> String {
+    let percent = (balance.clamp(-1.0, 1.0) * 100.0).round();
+    if percent < 0.0 {
+        format!("L{}", -percent)
+    } else if percent > 0.0 {
+        format!("R{}", percent)
+    } else {
+        "C".to_string()
+    }
+}
+
+/// A round dot floating over its parent at `offset`.
+fn dot(id: Option<Id>, offset: Vector2, size: f32, style: &Style) -> UiDeclaration<'static> {
+    let mut decl = Declaration::new();
+    if let Some(id) = id {
+        decl.id(id);
+    }
+    decl.layout()
+        .width(fixed!(size))
+        .height(fixed!(size))
+        .end()
+        .floating()
+        .attach_to(FloatingAttachToElement::Parent)
+        .clip_to(FloatingClipToElement::AttachedParent)
+        .offset(offset)
+        .end();
+    apply_style(&mut decl, style);
+    decl.corner_radius().all(size * 0.5).end();
+    decl
+}
+
+impl Ui {
+    /// A stereo balance for `balance` from -1.0, all left, to 1.0, all right, labeled with
+    /// `label`. Pressing the strip moves the point there and dragging follows the mouse, with
+    /// the balance shown next to it. Returns true when the balance changed this frame.
+    ///
+    /// The strip is styled by the `pan` rule and the point by the `pan_source` rules.
+    pub fn pan(&self, label: &str, balance: &mut f32) -> bool {
+        let old_balance = *balance;
+        let mut position = Vec2::new(*balance, 0.0);
+        let active = self.pan_drag(label, &mut position);
+        *balance = position.x;
+
+        let area = Vec2::new(STRIP_LENGTH, STRIP_THICKNESS);
+        let offset = dot_offset(Vec2::new(*balance, 0.0), area, SOURCE_SIZE);
+        let text = active.then(|| self.alloc_str(format_balance(*balance)));
+        self.pan_widget(label, area, &[], offset, active, text);
+        *balance != old_balance
+    }
+
+    /// A pad placing a source at `position` among the speakers of `layout`, labeled with
+    /// `label`. Both axes go from -1.0 to 1.0, left to right and back to front. Pressing the
+    /// pad moves the source there and dragging follows the mouse, with the position shown next
+    /// to it. Returns true when the position changed this frame.
+    ///
+    /// The pad is styled by the `pan` rule, the source by the `pan_source` rules and the
+    /// speakers by `pan_speaker`, faded by how much of the source they play.
+    pub fn surround_pan(&self, label: &str, position: &mut Vec2, layout: SpeakerLayout) -> bool {
+        let old_position = *position;
+        let active = self.pan_drag(label, position);
+
+        let area = Vec2::splat(PAD_SIZE);
+        let speakers = layout.speakers();
+        let gains = speaker_gains(*position, speakers);
+        let speakers: Vec<(Vector2, f32)> = speakers
+            .iter()
+            .zip(gains)
+            .map(|(speaker, gain)| (dot_offset(*speaker, area, SPEAKER_SIZE), gain))
+            .collect();
+        let offset = dot_offset(*position, area, SOURCE_SIZE);
+        let text = active.then(|| self.alloc_str(format!("{:.2}, {:.2}", position.x, position.y)));
+        self.pan_widget(label, area, &speakers, offset, active, text);
+        *position != old_position
+    }
+
+    /// Moves `position` to the mouse while the pan labeled `label` is held, returning whether
+    /// it is. The position follows the drag from where it was pressed, so the drag modifiers
+    /// can slow it down, step it and lock it to one axis.
+    fn pan_drag(&self, label: &str, position: &mut Vec2) -> bool {
+        let id = self.id(label);
+        let source_id = self.id_index(label, 1);
+        let input = self.input();
+        let held = input.button_down(MouseButton::Left)
+            && (input.pressed_id == Some(id.id.id) || input.pressed_id == Some(source_id.id.id));
+        if let Some((from, offset)) = self.drag_offset(id, held)
+            && let Some(rect) = self.last_frame_rect(id)
+        {
+            *position = to_position(from + offset, rect, SOURCE_SIZE * 0.5);
+            if self.drag_config().stepping(input.modifiers()) {
+                *position = Vec2::new(round_to(position.x, STEP), round_to(position.y, STEP));
+            }
+        }
+        *position = position.clamp(Vec2::NEG_ONE, Vec2::ONE);
+        held
+    }
+
+    /// Draws a pan of `area` with dots for `speakers` at their offsets and gains and the source
+    /// at `source`, showing `text` next to the source when given.
+    fn pan_widget(
+        &self,
+        label: &str,
+        area: Vec2,
+        speakers: &[(Vector2, f32)],
+        source: Vector2,
+        active: bool,
+        text: Option<&str>,
+    ) {
+        let id = self.id(label);
+        let source_id = self.id_index(label, 1);
+        let hovered = self
+            .last_frame_rect(source_id)
+            .is_some_and(|rect| self.input().is_hovered(rect));
+        let style = self.widget_style("pan", WidgetState::default());
+        let speaker_style = self.widget_style("pan_speaker", WidgetState::default());
+        let source_style = self.widget_style(
+            "pan_source",
+            WidgetState {
+                hovered,
+                active,
+                ..Default::default()
+            },
+        );
+        let color = style.text_color.unwrap_or(rgb(220, 220, 220));
+        let speaker_color = speaker_style.background.unwrap_or(rgb(100, 150, 255));
+
+        let mut decl = Declaration::new();
+        decl.layout()
+            .width(fit!())
+            .height(fit!())
+            .child_gap(2)
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Center,
+                LayoutAlignmentY::Top,
+            ))
+            .direction(LayoutDirection::TopToBottom)
+            .end();
+        let mut field = Declaration::new();
+        field
+            .id(id)
+            .layout()
+            .width(fixed!(area.x))
+            .height(fixed!(area.y))
+            .end();
+        apply_style(&mut field, &style);
+        // Floating so the pan doesn't move as the value changes
+        let mut readout = Declaration::new();
+        readout
+            .layout()
+            .width(fit!())
+            .height(fit!())
+            .end()
+            .floating()
+            .attach_to(FloatingAttachToElement::Parent)
+            .offset(Vector2::new(SOURCE_SIZE + 4.0, SOURCE_SIZE + 2.0))
+            .end();
+
+        self.with_layout(&decl, |ui| {
+            ui.with_layout(&field, |ui| {
+                for (offset, gain) in speakers {
+                    // Speakers not playing the source stay faintly visible
+                    let style = Style {
+                        background: Some(ClayColor {
+                            a: speaker_color.a * (0.25 + 0.75 * gain.clamp(0.0, 1.0)),
+                            ..speaker_color
+                        }),
+                        ..speaker_style
+                    };
+                    ui.with_layout(&dot(None, *offset, SPEAKER_SIZE, &style), |_| {});
+                }
+                let source = dot(Some(source_id), source, SOURCE_SIZE, &source_style);
+                ui.with_layout(&source, |ui| {
+                    if let Some(text) = text {
+                        ui.with_layout(&readout, |ui| ui.text_element(text, color));
+                    }
+                });
+            });
+            ui.text_element(label, color);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-3, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_speaker_gains() {
+        // Evenly spread in the middle, the power stays the same anywhere
+        let gains = speaker_gains(Vec2::ZERO, &QUAD);
+        for gain in &gains {
+            assert_near(*gain, 0.5);
+        }
+        let gains = speaker_gains(Vec2::new(0.3, -0.6), &SURROUND);
+        assert_near(gains.iter().map(|gain| gain * gain).sum(), 1.0);
+
+        // On a speaker nearly only that one plays
+        let gains = speaker_gains(Vec2::new(1.0, 1.0), &STEREO);
+        assert!(gains[1] > 0.99 && gains[0] < 0.01);
+    }
+
+    #[test]
+    fn test_positions() {
+        let rect = Vec4::new(10.0, 20.0, 110.0, 120.0);
+        assert_eq!(to_position(Vec2::new(60.0, 70.0), rect, 0.0), Vec2::ZERO);
+        assert_eq!(
+            to_position(Vec2::new(10.0, 20.0), rect, 0.0),
+            Vec2::new(-1.0, 1.0)
+        );
+        assert_eq!(
+            to_position(Vec2::new(15.0, 25.0), rect, 5.0),
+            Vec2::new(-1.0, 1.0)
+        );
+        assert_eq!(
+            to_position(Vec2::new(500.0, 500.0), rect, 5.0),
+            Vec2::new(1.0, -1.0)
+        );
+
+        // Dots at the edges stay inside
+        let offset = dot_offset(Vec2::new(-1.0, 1.0), Vec2::splat(100.0), 10.0);
+        assert_eq!((offset.x, offset.y), (0.0, 0.0));
+        let offset = dot_offset(Vec2::ZERO, Vec2::splat(100.0), 10.0);
+        assert_eq!((offset.x, offset.y), (45.0, 45.0));
+
+        assert_eq!(format_balance(0.0), "C");
+        assert_eq!(format_balance(-0.5), "L50");
+        assert_eq!(format_balance(2.0), "R100");
+    }
+}
@@ -0,0 +1,657 @@
+//! Piano rolls, the grid MIDI notes are drawn and edited on.
+//!
+//! Pitch goes up the roll with a row per note, 127 at the top, and time goes to the right in
+//! beats. [`Ui::piano_roll`] edits a list of [`MidiNote`]s: pressing an empty spot draws a
+//! note, notes are moved by dragging them and resized by dragging their right end, and a box
+//! dragged with shift held selects the notes it touches. The part of the roll in view is a
+//! [`PianoRollView`] kept by the caller and zoomed along time and pitch separately.
+//!
+//! Given a [`Scale`] in the [`PianoRoll`] options, rows of notes outside of it are shaded and
+//! the rows of its root stand out, and the rows of the notes of a chord can be highlighted on
+//! top, so the notes that fit are easy to find.
+use crate::input::{CursorIcon, Key, Modifiers, MouseButton};
+use crate::scale::Scale;
+use crate::style::WidgetState;
+use crate::ui::{Ui, UiDeclaration, apply_style};
+use crate::value_drag::round_to;
+use clay_layout::color::Color as ClayColor;
+use clay_layout::elements::{FloatingAttachToElement, FloatingClipToElement};
+use clay_layout::id::Id;
+use clay_layout::math::Vector2;
+use clay_layout::{Declaration, fixed, grow};
+use glam::{Vec2, Vec4, Vec4Swizzles};
+
+/// Number of notes in a roll, one row each.
+const NOTES: u8 = 128;
+/// Beats in a bar, every bar line is drawn stronger.
+const BEATS_PER_BAR: u32 = 4;
+/// Width of the handle at the right end of a note that resizes it.
+const RESIZE_HANDLE: f32 = 6.0;
+/// Pixels scrolled by one step of the mouse wheel.
+const WHEEL_STEP: f32 = 40.0;
+/// Zoom factor of one step of the mouse wheel.
+const WHEEL_ZOOM: f32 = 1.1;
+/// Pixels per beat and per row the zoom can't go below.
+const MIN_ZOOM: Vec2 = Vec2::new(8.0, 4.0);
+/// Pixels per beat and per row the zoom can't go above.
+const MAX_ZOOM: Vec2 = Vec2::new(400.0, 40.0);
+/// Velocity of the notes drawn.
+const VELOCITY: u8 = 100;
+
+/// Background of a row in the scale, or of every row without one.
+const ROW_COLOR: ClayColor = ClayColor::rgb(34.0, 34.0, 44.0);
+/// Background of a row outside of the scale.
+const OUT_OF_SCALE_COLOR: ClayColor = ClayColor::rgb(24.0, 24.0, 30.0);
+/// Background of a row of the root of the scale.
+const ROOT_COLOR: ClayColor = ClayColor::rgb(44.0, 44.0, 58.0);
+/// Background of a row of a note of the highlighted chord.
+const CHORD_COLOR: ClayColor = ClayColor::rgb(40.0, 58.0, 84.0);
+/// Line between two octaves.
+const OCTAVE_LINE_COLOR: ClayColor = ClayColor::rgb(60.0, 60.0, 74.0);
+const BEAT_LINE_COLOR: ClayColor = ClayColor::rgb(50.0, 50.0, 62.0);
+const BAR_LINE_COLOR: ClayColor = ClayColor::rgb(70.0, 70.0, 85.0);
+
+/// A note of a MIDI clip, timed in beats.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MidiNote {
+    pub pitch: u8,
+    pub velocity: u8,
+    pub start: f32,
+    pub duration: f32,
+}
+
+impl MidiNote {
+    /// Where the note is on the roll, from its start and row to its end and the row below.
+    fn rect(&self) -> Vec4 {
+        let row = row(self.pitch);
+        Vec4::new(self.start, row, self.start + self.duration, row + 1.0)
+    }
+}
+
+/// Row of `pitch`, counting down from 127 at the top.
+fn row(pitch: u8) -> f32 {
+    (NOTES - 1 - pitch.min(NOTES - 1)) as f32
+}
+
+/// The pitch of the row at `row`, `None` above or below the roll.
+fn pitch_at(row: f32) -> Option<u8> {
+    let row = row.floor();
+    (0.0..NOTES as f32)
+        .contains(&row)
+        .then(|| NOTES - 1 - row as u8)
+}
+
+/// Indices of the notes touching the box between the corners `a` and `b`, in beats and rows.
+fn notes_in(notes: &[MidiNote], a: Vec2, b: Vec2) -> Vec<usize> {
+    let (min, max) = (a.min(b), a.max(b));
+    notes
+        .iter()
+        .enumerate()
+        .filter(|(_, note)| {
+            let rect = note.rect();
+            rect.x < max.x && rect.z > min.x && rect.y < max.y && rect.w > min.y
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// The part of a piano roll in view, kept by the caller between frames.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PianoRollView {
+    /// Beat and row at the top left corner. Rows count down from note 127, so row 0.5 is
+    /// half way down the top row.
+    pub offset: Vec2,
+    /// Pixels per beat and per row.
+    pub zoom: Vec2,
+}
+
+impl Default for PianoRollView {
+    /// The first beat with C6 at the top.
+    fn default() -> Self {
+        Self {
+            offset: Vec2::new(0.0, row(84)),
+            zoom: Vec2::new(40.0, 12.0),
+        }
+    }
+}
+
+impl PianoRollView {
+    /// Screen position of `world`, in beats and rows, on a roll laid out at `rect`.
+    pub fn to_screen(self, rect: Vec4, world: Vec2) -> Vec2 {
+        rect.xy() + (world - self.offset) * self.zoom
+    }
+
+    /// Beat and row at the screen position `screen` on a roll laid out at `rect`.
+    pub fn to_world(self, rect: Vec4, screen: Vec2) -> Vec2 {
+        self.offset + (screen - rect.xy()) / self.zoom
+    }
+
+    /// Changes the zoom to `zoom`, keeping what's at `anchor` from the top left corner of the
+    /// roll in place.
+    fn zoom_around(&mut self, zoom: Vec2, anchor: Vec2) {
+        let zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        self.offset += anchor / self.zoom - anchor / zoom;
+        self.zoom = zoom;
+    }
+}
+
+/// Highlighting and editing options of a piano roll.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PianoRoll {
+    /// Scale whose notes are highlighted, all rows look the same without one.
+    pub scale: Option<Scale>,
+    /// Pitch classes of the chord whose rows are highlighted, from 0 for C to 11 for B.
+    pub chord: Vec<u8>,
+    /// Beats the starts and lengths of notes snap to, a quarter beat unless set. Nothing snaps
+    /// at 0.
+    pub snap: f32,
+}
+
+impl Default for PianoRoll {
+    fn default() -> Self {
+        Self {
+            scale: None,
+            chord: Vec::new(),
+            snap: 0.25,
+        }
+    }
+}
+
+impl PianoRoll {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn scale(mut self, scale: Scale) -> Self {
+        self.scale = Some(scale);
+        self
+    }
+
+    /// Highlights the rows of the pitch classes in `chord`, as given by [`Scale::chord`].
+    pub fn chord(mut self, chord: Vec<u8>) -> Self {
+        self.chord = chord;
+        self
+    }
+
+    pub fn snap(mut self, snap: f32) -> Self {
+        self.snap = snap;
+        self
+    }
+
+    /// Background color of the row of `pitch`.
+    fn row_color(&self, pitch: u8) -> ClayColor {
+        if self.chord.contains(&(pitch % 12)) {
+            return CHORD_COLOR;
+        }
+        match self.scale {
+            Some(scale) if scale.is_root(pitch) => ROOT_COLOR,
+            Some(scale) if !scale.contains(pitch) => OUT_OF_SCALE_COLOR,
+            _ => ROW_COLOR,
+        }
+    }
+}
+
+/// A drag going on over a piano roll, with what it started from.
+#[derive(Debug, Clone)]
+enum Drag {
+    /// Moves notes, each given with the start and pitch it had.
+    Move {
+        from: Vec2,
+        notes: Vec<(usize, f32, u8)>,
+    },
+    /// Resizes notes, each given with the duration it had.
+    Resize {
+        from: f32,
+        notes: Vec<(usize, f32)>,
+    },
+    /// Selects the notes in a box on top of the ones selected before.
+    Select {
+        from: Vec2,
+        selected: Vec<usize>,
+    },
+    Pan,
+}
+
+impl Drag {
+    /// True if the drag holds the index of a note at `len` or past it, which happens when the
+    /// caller removes notes while it goes on.
+    fn holds_past(&self, len: usize) -> bool {
+        match self {
+            Drag::Move { notes, .. } => notes.iter().any(|(index, ..)| *index >= len),
+            Drag::Resize { notes, .. } => notes.iter().any(|(index, _)| *index >= len),
+            Drag::Select { selected, .. } => selected.iter().any(|index| *index >= len),
+            Drag::Pan => false,
+        }
+    }
+}
+
+/// What a piano roll keeps between frames.
+#[derive(Debug, Clone)]
+pub(crate) struct PianoRollState {
+    /// Indices of the selected notes.
+    selected: Vec<usize>,
+    drag: Option<Drag>,
+    /// Duration of the note drawn or resized last, new notes are drawn as long.
+    length: f32,
+}
+
+impl Default for PianoRollState {
+    fn default() -> Self {
+        Self {
+            selected: Vec::new(),
+            drag: None,
+            length: 1.0,
+        }
+    }
+}
+
+/// An element of `size` floating over its parent at `offset`.
+fn floating(offset: Vec2, size: Vec2) -> UiDeclaration<'static> {
+    let mut decl = Declaration::new();
+    decl.layout()
+        .width(fixed!(size.x.max(0.0)))
+        .height(fixed!(size.y.max(0.0)))
+        .end()
+        .floating()
+        .attach_to(FloatingAttachToElement::Parent)
+        .clip_to(FloatingClipToElement::AttachedParent)
+        .offset(Vector2::new(offset.x, offset.y))
+        .end();
+    decl
+}
+
+impl Ui {
+    /// An editor for `notes` filling the space it's given, showing the part of the roll in
+    /// `view`. Returns true when the notes changed.
+    ///
+    /// Pressing an empty spot draws a note and dragging on makes it longer. Pressing a note
+    /// selects it, or adds it to the selection with shift held, and dragging moves the
+    /// selected notes, or resizes them when pressed at the right end. Moved notes keep their
+    /// pitch or their start with the axis lock of [`crate::value_drag::DragConfig`] held.
+    /// Dragging from an empty spot with shift held selects the notes in a box. A right click
+    /// deletes a note, and Delete or Backspace the selected ones while the roll has the
+    /// keyboard focus.
+    ///
+    /// The wheel scrolls, along time with shift held, and zooms time around the mouse with
+    /// Ctrl or Cmd held and pitch with Alt. Dragging with the middle button pans.
+    pub fn piano_roll(
+        &self,
+        id: &str,
+        notes: &mut Vec<MidiNote>,
+        view: &mut PianoRollView,
+    ) -> bool {
+        self.piano_roll_with(id, notes, view, &PianoRoll::new())
+    }
+
+    /// [`Ui::piano_roll`] with options, for example highlighting C major and its tonic triad
+    /// with `PianoRoll::new().scale(scale).chord(scale.chord(0, 3))`.
+    ///
+    /// The roll is styled by the `piano_roll` rule, the notes by `piano_roll_note`, with
+    /// `:active` while selected, and the selection box by `piano_roll_selection`.
+    pub fn piano_roll_with(
+        &self,
+        id: &str,
+        notes: &mut Vec<MidiNote>,
+        view: &mut PianoRollView,
+        options: &PianoRoll,
+    ) -> bool {
+        let before = notes.clone();
+        let roll_id = self.id(id);
+        let input = self.input();
+        let rect = self.last_frame_rect(roll_id).unwrap_or(Vec4::ZERO);
+        let hovered = input.is_hovered(rect);
+        // Each note is followed by the handle at its right end
+        let note_id = |index: usize| self.id_index(id, index as u32 * 2 + 1);
+        let handle_id = |index: usize| self.id_index(id, index as u32 * 2 + 2);
+        let count = notes.len();
+        let find = |element: Option<u32>, element_id: &dyn Fn(usize) -> Id| {
+            (0..count).find(|index| Some(element_id(*index).id.id) == element)
+        };
+
+        self.register_focusable(roll_id);
+        let mut focused = self.is_focused(roll_id);
+        if input.button_pressed(MouseButton::Left) {
+            focused = hovered;
+        }
+        self.set_focused(roll_id, focused);
+
+        let wheel = self.take_scroll(rect);
+        if wheel != Vec2::ZERO {
+            let modifiers = input.modifiers();
+            let anchor = input.mouse_pos - rect.xy();
+            let factor = WHEEL_ZOOM.powf(wheel.y);
+            if modifiers.command() {
+                view.zoom_around(view.zoom * Vec2::new(factor, 1.0), anchor);
+            } else if modifiers.contains(Modifiers::ALT) {
+                view.zoom_around(view.zoom * Vec2::new(1.0, factor), anchor);
+            } else if modifiers.shift() {
+                view.offset.x -= (wheel.x + wheel.y) * WHEEL_STEP / view.zoom.x;
+            } else {
+                view.offset -= wheel * WHEEL_STEP / view.zoom;
+            }
+        }
+
+        let state = self.piano_rolls().entry(roll_id.id.id).or_default();
+        state.selected.retain(|index| *index < count);
+        if state
+            .drag
+            .as_ref()
+            .is_some_and(|drag| drag.holds_past(count))
+        {
+            state.drag = None;
+        }
+        if input.button_pressed(MouseButton::Middle) && hovered {
+            state.drag = Some(Drag::Pan);
+        }
+        if !input.button_down(MouseButton::Left) && !input.button_down(MouseButton::Middle) {
+            state.drag = None;
+        }
+        if let Some(Drag::Pan) = state.drag {
+            view.offset -= input.mouse_delta / view.zoom;
+        }
+        view.offset = view.offset.max(Vec2::ZERO);
+        let mouse = view.to_world(rect, input.mouse_pos);
+
+        if input.button_pressed(MouseButton::Left) && hovered {
+            let pressed = input.pressed_id;
+            if let Some(index) = find(pressed, &handle_id) {
+                if !state.selected.contains(&index) {
+                    state.selected = vec![index];
+                }
+                let resized = state.selected.iter().map(|i| (*i, notes[*i].duration));
+                state.drag = Some(Drag::Resize {
+                    from: mouse.x,
+                    notes: resized.collect(),
+                });
+            } else if let Some(index) = find(pressed, &note_id) {
+                if input.modifiers().shift() {
+                    if let Some(at) = state.selected.iter().position(|i| *i == index) {
+                        state.selected.remove(at);
+                    } else {
+                        state.selected.push(index);
+                    }
+                } else if !state.selected.contains(&index) {
+                    state.selected = vec![index];
+                }
+                let moved = state
+                    .selected
+                    .iter()
+                    .map(|i| (*i, notes[*i].start, notes[*i].pitch));
+                state.drag = Some(Drag::Move {
+                    from: mouse,
+                    notes: moved.collect(),
+                });
+            } else if pressed == Some(roll_id.id.id) {
+                if input.modifiers().shift() {
+                    state.drag = Some(Drag::Select {
+                        from: mouse,
+                        selected: state.selected.clone(),
+                    });
+                } else if let Some(pitch) = pitch_at(mouse.y) {
+                    // Starts on the grid line left of the mouse and grows to the right
+                    let start = if options.snap > 0.0 {
+                        (mouse.x / options.snap).floor() * options.snap
+                    } else {
+                        mouse.x
+                    };
+                    notes.push(MidiNote {
+                        pitch,
+                        velocity: VELOCITY,
+                        start: start.max(0.0),
+                        duration: state.length,
+                    });
+                    let index = notes.len() - 1;
+                    state.selected = vec![index];
+                    state.drag = Some(Drag::Resize {
+                        from: mouse.x,
+                        notes: vec![(index, state.length)],
+                    });
+                }
+            }
+        }
+
+        match &state.drag {
+            Some(Drag::Move { from, notes: moved }) => {
+                // Locked to the axis the notes moved furthest along on screen
+                let moved_on_screen = (mouse - *from) * view.zoom;
+                let delta = self
+                    .drag_config()
+                    .lock_axis(input.modifiers(), moved_on_screen)
+                    / view.zoom;
+                let beats = round_to(delta.x, options.snap);
+                let rows = delta.y.round();
+                for (index, start, pitch) in moved {
+                    let note = &mut notes[*index];
+                    note.start = (start + beats).max(0.0);
+                    note.pitch = (*pitch as f32 - rows).clamp(0.0, (NOTES - 1) as f32) as u8;
+                }
+            }
+            Some(Drag::Resize {
+                from,
+                notes: resized,
+            }) => {
+                let delta = round_to(mouse.x - *from, options.snap);
+                let shortest = if options.snap > 0.0 {
+                    options.snap
+                } else {
+                    0.01
+                };
+                for (index, duration) in resized {
+                    let duration = (duration + delta).max(shortest);
+                    notes[*index].duration = duration;
+                    state.length = duration;
+                }
+            }
+            Some(Drag::Select { from, selected }) => {
+                let mut selection = selected.clone();
+                for index in notes_in(notes, *from, mouse) {
+                    if !selection.contains(&index) {
+                        selection.push(index);
+                    }
+                }
+                state.selected = selection;
+            }
+            Some(Drag::Pan) | None => {}
+        }
+
+        let hit = input.hit_test(input.mouse_pos);
+        let mut deleted = Vec::new();
+        if input.button_pressed(MouseButton::Right)
+            && let Some(index) = find(hit, &note_id).or_else(|| find(hit, &handle_id))
+        {
+            deleted.push(index);
+        }
+        if focused
+            && (input.keys_pressed.contains(&Key::Delete)
+                || input.keys_pressed.contains(&Key::Backspace))
+        {
+            deleted.append(&mut state.selected);
+        }
+        if !deleted.is_empty() {
+            deleted.sort_unstable();
+            deleted.dedup();
+            for index in deleted.iter().rev() {
+                notes.remove(*index);
+            }
+            // The indices the drag holds on to are gone too
+            state.drag = None;
+            state.selected.retain(|index| !deleted.contains(index));
+            for index in &mut state.selected {
+                *index -= deleted.iter().filter(|deleted| **deleted < *index).count();
+            }
+        }
+
+        let selected = state.selected.clone();
+        let selection_box = match &state.drag {
+            Some(Drag::Select { from, .. }) => Some((*from, mouse)),
+            _ => None,
+        };
+        let style = self.widget_style("piano_roll", WidgetState::default());
+        let selection_style = self.widget_style("piano_roll_selection", WidgetState::default());
+        let mut decl = Declaration::new();
+        decl.id(roll_id)
+            .layout()
+            .width(grow!())
+            .height(grow!())
+            .end()
+            .clip(true, true, Vector2::new(0.0, 0.0));
+        apply_style(&mut decl, &style);
+
+        let size = rect.zw() - rect.xy();
+        let visible = Vec4::new(
+            view.offset.x,
+            view.offset.y,
+            view.offset.x + size.x / view.zoom.x,
+            view.offset.y + size.y / view.zoom.y,
+        );
+        // Offset from the top left corner of the roll of the beat and row `world`
+        let local = |world: Vec2| ((world - view.offset) * view.zoom).round();
+
+        self.with_layout(&decl, |ui| {
+            let first_row = visible.y as u32;
+            let last_row = (visible.w.ceil() as u32).min(NOTES as u32);
+            for row in first_row..last_row {
+                let pitch = NOTES - 1 - row as u8;
+                let top = local(Vec2::new(0.0, row as f32)).y;
+                let bottom = local(Vec2::new(0.0, row as f32 + 1.0)).y;
+                let mut decl = floating(Vec2::new(0.0, top), Vec2::new(size.x, bottom - top));
+                decl.background_color(options.row_color(pitch));
+                ui.with_layout(&decl, |_| {});
+                if pitch.is_multiple_of(12) {
+                    let mut decl = floating(Vec2::new(0.0, bottom - 1.0), Vec2::new(size.x, 1.0));
+                    decl.background_color(OCTAVE_LINE_COLOR);
+                    ui.with_layout(&decl, |_| {});
+                }
+            }
+
+            let first_beat = visible.x.ceil() as u32;
+            let last_beat = visible.z as u32;
+            for beat in first_beat..=last_beat {
+                let x = local(Vec2::new(beat as f32, 0.0)).x;
+                let mut decl = floating(Vec2::new(x, 0.0), Vec2::new(1.0, size.y));
+                decl.background_color(if beat % BEATS_PER_BAR == 0 {
+                    BAR_LINE_COLOR
+                } else {
+                    BEAT_LINE_COLOR
+                });
+                ui.with_layout(&decl, |_| {});
+            }
+
+            for (index, note) in notes.iter().enumerate() {
+                let world = note.rect();
+                if world.x >= visible.z
+                    || world.z <= visible.x
+                    || world.y >= visible.w
+                    || world.w <= visible.y
+                {
+                    continue;
+                }
+                let (note_id, handle_id) = (note_id(index), handle_id(index));
+                let pos = local(world.xy());
+                let note_size = (local(world.zw()) - pos).max(Vec2::ONE);
+                let note_style = ui.widget_style(
+                    "piano_roll_note",
+                    WidgetState {
+                        hovered: hit == Some(note_id.id.id) || hit == Some(handle_id.id.id),
+                        active: selected.contains(&index),
+                        ..Default::default()
+                    },
+                );
+                let mut decl = floating(pos, note_size);
+                decl.id(note_id);
+                apply_style(&mut decl, &note_style);
+                let handle_width = RESIZE_HANDLE.min(note_size.x * 0.5);
+                let mut handle = floating(
+                    Vec2::new(note_size.x - handle_width, 0.0),
+                    Vec2::new(handle_width, note_size.y),
+                );
+                handle.id(handle_id);
+                ui.set_cursor(handle_id, CursorIcon::ResizeHorizontal);
+                ui.with_layout(&decl, |ui| ui.with_layout(&handle, |_| {}));
+            }
+
+            if let Some((from, to)) = selection_box {
+                let (min, max) = (local(from.min(to)), local(from.max(to)));
+                if min.x == max.x || min.y == max.y {
+                    return;
+                }
+                let mut decl = floating(min, max - min);
+                apply_style(&mut decl, &selection_style);
+                ui.with_layout(&decl, |_| {});
+            }
+        });
+        *notes != before
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scale::ScaleKind;
+
+    fn note(pitch: u8, start: f32, duration: f32) -> MidiNote {
+        MidiNote {
+            pitch,
+            velocity: VELOCITY,
+            start,
+            duration,
+        }
+    }
+
+    #[test]
+    fn test_view() {
+        assert_eq!(row(127), 0.0);
+        assert_eq!(pitch_at(row(60) + 0.9), Some(60));
+        assert_eq!(pitch_at(-0.5), None);
+        assert_eq!(pitch_at(128.0), None);
+
+        let mut view = PianoRollView {
+            offset: Vec2::new(4.0, 60.0),
+            zoom: Vec2::new(40.0, 10.0),
+        };
+        let rect = Vec4::new(100.0, 50.0, 500.0, 250.0);
+        let world = Vec2::new(5.5, 62.0);
+        assert_eq!(view.to_screen(rect, world), Vec2::new(160.0, 70.0));
+        assert_eq!(view.to_world(rect, view.to_screen(rect, world)), world);
+
+        // Zooming time keeps the beat under the mouse and leaves the rows alone
+        let anchor = Vec2::new(60.0, 20.0);
+        view.zoom_around(Vec2::new(80.0, 10.0), anchor);
+        assert_eq!(view.to_world(rect, rect.xy() + anchor), world);
+        assert_eq!(view.offset.y, 60.0);
+        view.zoom_around(Vec2::new(10000.0, 0.0), anchor);
+        assert_eq!(view.zoom, MAX_ZOOM * Vec2::X + MIN_ZOOM * Vec2::Y);
+    }
+
+    #[test]
+    fn test_editing() {
+        let resize = Drag::Resize {
+            from: 0.0,
+            notes: vec![(0, 1.0), (2, 0.5)],
+        };
+        assert!(!resize.holds_past(3));
+        assert!(resize.holds_past(2));
+        assert!(!Drag::Pan.holds_past(0));
+
+        let notes = [note(60, 0.0, 1.0), note(62, 1.0, 1.0), note(72, 0.5, 0.5)];
+        // From half way into the first beat on D4 to the second beat on C4
+        let a = Vec2::new(0.5, row(62));
+        let b = Vec2::new(1.2, row(60) + 0.5);
+        assert_eq!(notes_in(&notes, a, b), [0, 1]);
+        assert_eq!(notes_in(&notes, b, a), [0, 1]);
+        assert!(notes_in(&notes, Vec2::new(3.0, 0.0), Vec2::new(4.0, 127.0)).is_empty());
+    }
+
+    #[test]
+    fn test_row_color() {
+        let scale = Scale::new(0, ScaleKind::Major);
+        let roll = PianoRoll::new().scale(scale);
+        assert_eq!(roll.row_color(60), ROOT_COLOR);
+        assert_eq!(roll.row_color(62), ROW_COLOR);
+        assert_eq!(roll.row_color(61), OUT_OF_SCALE_COLOR);
+        let roll = roll.chord(scale.chord(4, 3));
+        assert_eq!(roll.row_color(62), CHORD_COLOR);
+        assert_eq!(roll.row_color(60), ROOT_COLOR);
+        assert_eq!(PianoRoll::new().row_color(61), ROW_COLOR);
+    }
+}
@@ -0,0 +1,232 @@
+//! Popups and context menus.
+//!
+//! Open popups are kept on a stack so a popup can open another one, like a submenu, without
+//! closing itself. Opening a popup from outside of any popup replaces the whole stack. A click
+//! outside of a popup and the popups it opened closes it, and Escape closes the top-most one.
+//! Each popup traps the keyboard focus while open.
+use crate::input::{Key, MouseButton};
+use crate::ui::{Ui, apply_style};
+use clay_layout::elements::FloatingAttachToElement;
+use clay_layout::id::Id;
+use clay_layout::layout::LayoutDirection;
+use clay_layout::math::Vector2;
+use clay_layout::{Declaration, fit};
+use glam::{Vec2, Vec4, Vec4Swizzles};
+
+/// Above modals, so popups opened from a dialog show on top of it, but below tooltips.
+pub(crate) const POPUP_Z_INDEX: i16 = 950;
+
+struct OpenPopup {
+    id: u32,
+    /// Where the popup's top left corner goes, usually where the mouse was when it opened.
+    pos: Vec2,
+    opened_frame: u64,
+}
+
+#[derive(Default)]
+pub(crate) struct Popups {
+    stack: Vec<OpenPopup>,
+    /// Number of popups being declared around the current element.
+    depth: usize,
+}
+
+impl Popups {
+    pub(crate) fn index_of(&self, id: u32) -> Option<usize> {
+        self.stack.iter().position(|popup| popup.id == id)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Number of popups being declared around the current element.
+    pub(crate) fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The innermost popup being declared.
+    pub(crate) fn current(&self) -> Option<u32> {
+        self.depth.checked_sub(1).map(|index| self.stack[index].id)
+    }
+
+    /// Id of the bottom popup, the one the others were opened from.
+    pub(crate) fn first(&self) -> Option<u32> {
+        self.stack.first().map(|popup| popup.id)
+    }
+
+    /// Where the popup at `index` was opened, and in which frame.
+    pub(crate) fn opened(&self, index: usize) -> (Vec2, u64) {
+        let popup = &self.stack[index];
+        (popup.pos, popup.opened_frame)
+    }
+
+    /// Closes all popups from `index` up.
+    pub(crate) fn truncate(&mut self, index: usize) {
+        self.stack.truncate(index);
+    }
+
+    fn open(&mut self, id: u32, pos: Vec2, frame: u64) {
+        self.stack.truncate(self.depth);
+        self.stack.push(OpenPopup {
+            id,
+            pos,
+            opened_frame: frame,
+        });
+    }
+}
+
+/// True if `pos` is outside all of `rects`.
+fn outside(rects: &[Vec4], pos: Vec2) -> bool {
+    !rects
+        .iter()
+        .any(|r| pos.x >= r.x && pos.y >= r.y && pos.x < r.z && pos.y < r.w)
+}
+
+impl Ui {
+    /// Opens the popup `id` at the mouse position. Called from inside a popup, the popup stays
+    /// open below the new one.
+    pub fn open_popup(&self, id: &str) {
+        let id = self.id(id);
+        let pos = self.input().mouse_pos;
+        let frame = self.current_frame();
+        self.popups().open(id.id.id, pos, frame);
+    }
+
+    /// Opens the popup `id` with its top left corner at `pos`.
+    pub(crate) fn open_popup_at(&self, id: Id, pos: Vec2) {
+        let frame = self.current_frame();
+        self.popups().open(id.id.id, pos, frame);
+    }
+
+    /// Closes the innermost popup being declared, for example after a menu entry was picked.
+    pub fn close_popup(&self) {
+        let popups = self.popups();
+        popups.stack.truncate(popups.depth.saturating_sub(1));
+    }
+
+    pub fn is_popup_open(&self, id: &str) -> bool {
+        let id = self.id(id);
+        self.popups().index_of(id.id.id).is_some()
+    }
+
+    /// Shows the content added by `f` in a floating panel while the popup `id` is open. It
+    /// opens with [`Ui::open_popup`] or by right-clicking the widget added before this call,
+    /// which makes it a context menu. Returns true while shown.
+    ///
+    /// Styled by the `popup` stylesheet rule.
+    pub fn popup(&self, id: &str, f: impl FnOnce(&Ui)) -> bool {
+        let popup_id = self.id(id);
+        let input = self.input();
+        let frame = self.current_frame();
+
+        if let Some(item) = self.last_item()
+            && input.was_clicked(item.id.id, MouseButton::Right)
+        {
+            self.popups().open(popup_id.id.id, input.mouse_pos, frame);
+        }
+
+        self.popup_panel(popup_id, "popup", f)
+    }
+
+    /// Declares the floating panel of the popup `popup_id` styled by the `widget` rule if it's
+    /// open and not closing this frame.
+    pub(crate) fn popup_panel(&self, popup_id: Id, widget: &str, f: impl FnOnce(&Ui)) -> bool {
+        let frame = self.current_frame();
+        let Some(index) = self.popups().index_of(popup_id.id.id) else {
+            return false;
+        };
+
+        if self.should_close(index, frame) {
+            self.popups().stack.truncate(index);
+            return false;
+        }
+
+        let pos = self.popups().stack[index].pos;
+        let size = self
+            .last_frame_rect(popup_id)
+            .map_or(Vec2::ZERO, |rect| rect.zw() - rect.xy());
+        let (width, height) = self.window_size();
+        let pos = Vec2::new(
+            pos.x.min(width as f32 - size.x).max(0.0),
+            pos.y.min(height as f32 - size.y).max(0.0),
+        );
+
+        let style = self.widget_style(widget, Default::default());
+        let mut decl = Declaration::new();
+        decl.id(popup_id)
+            .layout()
+            .width(fit!())
+            .height(fit!())
+            .direction(LayoutDirection::TopToBottom)
+            .end()
+            .floating()
+            .attach_to(FloatingAttachToElement::Root)
+            .offset(Vector2::new(pos.x.round(), pos.y.round()))
+            .z_index(POPUP_Z_INDEX + index as i16)
+            .end();
+        apply_style(&mut decl, &style);
+
+        let last_item = self.last_item();
+        let popups = self.popups();
+        let depth = std::mem::replace(&mut popups.depth, index + 1);
+        self.with_layout(&decl, |ui| ui.focus_trap(popup_id, f));
+        self.popups().depth = depth;
+        // A context menu belongs to the item, like a tooltip
+        self.set_last_item(last_item);
+        true
+    }
+
+    /// Escape closes the top-most popup, pressing outside of a popup and the popups it opened
+    /// closes it. Popups opened this frame have no size yet and stay.
+    fn should_close(&self, index: usize, frame: u64) -> bool {
+        let input = self.input();
+        let popups = self.popups();
+        if popups.stack[index].opened_frame == frame {
+            return false;
+        }
+        if index + 1 == popups.stack.len() && input.keys_pressed.contains(&Key::Escape) {
+            return true;
+        }
+
+        let rects: Vec<Vec4> = popups.stack[index..]
+            .iter()
+            .filter_map(|popup| input.rect_of(popup.id))
+            .collect();
+        input.mouse_pressed.iter().any(|pressed| *pressed) && outside(&rects, input.mouse_pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stack() {
+        let mut popups = Popups::default();
+        popups.open(1, Vec2::ZERO, 0);
+        // A submenu opened from inside popup 1
+        popups.depth = 1;
+        popups.open(2, Vec2::ZERO, 0);
+        assert_eq!(popups.index_of(2), Some(1));
+
+        // Another submenu replaces the first one
+        popups.open(3, Vec2::ZERO, 0);
+        assert_eq!((popups.index_of(2), popups.index_of(3)), (None, Some(1)));
+
+        // Opening from outside of the popups replaces them all
+        popups.depth = 0;
+        popups.open(4, Vec2::ZERO, 0);
+        assert_eq!(popups.stack.len(), 1);
+    }
+
+    #[test]
+    fn test_outside() {
+        let rects = [
+            Vec4::new(0.0, 0.0, 10.0, 10.0),
+            Vec4::new(10.0, 0.0, 20.0, 5.0),
+        ];
+        assert!(!outside(&rects, Vec2::new(5.0, 5.0)));
+        assert!(!outside(&rects, Vec2::new(15.0, 2.0)));
+        assert!(outside(&rects, Vec2::new(15.0, 8.0)));
+    }
+}
@@ -0,0 +1,164 @@
+//! Pie menus opened around the mouse, for picking a tool without moving far.
+//!
+//! A radial menu is a popup, so it opens and closes like one, centered on where the mouse was
+//! when it opened. Its items are spread around a circle starting at the top and going
+//! clockwise. Which item is picked depends only on the direction of the mouse from the center,
+//! so it can be picked with a quick flick past the item.
+use crate::input::{Key, MouseButton};
+use crate::popup::POPUP_Z_INDEX;
+use crate::style::WidgetState;
+use crate::ui::{Ui, apply_style, rgb};
+use clay_layout::elements::FloatingAttachToElement;
+use clay_layout::math::Vector2;
+use clay_layout::{Declaration, fit, fixed};
+use glam::{Vec2, Vec4Swizzles};
+use std::f32::consts::TAU;
+
+/// Radius of the disc behind the items once fully open.
+const RADIUS: f32 = 90.0;
+/// Distance of the item centers from the center of the menu.
+const ITEM_DISTANCE: f32 = 62.0;
+/// The mouse has to be this far from the center before an item is picked.
+const DEAD_ZONE: f32 = 16.0;
+/// Seconds the menu takes to open.
+const OPEN_TIME: f32 = 0.12;
+
+/// The item of `count` in the direction of `offset` from the center, `None` within
+/// `dead_zone` of it. Item 0 is at the top and the rest follow clockwise.
+fn item_at(offset: Vec2, count: usize, dead_zone: f32) -> Option<usize> {
+    if count == 0 || offset.length() < dead_zone {
+        return None;
+    }
+    let sector = TAU / count as f32;
+    // Clockwise from the top, with y pointing down
+    let angle = offset.x.atan2(-offset.y).rem_euclid(TAU);
+    Some(((angle + sector * 0.5) / sector) as usize % count)
+}
+
+/// Unit vector from the center towards the item `index` of `count`.
+fn item_direction(index: usize, count: usize) -> Vec2 {
+    let angle = index as f32 * TAU / count as f32;
+    Vec2::new(angle.sin(), -angle.cos())
+}
+
+impl Ui {
+    /// Shows `items` around a circle while the popup `id` is open. It opens with
+    /// [`Ui::open_popup`] or by right-clicking the widget added before this call. Returns the
+    /// index of the item picked with a left click this frame, which closes the menu. Clicking
+    /// the center or pressing Escape closes it without picking one.
+    ///
+    /// Styled by the `radial_menu` rule for the disc and `radial_menu_item` for the items,
+    /// with `:hover` for the one under the mouse.
+    pub fn radial_menu(&self, id: &str, items: &[&str]) -> Option<usize> {
+        let popup_id = self.id(id);
+        let input = self.input();
+
+        if let Some(item) = self.last_item()
+            && input.was_clicked(item.id.id, MouseButton::Right)
+        {
+            self.open_popup_at(popup_id, input.mouse_pos);
+        }
+
+        let index = self.popups().index_of(popup_id.id.id);
+        let open = self.animate_bool(popup_id, index.is_some(), OPEN_TIME);
+        let index = index?;
+        let (pos, opened_frame) = self.popups().opened(index);
+        // Moved in from the window edges so the whole disc shows
+        let (width, height) = self.window_size();
+        let center = pos.clamp(
+            Vec2::splat(RADIUS),
+            Vec2::new(width as f32 - RADIUS, height as f32 - RADIUS).max(Vec2::splat(RADIUS)),
+        );
+        let hovered = item_at(input.mouse_pos - center, items.len(), DEAD_ZONE);
+
+        if opened_frame != self.current_frame() {
+            let pressed = input.mouse_pressed.iter().any(|pressed| *pressed);
+            let picked = hovered.filter(|_| input.button_pressed(MouseButton::Left));
+            if pressed || input.keys_pressed.contains(&Key::Escape) {
+                self.popups().truncate(index);
+                return picked;
+            }
+        }
+
+        let radius = (RADIUS * open).round();
+        let style = self.widget_style("radial_menu", Default::default());
+        let mut decl = Declaration::new();
+        decl.id(popup_id)
+            .layout()
+            .width(fixed!(radius * 2.0))
+            .height(fixed!(radius * 2.0))
+            .end()
+            .floating()
+            .attach_to(FloatingAttachToElement::Root)
+            .offset(Vector2::new(center.x - radius, center.y - radius))
+            .z_index(POPUP_Z_INDEX + index as i16)
+            .end();
+        apply_style(&mut decl, &style);
+        decl.corner_radius().all(radius).end();
+
+        let last_item = self.last_item();
+        self.with_layout(&decl, |ui| {
+            for (item, text) in items.iter().enumerate() {
+                let item_id = ui.id_index(id, item as u32 + 1);
+                let size = ui
+                    .last_frame_rect(item_id)
+                    .map_or(Vec2::ZERO, |rect| rect.zw() - rect.xy());
+                let pos = Vec2::splat(radius)
+                    + item_direction(item, items.len()) * ITEM_DISTANCE * open
+                    - size * 0.5;
+
+                let style = ui.widget_style(
+                    "radial_menu_item",
+                    WidgetState {
+                        hovered: hovered == Some(item),
+                        ..Default::default()
+                    },
+                );
+                let color = style.text_color.unwrap_or(rgb(220, 220, 220));
+                let mut item_decl = Declaration::new();
+                item_decl
+                    .id(item_id)
+                    .layout()
+                    .width(fit!())
+                    .height(fit!())
+                    .end()
+                    .floating()
+                    .attach_to(FloatingAttachToElement::Parent)
+                    .offset(Vector2::new(pos.x.round(), pos.y.round()))
+                    .z_index(POPUP_Z_INDEX + index as i16)
+                    .end();
+                apply_style(&mut item_decl, &style);
+                ui.with_layout(&item_decl, |ui| ui.text_element(text, color));
+            }
+        });
+        // Right-clicking the item again reopens the menu
+        self.set_last_item(last_item);
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_item_at() {
+        // Four items: top, right, bottom, left
+        assert_eq!(item_at(Vec2::new(0.0, -50.0), 4, 10.0), Some(0));
+        assert_eq!(item_at(Vec2::new(50.0, 0.0), 4, 10.0), Some(1));
+        assert_eq!(item_at(Vec2::new(0.0, 50.0), 4, 10.0), Some(2));
+        assert_eq!(item_at(Vec2::new(-50.0, 0.0), 4, 10.0), Some(3));
+        // Just left of the top still picks the top item
+        assert_eq!(item_at(Vec2::new(-20.0, -50.0), 4, 10.0), Some(0));
+        assert_eq!(item_at(Vec2::new(3.0, 3.0), 4, 10.0), None);
+        assert_eq!(item_at(Vec2::new(50.0, 0.0), 0, 10.0), None);
+
+        // The directions items are placed in are picked
+        for count in 1..8 {
+            for index in 0..count {
+                let offset = item_direction(index, count) * 40.0;
+                assert_eq!(item_at(offset, count, 10.0), Some(index));
+            }
+        }
+    }
+}
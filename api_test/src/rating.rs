@@ -0,0 +1,176 @@
+//! Star ratings, for rating samples and presets in a library browser.
+//!
+//! [`Ui::rating`] shows a row of stars with the rated ones filled. Hovering a star previews
+//! the rating clicking it would set, clicking the star of the current rating clears it. The
+//! stars are drawn as images so they don't depend on the font having a star glyph.
+use crate::image::ImageInfo;
+use crate::input::MouseButton;
+use crate::style::WidgetState;
+use crate::ui::{Ui, rgb};
+use clay_layout::layout::{Alignment, LayoutAlignmentX, LayoutAlignmentY, LayoutDirection};
+use clay_layout::{Declaration, fit, fixed};
+use std::collections::hash_map::Entry;
+use std::f32::consts::{FRAC_PI_2, PI};
+use tiny_skia::{FillRule, Paint, PathBuilder, Pixmap, Transform};
+
+/// Radius of the inner corners of a star relative to the outer ones.
+const INNER_RADIUS: f32 = 0.45;
+
+/// A white five-pointed star filling a `size` x `size` pixmap.
+pub(crate) fn star_pixmap(size: u32) -> Option<Pixmap> {
+    let mut pixmap = Pixmap::new(size, size)?;
+    let center = size as f32 * 0.5;
+    let mut path = PathBuilder::new();
+    for corner in 0..10 {
+        let radius = if corner % 2 == 0 {
+            center
+        } else {
+            center * INNER_RADIUS
+        };
+        let angle = corner as f32 * PI / 5.0 - FRAC_PI_2;
+        let (x, y) = (center + radius * angle.cos(), center + radius * angle.sin());
+        if corner == 0 {
+            path.move_to(x, y);
+        } else {
+            path.line_to(x, y);
+        }
+    }
+    path.close();
+
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(255, 255, 255, 255);
+    paint.anti_alias = true;
+    pixmap.fill_path(
+        &path.finish()?,
+        &paint,
+        FillRule::Winding,
+        Transform::identity(),
+        None,
+    );
+    Some(pixmap)
+}
+
+/// The rating after clicking the star for `clicked`, which clears the rating if it's the
+/// current one.
+fn click_rating(value: u8, clicked: u8) -> u8 {
+    if clicked == value { 0 } else { clicked }
+}
+
+impl Ui {
+    /// Shows `max` stars with the first `value` filled. Clicking a star sets `value` to its
+    /// rating, clicking the star of the current rating sets it to 0. Returns true when `value`
+    /// changed this frame.
+    ///
+    /// Stars take their color from the `rating_star` rules: the text color for rated stars,
+    /// `rating_star:hover` for the previewed ones and `rating_star.empty` for the rest.
+    pub fn rating(&self, id: &str, value: &mut u8, max: u8) -> bool {
+        let old_value = *value;
+        let input = self.input();
+        let star_ids: Vec<_> = (0..max)
+            .map(|index| self.id_index(id, index as u32 + 1))
+            .collect();
+        for (rating, star_id) in (1..=max).zip(&star_ids) {
+            if input.was_clicked(star_id.id.id, MouseButton::Left) {
+                *value = click_rating(*value, rating);
+            }
+        }
+        *value = (*value).min(max);
+        let preview = (1..=max).zip(&star_ids).find_map(|(rating, star_id)| {
+            self.last_frame_rect(*star_id)
+                .is_some_and(|rect| input.is_hovered(rect))
+                .then_some(rating)
+        });
+
+        let size = (self.font_size() as f32 * 1.2).round();
+        let color = |class: Option<&str>, hovered| {
+            if let Some(class) = class {
+                self.push_pending_class(class);
+            }
+            let style = self.widget_style(
+                "rating_star",
+                WidgetState {
+                    hovered,
+                    ..Default::default()
+                },
+            );
+            style.text_color
+        };
+        let rated = color(None, false).unwrap_or(rgb(240, 180, 40));
+        let previewed = color(None, true).unwrap_or(rgb(255, 215, 100));
+        let empty = color(Some("empty"), false).unwrap_or(rgb(80, 80, 80));
+        let star = self.rating_star(size as u32);
+
+        let mut decl = Declaration::new();
+        decl.id(self.id(id))
+            .layout()
+            .width(fit!())
+            .height(fit!())
+            .child_gap(2)
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Left,
+                LayoutAlignmentY::Center,
+            ))
+            .direction(LayoutDirection::LeftToRight)
+            .end();
+        self.with_layout(&decl, |ui| {
+            for (rating, star_id) in (1..=max).zip(&star_ids) {
+                let color = match preview {
+                    Some(preview) if rating <= preview => previewed,
+                    None if rating <= *value => rated,
+                    _ => empty,
+                };
+                let mut star_decl = Declaration::new();
+                star_decl
+                    .id(*star_id)
+                    .layout()
+                    .width(fixed!(size))
+                    .height(fixed!(size))
+                    .end();
+                if let Some(star) = &star {
+                    star_decl
+                        .image()
+                        .data(ui.alloc_image(star.clone().tint(color)))
+                        .end();
+                }
+                ui.with_layout(&star_decl, |_| {});
+            }
+        });
+        *value != old_value
+    }
+
+    /// The star image `size` pixels wide, made the first time it's needed.
+    fn rating_star(&self, size: u32) -> Option<ImageInfo> {
+        let stars = self.rating_stars();
+        if let Entry::Vacant(entry) = stars.entry(size) {
+            entry.insert(ImageInfo::new(star_pixmap(size)?));
+        }
+        stars.get(&size).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_click_rating() {
+        assert_eq!(click_rating(0, 3), 3);
+        assert_eq!(click_rating(3, 5), 5);
+        assert_eq!(click_rating(3, 1), 1);
+        // Clicking the current rating clears it
+        assert_eq!(click_rating(3, 3), 0);
+    }
+
+    #[test]
+    fn test_star_pixmap() {
+        let star = star_pixmap(20).unwrap();
+        let alpha = |x: u32, y: u32| star.pixel(x, y).unwrap().alpha();
+        // Filled in the middle and at the top point, empty in the corners between the points
+        assert_eq!(alpha(10, 10), 255);
+        assert!(alpha(10, 1) > 0);
+        assert_eq!(alpha(0, 0), 0);
+        assert_eq!(alpha(19, 0), 0);
+        assert_eq!(alpha(10, 19), 0);
+        assert!(star_pixmap(0).is_none());
+    }
+}
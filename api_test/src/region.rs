@@ -0,0 +1,256 @@
+//! Named time regions drawn over timeline widgets, such as loop and selection ranges.
+//!
+//! [`Ui::region_overlay`] covers the element it's declared in and maps its width to a
+//! [`TimeSpan`], the same way [`Ui::scrub`] does. Dragging on empty space creates a region and
+//! dragging the handles at its edges resizes it.
+use crate::input::{CursorIcon, MouseButton};
+use crate::scrub::{TimeSpan, position_in};
+use crate::ui::{Ui, rgb};
+use clay_layout::color::Color as ClayColor;
+use clay_layout::elements::{FloatingAttachToElement, FloatingClipToElement};
+use clay_layout::id::Id;
+use clay_layout::layout::{Alignment, LayoutAlignmentX, LayoutAlignmentY, Padding};
+use clay_layout::math::Vector2;
+use clay_layout::{Declaration, fixed};
+use glam::{Vec4, Vec4Swizzles};
+
+/// Width of the grab area at each edge of a region.
+const HANDLE_WIDTH: f32 = 6.0;
+
+/// Colors given to regions created by dragging, in turn.
+const REGION_COLORS: [ClayColor; 4] = [
+    ClayColor::rgba(100.0, 150.0, 255.0, 60.0),
+    ClayColor::rgba(255.0, 170.0, 60.0, 60.0),
+    ClayColor::rgba(110.0, 220.0, 120.0, 60.0),
+    ClayColor::rgba(230.0, 100.0, 200.0, 60.0),
+];
+
+/// A named span of time, in the units of the [`TimeSpan`] it's shown with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Region {
+    pub name: String,
+    pub start: f64,
+    pub end: f64,
+    /// Fill of the region, the edges are drawn with it fully opaque.
+    pub color: ClayColor,
+}
+
+impl Region {
+    pub fn new(name: impl Into<String>, start: f64, end: f64, color: ClayColor) -> Self {
+        Self {
+            name: name.into(),
+            start,
+            end,
+            color,
+        }
+    }
+}
+
+/// What changed in the regions of a [`Ui::region_overlay`] this frame, by index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionEvent {
+    /// A region was dragged out on empty space and the button released.
+    Created(usize),
+    /// An edge of a region was dragged.
+    Resized(usize),
+}
+
+/// An edge being dragged in an overlay. Creating a region starts out dragging its end.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RegionDrag {
+    index: usize,
+    end: bool,
+    creating: bool,
+}
+
+/// Moves the `end` or start edge of `region` to `time`. Edges dragged past each other swap, so
+/// the returned flag tells which edge is being dragged afterwards.
+fn drag_edge(region: &mut Region, end: bool, time: f64) -> bool {
+    if end {
+        region.end = time;
+    } else {
+        region.start = time;
+    }
+    if region.end < region.start {
+        std::mem::swap(&mut region.start, &mut region.end);
+        return !end;
+    }
+    end
+}
+
+impl Ui {
+    /// Draws `regions` over the element being declared into, which shows `span` across its
+    /// width as laid out in the previous frame. Nothing is drawn before that element has been
+    /// laid out once.
+    pub fn region_overlay(
+        &self,
+        id: &str,
+        regions: &mut Vec<Region>,
+        span: TimeSpan,
+    ) -> Option<RegionEvent> {
+        let overlay_id = self.id(id);
+        let (_, parent) = self.available_space();
+        let rect = parent?;
+        let input = self.input();
+        let handle_id =
+            |index: usize, end: bool| self.id_index(id, 1 + 2 * index as u32 + end as u32);
+        let (_, time, _) = position_in(rect, input.mouse_pos.x, span);
+        let mut event = None;
+
+        let drags = self.region_drags();
+        if input.button_pressed(MouseButton::Left) {
+            let hit = input.hit_test(input.mouse_pos);
+            let handle = (0..regions.len())
+                .flat_map(|index| [(index, false), (index, true)])
+                .find(|(index, end)| hit == Some(handle_id(*index, *end).id.id));
+            let drag = if let Some((index, end)) = handle {
+                Some(RegionDrag {
+                    index,
+                    end,
+                    creating: false,
+                })
+            } else if hit == Some(overlay_id.id.id) {
+                let color = REGION_COLORS[regions.len() % REGION_COLORS.len()];
+                let name = format!("Region {}", regions.len() + 1);
+                regions.push(Region::new(name, time, time, color));
+                Some(RegionDrag {
+                    index: regions.len() - 1,
+                    end: true,
+                    creating: true,
+                })
+            } else {
+                None
+            };
+            if let Some(drag) = drag {
+                drags.insert(overlay_id.id.id, drag);
+            }
+        }
+
+        if let Some(mut drag) = drags.get(&overlay_id.id.id).copied() {
+            if drag.index >= regions.len() {
+                drags.remove(&overlay_id.id.id);
+            } else if input.button_down(MouseButton::Left) {
+                let region = &mut regions[drag.index];
+                let before = (region.start, region.end);
+                drag.end = drag_edge(region, drag.end, time);
+                drags.insert(overlay_id.id.id, drag);
+                if !drag.creating && before != (region.start, region.end) {
+                    event = Some(RegionEvent::Resized(drag.index));
+                }
+            } else {
+                drags.remove(&overlay_id.id.id);
+                // A click without a drag doesn't leave an empty region behind
+                let pixel = span.duration / (rect.z - rect.x).max(1.0) as f64;
+                let region = &regions[drag.index];
+                if drag.creating && region.end - region.start < pixel {
+                    regions.remove(drag.index);
+                } else if drag.creating {
+                    event = Some(RegionEvent::Created(drag.index));
+                }
+            }
+        }
+
+        self.declare_regions(overlay_id, rect, regions, span, handle_id);
+        event
+    }
+
+    fn declare_regions(
+        &self,
+        overlay_id: Id,
+        rect: Vec4,
+        regions: &[Region],
+        span: TimeSpan,
+        handle_id: impl Fn(usize, bool) -> Id,
+    ) {
+        let size = rect.zw() - rect.xy();
+        let x_of = |time: f64| ((time - span.start) / span.duration) as f32 * size.x;
+
+        let mut decl = Declaration::new();
+        decl.id(overlay_id)
+            .layout()
+            .width(fixed!(size.x))
+            .height(fixed!(size.y))
+            .end()
+            .floating()
+            .attach_to(FloatingAttachToElement::Parent)
+            .end()
+            .clip(true, true, Vector2::new(0.0, 0.0));
+
+        self.with_layout(&decl, |ui| {
+            for (index, region) in regions.iter().enumerate() {
+                let x0 = x_of(region.start).round();
+                let x1 = x_of(region.end).round();
+                let edge_color = ClayColor {
+                    a: 255.0,
+                    ..region.color
+                };
+
+                let mut body = Declaration::new();
+                body.layout()
+                    .width(fixed!((x1 - x0).max(1.0)))
+                    .height(fixed!(size.y))
+                    .padding(Padding::all(4))
+                    .end()
+                    .background_color(region.color)
+                    .floating()
+                    .attach_to(FloatingAttachToElement::Parent)
+                    .clip_to(FloatingClipToElement::AttachedParent)
+                    .offset(Vector2::new(x0, 0.0))
+                    .end()
+                    .clip(true, false, Vector2::new(0.0, 0.0));
+
+                ui.with_layout(&body, |ui| {
+                    ui.text_element(ui.alloc_str(region.name.clone()), rgb(230, 230, 230));
+                });
+
+                // Handles are outside the body so they aren't clipped by it
+                for (end, x) in [(false, x0), (true, x1)] {
+                    let id = handle_id(index, end);
+                    ui.set_cursor(id, CursorIcon::ResizeHorizontal);
+                    let mut handle = Declaration::new();
+                    handle
+                        .id(id)
+                        .layout()
+                        .width(fixed!(HANDLE_WIDTH))
+                        .height(fixed!(size.y))
+                        .child_alignment(Alignment::new(
+                            LayoutAlignmentX::Center,
+                            LayoutAlignmentY::Top,
+                        ))
+                        .end()
+                        .floating()
+                        .attach_to(FloatingAttachToElement::Parent)
+                        .clip_to(FloatingClipToElement::AttachedParent)
+                        .offset(Vector2::new(x - HANDLE_WIDTH * 0.5, 0.0))
+                        .end();
+
+                    let mut line = Declaration::new();
+                    line.layout()
+                        .width(fixed!(2.0))
+                        .height(fixed!(size.y))
+                        .end()
+                        .background_color(edge_color);
+                    ui.with_layout(&handle, |ui| ui.with_layout(&line, |_| {}));
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drag_edge() {
+        let mut region = Region::new("Loop", 2.0, 4.0, REGION_COLORS[0]);
+        assert!(drag_edge(&mut region, true, 5.0));
+        assert_eq!((region.start, region.end), (2.0, 5.0));
+
+        // Dragging the start past the end swaps them and keeps dragging the moved edge
+        assert!(drag_edge(&mut region, false, 6.0));
+        assert_eq!((region.start, region.end), (5.0, 6.0));
+        assert!(!drag_edge(&mut region, true, 3.0));
+        assert_eq!((region.start, region.end), (3.0, 5.0));
+    }
+}
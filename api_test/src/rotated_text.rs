@@ -0,0 +1,100 @@
+//! Labels turned by 90 degrees, for vertical track names and narrow mixer strips.
+//!
+//! Rotated text starts out as the string rasterized by the text workers like any other text.
+//! Once it's there it is turned and kept by the [`crate::font::TextGenerator`] as an image, so
+//! the pixels are only turned once. Until then the label reserves its space and draws nothing.
+use crate::ui::{Ui, apply_style};
+use clay_layout::color::Color as ClayColor;
+use clay_layout::{Declaration, fit, fixed};
+use tiny_skia::Pixmap;
+
+/// Direction text is turned in, see [`crate::ui::Label::rotation`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextRotation {
+    #[default]
+    Upright,
+    /// Reads top to bottom.
+    Clockwise,
+    /// Reads bottom to top, the usual way for names along the side of a strip.
+    CounterClockwise,
+}
+
+/// `pixmap` turned by `rotation`, `None` for [`TextRotation::Upright`].
+pub(crate) fn rotate_pixmap(pixmap: &Pixmap, rotation: TextRotation) -> Option<Pixmap> {
+    if rotation == TextRotation::Upright {
+        return None;
+    }
+    let (width, height) = (pixmap.width() as usize, pixmap.height() as usize);
+    let mut rotated = Pixmap::new(height as u32, width as u32)?;
+    let source = pixmap.pixels();
+    let target = rotated.pixels_mut();
+    for y in 0..height {
+        for x in 0..width {
+            // The rotated pixmap is `height` wide
+            let (tx, ty) = match rotation {
+                TextRotation::Clockwise => (height - 1 - y, x),
+                _ => (y, width - 1 - x),
+            };
+            target[ty * height + tx] = source[y * width + x];
+        }
+    }
+    Some(rotated)
+}
+
+impl Ui {
+    /// `text` turned by `rotation` in `color`, see [`Ui::label_with`].
+    pub(crate) fn rotated_label(&self, text: &str, color: ClayColor, rotation: TextRotation) {
+        let id = self.id(text);
+        let style = self.animated_widget_style("label", id);
+        let color = style.text_color.unwrap_or(color);
+        let size = self.text_size(text, self.font_size());
+        let image = self.rotated_text(text, rotation);
+
+        let mut decl = Declaration::new();
+        decl.id(id).layout().width(fit!()).height(fit!()).end();
+        apply_style(&mut decl, &style);
+
+        let mut content = Declaration::new();
+        content
+            .layout()
+            .width(fixed!(size.height))
+            .height(fixed!(size.width))
+            .end();
+        if let Some(image) = image {
+            content
+                .image()
+                .data(self.alloc_image(image.tint(color)))
+                .end();
+        }
+        self.with_layout(&decl, |ui| ui.with_layout(&content, |_| {}));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tiny_skia::PremultipliedColorU8;
+
+    #[test]
+    fn test_rotate_pixmap() {
+        // 3 x 2, every pixel its own alpha
+        let mut pixmap = Pixmap::new(3, 2).unwrap();
+        for (index, pixel) in pixmap.pixels_mut().iter_mut().enumerate() {
+            let alpha = index as u8 + 1;
+            *pixel = PremultipliedColorU8::from_rgba(alpha, alpha, alpha, alpha).unwrap();
+        }
+        let alphas = |pixmap: &Pixmap| -> Vec<u8> {
+            pixmap.pixels().iter().map(|pixel| pixel.alpha()).collect()
+        };
+        assert_eq!(alphas(&pixmap), [1, 2, 3, 4, 5, 6]);
+
+        let clockwise = rotate_pixmap(&pixmap, TextRotation::Clockwise).unwrap();
+        assert_eq!((clockwise.width(), clockwise.height()), (2, 3));
+        assert_eq!(alphas(&clockwise), [4, 1, 5, 2, 6, 3]);
+
+        let counter = rotate_pixmap(&pixmap, TextRotation::CounterClockwise).unwrap();
+        assert_eq!(alphas(&counter), [3, 6, 2, 5, 1, 4]);
+
+        assert!(rotate_pixmap(&pixmap, TextRotation::Upright).is_none());
+    }
+}
@@ -0,0 +1,414 @@
+//! Rulers with pluggable units.
+//!
+//! A [`RulerScale`] decides which distances between ticks read well for its units (the
+//! "nice numbers"), how a major step is subdivided and how tick values are labelled.
+//! [`ruler_ticks`] turns a scale and a zoom into the ticks of a visible range and
+//! [`Ui::ruler`] draws them, so the same widget serves timecode, sample counts, bars and beats
+//! or any unit a scale is written for.
+use crate::ui::{Ui, apply_style, rgb};
+use clay_layout::elements::{FloatingAttachToElement, FloatingClipToElement};
+use clay_layout::math::Vector2;
+use clay_layout::{Declaration, fit, fixed};
+use glam::Vec2;
+
+/// Pixels needed between labelled ticks so labels don't run into each other.
+pub const MIN_LABEL_SPACING: f64 = 80.0;
+/// Pixels needed between ticks of a subdivision level for it to be drawn.
+pub const MIN_TICK_SPACING: f64 = 6.0;
+
+/// Tick generation for a unit shown on a ruler.
+pub trait RulerScale {
+    /// Distance between labelled ticks, the smallest nice value of at least `min_step` units.
+    fn major_step(&self, min_step: f64) -> f64 {
+        nice_step(min_step)
+    }
+
+    /// How many parts each level splits the level above into, starting with the major step.
+    /// `[2, 5]` for a step of 1 gives ticks at every 0.5 and then 0.1. Levels that get too
+    /// dense at the current zoom are left out.
+    fn subdivisions(&self, major_step: f64) -> Vec<u32> {
+        match leading_digit(major_step) {
+            1 => vec![2, 5],
+            2 => vec![2, 2],
+            _ => vec![5, 2],
+        }
+    }
+
+    /// Text of the labelled tick at `value`.
+    fn label(&self, value: f64, major_step: f64) -> String;
+}
+
+/// Smallest of 1, 2 and 5 times a power of ten that is at least `min_step`.
+pub fn nice_step(min_step: f64) -> f64 {
+    if !(min_step > 0.0 && min_step.is_finite()) {
+        return 1.0;
+    }
+    let magnitude = 10f64.powf(min_step.log10().floor());
+    [1.0, 2.0, 5.0]
+        .into_iter()
+        .map(|factor| factor * magnitude)
+        // Allow for rounding in `log10` and `powf`
+        .find(|step| *step >= min_step * (1.0 - 1e-9))
+        .unwrap_or(magnitude * 10.0)
+}
+
+/// 1, 2 or 5 for steps made by [`nice_step`].
+fn leading_digit(step: f64) -> u32 {
+    let magnitude = 10f64.powf(step.log10().floor());
+    (step / magnitude).round() as u32
+}
+
+/// A tick of a ruler.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tick {
+    pub value: f64,
+    /// Offset from the start of the ruler in pixels.
+    pub x: f32,
+    /// 0 for labelled major ticks, then one higher per subdivision.
+    pub level: usize,
+    pub label: Option<String>,
+}
+
+/// Ticks for `width` pixels of ruler starting at `start` units, zoomed to `pixels_per_unit`.
+pub fn ruler_ticks(
+    scale: &impl RulerScale,
+    start: f64,
+    pixels_per_unit: f64,
+    width: f32,
+) -> Vec<Tick> {
+    if pixels_per_unit.is_nan() || pixels_per_unit <= 0.0 || width <= 0.0 {
+        return Vec::new();
+    }
+
+    let major = scale.major_step(MIN_LABEL_SPACING / pixels_per_unit);
+    // Number of finest steps per step of each level, the finest that is still wide enough
+    let mut per_level = vec![1u64];
+    let mut step = major;
+    for parts in scale.subdivisions(major) {
+        step /= parts.max(1) as f64;
+        if step * pixels_per_unit < MIN_TICK_SPACING {
+            break;
+        }
+        per_level.push(parts.max(1) as u64);
+    }
+    let mut counts = Vec::with_capacity(per_level.len());
+    let mut count = 1;
+    for parts in per_level.iter().skip(1).rev() {
+        counts.push(count);
+        count *= parts;
+    }
+    counts.push(count);
+    counts.reverse();
+    let fine = major / count as f64;
+
+    let end = start + width as f64 / pixels_per_unit;
+    let first = (start / fine).ceil() as i64;
+    let last = (end / fine).floor() as i64;
+
+    (first..=last)
+        .map(|index| {
+            let value = index as f64 * fine;
+            let level = counts
+                .iter()
+                .position(|count| index.rem_euclid(*count as i64) == 0)
+                .unwrap_or(counts.len() - 1);
+            Tick {
+                value,
+                x: ((value - start) * pixels_per_unit) as f32,
+                level,
+                label: (level == 0).then(|| scale.label(value, major)),
+            }
+        })
+        .collect()
+}
+
+/// Seconds shown as timecode, `m:ss` or with milliseconds when zoomed in.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TimeScale;
+
+impl RulerScale for TimeScale {
+    fn major_step(&self, min_step: f64) -> f64 {
+        const STEPS: [f64; 13] = [
+            1.0, 2.0, 5.0, 10.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 900.0, 1800.0, 3600.0,
+        ];
+        if min_step < 1.0 {
+            return nice_step(min_step);
+        }
+        STEPS
+            .into_iter()
+            .find(|step| *step >= min_step)
+            .unwrap_or_else(|| nice_step(min_step / 3600.0) * 3600.0)
+    }
+
+    fn subdivisions(&self, major_step: f64) -> Vec<u32> {
+        match major_step as u32 {
+            15 | 900 => vec![3, 5],
+            30 | 1800 => vec![2, 3],
+            60 | 3600 => vec![2, 6],
+            300 => vec![5, 2],
+            600 => vec![2, 5],
+            _ if major_step < 1.0 || major_step <= 10.0 || major_step > 3600.0 => {
+                match leading_digit(major_step) {
+                    1 => vec![2, 5],
+                    2 => vec![2, 2],
+                    _ => vec![5, 2],
+                }
+            }
+            _ => vec![2],
+        }
+    }
+
+    fn label(&self, value: f64, major_step: f64) -> String {
+        let sign = if value < 0.0 { "-" } else { "" };
+        let millis = (value.abs() * 1000.0).round() as u64;
+        let (hours, minutes) = (millis / 3_600_000, millis / 60_000 % 60);
+        let seconds = millis / 1000 % 60;
+
+        let time = if hours > 0 {
+            format!("{sign}{hours}:{minutes:02}:{seconds:02}")
+        } else {
+            format!("{sign}{minutes}:{seconds:02}")
+        };
+        if major_step < 1.0 {
+            format!("{time}.{:03}", millis % 1000)
+        } else {
+            time
+        }
+    }
+}
+
+/// Sample counts, with steps of whole samples.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SampleScale;
+
+impl RulerScale for SampleScale {
+    fn major_step(&self, min_step: f64) -> f64 {
+        nice_step(min_step).max(1.0)
+    }
+
+    fn subdivisions(&self, major_step: f64) -> Vec<u32> {
+        let mut step = major_step;
+        let mut parts = Vec::new();
+        for part in match leading_digit(major_step) {
+            1 => [2, 5],
+            2 => [2, 2],
+            _ => [5, 2],
+        } {
+            step /= part as f64;
+            if step < 1.0 {
+                break;
+            }
+            parts.push(part);
+        }
+        parts
+    }
+
+    fn label(&self, value: f64, _major_step: f64) -> String {
+        format!("{}", value.round() as i64)
+    }
+}
+
+/// Beats shown as `bar`, `bar.beat` or `bar.beat.sixteenth` depending on the zoom. Bars and
+/// beats count from 1.
+#[derive(Debug, Clone, Copy)]
+pub struct BarsScale {
+    pub beats_per_bar: u32,
+}
+
+impl RulerScale for BarsScale {
+    fn major_step(&self, min_step: f64) -> f64 {
+        let bar = self.beats_per_bar.max(1) as f64;
+        if min_step > bar {
+            // Whole bars, doubling
+            return bar * 2f64.powf((min_step / bar).log2().ceil());
+        }
+        if min_step > 1.0 {
+            return bar;
+        }
+        // Beats and fractions of a beat down to a sixteenth of a bar in 4/4
+        [0.25, 0.5, 1.0]
+            .into_iter()
+            .find(|step| *step >= min_step)
+            .unwrap_or(0.25)
+    }
+
+    fn subdivisions(&self, major_step: f64) -> Vec<u32> {
+        let bar = self.beats_per_bar.max(1) as f64;
+        let mut parts = Vec::new();
+        let mut step = major_step;
+        while step > bar {
+            parts.push(2);
+            step /= 2.0;
+        }
+        if step >= bar {
+            parts.push(self.beats_per_bar.max(1));
+            step = 1.0;
+        }
+        while step > 0.25 {
+            parts.push(2);
+            step /= 2.0;
+        }
+        parts
+    }
+
+    fn label(&self, value: f64, major_step: f64) -> String {
+        let beats_per_bar = self.beats_per_bar.max(1) as f64;
+        let bar = (value / beats_per_bar).floor();
+        let beat = value - bar * beats_per_bar;
+
+        if major_step >= beats_per_bar {
+            format!("{}", bar as i64 + 1)
+        } else if major_step >= 1.0 {
+            format!("{}.{}", bar as i64 + 1, beat.floor() as i64 + 1)
+        } else {
+            let sixteenth = (beat.fract() * 4.0).round() as i64;
+            format!(
+                "{}.{}.{}",
+                bar as i64 + 1,
+                beat.floor() as i64 + 1,
+                sixteenth + 1
+            )
+        }
+    }
+}
+
+impl Ui {
+    /// A `size` ruler showing `scale` units from `start`, zoomed to `pixels_per_unit`. Major
+    /// ticks are labelled and subdivisions are drawn shorter while there is room for them.
+    ///
+    /// Styled by the `ruler` stylesheet rule: `background` for the ruler, `border_color` for
+    /// the ticks and `text_color` for the labels.
+    pub fn ruler(
+        &self,
+        id: &str,
+        scale: &impl RulerScale,
+        start: f64,
+        pixels_per_unit: f64,
+        size: Vec2,
+    ) {
+        let id = self.id(id);
+        let style = self.animated_widget_style("ruler", id);
+        let tick_color = style.border_color.unwrap_or(rgb(140, 140, 140));
+        let text_color = style.text_color.unwrap_or(rgb(200, 200, 200));
+
+        let mut decl = Declaration::new();
+        decl.id(id)
+            .layout()
+            .width(fixed!(size.x))
+            .height(fixed!(size.y))
+            .end()
+            .clip(true, false, Vector2::new(0.0, 0.0));
+        apply_style(&mut decl, &style);
+
+        let floating = |decl: &mut Declaration<'_, _, _>, x: f32, y: f32| {
+            decl.floating()
+                .attach_to(FloatingAttachToElement::Parent)
+                .clip_to(FloatingClipToElement::AttachedParent)
+                .offset(Vector2::new(x, y))
+                .end();
+        };
+
+        self.with_layout(&decl, |ui| {
+            for tick in ruler_ticks(scale, start, pixels_per_unit, size.x) {
+                let height = match tick.level {
+                    0 => size.y,
+                    1 => size.y * 0.5,
+                    _ => size.y * 0.25,
+                };
+                let x = tick.x.round();
+
+                let mut line = Declaration::new();
+                line.layout()
+                    .width(fixed!(1.0))
+                    .height(fixed!(height))
+                    .end()
+                    .background_color(tick_color);
+                floating(&mut line, x, size.y - height);
+                ui.with_layout(&line, |_ui| {});
+
+                if let Some(label) = tick.label {
+                    let mut text = Declaration::new();
+                    text.layout().width(fit!()).height(fit!()).end();
+                    floating(&mut text, x + 3.0, 0.0);
+                    let label = ui.alloc_str(label);
+                    ui.with_layout(&text, |ui| ui.text_element(label, text_color));
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nice_step() {
+        assert_eq!(nice_step(0.7), 1.0);
+        assert_eq!(nice_step(1.0), 1.0);
+        assert_eq!(nice_step(1.3), 2.0);
+        assert_eq!(nice_step(3.0), 5.0);
+        assert_eq!(nice_step(7.0), 10.0);
+        assert_eq!(nice_step(240.0), 500.0);
+        assert!((nice_step(0.03) - 0.05).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_ticks() {
+        // 10 pixels per unit gives labels every 10 units, halves at 5 and ticks at every unit
+        let ticks = ruler_ticks(&SampleScale, 0.0, 10.0, 200.0);
+        let major: Vec<_> = ticks
+            .iter()
+            .filter(|t| t.level == 0)
+            .map(|t| t.value)
+            .collect();
+        assert_eq!(major, [0.0, 10.0, 20.0]);
+        assert_eq!(ticks.len(), 21);
+        assert_eq!((ticks[1].level, ticks[5].level), (2, 1));
+        assert_eq!(ticks[5].x, 50.0);
+        assert_eq!(ticks[10].label.as_deref(), Some("10"));
+
+        // Too dense subdivisions are left out
+        struct Tenths;
+        impl RulerScale for Tenths {
+            fn subdivisions(&self, _major_step: f64) -> Vec<u32> {
+                vec![10, 10]
+            }
+
+            fn label(&self, value: f64, _major_step: f64) -> String {
+                format!("{value}")
+            }
+        }
+        let ticks = ruler_ticks(&Tenths, 0.0, 10.0, 200.0);
+        assert_eq!(ticks.len(), 21);
+        assert_eq!(ticks[1].level, 1);
+
+        // Ticks before the start are left out and x is relative to the start
+        let ticks = ruler_ticks(&SampleScale, 12.5, 10.0, 100.0);
+        assert_eq!((ticks[0].value, ticks[0].x), (13.0, 5.0));
+    }
+
+    #[test]
+    fn test_time_labels() {
+        assert_eq!(TimeScale.major_step(40.0), 60.0);
+        assert_eq!(TimeScale.label(75.0, 15.0), "1:15");
+        assert_eq!(TimeScale.label(3725.0, 60.0), "1:02:05");
+        assert_eq!(TimeScale.label(1.25, 0.05), "0:01.250");
+        // A minute splits into 30 and 5 seconds
+        let ticks = ruler_ticks(&TimeScale, 0.0, 2.0, 121.0);
+        let levels: Vec<_> = ticks.iter().map(|t| t.level).collect();
+        assert_eq!(levels[..7], [0, 2, 2, 2, 2, 2, 1]);
+    }
+
+    #[test]
+    fn test_bars() {
+        let scale = BarsScale { beats_per_bar: 4 };
+        assert_eq!(scale.major_step(3.0), 4.0);
+        assert_eq!(scale.major_step(5.0), 8.0);
+        assert_eq!(scale.major_step(0.3), 0.5);
+        assert_eq!(scale.subdivisions(8.0), [2, 4, 2, 2]);
+        assert_eq!(scale.label(9.0, 4.0), "3");
+        assert_eq!(scale.label(9.0, 1.0), "3.2");
+        assert_eq!(scale.label(9.75, 0.25), "3.2.4");
+    }
+}
@@ -0,0 +1,151 @@
+//! Musical scales and the chords built from them.
+//!
+//! A [`Scale`] is a root and a [`ScaleKind`], and answers which notes belong to it. Notes are
+//! MIDI note numbers, and pitch classes the note within its octave from 0 for C to 11 for B.
+//! Chords are stacked from every other note of the scale, which gives the triads and seventh
+//! chords of the major and minor scales.
+use std::fmt;
+
+/// Names of the pitch classes, using sharps.
+const NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// The pattern of a scale, as the semitones of its notes above the root.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScaleKind {
+    #[default]
+    Major,
+    NaturalMinor,
+    HarmonicMinor,
+    Dorian,
+    Mixolydian,
+    MajorPentatonic,
+    MinorPentatonic,
+    /// Every note, for when nothing should be shaded.
+    Chromatic,
+}
+
+impl ScaleKind {
+    pub const ALL: [ScaleKind; 8] = [
+        ScaleKind::Major,
+        ScaleKind::NaturalMinor,
+        ScaleKind::HarmonicMinor,
+        ScaleKind::Dorian,
+        ScaleKind::Mixolydian,
+        ScaleKind::MajorPentatonic,
+        ScaleKind::MinorPentatonic,
+        ScaleKind::Chromatic,
+    ];
+
+    /// Semitones of the notes above the root, from the root up.
+    pub fn intervals(self) -> &'static [u8] {
+        match self {
+            ScaleKind::Major => &[0, 2, 4, 5, 7, 9, 11],
+            ScaleKind::NaturalMinor => &[0, 2, 3, 5, 7, 8, 10],
+            ScaleKind::HarmonicMinor => &[0, 2, 3, 5, 7, 8, 11],
+            ScaleKind::Dorian => &[0, 2, 3, 5, 7, 9, 10],
+            ScaleKind::Mixolydian => &[0, 2, 4, 5, 7, 9, 10],
+            ScaleKind::MajorPentatonic => &[0, 2, 4, 7, 9],
+            ScaleKind::MinorPentatonic => &[0, 3, 5, 7, 10],
+            ScaleKind::Chromatic => &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ScaleKind::Major => "Major",
+            ScaleKind::NaturalMinor => "Minor",
+            ScaleKind::HarmonicMinor => "Harmonic Minor",
+            ScaleKind::Dorian => "Dorian",
+            ScaleKind::Mixolydian => "Mixolydian",
+            ScaleKind::MajorPentatonic => "Major Pentatonic",
+            ScaleKind::MinorPentatonic => "Minor Pentatonic",
+            ScaleKind::Chromatic => "Chromatic",
+        }
+    }
+}
+
+/// Name of the pitch class of `note`, like `C#`.
+pub fn note_name(note: u8) -> &'static str {
+    NAMES[note as usize % 12]
+}
+
+/// A scale starting at a root pitch class.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Scale {
+    /// Pitch class of the root, from 0 for C to 11 for B.
+    pub root: u8,
+    pub kind: ScaleKind,
+}
+
+impl Scale {
+    pub fn new(root: u8, kind: ScaleKind) -> Self {
+        Self {
+            root: root % 12,
+            kind,
+        }
+    }
+
+    /// Which note of the scale `note` is, 0 for the root, or `None` when it's not in it.
+    pub fn degree(&self, note: u8) -> Option<usize> {
+        let interval = (note % 12 + 12 - self.root) % 12;
+        self.kind
+            .intervals()
+            .iter()
+            .position(|step| *step == interval)
+    }
+
+    pub fn contains(&self, note: u8) -> bool {
+        self.degree(note).is_some()
+    }
+
+    pub fn is_root(&self, note: u8) -> bool {
+        note % 12 == self.root
+    }
+
+    /// Pitch classes of the chord of `size` notes on the `degree`th note of the scale, 0 for
+    /// the root: 3 for a triad and 4 for a seventh chord.
+    pub fn chord(&self, degree: usize, size: usize) -> Vec<u8> {
+        let intervals = self.kind.intervals();
+        (0..size)
+            .map(|step| (self.root + intervals[(degree + step * 2) % intervals.len()]) % 12)
+            .collect()
+    }
+}
+
+impl fmt::Display for Scale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", note_name(self.root), self.kind.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scale() {
+        let d_minor = Scale::new(2, ScaleKind::NaturalMinor);
+        assert_eq!(d_minor.to_string(), "D Minor");
+        // D4 and the B flat above it are in, B isn't
+        assert_eq!(d_minor.degree(62), Some(0));
+        assert_eq!(d_minor.degree(70), Some(5));
+        assert!(!d_minor.contains(71));
+        assert!(d_minor.is_root(38) && !d_minor.is_root(40));
+        assert!((0..128).all(|note| Scale::new(5, ScaleKind::Chromatic).contains(note)));
+        assert_eq!(Scale::new(14, ScaleKind::Major).root, 2);
+    }
+
+    #[test]
+    fn test_chord() {
+        let c_major = Scale::new(0, ScaleKind::Major);
+        assert_eq!(c_major.chord(0, 3), [0, 4, 7]);
+        // The dominant seventh on G wraps around the octave
+        assert_eq!(c_major.chord(4, 4), [7, 11, 2, 5]);
+        assert_eq!(
+            Scale::new(9, ScaleKind::HarmonicMinor).chord(4, 3),
+            [4, 8, 11]
+        );
+    }
+}
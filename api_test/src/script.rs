@@ -0,0 +1,902 @@
+//! Small scripting layer for prototyping panels without recompiling.
+//!
+//! A script declares areas and labels through a safe subset of the [`Ui`] API. Scripts loaded
+//! from disk are reloaded whenever the file changes, so a panel can be tweaked while the app is
+//! running. Variables set from Rust with [`ScriptPanel::set_var`] can be used for repetition,
+//! conditions and `{name}` interpolation in strings.
+//!
+//! Only compiled with the `script` feature.
+//!
+//! The language is our own rather than an embedded one like rhai on purpose. A panel script is
+//! a layout, not a program: the properties are the ones of [`crate::area!`] and are parsed by
+//! the same [`crate::area_props`] code as ui descriptions, and there is nothing to evaluate
+//! besides repeating and hiding parts of it. A general purpose language would need bindings for
+//! the declaration API on top of that, pull a sizable dependency into the crate and let scripts
+//! loop forever or touch state they shouldn't while the frame is declared.
+//!
+//! ```text
+//! // Mixer prototype
+//! area "mixer" {
+//!     width: grow
+//!     height: 200
+//!     direction: left_to_right
+//!     padding: 5
+//!     background: #323232
+//!
+//!     repeat tracks as i {
+//!         area "strip_{i}" {
+//!             width: 80
+//!             height: grow
+//!             background: #2d2d2d
+//!             label "Track {i}" { color: #ffffff }
+//!         }
+//!     }
+//!
+//!     if show_master {
+//!         label "MASTER"
+//!     }
+//! }
+//! ```
+use crate::area_props::{
+    AreaProp, PropValue, apply_area_props, area_prop, color_value, parse_hex_color,
+};
+use crate::file_watch::WatchedFile;
+use crate::internal_error::InternalResult;
+use crate::{Ui, rgb};
+use clay_layout::Declaration;
+use clay_layout::color::Color as ClayColor;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Upper bound for `repeat` so a typo can't lock up the frame.
+const MAX_REPEAT: u32 = 1024;
+
+const DEFAULT_LABEL_COLOR: ClayColor = ClayColor::rgb(220.0, 220.0, 220.0);
+
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("line {line}: {message}")]
+pub struct ScriptError {
+    pub line: u32,
+    pub message: String,
+}
+
+impl ScriptError {
+    fn new(line: u32, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+type ScriptResult<T> = Result<T, ScriptError>;
+
+/// A value that can be handed to a script from Rust.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptValue {
+    Number(f64),
+    Bool(bool),
+    Text(String),
+}
+
+impl ScriptValue {
+    fn is_truthy(&self) -> bool {
+        match self {
+            ScriptValue::Number(v) => *v != 0.0,
+            ScriptValue::Bool(v) => *v,
+            ScriptValue::Text(v) => !v.is_empty(),
+        }
+    }
+}
+
+impl fmt::Display for ScriptValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptValue::Number(v) => write!(f, "{}", v),
+            ScriptValue::Bool(v) => write!(f, "{}", v),
+            ScriptValue::Text(v) => f.write_str(v),
+        }
+    }
+}
+
+impl From<f64> for ScriptValue {
+    fn from(value: f64) -> Self {
+        ScriptValue::Number(value)
+    }
+}
+
+impl From<f32> for ScriptValue {
+    fn from(value: f32) -> Self {
+        ScriptValue::Number(value as f64)
+    }
+}
+
+impl From<i32> for ScriptValue {
+    fn from(value: i32) -> Self {
+        ScriptValue::Number(value as f64)
+    }
+}
+
+impl From<usize> for ScriptValue {
+    fn from(value: usize) -> Self {
+        ScriptValue::Number(value as f64)
+    }
+}
+
+impl From<bool> for ScriptValue {
+    fn from(value: bool) -> Self {
+        ScriptValue::Bool(value)
+    }
+}
+
+impl From<&str> for ScriptValue {
+    fn from(value: &str) -> Self {
+        ScriptValue::Text(value.to_string())
+    }
+}
+
+impl From<String> for ScriptValue {
+    fn from(value: String) -> Self {
+        ScriptValue::Text(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Number(f32),
+    Percent(f32),
+    Color(ClayColor),
+    LBrace,
+    RBrace,
+    Colon,
+    Bang,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(v) => write!(f, "'{}'", v),
+            Token::Str(v) => write!(f, "\"{}\"", v),
+            Token::Number(v) => write!(f, "{}", v),
+            Token::Percent(v) => write!(f, "{}%", v * 100.0),
+            Token::Color(_) => f.write_str("color"),
+            Token::LBrace => f.write_str("'{'"),
+            Token::RBrace => f.write_str("'}'"),
+            Token::Colon => f.write_str("':'"),
+            Token::Bang => f.write_str("'!'"),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> ScriptResult<Vec<(Token, u32)>> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    let mut line = 1;
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            '\n' => {
+                line += 1;
+                chars.next();
+            }
+            // Commas and semicolons are accepted as optional separators
+            c if c.is_whitespace() || c == ',' || c == ';' => {
+                chars.next();
+            }
+            '/' => {
+                chars.next();
+                if chars.next_if_eq(&'/').is_none() {
+                    return Err(ScriptError::new(line, "unexpected '/'"));
+                }
+                while chars.next_if(|c| *c != '\n').is_some() {}
+            }
+            '{' | '}' | ':' | '!' => {
+                chars.next();
+                tokens.push((
+                    match c {
+                        '{' => Token::LBrace,
+                        '}' => Token::RBrace,
+                        ':' => Token::Colon,
+                        _ => Token::Bang,
+                    },
+                    line,
+                ));
+            }
+            '"' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => text.push('\n'),
+                            Some(c @ ('"' | '\\')) => text.push(c),
+                            _ => return Err(ScriptError::new(line, "invalid escape in string")),
+                        },
+                        Some('\n') | None => {
+                            return Err(ScriptError::new(line, "unterminated string"));
+                        }
+                        Some(c) => text.push(c),
+                    }
+                }
+                tokens.push((Token::Str(text), line));
+            }
+            '#' => {
+                chars.next();
+                let mut hex = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric()) {
+                    hex.push(c);
+                }
+                let color = parse_hex_color(&hex)
+                    .ok_or_else(|| ScriptError::new(line, format!("invalid color '#{}'", hex)))?;
+                tokens.push((Token::Color(color), line));
+            }
+            c if c.is_ascii_digit() || c == '-' || c == '.' => {
+                let mut number = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || *c == '-' || *c == '.')
+                {
+                    number.push(c);
+                }
+                let value: f32 = number
+                    .parse()
+                    .map_err(|_| ScriptError::new(line, format!("invalid number '{}'", number)))?;
+                if chars.next_if_eq(&'%').is_some() {
+                    tokens.push((Token::Percent(value / 100.0), line));
+                } else {
+                    tokens.push((Token::Number(value), line));
+                }
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut ident = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                    ident.push(c);
+                }
+                tokens.push((Token::Ident(ident), line));
+            }
+            c => {
+                return Err(ScriptError::new(
+                    line,
+                    format!("unexpected character '{}'", c),
+                ));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone)]
+enum Count {
+    Fixed(u32),
+    Var(String),
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Area {
+        id: Option<String>,
+        props: Vec<AreaProp>,
+        children: Vec<Node>,
+        line: u32,
+    },
+    Label {
+        text: String,
+        color: Option<ClayColor>,
+        line: u32,
+    },
+    Repeat {
+        count: Count,
+        var: Option<String>,
+        children: Vec<Node>,
+        line: u32,
+    },
+    If {
+        var: String,
+        negate: bool,
+        children: Vec<Node>,
+        line: u32,
+    },
+}
+
+impl Token {
+    fn prop_value(&self) -> Option<PropValue<'_>> {
+        match self {
+            Token::Ident(v) => Some(PropValue::Ident(v)),
+            Token::Number(v) => Some(PropValue::Number(*v)),
+            Token::Percent(v) => Some(PropValue::Percent(*v)),
+            Token::Color(c) => Some(PropValue::Color(*c)),
+            _ => None,
+        }
+    }
+}
+
+/// Only a subset of tokens are valid property values, anything else is rejected up front.
+fn token_area_prop(name: &str, value: &Token) -> Result<AreaProp, String> {
+    match value.prop_value() {
+        Some(value) => area_prop(name, value),
+        None => Err(format!("invalid value {} for '{}'", value, name)),
+    }
+}
+
+/// `name: value` pairs (with their line) and child elements of a `{ ... }` block.
+type Block = (Vec<(String, Token, u32)>, Vec<Node>);
+
+struct Parser {
+    tokens: Vec<(Token, u32)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn line(&self) -> u32 {
+        self.tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map_or(1, |(_, line)| *line)
+    }
+
+    fn next(&mut self) -> ScriptResult<Token> {
+        let line = self.line();
+        let token = self.tokens.get(self.pos).map(|(t, _)| t.clone());
+        self.pos += 1;
+        token.ok_or_else(|| ScriptError::new(line, "unexpected end of script"))
+    }
+
+    fn expect(&mut self, expected: Token) -> ScriptResult<()> {
+        let line = self.line();
+        let token = self.next()?;
+        if token == expected {
+            Ok(())
+        } else {
+            Err(ScriptError::new(
+                line,
+                format!("expected {} but found {}", expected, token),
+            ))
+        }
+    }
+
+    fn ident(&mut self) -> ScriptResult<String> {
+        let line = self.line();
+        match self.next()? {
+            Token::Ident(name) => Ok(name),
+            token => Err(ScriptError::new(
+                line,
+                format!("expected a name but found {}", token),
+            )),
+        }
+    }
+
+    fn program(&mut self) -> ScriptResult<Vec<Node>> {
+        let mut nodes = Vec::new();
+        while self.peek().is_some() {
+            nodes.push(self.element()?);
+        }
+        Ok(nodes)
+    }
+
+    /// Parses `{ ... }` returning the `name: value` pairs and child elements.
+    fn block(&mut self) -> ScriptResult<Block> {
+        self.expect(Token::LBrace)?;
+
+        let mut props = Vec::new();
+        let mut children = Vec::new();
+
+        loop {
+            match self.peek() {
+                Some(Token::RBrace) => {
+                    self.pos += 1;
+                    return Ok((props, children));
+                }
+                Some(Token::Ident(_))
+                    if matches!(self.tokens.get(self.pos + 1), Some((Token::Colon, _))) =>
+                {
+                    let line = self.line();
+                    let name = self.ident()?;
+                    self.expect(Token::Colon)?;
+                    props.push((name, self.next()?, line));
+                }
+                Some(_) => children.push(self.element()?),
+                None => return Err(ScriptError::new(self.line(), "missing '}'")),
+            }
+        }
+    }
+
+    fn element(&mut self) -> ScriptResult<Node> {
+        let line = self.line();
+        let keyword = self.ident()?;
+
+        match keyword.as_str() {
+            "area" => {
+                let id = match self.peek() {
+                    Some(Token::Str(_)) => match self.next()? {
+                        Token::Str(id) => Some(id),
+                        _ => unreachable!(),
+                    },
+                    _ => None,
+                };
+
+                let (props, children) = self.block()?;
+                let props = props
+                    .iter()
+                    .map(|(name, value, line)| {
+                        token_area_prop(name, value).map_err(|e| ScriptError::new(*line, e))
+                    })
+                    .collect::<ScriptResult<_>>()?;
+
+                Ok(Node::Area {
+                    id,
+                    props,
+                    children,
+                    line,
+                })
+            }
+            "label" => {
+                let text = match self.next()? {
+                    Token::Str(text) => text,
+                    token => {
+                        return Err(ScriptError::new(
+                            line,
+                            format!("label expects a string but found {}", token),
+                        ));
+                    }
+                };
+
+                let mut color = None;
+                if self.peek() == Some(&Token::LBrace) {
+                    let (props, children) = self.block()?;
+                    if !children.is_empty() {
+                        return Err(ScriptError::new(line, "labels can't have children"));
+                    }
+                    for (name, value, line) in props {
+                        match name.as_str() {
+                            "color" => {
+                                color = Some(value.prop_value().and_then(color_value).ok_or_else(
+                                    || ScriptError::new(line, format!("invalid color {}", value)),
+                                )?)
+                            }
+                            _ => {
+                                return Err(ScriptError::new(
+                                    line,
+                                    format!("unknown label property '{}'", name),
+                                ));
+                            }
+                        }
+                    }
+                }
+
+                Ok(Node::Label { text, color, line })
+            }
+            "repeat" => {
+                let count = match self.next()? {
+                    Token::Number(n) if n >= 0.0 && n.fract() == 0.0 => Count::Fixed(n as u32),
+                    Token::Ident(name) => Count::Var(name),
+                    token => {
+                        return Err(ScriptError::new(
+                            line,
+                            format!("invalid repeat count {}", token),
+                        ));
+                    }
+                };
+
+                let var = if matches!(self.peek(), Some(Token::Ident(v)) if v == "as") {
+                    self.pos += 1;
+                    Some(self.ident()?)
+                } else {
+                    None
+                };
+
+                let children = self.children_only(line)?;
+                Ok(Node::Repeat {
+                    count,
+                    var,
+                    children,
+                    line,
+                })
+            }
+            "if" => {
+                let negate = self.peek() == Some(&Token::Bang);
+                if negate {
+                    self.pos += 1;
+                }
+                let var = self.ident()?;
+                let children = self.children_only(line)?;
+                Ok(Node::If {
+                    var,
+                    negate,
+                    children,
+                    line,
+                })
+            }
+            _ => Err(ScriptError::new(
+                line,
+                format!("unknown element '{}'", keyword),
+            )),
+        }
+    }
+
+    fn children_only(&mut self, line: u32) -> ScriptResult<Vec<Node>> {
+        let (props, children) = self.block()?;
+        if let Some((name, _, _)) = props.first() {
+            return Err(ScriptError::new(
+                line,
+                format!("unexpected property '{}'", name),
+            ));
+        }
+        Ok(children)
+    }
+}
+
+fn parse(source: &str) -> ScriptResult<Vec<Node>> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        pos: 0,
+    };
+    parser.program()
+}
+
+/// Replaces `{name}` with the value of the variable. `{{` produces a literal `{`.
+fn interpolate<'t>(
+    text: &'t str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<Cow<'t, str>, String> {
+    if !text.contains('{') {
+        return Ok(Cow::Borrowed(text));
+    }
+
+    let mut output = String::with_capacity(text.len() + 8);
+    let mut rest = text;
+
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+
+        if let Some(stripped) = rest.strip_prefix('{') {
+            output.push('{');
+            rest = stripped;
+            continue;
+        }
+
+        let end = rest
+            .find('}')
+            .ok_or_else(|| format!("missing '}}' in \"{}\"", text))?;
+        let name = &rest[..end];
+        let value = lookup(name).ok_or_else(|| format!("unknown variable '{}'", name))?;
+        output.push_str(&value);
+        rest = &rest[end + 1..];
+    }
+
+    output.push_str(rest);
+    Ok(Cow::Owned(output))
+}
+
+/// A script file (or source string) that declares a panel.
+pub struct ScriptPanel {
+    file: Option<WatchedFile>,
+    program: Vec<Node>,
+    parse_error: Option<ScriptError>,
+    runtime_errors: Vec<ScriptError>,
+    vars: HashMap<String, ScriptValue>,
+}
+
+impl ScriptPanel {
+    /// Loads a script from disk. Parse errors don't fail the load, they are shown in the panel
+    /// and the script is picked up again once the file has been fixed.
+    pub fn load(path: impl Into<PathBuf>) -> InternalResult<Self> {
+        let mut panel = Self::new();
+        let mut file = WatchedFile::new(path);
+        panel.set_source(&file.read()?);
+        panel.file = Some(file);
+        Ok(panel)
+    }
+
+    /// Creates a panel from a source string. These panels are never reloaded.
+    pub fn from_source(source: &str) -> Self {
+        let mut panel = Self::new();
+        panel.set_source(source);
+        panel
+    }
+
+    fn new() -> Self {
+        Self {
+            file: None,
+            program: Vec::new(),
+            parse_error: None,
+            runtime_errors: Vec::new(),
+            vars: HashMap::new(),
+        }
+    }
+
+    /// Sets a variable that the script can read.
+    pub fn set_var(&mut self, name: &str, value: impl Into<ScriptValue>) {
+        self.vars.insert(name.to_string(), value.into());
+    }
+
+    /// Parse error of the latest reload and errors from the last run.
+    pub fn errors(&self) -> impl Iterator<Item = &ScriptError> {
+        self.parse_error.iter().chain(self.runtime_errors.iter())
+    }
+
+    fn set_source(&mut self, source: &str) {
+        match parse(source) {
+            Ok(program) => {
+                self.program = program;
+                self.parse_error = None;
+            }
+            Err(e) => {
+                // Keep running the last good version of the script
+                eprintln!("Script error: {}", e);
+                self.parse_error = Some(e);
+            }
+        }
+    }
+
+    /// Reloads the script if the file changed on disk. Returns true if it was reloaded.
+    pub fn reload_if_changed(&mut self) -> bool {
+        let Some(file) = &mut self.file else {
+            return false;
+        };
+
+        match file.read_if_changed() {
+            Some(Ok(source)) => {
+                self.set_source(&source);
+                true
+            }
+            Some(Err(e)) => {
+                eprintln!("Unable to reload script {:?}: {}", file.path(), e);
+                false
+            }
+            None => false,
+        }
+    }
+}
+
+struct Runner<'u, 'p> {
+    ui: &'u Ui,
+    vars: &'p HashMap<String, ScriptValue>,
+    locals: Vec<(&'p str, ScriptValue)>,
+    used_ids: HashSet<String>,
+    errors: Vec<ScriptError>,
+}
+
+impl<'p> Runner<'_, 'p> {
+    fn lookup(&self, name: &str) -> Option<&ScriptValue> {
+        self.locals
+            .iter()
+            .rev()
+            .find(|(local, _)| *local == name)
+            .map(|(_, value)| value)
+            .or_else(|| self.vars.get(name))
+    }
+
+    fn interpolate(&mut self, text: &str, line: u32) -> Option<String> {
+        match interpolate(text, |name| self.lookup(name).map(|v| v.to_string())) {
+            Ok(text) => Some(text.into_owned()),
+            Err(e) => {
+                self.errors.push(ScriptError::new(line, e));
+                None
+            }
+        }
+    }
+
+    /// Clay treats duplicated ids as a fatal error so they are rejected here instead.
+    fn claim_id(&mut self, id: &str, line: u32) -> bool {
+        if self.used_ids.insert(id.to_string()) {
+            true
+        } else {
+            self.errors
+                .push(ScriptError::new(line, format!("duplicate id \"{}\"", id)));
+            false
+        }
+    }
+
+    fn run(&mut self, nodes: &'p [Node]) {
+        for node in nodes {
+            match node {
+                Node::Area {
+                    id,
+                    props,
+                    children,
+                    line,
+                } => {
+                    let mut decl = Declaration::new();
+
+                    if let Some(id) = id {
+                        let Some(id) = self.interpolate(id, *line) else {
+                            continue;
+                        };
+                        if !self.claim_id(&id, *line) {
+                            continue;
+                        }
+                        decl.id(self.ui.id(self.ui.alloc_str(id)));
+                    }
+
+                    apply_area_props(&mut decl, props);
+
+                    let ui = self.ui;
+                    ui.with_layout(&decl, |_ui| self.run(children));
+                }
+                Node::Label { text, color, line } => {
+                    let Some(text) = self.interpolate(text, *line) else {
+                        continue;
+                    };
+                    if !self.claim_id(&text, *line) {
+                        continue;
+                    }
+                    let text = self.ui.alloc_str(text);
+                    self.ui.label(text, color.unwrap_or(DEFAULT_LABEL_COLOR));
+                }
+                Node::Repeat {
+                    count,
+                    var,
+                    children,
+                    line,
+                } => {
+                    let count = match count {
+                        Count::Fixed(n) => Some(*n),
+                        Count::Var(name) => match self.lookup(name) {
+                            Some(ScriptValue::Number(n)) if *n >= 0.0 => Some(*n as u32),
+                            _ => None,
+                        },
+                    };
+
+                    let Some(count) = count else {
+                        self.errors
+                            .push(ScriptError::new(*line, "repeat count must be a number"));
+                        continue;
+                    };
+
+                    for i in 0..count.min(MAX_REPEAT) {
+                        if let Some(var) = var {
+                            self.locals.push((var, ScriptValue::Number(i as f64)));
+                        }
+                        self.run(children);
+                        if var.is_some() {
+                            self.locals.pop();
+                        }
+                    }
+                }
+                Node::If {
+                    var,
+                    negate,
+                    children,
+                    line,
+                } => match self.lookup(var).map(ScriptValue::is_truthy) {
+                    Some(value) => {
+                        if value != *negate {
+                            self.run(children);
+                        }
+                    }
+                    None => self.errors.push(ScriptError::new(
+                        *line,
+                        format!("unknown variable '{}'", var),
+                    )),
+                },
+            }
+        }
+    }
+}
+
+impl Ui {
+    /// Reloads `panel` if its script changed on disk and declares its elements at the current
+    /// position in the layout.
+    pub fn script_panel(&self, panel: &mut ScriptPanel) {
+        panel.reload_if_changed();
+
+        let mut runner = Runner {
+            ui: self,
+            vars: &panel.vars,
+            locals: Vec::new(),
+            used_ids: HashSet::new(),
+            errors: Vec::new(),
+        };
+        runner.run(&panel.program);
+        let errors = runner.errors;
+        panel.runtime_errors = errors;
+
+        if let Some(error) = panel.errors().next() {
+            let text = self.alloc_str(format!("Script error: {}", error));
+            self.label(text, rgb(255, 90, 90));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clay_layout::layout::Sizing;
+
+    #[test]
+    fn test_parse_program() {
+        let program = parse(
+            r#"
+            // comment
+            area "root" {
+                width: grow
+                height: 50%
+                padding: 4
+                background: #ff000080
+                label "Hello {name}" { color: #ffffff }
+                repeat 3 as i {
+                    label "item {i}"
+                }
+                if !hidden {
+                    area { width: 10, height: fit }
+                }
+            }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(program.len(), 1);
+        let Node::Area {
+            id,
+            props,
+            children,
+            ..
+        } = &program[0]
+        else {
+            panic!("expected area");
+        };
+
+        assert_eq!(id.as_deref(), Some("root"));
+        assert!(matches!(props[0], AreaProp::Width(Sizing::Grow(..))));
+        assert!(matches!(props[1], AreaProp::Height(Sizing::Percent(p)) if p == 0.5));
+        assert!(matches!(props[2], AreaProp::Padding(4)));
+        assert!(
+            matches!(props[3], AreaProp::Background(c) if c == ClayColor::u_rgba(255, 0, 0, 128))
+        );
+        assert_eq!(children.len(), 3);
+        assert!(
+            matches!(&children[1], Node::Repeat { count: Count::Fixed(3), var: Some(v), .. } if v == "i")
+        );
+        assert!(matches!(&children[2], Node::If { negate: true, .. }));
+    }
+
+    #[test]
+    fn test_errors_report_line() {
+        let error = parse("area {\n  width: grow\n  colour: #ffffff\n}").unwrap_err();
+        assert_eq!(error.line, 3);
+        assert!(error.message.contains("colour"));
+
+        let error = parse("label \"oops\n").unwrap_err();
+        assert_eq!(error.line, 1);
+
+        let error = parse("area {\n  padding: -1\n}").unwrap_err();
+        assert_eq!(error.line, 2);
+
+        let error = parse("area {").unwrap_err();
+        assert!(error.message.contains("missing"));
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let lookup = |name: &str| (name == "n").then(|| "42".to_string());
+        assert_eq!(interpolate("plain", lookup).unwrap(), "plain");
+        assert_eq!(interpolate("n = {n}", lookup).unwrap(), "n = 42");
+        assert_eq!(interpolate("{{n}", lookup).unwrap(), "{n}");
+        assert!(interpolate("{missing}", lookup).is_err());
+        assert!(interpolate("{n", lookup).is_err());
+    }
+
+    #[test]
+    fn test_reload_keeps_last_good_program() {
+        let mut panel = ScriptPanel::from_source("label \"ok\"");
+        assert_eq!(panel.program.len(), 1);
+
+        panel.set_source("label");
+        assert_eq!(panel.program.len(), 1);
+        assert_eq!(panel.errors().count(), 1);
+
+        panel.set_source("label \"a\" label \"b\"");
+        assert_eq!(panel.program.len(), 2);
+        assert_eq!(panel.errors().count(), 0);
+    }
+}
@@ -0,0 +1,315 @@
+//! Scrolled regions and moving them from code.
+//!
+//! [`Ui::scroll_area`] clips its content and offsets it by a scroll position kept per id.
+//! Sizes are measured from the previous frame: the content element's rectangle gives the
+//! content size, and items to scroll to are looked up the same way.
+//!
+//! The mouse wheel and dragging the thumbs of the scrollbars shown along the edges of content
+//! that doesn't fit move the scroll position. Like [`Ui::scroll_to`] that takes effect next
+//! frame, so everything declared this frame agrees on where the content is, see
+//! [`Ui::scroll_area_state`].
+use crate::ui::{Ui, apply_style};
+use clay_layout::elements::FloatingAttachToElement;
+use clay_layout::id::Id;
+use clay_layout::layout::LayoutDirection;
+use clay_layout::math::Vector2;
+use clay_layout::{Declaration, fit, fixed, grow};
+use glam::{Vec2, Vec4, Vec4Swizzles};
+
+/// Thickness of the scrollbars.
+const SCROLLBAR_WIDTH: f32 = 8.0;
+/// Shortest a thumb gets, so it can still be grabbed in long content.
+const MIN_THUMB_LENGTH: f32 = 16.0;
+/// Pixels scrolled per step of the mouse wheel.
+const WHEEL_STEP: f32 = 40.0;
+
+/// Where a scrolled to target ends up in the visible part of a scroll area.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Start,
+    Center,
+    End,
+}
+
+/// What [`Ui::scroll_to`] brings into view.
+#[derive(Debug, Clone, Copy)]
+pub enum ScrollTarget {
+    /// An element inside the scroll area, by its layout as of the previous frame.
+    Item(Id),
+    /// A position in the content, relative to its top left corner.
+    Offset(Vec2),
+}
+
+impl From<Id> for ScrollTarget {
+    fn from(id: Id) -> Self {
+        ScrollTarget::Item(id)
+    }
+}
+
+impl From<Vec2> for ScrollTarget {
+    fn from(offset: Vec2) -> Self {
+        ScrollTarget::Offset(offset)
+    }
+}
+
+/// A scroll request waiting for its area to be declared.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ScrollRequest {
+    pub(crate) target: ScrollTarget,
+    pub(crate) align: Align,
+}
+
+/// Layout of a [`Ui::scroll_area`], from the previous frame as this frame isn't laid out yet.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ScrollAreaResponse {
+    /// Size of everything inside the area, including the parts scrolled out of view.
+    pub content_size: Vec2,
+    /// Rectangle (x0, y0, x1, y1) of the visible part on screen.
+    pub viewport: Vec4,
+    /// How far the content is scrolled this frame.
+    pub offset: Vec2,
+}
+
+/// Scroll offset along one axis that puts `start..start + size` of the content at `align`
+/// within a view of `view` pixels.
+fn aligned_offset(start: f32, size: f32, view: f32, align: Align) -> f32 {
+    match align {
+        Align::Start => start,
+        Align::Center => start + (size - view) * 0.5,
+        Align::End => start + size - view,
+    }
+}
+
+/// Start and length of the thumb on a scrollbar `track` pixels long, for a `view` pixels
+/// view of `content` pixels scrolled by `offset`. `None` when the content fits.
+fn thumb(track: f32, view: f32, content: f32, offset: f32) -> Option<(f32, f32)> {
+    if content <= view {
+        return None;
+    }
+    let length = (track * view / content).clamp(MIN_THUMB_LENGTH.min(track), track);
+    let start = (offset / (content - view)).clamp(0.0, 1.0) * (track - length);
+    Some((start, length))
+}
+
+/// How far the content scrolls when a thumb of `length` on a `track` pixels scrollbar is
+/// dragged by `delta` pixels.
+fn thumb_scroll(delta: f32, track: f32, length: f32, view: f32, content: f32) -> f32 {
+    let free = track - length;
+    if free <= 0.0 {
+        return 0.0;
+    }
+    delta * (content - view) / free
+}
+
+impl Ui {
+    /// A `size` region showing the content added by `f` scrolled by an offset kept for `id`.
+    /// Scrolled with the mouse wheel, the scrollbars and [`Ui::scroll_to`]. The scrollbars are
+    /// styled by the `scrollbar` and `scrollbar_thumb` rules.
+    pub fn scroll_area(&self, id: &str, size: Vec2, f: impl FnOnce(&Ui)) -> ScrollAreaResponse {
+        let area_id = self.id(id);
+        let content_id = self.id_index(id, 1);
+        let response = self.scroll_area_state(id, size);
+        let offset = response.offset;
+
+        // Along x for the horizontal scrollbar and y for the vertical one
+        let (view, content) = (size.to_array(), response.content_size.to_array());
+        let shown = [content[0] > view[0], content[1] > view[1]];
+        let tracks = [0, 1].map(|axis| {
+            let other = 1 - axis;
+            view[axis] - if shown[other] { SCROLLBAR_WIDTH } else { 0.0 }
+        });
+        let thumb_ids = [self.id_index(id, 2), self.id_index(id, 3)];
+        let thumbs =
+            [0, 1].map(|axis| thumb(tracks[axis], view[axis], content[axis], offset[axis]));
+
+        let mut decl = Declaration::new();
+        decl.id(area_id)
+            .layout()
+            .width(fixed!(size.x))
+            .height(fixed!(size.y))
+            .end();
+
+        let mut clip_decl = Declaration::new();
+        clip_decl
+            .layout()
+            .width(grow!())
+            .height(grow!())
+            .end()
+            .clip(true, true, Vector2::new(-offset.x, -offset.y));
+
+        let mut content_decl = Declaration::new();
+        content_decl
+            .id(content_id)
+            .layout()
+            .width(fit!())
+            .height(fit!())
+            .end();
+
+        self.with_layout(&decl, |ui| {
+            ui.with_layout(&clip_decl, |ui| ui.with_layout(&content_decl, f));
+            // After the content so the thumbs are hit before it
+            for axis in [0, 1] {
+                if let Some(thumb) = thumbs[axis] {
+                    ui.scrollbar(thumb_ids[axis], axis == 1, size, tracks[axis], thumb);
+                }
+            }
+        });
+
+        let mut next = offset - self.take_scroll(response.viewport) * WHEEL_STEP;
+        let pressed = self.input().pressed_id;
+        for axis in [0, 1] {
+            if let Some((_, length)) = thumbs[axis]
+                && pressed == Some(thumb_ids[axis].id.id)
+            {
+                let delta = self.input().mouse_delta[axis];
+                next[axis] += thumb_scroll(delta, tracks[axis], length, view[axis], content[axis]);
+            }
+        }
+        self.item_state(area_id).scroll =
+            next.clamp(Vec2::ZERO, (response.content_size - size).max(Vec2::ZERO));
+
+        response
+    }
+
+    /// Declares a scrollbar along the right or bottom edge of a scroll area of `size`, with
+    /// its thumb at `thumb` (start, length).
+    fn scrollbar(&self, thumb_id: Id, vertical: bool, size: Vec2, track: f32, thumb: (f32, f32)) {
+        let (start, length) = thumb;
+        let along = |length: f32, across: f32| {
+            if vertical {
+                (fixed!(across), fixed!(length))
+            } else {
+                (fixed!(length), fixed!(across))
+            }
+        };
+        let position = if vertical {
+            Vector2::new(size.x - SCROLLBAR_WIDTH, 0.0)
+        } else {
+            Vector2::new(0.0, size.y - SCROLLBAR_WIDTH)
+        };
+
+        let style = self.widget_style("scrollbar", Default::default());
+        let (width, height) = along(track, SCROLLBAR_WIDTH);
+        let mut decl = Declaration::new();
+        decl.layout()
+            .width(width)
+            .height(height)
+            .direction(if vertical {
+                LayoutDirection::TopToBottom
+            } else {
+                LayoutDirection::LeftToRight
+            })
+            .end()
+            .floating()
+            .attach_to(FloatingAttachToElement::Parent)
+            .offset(position)
+            .end();
+        apply_style(&mut decl, &style);
+
+        let (width, height) = along(start, SCROLLBAR_WIDTH);
+        let mut spacer = Declaration::new();
+        spacer.layout().width(width).height(height).end();
+
+        let thumb_style = self.animated_widget_style("scrollbar_thumb", thumb_id);
+        let (width, height) = along(length, SCROLLBAR_WIDTH);
+        let mut thumb_decl = Declaration::new();
+        thumb_decl
+            .id(thumb_id)
+            .layout()
+            .width(width)
+            .height(height)
+            .end();
+        apply_style(&mut thumb_decl, &thumb_style);
+
+        self.with_layout(&decl, |ui| {
+            ui.with_layout(&spacer, |_| {});
+            ui.with_layout(&thumb_decl, |_| {});
+        });
+    }
+
+    /// What [`Ui::scroll_area`] for `id` and `size` uses this frame, for lining up things
+    /// outside the area with its content before it is declared, such as a ruler above a
+    /// timeline.
+    pub fn scroll_area_state(&self, id: &str, size: Vec2) -> ScrollAreaResponse {
+        let area_id = self.id(id);
+        let content_id = self.id_index(id, 1);
+
+        let viewport = self.last_frame_rect(area_id).unwrap_or_default();
+        let content = self.last_frame_rect(content_id);
+        let content_size = content.map_or(Vec2::ZERO, |rect| rect.zw() - rect.xy());
+
+        let mut offset = self.item_state(area_id).scroll;
+        if let Some(request) = self.scroll_requests().remove(&area_id.id.id) {
+            let (start, target_size) = match request.target {
+                ScrollTarget::Offset(offset) => (Some(offset), Vec2::ZERO),
+                ScrollTarget::Item(item) => {
+                    let rect = self.last_frame_rect(item);
+                    let start = rect
+                        .zip(content)
+                        .map(|(rect, content)| rect.xy() - content.xy());
+                    (start, rect.map_or(Vec2::ZERO, |rect| rect.zw() - rect.xy()))
+                }
+            };
+            // Items that weren't laid out last frame can't be found, leave the offset alone
+            if let Some(start) = start {
+                offset = Vec2::new(
+                    aligned_offset(start.x, target_size.x, size.x, request.align),
+                    aligned_offset(start.y, target_size.y, size.y, request.align),
+                );
+            }
+        }
+        let offset = offset.clamp(Vec2::ZERO, (content_size - size).max(Vec2::ZERO));
+        self.item_state(area_id).scroll = offset;
+
+        ScrollAreaResponse {
+            content_size,
+            viewport,
+            offset,
+        }
+    }
+
+    /// Scrolls the scroll area `area` so `target` is placed at `align` in its view. Applied
+    /// when the area is declared, so after this frame's [`Ui::scroll_area`] call it takes
+    /// effect next frame.
+    pub fn scroll_to(&self, area: &str, target: impl Into<ScrollTarget>, align: Align) {
+        let area_id = self.id(area);
+        let request = ScrollRequest {
+            target: target.into(),
+            align,
+        };
+        self.scroll_requests().insert(area_id.id.id, request);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aligned_offset() {
+        // A 20 pixel item at 100 in a 50 pixel view
+        assert_eq!(aligned_offset(100.0, 20.0, 50.0, Align::Start), 100.0);
+        assert_eq!(aligned_offset(100.0, 20.0, 50.0, Align::Center), 85.0);
+        assert_eq!(aligned_offset(100.0, 20.0, 50.0, Align::End), 70.0);
+        // Offsets have no size
+        assert_eq!(aligned_offset(100.0, 0.0, 50.0, Align::Center), 75.0);
+    }
+
+    #[test]
+    fn test_thumb() {
+        assert_eq!(thumb(100.0, 100.0, 80.0, 0.0), None);
+        // Showing half of the content, scrolled to the end
+        assert_eq!(thumb(100.0, 100.0, 200.0, 0.0), Some((0.0, 50.0)));
+        assert_eq!(thumb(100.0, 100.0, 200.0, 100.0), Some((50.0, 50.0)));
+        // Long content keeps the thumb large enough to grab
+        assert_eq!(
+            thumb(100.0, 100.0, 100_000.0, 0.0),
+            Some((0.0, MIN_THUMB_LENGTH))
+        );
+
+        // Moving the thumb over all of its free space scrolls through all of the content
+        assert_eq!(thumb_scroll(50.0, 100.0, 50.0, 100.0, 200.0), 100.0);
+        assert_eq!(thumb_scroll(10.0, 100.0, 100.0, 100.0, 100.0), 0.0);
+    }
+}
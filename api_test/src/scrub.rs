@@ -0,0 +1,96 @@
+//! Reports the audio position under the mouse for widgets showing audio over time.
+//!
+//! Waveforms and clips map their width to a span of time. [`Ui::scrub`] turns the mouse
+//! position over such a widget into seconds and samples so a host can audition or scrub the
+//! audio without knowing how the widget was laid out.
+use crate::input::{Modifiers, MouseButton};
+use crate::ui::Ui;
+use clay_layout::id::Id;
+use glam::Vec4;
+
+/// The span of audio a widget shows across its width.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeSpan {
+    /// Seconds at the left edge.
+    pub start: f64,
+    /// Seconds from the left to the right edge.
+    pub duration: f64,
+    pub sample_rate: u32,
+}
+
+/// Position of the mouse over a widget, see [`Ui::scrub`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScrubPosition {
+    pub seconds: f64,
+    /// Sample at `seconds`, rounded to the nearest one.
+    pub sample: u64,
+    /// Position across the widget, 0 at the left edge and 1 at the right one.
+    pub fraction: f32,
+    /// True while a drag started on the widget goes on, as opposed to just hovering it.
+    pub dragging: bool,
+    pub modifiers: Modifiers,
+}
+
+/// Maps `x` to the position within `span` for a widget covering `rect`, clamped to its edges.
+pub(crate) fn position_in(rect: Vec4, x: f32, span: TimeSpan) -> (f32, f64, u64) {
+    let width = rect.z - rect.x;
+    let fraction = if width > 0.0 {
+        ((x - rect.x) / width).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let seconds = span.start + fraction as f64 * span.duration;
+    let sample = (seconds.max(0.0) * span.sample_rate as f64).round() as u64;
+    (fraction, seconds, sample)
+}
+
+impl Ui {
+    /// Where the mouse is over the widget `id` showing `span`, while it hovers the widget or
+    /// drags from it. A drag keeps reporting when the mouse leaves the widget, clamped to its
+    /// edges. Uses the layout of the previous frame.
+    pub fn scrub(&self, id: Id, span: TimeSpan) -> Option<ScrubPosition> {
+        let input = self.input();
+        let rect = self.last_frame_rect(id)?;
+        let item = self.item_state(id);
+
+        if input.button_pressed(MouseButton::Left) && item.was_hovered {
+            item.dragging = true;
+        } else if !input.button_down(MouseButton::Left) {
+            item.dragging = false;
+        }
+        if !item.dragging && !item.was_hovered {
+            return None;
+        }
+
+        let (fraction, seconds, sample) = position_in(rect, input.mouse_pos.x, span);
+        Some(ScrubPosition {
+            seconds,
+            sample,
+            fraction,
+            dragging: item.dragging,
+            modifiers: input.modifiers(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_in() {
+        let rect = Vec4::new(100.0, 0.0, 300.0, 50.0);
+        let span = TimeSpan {
+            start: 10.0,
+            duration: 4.0,
+            sample_rate: 48_000,
+        };
+
+        assert_eq!(position_in(rect, 200.0, span), (0.5, 12.0, 576_000));
+        // Clamped while dragging past the edges
+        assert_eq!(position_in(rect, 50.0, span), (0.0, 10.0, 480_000));
+        assert_eq!(position_in(rect, 400.0, span), (1.0, 14.0, 672_000));
+        let (_, _, sample) = position_in(rect, 100.5, span);
+        assert_eq!(sample, 480_480);
+    }
+}
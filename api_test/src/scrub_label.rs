@@ -0,0 +1,115 @@
+//! Value readouts changed by dragging over them, like the parameters of a synth or a 3D tool.
+//!
+//! [`Ui::scrub_label`] hides the pointer while it's dragged, so only the value is watched.
+//! Reaching a window edge asks the host to move the pointer to the other one with
+//! [`Ui::take_cursor_warp`], so a drag can go on for as long as it takes.
+use crate::input::{CursorIcon, MouseButton};
+use crate::style::WidgetState;
+use crate::ui::{Ui, apply_style, rgb};
+use crate::value_drag::{DragAxis, precision_step, round_to};
+use clay_layout::layout::{Alignment, LayoutAlignmentX, LayoutAlignmentY, LayoutDirection};
+use clay_layout::{Declaration, fit};
+use glam::Vec2;
+
+/// Distance from the window edges at which the pointer is wrapped around.
+const WRAP_MARGIN: f32 = 2.0;
+
+/// Where the pointer at `x` in a window `width` wide goes to wrap around, `None` when it's not
+/// at an edge.
+fn wrap_x(x: f32, width: f32) -> Option<f32> {
+    if width <= WRAP_MARGIN * 4.0 {
+        return None;
+    }
+    if x <= WRAP_MARGIN {
+        Some(width - WRAP_MARGIN * 2.0)
+    } else if x >= width - WRAP_MARGIN {
+        Some(WRAP_MARGIN * 2.0)
+    } else {
+        None
+    }
+}
+
+impl Ui {
+    /// Shows `text` with `value` after it and changes the value by `sensitivity` per pixel
+    /// while dragged horizontally, slower with shift held and in steps of 0.1 with Ctrl held,
+    /// see [`crate::value_drag::DragConfig`]. Returns true when the value changed this frame.
+    ///
+    /// Styled by the `scrub_label` rules, `:active` while dragged.
+    pub fn scrub_label(&self, text: &str, value: &mut f32, sensitivity: f32) -> bool {
+        let id = self.id(text);
+        let input = self.input();
+        let old_value = *value;
+        let dragging = input.button_down(MouseButton::Left) && input.pressed_id == Some(id.id.id);
+        let drag = self.value_drag(id, dragging, *value, DragAxis::Horizontal);
+        if let Some(drag) = drag {
+            let moved = drag.value + drag.delta * sensitivity;
+            *value = self.drag_to(id, moved, |value| round_to(value, precision_step(2)));
+
+            let (width, _) = self.window_size();
+            if let Some(x) = wrap_x(input.mouse_pos.x, width as f32) {
+                self.warp_cursor(Vec2::new(x, input.mouse_pos.y));
+            }
+        }
+
+        let hovered = self
+            .last_frame_rect(id)
+            .is_some_and(|rect| input.is_hovered(rect));
+        let style = self.widget_style(
+            "scrub_label",
+            WidgetState {
+                hovered,
+                active: dragging,
+                ..Default::default()
+            },
+        );
+        let color = style.text_color.unwrap_or(rgb(220, 220, 220));
+
+        let mut decl = Declaration::new();
+        decl.id(id)
+            .layout()
+            .width(fit!())
+            .height(fit!())
+            .child_gap(6)
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Left,
+                LayoutAlignmentY::Center,
+            ))
+            .direction(LayoutDirection::LeftToRight)
+            .end();
+        apply_style(&mut decl, &style);
+        self.set_cursor(
+            id,
+            if dragging {
+                CursorIcon::Hidden
+            } else {
+                CursorIcon::ResizeHorizontal
+            },
+        );
+
+        let readout = self.alloc_str(format!("{:.2}", *value));
+        self.with_layout(&decl, |ui| {
+            ui.text_element(text, color);
+            ui.text_element(readout, color);
+        });
+        if let Some(drag) = drag {
+            self.drag_readout(id, readout, *value - drag.start, 2);
+        }
+        *value != old_value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_x() {
+        assert_eq!(wrap_x(400.0, 800.0), None);
+        assert_eq!(wrap_x(1.0, 800.0), Some(796.0));
+        assert_eq!(wrap_x(799.0, 800.0), Some(4.0));
+        // Where it's wrapped to isn't an edge itself
+        assert_eq!(wrap_x(796.0, 800.0), None);
+        assert_eq!(wrap_x(4.0, 800.0), None);
+        assert_eq!(wrap_x(0.0, 6.0), None);
+    }
+}
@@ -0,0 +1,210 @@
+//! Search fields and lists filtered by their query.
+//!
+//! [`Ui::search_field`] is a text field with a button that clears it. [`Ui::filtered_list`]
+//! shows the items that contain the query, ignoring case, with the matching part highlighted.
+//! Rows keep their ids while the filter changes, so hover and selection follow the items.
+use crate::input::MouseButton;
+use crate::style::WidgetState;
+use crate::ui::{Ui, apply_style, rgb, rgba};
+use clay_layout::id::Id;
+use clay_layout::layout::{Alignment, LayoutAlignmentX, LayoutAlignmentY, LayoutDirection};
+use clay_layout::{Declaration, fit, fixed, grow};
+use std::ops::Range;
+
+/// Byte range of the first case-insensitive occurrence of `query` in `text`, an empty range at
+/// the start for an empty query.
+fn find_match(text: &str, query: &str) -> Option<Range<usize>> {
+    if query.is_empty() {
+        return Some(0..0);
+    }
+    text.char_indices().find_map(|(start, _)| {
+        let mut chars = text[start..].char_indices();
+        for wanted in query.chars() {
+            let (_, c) = chars.next()?;
+            if !c.to_lowercase().eq(wanted.to_lowercase()) {
+                return None;
+            }
+        }
+        let end = chars
+            .next()
+            .map_or(text.len(), |(offset, _)| start + offset);
+        Some(start..end)
+    })
+}
+
+impl Ui {
+    /// A text field for a search query with a button that clears it while there's a query.
+    /// Returns true when `query` changed this frame. The button is styled by the
+    /// `search_clear` rule, the field like [`Ui::input_text`].
+    pub fn search_field(&self, id: &str, query: &mut String) -> bool {
+        let clear_id = self.id_index(id, 1);
+        let mut changed = false;
+        if !query.is_empty() && self.input().was_clicked(clear_id.id.id, MouseButton::Left) {
+            query.clear();
+            changed = true;
+        }
+
+        let mut decl = Declaration::new();
+        decl.layout()
+            .width(grow!())
+            .height(fit!())
+            .child_gap(4)
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Left,
+                LayoutAlignmentY::Center,
+            ))
+            .direction(LayoutDirection::LeftToRight)
+            .end();
+        self.with_layout(&decl, |ui| {
+            changed |= ui.input_text(id, query);
+            if !query.is_empty() {
+                ui.search_clear(clear_id);
+            }
+        });
+        changed
+    }
+
+    /// Rows of the `items` containing `query`, in their order, with the match highlighted.
+    /// Clicking a row selects it, `selected` being an index into `items`. Returns true when the
+    /// selection changed.
+    ///
+    /// Rows are styled by the `list_item` rules like [`Ui::list_box`] and the highlight by the
+    /// `search_match` rule. Put the list in a [`Ui::scroll_area`] when it can get long.
+    pub fn filtered_list(
+        &self,
+        id: &str,
+        items: &[&str],
+        query: &str,
+        selected: &mut Option<usize>,
+    ) -> bool {
+        let input = self.input();
+        let mut changed = false;
+        let mut rows = Vec::new();
+        for (index, item) in items.iter().enumerate() {
+            let Some(range) = find_match(item, query) else {
+                continue;
+            };
+            let row_id = self.id_index(id, index as u32 + 1);
+            if input.was_clicked(row_id.id.id, MouseButton::Left) && *selected != Some(index) {
+                *selected = Some(index);
+                changed = true;
+            }
+            rows.push((index, row_id, range));
+        }
+
+        let mut decl = Declaration::new();
+        decl.id(self.id(id))
+            .layout()
+            .width(grow!())
+            .height(fit!())
+            .direction(LayoutDirection::TopToBottom)
+            .end();
+        self.with_layout(&decl, |ui| {
+            for (index, row_id, range) in rows {
+                ui.filtered_row(row_id, items[index], range, *selected == Some(index));
+            }
+        });
+        changed
+    }
+
+    /// The button of a search field that clears it.
+    fn search_clear(&self, id: Id) {
+        let hovered = self
+            .last_frame_rect(id)
+            .is_some_and(|rect| self.input().is_hovered(rect));
+        let style = self.widget_style(
+            "search_clear",
+            WidgetState {
+                hovered,
+                ..Default::default()
+            },
+        );
+        let color = style.text_color.unwrap_or(rgb(160, 160, 160));
+        let size = (self.font_size() as f32 * 1.6).round();
+
+        let mut decl = Declaration::new();
+        decl.id(id)
+            .layout()
+            .width(fixed!(size))
+            .height(fixed!(size))
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Center,
+                LayoutAlignmentY::Center,
+            ))
+            .end();
+        apply_style(&mut decl, &style);
+        self.with_layout(&decl, |ui| ui.text_element("x", color));
+    }
+
+    /// A row of a filtered list showing `label` with `highlight` marked.
+    fn filtered_row(&self, id: Id, label: &str, highlight: Range<usize>, selected: bool) {
+        let hovered = self
+            .last_frame_rect(id)
+            .is_some_and(|rect| self.input().is_hovered(rect));
+        if selected {
+            self.push_pending_class("selected");
+        }
+        let style = self.widget_style(
+            "list_item",
+            WidgetState {
+                hovered,
+                ..Default::default()
+            },
+        );
+        let color = style.text_color.unwrap_or(rgb(220, 220, 220));
+        let highlight_style = self.widget_style("search_match", WidgetState::default());
+        let highlight_color = highlight_style.text_color.unwrap_or(rgb(255, 255, 255));
+
+        let mut decl = Declaration::new();
+        decl.id(id)
+            .layout()
+            .width(grow!())
+            .height(fit!())
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Left,
+                LayoutAlignmentY::Center,
+            ))
+            .direction(LayoutDirection::LeftToRight)
+            .end();
+        apply_style(&mut decl, &style);
+
+        let mut highlight_decl = Declaration::new();
+        highlight_decl
+            .layout()
+            .width(fit!())
+            .height(fit!())
+            .end()
+            .background_color(rgba(100, 150, 255, 96));
+        apply_style(&mut highlight_decl, &highlight_style);
+
+        self.with_layout(&decl, |ui| {
+            if !label[..highlight.start].is_empty() {
+                ui.text_element(&label[..highlight.start], color);
+            }
+            if !highlight.is_empty() {
+                ui.with_layout(&highlight_decl, |ui| {
+                    ui.text_element(&label[highlight.clone()], highlight_color)
+                });
+            }
+            if !label[highlight.end..].is_empty() {
+                ui.text_element(&label[highlight.end..], color);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_match() {
+        assert_eq!(find_match("Reverb", ""), Some(0..0));
+        assert_eq!(find_match("Reverb", "VER"), Some(2..5));
+        assert_eq!(find_match("Reverb", "verbs"), None);
+        assert_eq!(find_match("Delay", "reverb"), None);
+        // Offsets are in bytes of the original text
+        assert_eq!(find_match("Ärger Bass", "BASS"), Some(7..11));
+        assert_eq!(find_match("ÄRGER", "är"), Some(0..3));
+    }
+}
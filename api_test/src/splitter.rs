@@ -0,0 +1,170 @@
+//! Panes split by a divider that can be dragged to resize them.
+//!
+//! [`Ui::split_horizontal`] puts two panes side by side and [`Ui::split_vertical`] one above
+//! the other. The split is a ratio owned by the caller, the size of the first pane as a fraction
+//! of the space, so it persists for as long as the caller keeps it.
+use crate::input::{CursorIcon, MouseButton};
+use crate::style::WidgetState;
+use crate::ui::{Ui, apply_style, rgb};
+use clay_layout::layout::{LayoutDirection, Sizing};
+use clay_layout::math::Vector2;
+use clay_layout::{Declaration, fixed, grow};
+
+/// Thickness of the divider between the panes.
+pub const DIVIDER_SIZE: f32 = 4.0;
+/// Smallest size a pane can be dragged to.
+const MIN_PANE_SIZE: f32 = 32.0;
+
+/// Ratio for the divider grabbed in its middle at `mouse`, in a split starting at `start` that
+/// is `size` long. Both panes are kept at least [`MIN_PANE_SIZE`] when there's room for it.
+fn drag_ratio(mouse: f32, start: f32, size: f32) -> f32 {
+    if size <= 0.0 {
+        return 0.5;
+    }
+    let max = (size - DIVIDER_SIZE - MIN_PANE_SIZE).max(MIN_PANE_SIZE);
+    let first = (mouse - start - DIVIDER_SIZE * 0.5).clamp(MIN_PANE_SIZE, max);
+    (first / size).clamp(0.0, 1.0)
+}
+
+impl Ui {
+    /// Splits the space of the current element into a `left` and a `right` pane, the left one
+    /// taking `ratio` of the width. Returns true when the divider was dragged this frame.
+    pub fn split_horizontal(
+        &self,
+        id: &str,
+        ratio: &mut f32,
+        left: impl FnOnce(&Ui),
+        right: impl FnOnce(&Ui),
+    ) -> bool {
+        self.split(id, ratio, LayoutDirection::LeftToRight, left, right)
+    }
+
+    /// Splits the space of the current element into a `top` and a `bottom` pane, the top one
+    /// taking `ratio` of the height. Returns true when the divider was dragged this frame.
+    pub fn split_vertical(
+        &self,
+        id: &str,
+        ratio: &mut f32,
+        top: impl FnOnce(&Ui),
+        bottom: impl FnOnce(&Ui),
+    ) -> bool {
+        self.split(id, ratio, LayoutDirection::TopToBottom, top, bottom)
+    }
+
+    /// The divider is styled by the `splitter` rule.
+    fn split(
+        &self,
+        id: &str,
+        ratio: &mut f32,
+        direction: LayoutDirection,
+        first: impl FnOnce(&Ui),
+        second: impl FnOnce(&Ui),
+    ) -> bool {
+        let split_id = self.id(id);
+        let divider_id = self.id_index(id, 1);
+        let horizontal = matches!(direction, LayoutDirection::LeftToRight);
+        let input = self.input();
+
+        let old_ratio = *ratio;
+        let dragging =
+            input.pressed_id == Some(divider_id.id.id) && input.button_down(MouseButton::Left);
+        if dragging && let Some(rect) = self.last_frame_rect(split_id) {
+            *ratio = if horizontal {
+                drag_ratio(input.mouse_pos.x, rect.x, rect.z - rect.x)
+            } else {
+                drag_ratio(input.mouse_pos.y, rect.y, rect.w - rect.y)
+            };
+        }
+        *ratio = ratio.clamp(0.0, 1.0);
+
+        let hovered = self
+            .last_frame_rect(divider_id)
+            .is_some_and(|rect| input.is_hovered(rect));
+        let style = self.widget_style(
+            "splitter",
+            WidgetState {
+                hovered,
+                active: dragging,
+                ..Default::default()
+            },
+        );
+        self.set_cursor(
+            divider_id,
+            if horizontal {
+                CursorIcon::ResizeHorizontal
+            } else {
+                CursorIcon::ResizeVertical
+            },
+        );
+
+        let mut decl = Declaration::new();
+        decl.id(split_id)
+            .layout()
+            .width(grow!())
+            .height(grow!())
+            .direction(direction)
+            .end();
+
+        // Percent sizes are of the whole split as there's no child gap, so the ratio is too
+        let (first_width, first_height) = if horizontal {
+            (Sizing::Percent(*ratio), grow!())
+        } else {
+            (grow!(), Sizing::Percent(*ratio))
+        };
+        let mut first_decl = Declaration::new();
+        first_decl
+            .layout()
+            .width(first_width)
+            .height(first_height)
+            .end()
+            .clip(true, true, Vector2::new(0.0, 0.0));
+
+        let (divider_width, divider_height) = if horizontal {
+            (fixed!(DIVIDER_SIZE), grow!())
+        } else {
+            (grow!(), fixed!(DIVIDER_SIZE))
+        };
+        let mut divider_decl = Declaration::new();
+        divider_decl
+            .id(divider_id)
+            .layout()
+            .width(divider_width)
+            .height(divider_height)
+            .end()
+            .background_color(rgb(30, 30, 30));
+        apply_style(&mut divider_decl, &style);
+
+        let mut second_decl = Declaration::new();
+        second_decl
+            .layout()
+            .width(grow!())
+            .height(grow!())
+            .end()
+            .clip(true, true, Vector2::new(0.0, 0.0));
+
+        self.with_layout(&decl, |ui| {
+            ui.with_layout(&first_decl, first);
+            ui.with_layout(&divider_decl, |_| {});
+            ui.with_layout(&second_decl, second);
+        });
+        *ratio != old_ratio
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drag_ratio() {
+        assert_eq!(drag_ratio(102.0, 0.0, 400.0), 0.25);
+        assert_eq!(drag_ratio(202.0, 100.0, 400.0), 0.25);
+        // Panes don't get smaller than the minimum
+        assert_eq!(drag_ratio(0.0, 0.0, 400.0), MIN_PANE_SIZE / 400.0);
+        assert_eq!(
+            drag_ratio(400.0, 0.0, 400.0),
+            (400.0 - DIVIDER_SIZE - MIN_PANE_SIZE) / 400.0
+        );
+        assert_eq!(drag_ratio(10.0, 0.0, 0.0), 0.5);
+    }
+}
@@ -0,0 +1,137 @@
+//! A status bar pinned to the bottom of the window.
+//!
+//! [`Ui::status_bar`] hands out a left, a center and a right [`StatusSection`] to fill with text
+//! and widgets, for things like the transport position, CPU usage and messages. The bar floats
+//! at the bottom of the root layout. Where it's declared it leaves a gap of its height, so
+//! declared last in a top to bottom layout nothing is hidden below it.
+use crate::style::WidgetState;
+use crate::ui::{Ui, apply_style, rgb};
+use clay_layout::color::Color as ClayColor;
+use clay_layout::elements::{FloatingAttachPointType, FloatingAttachToElement};
+use clay_layout::layout::{Alignment, LayoutAlignmentX, LayoutAlignmentY, LayoutDirection};
+use clay_layout::{Declaration, fit, fixed, grow};
+
+/// Below windows, so they can be moved over the bar.
+const STATUS_BAR_Z_INDEX: i16 = 400;
+/// Space between the items of a section.
+const ITEM_GAP: u16 = 12;
+
+enum StatusItem<'a> {
+    Text(String),
+    Widget(Box<dyn FnOnce(&Ui) + 'a>),
+}
+
+/// A section of the [`Ui::status_bar`], its items are shown left to right.
+#[derive(Default)]
+pub struct StatusSection<'a> {
+    items: Vec<StatusItem<'a>>,
+}
+
+impl<'a> StatusSection<'a> {
+    /// Adds `text` in the text color of the bar.
+    pub fn text(&mut self, text: impl Into<String>) -> &mut Self {
+        self.items.push(StatusItem::Text(text.into()));
+        self
+    }
+
+    /// Adds the widgets declared by `f`.
+    pub fn add(&mut self, f: impl FnOnce(&Ui) + 'a) -> &mut Self {
+        self.items.push(StatusItem::Widget(Box::new(f)));
+        self
+    }
+}
+
+impl Ui {
+    /// Shows a bar along the bottom of the window with the sections filled by `f`. The left
+    /// section is aligned to the left edge, the right one to the right edge and the center one
+    /// stays in the middle while they leave room for it.
+    ///
+    /// Styled by the `status_bar` rule.
+    pub fn status_bar<'a>(
+        &self,
+        f: impl FnOnce(&mut StatusSection<'a>, &mut StatusSection<'a>, &mut StatusSection<'a>),
+    ) {
+        let mut sections: [StatusSection; 3] = Default::default();
+        let [left, center, right] = &mut sections;
+        f(left, center, right);
+
+        let bar_id = self.id("status_bar");
+        let style = self.widget_style("status_bar", WidgetState::default());
+        let color = style.text_color.unwrap_or(rgb(180, 180, 180));
+        let (width, _) = self.window_size();
+
+        // Keeps what's declared before the bar from going below it
+        let height = self
+            .last_frame_rect(bar_id)
+            .map_or(0.0, |rect| rect.w - rect.y);
+        let mut gap = Declaration::new();
+        gap.layout().width(grow!()).height(fixed!(height)).end();
+        self.with_layout(&gap, |_| {});
+
+        let mut decl = Declaration::new();
+        decl.id(bar_id)
+            .layout()
+            .width(fixed!(width as f32))
+            .height(fit!())
+            .direction(LayoutDirection::LeftToRight)
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Left,
+                LayoutAlignmentY::Center,
+            ))
+            .end()
+            .floating()
+            .attach_to(FloatingAttachToElement::Root)
+            .attach_points(
+                FloatingAttachPointType::LeftBottom,
+                FloatingAttachPointType::LeftBottom,
+            )
+            .z_index(STATUS_BAR_Z_INDEX)
+            .end();
+        apply_style(&mut decl, &style);
+
+        let alignments = [
+            LayoutAlignmentX::Left,
+            LayoutAlignmentX::Center,
+            LayoutAlignmentX::Right,
+        ];
+        self.with_layout(&decl, |ui| {
+            for (index, (section, alignment)) in sections.into_iter().zip(alignments).enumerate() {
+                ui.status_section(index, section, alignment, color);
+            }
+        });
+    }
+
+    /// One of the sections of the status bar. The outer ones grow to split the width left by
+    /// the center one between them.
+    fn status_section(
+        &self,
+        index: usize,
+        section: StatusSection<'_>,
+        alignment: LayoutAlignmentX,
+        color: ClayColor,
+    ) {
+        let mut decl = Declaration::new();
+        {
+            let mut layout = decl.layout();
+            if index == 1 {
+                layout.width(fit!());
+            } else {
+                layout.width(grow!());
+            }
+            layout
+                .height(fit!())
+                .child_gap(ITEM_GAP)
+                .child_alignment(Alignment::new(alignment, LayoutAlignmentY::Center))
+                .direction(LayoutDirection::LeftToRight)
+                .end();
+        }
+        self.with_layout(&decl, |ui| {
+            for item in section.items {
+                match item {
+                    StatusItem::Text(text) => ui.text_element(ui.alloc_str(text), color),
+                    StatusItem::Widget(f) => f(ui),
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,153 @@
+//! Steppers: an integer between a `-` and a `+` button.
+//!
+//! [`Ui::stepper`] changes the value by one step per click. Holding a button down steps again
+//! after [`REPEAT_DELAY`] and then every [`REPEAT_INTERVAL`] while the mouse stays over it,
+//! which suits small ranges such as octaves or beats per bar better than a slider.
+use crate::input::MouseButton;
+use crate::style::WidgetState;
+use crate::ui::{Ui, apply_style, rgb};
+use clay_layout::id::Id;
+use clay_layout::layout::{Alignment, LayoutAlignmentX, LayoutAlignmentY, LayoutDirection};
+use clay_layout::{Declaration, fit, fixed};
+use std::ops::RangeInclusive;
+
+/// Seconds a button is held before it starts repeating.
+const REPEAT_DELAY: f32 = 0.4;
+/// Seconds between repeats once they started.
+const REPEAT_INTERVAL: f32 = 0.08;
+
+/// Number of repeats a button held from `before` to `after` seconds fires.
+fn repeats(before: f32, after: f32) -> u32 {
+    let fired = |held: f32| {
+        if held < REPEAT_DELAY {
+            0
+        } else {
+            ((held - REPEAT_DELAY) / REPEAT_INTERVAL) as u32 + 1
+        }
+    };
+    fired(after) - fired(before)
+}
+
+/// `value` moved by `steps` steps of `step`, clamped to `range`.
+fn step_value(value: i32, step: i32, steps: i32, range: &RangeInclusive<i32>) -> i32 {
+    value
+        .saturating_add(step.saturating_mul(steps))
+        .clamp(*range.start(), *range.end())
+}
+
+impl Ui {
+    /// Shows `label`, then `value` between buttons that lower and raise it by `step` within
+    /// `range`. Returns true when the value changed this frame. The buttons are styled by the
+    /// `stepper_button` rule and are disabled at the ends of the range.
+    pub fn stepper(
+        &self,
+        label: &str,
+        value: &mut i32,
+        step: i32,
+        range: RangeInclusive<i32>,
+    ) -> bool {
+        let old_value = *value;
+        let id = self.id(label);
+        let size = (self.font_size() as f32 * 1.6).round();
+        let color = self
+            .widget_style("stepper", WidgetState::default())
+            .text_color
+            .unwrap_or(rgb(220, 220, 220));
+        let text = self.alloc_str(format!("{}: ", label));
+        let number = self.alloc_str(format!("{}", value));
+
+        let mut decl = Declaration::new();
+        decl.id(id)
+            .layout()
+            .width(fit!())
+            .height(fit!())
+            .child_gap(4)
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Left,
+                LayoutAlignmentY::Center,
+            ))
+            .direction(LayoutDirection::LeftToRight)
+            .end();
+        self.with_layout(&decl, |ui| {
+            ui.text_element(text, color);
+            let down =
+                ui.stepper_button(ui.id_index(label, 1), "-", *value <= *range.start(), size);
+            ui.text_element(number, color);
+            let up = ui.stepper_button(ui.id_index(label, 2), "+", *value >= *range.end(), size);
+            *value = step_value(*value, step, up as i32 - down as i32, &range);
+        });
+        *value != old_value
+    }
+
+    /// A square button of a stepper showing `text`. Returns how many steps it fires this frame:
+    /// one when pressed and the repeats while it's held.
+    fn stepper_button(&self, id: Id, text: &str, disabled: bool, size: f32) -> u32 {
+        let input = self.input();
+        let hovered = self
+            .last_frame_rect(id)
+            .is_some_and(|rect| input.is_hovered(rect));
+        let held = input.pressed_id == Some(id.id.id);
+        let pressed = held && input.button_pressed(MouseButton::Left);
+
+        let dt = self.delta_time();
+        let item = self.item_state(id);
+        let before = if pressed { 0.0 } else { item.hold_time };
+        item.hold_time = if held { before + dt } else { 0.0 };
+        let steps = match (disabled, pressed, held && hovered) {
+            (true, _, _) => 0,
+            (false, true, _) => 1,
+            (false, false, true) => repeats(before, item.hold_time),
+            (false, false, false) => 0,
+        };
+
+        let style = self.widget_style(
+            "stepper_button",
+            WidgetState {
+                hovered,
+                active: held && hovered,
+                disabled,
+            },
+        );
+        let color = style.text_color.unwrap_or(if disabled {
+            rgb(110, 110, 110)
+        } else {
+            rgb(220, 220, 220)
+        });
+
+        let mut decl = Declaration::new();
+        decl.id(id)
+            .layout()
+            .width(fixed!(size))
+            .height(fixed!(size))
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Center,
+                LayoutAlignmentY::Center,
+            ))
+            .end();
+        apply_style(&mut decl, &style);
+        self.with_layout(&decl, |ui| ui.text_element(text, color));
+        steps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repeats() {
+        assert_eq!(repeats(0.0, REPEAT_DELAY - 0.01), 0);
+        assert_eq!(repeats(REPEAT_DELAY - 0.01, REPEAT_DELAY + 0.01), 1);
+        assert_eq!(repeats(REPEAT_DELAY + 0.01, REPEAT_DELAY + 0.02), 0);
+        // A long frame fires all the repeats it covered
+        assert_eq!(repeats(0.0, REPEAT_DELAY + REPEAT_INTERVAL * 3.5), 4);
+    }
+
+    #[test]
+    fn test_step_value() {
+        assert_eq!(step_value(4, 1, 1, &(1..=8)), 5);
+        assert_eq!(step_value(4, 3, -3, &(1..=8)), 1);
+        assert_eq!(step_value(7, 2, 1, &(1..=8)), 8);
+        assert_eq!(step_value(i32::MAX, 1, 1, &(0..=i32::MAX)), i32::MAX);
+    }
+}
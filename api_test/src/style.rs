@@ -0,0 +1,705 @@
+//! Stylesheets that map widget names, classes and interaction states to visual properties.
+//!
+//! Widgets look up their style by name (`button`, `label`, ...) together with the classes set
+//! through [`Ui::class`] and their current interaction state, so the same widget code can take
+//! different appearances.
+//!
+//! ```text
+//! // Comments work like in panel scripts
+//! button { background: #3c3c3c; text_color: #dcdcdc; radius: 4 }
+//! button:hover { background: #4a4a4a }
+//! .danger { background: #b43c3c }
+//! button.danger:active { background: #8c2828 }
+//! *:disabled { text_color: #808080 }
+//! ```
+//!
+//! Selectors are `widget`, `*` or nothing, followed by any number of `.class` and `:state`
+//! parts (`hover`, `active` and `disabled`). When several rules set the same property the most
+//! specific selector wins (each class and state counts more than the widget name), and rules
+//! with equal specificity are applied in source order.
+//!
+//! Text sizes come from the `heading`, `body`, `caption` and `monospace` rules, see
+//! [`TextRole`]. Without a `font_size` they follow [`Ui::set_default_font_size`].
+use crate::area_props::parse_hex_color;
+use crate::file_watch::WatchedFile;
+use crate::internal_error::{InternalError, InternalResult};
+use crate::ui::Ui;
+use clay_layout::color::Color as ClayColor;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Theme used until the application sets its own stylesheet.
+pub const DEFAULT_STYLESHEET: &str = r#"
+button { background: #3c3c3c; text_color: #dcdcdc; border_color: #505050; radius: 4; padding: 8 }
+button:hover { background: #4a4a4a }
+button:active { background: #2d2d2d }
+button.selected { background: #32508c; text_color: #ffffff }
+button.selected:hover { background: #3c5fa0 }
+button.danger { background: #a03232; border_color: #c04040 }
+button.danger:hover { background: #b43c3c }
+button.danger:active { background: #822828 }
+checkbox { text_color: #dcdcdc; border_color: #787878; background: #6496ff; radius: 2 }
+checkbox:hover { border_color: #b4b4b4 }
+radio { text_color: #dcdcdc; border_color: #787878; background: #6496ff }
+radio:hover { border_color: #b4b4b4 }
+knob { text_color: #dcdcdc; border_color: #464646; background: #6496ff }
+knob:hover { border_color: #5a5a5a }
+knob:active { background: #82aaff }
+fader { background: #1e1e1e; text_color: #dcdcdc; border_color: #505050; radius: 3 }
+fader_cap { background: #787878; border_color: #a0a0a0; radius: 2 }
+fader_cap:hover { background: #8c8c8c }
+fader_cap:active { background: #6496ff; border_color: #82aaff }
+meter { background: #191919 }
+meter_bridge { text_color: #b4b4b4; padding: 4 }
+pan { background: #1e1e1e; text_color: #dcdcdc; border_color: #505050; radius: 3 }
+pan_speaker { background: #6496ff }
+pan_source { background: #c8c8c8 }
+pan_source:hover { background: #e6e6e6 }
+pan_source:active { background: #82aaff }
+keyboard { border_color: #303030 }
+keyboard:active { border_color: #6496ff }
+keyboard_key { background: #f0f0f0; border_color: #505050 }
+keyboard_key:active { background: #82aaff }
+keyboard_black_key { background: #141414; radius: 2 }
+keyboard_black_key:active { background: #4a78d8 }
+piano_roll { background: #18181e }
+piano_roll_note { background: #6496ff; border_color: #3c64c8; border: 1; radius: 2 }
+piano_roll_note:hover { background: #82aaff }
+piano_roll_note:active { background: #ffc864; border_color: #c8963c }
+piano_roll_selection { background: #6496ff33; border_color: #6496ff; border: 1 }
+clip_grid { text_color: #b4b4b4; padding: 4 }
+clip_slot { background: #26262c; text_color: #dcdcdc; border_color: #dcdcdc; radius: 3; padding: 4 }
+clip_slot:hover { background: #303038 }
+clip_slot:active { border: 1 }
+clip_slot.loaded { background: #3a4660 }
+clip_slot.loaded:hover { background: #44527a }
+clip_slot.playing { background: #3c7850; text_color: #ffffff }
+clip_slot.playing:hover { background: #46885a }
+clip_slot.queued { background: #8c7840 }
+clip_progress { background: #ffffff; border_color: #00000060 }
+clip_scene { background: #323232; text_color: #dcdcdc; radius: 3; padding: 4 }
+clip_scene:hover { background: #3c3c3c }
+clip_scene:active { background: #2d2d2d }
+transport_button { background: #3c3c3c; text_color: #dcdcdc; border_color: #505050; radius: 4 }
+transport_button:hover { background: #4a4a4a }
+transport_button:active { background: #2d2d2d }
+transport_button.playing { background: #3c7850; text_color: #ffffff }
+transport_button.playing:hover { background: #46885a }
+transport_button.playing:active { background: #32643f }
+transport_button.recording { background: #a03232; text_color: #ffffff }
+transport_button.recording:hover { background: #b43c3c }
+transport_button.recording:active { background: #822828 }
+transport_button.looping { background: #32508c; text_color: #ffffff }
+transport_button.looping:hover { background: #3c5fa0 }
+transport_button.looping:active { background: #2a4478 }
+spinner { background: #6496ff }
+tree_node { background: #262626; text_color: #dcdcdc; border_color: #a0a0a0; radius: 3; padding: 4 }
+tree_node:hover { background: #303030 }
+collapsing_header { background: #323232; text_color: #dcdcdc; border_color: #b4b4b4; radius: 3; padding: 6 }
+collapsing_header:hover { background: #3c3c3c }
+accordion_header { background: #2e2e34; text_color: #dcdcdc; border_color: #b4b4b4; radius: 3; padding: 6 }
+accordion_header:hover { background: #3a3a42 }
+modal { background: #2d2d2d; text_color: #dcdcdc; border_color: #5a5a5a; border: 1; radius: 6; padding: 16 }
+modal_backdrop { background: #0000008c }
+popup { background: #2a2a2a; text_color: #dcdcdc; border_color: #5a5a5a; border: 1; radius: 4; padding: 4 }
+list_box { background: #1e1e1e; border_color: #505050; border: 1; separator: 1; radius: 3 }
+list_box:active { border_color: #6496ff }
+list_item { text_color: #dcdcdc; padding: 4 }
+list_item:hover { background: #2d2d2d }
+list_item.selected { background: #32508c; text_color: #ffffff }
+list_item.selected:hover { background: #3c5fa0 }
+file_tree_row { text_color: #dcdcdc; radius: 3; padding: 3 }
+file_tree_row:hover { background: #2d2d2d }
+file_tree_row.selected { background: #32508c; text_color: #ffffff }
+file_tree_row.selected:hover { background: #3c5fa0 }
+file_tree_icon { text_color: #aaaaaa }
+file_tree_icon.folder { text_color: #dcb45a }
+table { background: #1e1e1e; border_color: #505050; border: 1; radius: 3 }
+table_header { background: #2d2d2d; text_color: #dcdcdc }
+table_header:hover { background: #383838 }
+table_row { text_color: #dcdcdc }
+table_row.striped { background: #242424 }
+table_row:hover { background: #2d3a52 }
+canvas { background: #161616; border_color: #505050; border: 1 }
+scrollbar { background: #00000040; radius: 4 }
+scrollbar_thumb { background: #5a5a5a; radius: 4 }
+scrollbar_thumb:hover { background: #6e6e6e }
+scrollbar_thumb:active { background: #828282 }
+minimap { background: #16161c; border_color: #3c3c3c; border: 1 }
+minimap_viewport { background: #ffffff18; border_color: #b4b4b4; border: 1 }
+minimap_viewport:hover { background: #ffffff28 }
+minimap_viewport:active { background: #ffffff38; border_color: #ffffff }
+menu_bar { background: #262626; padding: 2 }
+menu_header { text_color: #dcdcdc; radius: 3; padding: 6 }
+menu_header:hover { background: #3c3c3c }
+menu_header:active { background: #32508c; text_color: #ffffff }
+menu { background: #2a2a2a; text_color: #dcdcdc; border_color: #5a5a5a; border: 1; separator: 1; radius: 4; padding: 4 }
+menu_item { text_color: #dcdcdc; radius: 3; padding: 6 }
+menu_item:hover { background: #32508c; text_color: #ffffff }
+radial_menu { background: #2a2a2ae6; border_color: #5a5a5a; border: 1 }
+radial_menu_item { text_color: #dcdcdc; radius: 3; padding: 5 }
+radial_menu_item:hover { background: #32508c; text_color: #ffffff }
+status_bar { background: #262626; text_color: #b4b4b4; padding: 4 }
+toolbar_overflow { background: #2a2a2a; border_color: #5a5a5a; border: 1; radius: 4; padding: 4 }
+toolbar_overflow_button { text_color: #dcdcdc; radius: 3 }
+toolbar_overflow_button:hover { background: #3c3c3c }
+picker_field { background: #2a2a2a; text_color: #dcdcdc; border_color: #505050; radius: 3; padding: 6 }
+picker_field:hover { background: #343434 }
+calendar_header { text_color: #969696 }
+calendar_cell { text_color: #dcdcdc; radius: 3 }
+calendar_cell:hover { background: #3c3c3c }
+calendar_cell.selected { background: #32508c; text_color: #ffffff }
+tooltip { background: #1a1a1a; text_color: #dcdcdc; border_color: #5a5a5a; border: 1; radius: 4; padding: 6 }
+drag_readout { background: #1a1a1a; text_color: #dcdcdc; border_color: #5a5a5a; border: 1; radius: 3; padding: 3 }
+toast { background: #2a2a2a; text_color: #dcdcdc; border_color: #5a5a5a; border: 1; radius: 4; padding: 8 }
+toast:hover { background: #333333 }
+toast.warning { border_color: #d0a030 }
+toast.error { border_color: #d04040 }
+rating_star { text_color: #f0b428 }
+rating_star:hover { text_color: #ffd764 }
+rating_star.empty { text_color: #505050 }
+ruler { background: #3c3c3c; text_color: #c8c8c8; border_color: #8c8c8c }
+drag_value { background: #2a2a2a; text_color: #dcdcdc; radius: 3; padding: 6 }
+drag_value:hover { background: #343434 }
+drag_value:active { background: #3c3c3c }
+scrub_label { text_color: #dcdcdc; radius: 3; padding: 4 }
+scrub_label:hover { background: #2d2d2d }
+scrub_label:active { background: #32508c; text_color: #ffffff }
+link { text_color: #6496ff }
+link:hover { text_color: #8cb4ff }
+link:active { text_color: #4a78d8 }
+input_text { background: #1e1e1e; text_color: #dcdcdc; border_color: #505050; border: 1; radius: 3; padding: 6 }
+input_text:hover { border_color: #707070 }
+tag_chip { background: #32508c; text_color: #ffffff; radius: 3; padding: 4 }
+tag_remove { text_color: #c8c8c8; radius: 3 }
+tag_remove:hover { background: #a03232; text_color: #ffffff }
+text_edit { background: #1e1e1e; text_color: #dcdcdc; border_color: #505050; border: 1; radius: 3; padding: 6 }
+text_edit:hover { border_color: #707070 }
+splitter { background: #1e1e1e }
+splitter:hover { background: #505050 }
+splitter:active { background: #6496ff }
+window { background: #282828; text_color: #dcdcdc; border_color: #5a5a5a; border: 1; radius: 4; padding: 8 }
+window_title { background: #37373c; text_color: #dcdcdc; padding: 6 }
+window_close { text_color: #a0a0a0; radius: 3 }
+window_close:hover { background: #a03232; text_color: #ffffff }
+window_grip { background: #5a5a5a40 }
+window_grip:hover { background: #6496ff }
+*:disabled { text_color: #787878 }
+"#;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("line {line}: {message}")]
+pub struct StyleError {
+    pub line: u32,
+    pub message: String,
+}
+
+impl StyleError {
+    fn new(line: u32, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+type StyleResult<T> = Result<T, StyleError>;
+
+/// Kinds of text with their own size in the theme, set by the `font_size` of the rule with the
+/// role's name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextRole {
+    Heading,
+    /// Labels and the text of widgets.
+    Body,
+    Caption,
+    /// Code and numbers that line up, drawn with the [`crate::ui::FontStyle::Monospace`] font.
+    Monospace,
+}
+
+impl TextRole {
+    pub fn name(self) -> &'static str {
+        match self {
+            TextRole::Heading => "heading",
+            TextRole::Body => "body",
+            TextRole::Caption => "caption",
+            TextRole::Monospace => "monospace",
+        }
+    }
+
+    /// Size relative to the default font size when the theme doesn't set one.
+    fn scale(self) -> f32 {
+        match self {
+            TextRole::Heading => 1.5,
+            TextRole::Body => 1.0,
+            TextRole::Caption => 0.75,
+            TextRole::Monospace => 0.9,
+        }
+    }
+
+    /// Font size of the role in pixels, `style` being its rule.
+    pub(crate) fn font_size(self, style: &Style, default_size: u32) -> u32 {
+        style.font_size.map_or_else(
+            || (default_size as f32 * self.scale()).round() as u32,
+            u32::from,
+        )
+    }
+}
+
+/// Interaction state of a widget, matched by `:hover`, `:active` and `:disabled`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WidgetState {
+    pub hovered: bool,
+    pub active: bool,
+    pub disabled: bool,
+}
+
+/// Visual properties set by a stylesheet. Unset properties fall back to the widget defaults.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Style {
+    pub background: Option<ClayColor>,
+    pub text_color: Option<ClayColor>,
+    pub border_color: Option<ClayColor>,
+    pub border_width: Option<u16>,
+    /// Width of the lines drawn between the children, in the border color.
+    pub separator: Option<u16>,
+    pub radius: Option<f32>,
+    pub padding: Option<u16>,
+    /// In pixels.
+    pub font_size: Option<u16>,
+}
+
+impl Style {
+    /// Overrides the properties that are set in `other`.
+    pub fn merge(&mut self, other: &Style) {
+        self.background = other.background.or(self.background);
+        self.text_color = other.text_color.or(self.text_color);
+        self.border_color = other.border_color.or(self.border_color);
+        self.border_width = other.border_width.or(self.border_width);
+        self.separator = other.separator.or(self.separator);
+        self.radius = other.radius.or(self.radius);
+        self.padding = other.padding.or(self.padding);
+        self.font_size = other.font_size.or(self.font_size);
+    }
+
+    /// Interpolates towards `target`, `t` going from 0.0 (self) to 1.0 (target). Colors and
+    /// the radius blend smoothly, the other properties and properties only set on one side
+    /// switch over half way.
+    pub fn lerp(&self, target: &Style, t: f32) -> Style {
+        let t = t.clamp(0.0, 1.0);
+        fn pick<T: Copy>(from: Option<T>, to: Option<T>, t: f32) -> Option<T> {
+            if t < 0.5 { from } else { to }
+        }
+        fn blend<T: Copy>(
+            from: Option<T>,
+            to: Option<T>,
+            t: f32,
+            lerp: impl Fn(T, T, f32) -> T,
+        ) -> Option<T> {
+            match (from, to) {
+                (Some(from), Some(to)) => Some(lerp(from, to, t)),
+                _ => pick(from, to, t),
+            }
+        }
+
+        Style {
+            background: blend(self.background, target.background, t, lerp_color),
+            text_color: blend(self.text_color, target.text_color, t, lerp_color),
+            border_color: blend(self.border_color, target.border_color, t, lerp_color),
+            border_width: pick(self.border_width, target.border_width, t),
+            separator: pick(self.separator, target.separator, t),
+            radius: blend(self.radius, target.radius, t, |a, b, t| a + (b - a) * t),
+            padding: pick(self.padding, target.padding, t),
+            font_size: pick(self.font_size, target.font_size, t),
+        }
+    }
+}
+
+pub fn lerp_color(from: ClayColor, to: ClayColor, t: f32) -> ClayColor {
+    ClayColor::rgba(
+        from.r + (to.r - from.r) * t,
+        from.g + (to.g - from.g) * t,
+        from.b + (to.b - from.b) * t,
+        from.a + (to.a - from.a) * t,
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StateSelector {
+    Hover,
+    Active,
+    Disabled,
+}
+
+impl StateSelector {
+    fn matches(self, state: WidgetState) -> bool {
+        match self {
+            StateSelector::Hover => state.hovered,
+            StateSelector::Active => state.active,
+            StateSelector::Disabled => state.disabled,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Selector {
+    /// `None` matches every widget
+    widget: Option<String>,
+    classes: Vec<String>,
+    states: Vec<StateSelector>,
+}
+
+impl Selector {
+    fn specificity(&self) -> u32 {
+        (self.classes.len() + self.states.len()) as u32 * 10 + self.widget.is_some() as u32
+    }
+
+    fn matches(&self, widget: &str, classes: &[String], state: WidgetState) -> bool {
+        self.widget.as_ref().is_none_or(|w| w == widget)
+            && self.classes.iter().all(|c| classes.contains(c))
+            && self.states.iter().all(|s| s.matches(state))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    selector: Selector,
+    style: Style,
+}
+
+fn parse_selector(text: &str, line: u32) -> StyleResult<Selector> {
+    let mut selector = Selector::default();
+    let valid_name = |name: &str| {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+    };
+
+    // Split before every '.' and ':' while keeping the separator with its part
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        if (c == '.' || c == ':') && i > start {
+            parts.push(&text[start..i]);
+            start = i;
+        }
+    }
+    parts.push(&text[start..]);
+
+    for (i, part) in parts.into_iter().enumerate() {
+        if let Some(class) = part.strip_prefix('.') {
+            if !valid_name(class) {
+                return Err(StyleError::new(line, format!("invalid class '{}'", part)));
+            }
+            selector.classes.push(class.to_string());
+        } else if let Some(state) = part.strip_prefix(':') {
+            selector.states.push(match state {
+                "hover" => StateSelector::Hover,
+                "active" => StateSelector::Active,
+                "disabled" => StateSelector::Disabled,
+                _ => return Err(StyleError::new(line, format!("unknown state '{}'", part))),
+            });
+        } else if i == 0 && part == "*" {
+            selector.widget = None;
+        } else if i == 0 && valid_name(part) {
+            selector.widget = Some(part.to_string());
+        } else {
+            return Err(StyleError::new(
+                line,
+                format!("invalid selector '{}'", text),
+            ));
+        }
+    }
+
+    Ok(selector)
+}
+
+fn parse_property(style: &mut Style, name: &str, value: &str, line: u32) -> StyleResult<()> {
+    let color = || {
+        value
+            .strip_prefix('#')
+            .and_then(parse_hex_color)
+            .ok_or_else(|| StyleError::new(line, format!("invalid color '{}'", value)))
+    };
+    let number = || {
+        value
+            .parse::<f32>()
+            .ok()
+            .filter(|v| *v >= 0.0 && *v <= u16::MAX as f32)
+            .ok_or_else(|| StyleError::new(line, format!("invalid number '{}'", value)))
+    };
+
+    match name {
+        "background" => style.background = Some(color()?),
+        "text_color" => style.text_color = Some(color()?),
+        "border_color" => style.border_color = Some(color()?),
+        "border" => style.border_width = Some(number()? as u16),
+        "separator" => style.separator = Some(number()? as u16),
+        "radius" => style.radius = Some(number()?),
+        "padding" => style.padding = Some(number()? as u16),
+        "font_size" => style.font_size = Some(number()? as u16),
+        _ => {
+            return Err(StyleError::new(
+                line,
+                format!("unknown style property '{}'", name),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn parse(source: &str) -> StyleResult<Vec<Rule>> {
+    // Strip comments up front, keeping the newlines so lines still match the source
+    let source: String = source
+        .lines()
+        .map(|l| l.split_once("//").map_or(l, |(code, _)| code))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut rules = Vec::new();
+    let mut rest = source.as_str();
+    let mut line = 1;
+
+    loop {
+        let Some(open) = rest.find('{') else {
+            if !rest.trim().is_empty() {
+                return Err(StyleError::new(line, "expected '{' after selector"));
+            }
+            return Ok(rules);
+        };
+
+        line += rest[..open].matches('\n').count() as u32;
+        let selectors = rest[..open].trim();
+        let Some(close) = rest[open..].find('}').map(|c| open + c) else {
+            return Err(StyleError::new(line, "missing '}'"));
+        };
+
+        let mut style = Style::default();
+        let mut prop_line = line;
+        for declaration in rest[open + 1..close].split_inclusive(['\n', ';']) {
+            let text = declaration.trim_end_matches('\n').trim_end_matches(';');
+            if !text.trim().is_empty() {
+                let Some((name, value)) = text.split_once(':') else {
+                    return Err(StyleError::new(
+                        prop_line,
+                        format!("expected 'name: value' but found '{}'", text.trim()),
+                    ));
+                };
+                parse_property(&mut style, name.trim(), value.trim(), prop_line)?;
+            }
+            if declaration.ends_with('\n') {
+                prop_line += 1;
+            }
+        }
+
+        // `a, b { ... }` declares the same properties for several selectors
+        for selector in selectors.split(',') {
+            rules.push(Rule {
+                selector: parse_selector(selector.trim(), line)?,
+                style,
+            });
+        }
+
+        line = prop_line;
+        rest = &rest[close + 1..];
+    }
+}
+
+/// A parsed stylesheet.
+pub struct Stylesheet {
+    file: Option<WatchedFile>,
+    /// Sorted by specificity, source order is kept for rules with equal specificity.
+    rules: Vec<Rule>,
+}
+
+impl Default for Stylesheet {
+    fn default() -> Self {
+        Self::parse(DEFAULT_STYLESHEET).expect("default stylesheet is valid")
+    }
+}
+
+impl Stylesheet {
+    pub fn parse(source: &str) -> Result<Self, StyleError> {
+        let mut sheet = Self {
+            file: None,
+            rules: Vec::new(),
+        };
+        sheet.set_rules(parse(source)?);
+        Ok(sheet)
+    }
+
+    /// Loads a stylesheet from disk. It's reloaded when the file changes, a stylesheet that fails
+    /// to parse on reload is reported and the previous rules are kept.
+    pub fn load(path: impl Into<PathBuf>) -> InternalResult<Self> {
+        let mut file = WatchedFile::new(path);
+        let source = file.read()?;
+        let mut sheet = Self::parse(&source).map_err(|e| InternalError::GenericError {
+            text: format!("{:?}: {}", file.path(), e),
+        })?;
+        sheet.file = Some(file);
+        Ok(sheet)
+    }
+
+    fn set_rules(&mut self, mut rules: Vec<Rule>) {
+        rules.sort_by_key(|rule| rule.selector.specificity());
+        self.rules = rules;
+    }
+
+    /// Reloads the stylesheet if the file changed on disk. Returns true if it was reloaded.
+    pub fn reload_if_changed(&mut self) -> bool {
+        let Some(file) = &mut self.file else {
+            return false;
+        };
+
+        let result = match file.read_if_changed() {
+            Some(Ok(source)) => parse(&source).map_err(|e| e.to_string()),
+            Some(Err(e)) => Err(e.to_string()),
+            None => return false,
+        };
+
+        match result {
+            Ok(rules) => {
+                self.set_rules(rules);
+                true
+            }
+            Err(e) => {
+                eprintln!("Unable to reload stylesheet {:?}: {}", file.path(), e);
+                false
+            }
+        }
+    }
+
+    /// Resolves the style of `widget` with the given classes and state.
+    pub fn resolve(&self, widget: &str, classes: &[String], state: WidgetState) -> Style {
+        let mut style = Style::default();
+        for rule in &self.rules {
+            if rule.selector.matches(widget, classes, state) {
+                style.merge(&rule.style);
+            }
+        }
+        style
+    }
+}
+
+impl Ui {
+    /// Adds a class to the next widget, for example `ui.class("danger").button("Delete")`.
+    /// Calls can be chained to add several classes.
+    pub fn class(&self, name: &str) -> &Self {
+        self.push_pending_class(name);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rgb;
+
+    fn classes(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn test_resolve_specificity() {
+        let sheet = Stylesheet::parse(
+            r#"
+            // Later, less specific rules don't override classes
+            .danger { background: #ff0000 }
+            button { background: #101010; text_color: #ffffff; radius: 4 }
+            button:hover, button:active { background: #202020 }
+            button.danger:hover { background: #ff8080 }
+            "#,
+        )
+        .unwrap();
+
+        let normal = WidgetState::default();
+        let hovered = WidgetState {
+            hovered: true,
+            ..Default::default()
+        };
+
+        let style = sheet.resolve("button", &[], normal);
+        assert_eq!(style.background, Some(rgb(16, 16, 16)));
+        assert_eq!(style.radius, Some(4.0));
+
+        let style = sheet.resolve("button", &[], hovered);
+        assert_eq!(style.background, Some(rgb(32, 32, 32)));
+
+        let style = sheet.resolve("button", &classes(&["danger"]), normal);
+        assert_eq!(style.background, Some(rgb(255, 0, 0)));
+        assert_eq!(style.text_color, Some(rgb(255, 255, 255)));
+
+        let style = sheet.resolve("button", &classes(&["danger"]), hovered);
+        assert_eq!(style.background, Some(rgb(255, 128, 128)));
+
+        let style = sheet.resolve("label", &classes(&["danger"]), hovered);
+        assert_eq!(style.background, Some(rgb(255, 0, 0)));
+        assert_eq!(style.text_color, None);
+    }
+
+    #[test]
+    fn test_errors_report_line() {
+        let error = Stylesheet::parse("button {\n  background: #ffffff\n  colour: #000000\n}")
+            .err()
+            .unwrap();
+        assert_eq!(error.line, 3);
+        assert!(error.message.contains("colour"));
+
+        let error = Stylesheet::parse("\n\nbutton:pressed { radius: 2 }")
+            .err()
+            .unwrap();
+        assert_eq!(error.line, 3);
+
+        assert!(Stylesheet::parse("button { radius: 2").is_err());
+        assert!(Stylesheet::parse("button { radius: -2 }").is_err());
+        assert!(Stylesheet::parse(DEFAULT_STYLESHEET).is_ok());
+    }
+
+    #[test]
+    fn test_lerp() {
+        let from = Style {
+            background: Some(rgb(0, 0, 0)),
+            radius: Some(2.0),
+            padding: Some(4),
+            ..Default::default()
+        };
+        let to = Style {
+            background: Some(rgb(200, 100, 0)),
+            radius: Some(6.0),
+            padding: Some(8),
+            text_color: Some(rgb(255, 255, 255)),
+            ..Default::default()
+        };
+
+        let quarter = from.lerp(&to, 0.25);
+        assert_eq!(quarter.background, Some(rgb(50, 25, 0)));
+        assert_eq!(quarter.radius, Some(3.0));
+        assert_eq!(quarter.padding, Some(4));
+        assert_eq!(quarter.text_color, None);
+
+        assert_eq!(from.lerp(&to, 1.0), to);
+        assert_eq!(
+            Stylesheet::parse("menu { separator: 2 }")
+                .unwrap()
+                .resolve("menu", &[], WidgetState::default())
+                .separator,
+            Some(2)
+        );
+        assert_eq!(from.lerp(&to, -1.0), from);
+    }
+
+    #[test]
+    fn test_text_role_sizes() {
+        let sheet = Stylesheet::parse("heading { font_size: 40 }").unwrap();
+        let size = |role: TextRole| {
+            let style = sheet.resolve(role.name(), &[], WidgetState::default());
+            role.font_size(&style, 16)
+        };
+        assert_eq!(size(TextRole::Heading), 40);
+        // Scaled from the default size without a rule
+        assert_eq!(size(TextRole::Body), 16);
+        assert_eq!(size(TextRole::Caption), 12);
+    }
+}
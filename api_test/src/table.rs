@@ -0,0 +1,322 @@
+//! Tables: rows of cells lined up under column headers.
+//!
+//! Every row is laid out on its own, so columns line up as long as they're sized the same way
+//! in each row, which is the case for fixed, grow and percent widths but not fit. Clicking the
+//! header of a sortable column sorts by it and clicking it again flips the order. The table
+//! only keeps track of the sort, the rows are declared in whatever order the caller sorted
+//! them in.
+use crate::image::ImageInfo;
+use crate::input::MouseButton;
+use crate::style::WidgetState;
+use crate::ui::{Ui, apply_style, rgb};
+use clay_layout::color::Color as ClayColor;
+use clay_layout::id::Id;
+use clay_layout::layout::{
+    Alignment, LayoutAlignmentX, LayoutAlignmentY, LayoutDirection, Padding, Sizing,
+};
+use clay_layout::math::Vector2;
+use clay_layout::{Declaration, fit, grow};
+use std::cell::Cell;
+use std::cmp::Ordering;
+
+/// A column of a [`Ui::table`].
+#[derive(Debug, Clone, Copy)]
+pub struct Column<'a> {
+    label: &'a str,
+    width: Sizing,
+    sortable: bool,
+}
+
+impl<'a> Column<'a> {
+    /// A column growing to share the width left by the others, that can't be sorted by.
+    pub fn new(label: &'a str) -> Self {
+        Self {
+            label,
+            width: grow!(),
+            sortable: false,
+        }
+    }
+
+    pub fn width(mut self, width: Sizing) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Lets clicking the header sort the table by this column.
+    pub fn sortable(mut self) -> Self {
+        self.sortable = true;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+impl SortOrder {
+    /// `ordering` of two rows in ascending order turned into this order, for `sort_by`.
+    pub fn apply(self, ordering: Ordering) -> Ordering {
+        match self {
+            SortOrder::Ascending => ordering,
+            SortOrder::Descending => ordering.reverse(),
+        }
+    }
+}
+
+/// The column a table is sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TableSort {
+    pub column: usize,
+    pub order: SortOrder,
+}
+
+/// What [`Ui::table`] reports after a frame.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TableResponse {
+    pub sort: Option<TableSort>,
+    /// A header was clicked this frame.
+    pub sort_changed: bool,
+    /// Row under the mouse, as the index of its [`Table::row`] call.
+    pub hovered_row: Option<usize>,
+}
+
+/// Sort after clicking the header of `column`: ascending for a new column, flipped for the
+/// current one.
+fn next_sort(current: Option<TableSort>, column: usize) -> TableSort {
+    let order = match current {
+        Some(sort) if sort.column == column && sort.order == SortOrder::Ascending => {
+            SortOrder::Descending
+        }
+        _ => SortOrder::Ascending,
+    };
+    TableSort { column, order }
+}
+
+/// Declares the rows of a [`Ui::table`].
+pub struct Table<'t> {
+    ui: &'t Ui,
+    id: &'t str,
+    columns: &'t [Column<'t>],
+    sort: Option<TableSort>,
+    rows: Cell<usize>,
+    hovered_row: Cell<Option<usize>>,
+}
+
+impl Table<'_> {
+    /// The sort as of this frame, header clicks have already been applied.
+    pub fn sort(&self) -> Option<TableSort> {
+        self.sort
+    }
+
+    /// Adds a row with the cells declared by `f`, one per column from the left.
+    pub fn row(&self, f: impl FnOnce(&TableRow)) {
+        let ui = self.ui;
+        let index = self.rows.get();
+        self.rows.set(index + 1);
+        let id = ui.id_index(self.id, (1 + self.columns.len() + index) as u32);
+
+        let hovered = ui
+            .last_frame_rect(id)
+            .is_some_and(|rect| ui.input().is_hovered(rect));
+        if hovered {
+            self.hovered_row.set(Some(index));
+        }
+        if index % 2 == 1 {
+            ui.push_pending_class("striped");
+        }
+        let style = ui.widget_style(
+            "table_row",
+            WidgetState {
+                hovered,
+                ..Default::default()
+            },
+        );
+
+        let mut decl = Declaration::new();
+        decl.id(id)
+            .layout()
+            .width(grow!())
+            .height(fit!())
+            .direction(LayoutDirection::LeftToRight)
+            .end();
+        apply_style(&mut decl, &style);
+
+        let row = TableRow {
+            table: self,
+            column: Cell::new(0),
+            color: style.text_color.unwrap_or(rgb(220, 220, 220)),
+        };
+        ui.with_layout(&decl, |_| f(&row));
+    }
+}
+
+/// Declares the cells of a row of a [`Ui::table`].
+pub struct TableRow<'r, 't> {
+    table: &'r Table<'t>,
+    column: Cell<usize>,
+    color: ClayColor,
+}
+
+impl TableRow<'_, '_> {
+    /// A cell showing `text`.
+    pub fn cell(&self, text: &str) {
+        let color = self.color;
+        self.cell_with(|ui| ui.text_element(text, color));
+    }
+
+    /// A cell with the contents declared by `f`. Cells past the last column are ignored.
+    pub fn cell_with(&self, f: impl FnOnce(&Ui)) {
+        let index = self.column.get();
+        let Some(column) = self.table.columns.get(index) else {
+            return;
+        };
+        self.column.set(index + 1);
+        self.table.ui.with_layout(&cell_declaration(column), f);
+    }
+}
+
+/// Sizes a header or row cell for `column`, cutting off what doesn't fit.
+fn cell_declaration<'a>(column: &Column) -> Declaration<'a, ImageInfo, ()> {
+    let mut decl = Declaration::new();
+    decl.layout()
+        .width(column.width)
+        .height(fit!())
+        .padding(Padding::all(4))
+        .child_alignment(Alignment::new(
+            LayoutAlignmentX::Left,
+            LayoutAlignmentY::Center,
+        ))
+        .end()
+        .clip(true, false, Vector2::new(0.0, 0.0));
+    decl
+}
+
+impl Ui {
+    /// A table with a header row for `columns` and the rows added by `f`. Styled by the
+    /// `table`, `table_header` and `table_row` rules, with the `striped` class on every other
+    /// row.
+    pub fn table(&self, id: &str, columns: &[Column], f: impl FnOnce(&Table)) -> TableResponse {
+        let table_id = self.id(id);
+        let header_ids: Vec<Id> = (0..columns.len())
+            .map(|index| self.id_index(id, 1 + index as u32))
+            .collect();
+
+        let mut sort = self.item_state(table_id).table_sort;
+        let clicked = columns
+            .iter()
+            .zip(&header_ids)
+            .position(|(column, header)| {
+                column.sortable && self.input().was_clicked(header.id.id, MouseButton::Left)
+            });
+        if let Some(column) = clicked {
+            sort = Some(next_sort(sort, column));
+            self.item_state(table_id).table_sort = sort;
+        }
+
+        let style = self.widget_style("table", Default::default());
+        let mut decl = Declaration::new();
+        decl.id(table_id)
+            .layout()
+            .width(grow!())
+            .height(fit!())
+            .direction(LayoutDirection::TopToBottom)
+            .end();
+        apply_style(&mut decl, &style);
+
+        let table = Table {
+            ui: self,
+            id,
+            columns,
+            sort,
+            rows: Cell::new(0),
+            hovered_row: Cell::new(None),
+        };
+        self.with_layout(&decl, |ui| {
+            ui.table_header(columns, &header_ids, sort);
+            f(&table);
+        });
+
+        TableResponse {
+            sort,
+            sort_changed: clicked.is_some(),
+            hovered_row: table.hovered_row.get(),
+        }
+    }
+
+    fn table_header(&self, columns: &[Column], ids: &[Id], sort: Option<TableSort>) {
+        let mut decl = Declaration::new();
+        decl.layout()
+            .width(grow!())
+            .height(fit!())
+            .direction(LayoutDirection::LeftToRight)
+            .end();
+
+        self.with_layout(&decl, |ui| {
+            for (index, (column, id)) in columns.iter().zip(ids).enumerate() {
+                let hovered = column.sortable
+                    && ui
+                        .last_frame_rect(*id)
+                        .is_some_and(|rect| ui.input().is_hovered(rect));
+                let style = ui.widget_style(
+                    "table_header",
+                    WidgetState {
+                        hovered,
+                        ..Default::default()
+                    },
+                );
+                let color = style.text_color.unwrap_or(rgb(220, 220, 220));
+
+                let mut cell = cell_declaration(column);
+                cell.id(*id)
+                    .layout()
+                    .child_gap(6)
+                    .direction(LayoutDirection::LeftToRight);
+                apply_style(&mut cell, &style);
+
+                ui.with_layout(&cell, |ui| {
+                    ui.text_element(column.label, color);
+                    match sort {
+                        Some(sort) if sort.column == index => {
+                            let arrow = match sort.order {
+                                SortOrder::Ascending => "^",
+                                SortOrder::Descending => "v",
+                            };
+                            ui.text_element(arrow, color);
+                        }
+                        _ => {}
+                    }
+                });
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_sort() {
+        let ascending = |column| TableSort {
+            column,
+            order: SortOrder::Ascending,
+        };
+        assert_eq!(next_sort(None, 1), ascending(1));
+        assert_eq!(
+            next_sort(Some(ascending(1)), 1).order,
+            SortOrder::Descending
+        );
+        assert_eq!(
+            next_sort(Some(next_sort(Some(ascending(1)), 1)), 1),
+            ascending(1)
+        );
+        // Another column starts out ascending
+        assert_eq!(next_sort(Some(ascending(1)), 2), ascending(2));
+
+        let mut values = [3, 1, 2];
+        values.sort_by(|a, b| SortOrder::Descending.apply(a.cmp(b)));
+        assert_eq!(values, [3, 2, 1]);
+    }
+}
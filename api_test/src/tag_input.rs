@@ -0,0 +1,146 @@
+//! Text fields that collect tags, for labeling tracks and clips.
+//!
+//! [`Ui::tag_input`] shows the tags as chips in front of a text field. Pressing Enter adds the
+//! typed text as a tag, the "x" of a chip removes it and Backspace in the empty field removes
+//! the last one. The text typed so far is kept by the ui until it becomes a tag.
+use crate::input::{Key, MouseButton};
+use crate::style::WidgetState;
+use crate::ui::{Ui, apply_style, rgb};
+use clay_layout::id::Id;
+use clay_layout::layout::{Alignment, LayoutAlignmentX, LayoutAlignmentY, LayoutDirection};
+use clay_layout::{Declaration, fit, fixed, grow};
+
+/// Adds `text` without surrounding whitespace to `tags` unless it's empty or already a tag.
+/// Returns true if it was added.
+fn add_tag(tags: &mut Vec<String>, text: &str) -> bool {
+    let tag = text.trim();
+    if tag.is_empty() || tags.iter().any(|existing| existing == tag) {
+        return false;
+    }
+    tags.push(tag.to_string());
+    true
+}
+
+impl Ui {
+    /// A text field with `tags` shown as removable chips in front of it. Enter adds the typed
+    /// text as a tag, Backspace in the empty field removes the last one. Returns true when
+    /// `tags` changed this frame.
+    ///
+    /// Chips are styled by the `tag_chip` rule and their remove buttons by `tag_remove`, the
+    /// field like [`Ui::input_text`].
+    pub fn tag_input(&self, id: &str, tags: &mut Vec<String>) -> bool {
+        let field_id = self.id(id);
+        let remove_id = |index: usize| self.id_index(id, index as u32 * 2 + 2);
+        let input = self.input();
+        let mut changed = false;
+
+        if let Some(index) = (0..tags.len())
+            .find(|index| input.was_clicked(remove_id(*index).id.id, MouseButton::Left))
+        {
+            tags.remove(index);
+            changed = true;
+        }
+
+        // Keys go to the field after this, which gives up focus on Enter
+        let focused = self
+            .focus_id()
+            .is_some_and(|focus| focus.id.id == field_id.id.id);
+        let mut draft = std::mem::take(&mut self.item_state(field_id).tag_draft);
+        let enter = focused && input.keys_pressed.contains(&Key::Enter);
+        if enter {
+            changed |= add_tag(tags, &draft);
+            draft.clear();
+        } else if focused && draft.is_empty() && input.keys_pressed.contains(&Key::Backspace) {
+            changed |= tags.pop().is_some();
+        }
+
+        let mut decl = Declaration::new();
+        decl.layout()
+            .width(grow!())
+            .height(fit!())
+            .child_gap(4)
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Left,
+                LayoutAlignmentY::Center,
+            ))
+            .direction(LayoutDirection::LeftToRight)
+            .end();
+        self.with_layout(&decl, |ui| {
+            for (index, tag) in tags.iter().enumerate() {
+                let chip_id = ui.id_index(id, index as u32 * 2 + 1);
+                ui.tag_chip(chip_id, remove_id(index), tag);
+            }
+            ui.input_text(id, &mut draft);
+        });
+
+        if enter {
+            self.set_focused(field_id, true);
+        }
+        self.item_state(field_id).tag_draft = draft;
+        changed
+    }
+
+    /// A chip showing `tag` with the button that removes it.
+    fn tag_chip(&self, id: Id, remove_id: Id, tag: &str) {
+        let style = self.widget_style("tag_chip", WidgetState::default());
+        let color = style.text_color.unwrap_or(rgb(220, 220, 220));
+        let hovered = self
+            .last_frame_rect(remove_id)
+            .is_some_and(|rect| self.input().is_hovered(rect));
+        let remove_style = self.widget_style(
+            "tag_remove",
+            WidgetState {
+                hovered,
+                ..Default::default()
+            },
+        );
+        let remove_color = remove_style.text_color.unwrap_or(rgb(160, 160, 160));
+        let size = (self.font_size() as f32 * 1.2).round();
+
+        let mut decl = Declaration::new();
+        decl.id(id)
+            .layout()
+            .width(fit!())
+            .height(fit!())
+            .child_gap(4)
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Left,
+                LayoutAlignmentY::Center,
+            ))
+            .direction(LayoutDirection::LeftToRight)
+            .end();
+        apply_style(&mut decl, &style);
+
+        let mut remove = Declaration::new();
+        remove
+            .id(remove_id)
+            .layout()
+            .width(fixed!(size))
+            .height(fixed!(size))
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Center,
+                LayoutAlignmentY::Center,
+            ))
+            .end();
+        apply_style(&mut remove, &remove_style);
+
+        self.with_layout(&decl, |ui| {
+            ui.text_element(tag, color);
+            ui.with_layout(&remove, |ui| ui.text_element("x", remove_color));
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_tag() {
+        let mut tags = vec!["drums".to_string()];
+        assert!(add_tag(&mut tags, "  vocals "));
+        assert!(!add_tag(&mut tags, "drums"));
+        assert!(!add_tag(&mut tags, "   "));
+        assert_eq!(tags, ["drums", "vocals"]);
+    }
+}
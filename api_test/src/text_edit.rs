@@ -0,0 +1,303 @@
+//! Caret and selection handling for editable text, independent of how the text is drawn.
+use crate::font::TextLayout;
+use crate::input::{Key, Modifiers};
+use std::ops::Range;
+
+/// Caret and selection of an edited string as byte offsets. The selection spans from `anchor`
+/// to `cursor`, which are equal when nothing is selected.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TextEditState {
+    pub cursor: usize,
+    pub anchor: usize,
+    /// Column kept while moving up and down so the caret returns to it after passing shorter
+    /// lines.
+    pub preferred_x: Option<f32>,
+}
+
+fn prev_boundary(text: &str, pos: usize) -> usize {
+    text[..pos].char_indices().next_back().map_or(0, |(i, _)| i)
+}
+
+fn next_boundary(text: &str, pos: usize) -> usize {
+    text[pos..]
+        .chars()
+        .next()
+        .map_or(pos, |c| pos + c.len_utf8())
+}
+
+impl TextEditState {
+    /// Caret at the end of `text`.
+    pub fn at_end(text: &str) -> Self {
+        Self {
+            cursor: text.len(),
+            anchor: text.len(),
+            preferred_x: None,
+        }
+    }
+
+    pub fn selection(&self) -> Range<usize> {
+        self.cursor.min(self.anchor)..self.cursor.max(self.anchor)
+    }
+
+    pub fn has_selection(&self) -> bool {
+        self.cursor != self.anchor
+    }
+
+    /// Keeps the offsets valid when the text was changed by someone else.
+    pub fn clamp(&mut self, text: &str) {
+        let fix = |pos: usize| {
+            let mut pos = pos.min(text.len());
+            while !text.is_char_boundary(pos) {
+                pos -= 1;
+            }
+            pos
+        };
+        self.cursor = fix(self.cursor);
+        self.anchor = fix(self.anchor);
+    }
+
+    /// Moves the caret, keeping the anchor when `extend` is set to grow the selection.
+    pub fn move_to(&mut self, pos: usize, extend: bool) {
+        self.cursor = pos;
+        if !extend {
+            self.anchor = pos;
+        }
+    }
+
+    pub fn select_all(&mut self, text: &str) {
+        self.anchor = 0;
+        self.cursor = text.len();
+    }
+
+    fn delete_selection(&mut self, text: &mut String) -> bool {
+        if !self.has_selection() {
+            return false;
+        }
+        let range = self.selection();
+        text.replace_range(range.clone(), "");
+        self.move_to(range.start, false);
+        true
+    }
+
+    /// Replaces the selection with `insert`. Returns true if the text changed.
+    pub fn insert(&mut self, text: &mut String, insert: &str) -> bool {
+        self.preferred_x = None;
+        let deleted = self.delete_selection(text);
+        if insert.is_empty() {
+            return deleted;
+        }
+        text.insert_str(self.cursor, insert);
+        self.move_to(self.cursor + insert.len(), false);
+        true
+    }
+
+    /// Handles an editing or navigation key. Returns true if the text changed.
+    pub fn key(&mut self, text: &mut String, key: Key, modifiers: Modifiers) -> bool {
+        let extend = modifiers.shift();
+        match key {
+            Key::Left if self.has_selection() && !extend => {
+                self.move_to(self.selection().start, false)
+            }
+            Key::Right if self.has_selection() && !extend => {
+                self.move_to(self.selection().end, false)
+            }
+            Key::Left => self.move_to(prev_boundary(text, self.cursor), extend),
+            Key::Right => self.move_to(next_boundary(text, self.cursor), extend),
+            Key::Home | Key::Up => self.move_to(0, extend),
+            Key::End | Key::Down => self.move_to(text.len(), extend),
+            Key::Backspace => {
+                if !self.has_selection() {
+                    self.anchor = prev_boundary(text, self.cursor);
+                }
+                return self.delete_selection(text);
+            }
+            Key::Delete => {
+                if !self.has_selection() {
+                    self.anchor = next_boundary(text, self.cursor);
+                }
+                return self.delete_selection(text);
+            }
+            Key::Character('a') if modifiers.command() => self.select_all(text),
+            _ => {}
+        }
+        false
+    }
+
+    /// Handles a key for text laid out over several lines. Up and Down move between visual
+    /// lines, Home and End go to the ends of the current line and Enter starts a new line.
+    /// Returns true if the text changed.
+    pub fn key_multiline(
+        &mut self,
+        text: &mut String,
+        key: Key,
+        modifiers: Modifiers,
+        layout: &TextLayout,
+    ) -> bool {
+        let extend = modifiers.shift();
+        let line = layout.line_at(self.cursor);
+        if !matches!(key, Key::Up | Key::Down) {
+            self.preferred_x = None;
+        }
+
+        match key {
+            Key::Up | Key::Down => {
+                let x = *self
+                    .preferred_x
+                    .get_or_insert_with(|| layout.caret_x(line, self.cursor));
+                let offset = match key {
+                    Key::Up if line == 0 => 0,
+                    Key::Down if line + 1 >= layout.lines.len() => text.len(),
+                    Key::Up => layout.offset_at_x(line - 1, x),
+                    _ => layout.offset_at_x(line + 1, x),
+                };
+                self.move_to(offset, extend);
+            }
+            Key::Home => {
+                let start = layout.lines.get(line).map_or(0, |line| line.range.start);
+                self.move_to(start, extend);
+            }
+            Key::End => {
+                let end = layout.lines.get(line).map_or(text.len(), |line| {
+                    line.carets
+                        .last()
+                        .map_or(line.range.end, |(offset, _)| *offset)
+                });
+                self.move_to(end, extend);
+            }
+            Key::Enter => return self.insert(text, "\n"),
+            key => return self.key(text, key, modifiers),
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::TextLine;
+
+    fn keys(state: &mut TextEditState, text: &mut String, keys: &[Key], modifiers: Modifiers) {
+        for key in keys {
+            state.key(text, *key, modifiers);
+        }
+    }
+
+    #[test]
+    fn test_editing() {
+        let mut text = String::from("Bass");
+        let mut state = TextEditState::at_end(&text);
+
+        assert!(state.insert(&mut text, " 2"));
+        assert_eq!(text, "Bass 2");
+
+        keys(
+            &mut state,
+            &mut text,
+            &[Key::Home, Key::Delete],
+            Modifiers::NONE,
+        );
+        assert_eq!(text, "ass 2");
+        assert!(state.insert(&mut text, "Å"));
+        assert_eq!((text.as_str(), state.cursor), ("Åass 2", 2));
+
+        // Backspace removes whole characters
+        keys(&mut state, &mut text, &[Key::Backspace], Modifiers::NONE);
+        assert_eq!((text.as_str(), state.cursor), ("ass 2", 0));
+
+        keys(
+            &mut state,
+            &mut text,
+            &[Key::End, Key::Right],
+            Modifiers::NONE,
+        );
+        assert!(!state.key(&mut text, Key::Delete, Modifiers::NONE));
+        assert_eq!(state.cursor, text.len());
+    }
+
+    #[test]
+    fn test_selection() {
+        let mut text = String::from("Lead vox");
+        let mut state = TextEditState::at_end(&text);
+
+        keys(&mut state, &mut text, &[Key::Left; 3], Modifiers::SHIFT);
+        assert_eq!(&text[state.selection()], "vox");
+        assert!(state.insert(&mut text, "gtr"));
+        assert_eq!(text, "Lead gtr");
+        assert!(!state.has_selection());
+
+        // Moving without shift collapses the selection to its side
+        keys(&mut state, &mut text, &[Key::Home], Modifiers::SHIFT);
+        keys(&mut state, &mut text, &[Key::Right], Modifiers::NONE);
+        assert_eq!(state.cursor, text.len());
+
+        state.key(&mut text, Key::Character('a'), Modifiers::COMMAND);
+        assert_eq!(state.selection(), 0..text.len());
+        keys(&mut state, &mut text, &[Key::Backspace], Modifiers::NONE);
+        assert!(text.is_empty());
+
+        let mut text = String::from("ÅÄ");
+        let mut state = TextEditState {
+            cursor: 3,
+            anchor: 9,
+            ..Default::default()
+        };
+        state.clamp(&text);
+        assert_eq!((state.cursor, state.anchor), (2, 4));
+        assert!(state.insert(&mut text, ""));
+        assert_eq!(text, "Å");
+    }
+
+    /// Layout without wrapping where every character is 10 pixels wide.
+    fn layout(text: &str) -> TextLayout {
+        let mut lines = Vec::new();
+        let mut start = 0;
+        for line in text.split('\n') {
+            let range = start..start + line.len();
+            let carets = range
+                .clone()
+                .chain(std::iter::once(range.end))
+                .map(|offset| (offset, (offset - start) as f32 * 10.0))
+                .collect();
+            lines.push(TextLine {
+                range,
+                carets,
+                width: line.len() as f32 * 10.0,
+            });
+            start += line.len() + 1;
+        }
+        TextLayout {
+            lines,
+            line_height: 20.0,
+        }
+    }
+
+    #[test]
+    fn test_multiline() {
+        let mut text = String::from("Kick drum\nsn\nhihats");
+        let mut state = TextEditState {
+            cursor: 4,
+            anchor: 4,
+            ..Default::default()
+        };
+
+        // The column is kept when passing a shorter line
+        let lines = layout(&text);
+        state.key_multiline(&mut text, Key::Down, Modifiers::NONE, &lines);
+        assert_eq!(state.cursor, 12);
+        state.key_multiline(&mut text, Key::Down, Modifiers::NONE, &lines);
+        assert_eq!(state.cursor, 17);
+        state.key_multiline(&mut text, Key::Down, Modifiers::SHIFT, &lines);
+        assert_eq!((state.anchor, state.cursor), (17, text.len()));
+
+        state.key_multiline(&mut text, Key::Home, Modifiers::NONE, &lines);
+        assert_eq!(state.cursor, 13);
+        state.key_multiline(&mut text, Key::Up, Modifiers::NONE, &lines);
+        assert_eq!(state.cursor, 10);
+        state.key_multiline(&mut text, Key::End, Modifiers::NONE, &lines);
+        assert_eq!(state.cursor, 12);
+
+        assert!(state.key_multiline(&mut text, Key::Enter, Modifiers::NONE, &lines));
+        assert_eq!(text, "Kick drum\nsn\n\nhihats");
+        assert_eq!(state.preferred_x, None);
+    }
+}
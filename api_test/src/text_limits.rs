@@ -0,0 +1,124 @@
+//! Guards against text that would stall the text workers or allocate giant pixmaps.
+//!
+//! Every string is cut to [`TextLimits::max_label_len`] and cleaned of control characters
+//! before it is measured or rasterized, so a label fed a megabyte of log output costs no more
+//! than a long line. Single-line widgets also apply the [`NewlinePolicy`] to the text they edit.
+//! The limits are set with [`crate::ui::Ui::set_text_limits`].
+use std::borrow::Cow;
+
+/// What single-line widgets do with line breaks in their text.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NewlinePolicy {
+    /// Each line break becomes a space.
+    #[default]
+    Replace,
+    /// Line breaks are removed, joining the lines.
+    Remove,
+    /// Only the first line is kept.
+    FirstLine,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextLimits {
+    /// Longest text in bytes that is measured and rasterized. Longer text is cut at the last
+    /// character that fits, what's past it isn't drawn.
+    pub max_label_len: usize,
+    /// Removes control characters other than line breaks and tabs before drawing, which fonts
+    /// have no glyphs for.
+    pub strip_control_chars: bool,
+    /// Line breaks in the text of single-line widgets like [`crate::ui::Ui::input_text`].
+    pub newlines: NewlinePolicy,
+}
+
+impl Default for TextLimits {
+    fn default() -> Self {
+        Self {
+            max_label_len: 16 * 1024,
+            strip_control_chars: true,
+            newlines: NewlinePolicy::default(),
+        }
+    }
+}
+
+fn is_stripped(c: char) -> bool {
+    c.is_control() && c != '\n' && c != '\t'
+}
+
+impl TextLimits {
+    /// `text` as it is measured and drawn. Only allocates when control characters are removed.
+    pub(crate) fn display<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut end = text.len().min(self.max_label_len);
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        let text = &text[..end];
+        if self.strip_control_chars && text.chars().any(is_stripped) {
+            Cow::Owned(text.chars().filter(|c| !is_stripped(*c)).collect())
+        } else {
+            Cow::Borrowed(text)
+        }
+    }
+
+    /// `text` with its line breaks handled by the newline policy, a `\r\n` pair counting as one.
+    /// Borrowed when there are none.
+    pub(crate) fn single_line<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let is_break = |c: char| c == '\n' || c == '\r';
+        if !text.contains(is_break) {
+            return Cow::Borrowed(text);
+        }
+        match self.newlines {
+            NewlinePolicy::FirstLine => Cow::Borrowed(&text[..text.find(is_break).unwrap()]),
+            NewlinePolicy::Remove => Cow::Owned(text.replace(is_break, "")),
+            NewlinePolicy::Replace => Cow::Owned(text.replace("\r\n", " ").replace(is_break, " ")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let limits = TextLimits {
+            max_label_len: 6,
+            ..Default::default()
+        };
+        assert!(matches!(limits.display("Kick"), Cow::Borrowed("Kick")));
+        assert_eq!(limits.display("Kick drum"), "Kick d");
+        // Cut at a character boundary, "ö" takes two bytes
+        assert_eq!(limits.display("Bööö"), "Böö");
+        assert_eq!(limits.display("a\u{7}\tb\r\n"), "a\tb\n");
+
+        let keep = TextLimits {
+            strip_control_chars: false,
+            ..limits
+        };
+        assert_eq!(keep.display("a\u{7}b"), "a\u{7}b");
+
+        let huge = "x".repeat(10_000_000);
+        assert_eq!(TextLimits::default().display(&huge).len(), 16 * 1024);
+    }
+
+    #[test]
+    fn test_single_line() {
+        let text = "Lead\r\nVox\nDouble";
+        let with = |newlines| TextLimits {
+            newlines,
+            ..Default::default()
+        };
+        assert_eq!(
+            with(NewlinePolicy::Replace).single_line(text),
+            "Lead Vox Double"
+        );
+        assert_eq!(
+            with(NewlinePolicy::Remove).single_line(text),
+            "LeadVoxDouble"
+        );
+        assert_eq!(with(NewlinePolicy::FirstLine).single_line(text), "Lead");
+        assert!(matches!(
+            with(NewlinePolicy::Remove).single_line("Lead"),
+            Cow::Borrowed("Lead")
+        ));
+    }
+}
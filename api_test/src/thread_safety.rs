@@ -0,0 +1,48 @@
+//! Which types may cross threads, checked when the crate is built.
+//!
+//! [`Ui`] stays on the thread that created it. It is neither `Send` nor `Sync`:
+//!
+//! - Its state sits in an `UnsafeCell` that every widget call borrows mutably, which is only
+//!   sound as long as a single thread calls into it.
+//! - Clay keeps the current layout context in a global and calls back into the state through
+//!   a raw pointer to measure text.
+//!
+//! Work is moved to the background worker instead: fonts are rasterized and images decoded
+//! on its threads. The results are handed back to the ui thread, so they must be `Send`.
+//! Images are also shared between frames and threads, so they must be `Sync` as well.
+//!
+//! A build that breaks one of these contracts fails to compile here.
+use crate::font::CachedString;
+use crate::image::ImageInfo;
+use crate::ui::Ui;
+
+/// Fails to compile unless `$ty` implements all of the traits.
+macro_rules! assert_impl {
+    ($ty:ty: $($t:path),+) => {
+        const _: fn() = || {
+            fn check<T: ?Sized $(+ $t)+>() {}
+            check::<$ty>();
+        };
+    };
+}
+
+/// Fails to compile if `$ty` implements the trait. If it does, both impls below apply, and
+/// the type of `_` can't be inferred.
+macro_rules! assert_not_impl {
+    ($ty:ty: $t:path) => {
+        const _: fn() = || {
+            trait AmbiguousIfImpl<A> {
+                fn some_item() {}
+            }
+            impl<T: ?Sized> AmbiguousIfImpl<()> for T {}
+            impl<T: ?Sized + $t> AmbiguousIfImpl<u8> for T {}
+            let _ = <$ty as AmbiguousIfImpl<_>>::some_item;
+        };
+    };
+}
+
+assert_not_impl!(Ui: Send);
+assert_not_impl!(Ui: Sync);
+
+assert_impl!(CachedString: Send);
+assert_impl!(ImageInfo: Send, Sync);
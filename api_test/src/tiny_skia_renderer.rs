@@ -1,9 +1,15 @@
 use crate::font::FontHandle;
 use crate::font::TextGenerator;
+use crate::image::{ImageEffects, ImageFit, fit_rect};
+use crate::long_text::ClipStack;
 use clay_layout::math::{BoundingBox, Dimensions};
-use clay_layout::render_commands::{RenderCommand, RenderCommandConfig};
+use clay_layout::render_commands::{Border, RenderCommand, RenderCommandConfig};
 use clay_layout::text::TextConfig;
 use clay_layout::{ClayLayoutScope, Color as ClayColor};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+use std::sync::Mutex;
 use tiny_skia::*;
 
 pub fn clay_to_tiny_skia_color(color: ClayColor) -> Color {
@@ -15,8 +21,47 @@ pub fn clay_to_tiny_skia_color(color: ClayColor) -> Color {
     )
 }
 
-fn clay_to_tiny_skia_rect(rect: BoundingBox) -> Rect {
-    Rect::from_xywh(rect.x, rect.y, rect.width, rect.height).expect("Invalid rectangle dimensions")
+/// Returns `None` for empty, negative or non-finite rectangles, which over-constrained layouts
+/// can produce.
+fn clay_to_tiny_skia_rect(rect: BoundingBox) -> Option<Rect> {
+    if !(rect.width > 0.0 && rect.height > 0.0) {
+        return None;
+    }
+    Rect::from_xywh(rect.x, rect.y, rect.width, rect.height)
+}
+
+/// Problems found while rendering a frame. Nothing here stops the frame from being drawn, the
+/// affected commands are skipped.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RenderDiagnostics {
+    /// Ids of rectangle commands skipped because their bounding box had no valid area.
+    pub invalid_rects: Vec<u32>,
+}
+
+/// Image data of render commands the renderer can draw.
+pub trait RenderImage {
+    /// The pixels to draw and how they are fit into the bounds of the command, `None` to
+    /// draw nothing.
+    fn image(&self) -> Option<(&Pixmap, ImageFit)>;
+
+    /// Color changes applied to the pixels while they're drawn.
+    fn effects(&self) -> ImageEffects {
+        ImageEffects::default()
+    }
+
+    /// Identifies the pixels in the hashes of tiles and frames. Defaults to the address of the
+    /// pixels, which assumes they're never modified and only identifies them within a run.
+    fn pixels_id(&self) -> u64 {
+        self.image()
+            .map_or(0, |(pixmap, _)| pixmap.data().as_ptr() as u64)
+    }
+}
+
+/// For commands that never carry images.
+impl RenderImage for () {
+    fn image(&self) -> Option<(&Pixmap, ImageFit)> {
+        None
+    }
 }
 
 /// Represents a pre-rendered text glyph as a pixmap
@@ -39,14 +84,48 @@ pub type TextBuffer = Pixmap;
 // // Convert to minifb buffer
 // let buffer = pixmap_to_minifb_buffer(&pixmap);
 
+/// `a * b / 255` rounded to nearest.
+fn mul_div_255(a: u8, b: u8) -> u8 {
+    let t = a as u32 * b as u32 + 128;
+    ((t + (t >> 8)) >> 8) as u8
+}
+
+/// Premultiplies `color` scaled by `coverage`. This is what an anti-aliased edge covering
+/// `coverage / 255` of a pixel contributes.
+pub fn premultiply_coverage(color: ColorU8, coverage: u8) -> PremultipliedColorU8 {
+    let alpha = mul_div_255(color.alpha(), coverage);
+    PremultipliedColorU8::from_rgba(
+        mul_div_255(color.red(), alpha),
+        mul_div_255(color.green(), alpha),
+        mul_div_255(color.blue(), alpha),
+        alpha,
+    )
+    .unwrap()
+}
+
+/// Composites premultiplied `src` over `dst`.
+pub fn blend_source_over(
+    dst: PremultipliedColorU8,
+    src: PremultipliedColorU8,
+) -> PremultipliedColorU8 {
+    let inv_alpha = 255 - src.alpha();
+    let blend = |s: u8, d: u8| s + mul_div_255(d, inv_alpha);
+    PremultipliedColorU8::from_rgba(
+        blend(src.red(), dst.red()),
+        blend(src.green(), dst.green()),
+        blend(src.blue(), dst.blue()),
+        blend(src.alpha(), dst.alpha()),
+    )
+    .unwrap()
+}
+
 /// Create a pixmap from A8 alpha data
 pub fn pixmap_from_a8_data(width: u32, height: u32, alpha_data: &[u8]) -> Option<Pixmap> {
     let mut pixmap = Pixmap::new(width, height)?;
+    let white = ColorU8::from_rgba(255, 255, 255, 255);
 
-    for (i, &alpha) in alpha_data.iter().enumerate() {
-        // Create white pixels with varying alpha
-        let color = PremultipliedColorU8::from_rgba(255, 255, 255, alpha).unwrap();
-        pixmap.pixels_mut()[i] = color;
+    for (pixel, &alpha) in pixmap.pixels_mut().iter_mut().zip(alpha_data) {
+        *pixel = premultiply_coverage(white, alpha);
     }
 
     Some(pixmap)
@@ -55,9 +134,11 @@ pub fn pixmap_from_a8_data(width: u32, height: u32, alpha_data: &[u8]) -> Option
 /// Draw a text pixmap onto the target pixmap with color modulation
 fn draw_text_pixmap(target: &mut Pixmap, text_pixmap: &Pixmap, x: i32, y: i32, color: Color) {
     // Create a paint for color modulation if needed
-    let mut paint = PixmapPaint::default();
-    paint.opacity = color.alpha();
-    paint.blend_mode = BlendMode::SourceOver;
+    let paint = PixmapPaint {
+        opacity: color.alpha(),
+        blend_mode: BlendMode::SourceOver,
+        ..Default::default()
+    };
 
     // If the text pixmap is in alpha-only format, you might want to
     // create a colored version first, or use it as a mask
@@ -106,332 +187,797 @@ fn create_colored_text_pixmap(
 }
  */
 
-/// Create a path for rounded rectangle
-fn create_rounded_rect_path(rect: Rect, corner_radii: &[f32; 4]) -> Option<Path> {
-    let mut pb = PathBuilder::new();
+/// A render command resolved to what gets drawn. Ops only borrow plain data so they can be
+/// binned into tiles and rasterized on any thread.
+#[derive(Debug, Clone)]
+enum DrawOp<'a> {
+    Rect {
+        bounds: Rect,
+        color: Color,
+        corner_radii: [f32; 4],
+    },
+    Border {
+        bounds: BoundingBox,
+        color: Color,
+        border: Border,
+    },
+    Text {
+        x: i32,
+        y: i32,
+        pixmap: &'a Pixmap,
+        text: &'a str,
+        font_size: u32,
+        font_id: FontHandle,
+    },
+    Image {
+        /// Bounds of the element, the image isn't drawn outside of them.
+        bounds: Rect,
+        pixmap: &'a Pixmap,
+        /// See [`RenderImage::pixels_id`].
+        pixels_id: u64,
+        fit: ImageFit,
+        effects: ImageEffects,
+    },
+}
+
+impl DrawOp<'_> {
+    /// Area the op can touch as (x, y, width, height).
+    fn bounds(&self) -> (f32, f32, f32, f32) {
+        match self {
+            DrawOp::Rect { bounds, .. } => {
+                (bounds.x(), bounds.y(), bounds.width(), bounds.height())
+            }
+            DrawOp::Border { bounds, .. } => (bounds.x, bounds.y, bounds.width, bounds.height),
+            DrawOp::Text { x, y, pixmap, .. } => (
+                *x as f32,
+                *y as f32,
+                pixmap.width() as f32,
+                pixmap.height() as f32,
+            ),
+            DrawOp::Image { bounds, .. } => {
+                (bounds.x(), bounds.y(), bounds.width(), bounds.height())
+            }
+        }
+    }
+
+    /// Feeds everything that affects the drawn pixels to `hasher`.
+    fn hash(&self, hasher: &mut impl Hasher) {
+        let mut floats = |values: &[f32]| values.iter().for_each(|v| v.to_bits().hash(hasher));
+        match self {
+            DrawOp::Rect {
+                bounds,
+                color,
+                corner_radii,
+            } => {
+                floats(&[bounds.x(), bounds.y(), bounds.width(), bounds.height()]);
+                floats(&[color.red(), color.green(), color.blue(), color.alpha()]);
+                floats(corner_radii);
+            }
+            DrawOp::Border {
+                bounds,
+                color,
+                border,
+            } => {
+                let radii = &border.corner_radii;
+                let width = &border.width;
+                floats(&[bounds.x, bounds.y, bounds.width, bounds.height]);
+                floats(&[color.red(), color.green(), color.blue(), color.alpha()]);
+                floats(&[
+                    radii.top_left,
+                    radii.top_right,
+                    radii.bottom_left,
+                    radii.bottom_right,
+                ]);
+                [width.left, width.right, width.top, width.bottom].hash(hasher);
+            }
+            DrawOp::Text {
+                x,
+                y,
+                pixmap,
+                text,
+                font_size,
+                font_id,
+            } => {
+                (x, y, text, font_size, font_id).hash(hasher);
+                (pixmap.width(), pixmap.height()).hash(hasher);
+            }
+            DrawOp::Image {
+                bounds,
+                pixmap,
+                pixels_id,
+                fit,
+                effects,
+            } => {
+                floats(&[bounds.x(), bounds.y(), bounds.width(), bounds.height()]);
+                let tint = &effects.tint;
+                floats(&[tint.r, tint.g, tint.b, tint.a, effects.opacity]);
+                (pixels_id, pixmap.width(), pixmap.height(), fit).hash(hasher);
+                effects.grayscale.hash(hasher);
+            }
+        }
+        std::mem::discriminant(self).hash(hasher);
+    }
+}
 
-    let [tl, tr, bl, br] = *corner_radii;
-    let x = rect.x();
-    let y = rect.y();
-    let w = rect.width();
-    let h = rect.height();
+/// Resolves `render_commands` for a frame of `width` x `height` to draw ops, skipping commands
+/// that draw nothing.
+fn build_ops<'a, ImageData: RenderImage + 'a, CustomElementData: 'a>(
+    width: f32,
+    height: f32,
+    render_commands: &[RenderCommand<'a, ImageData, CustomElementData>],
+    text_generator: &'a TextGenerator,
+    diagnostics: &mut RenderDiagnostics,
+) -> Vec<DrawOp<'a>> {
+    let mut ops = Vec::with_capacity(render_commands.len());
+    let mut clips = ClipStack::new(width, height);
 
-    // Start from top-left corner (after radius)
-    pb.move_to(x + tl, y);
+    for command in render_commands {
+        match &command.config {
+            RenderCommandConfig::Text(text) => {
+                let text_data = text.text;
+                let font_size = text.font_size as u32;
+                let font_id = text.font_id as FontHandle;
 
-    // Top edge
-    pb.line_to(x + w - tr, y);
+                // Long strings are drawn from the pieces that show, see `long_text`
+                if let Some(pieces) =
+                    text_generator.cached_text_pieces(text_data, font_size, font_id)
+                {
+                    let bb = command.bounding_box;
+                    for piece in clips.visible(pieces, bb) {
+                        let Some(piece_pixmap) = text_generator
+                            .get_text(&piece.text, font_size, font_id)
+                            .and_then(|data| data.data.as_ref())
+                        else {
+                            continue;
+                        };
+                        ops.push(DrawOp::Text {
+                            x: (bb.x + piece.x).round() as i32,
+                            y: bb.y as i32,
+                            pixmap: piece_pixmap,
+                            text: &piece.text,
+                            font_size,
+                            font_id,
+                        });
+                    }
+                } else if let Some(text_pixmap) = text_generator
+                    .get_text(text_data, font_size, font_id)
+                    .and_then(|data| data.data.as_ref())
+                {
+                    ops.push(DrawOp::Text {
+                        x: command.bounding_box.x as i32,
+                        y: command.bounding_box.y as i32,
+                        pixmap: text_pixmap,
+                        text: text_data,
+                        font_size,
+                        font_id,
+                    });
+                }
+            }
+            RenderCommandConfig::Image(image) => {
+                let Some((pixmap, fit)) = image.data.image() else {
+                    continue;
+                };
+                let Some(bounds) = clay_to_tiny_skia_rect(command.bounding_box) else {
+                    diagnostics.invalid_rects.push(command.id);
+                    continue;
+                };
+                ops.push(DrawOp::Image {
+                    bounds,
+                    pixmap,
+                    pixels_id: image.data.pixels_id(),
+                    fit,
+                    effects: image.data.effects(),
+                });
+            }
+            // TODO: Clipping isn't supported yet, the clip rects only pick the pieces of long
+            // strings that are drawn
+            RenderCommandConfig::ScissorStart() => clips.push(command.bounding_box),
+            RenderCommandConfig::ScissorEnd() => clips.pop(),
+            RenderCommandConfig::Rectangle(rect) => {
+                let Some(bounds) = clay_to_tiny_skia_rect(command.bounding_box) else {
+                    diagnostics.invalid_rects.push(command.id);
+                    continue;
+                };
 
-    // Top-right corner
-    if tr > 0.0 {
-        pb.quad_to(x + w, y, x + w, y + tr);
+                ops.push(DrawOp::Rect {
+                    bounds,
+                    color: clay_to_tiny_skia_color(rect.color),
+                    corner_radii: [
+                        rect.corner_radii.top_left,
+                        rect.corner_radii.top_right,
+                        rect.corner_radii.bottom_left,
+                        rect.corner_radii.bottom_right,
+                    ],
+                });
+            }
+            RenderCommandConfig::Border(border) => ops.push(DrawOp::Border {
+                bounds: command.bounding_box,
+                color: clay_to_tiny_skia_color(border.color),
+                border: border.clone(),
+            }),
+            RenderCommandConfig::Custom(_custom) => {
+                //render_custom_element(&command, custom, pixmap);
+            }
+            RenderCommandConfig::None() => {}
+        }
     }
 
-    // Right edge
-    pb.line_to(x + w, y + h - br);
+    ops
+}
 
-    // Bottom-right corner
-    if br > 0.0 {
-        pb.quad_to(x + w, y + h, x + w - br, y + h);
+/// Fills `rect` with the solid color of `paint`, writing whole rows directly when the color is
+/// opaque and the edges are on pixel boundaries. That's most rectangles in a frame and skips
+/// tiny-skia's pipeline setup and coverage math.
+fn fill_rect(pixmap: &mut Pixmap, rect: Rect, paint: &Paint) {
+    if !fill_opaque_rect(pixmap, rect, paint) {
+        pixmap.fill_rect(rect, paint, Transform::identity(), None);
     }
+}
+
+/// Fast path of [`fill_rect`]. Returns false if `rect` and `paint` aren't eligible.
+fn fill_opaque_rect(pixmap: &mut Pixmap, rect: Rect, paint: &Paint) -> bool {
+    let Shader::SolidColor(color) = paint.shader else {
+        return false;
+    };
+    let edges = [rect.left(), rect.top(), rect.right(), rect.bottom()];
+    if !color.is_opaque()
+        || paint.blend_mode != BlendMode::SourceOver
+        || edges.iter().any(|edge| edge.fract() != 0.0)
+    {
+        return false;
+    }
+
+    let (width, height) = (pixmap.width() as f32, pixmap.height() as f32);
+    let x0 = rect.left().clamp(0.0, width) as usize;
+    let x1 = rect.right().clamp(0.0, width) as usize;
+    let y0 = rect.top().clamp(0.0, height) as usize;
+    let y1 = rect.bottom().clamp(0.0, height) as usize;
 
-    // Bottom edge
-    pb.line_to(x + bl, y + h);
+    let pixel = color.premultiply().to_color_u8();
+    let stride = pixmap.width() as usize;
+    let pixels = pixmap.pixels_mut();
+    for row in y0..y1 {
+        pixels[row * stride + x0..row * stride + x1].fill(pixel);
+    }
+    true
+}
 
-    // Bottom-left corner
-    if bl > 0.0 {
-        pb.quad_to(x, y + h, x, y + h - bl);
+/// Fills `rect` of `pixmap` with the image shader of `paint`, changing the colors with
+/// `effects`. The image is drawn into a scratch pixmap the size of `rect` first, so the effects
+/// only touch its pixels and not what's below.
+fn draw_with_effects(pixmap: &mut Pixmap, rect: Rect, paint: &Paint, effects: &ImageEffects) {
+    let Some(area) = rect.round_out() else {
+        return;
+    };
+    let Some(mut scratch) = Pixmap::new(area.width(), area.height()) else {
+        return;
+    };
+    let offset = Transform::from_translate(-area.x() as f32, -area.y() as f32);
+    scratch.fill_rect(rect, paint, offset, None);
+    for pixel in scratch.pixels_mut() {
+        *pixel = effects.apply(*pixel);
     }
+    pixmap.draw_pixmap(
+        area.x(),
+        area.y(),
+        scratch.as_ref(),
+        &PixmapPaint::default(),
+        Transform::identity(),
+        None,
+    );
+}
+
+/// Coverage of the pixel at column `px` and row `py` by `rect` with rounded corners of `radii`
+/// (top left, top right, bottom left, bottom right), from 0.0 to 1.0.
+fn rounded_rect_coverage(rect: Rect, radii: [f32; 4], px: i32, py: i32) -> f32 {
+    let (x, y) = (px as f32, py as f32);
+    let overlap =
+        |start: f32, min: f32, max: f32| ((start + 1.0).min(max) - start.max(min)).clamp(0.0, 1.0);
+    let edges = overlap(x, rect.left(), rect.right()) * overlap(y, rect.top(), rect.bottom());
 
-    // Left edge
-    pb.line_to(x, y + tl);
+    let (cx, cy) = (x + 0.5, y + 0.5);
+    let [tl, tr, bl, br] = radii;
+    let (left, top, right, bottom) = (rect.left(), rect.top(), rect.right(), rect.bottom());
+    let corner = if cx < left + tl && cy < top + tl {
+        Some((left + tl, top + tl, tl))
+    } else if cx > right - tr && cy < top + tr {
+        Some((right - tr, top + tr, tr))
+    } else if cx < left + bl && cy > bottom - bl {
+        Some((left + bl, bottom - bl, bl))
+    } else if cx > right - br && cy > bottom - br {
+        Some((right - br, bottom - br, br))
+    } else {
+        None
+    };
+    // Distance of the pixel center to the arc approximates the covered part of the pixel
+    let round = corner.map_or(1.0, |(ox, oy, radius)| {
+        (radius + 0.5 - (cx - ox).hypot(cy - oy)).clamp(0.0, 1.0)
+    });
+    edges * round
+}
 
-    // Top-left corner
-    if tl > 0.0 {
-        pb.quad_to(x, y, x + tl, y);
+/// Blends `color` covering `coverage` of each pixel in `pixels`.
+fn blend_span(pixels: &mut [PremultipliedColorU8], color: ColorU8, coverage: f32) {
+    let coverage = (coverage * 255.0).round() as u8;
+    if coverage == 0 {
+        return;
+    }
+    let src = premultiply_coverage(color, coverage);
+    if src.alpha() == 255 {
+        pixels.fill(src);
+    } else {
+        for pixel in pixels {
+            *pixel = blend_source_over(*pixel, src);
+        }
     }
+}
+
+/// Horizontal distances from the center of a corner at height `oy` with `radius` to where the
+/// pixels of `row` stop being covered at all and start being fully covered.
+fn arc_extent(oy: f32, radius: f32, row: i32) -> (f32, f32) {
+    let dy = row as f32 + 0.5 - oy;
+    let extent = |r: f32| (r * r - dy * dy).max(0.0).sqrt();
+    (extent(radius + 0.5), extent((radius - 0.5).max(0.0)))
+}
 
-    pb.close();
-    pb.finish()
+/// `radii` limited to half the shorter side of `rect`.
+fn clamp_radii(rect: Rect, radii: &[f32; 4]) -> [f32; 4] {
+    let limit = rect.width().min(rect.height()) * 0.5;
+    radii.map(|radius| radius.clamp(0.0, limit))
 }
 
-/// This is a port of Clay's raylib renderer using tiny-skia as the drawing API.
-pub fn clay_tiny_skia_render<'a, ImageData: 'a, CustomElementData: 'a>(
+/// Columns of `row` covered by `rect` with rounded corners of `radii`, as the columns with any
+/// coverage and the columns within them that are covered across their whole width.
+fn row_extent(rect: Rect, radii: [f32; 4], row: i32) -> (Range<i32>, Range<i32>) {
+    let [tl, tr, bl, br] = radii;
+    let (left, top, right, bottom) = (rect.left(), rect.top(), rect.right(), rect.bottom());
+    let cy = row as f32 + 0.5;
+    let (mut start, mut full_start) = (left.floor(), left.ceil());
+    let (mut full_end, mut end) = (right.floor(), right.ceil());
+
+    let left_corner = if cy < top + tl {
+        Some((left + tl, top + tl, tl))
+    } else if cy > bottom - bl {
+        Some((left + bl, bottom - bl, bl))
+    } else {
+        None
+    };
+    if let Some((ox, oy, radius)) = left_corner {
+        let (outer, inner) = arc_extent(oy, radius, row);
+        start = start.max((ox - outer - 0.5).floor());
+        full_start = full_start.max((ox - inner - 0.5).ceil() + 1.0);
+    }
+    let right_corner = if cy < top + tr {
+        Some((right - tr, top + tr, tr))
+    } else if cy > bottom - br {
+        Some((right - br, bottom - br, br))
+    } else {
+        None
+    };
+    if let Some((ox, oy, radius)) = right_corner {
+        let (outer, inner) = arc_extent(oy, radius, row);
+        end = end.min((ox + outer - 0.5).ceil() + 1.0);
+        full_end = full_end.min((ox + inner - 0.5).floor());
+    }
+    (start as i32..end as i32, full_start as i32..full_end as i32)
+}
+
+/// Fills `rect` with `color` and rounded corners of `corner_radii`. Only the pixels on the
+/// edges of each row get their own coverage, the span between them is filled at once. That's
+/// faster than filling a path, which matters for themes where most widgets are rounded.
+fn fill_rounded_rect(pixmap: &mut Pixmap, rect: Rect, corner_radii: &[f32; 4], color: Color) {
+    fill_rounded_shape(pixmap, rect, clamp_radii(rect, corner_radii), None, color);
+}
+
+/// Draws the border of `rect` with rounded corners of `corner_radii` and `widths` (left, top,
+/// right, bottom). It covers what a background of the same size and radii covers minus the
+/// inside of the border, so the two line up pixel for pixel.
+fn fill_rounded_border(
     pixmap: &mut Pixmap,
-    render_commands: &[RenderCommand<'a, ImageData, CustomElementData>],
-    text_generator: &TextGenerator,
-    /*
-    mut render_custom_element: impl FnMut(
-        &RenderCommand<'a, ImageData, CustomElementData>,
-        &Custom<'a, CustomElementData>,
-        &mut Pixmap,
-    ),
-     */
+    rect: Rect,
+    corner_radii: &[f32; 4],
+    widths: [f32; 4],
+    color: Color,
 ) {
-    // Save/restore stack for clipping
-    let clip_stack: Vec<Option<Mask>> = Vec::new();
+    if widths.iter().all(|width| *width <= 0.0) {
+        return;
+    }
+    let radii = clamp_radii(rect, corner_radii);
+    let [left, top, right, bottom] = widths;
+    let inner = Rect::from_ltrb(
+        rect.left() + left,
+        rect.top() + top,
+        rect.right() - right,
+        rect.bottom() - bottom,
+    )
+    .map(|inner| {
+        // The inner corners share their centers with the outer ones for even widths
+        let [tl, tr, bl, br] = radii;
+        let inner_radii = [
+            tl - left.max(top),
+            tr - right.max(top),
+            bl - left.max(bottom),
+            br - right.max(bottom),
+        ];
+        (inner, clamp_radii(inner, &inner_radii))
+    });
+    fill_rounded_shape(pixmap, rect, radii, inner, color);
+}
 
-    for command in render_commands {
-        match &command.config {
-            RenderCommandConfig::Text(text) => {
-                let text_data = text.text;
-                let font_size = text.font_size as u32;
-                let font_id = text.font_id as FontHandle;
+/// Fills `rect` with rounded corners of `radii` with `color`, leaving out `hole` with its own
+/// radii.
+fn fill_rounded_shape(
+    pixmap: &mut Pixmap,
+    rect: Rect,
+    radii: [f32; 4],
+    hole: Option<(Rect, [f32; 4])>,
+    color: Color,
+) {
+    let (width, height) = (pixmap.width() as i32, pixmap.height() as i32);
+    let clamp = |range: Range<i32>| range.start.clamp(0, width)..range.end.clamp(0, width);
+    let rows = (rect.top().floor() as i32).max(0)..(rect.bottom().ceil() as i32).min(height);
+    let color = color.to_color_u8();
+    let pixels = pixmap.pixels_mut();
+    for row in rows {
+        let coverage = |column| {
+            let outer = rounded_rect_coverage(rect, radii, column, row);
+            hole.map_or(outer, |(inner, inner_radii)| {
+                (outer - rounded_rect_coverage(inner, inner_radii, column, row)).max(0.0)
+            })
+        };
+        let (any, full) = row_extent(rect, radii, row);
+        let any = clamp(any);
 
-                if let Some(data) = text_generator.get_text(text_data, font_size, font_id) {
-                    // Option 1: Direct draw if text_pixmap is already colored
-                    let mut paint = PixmapPaint::default();
-                    paint.blend_mode = BlendMode::SourceOver;
-
-                    pixmap.draw_pixmap(
-                        command.bounding_box.x as i32,
-                        command.bounding_box.y as i32,
-                        data.data.as_ref(),
-                        &paint,
-                        Transform::identity(),
-                        None,
-                    );
-                }
+        // Columns in the middle are either filled at once or, inside the hole, skipped
+        let (y0, y1) = (row as f32, row as f32 + 1.0);
+        let (mut middle, fill) = match hole {
+            Some((inner, _)) if y0 >= inner.bottom() || y1 <= inner.top() => (clamp(full), true),
+            Some((inner, inner_radii)) if y0 >= inner.top() && y1 <= inner.bottom() => {
+                (clamp(row_extent(inner, inner_radii, row).1), false)
+            }
+            Some(_) => (0..0, false),
+            None => (clamp(full), true),
+        };
+        if middle.is_empty() {
+            middle = any.end..any.end;
+        }
 
-                /*
-                if let Some(text_pixmap) = text_pixmaps.get(text.font_id as usize) {
-                    let color = clay_to_tiny_skia_color(text.color);
-
-                    // Option 1: Direct draw if text_pixmap is already colored
-                    let mut paint = PixmapPaint::default();
-                    paint.opacity = color.alpha();
-                    paint.blend_mode = BlendMode::SourceOver;
-
-                    let current_clip = clip_stack.last().and_then(|c| c.as_ref());
-
-                    pixmap.draw_pixmap(
-                        command.bounding_box.x as i32,
-                        command.bounding_box.y as i32,
-                        text_pixmap.as_ref(),
-                        &paint,
-                        Transform::identity(),
-                        current_clip,
-                    );
-
-                    // Option 2: If text_pixmap is alpha-only, create colored version first
-                    // if let Some(colored_text) = create_colored_text_pixmap(text_pixmap, color) {
-                    //     pixmap.draw_pixmap(
-                    //         command.bounding_box.x as i32,
-                    //         command.bounding_box.y as i32,
-                    //         colored_text.as_ref(),
-                    //         &PixmapPaint::default(),
-                    //         Transform::identity(),
-                    //         current_clip,
-                    //     );
-                    // }
-                }
+        let row_pixels = &mut pixels[(row * width) as usize..((row + 1) * width) as usize];
+        if fill && !middle.is_empty() {
+            let span = middle.start as usize..middle.end as usize;
+            blend_span(&mut row_pixels[span], color, coverage(middle.start));
+        }
+        for column in (any.start..middle.start).chain(middle.end..any.end) {
+            let pixel = column as usize;
+            blend_span(&mut row_pixels[pixel..pixel + 1], color, coverage(column));
+        }
+    }
+}
 
-                 */
-            }
-            RenderCommandConfig::Image(_image) => {
-                /*
-                // image.data should be a Pixmap containing the image data
-                let image_pixmap = &image.data;
-
-                let mut paint = PixmapPaint::default();
-                paint.opacity = 1.0;
-                paint.blend_mode = BlendMode::SourceOver;
-
-                let current_clip = clip_stack.last().and_then(|c| c.as_ref());
-
-                // For scaling/fitting, you might need to create a scaled version first
-                // or use Transform to scale the image to fit the bounding box
-                let scale_x = command.bounding_box.width / image_pixmap.width() as f32;
-                let scale_y = command.bounding_box.height / image_pixmap.height() as f32;
-                let transform = Transform::from_scale(scale_x, scale_y)
-                    .post_translate(command.bounding_box.x, command.bounding_box.y);
-
-                pixmap.draw_pixmap(
-                    0, 0, // Using transform for positioning instead
-                    image_pixmap.as_ref(),
-                    &paint,
+/// Draws `op` into `pixmap`, which covers the screen starting at `origin`. Geometry is moved
+/// instead of using a transform so rectangles keep tiny-skia's fast path and every tile
+/// rasterizes the same pixels.
+fn draw_op(pixmap: &mut Pixmap, op: &DrawOp, origin: (f32, f32)) {
+    let (ox, oy) = origin;
+
+    match op {
+        DrawOp::Text {
+            x,
+            y,
+            pixmap: text_pixmap,
+            ..
+        } => {
+            let paint = PixmapPaint {
+                blend_mode: BlendMode::SourceOver,
+                ..Default::default()
+            };
+
+            pixmap.draw_pixmap(
+                x - ox as i32,
+                y - oy as i32,
+                text_pixmap.as_ref(),
+                &paint,
+                Transform::identity(),
+                None,
+            );
+        }
+        DrawOp::Image {
+            bounds,
+            pixmap: image,
+            fit,
+            effects,
+            ..
+        } => {
+            let size = (image.width() as f32, image.height() as f32);
+            let element = (bounds.x(), bounds.y(), bounds.width(), bounds.height());
+            let (x, y, width, height) = fit_rect(size, element, *fit);
+            let transform =
+                Transform::from_row(width / size.0, 0.0, 0.0, height / size.1, x - ox, y - oy);
+            let paint = Paint {
+                shader: Pattern::new(
+                    image.as_ref(),
+                    SpreadMode::Pad,
+                    FilterQuality::Bilinear,
+                    effects.opacity,
                     transform,
-                    current_clip,
-                );
+                ),
+                ..Default::default()
+            };
 
-                 */
-            }
-            RenderCommandConfig::ScissorStart() => {
-                /*
-                // Create a clip mask for the bounding box
-                let clip_rect = clay_to_tiny_skia_rect(command.bounding_box);
-                let mut new_clip_mask = Mask::new();
-
-                // Create a path for the clipping rectangle
-                if let Some(clip_path) = PathBuilder::from_rect(clip_rect) {
-                    new_clip_mask.set_path(
-                        pixmap.width(),
-                        pixmap.height(),
-                        &clip_path,
-                        FillRule::Winding,
-                        false, // anti-alias
-                    );
-                    clip_stack.push(Some(new_clip_mask));
-                } else {
-                    clip_stack.push(None);
+            // Covering images overflow the element, only the part inside of it is drawn
+            let visible = Rect::from_ltrb(
+                x.max(bounds.left()) - ox,
+                y.max(bounds.top()) - oy,
+                (x + width).min(bounds.right()) - ox,
+                (y + height).min(bounds.bottom()) - oy,
+            );
+            match visible {
+                Some(visible) if effects.changes_colors() => {
+                    draw_with_effects(pixmap, visible, &paint, effects)
                 }
-                 */
+                Some(visible) => pixmap.fill_rect(visible, &paint, Transform::identity(), None),
+                None => {}
             }
-            RenderCommandConfig::ScissorEnd() => {
-                //clip_stack.pop();
-            }
-            RenderCommandConfig::Rectangle(rect) => {
-                let mut paint = Paint::default();
-                paint.set_color(clay_to_tiny_skia_color(rect.color));
-                paint.anti_alias = true;
+        }
+        DrawOp::Rect {
+            bounds,
+            color,
+            corner_radii,
+        } => {
+            let mut paint = Paint::default();
+            paint.set_color(*color);
+            paint.anti_alias = true;
 
-                let bounds = clay_to_tiny_skia_rect(command.bounding_box);
-                let current_clip = None; //clip_stack.last().and_then(|c| c.as_ref());
+            let Some(bounds) = Rect::from_xywh(
+                bounds.x() - ox,
+                bounds.y() - oy,
+                bounds.width(),
+                bounds.height(),
+            ) else {
+                return;
+            };
 
-                if rect.corner_radii.top_left > 0.0
-                    || rect.corner_radii.top_right > 0.0
-                    || rect.corner_radii.bottom_left > 0.0
-                    || rect.corner_radii.bottom_right > 0.0
-                {
-                    let corner_radii = [
-                        rect.corner_radii.top_left,
-                        rect.corner_radii.top_right,
-                        rect.corner_radii.bottom_left,
-                        rect.corner_radii.bottom_right,
-                    ];
-
-                    if let Some(path) = create_rounded_rect_path(bounds, &corner_radii) {
-                        pixmap.fill_path(
-                            &path,
-                            &paint,
-                            FillRule::Winding,
-                            Transform::identity(),
-                            current_clip,
-                        );
-                    }
-                } else {
-                    pixmap.fill_rect(bounds, &paint, Transform::identity(), current_clip);
-                }
+            if corner_radii.iter().any(|radius| *radius > 0.0) {
+                fill_rounded_rect(pixmap, bounds, corner_radii, *color);
+            } else {
+                fill_rect(pixmap, bounds, &paint);
             }
-            RenderCommandConfig::Border(border) => {
-                let mut paint = Paint::default();
-                paint.set_color(clay_to_tiny_skia_color(border.color));
-                paint.anti_alias = true;
-
-                let bb = &command.bounding_box;
-                let current_clip = clip_stack.last().and_then(|c| c.as_ref());
-
-                // Draw each border side using fill rectangles
-
-                // Left border
-                if border.width.left > 0 {
-                    let rect = Rect::from_xywh(
-                        bb.x,
-                        bb.y + border.corner_radii.top_left,
-                        border.width.left as f32,
-                        bb.height - border.corner_radii.top_left - border.corner_radii.bottom_left,
-                    );
-                    if let Some(rect) = rect {
-                        pixmap.fill_rect(rect, &paint, Transform::identity(), current_clip);
-                    }
-                }
+        }
+        DrawOp::Border {
+            bounds,
+            color,
+            border,
+        } => {
+            let Some(rect) =
+                Rect::from_xywh(bounds.x - ox, bounds.y - oy, bounds.width, bounds.height)
+            else {
+                return;
+            };
+            let radii = &border.corner_radii;
+            let width = &border.width;
+            fill_rounded_border(
+                pixmap,
+                rect,
+                &[
+                    radii.top_left,
+                    radii.top_right,
+                    radii.bottom_left,
+                    radii.bottom_right,
+                ],
+                [width.left, width.top, width.right, width.bottom].map(f32::from),
+                *color,
+            );
+        }
+    }
+}
 
-                // Right border
-                if border.width.right > 0 {
-                    let rect = Rect::from_xywh(
-                        bb.x + bb.width - border.width.right as f32,
-                        bb.y + border.corner_radii.top_right,
-                        border.width.right as f32,
-                        bb.height
-                            - border.corner_radii.top_right
-                            - border.corner_radii.bottom_right,
-                    );
-                    if let Some(rect) = rect {
-                        pixmap.fill_rect(rect, &paint, Transform::identity(), current_clip);
-                    }
-                }
+/// This is a port of Clay's raylib renderer using tiny-skia as the drawing API. Draws every
+/// command straight into `pixmap`, see [`TileRenderer`] for rendering frames incrementally.
+pub fn clay_tiny_skia_render<'a, ImageData: RenderImage + 'a, CustomElementData: 'a>(
+    pixmap: &mut Pixmap,
+    render_commands: &[RenderCommand<'a, ImageData, CustomElementData>],
+    text_generator: &'a TextGenerator,
+) -> RenderDiagnostics {
+    let mut diagnostics = RenderDiagnostics::default();
+    let (width, height) = (pixmap.width() as f32, pixmap.height() as f32);
+    for op in build_ops(
+        width,
+        height,
+        render_commands,
+        text_generator,
+        &mut diagnostics,
+    ) {
+        draw_op(pixmap, &op, (0.0, 0.0));
+    }
+    diagnostics
+}
 
-                // Top border
-                if border.width.top > 0 {
-                    let rect = Rect::from_xywh(
-                        bb.x + border.corner_radii.top_left,
-                        bb.y,
-                        bb.width - border.corner_radii.top_left - border.corner_radii.top_right,
-                        border.width.top as f32,
-                    );
-                    if let Some(rect) = rect {
-                        pixmap.fill_rect(rect, &paint, Transform::identity(), current_clip);
-                    }
-                }
+/// Width and height in pixels of the tiles [`TileRenderer`] splits the screen into.
+pub const TILE_SIZE: u32 = 64;
 
-                // Bottom border
-                if border.width.bottom > 0 {
-                    let rect = Rect::from_xywh(
-                        bb.x + border.corner_radii.bottom_left,
-                        bb.y + bb.height - border.width.bottom as f32,
-                        bb.width
-                            - border.corner_radii.bottom_left
-                            - border.corner_radii.bottom_right,
-                        border.width.bottom as f32,
-                    );
-                    if let Some(rect) = rect {
-                        pixmap.fill_rect(rect, &paint, Transform::identity(), current_clip);
-                    }
-                }
+/// Renders frames in tiles. Commands are binned into the tiles they touch and only tiles whose
+/// commands changed since the previous frame are rasterized again, split over several threads.
+/// Typical frames only change a few widgets, so most of the screen is kept as is.
+pub struct TileRenderer {
+    pixmap: Pixmap,
+    /// Hash of the commands binned into each tile when it was last drawn, `None` for tiles that
+    /// have to be drawn.
+    tile_hashes: Vec<Option<u64>>,
+    threads: usize,
+    redrawn_tiles: usize,
+    frame_hash: u64,
+}
 
-                // For corners with radii, we need to draw arcs using paths
-                // tiny-skia doesn't have direct arc drawing, so we approximate with curves
+impl Default for TileRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-                // Helper to create an arc path (approximate with quadratic curves)
-                let create_arc_path = |center_x: f32,
-                                       center_y: f32,
-                                       radius: f32,
-                                       start_angle: f32,
-                                       end_angle: f32|
-                 -> Option<Path> {
-                    let mut pb = PathBuilder::new();
+impl TileRenderer {
+    pub fn new() -> Self {
+        Self {
+            pixmap: Pixmap::new(1, 1).unwrap(),
+            tile_hashes: Vec::new(),
+            threads: std::thread::available_parallelism().map_or(1, |threads| threads.get()),
+            redrawn_tiles: 0,
+            frame_hash: 0,
+        }
+    }
 
-                    // Simple approximation - for better arcs, use multiple cubic curves
-                    let start_x = center_x + radius * start_angle.to_radians().cos();
-                    let start_y = center_y + radius * start_angle.to_radians().sin();
-                    let end_x = center_x + radius * end_angle.to_radians().cos();
-                    let end_y = center_y + radius * end_angle.to_radians().sin();
+    /// The rendered frame.
+    pub fn pixmap(&self) -> &Pixmap {
+        &self.pixmap
+    }
 
-                    pb.move_to(start_x, start_y);
-                    pb.line_to(end_x, end_y);
+    /// Sets how many threads rasterize tiles, including the calling thread. Defaults to the
+    /// number of cores.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
 
-                    pb.finish()
-                };
+    /// Number of tiles rasterized by the last [`TileRenderer::render`].
+    pub fn redrawn_tiles(&self) -> usize {
+        self.redrawn_tiles
+    }
+
+    /// Hash of everything the last [`TileRenderer::render`] drew and the frame size. Frames
+    /// with the same hash look the same. Images are identified by [`RenderImage::pixels_id`].
+    pub fn frame_hash(&self) -> u64 {
+        self.frame_hash
+    }
+
+    /// Draws every tile on the next frame, for when something outside of the render commands
+    /// changed.
+    pub fn invalidate(&mut self) {
+        self.tile_hashes.fill(None);
+    }
 
-                // Draw corner arcs if needed
-                if border.corner_radii.top_left > 0.0 {
-                    let center_x = bb.x + border.corner_radii.top_left;
-                    let center_y = bb.y + border.corner_radii.top_left;
-                    if let Some(path) = create_arc_path(
-                        center_x,
-                        center_y,
-                        border.corner_radii.top_left,
-                        180.0,
-                        270.0,
-                    ) {
-                        let stroke_paint = paint;
-                        pixmap.stroke_path(
-                            &path,
-                            &stroke_paint,
-                            &Stroke::default(),
-                            Transform::identity(),
-                            current_clip,
-                        );
+    /// Renders `render_commands` into a frame of `width` x `height` pixels.
+    pub fn render<'a, ImageData: RenderImage + 'a, CustomElementData: 'a>(
+        &mut self,
+        width: u32,
+        height: u32,
+        render_commands: &[RenderCommand<'a, ImageData, CustomElementData>],
+        text_generator: &'a TextGenerator,
+    ) -> RenderDiagnostics {
+        let (width, height) = (width.max(1), height.max(1));
+        if (self.pixmap.width(), self.pixmap.height()) != (width, height) {
+            self.pixmap = Pixmap::new(width, height).unwrap();
+            self.tile_hashes.clear();
+        }
+
+        let mut diagnostics = RenderDiagnostics::default();
+        let ops = build_ops(
+            width as f32,
+            height as f32,
+            render_commands,
+            text_generator,
+            &mut diagnostics,
+        );
+
+        let mut hasher = DefaultHasher::new();
+        (width, height).hash(&mut hasher);
+        ops.iter().for_each(|op| op.hash(&mut hasher));
+        self.frame_hash = hasher.finish();
+
+        let columns = width.div_ceil(TILE_SIZE) as usize;
+        let rows = height.div_ceil(TILE_SIZE) as usize;
+        let bins = bin_ops(&ops, columns, rows);
+
+        self.tile_hashes.resize(columns * rows, None);
+        let dirty: Vec<bool> = bins
+            .iter()
+            .zip(&mut self.tile_hashes)
+            .map(|(bin, last_hash)| {
+                let mut hasher = DefaultHasher::new();
+                bin.iter().for_each(|index| ops[*index].hash(&mut hasher));
+                let hash = Some(hasher.finish());
+                std::mem::replace(last_hash, hash) != hash
+            })
+            .collect();
+        self.redrawn_tiles = dirty.iter().filter(|dirty| **dirty).count();
+
+        // Each job is a row of tiles, which is a separate slice of the frame
+        let band_size = (width * TILE_SIZE * 4) as usize;
+        let bands = Mutex::new(self.pixmap.data_mut().chunks_mut(band_size).enumerate());
+        let rasterize = || {
+            let mut tile = Pixmap::new(TILE_SIZE, TILE_SIZE).unwrap();
+            while let Some((row, band)) = bands.lock().unwrap().next() {
+                for column in 0..columns {
+                    let index = row * columns + column;
+                    if !dirty[index] {
+                        continue;
+                    }
+
+                    let (x, y) = (column as u32 * TILE_SIZE, row as u32 * TILE_SIZE);
+                    tile.fill(Color::TRANSPARENT);
+                    for op in &bins[index] {
+                        draw_op(&mut tile, &ops[*op], (x as f32, y as f32));
                     }
+                    copy_tile(&tile, band, width, x);
                 }
-                // ... similar for other corners
             }
-            RenderCommandConfig::Custom(_custom) => {
-                //render_custom_element(&command, custom, pixmap);
+        };
+
+        let threads = self
+            .threads
+            .min(self.redrawn_tiles.div_ceil(columns))
+            .max(1);
+        std::thread::scope(|scope| {
+            for _ in 1..threads {
+                scope.spawn(rasterize);
+            }
+            rasterize();
+        });
+
+        diagnostics
+    }
+}
+
+/// Indices of the ops touching each tile, in drawing order.
+fn bin_ops(ops: &[DrawOp], columns: usize, rows: usize) -> Vec<Vec<usize>> {
+    let mut bins = vec![Vec::new(); columns * rows];
+    let tile = TILE_SIZE as f32;
+
+    for (index, op) in ops.iter().enumerate() {
+        let (x, y, width, height) = op.bounds();
+        // Anti-aliasing can spill into the pixel next to the bounds
+        let range = |start: f32, size: f32, count: usize| {
+            let first = ((start - 1.0) / tile).floor().max(0.0);
+            let last = ((start + size + 1.0) / tile)
+                .floor()
+                .min(count as f32 - 1.0);
+            (first <= last).then_some(first as usize..=last as usize)
+        };
+
+        let (Some(columns_range), Some(rows_range)) =
+            (range(x, width, columns), range(y, height, rows))
+        else {
+            continue;
+        };
+        for row in rows_range {
+            for column in columns_range.clone() {
+                bins[row * columns + column].push(index);
             }
-            RenderCommandConfig::None() => {}
         }
     }
+
+    bins
+}
+
+/// Copies `tile` into `band`, a row of tiles of a frame `width` pixels wide, at column `x`.
+fn copy_tile(tile: &Pixmap, band: &mut [u8], width: u32, x: u32) {
+    let tile_width = (TILE_SIZE.min(width - x) * 4) as usize;
+    let row_size = (width * 4) as usize;
+    let x = (x * 4) as usize;
+
+    for (dst, src) in band
+        .chunks_mut(row_size)
+        .zip(tile.data().chunks(TILE_SIZE as usize * 4))
+    {
+        dst[x..x + tile_width].copy_from_slice(&src[..tile_width]);
+    }
 }
 
 pub type TinySkiaClayScope<'clay, 'render, CustomElements> =
@@ -456,3 +1002,330 @@ pub fn create_measure_text_function(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use background_worker::WorkSystem;
+    use clay_layout::layout::LayoutDirection;
+    use clay_layout::render_commands::{CornerRadii, Rectangle};
+    use clay_layout::{Clay, Declaration, fixed, grow};
+
+    fn rect_command<'a>(id: u32, bounding_box: BoundingBox) -> RenderCommand<'a, (), ()> {
+        RenderCommand {
+            bounding_box,
+            config: RenderCommandConfig::Rectangle(Rectangle {
+                color: ClayColor::u_rgb(0xFF, 0, 0),
+                corner_radii: CornerRadii {
+                    top_left: 4.0,
+                    top_right: 0.0,
+                    bottom_left: 0.0,
+                    bottom_right: 0.0,
+                },
+            }),
+            id,
+            z_index: 0,
+        }
+    }
+
+    #[test]
+    fn test_premultiplied_coverage() {
+        let ramp = [0, 1, 64, 128, 191, 254, 255];
+        let pixmap = pixmap_from_a8_data(ramp.len() as u32, 1, &ramp).unwrap();
+        for (pixel, alpha) in pixmap.pixels().iter().zip(ramp) {
+            assert_eq!(
+                (pixel.red(), pixel.green(), pixel.blue(), pixel.alpha()),
+                (alpha, alpha, alpha, alpha)
+            );
+        }
+
+        let red = ColorU8::from_rgba(255, 0, 0, 128);
+        let pixel = premultiply_coverage(red, 128);
+        assert_eq!((pixel.red(), pixel.green(), pixel.alpha()), (64, 0, 64));
+
+        // Overlapping coverage accumulates instead of replacing
+        let half = premultiply_coverage(ColorU8::from_rgba(255, 255, 255, 255), 128);
+        let both = blend_source_over(half, half);
+        assert_eq!((both.red(), both.alpha()), (192, 192));
+    }
+
+    /// Anti-aliased edge coverage drawn over a background must not fringe. These are the
+    /// expected pixels of a white coverage ramp composited on dark blue.
+    #[test]
+    fn test_aa_edge_golden() {
+        let ramp = [0, 32, 96, 160, 224, 255];
+        let golden: [[u8; 4]; 6] = [
+            [0, 0, 64, 255],
+            [32, 32, 88, 255],
+            [96, 96, 136, 255],
+            [160, 160, 184, 255],
+            [224, 224, 232, 255],
+            [255, 255, 255, 255],
+        ];
+
+        let text = pixmap_from_a8_data(ramp.len() as u32, 1, &ramp).unwrap();
+        let mut target = Pixmap::new(ramp.len() as u32, 1).unwrap();
+        target.fill(Color::from_rgba8(0, 0, 64, 255));
+        draw_text_pixmap(&mut target, &text, 0, 0, Color::WHITE);
+
+        let pixels: Vec<[u8; 4]> = target
+            .pixels()
+            .iter()
+            .map(|p| [p.red(), p.green(), p.blue(), p.alpha()])
+            .collect();
+        for (pixel, expected) in pixels.iter().zip(golden) {
+            for (channel, expected) in pixel.iter().zip(expected) {
+                assert!(channel.abs_diff(expected) <= 1, "{:?}", pixels);
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_rects_are_skipped() {
+        let worker = WorkSystem::new(1);
+        let text_generator = TextGenerator::new(&worker);
+        let mut pixmap = Pixmap::new(16, 16).unwrap();
+
+        let commands = [
+            rect_command(1, BoundingBox::new(0.0, 0.0, 0.0, 10.0)),
+            rect_command(2, BoundingBox::new(0.0, 0.0, 10.0, -3.0)),
+            rect_command(3, BoundingBox::new(0.0, 0.0, f32::NAN, 10.0)),
+            rect_command(4, BoundingBox::new(2.0, 2.0, 8.0, 8.0)),
+        ];
+
+        let diagnostics = clay_tiny_skia_render(&mut pixmap, &commands, &text_generator);
+
+        assert_eq!(diagnostics.invalid_rects, [1, 2, 3]);
+        // The valid rectangle after the broken ones is still drawn
+        assert_eq!(pixmap.pixel(6, 6).unwrap().red(), 0xFF);
+    }
+
+    #[test]
+    fn test_tiles_match_direct_rendering() {
+        let worker = WorkSystem::new(1);
+        let text_generator = TextGenerator::new(&worker);
+
+        // Rounded and anti-aliased edges crossing tile borders
+        let commands = [
+            rect_command(1, BoundingBox::new(10.5, 20.25, 150.0, 90.0)),
+            rect_command(2, BoundingBox::new(60.0, 60.0, 9.5, 120.0)),
+            rect_command(3, BoundingBox::new(120.3, 5.0, 80.0, 70.7)),
+        ];
+
+        let mut direct = Pixmap::new(200, 150).unwrap();
+        clay_tiny_skia_render(&mut direct, &commands, &text_generator);
+
+        for threads in [1, 3] {
+            let mut renderer = TileRenderer::new();
+            renderer.set_threads(threads);
+            renderer.render(200, 150, &commands, &text_generator);
+            assert!(renderer.pixmap().data() == direct.data());
+        }
+    }
+
+    #[test]
+    fn test_frame_hash() {
+        let worker = WorkSystem::new(1);
+        let text_generator = TextGenerator::new(&worker);
+        let mut renderer = TileRenderer::new();
+
+        let mut commands = vec![rect_command(1, BoundingBox::new(10.0, 10.0, 20.0, 20.0))];
+        renderer.render(64, 64, &commands, &text_generator);
+        let hash = renderer.frame_hash();
+        renderer.render(64, 64, &commands, &text_generator);
+        assert_eq!(renderer.frame_hash(), hash);
+
+        // Ids don't change what is drawn
+        commands[0].id = 2;
+        renderer.render(64, 64, &commands, &text_generator);
+        assert_eq!(renderer.frame_hash(), hash);
+
+        commands[0].bounding_box.x = 11.0;
+        renderer.render(64, 64, &commands, &text_generator);
+        assert_ne!(renderer.frame_hash(), hash);
+
+        commands[0].bounding_box.x = 10.0;
+        renderer.render(64, 80, &commands, &text_generator);
+        assert_ne!(renderer.frame_hash(), hash);
+    }
+
+    #[test]
+    fn test_unchanged_tiles_are_skipped() {
+        let worker = WorkSystem::new(1);
+        let text_generator = TextGenerator::new(&worker);
+        let mut renderer = TileRenderer::new();
+
+        let mut commands = vec![
+            rect_command(1, BoundingBox::new(0.0, 0.0, 256.0, 128.0)),
+            rect_command(2, BoundingBox::new(10.0, 10.0, 20.0, 20.0)),
+        ];
+        renderer.render(256, 128, &commands, &text_generator);
+        assert_eq!(renderer.redrawn_tiles(), 8);
+
+        renderer.render(256, 128, &commands, &text_generator);
+        assert_eq!(renderer.redrawn_tiles(), 0);
+
+        // Moving the small rectangle only touches the tiles it leaves and enters
+        commands[1].bounding_box = BoundingBox::new(140.0, 80.0, 20.0, 20.0);
+        renderer.render(256, 128, &commands, &text_generator);
+        assert_eq!(renderer.redrawn_tiles(), 2);
+        assert_eq!(
+            renderer.pixmap().pixel(20, 20),
+            renderer.pixmap().pixel(100, 20)
+        );
+
+        renderer.invalidate();
+        renderer.render(256, 128, &commands, &text_generator);
+        assert_eq!(renderer.redrawn_tiles(), 8);
+
+        renderer.render(300, 128, &commands, &text_generator);
+        assert_eq!(renderer.redrawn_tiles(), 10);
+    }
+
+    #[test]
+    fn test_rounded_rect_coverage() {
+        let white = Color::from_rgba8(255, 255, 255, 255);
+        let rect = Rect::from_xywh(2.0, 2.0, 20.0, 20.0).unwrap();
+        let mut pixmap = Pixmap::new(24, 24).unwrap();
+        fill_rounded_rect(&mut pixmap, rect, &[8.0; 4], white);
+
+        let alpha = |x, y| pixmap.pixel(x, y).unwrap().alpha();
+        assert_eq!(alpha(2, 2), 0);
+        assert_eq!(alpha(12, 12), 255);
+        assert_eq!(alpha(2, 12), 255);
+        assert_eq!(alpha(21, 12), 255);
+        assert_eq!(alpha(12, 22), 0);
+        // Covers about the area of a square with quarter circles cut from its corners
+        let area: f32 = pixmap
+            .pixels()
+            .iter()
+            .map(|p| p.alpha() as f32 / 255.0)
+            .sum();
+        let expected = 400.0 - (4.0 - std::f32::consts::PI) * 64.0;
+        assert!((area - expected).abs() < expected * 0.01, "{}", area);
+
+        // Each pixel gets the coverage of its own, whether it's on an edge or within a span
+        let rect = Rect::from_xywh(3.3, 2.7, 40.0, 20.0).unwrap();
+        let radii = [4.0, 9.0, 0.0, 10.0];
+        let mut pixmap = Pixmap::new(48, 32).unwrap();
+        fill_rounded_rect(&mut pixmap, rect, &radii, white);
+        for (index, pixel) in pixmap.pixels().iter().enumerate() {
+            let (x, y) = ((index % 48) as i32, (index / 48) as i32);
+            let coverage = rounded_rect_coverage(rect, radii, x, y);
+            assert_eq!(
+                pixel.alpha(),
+                (coverage * 255.0).round() as u8,
+                "{} {}",
+                x,
+                y
+            );
+        }
+    }
+
+    #[test]
+    fn test_separators_between_children() {
+        let worker = WorkSystem::new(1);
+        let text_generator = TextGenerator::new(&worker);
+        let mut clay = Clay::new(Dimensions::new(32.0, 64.0));
+        let mut scope = clay.begin::<(), ()>();
+
+        let mut list = Declaration::new();
+        list.layout()
+            .width(fixed!(32.0))
+            .direction(LayoutDirection::TopToBottom)
+            .end()
+            .border()
+            .between_children(1)
+            .color(ClayColor::u_rgb(0xFF, 0, 0))
+            .end();
+        let mut row = Declaration::new();
+        row.layout()
+            .width(grow!())
+            .height(fixed!(10.0))
+            .end()
+            .background_color(ClayColor::u_rgb(0, 0, 0xFF));
+        scope.with(&list, |scope| {
+            for _ in 0..3 {
+                scope.with(&row, |_| {});
+            }
+        });
+        let commands = scope.end().collect::<Vec<_>>();
+
+        let mut pixmap = Pixmap::new(32, 64).unwrap();
+        clay_tiny_skia_render(&mut pixmap, &commands, &text_generator);
+        // Drawn over the first line of each row after the first
+        let red = |y| pixmap.pixel(16, y).unwrap().red() == 0xFF;
+        assert!(!red(0) && red(10) && !red(15) && red(20) && !red(25));
+    }
+
+    /// Expected coverage of a 1 pixel wide border with a radius of 4 around a 10x10 element.
+    #[test]
+    fn test_rounded_border_golden() {
+        let golden: [[u8; 10]; 5] = [
+            [0, 51, 176, 246, 255, 255, 246, 176, 51, 0],
+            [51, 246, 106, 13, 0, 0, 13, 106, 246, 51],
+            [176, 106, 0, 0, 0, 0, 0, 0, 106, 176],
+            [246, 13, 0, 0, 0, 0, 0, 0, 13, 246],
+            [255, 0, 0, 0, 0, 0, 0, 0, 0, 255],
+        ];
+
+        let rect = Rect::from_xywh(0.0, 0.0, 10.0, 10.0).unwrap();
+        let mut pixmap = Pixmap::new(10, 10).unwrap();
+        fill_rounded_border(&mut pixmap, rect, &[4.0; 4], [1.0; 4], Color::WHITE);
+
+        // The bottom half mirrors the top
+        let rows = golden.iter().chain(golden.iter().rev());
+        for (y, expected) in rows.enumerate() {
+            for (x, expected) in expected.iter().enumerate() {
+                let alpha = pixmap.pixel(x as u32, y as u32).unwrap().alpha();
+                assert!(alpha.abs_diff(*expected) <= 1, "{} {}: {}", x, y, alpha);
+            }
+        }
+    }
+
+    #[test]
+    fn test_border_lines_up_with_background() {
+        let rect = Rect::from_xywh(1.5, 2.0, 20.0, 14.0).unwrap();
+        let radii = [4.0, 6.0, 0.0, 3.0];
+        let mut border = Pixmap::new(24, 20).unwrap();
+        let mut inside = Pixmap::new(24, 20).unwrap();
+        let mut background = Pixmap::new(24, 20).unwrap();
+        fill_rounded_border(&mut border, rect, &radii, [2.0; 4], Color::WHITE);
+        let inner = Rect::from_xywh(3.5, 4.0, 16.0, 10.0).unwrap();
+        fill_rounded_rect(&mut inside, inner, &[2.0, 4.0, 0.0, 1.0], Color::WHITE);
+        fill_rounded_rect(&mut background, rect, &radii, Color::WHITE);
+
+        // The border and what it encloses add up to the background, without gaps or overlap
+        for (index, pixel) in background.pixels().iter().enumerate() {
+            let sum = border.pixels()[index].alpha() as u16 + inside.pixels()[index].alpha() as u16;
+            assert!(sum.abs_diff(pixel.alpha() as u16) <= 1, "{}", index);
+        }
+    }
+
+    #[test]
+    fn test_opaque_rect_fast_path() {
+        let mut paint = Paint::default();
+        paint.set_color(Color::from_rgba8(40, 120, 200, 255));
+        paint.anti_alias = true;
+
+        // Partly outside of the pixmap
+        for rect in [
+            Rect::from_xywh(4.0, 3.0, 10.0, 6.0).unwrap(),
+            Rect::from_xywh(-5.0, 10.0, 12.0, 40.0).unwrap(),
+            Rect::from_xywh(30.0, 30.0, 10.0, 10.0).unwrap(),
+        ] {
+            let mut fast = Pixmap::new(32, 32).unwrap();
+            let mut reference = Pixmap::new(32, 32).unwrap();
+            assert!(fill_opaque_rect(&mut fast, rect, &paint));
+            reference.fill_rect(rect, &paint, Transform::identity(), None);
+            assert!(fast.data() == reference.data(), "{:?}", rect);
+        }
+
+        let mut pixmap = Pixmap::new(32, 32).unwrap();
+        let fractional = Rect::from_xywh(4.5, 3.0, 10.0, 6.0).unwrap();
+        assert!(!fill_opaque_rect(&mut pixmap, fractional, &paint));
+        paint.set_color(Color::from_rgba8(40, 120, 200, 128));
+        let aligned = Rect::from_xywh(4.0, 3.0, 10.0, 6.0).unwrap();
+        assert!(!fill_opaque_rect(&mut pixmap, aligned, &paint));
+    }
+}
@@ -0,0 +1,189 @@
+//! Toasts: short notifications stacked in the bottom right corner of the window.
+//!
+//! [`Ui::notify`] queues a message that is shown for a few seconds, the newest one at the
+//! bottom of the stack. Clicking a toast dismisses it early and hovering it keeps it from
+//! expiring. Errors of the background work, such as images that couldn't be loaded, show up
+//! as toasts as well.
+use crate::input::MouseButton;
+use crate::style::WidgetState;
+use crate::ui::{Ui, apply_style, rgb};
+use clay_layout::Declaration;
+use clay_layout::elements::{FloatingAttachPointType, FloatingAttachToElement};
+use clay_layout::fit;
+use clay_layout::layout::{Alignment, LayoutAlignmentX, LayoutAlignmentY, LayoutDirection};
+use clay_layout::math::Vector2;
+
+/// Seconds a toast is shown.
+const TOAST_DURATION: f32 = 4.0;
+/// Toasts shown at once, the oldest ones are dropped first.
+const MAX_TOASTS: usize = 5;
+/// Distance of the stack from the edges of the window.
+const TOAST_MARGIN: f32 = 12.0;
+
+/// Above everything, including modal dialogs and tooltips.
+const TOAST_Z_INDEX: i16 = 1100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotifyLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+impl NotifyLevel {
+    /// Class of the toast for the stylesheet, `toast.warning` for example.
+    fn class(self) -> &'static str {
+        match self {
+            NotifyLevel::Info => "info",
+            NotifyLevel::Warning => "warning",
+            NotifyLevel::Error => "error",
+        }
+    }
+}
+
+struct Toast {
+    /// Unique among the toasts of the ui, keeps the id of a toast when the ones above it expire.
+    key: u32,
+    level: NotifyLevel,
+    message: String,
+    /// Seconds left until it expires.
+    remaining: f32,
+}
+
+#[derive(Default)]
+pub(crate) struct Toasts {
+    /// Oldest first.
+    queue: Vec<Toast>,
+    next_key: u32,
+}
+
+impl Toasts {
+    fn push(&mut self, level: NotifyLevel, message: String) {
+        self.queue.push(Toast {
+            key: self.next_key,
+            level,
+            message,
+            remaining: TOAST_DURATION,
+        });
+        self.next_key = self.next_key.wrapping_add(1);
+        let excess = self.queue.len().saturating_sub(MAX_TOASTS);
+        self.queue.drain(..excess);
+    }
+
+    /// Counts down the toasts except the `held` one and drops the expired ones.
+    fn expire(&mut self, delta_time: f32, held: Option<u32>) {
+        for toast in &mut self.queue {
+            if held != Some(toast.key) {
+                toast.remaining -= delta_time;
+            }
+        }
+        self.queue.retain(|toast| toast.remaining > 0.0);
+    }
+}
+
+impl Ui {
+    /// Shows `message` in a toast for a few seconds. Styled by the `toast` stylesheet rule
+    /// with the `info`, `warning` or `error` class.
+    pub fn notify(&self, level: NotifyLevel, message: impl Into<String>) {
+        self.toasts().push(level, message.into());
+    }
+
+    /// Declares the queued toasts, run when the frame is done.
+    pub(crate) fn declare_toasts(&self) {
+        let toasts = self.toasts();
+        let ids: Vec<_> = toasts
+            .queue
+            .iter()
+            .map(|toast| self.id_index("toast", toast.key))
+            .collect();
+
+        let input = self.input();
+        let mut held = None;
+        for (toast, id) in toasts.queue.iter_mut().zip(&ids) {
+            if input.was_clicked(id.id.id, MouseButton::Left) {
+                toast.remaining = 0.0;
+            } else if self
+                .last_frame_rect(*id)
+                .is_some_and(|rect| input.is_hovered(rect))
+            {
+                held = Some(toast.key);
+            }
+        }
+        toasts.expire(self.delta_time(), held);
+        if toasts.queue.is_empty() {
+            return;
+        }
+
+        let mut stack = Declaration::new();
+        stack
+            .layout()
+            .width(fit!())
+            .height(fit!())
+            .direction(LayoutDirection::TopToBottom)
+            .child_gap(6)
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Right,
+                LayoutAlignmentY::Top,
+            ))
+            .end()
+            .floating()
+            .attach_to(FloatingAttachToElement::Root)
+            .attach_points(
+                FloatingAttachPointType::RightBottom,
+                FloatingAttachPointType::RightBottom,
+            )
+            .offset(Vector2::new(-TOAST_MARGIN, -TOAST_MARGIN))
+            .z_index(TOAST_Z_INDEX)
+            .end();
+
+        self.with_layout(&stack, |ui| {
+            for toast in &toasts.queue {
+                let id = ui.id_index("toast", toast.key);
+                let hovered = held == Some(toast.key);
+                ui.push_pending_class(toast.level.class());
+                let style = ui.widget_style(
+                    "toast",
+                    WidgetState {
+                        hovered,
+                        ..Default::default()
+                    },
+                );
+
+                let mut decl = Declaration::new();
+                decl.id(id).layout().width(fit!()).height(fit!()).end();
+                apply_style(&mut decl, &style);
+
+                let color = style.text_color.unwrap_or(rgb(220, 220, 220));
+                ui.with_layout(&decl, |ui| {
+                    ui.text_element(ui.alloc_str(toast.message.clone()), color)
+                });
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toasts_expire() {
+        let mut toasts = Toasts::default();
+        toasts.push(NotifyLevel::Info, "first".to_string());
+        toasts.expire(TOAST_DURATION - 1.0, None);
+        toasts.push(NotifyLevel::Error, "second".to_string());
+
+        // Hovering keeps the first one, the second one is shown for its own duration
+        toasts.expire(2.0, Some(0));
+        assert_eq!(toasts.queue.len(), 2);
+        toasts.expire(1.5, None);
+        assert_eq!(toasts.queue.len(), 1);
+        assert_eq!(toasts.queue[0].message, "second");
+
+        for index in 0..MAX_TOASTS + 2 {
+            toasts.push(NotifyLevel::Warning, index.to_string());
+        }
+        assert_eq!(toasts.queue.len(), MAX_TOASTS);
+        assert_eq!(toasts.queue[0].message, "2");
+    }
+}
@@ -0,0 +1,282 @@
+//! Toolbars that move the items that don't fit into an overflow popup.
+//!
+//! Every element declared directly inside of [`Ui::toolbar`] is an item. Items are laid out
+//! left to right for as long as they fit in the width the toolbar had last frame, the rest are
+//! left out and a "»" button shows them in a popup below it. The items are declared once per
+//! frame whatever their place, so widgets in them work the same in the bar and in the popup.
+use crate::input::MouseButton;
+use crate::popup::POPUP_Z_INDEX;
+use crate::style::WidgetState;
+use crate::ui::{Ui, UiDeclaration, apply_style, rgb};
+use clay_layout::elements::FloatingAttachToElement;
+use clay_layout::id::Id;
+use clay_layout::layout::{Alignment, LayoutAlignmentX, LayoutAlignmentY, LayoutDirection};
+use clay_layout::math::Vector2;
+use clay_layout::{Declaration, fit, fixed, grow};
+use glam::{Vec2, Vec4Swizzles};
+use std::collections::HashMap;
+
+/// Space between the items, in the bar and in the overflow popup.
+const ITEM_GAP: f32 = 4.0;
+
+/// Item sizes of a toolbar as they were last laid out.
+#[derive(Default)]
+struct ToolbarState {
+    sizes: Vec<Vec2>,
+}
+
+/// The toolbar whose items are being declared.
+struct DeclaringToolbar {
+    name: String,
+    /// Nesting depth of the items.
+    depth: usize,
+    /// Items declared so far.
+    count: usize,
+    /// Items shown in the bar, the rest go into the overflow popup.
+    visible: usize,
+    /// Heights of the items as last laid out, to stack the overflow items.
+    heights: Vec<f32>,
+    /// Top left corner of the overflow items while the popup is open, with their z-index.
+    overflow: Option<(Vec2, i16)>,
+    /// Offset of the next overflow item from the top of the popup.
+    overflow_y: f32,
+}
+
+#[derive(Default)]
+pub(crate) struct Toolbars {
+    toolbars: HashMap<u32, ToolbarState>,
+    declaring: Option<DeclaringToolbar>,
+}
+
+/// What [`Ui::with_layout`] does with an element, see [`Ui::toolbar_slot`].
+pub(crate) enum ToolbarSlot {
+    /// Not an item of a toolbar, declared as usual.
+    Outside,
+    /// An overflow item while the popup is closed, left out.
+    Hidden,
+    /// An item, declared inside of this wrapper that gives it an id to be measured by.
+    Item(Box<UiDeclaration<'static>>),
+}
+
+impl Toolbars {
+    /// Forgets the toolbar being declared in a frame that was given up, see [`Ui::end`].
+    pub(crate) fn discard_frame(&mut self) {
+        self.declaring = None;
+    }
+}
+
+/// Number of items with `widths` that go in the bar when it's `available` wide. When not all of
+/// them fit, `overflow_width` is kept free for the overflow button.
+fn visible_count(widths: &[f32], available: f32, overflow_width: f32) -> usize {
+    let total = widths.iter().sum::<f32>() + ITEM_GAP * widths.len().saturating_sub(1) as f32;
+    if total <= available {
+        return widths.len();
+    }
+    let mut used = overflow_width;
+    widths
+        .iter()
+        .take_while(|width| {
+            used += *width + ITEM_GAP;
+            used <= available
+        })
+        .count()
+}
+
+impl Ui {
+    /// Lays out the elements declared by `f` left to right, each of them an item. Items that
+    /// don't fit are shown in a popup opened by a "»" button after the last item that fits.
+    ///
+    /// Styled by the `toolbar` rule, the button by `toolbar_overflow_button` and the popup by
+    /// `toolbar_overflow`.
+    pub fn toolbar(&self, id: &str, f: impl FnOnce(&Ui)) {
+        let toolbar_id = self.id(id);
+        let button_id = self.id_index(id, 1);
+        let popup_id = self.id_index(id, 2);
+        let button_size = (self.font_size() as f32 * 1.6).round();
+
+        let style = self.widget_style("toolbar", WidgetState::default());
+        let padding = style.padding.unwrap_or(0) as f32;
+        let popup_padding = self
+            .widget_style("toolbar_overflow", WidgetState::default())
+            .padding
+            .unwrap_or(0) as f32;
+
+        // Items that weren't laid out last frame keep the size they had before
+        let toolbars = self.toolbars();
+        let sizes = &mut toolbars.toolbars.entry(toolbar_id.id.id).or_default().sizes;
+        for (index, size) in sizes.iter_mut().enumerate() {
+            if let Some(rect) = self.last_frame_rect(self.id_index(id, index as u32 + 3)) {
+                *size = rect.zw() - rect.xy();
+            }
+        }
+        let widths: Vec<f32> = sizes.iter().map(|size| size.x).collect();
+        let heights: Vec<f32> = sizes.iter().map(|size| size.y).collect();
+        let available = self
+            .last_frame_rect(toolbar_id)
+            .map_or(f32::INFINITY, |rect| rect.z - rect.x - padding * 2.0);
+        // Items added since last frame have no size yet, they're shown to be measured as long
+        // as the others fit
+        let visible = match visible_count(&widths, available, button_size + ITEM_GAP) {
+            all if all == widths.len() => usize::MAX,
+            visible => visible,
+        };
+
+        let popups = self.popups();
+        let overflow = popups
+            .index_of(popup_id.id.id)
+            .zip(self.last_frame_rect(popup_id))
+            .map(|(index, rect)| {
+                (
+                    rect.xy() + Vec2::splat(popup_padding),
+                    POPUP_Z_INDEX + index as i16 + 1,
+                )
+            });
+
+        let mut decl = Declaration::new();
+        decl.id(toolbar_id)
+            .layout()
+            .width(grow!())
+            .height(fit!())
+            .child_gap(ITEM_GAP as u16)
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Left,
+                LayoutAlignmentY::Center,
+            ))
+            .direction(LayoutDirection::LeftToRight)
+            .end()
+            .clip(true, false, Vector2::new(0.0, 0.0));
+        apply_style(&mut decl, &style);
+
+        let mut count = 0;
+        self.with_layout(&decl, |ui| {
+            let outer = ui.toolbars().declaring.replace(DeclaringToolbar {
+                name: id.to_string(),
+                depth: ui.declaring_depth(),
+                count: 0,
+                visible,
+                heights,
+                overflow,
+                overflow_y: 0.0,
+            });
+            f(ui);
+            let toolbars = ui.toolbars();
+            count = toolbars
+                .declaring
+                .as_ref()
+                .map_or(0, |toolbar| toolbar.count);
+            toolbars.declaring = outer;
+
+            if count > visible {
+                ui.toolbar_overflow_button(button_id, popup_id, button_size);
+            }
+        });
+
+        let sizes = &mut self
+            .toolbars()
+            .toolbars
+            .get_mut(&toolbar_id.id.id)
+            .unwrap()
+            .sizes;
+        sizes.resize(count, Vec2::ZERO);
+        let hidden = sizes.get(visible..).unwrap_or_default();
+        let width = hidden.iter().map(|size| size.x).fold(0.0, f32::max);
+        let height = hidden.iter().map(|size| size.y + ITEM_GAP).sum::<f32>() - ITEM_GAP;
+
+        // The overflow items float on top of the popup, which only reserves their space
+        let mut spacer = Declaration::new();
+        spacer
+            .layout()
+            .width(fixed!(width))
+            .height(fixed!(height.max(0.0)))
+            .end();
+        if count > visible {
+            self.popup_panel(popup_id, "toolbar_overflow", |ui| {
+                ui.with_layout(&spacer, |_| {})
+            });
+        }
+    }
+
+    /// The "»" button that opens the overflow popup below it.
+    fn toolbar_overflow_button(&self, id: Id, popup_id: Id, size: f32) {
+        let input = self.input();
+        if input.was_clicked(id.id.id, MouseButton::Left)
+            && let Some(rect) = self.last_frame_rect(id)
+        {
+            self.open_popup_at(popup_id, Vec2::new(rect.x, rect.w));
+        }
+
+        let hovered = self
+            .last_frame_rect(id)
+            .is_some_and(|rect| input.is_hovered(rect));
+        let style = self.widget_style(
+            "toolbar_overflow_button",
+            WidgetState {
+                hovered,
+                ..Default::default()
+            },
+        );
+        let color = style.text_color.unwrap_or(rgb(220, 220, 220));
+
+        let mut decl = Declaration::new();
+        decl.id(id)
+            .layout()
+            .width(fixed!(size))
+            .height(fixed!(size))
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Center,
+                LayoutAlignmentY::Center,
+            ))
+            .end();
+        apply_style(&mut decl, &style);
+        self.with_layout(&decl, |ui| ui.text_element("»", color));
+    }
+
+    /// Where an element about to be declared at nesting `depth` goes. Called by
+    /// [`Ui::with_layout`] for every element.
+    pub(crate) fn toolbar_slot(&self, depth: usize) -> ToolbarSlot {
+        let Some(toolbar) = self.toolbars().declaring.as_mut() else {
+            return ToolbarSlot::Outside;
+        };
+        if toolbar.depth != depth {
+            return ToolbarSlot::Outside;
+        }
+
+        let index = toolbar.count;
+        toolbar.count += 1;
+        let item_id = self.id_index(&toolbar.name, index as u32 + 3);
+        let mut decl = Declaration::new();
+        decl.id(item_id).layout().width(fit!()).height(fit!()).end();
+
+        if index >= toolbar.visible {
+            let Some((origin, z_index)) = toolbar.overflow else {
+                return ToolbarSlot::Hidden;
+            };
+            let offset = origin + Vec2::new(0.0, toolbar.overflow_y);
+            toolbar.overflow_y += toolbar.heights.get(index).copied().unwrap_or(0.0) + ITEM_GAP;
+            decl.floating()
+                .attach_to(FloatingAttachToElement::Root)
+                .offset(Vector2::new(offset.x.round(), offset.y.round()))
+                .z_index(z_index)
+                .end();
+        }
+        ToolbarSlot::Item(Box::new(decl))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_visible_count() {
+        let widths = [100.0, 50.0, 80.0];
+        // 100 + 4 + 50 + 4 + 80
+        assert_eq!(visible_count(&widths, 238.0, 20.0), 3);
+        assert_eq!(visible_count(&widths, f32::INFINITY, 20.0), 3);
+        // The overflow button takes 20 after the items that fit
+        assert_eq!(visible_count(&widths, 237.0, 20.0), 2);
+        assert_eq!(visible_count(&widths, 150.0, 20.0), 1);
+        assert_eq!(visible_count(&widths, 100.0, 20.0), 0);
+        assert_eq!(visible_count(&[], 0.0, 20.0), 0);
+    }
+}
@@ -0,0 +1,86 @@
+//! Tooltips for the widget added last.
+//!
+//! Items count how long the mouse has rested on them in their [`crate::ui::ItemState`] and
+//! [`Ui::tooltip`] shows its content next to the mouse once that passes the tooltip delay.
+use crate::style::Style;
+use crate::ui::{Ui, apply_style, rgb};
+use clay_layout::elements::FloatingAttachToElement;
+use clay_layout::id::Id;
+use clay_layout::layout::LayoutDirection;
+use clay_layout::math::Vector2;
+use clay_layout::{Declaration, fit};
+use glam::{Vec2, Vec4Swizzles};
+
+/// Seconds the mouse has to rest on an item before its tooltip shows.
+pub const DEFAULT_TOOLTIP_DELAY: f32 = 0.5;
+
+/// Distance from the mouse to the tooltip's top left corner.
+const MOUSE_OFFSET: Vec2 = Vec2::new(12.0, 18.0);
+
+/// Above everything else in the frame.
+const TOOLTIP_Z_INDEX: i16 = 1000;
+
+impl Ui {
+    /// Shows the content added by `f` in a floating panel near the mouse once the widget added
+    /// before this call has been hovered for the delay set with [`Ui::set_tooltip_delay`].
+    /// Hidden while a mouse button is held. Returns true while shown.
+    ///
+    /// Styled by the `tooltip` stylesheet rule.
+    pub fn tooltip(&self, f: impl FnOnce(&Ui)) -> bool {
+        let Some(item) = self.last_item() else {
+            return false;
+        };
+
+        let input = self.input();
+        let hovered = self.item_state(item).hover_time;
+        if hovered < self.tooltip_delay() || input.mouse_down.iter().any(|down| *down) {
+            return false;
+        }
+
+        let id = self.id_index("tooltip", item.id.id);
+        let style = self.widget_style("tooltip", Default::default());
+        self.float_at_mouse(id, &style, f);
+        // Tooltips go with the item, so a tooltip after it still refers to it
+        self.set_last_item(Some(item));
+        true
+    }
+
+    /// A panel `id` next to the mouse above everything else, with the content added by `f`.
+    /// Kept inside the window, going to the other side of the mouse when it doesn't fit.
+    pub(crate) fn float_at_mouse(&self, id: Id, style: &Style, f: impl FnOnce(&Ui)) {
+        let input = self.input();
+        let size = self
+            .last_frame_rect(id)
+            .map_or(Vec2::ZERO, |rect| rect.zw() - rect.xy());
+        let (width, height) = self.window_size();
+        let mut pos = input.mouse_pos + MOUSE_OFFSET;
+        if pos.x + size.x > width as f32 {
+            pos.x = (input.mouse_pos.x - MOUSE_OFFSET.x - size.x).max(0.0);
+        }
+        if pos.y + size.y > height as f32 {
+            pos.y = (input.mouse_pos.y - size.y - 4.0).max(0.0);
+        }
+
+        let mut decl = Declaration::new();
+        decl.id(id)
+            .layout()
+            .width(fit!())
+            .height(fit!())
+            .direction(LayoutDirection::TopToBottom)
+            .end()
+            .floating()
+            .attach_to(FloatingAttachToElement::Root)
+            .offset(Vector2::new(pos.x.round(), pos.y.round()))
+            .z_index(TOOLTIP_Z_INDEX)
+            .end();
+        apply_style(&mut decl, style);
+        self.with_layout(&decl, f);
+    }
+
+    /// A [`Ui::tooltip`] showing `text`.
+    pub fn tooltip_text(&self, text: &str) -> bool {
+        let style = self.widget_style("tooltip", Default::default());
+        let color = style.text_color.unwrap_or(rgb(220, 220, 220));
+        self.tooltip(|ui| ui.text_element(text, color))
+    }
+}
@@ -0,0 +1,100 @@
+//! Animated show and hide of a group of widgets.
+//!
+//! [`Ui::show_animated`] wraps the content in a clipping element whose size follows the
+//! content's size from the previous frame scaled by an eased factor, so the layout around it
+//! reflows smoothly while the content slides in or out.
+use crate::animation::Easing;
+use crate::ui::Ui;
+use clay_layout::layout::LayoutDirection;
+use clay_layout::math::Vector2;
+use clay_layout::{Declaration, fit, fixed, grow};
+
+/// Seconds a show or hide transition takes.
+pub const TRANSITION_TIME: f32 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    /// The height grows and shrinks with the content anchored at the top.
+    Collapse,
+    /// The height grows and the content slides down from above.
+    SlideDown,
+    /// The width grows and the content slides in from the left, for side panels.
+    SlideRight,
+}
+
+impl Transition {
+    fn is_horizontal(self) -> bool {
+        matches!(self, Transition::SlideRight)
+    }
+
+    fn slides(self) -> bool {
+        !matches!(self, Transition::Collapse)
+    }
+}
+
+impl Ui {
+    /// Shows the content added by `f` while `visible` is true, animating it in and out with
+    /// `transition`. The content isn't built at all once it's fully hidden.
+    pub fn show_animated(
+        &self,
+        id: &str,
+        visible: bool,
+        transition: Transition,
+        f: impl FnOnce(&Ui),
+    ) {
+        let outer_id = self.id(id);
+        let content_id = self.id_index(id, 1);
+
+        let factor =
+            self.animate_bool_with(outer_id, visible, TRANSITION_TIME, Easing::EASE_IN_OUT);
+        if factor <= 0.0 {
+            return;
+        }
+
+        let horizontal = transition.is_horizontal();
+        // Size along the animated axis as laid out last frame, unknown on the first frame it
+        // is shown
+        let content_size = self.last_frame_rect(content_id).map(|aabb| {
+            if horizontal {
+                aabb.z - aabb.x
+            } else {
+                aabb.w - aabb.y
+            }
+        });
+
+        let mut outer = Declaration::new();
+        outer.id(outer_id);
+
+        // Unknown sizes start collapsed, the content is measured this frame
+        let animated_size = (factor < 1.0).then(|| content_size.unwrap_or(0.0) * factor);
+        {
+            let mut layout = outer.layout();
+            match (horizontal, animated_size) {
+                (true, Some(size)) => layout.width(fixed!(size)).height(grow!()),
+                (false, Some(size)) => layout.width(grow!()).height(fixed!(size)),
+                (true, None) => layout.width(fit!()).height(grow!()),
+                (false, None) => layout.width(grow!()).height(fit!()),
+            };
+            layout.end();
+        }
+
+        let hidden = content_size.unwrap_or(0.0) * (1.0 - factor);
+        let offset = match transition {
+            t if !t.slides() => Vector2::new(0.0, 0.0),
+            t if t.is_horizontal() => Vector2::new(-hidden, 0.0),
+            _ => Vector2::new(0.0, -hidden),
+        };
+        outer.clip(horizontal, !horizontal, offset);
+
+        let mut content = Declaration::new();
+        content
+            .id(content_id)
+            .layout()
+            .width(if horizontal { fit!() } else { grow!() })
+            .height(if horizontal { grow!() } else { fit!() })
+            .direction(LayoutDirection::TopToBottom)
+            .end();
+
+        self.with_layout(&outer, |ui| ui.with_layout(&content, f));
+    }
+}
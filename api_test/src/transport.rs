@@ -0,0 +1,288 @@
+//! Transport controls: the play, stop, record and loop buttons of a DAW.
+//!
+//! [`Ui::transport`] shows a button for each of them with an icon drawn in the text color of
+//! its style, and toggles the [`TransportState`] they control when clicked. Space starts and
+//! pauses playback from anywhere while no widget has keyboard focus, so it doesn't get in the
+//! way of typing in a text field.
+use crate::image::ImageInfo;
+use crate::input::{Key, Modifiers, MouseButton};
+use crate::style::WidgetState;
+use crate::tiny_skia_renderer::clay_to_tiny_skia_color;
+use crate::ui::{Ui, apply_style, rgb};
+use clay_layout::color::Color as ClayColor;
+use clay_layout::id::Id;
+use clay_layout::layout::{Alignment, LayoutAlignmentX, LayoutAlignmentY, LayoutDirection};
+use clay_layout::{Declaration, fit, fixed};
+use std::f32::consts::{FRAC_PI_2, TAU};
+use tiny_skia::{FillRule, LineCap, Paint, PathBuilder, Pixmap, Rect, Stroke, Transform};
+
+/// Size of the buttons in font sizes.
+const BUTTON_SIZE: f32 = 1.6;
+/// Size of the icons in button sizes.
+const ICON_SIZE: f32 = 0.55;
+
+/// What the transport is doing.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TransportState {
+    pub playing: bool,
+    /// Recording while playing, or armed to record once playback starts.
+    pub recording: bool,
+    /// Playback repeats the loop range.
+    pub looping: bool,
+}
+
+/// Which buttons of the transport were used this frame.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TransportResponse {
+    /// Playback was started or paused, with the play button or space.
+    pub play_toggled: bool,
+    /// The stop button was clicked, also when nothing was playing.
+    pub stopped: bool,
+    pub record_toggled: bool,
+    pub loop_toggled: bool,
+}
+
+impl TransportResponse {
+    /// True if the [`TransportState`] changed this frame.
+    pub fn changed(&self) -> bool {
+        self.play_toggled || self.stopped || self.record_toggled || self.loop_toggled
+    }
+}
+
+/// The icon of a transport button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Icon {
+    Play,
+    Pause,
+    Stop,
+    Record,
+    Loop,
+}
+
+/// `icon` drawn in `color` on a transparent pixmap `size` pixels across.
+fn icon_pixmap(icon: Icon, size: u32, color: ClayColor) -> Option<Pixmap> {
+    let mut pixmap = Pixmap::new(size, size)?;
+    let s = size as f32;
+    let mut paint = Paint {
+        anti_alias: true,
+        ..Default::default()
+    };
+    paint.set_color(clay_to_tiny_skia_color(color));
+    let mut fill_rect = |x: f32, y: f32, width: f32, height: f32| {
+        if let Some(rect) = Rect::from_xywh(x * s, y * s, width * s, height * s) {
+            pixmap.fill_rect(rect, &paint, Transform::identity(), None);
+        }
+    };
+
+    let mut path = PathBuilder::new();
+    match icon {
+        Icon::Play => {
+            path.move_to(0.15 * s, 0.05 * s);
+            path.line_to(0.95 * s, 0.5 * s);
+            path.line_to(0.15 * s, 0.95 * s);
+            path.close();
+        }
+        Icon::Pause => {
+            fill_rect(0.15, 0.05, 0.25, 0.9);
+            fill_rect(0.6, 0.05, 0.25, 0.9);
+            return Some(pixmap);
+        }
+        Icon::Stop => {
+            fill_rect(0.1, 0.1, 0.8, 0.8);
+            return Some(pixmap);
+        }
+        Icon::Record => path.push_circle(0.5 * s, 0.5 * s, 0.42 * s),
+        Icon::Loop => {
+            // An arrow going clockwise around most of a circle, ending in a head
+            let (center, radius, width) = (0.5 * s, 0.34 * s, (0.12 * s).max(1.5));
+            let (from, to) = (-FRAC_PI_2 + 0.5, -FRAC_PI_2 + TAU - 0.9);
+            let point = |angle: f32, radius: f32| {
+                (center + radius * angle.cos(), center + radius * angle.sin())
+            };
+            let mut arc = PathBuilder::new();
+            for step in 0..=32 {
+                let (x, y) = point(from + (to - from) * step as f32 / 32.0, radius);
+                if step == 0 {
+                    arc.move_to(x, y);
+                } else {
+                    arc.line_to(x, y);
+                }
+            }
+            let stroke = Stroke {
+                width,
+                line_cap: LineCap::Butt,
+                ..Default::default()
+            };
+            if let Some(arc) = arc.finish() {
+                pixmap.stroke_path(&arc, &paint, &stroke, Transform::identity(), None);
+            }
+            let (outer, inner) = (
+                point(to, radius + width * 1.3),
+                point(to, radius - width * 1.3),
+            );
+            let tip = point(to + 0.55, radius);
+            path.move_to(outer.0, outer.1);
+            path.line_to(tip.0, tip.1);
+            path.line_to(inner.0, inner.1);
+            path.close();
+        }
+    }
+    if let Some(path) = path.finish() {
+        pixmap.fill_path(
+            &path,
+            &paint,
+            FillRule::Winding,
+            Transform::identity(),
+            None,
+        );
+    }
+    Some(pixmap)
+}
+
+impl Ui {
+    /// Play, stop, record and loop buttons controlling `state`. Returns which of them were used
+    /// this frame.
+    ///
+    /// Play starts playback and turns into pause while playing, stop ends both playback and
+    /// recording, and record and loop toggle. Space starts and pauses playback too as long as
+    /// no widget has keyboard focus.
+    ///
+    /// The buttons are styled by the `transport_button` rule, with the `playing`, `recording`
+    /// and `looping` classes while the play, record and loop buttons are engaged.
+    pub fn transport(&self, id: &str, state: &mut TransportState) -> TransportResponse {
+        let size = (self.font_size() as f32 * BUTTON_SIZE).round();
+        let space = self.focus_id().is_none() && self.key_pressed_with(Modifiers::NONE, Key::Space);
+        let mut response = TransportResponse::default();
+
+        let mut decl = Declaration::new();
+        decl.id(self.id(id))
+            .layout()
+            .width(fit!())
+            .height(fit!())
+            .child_gap(2)
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Left,
+                LayoutAlignmentY::Center,
+            ))
+            .direction(LayoutDirection::LeftToRight)
+            .end();
+        self.with_layout(&decl, |ui| {
+            let stop = ui.transport_button(ui.id_index(id, 1), Icon::Stop, None, size);
+            let play_icon = if state.playing {
+                Icon::Pause
+            } else {
+                Icon::Play
+            };
+            let playing = state.playing.then_some("playing");
+            let play = ui.transport_button(ui.id_index(id, 2), play_icon, playing, size);
+            let recording = state.recording.then_some("recording");
+            let record = ui.transport_button(ui.id_index(id, 3), Icon::Record, recording, size);
+            let looping = state.looping.then_some("looping");
+            let looped = ui.transport_button(ui.id_index(id, 4), Icon::Loop, looping, size);
+
+            response.play_toggled = play || space;
+            if response.play_toggled {
+                state.playing = !state.playing;
+            }
+            response.stopped = stop;
+            if stop {
+                state.playing = false;
+                state.recording = false;
+            }
+            response.record_toggled = record;
+            state.recording ^= record;
+            response.loop_toggled = looped;
+            state.looping ^= looped;
+        });
+        response
+    }
+
+    /// A square button of a transport showing `icon`, with `class` added to its style. Returns
+    /// true when it was clicked.
+    fn transport_button(&self, id: Id, icon: Icon, class: Option<&str>, size: f32) -> bool {
+        let input = self.input();
+        let hovered = self
+            .last_frame_rect(id)
+            .is_some_and(|rect| input.is_hovered(rect));
+        let held = input.pressed_id == Some(id.id.id);
+
+        if let Some(class) = class {
+            self.push_pending_class(class);
+        }
+        let style = self.widget_style(
+            "transport_button",
+            WidgetState {
+                hovered,
+                active: held && hovered,
+                disabled: false,
+            },
+        );
+        let color = style.text_color.unwrap_or(rgb(220, 220, 220));
+        let icon_size = (size * ICON_SIZE).round();
+
+        let mut decl = Declaration::new();
+        decl.id(id)
+            .layout()
+            .width(fixed!(size))
+            .height(fixed!(size))
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Center,
+                LayoutAlignmentY::Center,
+            ))
+            .end();
+        apply_style(&mut decl, &style);
+        self.with_layout(&decl, |ui| {
+            let mut image = Declaration::new();
+            image
+                .layout()
+                .width(fixed!(icon_size))
+                .height(fixed!(icon_size))
+                .end();
+            if let Some(pixmap) = icon_pixmap(icon, icon_size as u32, color) {
+                image
+                    .image()
+                    .data(ui.alloc_image(ImageInfo::new(pixmap)))
+                    .end();
+            }
+            ui.with_layout(&image, |_| {});
+        });
+        input.was_clicked(id.id.id, MouseButton::Left)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_icon_pixmap() {
+        let color = rgb(255, 255, 255);
+        let alpha = |pixmap: &Pixmap, x: u32, y: u32| pixmap.pixel(x, y).unwrap().alpha();
+        let play = icon_pixmap(Icon::Play, 20, color).unwrap();
+        assert_eq!(alpha(&play, 8, 10), 255);
+        // The triangle points right, leaving the right corners empty
+        assert_eq!(alpha(&play, 18, 2), 0);
+        assert_eq!(alpha(&play, 18, 17), 0);
+        let pause = icon_pixmap(Icon::Pause, 20, color).unwrap();
+        assert_eq!(alpha(&pause, 5, 10), 255);
+        assert_eq!(alpha(&pause, 10, 10), 0);
+        let record = icon_pixmap(Icon::Record, 20, color).unwrap();
+        assert_eq!(alpha(&record, 10, 10), 255);
+        assert_eq!(alpha(&record, 1, 1), 0);
+        // The loop arrow leaves its center empty
+        let looped = icon_pixmap(Icon::Loop, 20, color).unwrap();
+        assert_eq!(alpha(&looped, 10, 10), 0);
+        assert!(looped.pixels().iter().any(|pixel| pixel.alpha() == 255));
+        assert!(icon_pixmap(Icon::Stop, 0, color).is_none());
+    }
+
+    #[test]
+    fn test_response_changed() {
+        assert!(!TransportResponse::default().changed());
+        let stopped = TransportResponse {
+            stopped: true,
+            ..Default::default()
+        };
+        assert!(stopped.changed());
+    }
+}
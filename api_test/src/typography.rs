@@ -0,0 +1,76 @@
+//! Text in the sizes of the typography scale.
+//!
+//! [`Ui::heading`], [`Ui::small`] and [`Ui::monospace`] draw a line of text in the font size of
+//! their [`TextRole`], so application code doesn't set sizes around every label. The rule with
+//! the role's name styles the text, `heading { font_size: 24; text_color: #ffffff }` for
+//! example. Monospace text is drawn with the font registered for [`FontStyle::Monospace`].
+use crate::style::{Style, TextRole, WidgetState};
+use crate::ui::{FontStyle, Ui, apply_style, rgb};
+use clay_layout::Declaration;
+use clay_layout::color::Color as ClayColor;
+use clay_layout::fit;
+
+/// Color of `role` text when its rule doesn't set one.
+fn text_color(role: TextRole, style: &Style) -> ClayColor {
+    style.text_color.unwrap_or(match role {
+        TextRole::Heading => rgb(255, 255, 255),
+        TextRole::Body | TextRole::Monospace => rgb(220, 220, 220),
+        TextRole::Caption => rgb(160, 160, 160),
+    })
+}
+
+impl Ui {
+    /// A line of text in the heading size.
+    pub fn heading(&self, text: &str) {
+        self.role_text(text, TextRole::Heading);
+    }
+
+    /// A line of text in the caption size, for hints and secondary information.
+    pub fn small(&self, text: &str) {
+        self.role_text(text, TextRole::Caption);
+    }
+
+    /// A line of text in the monospace font, for code and numbers that line up.
+    pub fn monospace(&self, text: &str) {
+        self.role_text(text, TextRole::Monospace);
+    }
+
+    fn role_text(&self, text: &str, role: TextRole) {
+        let id = self.id(text);
+        let style = self.widget_style(role.name(), WidgetState::default());
+        let color = text_color(role, &style);
+
+        let font = self.active_font();
+        let font_size = self.font_size();
+        self.set_font_size(self.font_size_for(role));
+        if role == TextRole::Monospace
+            && let Some(monospace) = self.font_for_style(FontStyle::Monospace)
+        {
+            self.set_font(monospace);
+        }
+
+        let mut decl = Declaration::new();
+        decl.id(id).layout().width(fit!()).height(fit!()).end();
+        apply_style(&mut decl, &style);
+        self.with_layout(&decl, |ui| ui.text_element(text, color));
+
+        self.set_font(font);
+        self.set_font_size(font_size);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::Stylesheet;
+
+    #[test]
+    fn test_text_color() {
+        let sheet = Stylesheet::parse("heading { text_color: #ff0000 }").unwrap();
+        let heading = sheet.resolve("heading", &[], WidgetState::default());
+        assert_eq!(text_color(TextRole::Heading, &heading), rgb(255, 0, 0));
+
+        let caption = sheet.resolve("caption", &[], WidgetState::default());
+        assert_eq!(text_color(TextRole::Caption, &caption), rgb(160, 160, 160));
+    }
+}
@@ -1,16 +1,67 @@
-use crate::font::{FontHandle, TextGenerator};
+use crate::animation::{BoolAnimation, approach_rect, smoothing_factor};
+use crate::ballistics::MeterBallistics;
+use crate::budget::{FrameDeadline, PendingWork};
+use crate::canvas::CanvasView;
+use crate::clip_grid::ClipGridState;
+#[cfg(feature = "debug-server")]
+use crate::debug_server::DebugServer;
+use crate::file_tree::{DirectoryCache, FileTreeState};
+use crate::focus::FocusState;
+use crate::font::{
+    CachedString, CharIndex, DEFAULT_TEXT_JOBS_PER_FRAME, FontHandle, FontMetrics, TextGenerator,
+    TextLayout,
+};
+use crate::id_hash::make_id;
+use crate::id_path::IdPaths;
+use crate::image::ImageInfo;
+use crate::images::ImageCache;
+use crate::input::{CursorIcon, Event, EventHooks, InputState, Key, Modifiers, MouseButton};
 use crate::internal_error::InternalResult;
-use background_worker::WorkSystem;
-use clay_layout::layout::{Alignment, LayoutAlignmentX, LayoutAlignmentY};
+#[cfg(feature = "debug-server")]
+use crate::json::JsonValue;
+use crate::list_box::ListCursor;
+use crate::long_text::ClipStack;
+use crate::memo::MemoCache;
+use crate::menu::Menus;
+use crate::meter_bridge::MeterBridge;
+use crate::metrics::MetricsRecorder;
+use crate::piano_roll::PianoRollState;
+use crate::popup::Popups;
+use crate::region::RegionDrag;
+use crate::rotated_text::TextRotation;
+use crate::scroll_area::ScrollRequest;
+use crate::style::{Style, Stylesheet, TextRole, WidgetState};
+use crate::table::TableSort;
+use crate::text_edit::TextEditState;
+use crate::text_limits::TextLimits;
+use crate::tiny_skia_renderer::{RenderDiagnostics, TileRenderer};
+use crate::toast::{NotifyLevel, Toasts};
+use crate::toolbar::{ToolbarSlot, Toolbars};
+use crate::tooltip::DEFAULT_TOOLTIP_DELAY;
+use crate::value_drag::{DragAxis, DragConfig, DragState, precision_step, round_to};
+use crate::window::Windows;
+use background_worker::{ThreadPriority, WorkSystem, WorkerConfig};
+use clay_layout::elements::{FloatingAttachToElement, FloatingClipToElement};
+use clay_layout::layout::{Alignment, LayoutAlignmentX, LayoutAlignmentY, Padding};
+use clay_layout::render_commands::RenderCommandConfig;
 use clay_layout::{
     Clay, Clay_Dimensions, Clay_StringSlice, Clay_TextElementConfig, ClayLayoutScope, Declaration,
-    color::Color as ClayColor, fixed, grow, id::Id, layout::LayoutDirection, math::Dimensions,
-    text::TextConfig,
+    color::Color as ClayColor,
+    fit, fixed, grow,
+    id::Id,
+    layout::LayoutDirection,
+    math::{BoundingBox, Dimensions, Vector2},
+    text::{TextConfig, TextElementConfig},
 };
-use glam::Vec4;
+use glam::{Vec2, Vec4};
+use std::borrow::Cow;
 use std::cell::UnsafeCell;
-use std::collections::HashMap;
-use tiny_skia::Pixmap;
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
+use std::time::{Duration, Instant};
+
+/// Size of body text until [`Ui::set_default_font_size`] is called.
+pub const DEFAULT_FONT_SIZE: u32 = 32;
 
 // TODO: We likely need something better than this
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
@@ -20,245 +71,2209 @@ pub enum FontStyle {
     Bold,
     Thin,
     Light,
+    Monospace,
+}
+
+pub(crate) type UiDeclaration<'a> = Declaration<'a, ImageInfo, ()>;
+type UiLayoutScope<'a> = ClayLayoutScope<'a, 'a, ImageInfo, ()>;
+#[derive(Debug, Default)]
+#[allow(dead_code)]
+pub struct ItemState {
+    pub aabb: Vec4,
+    pub was_hovered: bool,
+    pub was_clicked: bool,
+    /// Modifiers held when the item was clicked.
+    pub click_modifiers: Modifiers,
+    /// Moves towards 1.0 while the item has focus or is open.
+    pub active: f32,
+    /// Moves towards 1.0 while the mouse is over the item.
+    pub hover: f32,
+    /// Seconds the mouse has been over the item without leaving it.
+    pub hover_time: f32,
+    /// Moves towards 1.0 while the item is held down with the left button.
+    pub press: f32,
+    /// Where the item is drawn. Follows `aabb` when animated layout is enabled, see
+    /// [`Ui::set_animated_layout`].
+    pub display_aabb: Vec4,
+    /// Caret and selection for text inputs.
+    pub text_edit: TextEditState,
+    /// Scroll offset of scrollable items.
+    pub scroll: Vec2,
+    /// Whether a tree node or accordion section is expanded.
+    pub open: bool,
+    /// Keyboard cursor of list boxes.
+    pub list_cursor: ListCursor,
+    /// Set while a drag that started on the item with the left button goes on.
+    pub dragging: bool,
+    /// Column tables are sorted by.
+    pub table_sort: Option<TableSort>,
+    /// Month a date picker's calendar shows, as (year, month).
+    pub calendar_month: Option<(i32, u32)>,
+    /// Seconds a repeating button has been held down with the left button.
+    pub hold_time: f32,
+    /// Text typed into a tag input that isn't a tag yet.
+    pub tag_draft: String,
+    /// Seconds since the item was last clicked without being dragged, for double-clicks.
+    pub last_click: Option<f32>,
+    /// Drag of the item going on with the left button, see [`Ui::value_drag`].
+    pub drag: Option<DragState>,
+    pub frame: u64,
+}
+
+/// How fast items move to their new place with animated layout, see [`smoothing_factor`].
+const LAYOUT_ANIMATION_SPEED: f32 = 12.0;
+
+/// Interaction with a [`Ui::button`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ButtonResponse {
+    /// Pressed and released with the left mouse button while over the button.
+    pub clicked: bool,
+    pub hovered: bool,
+    /// The left mouse button went down on the button and is still down.
+    pub held: bool,
+    /// Modifiers held when clicked.
+    pub modifiers: Modifiers,
+}
+
+impl ButtonResponse {
+    /// Clicked while exactly `modifiers` were held, `Modifiers::NONE` for a plain click.
+    pub fn clicked_with(&self, modifiers: Modifiers) -> bool {
+        self.clicked && self.modifiers == modifiers
+    }
+}
+
+/// Options for [`Ui::drag_value_with`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DragValue {
+    /// Value change per pixel dragged, less with fine adjustment held, see [`DragConfig`].
+    pub speed: f32,
+    /// Inclusive range the value is clamped to.
+    pub range: Option<(f32, f32)>,
+    /// Number of decimals shown.
+    pub precision: usize,
+    /// Steps the value moves in while stepping is held, one decimal fewer than shown unless set.
+    pub step: Option<f32>,
+}
+
+impl DragValue {
+    pub fn new(speed: f32) -> Self {
+        Self {
+            speed,
+            range: None,
+            precision: 2,
+            step: None,
+        }
+    }
+
+    pub fn range(mut self, min: f32, max: f32) -> Self {
+        self.range = Some((min, max));
+        self
+    }
+
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    pub fn step(mut self, step: f32) -> Self {
+        self.step = Some(step);
+        self
+    }
+}
+
+/// Options for [`Ui::label_with`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Label {
+    pub rotation: TextRotation,
+}
+
+impl Label {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Turns the text by 90 degrees, the label takes the turned size in the layout.
+    pub fn rotation(mut self, rotation: TextRotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+}
+
+/// Animated interaction factors of an item, each easing between 0.0 and 1.0 as of the previous
+/// frame. Widgets use them to blend between their normal, hover and pressed appearance.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ItemFactors {
+    pub hover: f32,
+    pub press: f32,
+    /// Focus, or being open for expandable items.
+    pub active: f32,
+}
+
+/// An element with an explicit id emitted during the current frame. Anonymous elements are
+/// skipped so their children are attached to the closest ancestor that has an id.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TreeNode {
+    pub(crate) id: Id,
+    pub(crate) parent: Option<u32>,
+    pub(crate) depth: u32,
+    pub(crate) padding: Padding,
+    /// Z-index of the closest floating element around it, 0 in the regular layout.
+    pub(crate) z_index: i16,
+}
+
+/// Where the ui is between [`Ui::begin`] and [`Ui::end`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum FramePhase {
+    /// No frame open, the last one ended or none began yet.
+    #[default]
+    Idle,
+    Declaring,
+    /// A closure declaring an element panicked, so Clay has elements left open. Nothing of the
+    /// frame is laid out or rendered.
+    Aborted,
+}
+
+struct State {
+    /// The frame being declared into `layout`, which it borrows. Only valid because the state
+    /// is boxed and never moves, and declared before `layout` so it's dropped first.
+    layout_scope: Option<UiLayoutScope<'static>>,
+    frame_phase: FramePhase,
+    bg_worker: WorkSystem,
+    layout: Clay,
+    text_generator: TextGenerator,
+    /// Max number of text generation jobs submitted to the workers each frame.
+    text_jobs_per_frame: usize,
+    frame_budget: Option<Duration>,
+    frame_start: Instant,
+    /// Calls to [`Ui::run_budgeted`] that ran out of time this frame.
+    deferred_tasks: usize,
+    font_styles: HashMap<FontStyle, FontHandle>,
+    image_cache: ImageCache,
+    /// Directory listings of file trees, see [`Ui::file_tree`].
+    directories: DirectoryCache,
+    /// Expanded directories and selection of the file trees, by tree id.
+    file_trees: HashMap<u32, FileTreeState>,
+    /// Pan and zoom of the canvases, by canvas id.
+    canvas_views: HashMap<u32, CanvasView>,
+    /// Smoothed levels of the meters, by meter id.
+    meters: HashMap<u32, MeterBallistics>,
+    /// Smoothed levels and clip lights of the meter bridges, by bridge id.
+    meter_bridges: HashMap<u32, MeterBridge>,
+    /// Selection and drags of the piano rolls, by roll id.
+    piano_rolls: HashMap<u32, PianoRollState>,
+    /// Clips being dragged on the clip grids, by grid id.
+    clip_grids: HashMap<u32, ClipGridState>,
+    item_states: HashMap<u32, ItemState>, // TODO: Arena hashmap
+    memo_cache: MemoCache,
+    /// Requests from [`Ui::scroll_to`] by scroll area id.
+    scroll_requests: HashMap<u32, ScrollRequest>,
+    active_font: FontHandle,
+    /// Font loaded first, see [`Ui::load_font`].
+    default_font: Option<FontHandle>,
+    /// Size of text declared now, reset to the body size every frame.
+    font_size: u32,
+    default_font_size: u32,
+    window_size: (usize, usize),
+    current_frame: u64,
+    delta_time: f32,
+    /// Seconds since the ui was created, summed from the frame delta times.
+    time: f64,
+    focus_id: Option<Id>,
+    focus: FocusState,
+    id_paths: IdPaths,
+    /// Seed of the id hash, see [`crate::id_hash`].
+    id_seed: u32,
+    popups: Popups,
+    windows: Windows,
+    toasts: Toasts,
+    toolbars: Toolbars,
+    /// Star images of ratings, by size.
+    rating_stars: HashMap<u32, ImageInfo>,
+    menus: Menus,
+    /// Edges being dragged in region overlays, by overlay id.
+    region_drags: HashMap<u32, RegionDrag>,
+    /// Backdrop of the modal shown this frame, see [`Ui::modal`].
+    modal_layer: Option<Id>,
+    /// Last element with an id that was closed this frame, see [`Ui::tooltip`].
+    last_item: Option<Id>,
+    /// Seconds an item has to be hovered before its tooltip shows.
+    tooltip_delay: f32,
+    /// Modifiers that change how values and points are dragged.
+    drag_config: DragConfig,
+    /// Element hierarchy of the current frame, in declaration order.
+    frame_tree: Vec<TreeNode>,
+    parent_stack: Vec<u32>,
+    /// Z-index of every element being declared, anonymous ones included.
+    z_stack: Vec<i16>,
+    /// Strings created while building the frame. Clay keeps pointers to them until rendering.
+    frame_strings: Vec<String>,
+    /// Images shown in the current frame, boxed so Clay's pointers stay valid until rendering.
+    #[allow(clippy::vec_box)]
+    frame_images: Vec<Box<ImageInfo>>,
+    stylesheet: Stylesheet,
+    /// Classes set with `Ui::class`, consumed by the next widget.
+    pending_classes: Vec<String>,
+    input: InputState,
+    /// Events pushed by the host since the last `begin`.
+    pending_events: Vec<Event>,
+    event_hooks: EventHooks,
+    /// Cursors declared by elements of the current frame.
+    frame_cursors: HashMap<u32, CursorIcon>,
+    /// Where a widget asked for the pointer to be moved to, see [`Ui::take_cursor_warp`].
+    cursor_warp: Option<Vec2>,
+    bool_animations: HashMap<u32, BoolAnimation>,
+    animated_layout: bool,
+    renderer: TileRenderer,
+    render_diagnostics: RenderDiagnostics,
+    /// Interactions recorded while widget metrics are on, see [`Ui::set_widget_metrics`].
+    widget_metrics: Option<MetricsRecorder>,
+    #[cfg(feature = "debug-server")]
+    debug_server: Option<DebugServer>,
+}
+
+impl State {
+    #[inline(always)]
+    pub fn layout(&mut self) -> &mut UiLayoutScope<'static> {
+        unsafe { self.layout_scope.as_mut().unwrap_unchecked() }
+    }
+
+    fn text_size(&mut self, text: &str, font_size: u32) -> Dimensions {
+        // Nothing to measure with before the first font is loaded
+        let size = self
+            .text_generator
+            .measure_text_size(text, self.active_font, font_size as _)
+            .unwrap_or_default();
+
+        Dimensions::new(size.0 as _, size.1 as _)
+    }
+}
+
+macro_rules! get_state_mut {
+    ($self:expr) => {
+        unsafe { &mut *$self.state.get() }
+    };
+}
+
+macro_rules! get_layout_mut {
+    ($self:expr) => {
+        unsafe { $self.layout_scope.as_mut().unwrap_unchecked() }
+    };
 }
 
-#[derive(Debug)]
-#[allow(dead_code)]
-pub struct ImageInfo {
-    pixmap: Pixmap,
-}
+/// Aborts the frame when the closure declaring an element unwinds, see [`Ui::with_layout`].
+struct UnwindGuard<'a>(&'a Ui);
+
+impl Drop for UnwindGuard<'_> {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            get_state_mut!(self.0).frame_phase = FramePhase::Aborted;
+        }
+    }
+}
+
+/// The ui state and the entry point of all widgets. It has no lifetime and its state lives
+/// on the heap, so it can be moved and stored in the application's own structs, but it stays
+/// on the thread that created it, see [`crate::thread_safety`].
+pub struct Ui {
+    state: Box<UnsafeCell<State>>,
+    /// Keeps the ui `!Send` and `!Sync` whatever the state holds.
+    _single_thread: PhantomData<*mut ()>,
+}
+
+impl Ui {
+    pub fn new() -> Self {
+        // Rasterization must never compete with the host's audio threads
+        let bg_worker =
+            WorkSystem::with_config(WorkerConfig::new(2).priority(ThreadPriority::BelowNormal));
+
+        let state = State {
+            text_generator: TextGenerator::new(&bg_worker),
+            text_jobs_per_frame: DEFAULT_TEXT_JOBS_PER_FRAME,
+            frame_budget: None,
+            frame_start: Instant::now(),
+            deferred_tasks: 0,
+            image_cache: ImageCache::new(&bg_worker),
+            directories: DirectoryCache::new(&bg_worker),
+            file_trees: HashMap::new(),
+            canvas_views: HashMap::new(),
+            meters: HashMap::new(),
+            meter_bridges: HashMap::new(),
+            piano_rolls: HashMap::new(),
+            clip_grids: HashMap::new(),
+            layout: Clay::new(Dimensions::new(320.0, 256.0)),
+            layout_scope: None,
+            frame_phase: FramePhase::Idle,
+            bg_worker,
+            font_styles: HashMap::with_capacity(8),
+            active_font: 0,
+            default_font: None,
+            font_size: DEFAULT_FONT_SIZE,
+            default_font_size: DEFAULT_FONT_SIZE,
+            window_size: (320, 256),
+            item_states: HashMap::with_capacity(64),
+            memo_cache: MemoCache::default(),
+            scroll_requests: HashMap::new(),
+            current_frame: 0,
+            delta_time: 0.0,
+            time: 0.0,
+            focus_id: None,
+            focus: FocusState::default(),
+            id_paths: IdPaths::default(),
+            id_seed: 0,
+            popups: Popups::default(),
+            windows: Windows::default(),
+            toasts: Toasts::default(),
+            toolbars: Toolbars::default(),
+            rating_stars: HashMap::new(),
+            menus: Menus::default(),
+            region_drags: HashMap::new(),
+            modal_layer: None,
+            last_item: None,
+            tooltip_delay: DEFAULT_TOOLTIP_DELAY,
+            drag_config: DragConfig::default(),
+            frame_tree: Vec::with_capacity(256),
+            parent_stack: Vec::with_capacity(32),
+            z_stack: Vec::with_capacity(32),
+            frame_strings: Vec::with_capacity(64),
+            frame_images: Vec::new(),
+            stylesheet: Stylesheet::default(),
+            pending_classes: Vec::new(),
+            input: InputState::default(),
+            pending_events: Vec::with_capacity(32),
+            event_hooks: EventHooks::default(),
+            frame_cursors: HashMap::new(),
+            cursor_warp: None,
+            bool_animations: HashMap::new(),
+            animated_layout: false,
+            renderer: TileRenderer::new(),
+            render_diagnostics: RenderDiagnostics::default(),
+            widget_metrics: None,
+            #[cfg(feature = "debug-server")]
+            debug_server: None,
+        };
+
+        let ui = Ui {
+            state: Box::new(UnsafeCell::new(state)),
+            _single_thread: PhantomData,
+        };
+
+        // Clay calls back with the state, which stays at the same address when the ui moves
+        unsafe {
+            Clay::set_measure_text_function_unsafe(
+                Self::measure_text_trampoline,
+                ui.state.get() as _,
+            );
+        }
+
+        ui
+    }
+
+    unsafe extern "C" fn measure_text_trampoline(
+        text_slice: Clay_StringSlice,
+        config: *mut Clay_TextElementConfig,
+        user_data: *mut core::ffi::c_void,
+    ) -> Clay_Dimensions {
+        unsafe {
+            let text = core::str::from_utf8_unchecked(core::slice::from_raw_parts(
+                text_slice.chars as *const u8,
+                text_slice.length as _,
+            ));
+
+            let text_config = TextConfig::from(*config);
+            let state = &mut *(user_data as *mut State);
+
+            state.text_size(text, text_config.font_size as u32).into()
+        }
+    }
+
+    // Internal helper for the area! macro
+    #[doc(hidden)]
+    pub fn __internal_with_layout<F>(&self, declaration: &UiDeclaration<'_>, f: F)
+    where
+        F: FnOnce(&Ui),
+    {
+        let state = get_state_mut!(self);
+        let declaration = self.frame_declaration(declaration);
+
+        state.layout().with(&declaration, |_clay| {
+            f(self);
+        });
+    }
+
+    /// Loads the font file at `path`. Text needs a font, the first one loaded is the default
+    /// font that text is drawn with until [`Ui::set_font`] selects another.
+    pub fn load_font(&self, path: &str) -> InternalResult<FontHandle> {
+        let state = get_state_mut!(self);
+        let font = state.text_generator.load_font(path, &state.bg_worker)?;
+        if state.default_font.is_none() {
+            state.default_font = Some(font);
+            state.active_font = font;
+        }
+        Ok(font)
+    }
+
+    /// The font loaded first, `None` until a font has been loaded.
+    pub fn default_font(&self) -> Option<FontHandle> {
+        let state = get_state_mut!(self);
+        state.default_font
+    }
+
+    /// Sets the size of body text, which headings and captions are scaled from unless the
+    /// stylesheet sets their sizes. Takes effect with the next frame.
+    pub fn set_default_font_size(&self, px: u32) {
+        let state = get_state_mut!(self);
+        state.default_font_size = px;
+    }
+
+    /// Font size of `role` as set by the stylesheet or scaled from the default font size.
+    pub fn font_size_for(&self, role: TextRole) -> u32 {
+        let state = get_state_mut!(self);
+        let style = state
+            .stylesheet
+            .resolve(role.name(), &[], WidgetState::default());
+        role.font_size(&style, state.default_font_size)
+    }
+
+    /// Font of the text declared now.
+    pub(crate) fn active_font(&self) -> FontHandle {
+        let state = get_state_mut!(self);
+        state.active_font
+    }
+
+    /// Font registered for `style` with [`Ui::register_font`].
+    pub(crate) fn font_for_style(&self, style: FontStyle) -> Option<FontHandle> {
+        let state = get_state_mut!(self);
+        state.font_styles.get(&style).copied()
+    }
+
+    /// Size of the text declared now.
+    pub(crate) fn font_size(&self) -> u32 {
+        let state = get_state_mut!(self);
+        state.font_size
+    }
+
+    /// Sets the size of the text declared after this call, until the frame ends.
+    pub fn set_font_size(&self, px: u32) {
+        let state = get_state_mut!(self);
+        state.font_size = px;
+    }
+
+    pub fn register_font(&self, font_id: FontHandle, style: FontStyle) {
+        let state = get_state_mut!(self);
+        state.font_styles.insert(style, font_id);
+    }
+
+    #[inline(always)]
+    #[allow(dead_code)]
+    pub fn set_font(&self, font_handle: FontHandle) {
+        let state = get_state_mut!(self);
+        state.active_font = font_handle;
+    }
+
+    #[allow(dead_code)]
+    pub fn set_font_style(&self, style: FontStyle) {
+        let state = get_state_mut!(self);
+        if let Some(font_handle) = state.font_styles.get(&style) {
+            state.active_font = *font_handle;
+        } else {
+            // Handle the case where the font style is not registered
+            eprintln!("Font style {:?} not registered", style);
+        }
+    }
+
+    pub fn text_size(&self, text: &str, font_size: u32) -> Dimensions {
+        let state = get_state_mut!(self);
+        state.text_size(text, font_size)
+    }
+
+    /// Ascent, descent and line height of `font` at `font_size`, as used when laying out and
+    /// rasterizing text.
+    pub fn font_metrics(&self, font: FontHandle, font_size: u32) -> Option<FontMetrics> {
+        let state = get_state_mut!(self);
+        state.text_generator.font_metrics(font, font_size)
+    }
+
+    /// Draws `text` with `col` unless the stylesheet sets a text color for the label.
+    pub fn label(&self, text: &str, col: ClayColor) {
+        self.label_with(text, col, Label::new());
+    }
+
+    /// [`Ui::label`] with options, for example
+    /// `ui.label_with(&track.name, color, Label::new().rotation(TextRotation::CounterClockwise))`
+    /// for a name along the side of a mixer strip.
+    pub fn label_with(&self, text: &str, col: ClayColor, options: Label) {
+        if options.rotation != TextRotation::Upright {
+            self.rotated_label(text, col, options.rotation);
+            return;
+        }
+
+        let state = get_state_mut!(self);
+        let font_id = state.active_font;
+        let font_size = state.font_size;
+
+        let _ = state
+            .text_generator
+            .queue_generate_text(text, font_size, font_id);
+
+        let id = self.id(text);
+        let style = self.animated_widget_style("label", id);
+        let col = style.text_color.unwrap_or(col);
+
+        let mut decl = Declaration::new();
+        decl.id(id)
+            .layout()
+            .width(grow!())
+            .height(fixed!(80.0))
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Center,
+                LayoutAlignmentY::Center,
+            ))
+            .child_gap(40)
+            .direction(LayoutDirection::LeftToRight)
+            .end();
+        apply_style(&mut decl, &style);
+
+        self.with_layout(&decl, |_ui| {
+            self.declare_text(
+                text,
+                TextConfig::new()
+                    .font_id(font_id as u16)
+                    .font_size(font_size as _)
+                    .wrap_mode(clay_layout::text::TextElementConfigWrapMode::None)
+                    .color(col)
+                    .end(),
+            );
+        });
+    }
+
+    /// A clickable button styled by the `button` stylesheet rules. The response reflects the
+    /// input processed in [`Ui::begin`] for the button as laid out in the previous frame.
+    /// Tab can move the keyboard focus to it, Enter and Space click it while it has focus.
+    pub fn button(&self, text: &str) -> ButtonResponse {
+        let state = get_state_mut!(self);
+        let font_id = state.active_font;
+        let font_size = state.font_size;
+
+        let _ = state
+            .text_generator
+            .queue_generate_text(text, font_size, font_id);
+
+        let id = self.id(text);
+        self.register_focusable(id);
+        let input = &state.input;
+        let focused = self.is_focused(id)
+            && !(input.button_pressed(MouseButton::Left) && input.pressed_id != Some(id.id.id));
+        self.set_focused(id, focused);
+        let activated = focused
+            && input
+                .keys_pressed
+                .iter()
+                .any(|key| matches!(key, Key::Enter | Key::Space));
+        let widget_state = self.widget_state(id);
+        let item = state.item_states.get(&id.id.id);
+        let response = ButtonResponse {
+            clicked: item.is_some_and(|item| item.was_clicked) || activated,
+            hovered: widget_state.hovered,
+            held: widget_state.active,
+            modifiers: item.map_or(Modifiers::NONE, |item| item.click_modifiers),
+        };
+
+        let style = self.animated_widget_style("button", id);
+        let col = style.text_color.unwrap_or(rgb(220, 220, 220));
+
+        let mut decl = Declaration::new();
+        decl.id(id)
+            .layout()
+            .width(fit!())
+            .height(fit!())
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Center,
+                LayoutAlignmentY::Center,
+            ))
+            .end();
+        apply_style(&mut decl, &style);
+
+        self.with_layout(&decl, |_ui| {
+            self.declare_text(
+                text,
+                TextConfig::new()
+                    .font_id(font_id as u16)
+                    .font_size(font_size as _)
+                    .wrap_mode(clay_layout::text::TextElementConfigWrapMode::None)
+                    .color(col)
+                    .end(),
+            );
+        });
+
+        response
+    }
+
+    /// Underlined text that returns true when clicked, for actions that read like a hyperlink
+    /// such as "Open project folder". Styled by the `link` stylesheet rules, the underline
+    /// takes the text color. Shows a pointing cursor while hovered.
+    pub fn link(&self, text: &str) -> bool {
+        let id = self.id(text);
+        let input = self.input();
+        let hovered = self
+            .last_frame_rect(id)
+            .is_some_and(|rect| input.is_hovered(rect));
+        let style = self.widget_style(
+            "link",
+            WidgetState {
+                hovered,
+                active: input.pressed_id == Some(id.id.id),
+                disabled: false,
+            },
+        );
+        let color = style.text_color.unwrap_or(rgb(100, 150, 255));
+        self.set_cursor(id, CursorIcon::Pointer);
+
+        let mut decl = Declaration::new();
+        decl.id(id)
+            .layout()
+            .width(fit!())
+            .height(fit!())
+            .child_gap(1)
+            .direction(LayoutDirection::TopToBottom)
+            .end();
+        apply_style(&mut decl, &style);
+
+        let mut underline = Declaration::new();
+        underline
+            .layout()
+            .width(grow!())
+            .height(fixed!(1.0))
+            .end()
+            .background_color(color);
+
+        self.with_layout(&decl, |ui| {
+            ui.text_element(text, color);
+            ui.with_layout(&underline, |_| {});
+        });
+        input.was_clicked(id.id.id, MouseButton::Left)
+    }
+
+    /// Shows `label: value` and changes the value while dragged horizontally, `speed` per
+    /// pixel. Returns true when the value changed this frame.
+    pub fn drag_value(&self, label: &str, value: &mut f32, speed: f32) -> bool {
+        self.drag_value_with(label, value, DragValue::new(speed))
+    }
+
+    /// [`Ui::drag_value`] with clamping and formatting options, for example
+    /// `ui.drag_value_with("Tempo", &mut bpm, DragValue::new(0.5).range(20.0, 300.0).precision(1))`.
+    pub fn drag_value_with(&self, label: &str, value: &mut f32, options: DragValue) -> bool {
+        let state = get_state_mut!(self);
+        let font_id = state.active_font;
+        let font_size = state.font_size;
+
+        let id = self.id(label);
+        let old_value = *value;
+        let dragging = state.input.pressed_id == Some(id.id.id);
+        let drag = self.value_drag(id, dragging, *value, DragAxis::Horizontal);
+        if let Some(drag) = drag {
+            let mut moved = drag.value + drag.delta * options.speed;
+            if let Some((min, max)) = options.range {
+                moved = moved.clamp(min, max);
+            }
+            let step = options.step.unwrap_or(precision_step(options.precision));
+            *value = self.drag_to(id, moved, |value| round_to(value, step));
+        }
+        if let Some((min, max)) = options.range {
+            *value = value.clamp(min, max);
+        }
+
+        let text = self.alloc_str(format!("{}: {:.*}", label, options.precision, *value));
+        let _ = state
+            .text_generator
+            .queue_generate_text(text, font_size, font_id);
+
+        let style = self.animated_widget_style("drag_value", id);
+        let text_color = style.text_color.unwrap_or(rgb(220, 220, 220));
+
+        let mut decl = Declaration::new();
+        decl.id(id)
+            .layout()
+            .width(fit!())
+            .height(fit!())
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Center,
+                LayoutAlignmentY::Center,
+            ))
+            .end();
+        apply_style(&mut decl, &style);
+        self.set_cursor(id, CursorIcon::ResizeHorizontal);
+
+        self.with_layout(&decl, |_ui| {
+            self.declare_text(
+                text,
+                TextConfig::new()
+                    .font_id(font_id as u16)
+                    .font_size(font_size as _)
+                    .wrap_mode(clay_layout::text::TextElementConfigWrapMode::None)
+                    .color(text_color)
+                    .end(),
+            );
+        });
+        if let Some(drag) = drag {
+            let text = format!("{:.*}", options.precision, *value);
+            self.drag_readout(id, &text, *value - drag.start, options.precision);
+        }
+
+        *value != old_value
+    }
+
+    /// Single line text field. Clicking it takes keyboard focus, Enter, Escape or clicking
+    /// elsewhere gives it up. Returns true when `text` changed this frame, which includes line
+    /// breaks in it being handled by the [`TextLimits::newlines`] policy.
+    ///
+    /// Styled by the `input_text` stylesheet rules, the border turns to the accent color while
+    /// focused.
+    pub fn input_text(&self, id: &str, text: &mut String) -> bool {
+        let state = get_state_mut!(self);
+        let font_id = state.active_font;
+        let font_size = state.font_size;
+        let id = self.id(id);
+
+        let style = self.animated_widget_style("input_text", id);
+        let padding = style.padding.unwrap_or(6) as f32;
+
+        // Line breaks set by the application can't be shown on one line
+        let mut changed = false;
+        if let Cow::Owned(line) = self.text_limits().single_line(text) {
+            *text = line;
+            changed = true;
+        }
+
+        let item = state.item_states.entry(id.id.id).or_default();
+        let (was_clicked, aabb) = (item.was_clicked, item.aabb);
+        let mut edit = item.text_edit;
+        edit.clamp(text);
+
+        let input = &state.input;
+        let modifiers = input.modifiers();
+        self.register_focusable(id);
+        let mut focused = self.text_focus(id, was_clicked);
+
+        if was_clicked {
+            let point = Vec2::new(input.mouse_pos.x - aabb.x - padding, 0.0);
+            edit.move_to(self.text_hit_test(text, point), modifiers.shift());
+        }
+
+        if focused {
+            for key in &input.keys_pressed {
+                match key {
+                    Key::Enter | Key::Escape => focused = false,
+                    key => changed |= edit.key(text, *key, modifiers),
+                }
+            }
+            // Shortcuts can also produce text on some platforms
+            if !modifiers.command() {
+                let typed: String = input.text.chars().filter(|c| !c.is_control()).collect();
+                changed |= edit.insert(text, &typed);
+            }
+        }
+
+        self.set_focused(id, focused);
+        state.item_states.entry(id.id.id).or_default().text_edit = edit;
+
+        let display = self.alloc_str(text.clone());
+        let _ = state
+            .text_generator
+            .queue_generate_text(display, font_size, font_id);
+
+        let line_height = self
+            .font_metrics(font_id, font_size)
+            .map_or(font_size as f32 * 1.2, |metrics| metrics.line_height.ceil());
+        let layout = self.text_layout(text, None);
+        let caret_x = layout.caret_x(0, edit.cursor);
+        let selection = edit.selection();
+        let selection_x = layout.caret_x(0, selection.start);
+        let selection_width = layout.caret_x(0, selection.end) - selection_x;
+
+        // Scroll long text so the caret stays inside the field
+        let inner_width = (aabb.z - aabb.x - padding * 2.0).max(0.0);
+        let scroll = (caret_x + 2.0 - inner_width).max(0.0);
+
+        let mut decl = Declaration::new();
+        decl.id(id)
+            .layout()
+            .width(grow!())
+            .height(fixed!(line_height + padding * 2.0))
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Left,
+                LayoutAlignmentY::Center,
+            ))
+            .end();
+        apply_style(&mut decl, &style);
+        decl.clip(true, false, Vector2::new(-scroll, 0.0));
+        if focused {
+            decl.border().color(rgb(100, 150, 255)).end();
+        }
+        self.set_cursor(id, CursorIcon::Text);
+
+        let overlay = |x: f32, width: f32, color: ClayColor| {
+            let mut decl = Declaration::new();
+            decl.layout()
+                .width(fixed!(width))
+                .height(fixed!(line_height))
+                .end()
+                .floating()
+                .attach_to(FloatingAttachToElement::Parent)
+                .offset(Vector2::new(padding + x - scroll, padding))
+                .end()
+                .background_color(color);
+            decl
+        };
+
+        let text_color = style.text_color.unwrap_or(rgb(220, 220, 220));
+        self.with_layout(&decl, |ui| {
+            if focused && selection_width > 0.0 {
+                ui.with_layout(
+                    &overlay(selection_x, selection_width, rgba(100, 150, 255, 96)),
+                    |_ui| {},
+                );
+            }
+
+            self.declare_text(
+                display,
+                TextConfig::new()
+                    .font_id(font_id as u16)
+                    .font_size(font_size as _)
+                    .wrap_mode(clay_layout::text::TextElementConfigWrapMode::None)
+                    .color(text_color)
+                    .end(),
+            );
+
+            if focused {
+                ui.with_layout(&overlay(caret_x, 2.0, text_color), |_ui| {});
+            }
+        });
+
+        changed
+    }
+
+    /// Text editor for several lines of text, wrapped at the width of the editor and scrolled
+    /// vertically with the mouse wheel or by moving the caret. `size` is the outer size of the
+    /// editor. Returns true when `text` changed this frame.
+    ///
+    /// Styled by the `text_edit` stylesheet rules.
+    pub fn text_edit_multiline(&self, id: &str, text: &mut String, size: Vec2) -> bool {
+        let state = get_state_mut!(self);
+        let font_id = state.active_font;
+        let font_size = state.font_size;
+        let id = self.id(id);
+
+        let style = self.animated_widget_style("text_edit", id);
+        let padding = style.padding.unwrap_or(6) as f32;
+        let wrap_width = (size.x - padding * 2.0).max(0.0);
+        let view_height = (size.y - padding * 2.0).max(0.0);
+
+        let item = state.item_states.entry(id.id.id).or_default();
+        let (was_clicked, aabb) = (item.was_clicked, item.aabb);
+        let (mut edit, mut scroll) = (item.text_edit, item.scroll.y);
+        edit.clamp(text);
+
+        let layout_text = |text: &str| {
+            get_state_mut!(self)
+                .text_generator
+                .layout_text(text, font_id, font_size, Some(wrap_width))
+                .unwrap_or_default()
+        };
+        let mut layout = layout_text(text);
+
+        let input = &state.input;
+        let modifiers = input.modifiers();
+        self.register_focusable(id);
+        let mut focused = self.text_focus(id, was_clicked);
+        let mut changed = false;
+        let caret = edit.cursor;
+
+        if was_clicked {
+            let offset = layout.offset_at(
+                input.mouse_pos.x - aabb.x - padding,
+                input.mouse_pos.y - aabb.y - padding + scroll,
+            );
+            edit.preferred_x = None;
+            edit.move_to(offset, modifiers.shift());
+        }
+        scroll -= self.take_scroll(aabb).y * layout.line_height;
+
+        if focused {
+            for key in &input.keys_pressed {
+                if *key == Key::Escape {
+                    focused = false;
+                } else if edit.key_multiline(text, *key, modifiers, &layout) {
+                    changed = true;
+                    layout = layout_text(text);
+                }
+            }
+            if !modifiers.command() {
+                let typed: String = input.text.chars().filter(|c| !c.is_control()).collect();
+                if edit.insert(text, &typed) {
+                    changed = true;
+                    layout = layout_text(text);
+                }
+            }
+        }
+
+        // Follow the caret when it moved, otherwise keep the scroll position
+        let line_height = layout.line_height;
+        let caret_line = layout.line_at(edit.cursor);
+        let caret_top = caret_line as f32 * line_height;
+        if edit.cursor != caret || changed {
+            scroll = scroll.clamp(caret_top + line_height - view_height, caret_top);
+        }
+        scroll = scroll.clamp(0.0, (layout.height() - view_height).max(0.0));
+
+        self.set_focused(id, focused);
+        let item = state.item_states.entry(id.id.id).or_default();
+        item.text_edit = edit;
+        item.scroll.y = scroll;
+
+        let mut decl = Declaration::new();
+        decl.id(id)
+            .layout()
+            .width(fixed!(size.x))
+            .height(fixed!(size.y))
+            .direction(LayoutDirection::TopToBottom)
+            .end();
+        apply_style(&mut decl, &style);
+        decl.clip(false, true, Vector2::new(0.0, -scroll));
+        if focused {
+            decl.border().color(rgb(100, 150, 255)).end();
+        }
+        self.set_cursor(id, CursorIcon::Text);
+
+        let overlay = |x: f32, width: f32, color: ClayColor| {
+            let mut decl = Declaration::new();
+            decl.layout()
+                .width(fixed!(width))
+                .height(fixed!(line_height))
+                .end()
+                .floating()
+                .attach_to(FloatingAttachToElement::Parent)
+                .clip_to(FloatingClipToElement::AttachedParent)
+                .offset(Vector2::new(x, 0.0))
+                .end()
+                .background_color(color);
+            decl
+        };
+
+        // Only the visible lines are added, with a spacer for the ones scrolled past
+        let first = if line_height > 0.0 {
+            ((scroll / line_height) as usize).min(layout.lines.len())
+        } else {
+            0
+        };
+        let visible = if line_height > 0.0 {
+            (view_height / line_height).ceil() as usize + 1
+        } else {
+            layout.lines.len()
+        };
+
+        let text_color = style.text_color.unwrap_or(rgb(220, 220, 220));
+        let selection = edit.selection();
+        self.with_layout(&decl, |ui| {
+            if first > 0 {
+                let mut spacer = Declaration::new();
+                spacer
+                    .layout()
+                    .width(grow!())
+                    .height(fixed!(first as f32 * line_height))
+                    .end();
+                ui.with_layout(&spacer, |_ui| {});
+            }
+
+            for (index, line) in layout.lines.iter().enumerate().skip(first).take(visible) {
+                let mut row = Declaration::new();
+                row.layout()
+                    .width(grow!())
+                    .height(fixed!(line_height))
+                    .end();
+
+                ui.with_layout(&row, |ui| {
+                    let start = selection.start.max(line.range.start);
+                    if focused && start < selection.end && start <= line.range.end {
+                        let x = layout.caret_x(index, start);
+                        let end_x = if selection.end > line.range.end {
+                            line.width
+                        } else {
+                            layout.caret_x(index, selection.end)
+                        };
+                        ui.with_layout(
+                            &overlay(x, (end_x - x).max(4.0), rgba(100, 150, 255, 96)),
+                            |_ui| {},
+                        );
+                    }
+
+                    if !line.range.is_empty() {
+                        let line_text = &text[line.range.clone()];
+                        let _ = state
+                            .text_generator
+                            .queue_generate_text(line_text, font_size, font_id);
+                        self.declare_text(
+                            line_text,
+                            TextConfig::new()
+                                .font_id(font_id as u16)
+                                .font_size(font_size as _)
+                                .wrap_mode(clay_layout::text::TextElementConfigWrapMode::None)
+                                .color(text_color)
+                                .end(),
+                        );
+                    }
+
+                    if focused && index == caret_line {
+                        let x = layout.caret_x(index, edit.cursor);
+                        ui.with_layout(&overlay(x, 2.0, text_color), |_ui| {});
+                    }
+                });
+            }
+        });
+
+        changed
+    }
+
+    /// Focus handling shared by the text inputs: clicking takes focus and pressing the mouse
+    /// anywhere else gives it up. Returns if `id` is focused.
+    fn text_focus(&self, id: Id, was_clicked: bool) -> bool {
+        let state = get_state_mut!(self);
+        let input = &state.input;
+        let focused = state.focus_id.is_some_and(|focus| focus.id.id == id.id.id);
+        if was_clicked {
+            true
+        } else {
+            focused && !(input.button_down(MouseButton::Left) && input.pressed_id != Some(id.id.id))
+        }
+    }
+
+    pub(crate) fn is_focused(&self, id: Id) -> bool {
+        let state = get_state_mut!(self);
+        state.focus_id.is_some_and(|focus| focus.id.id == id.id.id)
+    }
+
+    /// Gives `id` keyboard focus, or takes it away if it has it.
+    pub(crate) fn set_focused(&self, id: Id, focused: bool) {
+        let state = get_state_mut!(self);
+        if focused {
+            state.focus_id = Some(id);
+        } else if state.focus_id.is_some_and(|focus| focus.id.id == id.id.id) {
+            state.focus_id = None;
+        }
+    }
+
+    /// Adds `text` in the active font and size to the current element.
+    pub(crate) fn text_element(&self, text: &str, color: ClayColor) {
+        let state = get_state_mut!(self);
+        let font_id = state.active_font;
+        let font_size = state.font_size;
+
+        let _ = state
+            .text_generator
+            .queue_generate_text(text, font_size, font_id);
+
+        self.declare_text(
+            text,
+            TextConfig::new()
+                .font_id(font_id as u16)
+                .font_size(font_size as _)
+                .wrap_mode(clay_layout::text::TextElementConfigWrapMode::None)
+                .color(color)
+                .end(),
+        );
+    }
+
+    /// Width of `text` in the active font and size.
+    pub(crate) fn text_width(&self, text: &str) -> f32 {
+        let state = get_state_mut!(self);
+        state
+            .text_generator
+            .measure_text_size(text, state.active_font, state.font_size)
+            .map_or(0.0, |(width, _)| width)
+    }
+
+    /// Lays out `text` in the active font and size, wrapped at `wrap_width` if given, to find
+    /// where its characters are. Empty before the first font is loaded.
+    pub fn text_layout(&self, text: &str, wrap_width: Option<f32>) -> TextLayout {
+        let state = get_state_mut!(self);
+        state
+            .text_generator
+            .layout_text(text, state.active_font, state.font_size, wrap_width)
+            .unwrap_or_default()
+    }
+
+    /// The character boundary of `text` on one line closest to `point`, relative to the top
+    /// left of the text. This is where the caret goes when the text is clicked at `point`.
+    pub fn text_hit_test(&self, text: &str, point: Vec2) -> CharIndex {
+        self.text_layout(text, None).offset_at(point.x, point.y)
+    }
+
+    /// Rect of the character of `text` on one line at `index`, see [`TextLayout::char_rect`].
+    pub fn char_rect(&self, text: &str, index: CharIndex) -> Option<Vec4> {
+        self.text_layout(text, None).char_rect(index)
+    }
+
+    /// A box with a check mark followed by `label`. Clicking anywhere on it toggles `value`.
+    /// Returns true when the value changed this frame.
+    ///
+    /// The `checkbox` stylesheet rules style the box: `border_color`, `border` and `radius`
+    /// for the frame, `background` for the check mark and `text_color` for the label.
+    pub fn checkbox(&self, label: &str, value: &mut bool) -> bool {
+        let clicked = self.mark_widget("checkbox", label, *value, false);
+        if clicked {
+            *value = !*value;
+        }
+        clicked
+    }
+
+    /// A round button followed by `label` that is selected when `current` equals `value`.
+    /// Clicking it sets `current` to `value`, use one per choice to pick one of several values.
+    /// Returns true when `current` changed this frame.
+    ///
+    /// Styled by the `radio` stylesheet rules, like [`Ui::checkbox`] but always round.
+    pub fn radio<T: PartialEq>(&self, label: &str, current: &mut T, value: T) -> bool {
+        let selected = *current == value;
+        let clicked = self.mark_widget("radio", label, selected, true);
+        if clicked && !selected {
+            *current = value;
+        }
+        clicked && !selected
+    }
+
+    /// Shared by checkboxes and radio buttons: a box or circle that shows a mark while
+    /// `marked`, followed by `label`. Returns true if it was clicked.
+    fn mark_widget(&self, widget: &str, label: &str, marked: bool, round: bool) -> bool {
+        let state = get_state_mut!(self);
+        let font_id = state.active_font;
+        let font_size = state.font_size;
+
+        let _ = state
+            .text_generator
+            .queue_generate_text(label, font_size, font_id);
+
+        let id = self.id(label);
+        let clicked = state
+            .item_states
+            .get(&id.id.id)
+            .is_some_and(|item| item.was_clicked);
+
+        let style = self.animated_widget_style(widget, id);
+        let text_color = style.text_color.unwrap_or(rgb(220, 220, 220));
+        let box_size = (font_size as f32 * 0.7).round();
+        let mark_padding = (box_size / 5.0).round();
+        let (box_radius, mark_radius) = if round {
+            (box_size / 2.0, box_size / 2.0 - mark_padding)
+        } else {
+            (style.radius.unwrap_or(0.0), 0.0)
+        };
+
+        let mut decl = Declaration::new();
+        decl.id(id)
+            .layout()
+            .width(fit!())
+            .height(fit!())
+            .padding(Padding::all(4))
+            .child_gap(8)
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Left,
+                LayoutAlignmentY::Center,
+            ))
+            .direction(LayoutDirection::LeftToRight)
+            .end();
+
+        let mut box_decl = Declaration::new();
+        box_decl
+            .layout()
+            .width(fixed!(box_size))
+            .height(fixed!(box_size))
+            .padding(Padding::all(mark_padding as u16))
+            .end()
+            .corner_radius()
+            .all(box_radius)
+            .end()
+            .border()
+            .all_directions(style.border_width.unwrap_or(1))
+            .color(style.border_color.unwrap_or(rgb(128, 128, 128)))
+            .end();
+
+        let mut mark_decl = Declaration::new();
+        mark_decl
+            .layout()
+            .width(grow!())
+            .height(grow!())
+            .end()
+            .corner_radius()
+            .all(mark_radius)
+            .end()
+            .background_color(style.background.unwrap_or(rgb(100, 150, 255)));
+
+        self.with_layout(&decl, |ui| {
+            ui.with_layout(&box_decl, |ui| {
+                if marked {
+                    ui.with_layout(&mark_decl, |_ui| {});
+                }
+            });
+
+            self.declare_text(
+                label,
+                TextConfig::new()
+                    .font_id(font_id as u16)
+                    .font_size(font_size as _)
+                    .wrap_mode(clay_layout::text::TextElementConfigWrapMode::None)
+                    .color(text_color)
+                    .end(),
+            );
+        });
+
+        clicked
+    }
+
+    /// An indeterminate busy indicator, a ring of `size` pixels with dots fading after a head
+    /// that goes around once a second. Use it while waiting on async work such as font loads,
+    /// it only moves when frames are drawn so the host has to keep requesting them.
+    ///
+    /// The `spinner` stylesheet rule sets the dot color with `background`.
+    pub fn spinner(&self, size: f32) {
+        const DOTS: usize = 8;
+
+        let state = get_state_mut!(self);
+        let style = self.widget_style("spinner", WidgetState::default());
+        let color = style.background.unwrap_or(rgb(220, 220, 220));
+        let head = state.time.fract() as f32;
+
+        let dot_size = (size * 0.2).round().max(1.0);
+        let radius = (size - dot_size) * 0.5;
+
+        let mut decl = Declaration::new();
+        decl.layout().width(fixed!(size)).height(fixed!(size)).end();
+
+        self.with_layout(&decl, |ui| {
+            for i in 0..DOTS {
+                let t = i as f32 / DOTS as f32;
+                let angle = t * std::f32::consts::TAU - std::f32::consts::FRAC_PI_2;
+                // How far behind the head the dot is, 0.0 for the dot at the head
+                let age = (head - t).rem_euclid(1.0);
+
+                let mut dot = Declaration::new();
+                dot.layout()
+                    .width(fixed!(dot_size))
+                    .height(fixed!(dot_size))
+                    .end()
+                    .floating()
+                    .attach_to(FloatingAttachToElement::Parent)
+                    .offset(Vector2::new(
+                        radius + radius * angle.cos(),
+                        radius + radius * angle.sin(),
+                    ))
+                    .end()
+                    .corner_radius()
+                    .all(dot_size * 0.5)
+                    .end()
+                    .background_color(ClayColor {
+                        a: color.a * (1.0 - age * 0.85),
+                        ..color
+                    });
+                ui.with_layout(&dot, |_ui| {});
+            }
+        });
+    }
+
+    /// A header row with `label` that expands to show the content added by `f` below it,
+    /// indented. Clicking the header toggles it and the open state is kept across frames.
+    /// Returns true while open.
+    ///
+    /// Styled by the `tree_node` stylesheet rules: `background`, `radius` and `padding` for the
+    /// header, `text_color` for the label and `border_color` for the expand icon.
+    pub fn tree_node(&self, label: &str, f: impl FnOnce(&Ui)) -> bool {
+        self.expander("tree_node", label, false, true, f)
+    }
+
+    /// A section with a clickable header showing `label` and an arrow, and the content added
+    /// by `f` below it while open. Starts out open if `default_open`, after that the open state
+    /// is kept across frames. Returns true while open.
+    ///
+    /// Styled by the `collapsing_header` stylesheet rules, like [`Ui::tree_node`].
+    pub fn collapsing_header(&self, label: &str, default_open: bool, f: impl FnOnce(&Ui)) -> bool {
+        self.expander("collapsing_header", label, default_open, false, f)
+    }
+
+    /// Shared by tree nodes and collapsing headers. Tree nodes show a plus or minus sign and
+    /// indent their children, headers show an arrow.
+    fn expander(
+        &self,
+        widget: &str,
+        label: &str,
+        default_open: bool,
+        tree: bool,
+        f: impl FnOnce(&Ui),
+    ) -> bool {
+        let state = get_state_mut!(self);
+        let font_size = state.font_size;
+
+        let id = self.id(label);
+        let is_new = !state.item_states.contains_key(&id.id.id);
+        let item = self.item_state(id);
+        if is_new {
+            item.open = default_open;
+        }
+        if item.was_clicked {
+            item.open = !item.open;
+        }
+        let open = item.open;
+
+        self.expander_header(widget, id, label, open, tree);
+
+        if open {
+            let indent = if tree {
+                (font_size as f32 * 0.8).round() as u16
+            } else {
+                0
+            };
+            let mut children = Declaration::new();
+            children
+                .layout()
+                .width(grow!())
+                .height(fit!())
+                .padding(Padding::new(indent, 0, 0, 0))
+                .direction(LayoutDirection::TopToBottom)
+                .end();
+            self.with_layout(&children, f);
+        }
+
+        // Tooltips and context menus after this go with the header, not the last child
+        self.set_last_item(Some(id));
+        open
+    }
+
+    /// The clickable row of an expander showing `label` after a plus or minus sign for tree
+    /// nodes, an arrow otherwise.
+    pub(crate) fn expander_header(
+        &self,
+        widget: &str,
+        id: Id,
+        label: &str,
+        open: bool,
+        tree: bool,
+    ) {
+        let state = get_state_mut!(self);
+        let font_id = state.active_font;
+        let font_size = state.font_size;
+
+        let _ = state
+            .text_generator
+            .queue_generate_text(label, font_size, font_id);
+
+        let style = self.animated_widget_style(widget, id);
+        let text_color = style.text_color.unwrap_or(rgb(220, 220, 220));
+        let icon_color = style.border_color.unwrap_or(rgb(160, 160, 160));
+
+        let mut decl = Declaration::new();
+        decl.id(id)
+            .layout()
+            .width(grow!())
+            .height(fit!())
+            .child_gap(8)
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Left,
+                LayoutAlignmentY::Center,
+            ))
+            .direction(LayoutDirection::LeftToRight)
+            .end();
+        apply_style(&mut decl, &style);
+
+        self.with_layout(&decl, |ui| {
+            ui.expand_icon(open, tree, icon_color);
+
+            self.declare_text(
+                label,
+                TextConfig::new()
+                    .font_id(font_id as u16)
+                    .font_size(font_size as _)
+                    .wrap_mode(clay_layout::text::TextElementConfigWrapMode::None)
+                    .color(text_color)
+                    .end(),
+            );
+        });
+    }
+
+    /// The expand icon of an expander in `icon_color`: a plus sign while closed and a minus sign
+    /// while open if `tree`, an arrow pointing right or down otherwise.
+    pub(crate) fn expand_icon(&self, open: bool, tree: bool, icon_color: ClayColor) {
+        let icon_size = (self.font_size() as f32 * 0.5).round();
+        let bar = (icon_size / 5.0).round().max(1.0);
+
+        // Arrows are stacked one pixel rows, pointing right while closed and down while open
+        let arrow_align = if open {
+            LayoutAlignmentX::Center
+        } else {
+            LayoutAlignmentX::Left
+        };
+        let mut icon_decl = Declaration::new();
+        icon_decl
+            .layout()
+            .width(fixed!(icon_size))
+            .height(fixed!(icon_size))
+            .direction(LayoutDirection::TopToBottom)
+            .child_alignment(Alignment::new(arrow_align, LayoutAlignmentY::Center))
+            .end();
+
+        let row_decl = |width: f32| {
+            let mut decl = Declaration::new();
+            decl.layout()
+                .width(fixed!(width))
+                .height(fixed!(1.0))
+                .end()
+                .background_color(icon_color);
+            decl
+        };
+
+        // A plus sign while closed and a minus sign while open
+        let bar_decl = |vertical: bool| {
+            let (width, height) = if vertical {
+                (bar, icon_size)
+            } else {
+                (icon_size, bar)
+            };
+            let mut decl = Declaration::new();
+            decl.layout()
+                .width(fixed!(width))
+                .height(fixed!(height))
+                .end()
+                .floating()
+                .attach_to(FloatingAttachToElement::Parent)
+                .clip_to(FloatingClipToElement::AttachedParent)
+                .offset(Vector2::new(
+                    ((icon_size - width) * 0.5).round(),
+                    ((icon_size - height) * 0.5).round(),
+                ))
+                .end()
+                .background_color(icon_color);
+            decl
+        };
+
+        self.with_layout(&icon_decl, |ui| {
+            let rows = icon_size as usize;
+            if tree {
+                ui.with_layout(&bar_decl(false), |_ui| {});
+                if !open {
+                    ui.with_layout(&bar_decl(true), |_ui| {});
+                }
+            } else if open {
+                for row in 0..rows / 2 {
+                    ui.with_layout(&row_decl((rows - row * 2) as f32), |_ui| {});
+                }
+            } else {
+                for row in 0..rows {
+                    ui.with_layout(&row_decl((row + 1).min(rows - row) as f32), |_ui| {});
+                }
+            }
+        });
+    }
+
+    pub fn with_layout<F: FnOnce(&Ui)>(&self, declaration: &UiDeclaration<'_>, f: F) {
+        match self.toolbar_slot(self.declaring_depth()) {
+            ToolbarSlot::Outside => self.declare_element(declaration, f),
+            ToolbarSlot::Hidden => {}
+            ToolbarSlot::Item(wrapper) => {
+                // Tooltips and context menus go with the item, not its wrapper
+                let mut last_item = None;
+                self.declare_element(&wrapper, |ui| {
+                    ui.declare_element(declaration, f);
+                    last_item = ui.last_item();
+                });
+                self.set_last_item(last_item);
+            }
+        }
+    }
+
+    fn declare_element<F: FnOnce(&Ui)>(&self, declaration: &UiDeclaration<'_>, f: F) {
+        let state = get_state_mut!(self);
+
+        let id = declaration.element_id();
+        let z_index = declaration
+            .floating_z_index()
+            .or(state.z_stack.last().copied())
+            .unwrap_or(0);
+        state.z_stack.push(z_index);
+        if let Some(id) = id {
+            state
+                .id_paths
+                .declare(id.id.id, state.parent_stack.last().copied());
+            state.frame_tree.push(TreeNode {
+                id,
+                parent: state.parent_stack.last().copied(),
+                depth: state.parent_stack.len() as u32,
+                padding: declaration.element_padding(),
+                z_index,
+            });
+            state.parent_stack.push(id.id.id);
+        }
+
+        let declaration = self.frame_declaration(declaration);
+        state.layout().with(&declaration, |_clay| {
+            let _guard = UnwindGuard(self);
+            f(self);
+        });
+
+        state.z_stack.pop();
+        if id.is_some() {
+            state.parent_stack.pop();
+            state.last_item = id;
+        }
+    }
+
+    /// Keeps `text` alive until the next frame starts and returns a reference to it. Use this for
+    /// strings built while declaring the frame that have to outlive the code building them, text
+    /// handed to Clay is copied the same way.
+    pub fn alloc_str(&self, text: String) -> &str {
+        let state = get_state_mut!(self);
+        state.frame_strings.push(text);
+        let text = state.frame_strings.last().unwrap().as_str();
+        // The heap buffer of a String doesn't move when the Vec grows and the strings are only
+        // dropped in `begin`, which borrows the ui mutably so the reference is gone by then.
+        unsafe { &*(text as *const str) }
+    }
+
+    /// Keeps `image` alive until the next frame starts, like [`Ui::alloc_str`].
+    pub(crate) fn alloc_image(&self, image: ImageInfo) -> &ImageInfo {
+        let state = get_state_mut!(self);
+        state.frame_images.push(Box::new(image));
+        let image = state.frame_images.last().unwrap().as_ref();
+        // Same as for strings, the box doesn't move and is only dropped in `begin`, which can't
+        // run while the reference is alive
+        unsafe { &*(image as *const ImageInfo) }
+    }
+
+    /// Copy of `declaration` to hand to Clay, which keeps a pointer to the image it shows until
+    /// the frame has been rendered. The image is copied into the frame first, so the caller's
+    /// may be dropped right after declaring it.
+    fn frame_declaration(&self, declaration: &UiDeclaration<'_>) -> UiDeclaration<'static> {
+        let image = declaration.image_data().map(|image| {
+            let image = self.alloc_image(image.clone());
+            // The copy lives until the next `begin`, which also starts a new layout scope
+            unsafe { &*(image as *const ImageInfo) }
+        });
+        declaration.with_data(image, declaration.custom_data().map(|_| &()))
+    }
+
+    /// Adds a copy of `text` to the element being declared, as Clay keeps a pointer to it until
+    /// the frame has been rendered.
+    fn declare_text(&self, text: &str, config: TextElementConfig) {
+        let text = self.alloc_str(text.to_owned());
+        get_state_mut!(self).layout().text(text, config);
+    }
+
+    /// Replaces the stylesheet (by default [`crate::style::DEFAULT_STYLESHEET`]).
+    pub fn set_stylesheet(&self, stylesheet: Stylesheet) {
+        let state = get_state_mut!(self);
+        state.stylesheet = stylesheet;
+    }
+
+    pub(crate) fn push_pending_class(&self, name: &str) {
+        let state = get_state_mut!(self);
+        state.pending_classes.push(name.to_string());
+    }
+
+    /// Resolves the style for a widget and consumes the classes set with [`Ui::class`].
+    pub(crate) fn widget_style(&self, widget: &str, widget_state: WidgetState) -> Style {
+        let state = get_state_mut!(self);
+        let style = state
+            .stylesheet
+            .resolve(widget, &state.pending_classes, widget_state);
+        state.pending_classes.clear();
+        style
+    }
+
+    /// Interaction state of `id` as of the previous frame.
+    pub(crate) fn widget_state(&self, id: Id) -> WidgetState {
+        let state = get_state_mut!(self);
+        state
+            .item_states
+            .get(&id.id.id)
+            .map_or(WidgetState::default(), |item| WidgetState {
+                hovered: item.was_hovered,
+                active: state.input.pressed_id == Some(id.id.id),
+                disabled: false,
+            })
+    }
+
+    /// Linear progress of the bool animation for `id`, see [`Ui::animate_bool`].
+    pub(crate) fn step_bool_animation(&self, id: Id, value: bool, time: f32) -> f32 {
+        let state = get_state_mut!(self);
+        let frame = state.current_frame;
+        let animation = state
+            .bool_animations
+            .entry(id.id.id)
+            .or_insert_with(|| BoolAnimation::new(value, frame));
+
+        // Only step once per frame if the same id is asked for several times
+        if animation.frame == frame {
+            return animation.update(value, time, 0.0);
+        }
+        animation.frame = frame;
+        animation.update(value, time, state.delta_time)
+    }
+
+    /// When enabled, items glide from where they were drawn last frame to their new layout
+    /// position instead of jumping, for example when a panel is toggled. Input hit testing
+    /// always uses the final layout.
+    pub fn set_animated_layout(&self, enabled: bool) {
+        let state = get_state_mut!(self);
+        state.animated_layout = enabled;
+    }
+
+    /// Hover, press and focus factors of `id`. Items that weren't laid out in the previous frame
+    /// are at rest.
+    pub fn item_factors(&self, id: Id) -> ItemFactors {
+        let state = get_state_mut!(self);
+        state
+            .item_states
+            .get(&id.id.id)
+            .map_or(ItemFactors::default(), |item| ItemFactors {
+                hover: item.hover,
+                press: item.press,
+                active: item.active,
+            })
+    }
+
+    /// Like [`Ui::widget_style`] but blends the normal, `:hover` and `:active` rules by the
+    /// animated [`ItemFactors`] of `id` so state changes fade instead of snapping.
+    pub(crate) fn animated_widget_style(&self, widget: &str, id: Id) -> Style {
+        let factors = self.item_factors(id);
+        let state = get_state_mut!(self);
+        let resolve = |hovered, active| {
+            state.stylesheet.resolve(
+                widget,
+                &state.pending_classes,
+                WidgetState {
+                    hovered,
+                    active,
+                    disabled: false,
+                },
+            )
+        };
+
+        let style = resolve(false, false)
+            .lerp(&resolve(true, false), factors.hover)
+            .lerp(&resolve(true, true), factors.press);
+        state.pending_classes.clear();
+        style
+    }
+
+    /// Queues an input event for the next frame. See [`crate::input`] for how events are
+    /// dispatched.
+    pub fn push_event(&self, event: Event) {
+        let state = get_state_mut!(self);
+        state.pending_events.push(event);
+    }
+
+    /// Shows `cursor` while the mouse is over `id`, unless an element on top of it declares its
+    /// own. Usually set with `cursor:` in [`crate::area!`].
+    pub fn set_cursor(&self, id: Id, cursor: CursorIcon) {
+        let state = get_state_mut!(self);
+        state.frame_cursors.insert(id.id.id, cursor);
+    }
+
+    /// Cursor the host should show, resolved in [`Ui::end`] from the mouse position and the
+    /// elements of the frame.
+    pub fn cursor_icon(&self) -> CursorIcon {
+        let state = get_state_mut!(self);
+        state.input.cursor_at(state.input.mouse_pos)
+    }
+
+    /// Where the host should move the mouse pointer to after [`Ui::end`], asked for by widgets
+    /// that wrap the pointer around the window while dragging. Taking it moves the mouse
+    /// position of the ui there too, so the jump isn't seen as movement. Hosts that can't move
+    /// the pointer don't call this.
+    pub fn take_cursor_warp(&self) -> Option<Vec2> {
+        let state = get_state_mut!(self);
+        let pos = state.cursor_warp.take()?;
+        state.input.mouse_pos = pos;
+        Some(pos)
+    }
+
+    /// Asks the host to move the pointer to `pos`, see [`Ui::take_cursor_warp`].
+    pub(crate) fn warp_cursor(&self, pos: Vec2) {
+        let state = get_state_mut!(self);
+        state.cursor_warp = Some(pos);
+    }
+
+    pub(crate) fn input(&self) -> &InputState {
+        let state = get_state_mut!(self);
+        &state.input
+    }
+
+    /// See [`InputState::take_scroll`].
+    pub(crate) fn take_scroll(&self, aabb: Vec4) -> Vec2 {
+        let state = get_state_mut!(self);
+        state.input.take_scroll(aabb)
+    }
+
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) fn event_hooks(&self) -> &mut EventHooks {
+        let state = get_state_mut!(self);
+        &mut state.event_hooks
+    }
+
+    /// Runs the queued events through the hooks and updates the hover and click state of the
+    /// items laid out in the previous frame.
+    fn process_events(&self) {
+        let state = get_state_mut!(self);
+        let mut events = std::mem::take(&mut state.pending_events);
+
+        state.input.begin_frame();
+        for event in events.drain(..) {
+            state
+                .input
+                .process(event, &mut state.event_hooks, state.current_frame);
+        }
+        state.pending_events = events;
+
+        let input = &state.input;
+        for (id, item) in state.item_states.iter_mut() {
+            let hovered_before = item.was_hovered;
+            item.was_hovered = input.is_hovered(item.aabb) && input.accepts(*id);
+            item.hover_time = if item.was_hovered {
+                item.hover_time + state.delta_time
+            } else {
+                0.0
+            };
+            let click = input
+                .clicked
+                .iter()
+                .find(|(clicked, button, _)| clicked == id && *button == MouseButton::Left);
+            item.was_clicked = click.is_some();
+            item.click_modifiers = click.map_or(Modifiers::NONE, |(_, _, modifiers)| *modifiers);
+
+            if let Some(metrics) = &mut state.widget_metrics {
+                let entered = item.was_hovered && !hovered_before;
+                metrics.interact(*id, entered, item.was_clicked, state.time);
+            }
+        }
+    }
+
+    /// Id of the element `name`. Ids are stable across runs and versions, see
+    /// [`crate::id_hash`].
+    #[inline]
+    pub fn id(&self, name: &str) -> Id {
+        let state = get_state_mut!(self);
+        let id = make_id(name, 0, state.id_seed);
+        state.id_paths.name(id.id.id, || name.to_string());
+        id
+    }
+
+    /// Id for the `index`th of several elements sharing `name`.
+    #[inline]
+    pub fn id_index(&self, name: &str, index: u32) -> Id {
+        let state = get_state_mut!(self);
+        let id = make_id(name, index, state.id_seed);
+        state
+            .id_paths
+            .name(id.id.id, || format!("{}[{}]", name, index));
+        id
+    }
+
+    /// Changes the seed all ids are hashed with, for example to keep the ids of two `Ui`s
+    /// sharing persisted state apart. Set it before the first frame, the state of widgets
+    /// declared before is lost.
+    pub fn set_id_seed(&self, seed: u32) {
+        let state = get_state_mut!(self);
+        state.id_seed = seed;
+    }
+
+    /// Path of the element `id` made from the names of it and its parents, such as
+    /// `daw_ui_root/toolbar/transport/play`, for diagnostics. Only debug builds keep track of
+    /// them, otherwise and for unknown ids it's the hash as `#1234`.
+    pub fn id_path(&self, id: Id) -> String {
+        self.describe_id(id.id.id)
+    }
+
+    /// [`Ui::id_path`] of the raw id of an element.
+    pub(crate) fn describe_id(&self, id: u32) -> String {
+        let state = get_state_mut!(self);
+        state.id_paths.describe(id)
+    }
+
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) fn metrics_recorder(&self) -> &mut Option<MetricsRecorder> {
+        let state = get_state_mut!(self);
+        &mut state.widget_metrics
+    }
+
+    /// Elements with an id in declaration order. Between [`Ui::end`] and the next
+    /// [`Ui::begin`] these are the ones of the frame that was just rendered.
+    pub(crate) fn frame_tree(&self) -> &[TreeNode] {
+        let state = get_state_mut!(self);
+        &state.frame_tree
+    }
+
+    /// `text` in the active font and size turned by `rotation`, once it's rasterized.
+    pub(crate) fn rotated_text(&self, text: &str, rotation: TextRotation) -> Option<ImageInfo> {
+        let state = get_state_mut!(self);
+        state
+            .text_generator
+            .rotated_text(text, state.font_size, state.active_font, rotation)
+    }
+
+    /// `text` in the active font rasterized on the calling thread, for tools that can't wait
+    /// for the workers. `None` until the font has been loaded by the workers.
+    pub(crate) fn generate_text_now(&self, text: &str, font_size: u32) -> Option<CachedString> {
+        let state = get_state_mut!(self);
+        state
+            .text_generator
+            .generate_text_now(text, font_size, state.active_font)
+    }
+
+    /// Rectangle (x0, y0, x1, y1) of the element with `id` in the previous frame.
+    pub(crate) fn last_frame_rect(&self, id: Id) -> Option<Vec4> {
+        let state = get_state_mut!(self);
+        state.input.rect_of(id.id.id)
+    }
+
+    /// Starts declaring a frame. A frame that never reached [`Ui::end`], for example because
+    /// declaring it panicked, is thrown away first.
+    ///
+    /// Takes the ui mutably as it frees the strings and images allocated for the previous frame,
+    /// see [`Ui::alloc_str`].
+    pub fn begin(&mut self, delta_time: f32, window_size: (usize, usize)) {
+        let state = get_state_mut!(self);
+        if state.frame_phase != FramePhase::Idle {
+            self.discard_frame();
+        }
+        state.frame_phase = FramePhase::Declaring;
+        state.window_size = window_size;
+        state.delta_time = delta_time;
+        state.time += delta_time as f64;
+        state.frame_start = Instant::now();
+        state.deferred_tasks = 0;
+        state.font_size = self.font_size_for(TextRole::Body);
+        state.frame_tree.clear();
+        state.parent_stack.clear();
+        state.z_stack.clear();
+        state.last_item = None;
+        state.modal_layer = None;
+        state.cursor_warp = None;
+        state.frame_strings.clear();
+        state.frame_images.clear();
+        state.pending_classes.clear();
+        state.stylesheet.reload_if_changed();
+
+        // Forget animations that weren't asked for in the last frame
+        let last_frame = state.current_frame.saturating_sub(1);
+        state
+            .bool_animations
+            .retain(|_, animation| animation.frame >= last_frame);
+        state
+            .layout
+            .set_layout_dimensions(Dimensions::new(window_size.0 as f32, window_size.1 as f32));
+
+        state.layout_scope = Some(state.layout.begin::<ImageInfo, ()>());
+
+        self.process_events();
+        self.update();
+    }
+
+    fn update(&self) {
+        let state = get_state_mut!(self);
+        let deadline = self.frame_deadline();
+        let mut errors = state.text_generator.update(deadline);
+        errors.extend(state.image_cache.update(&state.bg_worker));
+        state.directories.update(&state.bg_worker);
+        for error in errors {
+            self.notify(NotifyLevel::Error, error);
+        }
+    }
+
+    /// Requests strings to be rasterized ahead of time, for example all track names during a
+    /// loading screen, so they don't pop in on the first frame they're shown. Each entry is
+    /// `(text, font_size, font)`. The strings are generated after the visible text of each frame.
+    pub fn prewarm_text(&self, items: &[(&str, u32, FontHandle)]) {
+        let state = get_state_mut!(self);
+        for (text, size, font) in items {
+            let _ = state.text_generator.queue_generate_text(text, *size, *font);
+        }
+    }
+
+    /// Characters of the text shown so far that none of the loaded fonts have a glyph for, in
+    /// codepoint order. They're drawn as boxes, and reported on stderr when first seen.
+    pub fn missing_glyphs(&self) -> Vec<char> {
+        let state = get_state_mut!(self);
+        state
+            .text_generator
+            .missing_glyphs()
+            .iter()
+            .copied()
+            .collect()
+    }
+
+    /// Starts decoding the given PNG files in the background so they are cached before use.
+    pub fn prewarm_images(&self, paths: &[&str]) {
+        let state = get_state_mut!(self);
+        for path in paths {
+            state.image_cache.request(path);
+        }
+    }
+
+    /// Number of strings and images that are still being prepared. A loading screen can keep
+    /// running frames until this reaches zero.
+    pub fn prewarm_pending(&self) -> usize {
+        let state = get_state_mut!(self);
+        let text = state.text_generator.stats();
+        text.pending_generations + text.inflight_generations + state.image_cache.stats().loading
+    }
+
+    /// Sets how many strings may be sent for rasterization each frame. Text that is visible on
+    /// screen is generated first, the rest is deferred to later frames.
+    pub fn set_text_jobs_per_frame(&self, count: usize) {
+        let state = get_state_mut!(self);
+        state.text_jobs_per_frame = count.max(1);
+    }
+
+    /// Sets how long text may get before it's cut, and how control characters and line breaks
+    /// are handled, see [`TextLimits`]. Strings already rasterized keep the old limits.
+    pub fn set_text_limits(&self, limits: TextLimits) {
+        let state = get_state_mut!(self);
+        state.text_generator.set_limits(limits);
+    }
+
+    pub fn text_limits(&self) -> TextLimits {
+        let state = get_state_mut!(self);
+        state.text_generator.limits()
+    }
+
+    /// Limits how long deferrable work may take per frame, measured from [`Ui::begin`]. `None`,
+    /// the default, lets all work finish in the frame it's requested. See [`crate::budget`].
+    pub fn set_frame_budget(&self, budget: Option<Duration>) {
+        let state = get_state_mut!(self);
+        state.frame_budget = budget;
+    }
+
+    /// Work that is still in progress or was deferred to later frames. Hosts that only redraw
+    /// on input should keep requesting frames until this is empty.
+    pub fn pending_work(&self) -> PendingWork {
+        let state = get_state_mut!(self);
+        let text = state.text_generator.stats();
+        PendingWork {
+            text_generations: text.inflight_generations + text.pending_generations,
+            image_loads: state.image_cache.stats().loading,
+            directory_listings: state.directories.loading(),
+            deferred_tasks: state.deferred_tasks,
+        }
+    }
 
-type UiDeclaration<'a> = Declaration<'a, ImageInfo, ()>;
-type UiLayoutScope<'a> = ClayLayoutScope<'a, 'a, ImageInfo, ()>;
-#[derive(Debug, Default)]
-#[allow(dead_code)]
-pub struct ItemState {
-    pub aabb: Vec4,
-    pub was_hovered: bool,
-    pub was_clicked: bool,
-    pub active: f32,
-    pub frame: u64,
-}
+    /// Hash of what the last [`Ui::end`] drew. Frames with the same hash look the same, so tests
+    /// can check that the ui didn't change without storing images, and hosts can skip
+    /// presenting unchanged frames. The hash is the same across runs of the same build. Text that
+    /// is still being generated isn't drawn yet, so wait for [`Ui::pending_work`] to be empty
+    /// before comparing.
+    pub fn frame_hash(&self) -> u64 {
+        let state = get_state_mut!(self);
+        state.renderer.frame_hash()
+    }
 
-struct State<'a> {
-    bg_worker: WorkSystem,
-    layout: Clay,
-    text_generator: TextGenerator,
-    font_styles: HashMap<FontStyle, FontHandle>,
-    item_states: HashMap<u32, ItemState>, // TODO: Arena hashmap
-    active_font: FontHandle,
-    layout_scope: Option<UiLayoutScope<'a>>,
-    font_size: u32,
-    window_size: (usize, usize),
-    current_frame: u64,
-    delta_time: f32,
-    focus_id: Option<Id>,
-}
+    pub(crate) fn frame_deadline(&self) -> FrameDeadline {
+        let state = get_state_mut!(self);
+        FrameDeadline::new(state.frame_start, state.frame_budget)
+    }
 
-impl<'a> State<'a> {
-    #[inline(always)]
-    pub fn layout(&mut self) -> &mut UiLayoutScope<'a> {
-        unsafe { self.layout_scope.as_mut().unwrap_unchecked() }
+    pub(crate) fn count_deferred_task(&self) {
+        let state = get_state_mut!(self);
+        state.deferred_tasks += 1;
     }
-}
 
-macro_rules! get_state_mut {
-    ($self:expr) => {
-        unsafe { &mut *$self.state.get() }
-    };
-}
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) fn memo_cache(&self) -> &mut MemoCache {
+        let state = get_state_mut!(self);
+        &mut state.memo_cache
+    }
 
-macro_rules! get_layout_mut {
-    ($self:expr) => {
-        unsafe { $self.layout_scope.as_mut().unwrap_unchecked() }
-    };
-}
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) fn item_state(&self, id: Id) -> &mut ItemState {
+        let state = get_state_mut!(self);
+        state.item_states.entry(id.id.id).or_default()
+    }
 
-pub struct Ui<'a> {
-    state: UnsafeCell<State<'a>>,
-}
+    /// Whether `id` has state yet, false before its first interaction or layout.
+    pub(crate) fn has_item_state(&self, id: Id) -> bool {
+        let state = get_state_mut!(self);
+        state.item_states.contains_key(&id.id.id)
+    }
 
-impl<'a> Ui<'a> {
-    pub fn new() -> Box<Self> {
-        let bg_worker = WorkSystem::new(2);
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) fn scroll_requests(&self) -> &mut HashMap<u32, ScrollRequest> {
+        let state = get_state_mut!(self);
+        &mut state.scroll_requests
+    }
 
-        let state = State {
-            text_generator: TextGenerator::new(&bg_worker),
-            layout: Clay::new(Dimensions::new(320.0, 256.0)),
-            layout_scope: None,
-            bg_worker,
-            font_styles: HashMap::with_capacity(8),
-            active_font: 0,
-            font_size: 32,
-            window_size: (320, 256),
-            item_states: HashMap::with_capacity(64),
-            current_frame: 0,
-            delta_time: 0.0,
-            focus_id: None,
-        };
+    /// The element closed last, usually the widget added last.
+    pub(crate) fn last_item(&self) -> Option<Id> {
+        let state = get_state_mut!(self);
+        state.last_item
+    }
 
-        let data = Box::new(Ui {
-            state: UnsafeCell::new(state),
-        });
+    pub(crate) fn set_last_item(&self, id: Option<Id>) {
+        let state = get_state_mut!(self);
+        state.last_item = id;
+    }
 
-        // This is a hack. To be fixed later
-        unsafe {
-            let raw_ptr = Box::into_raw(data);
-            Clay::set_measure_text_function_unsafe(Self::measure_text_trampoline, raw_ptr as _);
-            Box::from_raw(raw_ptr)
-        }
+    pub(crate) fn tooltip_delay(&self) -> f32 {
+        let state = get_state_mut!(self);
+        state.tooltip_delay
     }
 
-    unsafe extern "C" fn measure_text_trampoline(
-        text_slice: Clay_StringSlice,
-        config: *mut Clay_TextElementConfig,
-        user_data: *mut core::ffi::c_void,
-    ) -> Clay_Dimensions {
-        unsafe {
-            let text = core::str::from_utf8_unchecked(core::slice::from_raw_parts(
-                text_slice.chars as *const u8,
-                text_slice.length as _,
-            ));
+    /// Seconds the mouse has to rest on an item before its tooltip shows, see [`Ui::tooltip`].
+    pub fn set_tooltip_delay(&self, seconds: f32) {
+        let state = get_state_mut!(self);
+        state.tooltip_delay = seconds.max(0.0);
+    }
 
-            let text_config = TextConfig::from(*config);
-            let ui = &*(user_data as *const Ui);
+    pub fn drag_config(&self) -> DragConfig {
+        let state = get_state_mut!(self);
+        state.drag_config
+    }
 
-            ui.measure_text(text, &text_config).into()
-        }
+    /// Sets the modifiers for fine adjustment, stepping and axis lock while dragging, honored
+    /// by all the widgets that are dragged. See [`DragConfig`].
+    pub fn set_drag_config(&self, config: DragConfig) {
+        let state = get_state_mut!(self);
+        state.drag_config = config;
     }
 
-    #[inline(always)]
-    fn state(&'a self) -> &'a mut State<'a> {
-        unsafe { &mut *self.state.get() }
+    /// Makes `id` and the elements inside of it the only ones that get mouse input next frame.
+    pub(crate) fn set_modal_layer(&self, id: Id) {
+        let state = get_state_mut!(self);
+        state.modal_layer = Some(id);
     }
 
-    // Internal helper for the area! macro
-    #[doc(hidden)]
-    pub fn __internal_with_layout<F>(&self, declaration: &Declaration<'a, ImageInfo, ()>, f: F)
-    where
-        F: FnOnce(&Ui),
-    {
-        let state = unsafe { &mut *self.state.get() };
-        let clay_scope = state.layout();
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) fn popups(&self) -> &mut Popups {
+        let state = get_state_mut!(self);
+        &mut state.popups
+    }
 
-        clay_scope.with(declaration, |_clay| {
-            f(self);
-        });
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) fn toolbars(&self) -> &mut Toolbars {
+        let state = get_state_mut!(self);
+        &mut state.toolbars
     }
 
-    pub fn load_font(&self, path: &str) -> InternalResult<FontHandle> {
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) fn rating_stars(&self) -> &mut HashMap<u32, ImageInfo> {
         let state = get_state_mut!(self);
-        state.text_generator.load_font(path, &state.bg_worker)
+        &mut state.rating_stars
     }
 
-    pub fn register_font(&self, font_id: FontHandle, style: FontStyle) {
+    /// Number of elements being declared around the current one, anonymous ones included.
+    pub(crate) fn declaring_depth(&self) -> usize {
         let state = get_state_mut!(self);
-        state.font_styles.insert(style, font_id);
+        state.z_stack.len()
     }
 
-    #[inline(always)]
-    #[allow(dead_code)]
-    pub fn set_font(&self, font_handle: FontHandle) {
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) fn windows(&self) -> &mut Windows {
         let state = get_state_mut!(self);
-        state.active_font = font_handle;
+        &mut state.windows
     }
 
-    #[allow(dead_code)]
-    pub fn set_font_style(&'a self, style: FontStyle) {
-        let state = self.state();
-        if let Some(font_handle) = state.font_styles.get(&style) {
-            state.active_font = *font_handle;
-        } else {
-            // Handle the case where the font style is not registered
-            eprintln!("Font style {:?} not registered", style);
-        }
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) fn menus(&self) -> &mut Menus {
+        let state = get_state_mut!(self);
+        &mut state.menus
     }
 
-    pub fn text_size(&'a self, text: &str, font_size: u32) -> Dimensions {
-        let state = self.state();
-        let size = state
-            .text_generator
-            .measure_text_size(text, state.active_font, font_size as _)
-            .unwrap();
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) fn image_cache(&self) -> &mut ImageCache {
+        let state = get_state_mut!(self);
+        &mut state.image_cache
+    }
 
-        Dimensions::new(size.0 as _, size.1 as _)
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) fn directories(&self) -> &mut DirectoryCache {
+        let state = get_state_mut!(self);
+        &mut state.directories
     }
 
-    fn measure_text(&'a self, text: &str, config: &TextConfig) -> Dimensions {
-        self.text_size(text, config.font_size as u32)
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) fn file_trees(&self) -> &mut HashMap<u32, FileTreeState> {
+        let state = get_state_mut!(self);
+        &mut state.file_trees
     }
 
-    pub fn label(&self, text: &str, col: ClayColor) {
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) fn canvas_views(&self) -> &mut HashMap<u32, CanvasView> {
         let state = get_state_mut!(self);
-        let font_id = state.active_font;
-        let font_size = state.font_size;
+        &mut state.canvas_views
+    }
 
-        let _ =
-            state
-                .text_generator
-                .queue_generate_text(text, font_size, font_id, &state.bg_worker);
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) fn meters(&self) -> &mut HashMap<u32, MeterBallistics> {
+        let state = get_state_mut!(self);
+        &mut state.meters
+    }
 
-        self.with_layout(
-            &Declaration::new()
-                .id(self.id(text))
-                .layout()
-                .width(grow!())
-                .height(fixed!(80.0))
-                .child_alignment(Alignment::new(
-                    LayoutAlignmentX::Center,
-                    LayoutAlignmentY::Center,
-                ))
-                .child_gap(40)
-                .direction(LayoutDirection::LeftToRight)
-                .end(),
-            |_ui| {
-                let scope = state.layout();
-
-                scope.text(
-                    text,
-                    TextConfig::new()
-                        .font_id(font_id as u16)
-                        .font_size(font_size as _)
-                        .wrap_mode(clay_layout::text::TextElementConfigWrapMode::None)
-                        .color(col)
-                        .end(),
-                );
-            },
-        );
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) fn meter_bridges(&self) -> &mut HashMap<u32, MeterBridge> {
+        let state = get_state_mut!(self);
+        &mut state.meter_bridges
     }
 
-    pub fn with_layout<F: FnOnce(&Ui)>(&self, declaration: &Declaration<'a, ImageInfo, ()>, f: F) {
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) fn piano_rolls(&self) -> &mut HashMap<u32, PianoRollState> {
         let state = get_state_mut!(self);
-        let scope = state.layout();
+        &mut state.piano_rolls
+    }
 
-        scope.with(declaration, |_clay| {
-            f(self);
-        });
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) fn clip_grids(&self) -> &mut HashMap<u32, ClipGridState> {
+        let state = get_state_mut!(self);
+        &mut state.clip_grids
     }
 
-    #[inline]
-    pub fn id(&self, name: &str) -> Id {
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) fn region_drags(&self) -> &mut HashMap<u32, RegionDrag> {
         let state = get_state_mut!(self);
-        let scope = state.layout();
-        scope.id(name)
+        &mut state.region_drags
     }
 
-    pub fn begin(&self, delta_time: f32, window_size: (usize, usize)) {
+    pub(crate) fn window_size(&self) -> (usize, usize) {
         let state = get_state_mut!(self);
-        state.window_size = window_size;
-        state.delta_time = delta_time;
-        state
-            .layout
-            .set_layout_dimensions(Dimensions::new(window_size.0 as f32, window_size.1 as f32));
+        state.window_size
+    }
 
-        state.layout_scope = Some(state.layout.begin::<ImageInfo, ()>());
+    /// Seconds since the previous frame, as passed to [`Ui::begin`].
+    pub(crate) fn delta_time(&self) -> f32 {
+        let state = get_state_mut!(self);
+        state.delta_time
+    }
 
-        self.update();
+    /// Seconds the ui has been running, as the sum of the frame times.
+    pub(crate) fn time(&self) -> f64 {
+        let state = get_state_mut!(self);
+        state.time
     }
 
-    fn update(&self) {
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) fn toasts(&self) -> &mut Toasts {
         let state = get_state_mut!(self);
-        state.text_generator.update();
+        &mut state.toasts
+    }
+
+    pub(crate) fn current_frame(&self) -> u64 {
+        let state = get_state_mut!(self);
+        state.current_frame
+    }
+
+    /// The window size and the last frame bounds of the element currently being declared into.
+    pub(crate) fn available_space(&self) -> ((usize, usize), Option<Vec4>) {
+        let state = get_state_mut!(self);
+        let parent = state.parent_stack.last().and_then(|parent| {
+            state
+                .input
+                .hit_boxes
+                .iter()
+                .find(|(id, _)| id == parent)
+                .map(|(_, rect)| *rect)
+        });
+        (state.window_size, parent)
     }
 
     pub fn set_focus_id(&self, id: Id) {
@@ -266,18 +2281,82 @@ impl<'a> Ui<'a> {
         state.focus_id = Some(id);
     }
 
+    pub(crate) fn focus_id(&self) -> Option<Id> {
+        let state = get_state_mut!(self);
+        state.focus_id
+    }
+
+    pub(crate) fn clear_focus(&self) {
+        let state = get_state_mut!(self);
+        state.focus_id = None;
+    }
+
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) fn focus_state(&self) -> &mut FocusState {
+        let state = get_state_mut!(self);
+        &mut state.focus
+    }
+
+    /// Lays out and renders the frame into `output`. A frame aborted by a panic while it was
+    /// declared is thrown away instead and `output` is left as it is.
     pub fn end(&self, output: &mut [u32]) {
+        if get_state_mut!(self).frame_phase == FramePhase::Aborted {
+            self.discard_frame();
+            return;
+        }
+        self.declare_toasts();
+        self.update_focus();
+
         let state = get_state_mut!(self);
-        let text_generator = &state.text_generator;
-        let mut pixmap =
-            Pixmap::new(state.window_size.0 as u32, state.window_size.1 as u32).unwrap();
 
         let scope = get_layout_mut!(state);
 
         // TODO: Fix me
-        let render_items: Vec<_> = scope.end().collect();
+        let mut render_items: Vec<_> = scope.end().collect();
+        state.memo_cache.process(
+            &mut render_items,
+            |s| self.alloc_str(s),
+            state.current_frame,
+        );
+
+        if let Some(metrics) = &mut state.widget_metrics {
+            for node in &state.frame_tree {
+                metrics.seen(node.id.id.id, state.time);
+            }
+        }
+
+        // Hit testing goes from the last box to the first, so floating elements with a higher
+        // z-index go last. The sort is stable to keep the declaration order otherwise.
+        let mut nodes: Vec<_> = state.frame_tree.iter().collect();
+        nodes.sort_by_key(|node| node.z_index);
+        state.input.hit_boxes.clear();
+        for node in nodes {
+            if let Some(bb) = scope.bounding_box(node.id) {
+                state.input.hit_boxes.push((
+                    node.id.id.id,
+                    Vec4::new(bb.x, bb.y, bb.x + bb.width, bb.y + bb.height),
+                ));
+            }
+        }
+        std::mem::swap(&mut state.input.cursor_zones, &mut state.frame_cursors);
+        state.frame_cursors.clear();
 
-        let anim_rate = 1.0 - 2f32.powf(-8.0 * state.delta_time);
+        // A modal captures the mouse for everything declared inside of it, parents are always
+        // declared before their children
+        state.input.capture = state.modal_layer.map(|layer| {
+            let mut layer_ids = HashSet::from([layer.id.id]);
+            for node in &state.frame_tree {
+                if node
+                    .parent
+                    .is_some_and(|parent| layer_ids.contains(&parent))
+                {
+                    layer_ids.insert(node.id.id.id);
+                }
+            }
+            layer_ids
+        });
+
+        let anim_rate = smoothing_factor(8.0, state.delta_time);
 
         let focus_id = if let Some(id) = state.focus_id {
             id.id
@@ -285,27 +2364,126 @@ impl<'a> Ui<'a> {
             scope.id("").id
         };
 
-        for command in &render_items {
+        let layout_rate = smoothing_factor(LAYOUT_ANIMATION_SPEED, state.delta_time);
+
+        for command in &mut render_items {
             let bb = command.bounding_box;
+            let aabb = Vec4::new(bb.x, bb.y, bb.x + bb.width, bb.y + bb.height);
 
+            let is_new = !state.item_states.contains_key(&command.id);
             let item = state.item_states.entry(command.id).or_insert(ItemState {
                 ..Default::default()
             });
 
-            let is_active = if command.id == focus_id.id { 1.0 } else { 0.0 };
+            // Several commands (background, border, ...) can share an id, only step once
+            if item.frame == state.current_frame {
+                if state.animated_layout {
+                    command.bounding_box = aabb_to_bounding_box(item.display_aabb);
+                }
+                continue;
+            }
+
+            // Items that just appeared start where they are laid out
+            let was_visible = !is_new && item.frame + 1 >= state.current_frame;
+            item.aabb = aabb;
+            item.display_aabb = if state.animated_layout && was_visible {
+                approach_rect(item.display_aabb, aabb, layout_rate)
+            } else {
+                aabb
+            };
+            if state.animated_layout {
+                command.bounding_box = aabb_to_bounding_box(item.display_aabb);
+            }
+
+            let target = |on: bool| if on { 1.0 } else { 0.0 };
+            let is_active = target(command.id == focus_id.id || item.open);
+            let is_hovered = target(item.was_hovered);
+            let is_pressed = target(state.input.pressed_id == Some(command.id));
 
             item.active += anim_rate * (is_active - item.active);
-            item.aabb = Vec4::new(bb.x, bb.y, bb.x + bb.width, bb.y + bb.height);
+            item.hover += anim_rate * (is_hovered - item.hover);
+            item.press += anim_rate * (is_pressed - item.press);
             item.frame = state.current_frame;
         }
 
-        crate::tiny_skia_renderer::clay_tiny_skia_render(
-            &mut pixmap,
+        // Long strings are only rasterized where they show, see `long_text`
+        let (width, height) = (state.window_size.0 as f32, state.window_size.1 as f32);
+        let mut clips = ClipStack::new(width, height);
+        let mut visible_pieces = Vec::new();
+        for command in &render_items {
+            match &command.config {
+                RenderCommandConfig::ScissorStart() => clips.push(command.bounding_box),
+                RenderCommandConfig::ScissorEnd() => clips.pop(),
+                RenderCommandConfig::Text(text) => {
+                    let (size, font) = (text.font_size as u32, text.font_id as FontHandle);
+                    if let Some(pieces) = state.text_generator.text_pieces(text.text, size, font) {
+                        visible_pieces.extend(
+                            clips
+                                .visible(pieces, command.bounding_box)
+                                .map(|piece| (piece.text.clone(), size, font)),
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+        for (piece, size, font) in &visible_pieces {
+            let _ = state
+                .text_generator
+                .queue_generate_text(piece, *size, *font);
+        }
+
+        let diagnostics = state.renderer.render(
+            state.window_size.0 as u32,
+            state.window_size.1 as u32,
             &render_items,
-            text_generator,
+            &state.text_generator,
+        );
+
+        // Only report when something changes to not flood the log every frame
+        if diagnostics != state.render_diagnostics && !diagnostics.invalid_rects.is_empty() {
+            let paths: Vec<String> = diagnostics
+                .invalid_rects
+                .iter()
+                .map(|id| state.id_paths.describe(*id))
+                .collect();
+            eprintln!("Skipped rectangles with invalid size: {}", paths.join(", "));
+        }
+        state.render_diagnostics = diagnostics;
+
+        let missing = state.text_generator.take_new_missing_glyphs();
+        if !missing.is_empty() {
+            let codepoints: Vec<String> = missing.iter().map(|c| codepoint(*c)).collect();
+            eprintln!("No loaded font has glyphs for: {}", codepoints.join(", "));
+        }
+
+        let mut visible_text: HashSet<_> = render_items
+            .iter()
+            .filter_map(|command| match &command.config {
+                RenderCommandConfig::Text(text) => {
+                    let bb = command.bounding_box;
+                    let on_screen = bb.x < width
+                        && bb.y < height
+                        && bb.x + bb.width > 0.0
+                        && bb.y + bb.height > 0.0;
+                    on_screen.then_some((text.text, text.font_size as u32, text.font_id as u64))
+                }
+                _ => None,
+            })
+            .collect();
+        visible_text.extend(
+            visible_pieces
+                .iter()
+                .map(|(piece, size, font)| (piece.as_str(), *size, *font)),
+        );
+
+        state.text_generator.submit_pending(
+            &state.bg_worker,
+            state.text_jobs_per_frame,
+            |text, size, font| visible_text.contains(&(text, size, font)),
         );
 
-        for (index, p) in pixmap.data().chunks_exact(4).enumerate() {
+        for (index, p) in state.renderer.pixmap().data().chunks_exact(4).enumerate() {
             // Convert RGBA to ARGB: tiny-skia uses RGBA, minifb expects ARGB
             output[index] = ((p[3] as u32) << 24) | // Alpha
                            ((p[0] as u32) << 16) | // Red  
@@ -318,7 +2496,212 @@ impl<'a> Ui<'a> {
             .item_states
             .retain(|_, item| item.frame == state.current_frame);
 
+        #[cfg(feature = "debug-server")]
+        self.serve_debug_requests();
+
         state.current_frame += 1;
+        state.frame_phase = FramePhase::Idle;
+    }
+
+    /// Throws away the frame being declared without laying it out. Clay starts from scratch on
+    /// the next begin, and what the ui collected while declaring is reset here or in
+    /// [`Ui::begin`]. The frame counter stays, as if the frame never happened.
+    fn discard_frame(&self) {
+        let state = get_state_mut!(self);
+        if let Some(scope) = state.layout_scope.take() {
+            scope.abandon();
+        }
+        state.focus.discard_frame();
+        state.memo_cache.discard_frame();
+        state.toolbars.discard_frame();
+        state.frame_phase = FramePhase::Idle;
+    }
+
+    /// Starts the debug server on `addr` (for example [`crate::debug_server::DEFAULT_ADDR`]).
+    /// Connected inspectors receive a JSON snapshot of the frame when they request one.
+    #[cfg(feature = "debug-server")]
+    pub fn start_debug_server(&self, addr: &str) -> std::io::Result<()> {
+        let state = get_state_mut!(self);
+        let server = DebugServer::start(addr)?;
+        println!("Debug server listening on {}", server.local_addr());
+        state.debug_server = Some(server);
+        Ok(())
+    }
+
+    #[cfg(feature = "debug-server")]
+    fn serve_debug_requests(&self) {
+        let state = get_state_mut!(self);
+        let Some(server) = state.debug_server.as_ref() else {
+            return;
+        };
+
+        let requests = server.take_requests();
+        if requests.is_empty() {
+            return;
+        }
+
+        let snapshot = self.debug_snapshot().to_string();
+        for request in requests {
+            let _ = request.send(snapshot.clone());
+        }
+    }
+
+    /// Builds the inspector snapshot. Must be called after the layout has been ended so the
+    /// bounding boxes belong to the current frame.
+    #[cfg(feature = "debug-server")]
+    fn debug_snapshot(&self) -> JsonValue {
+        let state = get_state_mut!(self);
+        let scope = get_layout_mut!(state);
+
+        let rect = |x: f32, y: f32, w: f32, h: f32| JsonValue::array([x, y, w, h]);
+
+        let items = state.frame_tree.iter().map(|node| {
+            let bounds = scope
+                .bounding_box(node.id)
+                .map(|bb| rect(bb.x, bb.y, bb.width, bb.height));
+
+            JsonValue::object([
+                ("id", node.id.id.id.into()),
+                ("path", state.id_paths.describe(node.id.id.id).into()),
+                ("parent", node.parent.into()),
+                ("depth", node.depth.into()),
+                ("rect", bounds.into()),
+            ])
+        });
+
+        let mut item_states: Vec<_> = state.item_states.iter().collect();
+        item_states.sort_by_key(|(id, _)| **id);
+        let item_states = item_states.into_iter().map(|(id, item)| {
+            JsonValue::object([
+                ("id", (*id).into()),
+                (
+                    "aabb",
+                    JsonValue::array([item.aabb.x, item.aabb.y, item.aabb.z, item.aabb.w]),
+                ),
+                ("was_hovered", item.was_hovered.into()),
+                ("was_clicked", item.was_clicked.into()),
+                ("active", item.active.into()),
+                ("hover", item.hover.into()),
+                ("press", item.press.into()),
+                ("frame", item.frame.into()),
+            ])
+        });
+
+        let text = state.text_generator.stats();
+        let images = state.image_cache.stats();
+        let workers = state.bg_worker.stats();
+
+        JsonValue::object([
+            ("frame", state.current_frame.into()),
+            ("delta_time", state.delta_time.into()),
+            (
+                "window_size",
+                JsonValue::array([state.window_size.0, state.window_size.1]),
+            ),
+            ("focus_id", state.focus_id.map(|id| id.id.id).into()),
+            ("items", JsonValue::Array(items.collect())),
+            ("item_states", JsonValue::Array(item_states.collect())),
+            (
+                "text_cache",
+                JsonValue::object([
+                    ("loaded_fonts", text.loaded_fonts.into()),
+                    ("cached_strings", text.cached_strings.into()),
+                    ("cached_bytes", text.cached_bytes.into()),
+                    ("inflight_generations", text.inflight_generations.into()),
+                    ("pending_generations", text.pending_generations.into()),
+                    (
+                        "missing_glyphs",
+                        JsonValue::Array(
+                            state
+                                .text_generator
+                                .missing_glyphs()
+                                .iter()
+                                .map(|c| codepoint(*c).into())
+                                .collect(),
+                        ),
+                    ),
+                ]),
+            ),
+            (
+                "image_cache",
+                JsonValue::object([
+                    ("loaded", images.loaded.into()),
+                    ("loading", images.loading.into()),
+                    ("failed", images.failed.into()),
+                ]),
+            ),
+            (
+                "renderer",
+                JsonValue::object([
+                    (
+                        "invalid_rects",
+                        JsonValue::Array(
+                            state
+                                .render_diagnostics
+                                .invalid_rects
+                                .iter()
+                                .map(|id| (*id).into())
+                                .collect(),
+                        ),
+                    ),
+                    ("redrawn_tiles", state.renderer.redrawn_tiles().into()),
+                ]),
+            ),
+            (
+                "workers",
+                JsonValue::object([
+                    ("num_workers", workers.num_workers.into()),
+                    ("registered_callbacks", workers.registered_callbacks.into()),
+                    ("jobs_submitted", workers.jobs_submitted.into()),
+                    ("jobs_completed", workers.jobs_completed.into()),
+                    ("jobs_discarded", workers.jobs_discarded.into()),
+                    ("jobs_pending", workers.jobs_pending().into()),
+                ]),
+            ),
+        ])
+    }
+}
+
+/// `c` written the way Unicode charts list it, like `U+1F3B5`.
+fn codepoint(c: char) -> String {
+    format!("U+{:04X}", c as u32)
+}
+
+fn aabb_to_bounding_box(aabb: Vec4) -> BoundingBox {
+    BoundingBox::new(aabb.x, aabb.y, aabb.z - aabb.x, aabb.w - aabb.y)
+}
+
+/// Applies the properties set in `style` on top of the widget's own declaration.
+pub(crate) fn apply_style(decl: &mut UiDeclaration<'_>, style: &Style) {
+    if let Some(color) = style.background {
+        decl.background_color(color);
+    }
+    if let Some(radius) = style.radius {
+        decl.corner_radius().all(radius);
+    }
+    if let Some(padding) = style.padding {
+        decl.layout().padding(Padding::all(padding));
+    }
+    if let Some(width) = style.border_width {
+        decl.border().all_directions(width);
+    }
+    if let Some(color) = style.border_color {
+        decl.border().color(color);
+        if style.border_width.is_none() && style.separator.is_none() {
+            decl.border().all_directions(1);
+        }
+    }
+    apply_separators(decl, style);
+}
+
+/// Draws the separators of `style` between the children of `decl`, for widgets whose rows are
+/// declared in an element inside of the one `style` is applied to.
+pub(crate) fn apply_separators(decl: &mut UiDeclaration<'_>, style: &Style) {
+    if let Some(width) = style.separator {
+        decl.border().between_children(width);
+        if let Some(color) = style.border_color {
+            decl.border().color(color);
+        }
     }
 }
 
@@ -377,6 +2760,7 @@ pub fn rgba(r: u8, g: u8, b: u8, a: u8) -> ClayColor {
 ///         width: 2,
 ///         color: rgb(100, 100, 100),
 ///     },
+///     cursor: CursorIcon::ResizeHorizontal, // needs `id`
 /// }, |ui| {
 ///     // Child elements here
 /// });
@@ -417,10 +2801,12 @@ macro_rules! area {
             $(parent_id: $float_parent:expr,)?
             $(attach_points: ($float_element:expr, $float_parent_point:expr),)?
             $(attach_to: $float_attach:expr,)?
+            $(clip_to: $float_clip:expr,)?
             $(pointer_capture_mode: $float_capture:expr,)?
         },)?
         $(aspect_ratio: $aspect:expr,)?
         $(clip: ($clip_h:expr, $clip_v:expr, $clip_offset:expr),)?
+        $(cursor: $cursor:expr,)?
     }, $body:expr) => {
         {
             use clay_layout::Declaration;
@@ -484,6 +2870,7 @@ macro_rules! area {
                     $(floating.parent_id($float_parent);)?
                     $(floating.attach_points($float_element, $float_parent_point);)?
                     $(floating.attach_to($float_attach);)?
+                    $(floating.clip_to($float_clip);)?
                     $(floating.pointer_capture_mode($float_capture);)?
                     floating.end();
                 }
@@ -495,6 +2882,13 @@ macro_rules! area {
             // Set clip if provided
             $(decl.clip($clip_h, $clip_v, $clip_offset);)?
 
+            // Cursors are hit tested by id so this needs `id` to be set
+            $(
+                if let Some(id) = decl.element_id() {
+                    $ui.set_cursor(id, $cursor);
+                }
+            )?
+
             // Execute the with call using the internal helper
             $ui.with_layout(&decl, $body)
         }
@@ -0,0 +1,414 @@
+//! Declarative ui descriptions loaded from JSON.
+//!
+//! A description holds the static part of a layout (areas, nesting, sizes, colors, ids and
+//! fixed labels) so it can be tweaked without touching Rust code. Dynamic content is inserted
+//! through named slots that the host fills when the description is emitted. Descriptions loaded
+//! from disk are reloaded whenever the file changes.
+//!
+//! A node is an object with a `type` of `area`, `label` or `slot`. Area properties use the same
+//! names and values as panel scripts, colors are written as `"#rrggbb[aa]"` and percentages as
+//! `"50%"`. The root is either a single node or an array of nodes.
+//!
+//! ```json
+//! {
+//!     "type": "area",
+//!     "id": "mixer",
+//!     "width": "grow",
+//!     "height": 200,
+//!     "direction": "left_to_right",
+//!     "padding": 5,
+//!     "background": "#323232",
+//!     "children": [
+//!         { "type": "label", "text": "Mixer", "color": "#ffffff" },
+//!         { "type": "slot", "name": "channels" }
+//!     ]
+//! }
+//! ```
+use crate::area_props::{AreaProp, PropValue, apply_area_props, area_prop, parse_hex_color};
+use crate::file_watch::WatchedFile;
+use crate::internal_error::InternalResult;
+use crate::json::{JsonError, JsonValue};
+use crate::{Ui, rgb};
+use clay_layout::Declaration;
+use clay_layout::color::Color as ClayColor;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use thiserror::Error;
+
+const DEFAULT_LABEL_COLOR: ClayColor = ClayColor::rgb(220.0, 220.0, 220.0);
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum DescriptionError {
+    #[error("invalid json at {0}")]
+    Json(#[from] JsonError),
+    /// `path` points at the offending value, for example `root.children[2].width`
+    #[error("{path}: {message}")]
+    Invalid { path: String, message: String },
+}
+
+type DescriptionResult<T> = Result<T, DescriptionError>;
+
+fn invalid(path: &str, message: impl Into<String>) -> DescriptionError {
+    DescriptionError::Invalid {
+        path: path.to_string(),
+        message: message.into(),
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Area {
+        id: Option<String>,
+        props: Vec<AreaProp>,
+        children: Vec<Node>,
+    },
+    Label {
+        text: String,
+        color: Option<ClayColor>,
+    },
+    Slot {
+        name: String,
+    },
+}
+
+/// JSON has no dedicated syntax for colors and percentages so they are recognized from strings.
+fn prop_value<'v>(value: &'v JsonValue, path: &str) -> DescriptionResult<PropValue<'v>> {
+    match value {
+        JsonValue::Number(v) => Ok(PropValue::Number(*v as f32)),
+        JsonValue::String(v) => {
+            if let Some(hex) = v.strip_prefix('#') {
+                parse_hex_color(hex)
+                    .map(PropValue::Color)
+                    .ok_or_else(|| invalid(path, format!("invalid color '{}'", v)))
+            } else if let Some(percent) = v.strip_suffix('%') {
+                percent
+                    .trim()
+                    .parse::<f32>()
+                    .map(|p| PropValue::Percent(p / 100.0))
+                    .map_err(|_| invalid(path, format!("invalid percentage '{}'", v)))
+            } else {
+                Ok(PropValue::Ident(v))
+            }
+        }
+        _ => Err(invalid(path, format!("unsupported value {}", value))),
+    }
+}
+
+fn string_field(
+    fields: &[(String, JsonValue)],
+    key: &str,
+    path: &str,
+) -> DescriptionResult<String> {
+    match fields.iter().find(|(k, _)| k == key) {
+        Some((_, JsonValue::String(v))) => Ok(v.clone()),
+        Some(_) => Err(invalid(&format!("{}.{}", path, key), "expected a string")),
+        None => Err(invalid(path, format!("missing '{}'", key))),
+    }
+}
+
+struct Loader {
+    /// Ids, label texts and slot names seen so far. Clay treats duplicated ids as a fatal error
+    /// and slots are looked up by name, so both have to be unique.
+    used_names: HashSet<String>,
+}
+
+impl Loader {
+    fn claim(&mut self, name: &str, path: &str) -> DescriptionResult<()> {
+        if self.used_names.insert(name.to_string()) {
+            Ok(())
+        } else {
+            Err(invalid(path, format!("duplicate id \"{}\"", name)))
+        }
+    }
+
+    fn nodes(&mut self, value: &JsonValue, path: &str) -> DescriptionResult<Vec<Node>> {
+        match value {
+            JsonValue::Array(items) => items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| self.node(item, &format!("{}[{}]", path, i)))
+                .collect(),
+            _ => Ok(vec![self.node(value, path)?]),
+        }
+    }
+
+    fn node(&mut self, value: &JsonValue, path: &str) -> DescriptionResult<Node> {
+        let JsonValue::Object(fields) = value else {
+            return Err(invalid(path, "expected an object"));
+        };
+
+        let kind = string_field(fields, "type", path)?;
+        match kind.as_str() {
+            "area" => {
+                let mut id = None;
+                let mut props = Vec::new();
+                let mut children = Vec::new();
+
+                for (key, value) in fields {
+                    let path = format!("{}.{}", path, key);
+                    match key.as_str() {
+                        "type" => {}
+                        "id" => {
+                            let Some(name) = value.as_str() else {
+                                return Err(invalid(&path, "expected a string"));
+                            };
+                            self.claim(name, &path)?;
+                            id = Some(name.to_string());
+                        }
+                        "children" => match value {
+                            JsonValue::Array(_) => children = self.nodes(value, &path)?,
+                            _ => return Err(invalid(&path, "expected an array")),
+                        },
+                        _ => props.push(
+                            area_prop(key, prop_value(value, &path)?)
+                                .map_err(|e| invalid(&path, e))?,
+                        ),
+                    }
+                }
+
+                Ok(Node::Area {
+                    id,
+                    props,
+                    children,
+                })
+            }
+            "label" => {
+                let text = string_field(fields, "text", path)?;
+                self.claim(&text, path)?;
+
+                let mut color = None;
+                for (key, value) in fields {
+                    let path = format!("{}.{}", path, key);
+                    match key.as_str() {
+                        "type" | "text" => {}
+                        "color" => match prop_value(value, &path)? {
+                            PropValue::Color(c) => color = Some(c),
+                            value => {
+                                return Err(invalid(&path, format!("invalid color {}", value)));
+                            }
+                        },
+                        _ => {
+                            return Err(invalid(
+                                &path,
+                                format!("unknown label property '{}'", key),
+                            ));
+                        }
+                    }
+                }
+
+                Ok(Node::Label { text, color })
+            }
+            "slot" => {
+                let name = string_field(fields, "name", path)?;
+                self.claim(&name, path)?;
+                Ok(Node::Slot { name })
+            }
+            _ => Err(invalid(
+                &format!("{}.type", path),
+                format!("unknown node type '{}'", kind),
+            )),
+        }
+    }
+}
+
+fn parse(source: &str) -> DescriptionResult<Vec<Node>> {
+    let value = JsonValue::parse(source)?;
+    let mut loader = Loader {
+        used_names: HashSet::new(),
+    };
+    loader.nodes(&value, "root")
+}
+
+/// A static layout with named slots for dynamic content.
+pub struct UiDescription {
+    file: Option<WatchedFile>,
+    nodes: Vec<Node>,
+    error: Option<DescriptionError>,
+}
+
+impl UiDescription {
+    /// Loads a description from disk. Like script panels, an invalid file doesn't fail the load,
+    /// the error is shown in place of the layout until the file has been fixed.
+    pub fn load(path: impl Into<PathBuf>) -> InternalResult<Self> {
+        let mut file = WatchedFile::new(path);
+        let mut desc = Self {
+            file: None,
+            nodes: Vec::new(),
+            error: None,
+        };
+        desc.set_source(&file.read()?);
+        desc.file = Some(file);
+        Ok(desc)
+    }
+
+    /// Parses a description from a string. These are never reloaded.
+    pub fn from_source(source: &str) -> Result<Self, DescriptionError> {
+        Ok(Self {
+            file: None,
+            nodes: parse(source)?,
+            error: None,
+        })
+    }
+
+    /// Error from the latest reload, if any.
+    pub fn error(&self) -> Option<&DescriptionError> {
+        self.error.as_ref()
+    }
+
+    /// Names of all slots in the description, in declaration order.
+    pub fn slots(&self) -> Vec<&str> {
+        fn collect<'n>(nodes: &'n [Node], names: &mut Vec<&'n str>) {
+            for node in nodes {
+                match node {
+                    Node::Area { children, .. } => collect(children, names),
+                    Node::Slot { name } => names.push(name),
+                    Node::Label { .. } => {}
+                }
+            }
+        }
+
+        let mut names = Vec::new();
+        collect(&self.nodes, &mut names);
+        names
+    }
+
+    fn set_source(&mut self, source: &str) {
+        match parse(source) {
+            Ok(nodes) => {
+                self.nodes = nodes;
+                self.error = None;
+            }
+            Err(e) => {
+                // Keep showing the last good version of the layout
+                eprintln!("Ui description error: {}", e);
+                self.error = Some(e);
+            }
+        }
+    }
+
+    /// Reloads the description if the file changed on disk. Returns true if it was reloaded.
+    pub fn reload_if_changed(&mut self) -> bool {
+        let Some(file) = &mut self.file else {
+            return false;
+        };
+
+        match file.read_if_changed() {
+            Some(Ok(source)) => {
+                self.set_source(&source);
+                true
+            }
+            Some(Err(e)) => {
+                eprintln!("Unable to reload ui description {:?}: {}", file.path(), e);
+                false
+            }
+            None => false,
+        }
+    }
+}
+
+impl Ui {
+    fn emit_description_nodes(&self, nodes: &[Node], fill_slot: &mut dyn FnMut(&Ui, &str)) {
+        for node in nodes {
+            match node {
+                Node::Area {
+                    id,
+                    props,
+                    children,
+                } => {
+                    let mut decl = Declaration::new();
+                    if let Some(id) = id {
+                        decl.id(self.id(self.alloc_str(id.clone())));
+                    }
+                    apply_area_props(&mut decl, props);
+
+                    self.with_layout(&decl, |ui| ui.emit_description_nodes(children, fill_slot));
+                }
+                Node::Label { text, color } => {
+                    let text = self.alloc_str(text.clone());
+                    self.label(text, color.unwrap_or(DEFAULT_LABEL_COLOR));
+                }
+                Node::Slot { name } => fill_slot(self, name),
+            }
+        }
+    }
+
+    /// Reloads `desc` if its file changed on disk and declares its elements at the current
+    /// position in the layout. `fill_slot` is called with the slot name wherever the description
+    /// has a slot, slots that aren't handled are simply left empty.
+    pub fn ui_description(&self, desc: &mut UiDescription, mut fill_slot: impl FnMut(&Ui, &str)) {
+        desc.reload_if_changed();
+        self.emit_description_nodes(&desc.nodes, &mut fill_slot);
+
+        if let Some(error) = desc.error() {
+            let text = self.alloc_str(format!("Ui description error: {}", error));
+            self.label(text, rgb(255, 90, 90));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clay_layout::layout::Sizing;
+
+    #[test]
+    fn test_parse_description() {
+        let desc = UiDescription::from_source(
+            r##"
+            {
+                "type": "area",
+                "id": "mixer",
+                "width": "grow",
+                "height": "50%",
+                "padding": 4,
+                "background": "#ff000080",
+                "children": [
+                    { "type": "label", "text": "Mixer", "color": "#ffffff" },
+                    { "type": "slot", "name": "channels" },
+                    { "type": "area", "children": [{ "type": "slot", "name": "master" }] }
+                ]
+            }
+            "##,
+        )
+        .unwrap();
+
+        let Node::Area {
+            id,
+            props,
+            children,
+        } = &desc.nodes[0]
+        else {
+            panic!("expected area");
+        };
+
+        assert_eq!(id.as_deref(), Some("mixer"));
+        assert!(matches!(props[0], AreaProp::Width(Sizing::Grow(..))));
+        assert!(matches!(props[1], AreaProp::Height(Sizing::Percent(p)) if p == 0.5));
+        assert!(matches!(props[2], AreaProp::Padding(4)));
+        assert!(
+            matches!(props[3], AreaProp::Background(c) if c == ClayColor::u_rgba(255, 0, 0, 128))
+        );
+        assert_eq!(children.len(), 3);
+        assert_eq!(desc.slots(), ["channels", "master"]);
+    }
+
+    #[test]
+    fn test_errors_report_path() {
+        let error = parse(
+            r##"[{ "type": "area", "children": [{ "type": "area", "colour": "#ffffff" }] }]"##,
+        )
+        .unwrap_err();
+        assert!(matches!(&error, DescriptionError::Invalid { path, message }
+                if path == "root[0].children[0].colour" && message.contains("unknown")));
+
+        let error = parse(r#"{ "type": "area", "padding": -1 }"#).unwrap_err();
+        assert!(matches!(&error, DescriptionError::Invalid { path, .. } if path == "root.padding"));
+
+        let error = parse(r#"[{ "type": "slot", "name": "a" }, { "type": "slot", "name": "a" }]"#)
+            .unwrap_err();
+        assert!(error.to_string().contains("duplicate"));
+
+        assert!(matches!(parse("{"), Err(DescriptionError::Json(_))));
+        assert!(parse(r#"{ "type": "button" }"#).is_err());
+    }
+}
@@ -0,0 +1,262 @@
+//! Dragging values with the mouse, shared by the knobs, faders and other value widgets.
+//!
+//! [`Ui::value_drag`] turns the mouse movement while a widget is held into pixels to change its
+//! value by and remembers the value the drag started from. [`Ui::drag_readout`] shows the value
+//! and how far it moved from there next to the mouse, so a widget only has to format its value
+//! to get the same readout as all the others. Widgets dragging a point instead of a value use
+//! [`Ui::drag_offset`].
+//!
+//! The modifiers change every drag the same way, see [`DragConfig`]: one for fine adjustment,
+//! one for moving in whole steps of the widget and one for keeping a point on one axis.
+use crate::input::Modifiers;
+use crate::ui::{Ui, rgb};
+use clay_layout::id::Id;
+use glam::Vec2;
+
+/// The mouse movement a dragged value follows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DragAxis {
+    Horizontal,
+    /// Up raises the value.
+    Vertical,
+}
+
+/// Which modifiers change how values and points are dragged, set with [`Ui::set_drag_config`].
+/// A modifier set to [`Modifiers::NONE`] turns its behavior off.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DragConfig {
+    /// Held for fine adjustment, the mouse moves the value `fine_scale` times as far.
+    pub fine: Modifiers,
+    pub fine_scale: f32,
+    /// Held to move the value in whole steps, the size of which each widget picks.
+    pub step: Modifiers,
+    /// Held to keep a dragged point on the axis it moved furthest along.
+    pub axis_lock: Modifiers,
+}
+
+impl Default for DragConfig {
+    fn default() -> Self {
+        Self {
+            fine: Modifiers::SHIFT,
+            fine_scale: 0.1,
+            step: Modifiers::CTRL,
+            axis_lock: Modifiers::ALT,
+        }
+    }
+}
+
+/// True if all of `modifiers` are in `held`, false when there are none.
+fn holds(held: Modifiers, modifiers: Modifiers) -> bool {
+    !modifiers.is_empty() && held.contains(modifiers)
+}
+
+impl DragConfig {
+    /// Scale of the mouse movement with `held` modifiers down.
+    fn scale(&self, held: Modifiers) -> f32 {
+        if holds(held, self.fine) {
+            self.fine_scale
+        } else {
+            1.0
+        }
+    }
+
+    pub(crate) fn stepping(&self, held: Modifiers) -> bool {
+        holds(held, self.step)
+    }
+
+    /// `offset` with the axis it moved less along zeroed when `held` has the axis lock.
+    pub(crate) fn lock_axis(&self, held: Modifiers, offset: Vec2) -> Vec2 {
+        if !holds(held, self.axis_lock) {
+            offset
+        } else if offset.x.abs() >= offset.y.abs() {
+            Vec2::new(offset.x, 0.0)
+        } else {
+            Vec2::new(0.0, offset.y)
+        }
+    }
+}
+
+/// A drag of an item going on, see [`Ui::value_drag`] and [`Ui::drag_offset`].
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DragState {
+    /// Where the mouse was when the drag started.
+    pub from: Vec2,
+    /// Mouse movement since then, scaled down while fine adjustment was held.
+    pub offset: Vec2,
+    /// Value of a value widget when the drag started.
+    pub start: f32,
+    /// Value the drag is at before stepping.
+    pub value: f32,
+    /// `value` as the widget was left with it, stepped or not.
+    pub shown: f32,
+}
+
+/// A drag of a value widget going on this frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ValueDrag {
+    /// Pixels the mouse moved along the axis this frame, scaled down for fine adjustment.
+    pub(crate) delta: f32,
+    /// Value of the widget when the drag started.
+    pub(crate) start: f32,
+    /// Value the drag is at, which `delta` moves on from. It differs from the value of the
+    /// widget while that is stepped.
+    pub(crate) value: f32,
+}
+
+/// `value` rounded to a multiple of `step`, unchanged unless `step` is positive.
+pub(crate) fn round_to(value: f32, step: f32) -> f32 {
+    if step > 0.0 {
+        (value / step).round() * step
+    } else {
+        value
+    }
+}
+
+/// Step of a value shown with `precision` decimals, one decimal fewer than it shows.
+pub(crate) fn precision_step(precision: usize) -> f32 {
+    10f32.powi(1 - precision as i32)
+}
+
+/// `delta` with its sign and `precision` decimals, like `+0.25`.
+fn format_delta(delta: f32, precision: usize) -> String {
+    if delta.is_nan() {
+        format!("{:+.*}", precision, 0.0)
+    } else {
+        format!("{:+.*}", precision, delta)
+    }
+}
+
+impl Ui {
+    /// The drag of the value widget `id` at `value`, `None` unless `held`. The value the drag
+    /// started from is the one passed on the first frame it's held. The widget moves the value
+    /// on from [`ValueDrag::value`] and passes the result to [`Ui::drag_to`].
+    pub(crate) fn value_drag(
+        &self,
+        id: Id,
+        held: bool,
+        value: f32,
+        axis: DragAxis,
+    ) -> Option<ValueDrag> {
+        let input = self.input();
+        let item = self.item_state(id);
+        if !held {
+            item.drag = None;
+            return None;
+        }
+        let drag = item.drag.get_or_insert(DragState {
+            from: input.mouse_pos,
+            start: value,
+            value,
+            shown: value,
+            ..Default::default()
+        });
+        // Changed by something other than the drag, like a double-click resetting it
+        if value != drag.shown {
+            drag.value = value;
+        }
+
+        let delta = match axis {
+            DragAxis::Horizontal => input.mouse_delta.x,
+            DragAxis::Vertical => -input.mouse_delta.y,
+        };
+        Some(ValueDrag {
+            delta: delta * self.drag_config().scale(input.modifiers()),
+            start: drag.start,
+            value: drag.value,
+        })
+    }
+
+    /// Moves the drag of the value widget `id` to `value`. Returns the value the widget takes,
+    /// `step(value)` while stepping is held and `value` otherwise.
+    pub(crate) fn drag_to(&self, id: Id, value: f32, step: impl FnOnce(f32) -> f32) -> f32 {
+        let shown = if self.drag_config().stepping(self.input().modifiers()) {
+            step(value)
+        } else {
+            value
+        };
+        if let Some(drag) = &mut self.item_state(id).drag {
+            drag.value = value;
+            drag.shown = shown;
+        }
+        shown
+    }
+
+    /// Where the mouse was when the drag of `id` started and how far it moved since, `None`
+    /// unless `held`. The movement is scaled down while fine adjustment is held and kept to one
+    /// axis with the axis lock.
+    pub(crate) fn drag_offset(&self, id: Id, held: bool) -> Option<(Vec2, Vec2)> {
+        let input = self.input();
+        let config = self.drag_config();
+        let item = self.item_state(id);
+        if !held {
+            item.drag = None;
+            return None;
+        }
+        if let Some(drag) = &mut item.drag {
+            drag.offset += input.mouse_delta * config.scale(input.modifiers());
+        }
+        let drag = item.drag.get_or_insert(DragState {
+            from: input.mouse_pos,
+            ..Default::default()
+        });
+        Some((drag.from, config.lock_axis(input.modifiers(), drag.offset)))
+    }
+
+    /// Shows `text`, the value of the widget `id` being dragged, next to the mouse along with
+    /// `delta`, how much it changed since the drag started, with `precision` decimals.
+    ///
+    /// Styled by the `drag_readout` rule.
+    pub(crate) fn drag_readout(&self, id: Id, text: &str, delta: f32, precision: usize) {
+        let style = self.widget_style("drag_readout", Default::default());
+        let color = style.text_color.unwrap_or(rgb(220, 220, 220));
+        let text = self.alloc_str(format!("{} ({})", text, format_delta(delta, precision)));
+        let id = self.id_index("drag_readout", id.id.id);
+        self.float_at_mouse(id, &style, |ui| ui.text_element(text, color));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_delta() {
+        assert_eq!(format_delta(0.25, 2), "+0.25");
+        assert_eq!(format_delta(-1.5, 1), "-1.5");
+        assert_eq!(format_delta(0.0, 2), "+0.00");
+        // A decibel fader going up from silence
+        assert_eq!(format_delta(f32::INFINITY, 1), "+inf");
+        assert_eq!(format_delta(f32::NAN, 1), "+0.0");
+    }
+
+    #[test]
+    fn test_drag_config() {
+        let config = DragConfig::default();
+        let held = Modifiers::SHIFT | Modifiers::ALT;
+        assert_eq!(config.scale(held), 0.1);
+        assert!(!config.stepping(held));
+        assert_eq!(
+            config.lock_axis(held, Vec2::new(3.0, -5.0)),
+            Vec2::new(0.0, -5.0)
+        );
+        assert_eq!(
+            config.lock_axis(Modifiers::SHIFT, Vec2::new(3.0, -5.0)),
+            Vec2::new(3.0, -5.0)
+        );
+        // Without a modifier the behavior is off rather than always on
+        let config = DragConfig {
+            fine: Modifiers::NONE,
+            ..config
+        };
+        assert_eq!(config.scale(Modifiers::NONE), 1.0);
+    }
+
+    #[test]
+    fn test_round_to() {
+        assert_eq!(round_to(0.37, 0.1), 0.4);
+        assert_eq!(round_to(-7.4, 1.0), -7.0);
+        assert_eq!(round_to(0.37, 0.0), 0.37);
+        assert_eq!(precision_step(2), 0.1);
+        assert_eq!(precision_step(0), 10.0);
+    }
+}
@@ -0,0 +1,318 @@
+//! Floating windows on top of the main layout.
+//!
+//! [`Ui::window`] shows a panel with a title bar that moves it, a close button and a grip in
+//! the bottom right corner that resizes it. Windows keep their position and size while the ui
+//! lives, also while closed. Pressing the mouse on a window raises it above the other windows.
+//! Windows float below modals and popups, so dialogs opened from a window show on top of it.
+use crate::input::{CursorIcon, MouseButton};
+use crate::style::WidgetState;
+use crate::ui::{Ui, apply_style, rgb};
+use clay_layout::elements::FloatingAttachPointType;
+use clay_layout::elements::FloatingAttachToElement;
+use clay_layout::id::Id;
+use clay_layout::layout::{
+    Alignment, LayoutAlignmentX, LayoutAlignmentY, LayoutDirection, Padding,
+};
+use clay_layout::math::Vector2;
+use clay_layout::{Declaration, fit, fixed, grow};
+use glam::{Vec2, Vec4};
+use std::collections::HashMap;
+
+/// Z-index of the window at the bottom, raised windows get the ones above it.
+const WINDOW_Z_INDEX: i16 = 500;
+/// Size of windows the first time they are shown.
+const DEFAULT_SIZE: Vec2 = Vec2::new(320.0, 240.0);
+/// Smallest size a window can be resized to.
+const MIN_SIZE: Vec2 = Vec2::new(120.0, 80.0);
+/// Size of the resize grip in the bottom right corner.
+const GRIP_SIZE: f32 = 12.0;
+/// Part of the title bar that has to stay inside of the ui so the window can be moved back.
+const VISIBLE_TITLE: f32 = 40.0;
+
+struct WindowState {
+    pos: Vec2,
+    size: Vec2,
+    /// Raised windows get a higher order, they go on top of lower ones.
+    order: u64,
+    /// Last frame the window was shown.
+    frame: u64,
+}
+
+#[derive(Default)]
+pub(crate) struct Windows {
+    windows: HashMap<u32, WindowState>,
+    next_order: u64,
+}
+
+impl Windows {
+    /// The state of window `id`, cascaded from the windows created before it if it's new.
+    fn get_or_create(&mut self, id: u32) -> &mut WindowState {
+        let count = self.windows.len() as f32;
+        let next_order = &mut self.next_order;
+        self.windows.entry(id).or_insert_with(|| {
+            *next_order += 1;
+            WindowState {
+                pos: Vec2::splat(40.0 + 24.0 * count),
+                size: DEFAULT_SIZE,
+                order: *next_order - 1,
+                frame: 0,
+            }
+        })
+    }
+
+    /// Puts window `id` on top of the others.
+    fn raise(&mut self, id: u32) {
+        if let Some(window) = self.windows.get_mut(&id) {
+            window.order = self.next_order;
+            self.next_order += 1;
+        }
+    }
+
+    /// The window shown last frame on top at `pos`, given the last frame rects of the windows.
+    fn top_at(&self, pos: Vec2, frame: u64, rect_of: impl Fn(u32) -> Option<Vec4>) -> Option<u32> {
+        self.windows
+            .iter()
+            .filter(|(_, window)| window.frame + 1 >= frame)
+            .filter(|(id, _)| {
+                rect_of(**id)
+                    .is_some_and(|r| pos.x >= r.x && pos.y >= r.y && pos.x < r.z && pos.y < r.w)
+            })
+            .max_by_key(|(_, window)| window.order)
+            .map(|(id, _)| *id)
+    }
+
+    /// Place of window `id` among the windows shown last frame or this one, 0 at the bottom.
+    fn rank(&self, id: u32, frame: u64) -> usize {
+        let order = self.windows[&id].order;
+        self.windows
+            .values()
+            .filter(|window| window.frame + 1 >= frame && window.order < order)
+            .count()
+    }
+}
+
+/// `pos` moved so at least [`VISIBLE_TITLE`] of the title bar of a window `width` wide stays
+/// inside of a ui of `bounds` size.
+fn keep_reachable(pos: Vec2, width: f32, bounds: Vec2) -> Vec2 {
+    let visible = VISIBLE_TITLE.min(width);
+    Vec2::new(
+        pos.x.clamp(visible - width, (bounds.x - visible).max(0.0)),
+        pos.y.clamp(0.0, (bounds.y - visible).max(0.0)),
+    )
+}
+
+impl Ui {
+    /// Shows the content added by `f` in a floating window titled `title` while `open` is
+    /// true. The close button sets `open` to false. Returns true while shown.
+    ///
+    /// Styled by the `window` rule for the frame and content, `window_title` for the title bar
+    /// and `window_close` for the close button.
+    pub fn window(&self, title: &str, open: &mut bool, f: impl FnOnce(&Ui)) -> bool {
+        let window_id = self.id(title);
+        let title_id = self.id_index(title, 1);
+        let close_id = self.id_index(title, 2);
+        let grip_id = self.id_index(title, 3);
+        let input = self.input();
+        let frame = self.current_frame();
+
+        if *open && input.was_clicked(close_id.id.id, MouseButton::Left) {
+            *open = false;
+        }
+        if !*open {
+            return false;
+        }
+
+        let windows = self.windows();
+        if input.button_pressed(MouseButton::Left)
+            && windows.top_at(input.mouse_pos, frame, |id| input.rect_of(id))
+                == Some(window_id.id.id)
+        {
+            windows.raise(window_id.id.id);
+        }
+
+        let dragged =
+            |id: Id| input.pressed_id == Some(id.id.id) && input.button_down(MouseButton::Left);
+        let (width, height) = self.window_size();
+        let window = windows.get_or_create(window_id.id.id);
+        if dragged(title_id) {
+            window.pos += input.mouse_delta;
+        }
+        if dragged(grip_id) {
+            window.size = (window.size + input.mouse_delta).max(MIN_SIZE);
+        }
+        window.pos = keep_reachable(
+            window.pos,
+            window.size.x,
+            Vec2::new(width as f32, height as f32),
+        );
+        window.frame = frame;
+        let (pos, size) = (window.pos, window.size);
+        let z_index = WINDOW_Z_INDEX + windows.rank(window_id.id.id, frame) as i16;
+
+        let style = self.widget_style("window", WidgetState::default());
+        let mut decl = Declaration::new();
+        decl.id(window_id)
+            .layout()
+            .width(fixed!(size.x))
+            .height(fixed!(size.y))
+            .direction(LayoutDirection::TopToBottom)
+            .end()
+            .floating()
+            .attach_to(FloatingAttachToElement::Root)
+            .offset(Vector2::new(pos.x.round(), pos.y.round()))
+            .z_index(z_index)
+            .end()
+            .background_color(rgb(40, 40, 40));
+        apply_style(&mut decl, &style);
+        // The padding goes around the content, not the title bar
+        decl.layout().padding(Default::default()).end();
+
+        let mut content = Declaration::new();
+        content
+            .layout()
+            .width(grow!())
+            .height(grow!())
+            .direction(LayoutDirection::TopToBottom)
+            .padding(Padding::all(style.padding.unwrap_or(8)))
+            .child_gap(4)
+            .end()
+            .clip(true, true, Vector2::new(0.0, 0.0));
+
+        self.with_layout(&decl, |ui| {
+            ui.window_title_bar(title, title_id, close_id);
+            ui.with_layout(&content, f);
+            ui.window_grip(grip_id, z_index);
+        });
+        true
+    }
+
+    /// The title bar of a window, which moves it, with its close button.
+    fn window_title_bar(&self, title: &str, id: Id, close_id: Id) {
+        let style = self.widget_style("window_title", WidgetState::default());
+        let color = style.text_color.unwrap_or(rgb(220, 220, 220));
+        let close_hovered = self
+            .last_frame_rect(close_id)
+            .is_some_and(|rect| self.input().is_hovered(rect));
+        let close_style = self.widget_style(
+            "window_close",
+            WidgetState {
+                hovered: close_hovered,
+                ..Default::default()
+            },
+        );
+        let close_color = close_style.text_color.unwrap_or(rgb(160, 160, 160));
+        let size = (self.font_size() as f32 * 1.4).round();
+
+        let mut decl = Declaration::new();
+        decl.id(id)
+            .layout()
+            .width(grow!())
+            .height(fit!())
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Left,
+                LayoutAlignmentY::Center,
+            ))
+            .direction(LayoutDirection::LeftToRight)
+            .end()
+            .background_color(rgb(55, 55, 60));
+        apply_style(&mut decl, &style);
+
+        let mut spacer = Declaration::new();
+        spacer.layout().width(grow!()).height(fixed!(1.0)).end();
+
+        let mut close = Declaration::new();
+        close
+            .id(close_id)
+            .layout()
+            .width(fixed!(size))
+            .height(fixed!(size))
+            .child_alignment(Alignment::new(
+                LayoutAlignmentX::Center,
+                LayoutAlignmentY::Center,
+            ))
+            .end();
+        apply_style(&mut close, &close_style);
+
+        self.set_cursor(id, CursorIcon::Move);
+        self.with_layout(&decl, |ui| {
+            ui.text_element(title, color);
+            ui.with_layout(&spacer, |_| {});
+            ui.with_layout(&close, |ui| ui.text_element("x", close_color));
+        });
+    }
+
+    /// The grip in the bottom right corner of a window that resizes it. Floating elements are
+    /// drawn by their own z-index, so it needs the one of the window to not go below it.
+    fn window_grip(&self, id: Id, z_index: i16) {
+        let hovered = self
+            .last_frame_rect(id)
+            .is_some_and(|rect| self.input().is_hovered(rect));
+        let style = self.widget_style(
+            "window_grip",
+            WidgetState {
+                hovered,
+                ..Default::default()
+            },
+        );
+
+        let mut decl = Declaration::new();
+        decl.id(id)
+            .layout()
+            .width(fixed!(GRIP_SIZE))
+            .height(fixed!(GRIP_SIZE))
+            .end()
+            .floating()
+            .attach_to(FloatingAttachToElement::Parent)
+            .attach_points(
+                FloatingAttachPointType::RightBottom,
+                FloatingAttachPointType::RightBottom,
+            )
+            .z_index(z_index)
+            .end();
+        apply_style(&mut decl, &style);
+        self.set_cursor(id, CursorIcon::ResizeDiagonal);
+        self.with_layout(&decl, |_| {});
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_order() {
+        let mut windows = Windows::default();
+        windows.get_or_create(1).frame = 5;
+        windows.get_or_create(2).frame = 5;
+        // Closed a while ago
+        windows.get_or_create(3).frame = 1;
+        assert_eq!(windows.windows[&2].pos, Vec2::splat(64.0));
+
+        let rect_of = |_| Some(Vec4::new(0.0, 0.0, 100.0, 100.0));
+        assert_eq!(windows.top_at(Vec2::splat(10.0), 6, rect_of), Some(2));
+        assert_eq!(windows.top_at(Vec2::splat(200.0), 6, rect_of), None);
+
+        windows.raise(1);
+        assert_eq!(windows.top_at(Vec2::splat(10.0), 6, rect_of), Some(1));
+        assert_eq!(windows.rank(1, 6), 1);
+        assert_eq!(windows.rank(2, 6), 0);
+        // Showing it again doesn't change the order
+        windows.get_or_create(2);
+        assert_eq!(windows.rank(2, 6), 0);
+    }
+
+    #[test]
+    fn test_keep_reachable() {
+        let bounds = Vec2::new(800.0, 600.0);
+        let pos = Vec2::new(100.0, 100.0);
+        assert_eq!(keep_reachable(pos, 300.0, bounds), pos);
+        assert_eq!(
+            keep_reachable(Vec2::new(-500.0, -20.0), 300.0, bounds),
+            Vec2::new(-260.0, 0.0)
+        );
+        assert_eq!(
+            keep_reachable(Vec2::new(900.0, 700.0), 300.0, bounds),
+            Vec2::new(760.0, 560.0)
+        );
+    }
+}
@@ -1,11 +1,12 @@
 use crossbeam_channel::bounded;
 use std::any::Any;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
 use std::thread;
+use std::time::Instant;
 use thiserror::Error;
 
-pub use crossbeam_channel::{Receiver, Sender};
+pub use crossbeam_channel::{Receiver, Sender, TryRecvError};
 
 // Type alias for Box<dyn Any + Send>
 pub type BoxAnySend = Box<dyn Any + Send>;
@@ -23,55 +24,284 @@ pub enum CallbackError {
     #[error("Callback with id {0} not found")]
     CallbackNotFound(usize),
 
+    #[error("Job passed its deadline")]
+    TimedOut,
+
     #[error("Other error: {0}")]
     Other(String),
 }
 
 pub type WorkerResult = Result<BoxAnySend, CallbackError>;
 
+/// Snapshot of the counters kept by a [`WorkSystem`], see [`WorkSystem::stats`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WorkerStats {
+    /// Number of worker threads.
+    pub num_workers: usize,
+    /// Number of registered callbacks.
+    pub registered_callbacks: usize,
+    /// Number of jobs handed to the workers since the system was created.
+    pub jobs_submitted: usize,
+    /// Number of jobs that finished (successfully or not).
+    pub jobs_completed: usize,
+    /// Number of jobs that were skipped or whose result was thrown away, because the deadline
+    /// passed or nobody was left to receive it. Skipped jobs count as completed too.
+    pub jobs_discarded: usize,
+}
+
+impl WorkerStats {
+    /// Jobs that are either queued or currently running.
+    pub fn jobs_pending(&self) -> usize {
+        self.jobs_submitted.saturating_sub(self.jobs_completed)
+    }
+}
+
+/// Bits stored in the progress slot before anything has been reported, a NaN.
+const NO_PROGRESS: u32 = u32::MAX;
+
+/// Passed to callbacks registered with [`WorkSystem::register_callback_with_progress`] to
+/// report how far along a job is.
+#[derive(Debug, Clone)]
+pub struct ProgressReporter {
+    value: Arc<AtomicU32>,
+}
+
+impl ProgressReporter {
+    fn new() -> Self {
+        Self {
+            value: Arc::new(AtomicU32::new(NO_PROGRESS)),
+        }
+    }
+
+    /// Reports the fraction of the job that is done, clamped to 0..=1. Only the latest value
+    /// is kept.
+    pub fn progress(&self, fraction: f32) {
+        let fraction = if fraction.is_nan() {
+            0.0
+        } else {
+            fraction.clamp(0.0, 1.0)
+        };
+        self.value.store(fraction.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// A job added to a [`WorkSystem`]. Dropping the handle before the job has started skips it.
+pub struct WorkHandle {
+    receiver: Receiver<WorkerResult>,
+    progress: Arc<AtomicU32>,
+    deadline: Option<Instant>,
+    // The worker checks if this is still alive to know if anyone wants the result
+    _consumer: Arc<()>,
+}
+
+impl WorkHandle {
+    /// Latest fraction reported by the job, `None` until it reports anything.
+    pub fn progress(&self) -> Option<f32> {
+        match self.progress.load(Ordering::Relaxed) {
+            NO_PROGRESS => None,
+            bits => Some(f32::from_bits(bits)),
+        }
+    }
+
+    /// Receives the result once the job is done.
+    pub fn receiver(&self) -> &Receiver<WorkerResult> {
+        &self.receiver
+    }
+
+    /// Like [`Receiver::try_recv`], but yields [`CallbackError::TimedOut`] as soon as the
+    /// deadline has passed instead of waiting for the worker to get to the job.
+    pub fn try_recv(&self) -> Result<WorkerResult, TryRecvError> {
+        match self.receiver.try_recv() {
+            Err(TryRecvError::Empty) if passed(self.deadline) => Ok(Err(CallbackError::TimedOut)),
+            result => result,
+        }
+    }
+}
+
+/// Scheduling priority of the worker threads relative to the rest of the process.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadPriority {
+    #[default]
+    Normal,
+    /// Yields to normal threads, such as a host's audio and ui threads, when cores are busy.
+    BelowNormal,
+    /// Only runs when nothing else wants the cores.
+    Lowest,
+}
+
+/// How a [`WorkSystem`] sets up its threads. Priority and affinity are hints: they are applied
+/// on Linux and ignored where the platform or permissions don't allow them.
+#[derive(Debug, Clone)]
+pub struct WorkerConfig {
+    num_workers: usize,
+    priority: ThreadPriority,
+    core_affinity: Vec<usize>,
+}
+
+impl WorkerConfig {
+    pub fn new(num_workers: usize) -> Self {
+        Self {
+            num_workers,
+            priority: ThreadPriority::Normal,
+            core_affinity: Vec::new(),
+        }
+    }
+
+    pub fn priority(mut self, priority: ThreadPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Cores the workers may run on, worker `i` is pinned to `cores[i % cores.len()]`. Leaving
+    /// cores out keeps them free for threads that must not be interrupted.
+    pub fn core_affinity(mut self, cores: Vec<usize>) -> Self {
+        self.core_affinity = cores;
+        self
+    }
+}
+
+/// Applies the priority and core hints to the calling thread. Returns false if any of them
+/// couldn't be applied.
+#[cfg(target_os = "linux")]
+fn apply_thread_hints(priority: ThreadPriority, core: Option<usize>) -> bool {
+    let nice = match priority {
+        ThreadPriority::Normal => None,
+        ThreadPriority::BelowNormal => Some(5),
+        ThreadPriority::Lowest => Some(19),
+    };
+
+    let mut applied = true;
+    // SAFETY: both calls only affect the calling thread and get valid arguments
+    unsafe {
+        if let Some(nice) = nice {
+            // On Linux the nice value is per thread when given the thread id
+            let tid = libc::syscall(libc::SYS_gettid) as libc::id_t;
+            applied &= libc::setpriority(libc::PRIO_PROCESS, tid, nice) == 0;
+        }
+        if let Some(core) = core.filter(|core| *core < libc::CPU_SETSIZE as usize) {
+            let mut set: libc::cpu_set_t = std::mem::zeroed();
+            libc::CPU_SET(core, &mut set);
+            applied &= libc::sched_setaffinity(0, std::mem::size_of_val(&set), &set) == 0;
+        }
+    }
+    applied
+}
+
+#[cfg(not(target_os = "linux"))]
+fn apply_thread_hints(priority: ThreadPriority, core: Option<usize>) -> bool {
+    priority == ThreadPriority::Normal && core.is_none()
+}
+
+#[derive(Default)]
+struct Counters {
+    submitted: AtomicUsize,
+    completed: AtomicUsize,
+    discarded: AtomicUsize,
+}
+
 // Type alias for the callback function with state.
 type CallbackWithState = (
     Box<
-        dyn Fn(BoxAnySend, Arc<Mutex<AnySend>>) -> Result<BoxAnySend, CallbackError>
+        dyn Fn(
+                BoxAnySend,
+                Arc<Mutex<AnySend>>,
+                &ProgressReporter,
+            ) -> Result<BoxAnySend, CallbackError>
             + Send
             + 'static,
     >,
     Arc<Mutex<AnySend>>,
 );
 
+struct Job {
+    callback_id: usize,
+    data: BoxAnySend,
+    sender: Sender<WorkerResult>,
+    progress: ProgressReporter,
+    deadline: Option<Instant>,
+    /// Alive while the [`WorkHandle`] of the job is.
+    consumer: Weak<()>,
+}
+
+fn passed(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|deadline| Instant::now() >= deadline)
+}
+
+impl Job {
+    fn abandoned(&self) -> bool {
+        self.consumer.strong_count() == 0
+    }
+}
+
+fn run_job(job: Job, callbacks: &Mutex<Vec<Option<CallbackWithState>>>, counters: &Counters) {
+    // Nobody is waiting for the result or it would come too late, don't spend time on it
+    let skipped = job.abandoned() || passed(job.deadline);
+
+    let result = if skipped {
+        Err(CallbackError::TimedOut)
+    } else if let Some(Some((callback, state))) = callbacks.lock().unwrap().get(job.callback_id) {
+        callback(job.data, Arc::clone(state), &job.progress)
+    } else {
+        Err(CallbackError::CallbackNotFound(job.callback_id))
+    };
+
+    let late = !skipped && result.is_ok() && passed(job.deadline);
+    let result = if late {
+        Err(CallbackError::TimedOut)
+    } else {
+        result
+    };
+
+    // Counted before the result is sent so the stats are up to date once it's received
+    let discarded = skipped || late;
+    if discarded {
+        counters.discarded.fetch_add(1, Ordering::Relaxed);
+    }
+    counters.completed.fetch_add(1, Ordering::Relaxed);
+    // Fails if the handle was dropped while the job ran, which drops the result right here
+    if job.sender.send(result).is_err() && !discarded {
+        counters.discarded.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
 #[allow(clippy::type_complexity)]
 pub struct WorkSystem {
-    sender: Sender<(usize, BoxAnySend, Sender<Result<BoxAnySend, CallbackError>>)>,
+    sender: Sender<Job>,
     callbacks: Arc<Mutex<Vec<Option<CallbackWithState>>>>,
     id_counter: AtomicUsize,
+    counters: Arc<Counters>,
+    num_workers: usize,
 }
 
 impl WorkSystem {
-    #[allow(clippy::type_complexity)]
     pub fn new(num_workers: usize) -> Self {
+        Self::with_config(WorkerConfig::new(num_workers))
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn with_config(config: WorkerConfig) -> Self {
+        let num_workers = config.num_workers;
         let (sender, receiver) = bounded(num_workers);
         let callbacks: Arc<Mutex<Vec<Option<CallbackWithState>>>> =
             Arc::new(Mutex::new(Vec::new()));
+        let counters = Arc::new(Counters::default());
 
         for i in 0..num_workers {
-            let worker_receiver: Receiver<(
-                usize,
-                BoxAnySend,
-                Sender<Result<BoxAnySend, CallbackError>>,
-            )> = receiver.clone();
+            let worker_receiver: Receiver<Job> = receiver.clone();
             let worker_callbacks = Arc::clone(&callbacks);
+            let worker_counters = Arc::clone(&counters);
 
             let name = format!("background_worker_{}", i);
+            let priority = config.priority;
+            let core = (!config.core_affinity.is_empty())
+                .then(|| config.core_affinity[i % config.core_affinity.len()]);
 
             let _ = thread::Builder::new().name(name.to_owned()).spawn(move || {
-                while let Ok((id, data, response_sender)) = worker_receiver.recv() {
-                    if let Some(Some((callback, state))) = worker_callbacks.lock().unwrap().get(id)
-                    {
-                        let result = callback(data, Arc::clone(state));
-                        let _ = response_sender.send(result);
-                    } else {
-                        let _ = response_sender.send(Err(CallbackError::CallbackNotFound(id)));
-                    }
+                // Best effort, the worker runs either way
+                let _ = apply_thread_hints(priority, core);
+
+                while let Ok(job) = worker_receiver.recv() {
+                    run_job(job, &worker_callbacks, &worker_counters);
                 }
             });
         }
@@ -80,6 +310,8 @@ impl WorkSystem {
             sender,
             callbacks,
             id_counter: AtomicUsize::new(0),
+            counters,
+            num_workers,
         }
     }
 
@@ -88,6 +320,29 @@ impl WorkSystem {
         F: Fn(BoxAnySend, Arc<Mutex<AnySend>>) -> Result<BoxAnySend, CallbackError>
             + Send
             + 'static,
+    {
+        self.register_callback_with_progress(
+            move |data, state, _progress| callback(data, state),
+            state,
+        )
+    }
+
+    /// Like [`WorkSystem::register_callback_with_state`] for long jobs that report how far along
+    /// they are. The progress can be polled from the [`WorkHandle`] returned by
+    /// [`WorkSystem::add_work_with_progress`].
+    pub fn register_callback_with_progress<F>(
+        &self,
+        callback: F,
+        state: Arc<Mutex<AnySend>>,
+    ) -> usize
+    where
+        F: Fn(
+                BoxAnySend,
+                Arc<Mutex<AnySend>>,
+                &ProgressReporter,
+            ) -> Result<BoxAnySend, CallbackError>
+            + Send
+            + 'static,
     {
         let id = self.id_counter.fetch_add(1, Ordering::Relaxed);
         let mut callbacks = self.callbacks.lock().unwrap();
@@ -98,12 +353,35 @@ impl WorkSystem {
         id
     }
 
-    pub fn add_work<T: Any + Send>(
+    pub fn add_work<T: Any + Send>(&self, id: usize, data: T) -> WorkHandle {
+        self.submit(id, data, None)
+    }
+
+    /// Like [`WorkSystem::add_work`] for callbacks registered with
+    /// [`WorkSystem::register_callback_with_progress`], the handle gives access to the progress
+    /// the job reports.
+    pub fn add_work_with_progress<T: Any + Send>(&self, id: usize, data: T) -> WorkHandle {
+        self.submit(id, data, None)
+    }
+
+    /// Like [`WorkSystem::add_work_with_progress`] for results that are useless after
+    /// `deadline`. A job still queued at the deadline is skipped and the result of one that
+    /// finishes after it is thrown away, either way the handle yields
+    /// [`CallbackError::TimedOut`].
+    pub fn add_work_with_deadline<T: Any + Send>(
         &self,
         id: usize,
         data: T,
-    ) -> Receiver<Result<BoxAnySend, CallbackError>> {
+        deadline: Instant,
+    ) -> WorkHandle {
+        self.submit(id, data, Some(deadline))
+    }
+
+    fn submit<T: Any + Send>(&self, id: usize, data: T, deadline: Option<Instant>) -> WorkHandle {
         let (response_sender, response_receiver) = bounded(1);
+        let progress = ProgressReporter::new();
+        let handle_progress = Arc::clone(&progress.value);
+        let consumer = Arc::new(());
         if self
             .callbacks
             .lock()
@@ -111,13 +389,38 @@ impl WorkSystem {
             .get(id)
             .is_some_and(|callback| callback.is_some())
         {
+            self.counters.submitted.fetch_add(1, Ordering::Relaxed);
+            let job = Job {
+                callback_id: id,
+                data: Box::new(data),
+                sender: response_sender,
+                progress,
+                deadline,
+                consumer: Arc::downgrade(&consumer),
+            };
             self.sender
-                .send((id, Box::new(data), response_sender))
+                .send(job)
                 .expect("Failed to send work to the channel");
         } else {
             let _ = response_sender.send(Err(CallbackError::CallbackNotFound(id)));
         }
-        response_receiver
+        WorkHandle {
+            receiver: response_receiver,
+            progress: handle_progress,
+            deadline,
+            _consumer: consumer,
+        }
+    }
+
+    /// Returns a snapshot of the job counters, useful for diagnostics.
+    pub fn stats(&self) -> WorkerStats {
+        WorkerStats {
+            num_workers: self.num_workers,
+            registered_callbacks: self.id_counter.load(Ordering::Relaxed),
+            jobs_submitted: self.counters.submitted.load(Ordering::Relaxed),
+            jobs_completed: self.counters.completed.load(Ordering::Relaxed),
+            jobs_discarded: self.counters.discarded.load(Ordering::Relaxed),
+        }
     }
 }
 
@@ -149,8 +452,8 @@ mod tests {
         let receiver1 = system.add_work(callback_id, 5usize);
         let receiver2 = system.add_work(callback_id, 10usize);
 
-        let result1 = receiver1.recv().unwrap().unwrap();
-        let result2 = receiver2.recv().unwrap().unwrap();
+        let result1 = receiver1.receiver().recv().unwrap().unwrap();
+        let result2 = receiver2.receiver().recv().unwrap().unwrap();
 
         assert_eq!(*result1.downcast::<usize>().unwrap(), 5);
         assert_eq!(*result2.downcast::<usize>().unwrap(), 15);
@@ -185,8 +488,8 @@ mod tests {
         let receiver1 = system.add_work(callback_id, "Hello, ".to_string());
         let receiver2 = system.add_work(callback_id, "world!".to_string());
 
-        let result1 = receiver1.recv().unwrap().unwrap();
-        let result2 = receiver2.recv().unwrap().unwrap();
+        let result1 = receiver1.receiver().recv().unwrap().unwrap();
+        let result2 = receiver2.receiver().recv().unwrap().unwrap();
 
         assert_eq!(*result1.downcast::<String>().unwrap(), "Hello, ");
         assert_eq!(*result2.downcast::<String>().unwrap(), "Hello, world!");
@@ -214,17 +517,216 @@ mod tests {
         );
 
         let receiver = system.add_work(callback_id, 42usize);
-        let result = receiver.recv().unwrap();
+        let result = receiver.receiver().recv().unwrap();
 
         assert!(matches!(result, Err(CallbackError::InvalidDataType)));
     }
 
+    #[test]
+    fn test_stats() {
+        let system = WorkSystem::new(2);
+
+        let state: Arc<Mutex<AnySend>> = Arc::new(Mutex::new(()));
+        let callback_id = system.register_callback_with_state(|data, _state| Ok(data), state);
+
+        let receiver1 = system.add_work(callback_id, 1usize);
+        let receiver2 = system.add_work(callback_id, 2usize);
+        receiver1.receiver().recv().unwrap().unwrap();
+        receiver2.receiver().recv().unwrap().unwrap();
+
+        let stats = system.stats();
+        assert_eq!(stats.num_workers, 2);
+        assert_eq!(stats.registered_callbacks, 1);
+        assert_eq!(stats.jobs_submitted, 2);
+        assert_eq!(stats.jobs_completed, 2);
+        assert_eq!(stats.jobs_pending(), 0);
+    }
+
+    #[test]
+    fn test_progress() {
+        let system = WorkSystem::new(1);
+
+        let state: Arc<Mutex<AnySend>> = Arc::new(Mutex::new(()));
+        let (step_sender, step_receiver) = bounded::<()>(0);
+        let step_receiver = Mutex::new(step_receiver);
+        let callback_id = system.register_callback_with_progress(
+            move |data, _state, progress| {
+                for i in 1..=4 {
+                    // Wait for the test to look at the previous value
+                    step_receiver.lock().unwrap().recv().unwrap();
+                    progress.progress(i as f32 / 4.0);
+                }
+                Ok(data)
+            },
+            state,
+        );
+
+        let handle = system.add_work_with_progress(callback_id, 1usize);
+        assert_eq!(handle.progress(), None);
+        step_sender.send(()).unwrap();
+        step_sender.send(()).unwrap();
+        // The second report is stored once the third step has been picked up
+        step_sender.send(()).unwrap();
+        assert!(handle.progress().is_some_and(|p| p >= 0.5));
+        step_sender.send(()).unwrap();
+
+        let result = handle.receiver().recv().unwrap().unwrap();
+        assert_eq!(*result.downcast::<usize>().unwrap(), 1);
+        assert_eq!(handle.progress(), Some(1.0));
+    }
+
+    #[test]
+    fn test_deadline() {
+        let system = WorkSystem::new(1);
+
+        let state: Arc<Mutex<AnySend>> = Arc::new(Mutex::new(()));
+        let (step_sender, step_receiver) = bounded::<()>(0);
+        let step_receiver = Mutex::new(step_receiver);
+        let callback_id = system.register_callback_with_state(
+            move |data, _state| {
+                step_receiver.lock().unwrap().recv().unwrap();
+                Ok(data)
+            },
+            state,
+        );
+
+        // The first job keeps the only worker busy until the deadline of the second has passed
+        let deadline = Instant::now() + std::time::Duration::from_millis(20);
+        let blocker = system.add_work(callback_id, 1usize);
+        let late = system.add_work_with_deadline(callback_id, 2usize, deadline);
+        thread::sleep(std::time::Duration::from_millis(30));
+        assert!(matches!(late.try_recv(), Ok(Err(CallbackError::TimedOut))));
+
+        step_sender.send(()).unwrap();
+        blocker.receiver().recv().unwrap().unwrap();
+        // Skipped by the worker without calling the callback
+        let result = late.receiver().recv().unwrap();
+        assert!(matches!(result, Err(CallbackError::TimedOut)));
+    }
+
+    #[test]
+    fn test_dropped_handle_is_skipped() {
+        let system = WorkSystem::new(1);
+
+        let state: Arc<Mutex<AnySend>> = Arc::new(Mutex::new(0usize));
+        let (step_sender, step_receiver) = bounded::<()>(0);
+        let step_receiver = Mutex::new(step_receiver);
+        let callback_id = system.register_callback_with_state(
+            move |data, state| {
+                step_receiver.lock().unwrap().recv().unwrap();
+                *state.lock().unwrap().downcast_mut::<usize>().unwrap() += 1;
+                Ok(data)
+            },
+            state.clone(),
+        );
+
+        // The queue holds a single job, so the dropped one waits there behind the blocker
+        let blocker = system.add_work(callback_id, 1usize);
+        drop(system.add_work_with_progress(callback_id, 2usize));
+        step_sender.send(()).unwrap();
+        blocker.receiver().recv().unwrap().unwrap();
+        // Same for a job added without progress
+        let blocker = system.add_work(callback_id, 3usize);
+        drop(system.add_work(callback_id, 4usize));
+        step_sender.send(()).unwrap();
+        blocker.receiver().recv().unwrap().unwrap();
+        let last = system.add_work_with_progress(callback_id, 5usize);
+        step_sender.send(()).unwrap();
+        last.receiver().recv().unwrap().unwrap();
+
+        assert_eq!(*state.lock().unwrap().downcast_ref::<usize>().unwrap(), 3);
+        let stats = system.stats();
+        assert_eq!(stats.jobs_discarded, 2);
+        assert_eq!(stats.jobs_pending(), 0);
+    }
+
+    #[test]
+    fn test_expired_job_is_discarded() {
+        let system = WorkSystem::new(1);
+
+        let state: Arc<Mutex<AnySend>> = Arc::new(Mutex::new(0usize));
+        let (step_sender, step_receiver) = bounded::<()>(0);
+        let step_receiver = Mutex::new(step_receiver);
+        let callback_id = system.register_callback_with_state(
+            move |data, state| {
+                step_receiver.lock().unwrap().recv().unwrap();
+                *state.lock().unwrap().downcast_mut::<usize>().unwrap() += 1;
+                Ok(data)
+            },
+            state.clone(),
+        );
+
+        // The deadline passes while the job waits behind the blocker, so it never starts
+        let deadline = Instant::now() + std::time::Duration::from_millis(10);
+        let blocker = system.add_work(callback_id, 1usize);
+        let expired = system.add_work_with_deadline(callback_id, 2usize, deadline);
+        thread::sleep(std::time::Duration::from_millis(20));
+        step_sender.send(()).unwrap();
+        blocker.receiver().recv().unwrap().unwrap();
+        let result = expired.receiver().recv().unwrap();
+        assert!(matches!(result, Err(CallbackError::TimedOut)));
+
+        assert_eq!(*state.lock().unwrap().downcast_ref::<usize>().unwrap(), 1);
+        let stats = system.stats();
+        assert_eq!(stats.jobs_discarded, 1);
+        assert_eq!(stats.jobs_completed, 2);
+    }
+
+    #[test]
+    fn test_progress_is_clamped() {
+        let reporter = ProgressReporter::new();
+        reporter.progress(1.5);
+        assert_eq!(f32::from_bits(reporter.value.load(Ordering::Relaxed)), 1.0);
+        reporter.progress(f32::NAN);
+        assert_eq!(f32::from_bits(reporter.value.load(Ordering::Relaxed)), 0.0);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_thread_hints() {
+        let handle = thread::spawn(|| {
+            // Lowering the priority is always allowed, pin to a core the test may run on
+            let mut allowed: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+            unsafe { libc::sched_getaffinity(0, std::mem::size_of_val(&allowed), &mut allowed) };
+            let core = (0..libc::CPU_SETSIZE as usize)
+                .find(|core| unsafe { libc::CPU_ISSET(*core, &allowed) });
+
+            let applied = apply_thread_hints(ThreadPriority::BelowNormal, core);
+            let tid = unsafe { libc::syscall(libc::SYS_gettid) } as libc::id_t;
+            let nice = unsafe { libc::getpriority(libc::PRIO_PROCESS, tid) };
+            (applied, nice)
+        });
+
+        let (applied, nice) = handle.join().unwrap();
+        assert!(applied);
+        assert!(nice >= 5);
+    }
+
+    #[test]
+    fn test_with_config() {
+        let config = WorkerConfig::new(2)
+            .priority(ThreadPriority::Lowest)
+            .core_affinity(vec![0]);
+        let system = WorkSystem::with_config(config);
+
+        let state: Arc<Mutex<AnySend>> = Arc::new(Mutex::new(()));
+        let callback_id = system.register_callback_with_state(|data, _state| Ok(data), state);
+        let result = system
+            .add_work(callback_id, 7usize)
+            .receiver()
+            .recv()
+            .unwrap()
+            .unwrap();
+        assert_eq!(*result.downcast::<usize>().unwrap(), 7);
+        assert_eq!(system.stats().num_workers, 2);
+    }
+
     #[test]
     fn test_callback_not_found() {
         let system = WorkSystem::new(4);
 
         let receiver = system.add_work(999, "Test data".to_string());
-        let result = receiver.recv().unwrap();
+        let result = receiver.receiver().recv().unwrap();
 
         assert!(matches!(result, Err(CallbackError::CallbackNotFound(999))));
     }
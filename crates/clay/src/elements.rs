@@ -59,7 +59,8 @@ impl<'declaration, 'render, ImageElementData: 'render, CustomElementData: 'rende
         self
     }
 
-    /// Sets the spacing between child elements.
+    /// Sets the width of the lines drawn between child elements. Clay emits them as rectangle
+    /// render commands in the border color.
     #[inline]
     pub fn between_children(&mut self, width: u16) -> &mut Self {
         self.parent.inner.border.width.betweenChildren = width;
@@ -163,6 +164,17 @@ pub enum FloatingAttachToElement {
     Root = Clay_FloatingAttachToElement_CLAY_ATTACH_TO_ROOT,
 }
 
+/// Defines whether a floating element is clipped by the clip container of the element it is
+/// attached to.
+#[derive(Debug, Clone)]
+#[repr(u8)]
+pub enum FloatingClipToElement {
+    /// The floating element is never clipped.
+    None = Clay_FloatingClipToElement_CLAY_CLIP_TO_NONE,
+    /// The floating element is clipped like its attached parent.
+    AttachedParent = Clay_FloatingClipToElement_CLAY_CLIP_TO_ATTACHED_PARENT,
+}
+
 /// Builder for configuring floating element properties in a `Declaration`.
 pub struct FloatingBuilder<
     'declaration,
@@ -236,6 +248,13 @@ impl<'declaration, 'render, ImageElementData: 'render, CustomElementData: 'rende
         self
     }
 
+    /// Sets whether the element is clipped by the clip container of its attached parent.
+    #[inline]
+    pub fn clip_to(&mut self, clip: FloatingClipToElement) -> &mut Self {
+        self.parent.inner.floating.clipTo = clip as _;
+        self
+    }
+
     /// Sets the pointer capture mode.
     #[inline]
     pub fn pointer_capture_mode(&mut self, mode: PointerCaptureMode) -> &mut Self {
@@ -61,7 +61,7 @@ impl From<Sizing> for Clay_SizingAxis {
 }
 
 /// Represents padding values for each side of an element.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub struct Padding {
     /// Padding on the left side.
     pub left: u16,
@@ -276,7 +276,6 @@ macro_rules! percent {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::{fit, fixed, grow, percent};
 
     #[test]
     fn fit_macro() {
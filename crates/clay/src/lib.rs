@@ -68,12 +68,69 @@ impl<'render, ImageElementData: 'render, CustomElementData: 'render>
         self
     }
 
+    /// Returns the id set with [`Declaration::id`], or `None` for anonymous elements.
+    #[inline]
+    pub fn element_id(&self) -> Option<Id> {
+        if self.inner.id.id != 0 {
+            Some(Id { id: self.inner.id })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the padding set with [`layout::LayoutBuilder::padding`].
+    #[inline]
+    pub fn element_padding(&self) -> layout::Padding {
+        let padding = &self.inner.layout.padding;
+        layout::Padding::new(padding.left, padding.right, padding.top, padding.bottom)
+    }
+
+    /// Returns the z-index set with [`elements::FloatingBuilder::z_index`], or `None` for
+    /// elements that aren't floating.
+    #[inline]
+    pub fn floating_z_index(&self) -> Option<i16> {
+        let floating = &self.inner.floating;
+        (floating.attachTo != bindings::Clay_FloatingAttachToElement_CLAY_ATTACH_TO_NONE)
+            .then_some(floating.zIndex)
+    }
+
     #[inline]
     pub fn custom_element(&mut self, data: &'render CustomElementData) -> &mut Self {
         self.inner.custom.customData = data as *const CustomElementData as _;
         self
     }
 
+    /// Returns the data set with [`elements::ImageBuilder::data`], if any.
+    #[inline]
+    pub fn image_data(&self) -> Option<&'render ImageElementData> {
+        unsafe { self.inner.image.imageData.cast::<ImageElementData>().as_ref() }
+    }
+
+    /// Returns the data set with [`Declaration::custom_element`], if any.
+    #[inline]
+    pub fn custom_data(&self) -> Option<&'render CustomElementData> {
+        unsafe { self.inner.custom.customData.cast::<CustomElementData>().as_ref() }
+    }
+
+    /// Copy of the declaration showing `image` and `custom` instead of the data it was built
+    /// with, for example to move the data somewhere it lives until the layout is rendered.
+    #[inline]
+    pub fn with_data<'other>(
+        &self,
+        image: Option<&'other ImageElementData>,
+        custom: Option<&'other CustomElementData>,
+    ) -> Declaration<'other, ImageElementData, CustomElementData> {
+        let mut declaration = Declaration {
+            inner: self.inner,
+            _phantom: PhantomData,
+        };
+        declaration.inner.image.imageData =
+            image.map_or(core::ptr::null(), |image| image as *const ImageElementData) as _;
+        declaration.inner.custom.customData =
+            custom.map_or(core::ptr::null(), |custom| custom as *const CustomElementData) as _;
+        declaration
+    }
+
     #[inline]
     pub fn layout(
         &mut self,
@@ -246,6 +303,12 @@ impl<'render, 'clay: 'render, ImageElementData: 'render, CustomElementData: 'ren
             .map(|command| unsafe { RenderCommand::from_clay_render_command(*command) })
     }
 
+    /// Gives up the layout without calculating it, for example when declaring it panicked and
+    /// left elements open. The next [`Clay::begin`] starts over from scratch.
+    pub fn abandon(mut self) {
+        self.dropped = true;
+    }
+
     /// Generates a unique ID based on the given `label`.
     ///
     /// This ID is global and must be unique across the entire scope.
@@ -580,7 +643,10 @@ impl From<Clay_StringSlice> for &str {
 /// # Examples
 ///
 /// ```rust
-/// use clay_layout::{clay, fixed};
+/// use clay_layout::{clay, fixed, grow, layout::LayoutDirection::TopToBottom, Clay, Color};
+/// # use clay_layout::math::Dimensions;
+/// # let mut clay = Clay::new(Dimensions::new(800.0, 600.0));
+/// # let mut clay = clay.begin::<(), ()>();
 ///
 /// // Simple red rectangle
 /// clay!(clay, {
@@ -592,7 +658,7 @@ impl From<Clay_StringSlice> for &str {
 ///     corner_radius: {
 ///         all: 5.,
 ///     },
-///     background_color: (0xFF, 0x00, 0x00).into(),
+///     background_color: Color::u_rgb(0xFF, 0x00, 0x00),
 /// }, |_| {});
 ///
 /// // Container with children
@@ -608,7 +674,7 @@ impl From<Clay_StringSlice> for &str {
 /// }, |clay| {
 ///     clay!(clay, {
 ///         id: "child_element",
-///         layout: { width: fixed!(100.), height: fixed!(50.) },
+///         layout: { width: fixed!(100.), height: fixed!(50.), },
 ///         background_color: Color::rgb(100.0, 100.0, 100.0),
 ///     }, |_| {});
 /// });
@@ -649,6 +715,7 @@ macro_rules! clay {
             $(parent_id: $float_parent:expr,)?
             $(attach_points: ($float_element:expr, $float_parent_point:expr),)?
             $(attach_to: $float_attach:expr,)?
+            $(clip_to: $float_clip:expr,)?
             $(pointer_capture_mode: $float_capture:expr,)?
         },)?
         $(image: {
@@ -719,6 +786,7 @@ macro_rules! clay {
                     $(floating.parent_id($float_parent);)?
                     $(floating.attach_points($float_element, $float_parent_point);)?
                     $(floating.attach_to($float_attach);)?
+                    $(floating.clip_to($float_clip);)?
                     $(floating.pointer_capture_mode($float_capture);)?
                     floating.end();
                 }
@@ -772,7 +840,7 @@ mod tests {
 
         let mut clay = clay.begin::<(), ()>();
 
-        clay.with(&Declaration::new()
+        clay.with(Declaration::new()
             .id(clay.id("parent_rect"))
             .layout()
                 .width(Sizing::Fixed(100.0))
@@ -781,7 +849,7 @@ mod tests {
                 .end()
             .background_color(Color::rgb(255., 255., 255.)), |clay|
         {
-            clay.with(&Declaration::new()
+            clay.with(Declaration::new()
                 .layout()
                     .width(Sizing::Fixed(100.0))
                     .height(Sizing::Fixed(100.0))
@@ -789,7 +857,7 @@ mod tests {
                     .end()
                 .background_color(Color::rgb(255., 255., 255.)), |clay| 
             {
-                clay.with(&Declaration::new()
+                clay.with(Declaration::new()
                     .id(clay.id("rect_under_rect"))
                     .layout()
                         .width(Sizing::Fixed(100.0))
@@ -807,7 +875,7 @@ mod tests {
             });
         });
 
-        clay.with(&Declaration::new()
+        clay.with(Declaration::new()
             .id(clay.id_index("border_container", 1))
             .layout()
                 .padding(Padding::all(16))
@@ -818,7 +886,7 @@ mod tests {
                 .end()
             .corner_radius().all(10.0).end(), |clay|
         {
-            clay.with(&Declaration::new()
+            clay.with(Declaration::new()
                 .id(clay.id("rect_under_border"))
                 .layout()
                     .width(Sizing::Fixed(50.0))
@@ -849,7 +917,7 @@ mod tests {
 
         let mut clay = clay.begin::<(), ()>();
 
-        clay.with(&Declaration::new()
+        clay.with(Declaration::new()
             .id(clay.id("parent_rect"))
             .layout()
                 .width(Sizing::Fixed(100.0))
@@ -866,4 +934,19 @@ mod tests {
 
         let _items = clay.end();
     }
+
+    #[test]
+    fn test_declaration_data() {
+        let image = 1u32;
+        let mut declaration = Declaration::<u32, ()>::new();
+        assert!(declaration.image_data().is_none());
+        declaration.image().data(&image).end();
+        assert_eq!(declaration.image_data(), Some(&1));
+
+        let moved = 2u32;
+        let copy = declaration.with_data(Some(&moved), None);
+        assert_eq!(copy.image_data(), Some(&2));
+        assert!(copy.custom_data().is_none());
+        assert_eq!(declaration.image_data(), Some(&1));
+    }
 }